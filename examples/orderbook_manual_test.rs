@@ -1,4 +1,4 @@
-use pinpet_server_v2::orderbook::{MarginOrder, OrderBookDBManager, MarginOrderUpdateData};
+use pinpet_server_v2::orderbook::{Direction, MarginOrder, OrderBookDBManager, MarginOrderUpdateData};
 use rocksdb::{Options, DB};
 use std::sync::Arc;
 
@@ -15,8 +15,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // 2. 创建 OrderBookDBManager
     println!("\n📝 步骤2: 创建 OrderBookDBManager...");
     let mint = "EPjFWaLb3crLvQQf89kiNqEX5jg5Kv431J06Y1AD3ic".to_string();
-    let direction = "dn".to_string();
-    let manager = OrderBookDBManager::new(db.clone(), mint.clone(), direction.clone());
+    let direction = Direction::Dn;
+    let manager = OrderBookDBManager::new(db.clone(), mint.clone(), direction);
     println!("✅ OrderBookDBManager 已创建: {}:{}", mint, direction);
 
     // 3. 初始化 OrderBook
@@ -108,6 +108,7 @@ fn create_test_order(user: &str, price: u128) -> MarginOrder {
         lock_lp_start_price: price,
         lock_lp_end_price: price + 100000,
         open_price: price + 50000,
+        take_profit_price: 0,
         order_id: 0,
         lock_lp_sol_amount: 1000000000,
         lock_lp_token_amount: 5000000000,
@@ -23,6 +23,18 @@ pub const FEE_RETENTION_PROBABILITY_DENOMINATOR: u64 = 20;
 /// 平仓/开仓时插入索引的最大数量
 pub const MAX_CLOSE_INSERT_INDICES: usize = 21;
 
+/// 一笔买入/卖出交易中最多自动止盈平仓的订单数量
+pub const MAX_TAKE_PROFIT_CLOSES_PER_TRADE: usize = 5;
+
+/// 订单延期后的最长总时长 = 贷款时长(borrow_duration) * 该倍数
+pub const MAX_ORDER_DURATION_MULTIPLIER: u32 = 3;
+
+/// 清算奖励: 非开仓者平掉已到期订单时, 从开仓者的平仓收入中支付给调用者的SOL数量
+pub const KEEPER_REWARD_SOL_AMOUNT: u64 = 1_000_000; // 0.001 sol
+
+/// 清算奖励上限: 不超过开仓者平仓收入的百分比, 保证开仓者仍能拿回剩余资金
+pub const KEEPER_REWARD_MAX_PERCENT: u64 = 10; // 10%
+
 
 // 报废代码: 
 
@@ -579,6 +579,97 @@ impl CurveAMM {
         Some((end_price_u128, token_amount_u64))
     }
 
+    /// 已知卖出的token数量和期望得到的SOL数量，反推起始价格
+    ///
+    /// # 参数
+    /// * `token_input_amount` - 卖出的token数量
+    /// * `sol_output_amount` - 期望得到的SOL数量
+    ///
+    /// # 返回值
+    /// * `Option<u128>` - 成功则返回Some(起始价格)，失败则返回None
+    /// 价格向上取整，从该价格卖出 token_input_amount 得到的SOL不少于 sol_output_amount
+    ///
+    /// # 推导
+    /// 卖出 T 个token得到 S = k*T / (x0 * (x0 + T))，x0 为起始token储备
+    /// 解 x0^2 + T*x0 - k*T/S = 0 得 x0 = (-T + sqrt(T^2 + 4*k*T/S)) / 2，价格 = k / x0^2
+    pub fn start_price_for_sell(token_input_amount: u64, sol_output_amount: u64) -> Option<u128> {
+        let token_dec = Self::u64_to_token_decimal(token_input_amount)?;
+        let sol_dec = Self::u64_to_sol_decimal(sol_output_amount)?;
+
+        // 检查输入参数是否有效
+        if token_dec <= Decimal::ZERO || sol_dec <= Decimal::ZERO {
+            return None;
+        }
+
+        let k = Self::calculate_initial_k();
+
+        // 判别式: T^2 + 4*k*T/S
+        let four_kt_div_s = k
+            .checked_mul(token_dec)?
+            .checked_div(sol_dec)?
+            .checked_mul(Decimal::from(4))?;
+        let discriminant = token_dec.checked_mul(token_dec)?.checked_add(four_kt_div_s)?;
+
+        // 起始token储备 x0
+        let start_token_reserve = discriminant
+            .sqrt()?
+            .checked_sub(token_dec)?
+            .checked_div(Decimal::from(2))?;
+        if start_token_reserve <= Decimal::ZERO {
+            return None;
+        }
+
+        // 价格 = k / x0^2
+        let price = k.checked_div(start_token_reserve.checked_mul(start_token_reserve)?)?;
+
+        // 向下取整后加1，保证卖出得到的SOL不少于期望值
+        Self::decimal_to_u128(price)?.checked_add(1)
+    }
+
+    /// 已知买入的token数量和愿意付出的SOL数量，反推起始价格
+    ///
+    /// # 参数
+    /// * `token_output_amount` - 买入的token数量
+    /// * `sol_input_amount` - 愿意付出的SOL数量
+    ///
+    /// # 返回值
+    /// * `Option<u128>` - 成功则返回Some(起始价格)，失败则返回None
+    /// 价格向下取整，从该价格买入 token_output_amount 需要的SOL不超过 sol_input_amount
+    ///
+    /// # 推导
+    /// 买入 T 个token需要 C = k*T / (x0 * (x0 - T))，x0 为起始token储备
+    /// 解 x0^2 - T*x0 - k*T/C = 0 得 x0 = (T + sqrt(T^2 + 4*k*T/C)) / 2，价格 = k / x0^2
+    pub fn start_price_for_buy(token_output_amount: u64, sol_input_amount: u64) -> Option<u128> {
+        let token_dec = Self::u64_to_token_decimal(token_output_amount)?;
+        let sol_dec = Self::u64_to_sol_decimal(sol_input_amount)?;
+
+        // 检查输入参数是否有效
+        if token_dec <= Decimal::ZERO || sol_dec <= Decimal::ZERO {
+            return None;
+        }
+
+        let k = Self::calculate_initial_k();
+
+        // 判别式: T^2 + 4*k*T/C
+        let four_kt_div_c = k
+            .checked_mul(token_dec)?
+            .checked_div(sol_dec)?
+            .checked_mul(Decimal::from(4))?;
+        let discriminant = token_dec.checked_mul(token_dec)?.checked_add(four_kt_div_c)?;
+
+        // 起始token储备 x0
+        let start_token_reserve = discriminant
+            .sqrt()?
+            .checked_add(token_dec)?
+            .checked_div(Decimal::from(2))?;
+
+        // 价格 = k / x0^2
+        let price = k.checked_div(start_token_reserve.checked_mul(start_token_reserve)?)?;
+
+        // 向下取整，保证买入需要的SOL不超过给定值
+        Self::decimal_to_u128(price)
+    }
+
     /// 计算扣除手续费后的剩余金额
     ///
     /// # 参数
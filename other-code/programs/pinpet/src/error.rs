@@ -302,4 +302,43 @@ pub enum ErrorCode {
 
     #[msg("链表删除计数异常：删除前后计数不一致")]
     LinkedListDeleteCountMismatch,
+
+    // ==================== 追加保证金错误 ====================
+    #[msg("追加的保证金数量必须大于0")]
+    InvalidAddMarginAmount,
+
+    #[msg("只有开仓者可以追加保证金")]
+    AddMarginNotOrderOwner,
+
+    #[msg("订单已到期，不能追加保证金")]
+    AddMarginOrderExpired,
+
+    #[msg("追加保证金计算溢出")]
+    AddMarginCalculationOverflow,
+
+    // ==================== 订单延期错误 ====================
+    #[msg("延期时长必须大于0")]
+    InvalidExtendDuration,
+
+    #[msg("只有开仓者可以延期订单")]
+    ExtendOrderNotOwner,
+
+    #[msg("订单已到期，不能延期")]
+    ExtendOrderExpired,
+
+    #[msg("延期后订单总时长超过最大限制")]
+    ExtendOrderExceedsMaxDuration,
+
+    #[msg("延期手续费计算溢出")]
+    ExtendOrderFeeOverflow,
+
+    // ==================== 止盈错误 ====================
+    #[msg("止盈价格无效: 做多必须高于开仓后价格, 做空必须低于开仓后价格")]
+    InvalidTakeProfitPrice,
+
+    #[msg("触发止盈的订单由他人平仓时必须全部平仓")]
+    TakeProfitCloseMustBeFull,
+
+    #[msg("自动止盈平仓找不到开仓者账户")]
+    TakeProfitUserAccountNotFound,
 }
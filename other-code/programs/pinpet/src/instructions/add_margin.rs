@@ -0,0 +1,280 @@
+// 导入所需的模块和依赖项
+use {
+    // 导入常量
+    crate::constants::MAX_CLOSE_INSERT_INDICES,
+    // 导入曲线AMM计算模块
+    crate::curve::curve_amm::{CurveAMM, FEE_DENOMINATOR},
+    // 导入错误处理
+    crate::error::ErrorCode,
+    crate::instructions::contexts::AddMargin,
+    // 导入上下文验证函数
+    crate::instructions::context_validator::validate_add_margin_context,
+    crate::instructions::events::AddMarginEvent,
+    // 导入订单簿管理器
+    crate::instructions::orderbook_manager::{MarginOrderUpdateData, OrderBookManager},
+    crate::instructions::structs::MarginOrder,
+    crate::instructions::utils::find_order_by_indices,
+    // 导入 Anchor 框架的基础组件
+    anchor_lang::prelude::*,
+};
+
+// 追加保证金 - 开仓者向 pool_sol_account 补充SOL, 并尽量把锁定区间推离当前价格
+//
+// 风险敞口按增量计算: 追加 add_sol 后, 锁定区间平仓时允许少收(做多)或多付(做空) add_sol 的SOL
+// 新区间不会与相邻订单重叠, 也不会比原区间更靠近当前价格; 找不到更安全的区间时只增加保证金
+pub fn add_margin(
+    ctx: Context<AddMargin>,
+    is_long: bool,                 // true: 做多订单(down_orderbook) false: 做空订单(up_orderbook)
+    order_id: u64,                 // 订单的唯一编号
+    close_order_indices: Vec<u16>, // 订单的位置索引 (可以有多个,万一订单被移动了,就会自动找第二位置)
+    add_sol: u64,                  // 追加的保证金数量 (SOL)
+) -> Result<()> {
+    validate_add_margin_context(&ctx)?;
+
+    if add_sol == 0 {
+        return Err(ErrorCode::InvalidAddMarginAmount.into());
+    }
+
+    // 验证 close_order_indices 数量
+    if close_order_indices.is_empty() {
+        return Err(ErrorCode::EmptyCloseInsertIndices.into());
+    }
+    if close_order_indices.len() > MAX_CLOSE_INSERT_INDICES {
+        return Err(ErrorCode::TooManyCloseInsertIndices.into());
+    }
+
+    let orderbook_info = if is_long {
+        ctx.accounts.down_orderbook.to_account_info()
+    } else {
+        ctx.accounts.up_orderbook.to_account_info()
+    };
+
+    // 查找订单
+    let (order, order_index) = find_order_by_indices(&orderbook_info, order_id, &close_order_indices)?;
+
+    // 只有开仓者本人可以追加保证金
+    if ctx.accounts.payer.key() != order.user {
+        return Err(ErrorCode::AddMarginNotOrderOwner.into());
+    }
+
+    // 已到期的订单任何人都可以平仓, 不能再追加
+    let current_timestamp = Clock::get()?.unix_timestamp as u32;
+    if current_timestamp >= order.end_time {
+        return Err(ErrorCode::AddMarginOrderExpired.into());
+    }
+
+    // 读取相邻节点
+    let (prev_order, next_order) = {
+        let orderbook_data = orderbook_info.data.borrow();
+        let prev_order = if order.prev_order != u16::MAX {
+            Some(*OrderBookManager::get_order(&orderbook_data, order.prev_order)?)
+        } else {
+            None
+        };
+        let next_order = if order.next_order != u16::MAX {
+            Some(*OrderBookManager::get_order(&orderbook_data, order.next_order)?)
+        } else {
+            None
+        };
+        (prev_order, next_order)
+    };
+
+    // 计算新的锁定区间 (None 表示保持原区间)
+    let new_range = if is_long {
+        safer_long_range(&order, next_order.as_ref(), add_sol)
+    } else {
+        safer_short_range(&order, next_order.as_ref(), add_sol)
+    };
+
+    let new_margin_sol_amount = order
+        .margin_sol_amount
+        .checked_add(add_sol)
+        .ok_or(ErrorCode::AddMarginCalculationOverflow)?;
+
+    // 将追加的保证金转入流动池SOL账户
+    anchor_lang::solana_program::program::invoke(
+        &anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.payer.key(),
+            &ctx.accounts.pool_sol_account.key(),
+            add_sol,
+        ),
+        &[
+            ctx.accounts.payer.to_account_info(),
+            ctx.accounts.pool_sol_account.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        ],
+    )?;
+
+    let (lock_lp_start_price, lock_lp_end_price, lock_lp_sol_amount) =
+        new_range.unwrap_or((order.lock_lp_start_price, order.lock_lp_end_price, order.lock_lp_sol_amount));
+
+    let mut update_data = MarginOrderUpdateData {
+        margin_sol_amount: Some(new_margin_sol_amount),
+        ..Default::default()
+    };
+
+    if new_range.is_some() {
+        update_data.lock_lp_start_price = Some(lock_lp_start_price);
+        update_data.lock_lp_end_price = Some(lock_lp_end_price);
+        update_data.lock_lp_sol_amount = Some(lock_lp_sol_amount);
+
+        // 重新计算当前订单到后节点的区间流动性 (尾节点保持 MAX_U64)
+        if let Some(next_order) = next_order.as_ref() {
+            let (next_lp_sol, next_lp_token) =
+                gap_liquidity(is_long, lock_lp_end_price, next_order.lock_lp_start_price)?;
+            update_data.next_lp_sol_amount = Some(next_lp_sol);
+            update_data.next_lp_token_amount = Some(next_lp_token);
+        }
+    }
+
+    OrderBookManager::update_order(&orderbook_info, order_index, order_id, &update_data)?;
+
+    // 区间移动后, 前节点到当前订单的区间流动性也需要重新计算
+    if new_range.is_some() {
+        if let Some(prev_order) = prev_order.as_ref() {
+            let (prev_lp_sol, prev_lp_token) =
+                gap_liquidity(is_long, prev_order.lock_lp_end_price, lock_lp_start_price)?;
+
+            let prev_update_data = MarginOrderUpdateData {
+                next_lp_sol_amount: Some(prev_lp_sol),
+                next_lp_token_amount: Some(prev_lp_token),
+                ..Default::default()
+            };
+
+            OrderBookManager::update_order(
+                &orderbook_info,
+                order.prev_order,
+                prev_order.order_id,
+                &prev_update_data,
+            )?;
+        }
+    }
+
+    emit!(AddMarginEvent {
+        payer: ctx.accounts.payer.key(),
+        mint_account: ctx.accounts.mint_account.key(),
+        order_id,
+        order_index,
+        order_type: order.order_type,
+        add_sol_amount: add_sol,
+        margin_sol_amount: new_margin_sol_amount,
+        lock_lp_start_price,
+        lock_lp_end_price,
+        lock_lp_sol_amount,
+        lock_lp_token_amount: order.lock_lp_token_amount,
+        latest_price: ctx.accounts.curve_account.price,
+    });
+
+    Ok(())
+}
+
+/// 计算两个价格之间的区间流动性, 返回 (SOL数量, token数量)
+///
+/// 做多订单簿价格向下排列, 用 sell_from_price_to_price; 做空订单簿价格向上排列, 用 buy_from_price_to_price
+fn gap_liquidity(is_long: bool, from_price: u128, to_price: u128) -> Result<(u64, u64)> {
+    if is_long {
+        let (token_amount, sol_amount) = CurveAMM::sell_from_price_to_price(from_price, to_price)
+            .ok_or(ErrorCode::AddMarginCalculationOverflow)?;
+        Ok((sol_amount, token_amount))
+    } else {
+        CurveAMM::buy_from_price_to_price(from_price, to_price)
+            .ok_or_else(|| ErrorCode::AddMarginCalculationOverflow.into())
+    }
+}
+
+/// 做多订单: 把区间起点向下移动, 平仓卖出所得(扣费后)最多减少 add_sol
+///
+/// # 返回值
+/// * `Option<(u128, u128, u64)>` - 新的 (起始价格, 结束价格, 锁定SOL数量), None 表示保持原区间
+fn safer_long_range(order: &MarginOrder, next_order: Option<&MarginOrder>, add_sol: u64) -> Option<(u128, u128, u64)> {
+    let fee = order.borrow_fee;
+    let token_amount = order.lock_lp_token_amount;
+
+    // 平仓时至少需要得到的扣费后SOL
+    let required_after_fee = CurveAMM::calculate_amount_after_fee(order.lock_lp_sol_amount, fee)?
+        .saturating_sub(add_sol)
+        .max(1);
+
+    // 扣费前的SOL: required * D / (D - fee), 向上取整
+    let denominator = u128::from(FEE_DENOMINATOR).checked_sub(u128::from(fee))?;
+    let required_sol = u128::from(required_after_fee)
+        .checked_mul(u128::from(FEE_DENOMINATOR))?
+        .checked_add(denominator - 1)?
+        .checked_div(denominator)?;
+    let required_sol = u64::try_from(required_sol).ok()?;
+
+    let mut start_price = CurveAMM::start_price_for_sell(token_amount, required_sol)?;
+
+    // 不能越过后节点: 从起点卖出后结束价格必须高于后节点的起始价格
+    if let Some(next_order) = next_order {
+        let (min_start_price, _) =
+            CurveAMM::buy_from_price_with_token_output(next_order.lock_lp_start_price, token_amount)?;
+        start_price = start_price.max(min_start_price.checked_add(1)?);
+    }
+
+    // 只往远离当前价格的方向移动
+    if start_price >= order.lock_lp_start_price {
+        return None;
+    }
+
+    let (end_price, sol_amount) = CurveAMM::sell_from_price_with_token_input(start_price, token_amount)?;
+
+    // 复核取整后的结果
+    if CurveAMM::calculate_amount_after_fee(sol_amount, fee)? < required_after_fee {
+        return None;
+    }
+    if let Some(next_order) = next_order {
+        if end_price <= next_order.lock_lp_start_price {
+            return None;
+        }
+    }
+
+    Some((start_price, end_price, sol_amount))
+}
+
+/// 做空订单: 把区间起点向上移动, 平仓买回成本(含费)最多增加 add_sol
+///
+/// # 返回值
+/// * `Option<(u128, u128, u64)>` - 新的 (起始价格, 结束价格, 锁定SOL数量), None 表示保持原区间
+fn safer_short_range(order: &MarginOrder, next_order: Option<&MarginOrder>, add_sol: u64) -> Option<(u128, u128, u64)> {
+    let fee = order.borrow_fee;
+    let token_amount = order.lock_lp_token_amount;
+
+    // 平仓时最多允许付出的含费SOL
+    let allowed_with_fee = CurveAMM::calculate_total_amount_with_fee(order.lock_lp_sol_amount, fee)?
+        .checked_add(add_sol)?;
+
+    // 不含费的SOL: allowed * D / (D + fee), 向下取整
+    let allowed_sol = u128::from(allowed_with_fee)
+        .checked_mul(u128::from(FEE_DENOMINATOR))?
+        .checked_div(u128::from(FEE_DENOMINATOR).checked_add(u128::from(fee))?)?;
+    let allowed_sol = u64::try_from(allowed_sol).ok()?;
+
+    let mut start_price = CurveAMM::start_price_for_buy(token_amount, allowed_sol)?;
+
+    // 不能越过后节点: 从起点买入后结束价格必须低于后节点的起始价格
+    if let Some(next_order) = next_order {
+        let (max_start_price, _) =
+            CurveAMM::sell_from_price_with_token_input(next_order.lock_lp_start_price, token_amount)?;
+        start_price = start_price.min(max_start_price.checked_sub(1)?);
+    }
+
+    // 只往远离当前价格的方向移动
+    if start_price <= order.lock_lp_start_price {
+        return None;
+    }
+
+    let (end_price, sol_amount) = CurveAMM::buy_from_price_with_token_output(start_price, token_amount)?;
+
+    // 复核取整后的结果
+    if CurveAMM::calculate_total_amount_with_fee(sol_amount, fee)? > allowed_with_fee {
+        return None;
+    }
+    if let Some(next_order) = next_order {
+        if end_price >= next_order.lock_lp_start_price {
+            return None;
+        }
+    }
+
+    Some((start_price, end_price, sol_amount))
+}
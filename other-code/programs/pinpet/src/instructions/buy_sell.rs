@@ -6,10 +6,12 @@ use {
     crate::error::ErrorCode,
     // 导入参数结构和账户结构
     crate::instructions::contexts::TradeBuySell,
-    crate::instructions::events::BuySellEvent,
+    crate::instructions::events::{BuySellEvent, FullCloseEvent},
     crate::context_validator::validate_trade_buy_sell_context,
-    crate::instructions::utils::calculate_fee_split,
-    crate::instructions::trade_engine::{buy_amounts, sell_amounts},
+    crate::instructions::utils::{calculate_fee_split, calculate_keeper_reward},
+    crate::instructions::trade_engine::{buy_amounts, sell_amounts, TakeProfitClose},
+    // 导入订单簿管理器
+    crate::instructions::orderbook_manager::{OrderBookManager, MarginOrderUpdateData},
     // 导入转移平仓手续费宏
     crate::transfer_close_fees_split,
     // 导入 CurveAMM 结构体
@@ -20,7 +22,7 @@ use {
 
 // 现货买入交易指令的处理函数
 pub fn buy_trade(
-    ctx: Context<TradeBuySell>,
+    mut ctx: Context<TradeBuySell>,
     buy_token_amount: u64, // 买入的token数量
     max_sol_amount: u64,   // 愿意给出的最大sol数量
 ) -> Result<()> {
@@ -62,6 +64,9 @@ pub fn buy_trade(
     // 提前读取 swap_fee，避免借用冲突
     let swap_fee = ctx.accounts.curve_account.swap_fee;
 
+    // 以可写账户提供的开仓者, 买入后触发止盈的做多订单会被自动平仓
+    let payout_users = take_profit_payout_users(&ctx);
+
     // 调用辅助函数计算交易数量
     let calc_result = buy_amounts(
         &mut ctx.accounts.curve_account,
@@ -70,6 +75,8 @@ pub fn buy_trade(
         buy_token_amount,
         max_sol_amount,
         swap_fee,
+        Some(&ctx.accounts.down_orderbook.to_account_info()),
+        &payout_users,
     )?;
 
     // 强平手续费
@@ -169,6 +176,9 @@ pub fn buy_trade(
         // msg!("批量删除订单完成");
     }
 
+    // 结算自动止盈平仓的做多订单
+    let take_profit_events = settle_take_profit_closes(&mut ctx, &calc_result.take_profit_closes, true)?;
+
     // 重新计算流动池储备量
     if let Some((sol_reserve, token_reserve)) = CurveAMM::price_to_reserves(ctx.accounts.curve_account.price) {
         ctx.accounts.curve_account.lp_sol_reserve = sol_reserve;
@@ -199,13 +209,18 @@ pub fn buy_trade(
         liquidate_indices: calc_result.liquidate_indices.clone(),
     });
 
+    // 止盈平仓事件在交易事件之后发射, 最新价格以最后一个平仓为准
+    for event in take_profit_events {
+        emit!(event);
+    }
+
     // 返回成功结果
     Ok(())
 }
 
 // 现货卖出交易指令的处理函数
 pub fn sell_trade(
-    ctx: Context<TradeBuySell>,
+    mut ctx: Context<TradeBuySell>,
     sell_token_amount: u64, // 希望卖出的token数量
     min_sol_output: u64,    // 卖出后最少得到的sol数量
 ) -> Result<()> {
@@ -242,6 +257,9 @@ pub fn sell_trade(
     // 提前读取 swap_fee，避免借用冲突
     let swap_fee = ctx.accounts.curve_account.swap_fee;
 
+    // 以可写账户提供的开仓者, 卖出后触发止盈的做空订单会被自动平仓
+    let payout_users = take_profit_payout_users(&ctx);
+
     // 调用辅助函数计算交易数量
     let calc_result = sell_amounts(
         &mut ctx.accounts.curve_account,
@@ -250,6 +268,8 @@ pub fn sell_trade(
         sell_token_amount,
         min_sol_output,
         swap_fee,
+        Some(&ctx.accounts.up_orderbook.to_account_info()),
+        &payout_users,
     )?;
 
     // 强制平仓手续费
@@ -357,6 +377,9 @@ pub fn sell_trade(
         // msg!("批量删除订单完成");
     }
 
+    // 结算自动止盈平仓的做空订单
+    let take_profit_events = settle_take_profit_closes(&mut ctx, &calc_result.take_profit_closes, false)?;
+
     // 重新计算流动池储备量
     if let Some((sol_reserve, token_reserve)) = CurveAMM::price_to_reserves(ctx.accounts.curve_account.price) {
         ctx.accounts.curve_account.lp_sol_reserve = sol_reserve;
@@ -407,6 +430,191 @@ pub fn sell_trade(
         liquidate_indices: calc_result.liquidate_indices.clone(),
     });
 
+    // 止盈平仓事件在交易事件之后发射, 最新价格以最后一个平仓为准
+    for event in take_profit_events {
+        emit!(event);
+    }
+
+
+    Ok(())
+}
+
+// 可以接收止盈平仓收入的开仓者地址 (remaining_accounts 中的可写账户)
+fn take_profit_payout_users(ctx: &Context<TradeBuySell>) -> Vec<Pubkey> {
+    ctx.remaining_accounts
+        .iter()
+        .filter(|account| account.is_writable)
+        .map(|account| account.key())
+        .collect()
+}
+
+// 结算交易中自动止盈平仓的订单: 重算前节点流动性, 转移手续费/清算奖励/平仓收入, 删除订单
+// 借币池已在 trade_engine 中归还, 返回的事件由调用者在交易事件之后发射
+fn settle_take_profit_closes(
+    ctx: &mut Context<TradeBuySell>,
+    closes: &[TakeProfitClose],
+    is_close_long: bool,
+) -> Result<Vec<FullCloseEvent>> {
+    let mut events = Vec::with_capacity(closes.len());
+    if closes.is_empty() {
+        return Ok(events);
+    }
+
+    // 做多订单在 down_orderbook, 做空订单在 up_orderbook
+    let orderbook_info = if is_close_long {
+        ctx.accounts.down_orderbook.to_account_info()
+    } else {
+        ctx.accounts.up_orderbook.to_account_info()
+    };
+    let payer_info = ctx.accounts.payer.to_account_info();
+    let system_program_info = ctx.accounts.system_program.to_account_info();
+    let fee_recipient_info = ctx.accounts.fee_recipient_account.to_account_info();
+    let base_fee_recipient_info = ctx.accounts.base_fee_recipient_account.to_account_info();
+    let minimum_rent = ctx.accounts.rent.minimum_balance(0);
+
+    for close in closes {
+        // 前面的平仓删除订单后索引会变化, 按订单ID重新查找
+        let mut found: Option<(u16, u16, u16)> = None;
+        {
+            let orderbook_data = orderbook_info.data.borrow();
+            OrderBookManager::traverse(&orderbook_data, u16::MAX, 0, |index, order| {
+                if order.order_id == close.order_id {
+                    found = Some((index, order.prev_order, order.next_order));
+                    return Ok(false);
+                }
+                Ok(true)
+            })?;
+        }
+        let (close_index, prev_index, next_index) =
+            found.ok_or(ErrorCode::OrderBookManagerOrderIdMismatch)?;
+
+        // 删除节点前重新计算前节点的流动性, 与平仓指令的4种场景相同
+        if prev_index != u16::MAX {
+            let orderbook_data = orderbook_info.data.borrow();
+            let prev_order = OrderBookManager::get_order(&orderbook_data, prev_index)?;
+            let prev_order_id = prev_order.order_id;
+            let prev_lock_lp_end_price = prev_order.lock_lp_end_price;
+            let next_lock_lp_start_price = if next_index != u16::MAX {
+                Some(OrderBookManager::get_order(&orderbook_data, next_index)?.lock_lp_start_price)
+            } else {
+                None
+            };
+            drop(orderbook_data);
+
+            let (new_sol_amount, new_token_amount) = match next_lock_lp_start_price {
+                // 场景4: 前后都有节点 - 重新计算前节点到后节点之间的流动性
+                Some(next_lock_lp_start_price) if is_close_long => {
+                    let (token_amount, sol_amount) = CurveAMM::sell_from_price_to_price(
+                        prev_lock_lp_end_price,
+                        next_lock_lp_start_price,
+                    )
+                    .ok_or(ErrorCode::CloseLongRemainingOverflow)?;
+                    (sol_amount, token_amount)
+                }
+                Some(next_lock_lp_start_price) => CurveAMM::buy_from_price_to_price(
+                    prev_lock_lp_end_price,
+                    next_lock_lp_start_price,
+                )
+                .ok_or(ErrorCode::CloseShortRemainingOverflow)?,
+                // 场景3: 删除尾节点 - 前节点变成尾节点，设置无限流动性
+                None => (CurveAMM::MAX_U64, CurveAMM::MAX_U64),
+            };
+
+            let update_data = MarginOrderUpdateData {
+                next_lp_sol_amount: Some(new_sol_amount),
+                next_lp_token_amount: Some(new_token_amount),
+                ..Default::default()
+            };
+            OrderBookManager::update_order(&orderbook_info, prev_index, prev_order_id, &update_data)?;
+        }
+        // 场景1/2: 删除头节点或链表变空，不需要处理
+
+        // 平仓手续费
+        transfer_close_fees_split!(
+            close.fee_sol,
+            &ctx.accounts.pool_sol_account,
+            &fee_recipient_info,
+            &base_fee_recipient_info,
+            ctx.accounts.curve_account.fee_split
+        )?;
+
+        // 非开仓者的交易触发止盈平仓时, 从开仓者的平仓收入中支付清算奖励给交易者
+        let keeper_reward = calculate_keeper_reward(payer_info.key() != close.user, close.profit_sol);
+        let user_close_profit = close
+            .profit_sol
+            .checked_sub(keeper_reward)
+            .ok_or(if is_close_long {
+                ErrorCode::CloseLongProfitOverflow
+            } else {
+                ErrorCode::CloseShortProfitOverflow
+            })?;
+
+        if keeper_reward > 0 {
+            debit_lamports(&ctx.accounts.pool_sol_account, keeper_reward)?;
+            credit_lamports(&payer_info, keeper_reward)?;
+        }
+
+        // 平仓收入转给开仓者, 转账后仍不够最小租金的新账户改转给技术提供方 (同 transfer_lamports!)
+        if user_close_profit > 0 {
+            let user_info = ctx
+                .remaining_accounts
+                .iter()
+                .find(|account| account.is_writable && account.key() == close.user)
+                .ok_or(ErrorCode::TakeProfitUserAccountNotFound)?;
+            debit_lamports(&ctx.accounts.pool_sol_account, user_close_profit)?;
+            if user_close_profit >= minimum_rent || user_info.lamports() >= minimum_rent {
+                credit_lamports(user_info, user_close_profit)?;
+            } else {
+                credit_lamports(&base_fee_recipient_info, user_close_profit)?;
+            }
+        }
+
+        // 更新价格
+        ctx.accounts.curve_account.price = close.target_price;
 
+        // 检查是否需要应用手续费折扣
+        crate::apply_fee_discount_if_needed!(ctx)?;
+
+        OrderBookManager::batch_remove_by_indices_unsafe(
+            &orderbook_info,
+            &[close_index],
+            &payer_info,
+            &system_program_info,
+        )?;
+
+        events.push(FullCloseEvent {
+            payer: payer_info.key(),
+            user_sol_account: close.user,
+            mint_account: ctx.accounts.mint_account.key(),
+            is_close_long,
+            final_token_amount: close.token_amount,
+            final_sol_amount: close.sol_amount,
+            user_close_profit,
+            latest_price: close.target_price,
+            order_id: close.order_id,
+            order_index: close_index,
+            liquidate_indices: vec![close_index],
+            keeper_reward,
+        });
+    }
+
+    Ok(events)
+}
+
+// 从账户扣除 lamports
+fn debit_lamports(account: &AccountInfo, amount: u64) -> Result<()> {
+    **account.try_borrow_mut_lamports()? = account
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(ErrorCode::LamportsDeductionOverflow)?;
+    Ok(())
+}
+
+// 向账户增加 lamports
+fn credit_lamports(account: &AccountInfo, amount: u64) -> Result<()> {
+    **account.try_borrow_mut_lamports()? = account
+        .lamports()
+        .checked_add(amount)
+        .ok_or(ErrorCode::LamportsAdditionOverflow)?;
     Ok(())
 }
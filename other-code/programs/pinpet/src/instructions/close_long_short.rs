@@ -7,6 +7,7 @@ use {
     // 导入错误处理
     crate::error::ErrorCode,
     crate::instructions::trade_engine::{buy_amounts, sell_amounts},
+    crate::instructions::utils::calculate_keeper_reward,
 
     crate::instructions::contexts::TradeClose,
     // 导入上下文验证函数
@@ -151,7 +152,13 @@ pub fn close_long_trade(
     if current_timestamp < close_margin_order.end_time {
         // 订单未超时，检查是否是开仓者本人进行平仓
         if ctx.accounts.payer.key() != close_margin_order.user {
-            return Err(ErrorCode::OrderNotExpiredMustCloseByOwner.into());
+            // 已触发止盈的订单任何人都可以全部平仓
+            if !close_margin_order.is_take_profit_reached(ctx.accounts.curve_account.price) {
+                return Err(ErrorCode::OrderNotExpiredMustCloseByOwner.into());
+            }
+            if sell_token_amount != close_margin_order.lock_lp_token_amount {
+                return Err(ErrorCode::TakeProfitCloseMustBeFull.into());
+            }
         }
     } else {
         //msg!("订单已超时，任何人都可以平仓");
@@ -192,7 +199,9 @@ pub fn close_long_trade(
         Some(close_margin_order.order_id), // 传入当前要平仓的订单的key
         sell_token_amount,
         min_sol_output,
-        close_margin_order.borrow_fee
+        close_margin_order.borrow_fee,
+        None, // 开仓/平仓不自动止盈平仓
+        &[],
     )?;
 
     // 强制平仓手续费 也可加上 主动平仓手续费
@@ -227,8 +236,30 @@ pub fn close_long_trade(
             .checked_sub(close_margin_order.borrow_amount)
             .ok_or(ErrorCode::CloseLongProfitOverflow)?;
 
+        // 非开仓者平掉已到期或已触发止盈的订单时, 从开仓者的平仓收入中支付清算奖励给调用者
+        let keeper_reward = calculate_keeper_reward(
+            ctx.accounts.payer.key() != close_margin_order.user,
+            profit_sol,
+        );
+        let user_close_profit = profit_sol
+            .checked_sub(keeper_reward)
+            .ok_or(ErrorCode::CloseLongProfitOverflow)?;
+
         // 6. 把 盈利资金 sol转到, user_sol_account 账户
-        transfer_pool_to_user_if_positive!(profit_sol, ctx);
+        transfer_pool_to_user_if_positive!(user_close_profit, ctx);
+
+        // 把清算奖励从 pool_sol_account 转给调用者
+        if keeper_reward > 0 {
+            let payer_info = ctx.accounts.payer.to_account_info();
+            let base_fee_recipient_info = ctx.accounts.base_fee_recipient_account.to_account_info();
+            transfer_lamports!(
+                keeper_reward,
+                &ctx.accounts.pool_sol_account,
+                &payer_info,
+                &ctx.accounts.rent.to_account_info(),
+                &base_fee_recipient_info
+            )?;
+        }
 
         // 更新价格
         ctx.accounts.curve_account.price = calc_result.target_price;
@@ -330,11 +361,12 @@ pub fn close_long_trade(
             is_close_long: true,
             final_token_amount: sell_token_amount,
             final_sol_amount: calc_result.output_sol,
-            user_close_profit: profit_sol,
+            user_close_profit,
             latest_price: calc_result.target_price,
             order_id: close_margin_order.order_id,
             order_index: close_margin_index,
             liquidate_indices: calc_result.liquidate_indices.clone(),
+            keeper_reward,
         });
 
         // 不在这里关,移到函数最后了 8. 关闭平仓订单的PDA账户并退还租金
@@ -744,7 +776,13 @@ pub fn close_short_trade(
             //     ctx.accounts.close_order.user,
             //     ctx.accounts.payer.key()
             // );
-            return Err(ErrorCode::OrderNotExpiredMustCloseByOwner.into());
+            // 已触发止盈的订单任何人都可以全部平仓
+            if !close_margin_order.is_take_profit_reached(ctx.accounts.curve_account.price) {
+                return Err(ErrorCode::OrderNotExpiredMustCloseByOwner.into());
+            }
+            if buy_token_amount != close_margin_order.lock_lp_token_amount {
+                return Err(ErrorCode::TakeProfitCloseMustBeFull.into());
+            }
         }
         //msg!("订单未超时，但由开仓者本人平仓，验证通过");
     } else {
@@ -797,6 +835,8 @@ pub fn close_short_trade(
         buy_token_amount,
         max_sol_amount,
         close_margin_order.borrow_fee,
+        None, // 开仓/平仓不自动止盈平仓
+        &[],
     )?;
 
     // 强制平仓手续费
@@ -838,8 +878,30 @@ pub fn close_short_trade(
             .checked_add(close_margin_order.borrow_amount)
             .ok_or(ErrorCode::CloseShortRepaymentOverflow)?;
 
+        // 非开仓者平掉已到期或已触发止盈的订单时, 从开仓者的平仓收入中支付清算奖励给调用者
+        let keeper_reward = calculate_keeper_reward(
+            ctx.accounts.payer.key() != close_margin_order.user,
+            profit_sol,
+        );
+        let user_close_profit = profit_sol
+            .checked_sub(keeper_reward)
+            .ok_or(ErrorCode::CloseShortProfitOverflow)?;
+
         // 把 盈利资金 sol转到, user_sol_account 账户
-        transfer_pool_to_user_if_positive!(profit_sol, ctx);
+        transfer_pool_to_user_if_positive!(user_close_profit, ctx);
+
+        // 把清算奖励从 pool_sol_account 转给调用者
+        if keeper_reward > 0 {
+            let payer_info = ctx.accounts.payer.to_account_info();
+            let base_fee_recipient_info = ctx.accounts.base_fee_recipient_account.to_account_info();
+            transfer_lamports!(
+                keeper_reward,
+                &ctx.accounts.pool_sol_account,
+                &payer_info,
+                &ctx.accounts.rent.to_account_info(),
+                &base_fee_recipient_info
+            )?;
+        }
 
         // 更新价格
         ctx.accounts.curve_account.price = calc_result.target_price;
@@ -944,11 +1006,12 @@ pub fn close_short_trade(
             is_close_long: false,
             final_token_amount: buy_token_amount,
             final_sol_amount: calc_result.required_sol,
-            user_close_profit: profit_sol,
+            user_close_profit,
             latest_price: calc_result.target_price,
             order_id: close_margin_order.order_id,
             order_index: close_margin_index,
             liquidate_indices: calc_result.liquidate_indices.clone(),
+            keeper_reward,
         });
 
         // 不在这里关,移到函数最后了  8. 关闭平仓订单的PDA账户并退还租金
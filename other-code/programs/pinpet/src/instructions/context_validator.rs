@@ -2,7 +2,7 @@
 use {
     crate::error::ErrorCode,
     crate::instructions::pdas::{BorrowingBondingCurve},
-    crate::instructions::contexts::{TradeBuySell, TradeClose, TradeLongShort},
+    crate::instructions::contexts::{AddMargin, ExtendOrder, TradeBuySell, TradeClose, TradeLongShort},
     anchor_lang::prelude::*,
 };
 
@@ -175,5 +175,74 @@ pub fn validate_trade_close_context(ctx: &Context<TradeClose>) -> Result<()> {
 
 
 
+    Ok(())
+}
+
+/// 验证AddMargin上下文中的账户约束
+///
+/// # 参数
+/// * `ctx` - AddMargin上下文
+///
+/// # 返回值
+/// * `Result<()>` - 验证成功返回Ok，失败返回错误
+pub fn validate_add_margin_context(ctx: &Context<AddMargin>) -> Result<()> {
+    // PDA账户所有权验证
+    require!(
+        ctx.accounts.up_orderbook.to_account_info().owner == ctx.program_id,
+        ErrorCode::InvalidAccountOwner
+    );
+    require!(
+        ctx.accounts.down_orderbook.to_account_info().owner == ctx.program_id,
+        ErrorCode::InvalidAccountOwner
+    );
+
+    // 订单簿地址验证
+    require!(
+        ctx.accounts.curve_account.up_orderbook == ctx.accounts.up_orderbook.key(),
+        ErrorCode::InvalidOrderMintAddress
+    );
+    require!(
+        ctx.accounts.curve_account.down_orderbook == ctx.accounts.down_orderbook.key(),
+        ErrorCode::InvalidOrderMintAddress
+    );
+
+    Ok(())
+}
+
+/// 验证ExtendOrder上下文中的账户约束
+///
+/// # 参数
+/// * `ctx` - ExtendOrder上下文
+///
+/// # 返回值
+/// * `Result<()>` - 验证成功返回Ok，失败返回错误
+pub fn validate_extend_order_context(ctx: &Context<ExtendOrder>) -> Result<()> {
+    // 验证手续费接收账户
+    validate_fee_recipient_accounts(
+        &ctx.accounts.curve_account,
+        &ctx.accounts.fee_recipient_account,
+        &ctx.accounts.base_fee_recipient_account,
+    )?;
+
+    // PDA账户所有权验证
+    require!(
+        ctx.accounts.up_orderbook.to_account_info().owner == ctx.program_id,
+        ErrorCode::InvalidAccountOwner
+    );
+    require!(
+        ctx.accounts.down_orderbook.to_account_info().owner == ctx.program_id,
+        ErrorCode::InvalidAccountOwner
+    );
+
+    // 订单簿地址验证
+    require!(
+        ctx.accounts.curve_account.up_orderbook == ctx.accounts.up_orderbook.key(),
+        ErrorCode::InvalidOrderMintAddress
+    );
+    require!(
+        ctx.accounts.curve_account.down_orderbook == ctx.accounts.down_orderbook.key(),
+        ErrorCode::InvalidOrderMintAddress
+    );
+
     Ok(())
 }
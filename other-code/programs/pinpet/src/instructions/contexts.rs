@@ -425,6 +425,98 @@ pub struct TradeClose<'info> {
 
 }
 
+// 定义追加保证金的上下文结构
+#[derive(Accounts)]
+pub struct AddMargin<'info> {
+    // 追加保证金的用户，必须是订单的开仓者
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    // 代币铸造账户 - 存储代币的基本信息
+    pub mint_account: Box<Account<'info, Mint>>,
+
+    // 借贷流动池账户 - 与mint_account一一对应
+    #[account(
+        seeds = [b"borrowing_curve", mint_account.key().as_ref()],
+        bump,
+    )]
+    pub curve_account: Account<'info, BorrowingBondingCurve>,
+
+    // 流动池SOL账户 - 接收追加的保证金
+    #[account(
+        mut,
+        seeds = [b"pool_sol", mint_account.key().as_ref()],
+        bump
+    )]
+    pub pool_sol_account: AccountInfo<'info>,
+
+    // Solana系统程序 - 处理SOL转账
+    pub system_program: Program<'info, System>,
+
+    // 做空订单账本 (Up方向) - 引用创币时创建的PDA
+    #[account(
+        mut,
+        seeds = [b"up_orderbook", mint_account.key().as_ref()],
+        bump,
+    )]
+    pub up_orderbook: AccountLoader<'info, OrderBook>,
+
+    // 做多订单账本 (Down方向) - 引用创币时创建的PDA
+    #[account(
+        mut,
+        seeds = [b"down_orderbook", mint_account.key().as_ref()],
+        bump,
+    )]
+    pub down_orderbook: AccountLoader<'info, OrderBook>,
+}
+
+// 定义订单延期的上下文结构
+#[derive(Accounts)]
+pub struct ExtendOrder<'info> {
+    // 申请延期的用户，必须是订单的开仓者
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    // 代币铸造账户 - 存储代币的基本信息
+    pub mint_account: Box<Account<'info, Mint>>,
+
+    // 借贷流动池账户 - 与mint_account一一对应
+    #[account(
+        seeds = [b"borrowing_curve", mint_account.key().as_ref()],
+        bump,
+    )]
+    pub curve_account: Account<'info, BorrowingBondingCurve>,
+
+    // Solana系统程序 - 处理手续费转账
+    pub system_program: Program<'info, System>,
+
+    // 合作伙伴手续费接收账户
+    /// CHECK: 合作伙伴手续费接收账户，地址在运行时从curve_account.fee_recipient验证
+    #[account(mut)]
+    pub fee_recipient_account: UncheckedAccount<'info>,
+
+    // 技术提供方基础手续费接收账户
+    /// CHECK: 基础手续费接收账户，地址在运行时从curve_account.base_fee_recipient验证
+    #[account(mut)]
+    pub base_fee_recipient_account: UncheckedAccount<'info>,
+
+    // 做空订单账本 (Up方向) - 引用创币时创建的PDA
+    #[account(
+        mut,
+        seeds = [b"up_orderbook", mint_account.key().as_ref()],
+        bump,
+    )]
+    pub up_orderbook: AccountLoader<'info, OrderBook>,
+
+    // 做多订单账本 (Down方向) - 引用创币时创建的PDA
+    #[account(
+        mut,
+        seeds = [b"down_orderbook", mint_account.key().as_ref()],
+        bump,
+    )]
+    pub down_orderbook: AccountLoader<'info, OrderBook>,
+}
+
 // 手动关闭 TradeCooldown PDA 的上下文结构
 #[derive(Accounts)]
 pub struct CloseCooldown<'info> {
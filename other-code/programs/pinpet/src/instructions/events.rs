@@ -58,6 +58,7 @@ pub struct LongShortEvent {
     pub position_asset_amount: u64,         // 当前持仓币的数量
     pub borrow_fee: u16,                    // 保证金交易手续费
     pub liquidate_indices: Vec<u16>,        // 需要清算的订单索引列表 (只是索引不是订单id哦!)
+    pub take_profit_price: u128,            // 止盈价格 (0 表示未设置)
 }
 
 
@@ -76,6 +77,7 @@ pub struct FullCloseEvent {
     pub order_id: u64,                      // 平仓订单订单的唯一编号
     pub order_index: u16,                   // 平仓的订单在订单账本中的索引
     pub liquidate_indices: Vec<u16>,        // 需要清算的订单索引列表 这里包括平仓订单自已 (只是索引不是订单id哦!)
+    pub keeper_reward: u64,                 // 支付给调用者的清算奖励sol数量 (开仓者自己平仓时为0)
 }
 
 // 定义部分平仓事件
@@ -117,4 +119,36 @@ pub struct MilestoneDiscountEvent {
     pub swap_fee: u16,                     // 现货交易手续费
     pub borrow_fee: u16,                   // 保证金交易手续费
     pub fee_discount_flag: u8,             // 手续费折扣标志 0: 原价 1: 5折 2: 2.5折  3: 1.25折
-}
\ No newline at end of file
+}
+
+// 追加保证金 事件
+#[event]
+pub struct AddMarginEvent {
+    pub payer: Pubkey,
+    pub mint_account: Pubkey,
+    pub order_id: u64,                      // 订单的唯一编号
+    pub order_index: u16,                   // 订单在订单账本中的索引
+    pub order_type: u8,                     // 订单类型 1: 做多 2: 做空
+    pub add_sol_amount: u64,                // 本次追加的保证金SOL数量
+    pub margin_sol_amount: u64,             // 追加后的保证金SOL数量
+    pub lock_lp_start_price: u128,          // 锁定流动池区间开始价(追加后)
+    pub lock_lp_end_price: u128,            // 锁定流动池区间结束价(追加后)
+    pub lock_lp_sol_amount: u64,            // 锁定流动池区间sol数量(追加后)
+    pub lock_lp_token_amount: u64,          // 锁定流动池区间token数量
+    pub latest_price: u128,                 // 最新的价格
+}
+
+// 订单延期 事件
+#[event]
+pub struct ExtendOrderEvent {
+    pub payer: Pubkey,
+    pub mint_account: Pubkey,
+    pub order_id: u64,                      // 订单的唯一编号
+    pub order_index: u16,                   // 订单在订单账本中的索引
+    pub order_type: u8,                     // 订单类型 1: 做多 2: 做空
+    pub extend_seconds: u32,                // 本次延期的秒数
+    pub old_end_time: u32,                  // 延期前的到期时间戳(秒)
+    pub end_time: u32,                      // 延期后的到期时间戳(秒)
+    pub fee_sol_amount: u64,                // 本次收取的延期手续费(SOL)
+    pub latest_price: u128,                 // 最新的价格
+}
@@ -0,0 +1,167 @@
+// 导入所需的模块和依赖项
+use {
+    // 导入常量
+    crate::constants::{MAX_CLOSE_INSERT_INDICES, MAX_ORDER_DURATION_MULTIPLIER},
+    // 导入曲线AMM计算模块
+    crate::curve::curve_amm::FEE_DENOMINATOR,
+    // 导入错误处理
+    crate::error::ErrorCode,
+    crate::instructions::contexts::ExtendOrder,
+    // 导入上下文验证函数
+    crate::instructions::context_validator::validate_extend_order_context,
+    crate::instructions::events::ExtendOrderEvent,
+    // 导入订单簿管理器
+    crate::instructions::orderbook_manager::{MarginOrderUpdateData, OrderBookManager},
+    crate::instructions::structs::MarginOrder,
+    crate::instructions::utils::{calculate_fee_split, find_order_by_indices},
+    // 导入 Anchor 框架的基础组件
+    anchor_lang::prelude::*,
+};
+
+// 订单延期 - 开仓者支付与延期时长成比例的借贷手续费, 推迟订单的 end_time
+//
+// 手续费 = 借款价值(SOL) * borrow_fee * 延期秒数 / borrow_duration, 即按开仓手续费率对延长的时间折算
+// 延期后订单总时长 (end_time - start_time) 不能超过 borrow_duration * MAX_ORDER_DURATION_MULTIPLIER
+pub fn extend_order(
+    ctx: Context<ExtendOrder>,
+    is_long: bool,                 // true: 做多订单(down_orderbook) false: 做空订单(up_orderbook)
+    order_id: u64,                 // 订单的唯一编号
+    close_order_indices: Vec<u16>, // 订单的位置索引 (可以有多个,万一订单被移动了,就会自动找第二位置)
+    extend_seconds: u32,           // 延期的秒数
+) -> Result<()> {
+    validate_extend_order_context(&ctx)?;
+
+    if extend_seconds == 0 {
+        return Err(ErrorCode::InvalidExtendDuration.into());
+    }
+
+    // 验证 close_order_indices 数量
+    if close_order_indices.is_empty() {
+        return Err(ErrorCode::EmptyCloseInsertIndices.into());
+    }
+    if close_order_indices.len() > MAX_CLOSE_INSERT_INDICES {
+        return Err(ErrorCode::TooManyCloseInsertIndices.into());
+    }
+
+    let orderbook_info = if is_long {
+        ctx.accounts.down_orderbook.to_account_info()
+    } else {
+        ctx.accounts.up_orderbook.to_account_info()
+    };
+
+    // 查找订单
+    let (order, order_index) = find_order_by_indices(&orderbook_info, order_id, &close_order_indices)?;
+
+    // 只有开仓者本人可以延期
+    if ctx.accounts.payer.key() != order.user {
+        return Err(ErrorCode::ExtendOrderNotOwner.into());
+    }
+
+    // 已到期的订单任何人都可以平仓, 不能再延期
+    let current_timestamp = Clock::get()?.unix_timestamp as u32;
+    if current_timestamp >= order.end_time {
+        return Err(ErrorCode::ExtendOrderExpired.into());
+    }
+
+    let borrow_duration = ctx.accounts.curve_account.borrow_duration;
+
+    // 计算新的到期时间并检查最长总时长
+    let new_end_time = order
+        .end_time
+        .checked_add(extend_seconds)
+        .ok_or(ErrorCode::DeadlineCalculationOverflow)?;
+    let max_total_duration = borrow_duration
+        .checked_mul(MAX_ORDER_DURATION_MULTIPLIER)
+        .ok_or(ErrorCode::DeadlineCalculationOverflow)?;
+    let total_duration = new_end_time
+        .checked_sub(order.start_time)
+        .ok_or(ErrorCode::DeadlineCalculationOverflow)?;
+    if total_duration > max_total_duration {
+        return Err(ErrorCode::ExtendOrderExceedsMaxDuration.into());
+    }
+
+    let fee_sol = calculate_extend_fee(&order, extend_seconds, borrow_duration)
+        .ok_or(ErrorCode::ExtendOrderFeeOverflow)?;
+
+    // 手续费转账 - 与开仓手续费使用同样的分配逻辑
+    if fee_sol > 0 {
+        let fee_split_result = calculate_fee_split(fee_sol, ctx.accounts.curve_account.fee_split)?;
+        // 转账给合作伙伴
+        if fee_split_result.partner_fee > 0 {
+            let partner_fee_transfer_ctx = CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: ctx.accounts.fee_recipient_account.to_account_info(),
+                },
+            );
+            anchor_lang::system_program::transfer(
+                partner_fee_transfer_ctx,
+                fee_split_result.partner_fee,
+            )?;
+        }
+
+        // 转账给技术提供方
+        if fee_split_result.base_fee > 0 {
+            let base_fee_transfer_ctx = CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: ctx.accounts.base_fee_recipient_account.to_account_info(),
+                },
+            );
+            anchor_lang::system_program::transfer(
+                base_fee_transfer_ctx,
+                fee_split_result.base_fee,
+            )?;
+        }
+    }
+
+    // 更新订单的到期时间
+    let update_data = MarginOrderUpdateData {
+        end_time: Some(new_end_time),
+        ..Default::default()
+    };
+
+    OrderBookManager::update_order(&orderbook_info, order_index, order_id, &update_data)?;
+
+    emit!(ExtendOrderEvent {
+        payer: ctx.accounts.payer.key(),
+        mint_account: ctx.accounts.mint_account.key(),
+        order_id,
+        order_index,
+        order_type: order.order_type,
+        extend_seconds,
+        old_end_time: order.end_time,
+        end_time: new_end_time,
+        fee_sol_amount: fee_sol,
+        latest_price: ctx.accounts.curve_account.price,
+    });
+
+    Ok(())
+}
+
+/// 计算延期手续费, 向上取整
+///
+/// 借款价值: 做多为借入的SOL (borrow_amount), 做空为买回借入代币所需的SOL (lock_lp_sol_amount)
+///
+/// # 返回值
+/// * `Option<u64>` - 成功则返回Some(手续费SOL数量)，溢出或 borrow_duration 为0时返回None
+fn calculate_extend_fee(order: &MarginOrder, extend_seconds: u32, borrow_duration: u32) -> Option<u64> {
+    let borrow_value_sol = if order.order_type == 1 {
+        order.borrow_amount
+    } else {
+        order.lock_lp_sol_amount
+    };
+
+    let numerator = u128::from(borrow_value_sol)
+        .checked_mul(u128::from(order.borrow_fee))?
+        .checked_mul(u128::from(extend_seconds))?;
+    let denominator = u128::from(FEE_DENOMINATOR).checked_mul(u128::from(borrow_duration))?;
+    if denominator == 0 {
+        return None;
+    }
+
+    let fee = numerator.checked_add(denominator - 1)?.checked_div(denominator)?;
+    u64::try_from(fee).ok()
+}
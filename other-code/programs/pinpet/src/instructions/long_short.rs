@@ -29,6 +29,7 @@ pub fn long_trade(
     margin_sol_max: u64,            // 最大保证金数量 (SOL)
     close_price: u128,              // 平仓价格
     close_insert_indices: Vec<u16>, // 平仓时插入订单簿的位置索引 u16::MAX代表插入到最前面 (可以有多个,是防止我们需要的位置刚好被删除,就会自动找第二位置)
+    take_profit_price: u128,        // 止盈价格 (0 表示不设置止盈)
 ) -> Result<()> {
     // 输出函数名和关键参数
     // msg!("-处理保证金做多交易-");
@@ -85,6 +86,8 @@ pub fn long_trade(
         buy_token_amount,
         max_sol_amount,
         fee,
+        None, // 开仓/平仓不自动止盈平仓
+        &[],
     )?;
 
     // 实际需要借出的SOL数量
@@ -184,7 +187,7 @@ pub fn long_trade(
         .ok_or(ErrorCode::DeadlineCalculationOverflow)?;
 
     // 创建新的 MarginOrder 实例
-    let new_margin_order = crate::instructions::structs::MarginOrder {
+    let mut new_margin_order = crate::instructions::structs::MarginOrder {
         // ========== 32-byte 对齐字段 (Pubkey) ==========
         // 用户公钥
         user: ctx.accounts.payer.key(),
@@ -238,9 +241,21 @@ pub fn long_trade(
         // ========== 1-byte 对齐字段 (u8) ==========
         // 订单类型: 1=做多(Down方向)
         order_type: 1,
+        // 止盈价 (下面设置)
+        take_profit_price: [0; 12],
         // 保留字段（对齐到结构体 32-byte 边界）
-        _padding: [0; 13],
+        _padding: [0; 1],
     };
+
+    // 设置止盈价: 做多的止盈价必须高于开仓后的价格
+    if take_profit_price > 0 {
+        if take_profit_price <= calc_buy_result.target_price {
+            return Err(ErrorCode::InvalidTakeProfitPrice.into());
+        }
+        new_margin_order
+            .set_take_profit_price(take_profit_price)
+            .ok_or(ErrorCode::InvalidTakeProfitPrice)?;
+    }
     // msg!(
     //     "生成新的做多订单: 用户={}, 保证金={}, 借款={}, 持仓Token={}, 开仓价={}, 止损价={}",
     //     new_margin_order.user,
@@ -753,6 +768,7 @@ pub fn long_trade(
         position_asset_amount: new_margin_order.position_asset_amount,
         borrow_fee: new_margin_order.borrow_fee,
         liquidate_indices: calc_buy_result.liquidate_indices.clone(),
+        take_profit_price,
     });
 
     // msg!("订单插入处理完成");
@@ -768,6 +784,7 @@ pub fn short_trade(
     margin_sol_max: u64,           // 最大保证金数量 (SOL)
     close_price: u128,             // 平仓价格
     close_insert_indices: Vec<u16>, // 平仓时插入订单簿的位置索引 (可以有多个,是防止我们需要的位置刚好被删除,就会自动找第二位置)
+    take_profit_price: u128,       // 止盈价格 (0 表示不设置止盈)
 ) -> Result<()> {
     // msg!("-处理保证金做空交易-");
 
@@ -820,6 +837,8 @@ pub fn short_trade(
         borrow_sell_token_amount,
         min_sol_output,
         fee,
+        None, // 开仓/平仓不自动止盈平仓
+        &[],
     )?;
 
     // 1. 确保得到的 token 等于 borrow_sell_token_amount
@@ -899,7 +918,7 @@ pub fn short_trade(
 
     // msg!("borrow_sell_token_amount = {}", borrow_sell_token_amount);
     // 创建新的 MarginOrder 实例
-    let new_margin_order = crate::instructions::structs::MarginOrder {
+    let mut new_margin_order = crate::instructions::structs::MarginOrder {
         // ========== 32-byte 对齐字段 (Pubkey) ==========
         // 用户公钥
         user: ctx.accounts.payer.key(),
@@ -953,9 +972,21 @@ pub fn short_trade(
         // ========== 1-byte 对齐字段 (u8) ==========
         // 订单类型: 2=做空(Up方向)
         order_type: 2,
+        // 止盈价 (下面设置)
+        take_profit_price: [0; 12],
         // 保留字段（对齐到结构体 32-byte 边界）
-        _padding: [0; 13],
+        _padding: [0; 1],
     };
+
+    // 设置止盈价: 做空的止盈价必须低于开仓后的价格
+    if take_profit_price > 0 {
+        if take_profit_price >= calc_sell_result.target_price {
+            return Err(ErrorCode::InvalidTakeProfitPrice.into());
+        }
+        new_margin_order
+            .set_take_profit_price(take_profit_price)
+            .ok_or(ErrorCode::InvalidTakeProfitPrice)?;
+    }
     // msg!(
     //     "生成新的做空订单: 用户={}, 保证金={}, 借款Token={}, 持仓SOL={}, 开仓价={}, 止损价={}",
     //     new_margin_order.user,
@@ -1545,6 +1576,7 @@ pub fn short_trade(
         position_asset_amount: new_margin_order.position_asset_amount,
         borrow_fee: new_margin_order.borrow_fee,
         liquidate_indices: calc_sell_result.liquidate_indices.clone(),
+        take_profit_price,
     });
 
 
@@ -14,6 +14,8 @@ pub mod context_validator;
 pub mod utils;
 pub mod trade_engine;
 pub mod cooldown_utils;
+pub mod add_margin;
+pub mod extend_order;
 
 
 // 重新导出指令
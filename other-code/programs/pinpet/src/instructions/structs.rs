@@ -150,8 +150,13 @@ pub struct MarginOrder {
     /// 订单类型: 1=做多(Down方向) 2=做空(Up方向) - 1 byte
     pub order_type: u8,
 
-    /// 保留字段（对齐到结构体 32-byte 边界，bytemuck::Pod 要求无 padding） - 13 bytes
-    pub _padding: [u8; 13],
+    /// 止盈价 (小端 96 位整数，0 表示未设置) - 12 bytes
+    /// 占用原保留字段，旧订单这里全是0，即没有止盈，不需要迁移账户数据
+    /// 通过 take_profit_price() / set_take_profit_price() 读写
+    pub take_profit_price: [u8; 12],
+
+    /// 保留字段（对齐到结构体 32-byte 边界，bytemuck::Pod 要求无 padding） - 1 byte
+    pub _padding: [u8; 1],
 }
 
 
@@ -159,6 +164,38 @@ impl MarginOrder {
     /// 槽位大小: 使用 size_of 自动计算
     pub const SIZE: usize = std::mem::size_of::<MarginOrder>();
 
+    /// 止盈价能表示的最大值 (96 位)
+    pub const MAX_TAKE_PROFIT_PRICE: u128 = (1u128 << 96) - 1;
+
+    /// 读取止盈价，0 表示未设置
+    pub fn take_profit_price(&self) -> u128 {
+        let mut bytes = [0u8; 16];
+        bytes[..12].copy_from_slice(&self.take_profit_price);
+        u128::from_le_bytes(bytes)
+    }
+
+    /// 设置止盈价，超过 96 位时返回 None
+    pub fn set_take_profit_price(&mut self, price: u128) -> Option<()> {
+        if price > Self::MAX_TAKE_PROFIT_PRICE {
+            return None;
+        }
+        self.take_profit_price.copy_from_slice(&price.to_le_bytes()[..12]);
+        Some(())
+    }
+
+    /// 当前价格是否已触发止盈 (做多: 价格涨到止盈价; 做空: 价格跌到止盈价)
+    pub fn is_take_profit_reached(&self, current_price: u128) -> bool {
+        let take_profit_price = self.take_profit_price();
+        if take_profit_price == 0 {
+            return false;
+        }
+        if self.order_type == 1 {
+            current_price >= take_profit_price
+        } else {
+            current_price <= take_profit_price
+        }
+    }
+
     // /// 检查订单是否过期（可被任何用户平仓）
     // pub fn is_expired(&self, current_timestamp: u32) -> bool {
     //     self.end_time > 0 && current_timestamp >= self.end_time
@@ -1,13 +1,15 @@
 // 交易计算模块 - 包含买入和卖出交易的计算逻辑
 use {
     // 导入常量
-    crate::constants::{MAX_TOKEN_DIFFERENCE},
+    crate::constants::{MAX_TOKEN_DIFFERENCE, MAX_TAKE_PROFIT_CLOSES_PER_TRADE, TRADE_COOLDOWN_SECONDS},
     // 导入曲线计算模块
     crate::curve::curve_amm::CurveAMM,
     // 导入错误处理
     crate::error::ErrorCode,
     // 导入订单管理器和链表方向枚举
     crate::instructions::orderbook_manager::OrderBookManager,
+    // 导入订单结构
+    crate::instructions::structs::MarginOrder,
     // 导入参数结构和账户结构
     crate::instructions::pdas::BorrowingBondingCurve,
     // 导入 Anchor 框架的基础组件
@@ -29,6 +31,8 @@ pub struct BuyAmountsResult {
     pub liquidate_fee_sol: u64,
     /// 需要清算的订单索引列表
     pub liquidate_indices: Vec<u16>,
+    /// 买入后价格到达止盈价而自动平仓的做多订单 (按平仓顺序)
+    pub take_profit_closes: Vec<TakeProfitClose>,
 }
 
 /// 卖出交易计算结果
@@ -46,6 +50,27 @@ pub struct SellAmountsResult {
     pub liquidate_fee_sol: u64,
     /// 需要清算的订单索引列表
     pub liquidate_indices: Vec<u16>,
+    /// 卖出后价格到达止盈价而自动平仓的做空订单 (按平仓顺序)
+    pub take_profit_closes: Vec<TakeProfitClose>,
+}
+
+/// 止盈自动平仓的计算结果, 资金转账和删除订单由调用的指令完成
+#[derive(Debug)]
+pub struct TakeProfitClose {
+    /// 订单ID (订单索引在前面的平仓删除订单后会变化, 所以按ID记录)
+    pub order_id: u64,
+    /// 开仓者地址, 平仓收入转到这里
+    pub user: Pubkey,
+    /// 平仓卖出(做多)或买回(做空)的代币数量
+    pub token_amount: u64,
+    /// 做多: 卖出得到的SOL(已扣手续费); 做空: 买回需要的SOL(不含手续费)
+    pub sol_amount: u64,
+    /// 平仓手续费
+    pub fee_sol: u64,
+    /// 归还借款后开仓者的平仓收入 (尚未扣除清算奖励)
+    pub profit_sol: u64,
+    /// 平仓后的价格
+    pub target_price: u128,
 }
 
 /// 计算买入时所需的SOL数量和可获得的token数量，并处理订单逻辑, 注意: 这里为平仓定单归还了借币池
@@ -61,8 +86,8 @@ pub struct SellAmountsResult {
 /// * `fee` - 手续费率
 ///
 /// # 返回值
-/// * `Result<BuyAmountsResult>` - 成功则返回包含交易详情的结构体
-pub fn buy_amounts<'info>(
+/// * `Result<BuyAmountsResult>` - 成功则返回包含交易详情的结构体 (不含止盈平仓)
+fn buy_amounts_on_curve<'info>(
     curve_account: &mut Account<'info, BorrowingBondingCurve>,
     up_orderbook: &AccountInfo<'info>,
     pass_order_id: Option<u64>,
@@ -145,6 +170,7 @@ pub fn buy_amounts<'info>(
             fee_sol,
             liquidate_fee_sol: 0,          // 没有强制平仓，手续费为0
             liquidate_indices: Vec::new(), // 没有需要清算的订单
+            take_profit_closes: Vec::new(),
         });
     } else {
         // msg!(
@@ -213,6 +239,7 @@ pub fn buy_amounts<'info>(
                 fee_sol,
                 liquidate_fee_sol: 0,          // 没有强制平仓，手续费为0
                 liquidate_indices: Vec::new(), // 没有需要清算的订单
+                take_profit_closes: Vec::new(),
             });
         } else {
             // msg!("分支2-2, 止损被触发");
@@ -387,6 +414,7 @@ pub fn buy_amounts<'info>(
                             fee_sol,
                             liquidate_fee_sol: 0,
                             liquidate_indices: Vec::new(),
+                            take_profit_closes: Vec::new(),
                         });
                     }
 
@@ -471,8 +499,8 @@ pub fn buy_amounts<'info>(
 /// * `output_sol_min` - 最小期望获得的SOL数量
 ///
 /// # 返回值
-/// * `Result<SellAmountsResult>` - 成功则返回包含交易详情的结构体
-pub fn sell_amounts<'info>(
+/// * `Result<SellAmountsResult>` - 成功则返回包含交易详情的结构体 (不含止盈平仓)
+fn sell_amounts_on_curve<'info>(
     curve_account: &mut Account<'info, BorrowingBondingCurve>,
     down_orderbook: &AccountInfo<'info>,
     pass_order_id: Option<u64>,
@@ -552,6 +580,7 @@ pub fn sell_amounts<'info>(
             fee_sol,
             liquidate_fee_sol: 0,          // 没有强制平仓，手续费为0
             liquidate_indices: Vec::new(), // 没有需要清算的订单
+            take_profit_closes: Vec::new(),
         });
     } else {
         // msg!(
@@ -630,6 +659,7 @@ pub fn sell_amounts<'info>(
                 fee_sol,
                 liquidate_fee_sol: 0,          // 没有强制平仓，手续费为0
                 liquidate_indices: Vec::new(), // 没有需要清算的订单
+                take_profit_closes: Vec::new(),
             });
         } else {
             // msg!("分支2-2, 止损被触发");
@@ -850,6 +880,7 @@ pub fn sell_amounts<'info>(
                             fee_sol,
                             liquidate_fee_sol: 0,
                             liquidate_indices: Vec::new(),
+                            take_profit_closes: Vec::new(),
                         });
                     }
 
@@ -926,3 +957,234 @@ pub fn sell_amounts<'info>(
 
     Err(ErrorCode::InsufficientMarketLiquidity.into())
 }
+
+/// 计算买入交易, 买入后价格到达做多订单的止盈价时自动平仓这些订单
+///
+/// # 参数
+/// * 前6个参数同 buy_amounts_on_curve
+/// * `down_orderbook` - 做多订单簿账户, None 表示不处理止盈 (开仓/平仓指令)
+/// * `payout_users` - 交易中以可写账户提供的地址, 只有这些开仓者的订单会被自动止盈平仓
+///
+/// # 返回值
+/// * `Result<BuyAmountsResult>` - target_price 是买入本身的成交价, 止盈平仓后的价格见 take_profit_closes
+pub fn buy_amounts<'info>(
+    curve_account: &mut Account<'info, BorrowingBondingCurve>,
+    up_orderbook: &AccountInfo<'info>,
+    pass_order_id: Option<u64>,
+    output_token_amount: u64,
+    input_sol_max: u64,
+    fee: u16,
+    down_orderbook: Option<&AccountInfo<'info>>,
+    payout_users: &[Pubkey],
+) -> Result<BuyAmountsResult> {
+    let mut result = buy_amounts_on_curve(
+        curve_account,
+        up_orderbook,
+        pass_order_id,
+        output_token_amount,
+        input_sol_max,
+        fee,
+    )?;
+
+    if let Some(down_orderbook) = down_orderbook {
+        result.take_profit_closes = take_profit_closes(
+            curve_account,
+            down_orderbook,
+            true,
+            result.target_price,
+            payout_users,
+        )?;
+    }
+
+    Ok(result)
+}
+
+/// 计算卖出交易, 卖出后价格到达做空订单的止盈价时自动平仓这些订单
+///
+/// # 参数
+/// * 前6个参数同 sell_amounts_on_curve
+/// * `up_orderbook` - 做空订单簿账户, None 表示不处理止盈 (开仓/平仓指令)
+/// * `payout_users` - 交易中以可写账户提供的地址, 只有这些开仓者的订单会被自动止盈平仓
+///
+/// # 返回值
+/// * `Result<SellAmountsResult>` - target_price 是卖出本身的成交价, 止盈平仓后的价格见 take_profit_closes
+pub fn sell_amounts<'info>(
+    curve_account: &mut Account<'info, BorrowingBondingCurve>,
+    down_orderbook: &AccountInfo<'info>,
+    pass_order_id: Option<u64>,
+    input_token_amount: u64,
+    output_sol_min: u64,
+    fee: u16,
+    up_orderbook: Option<&AccountInfo<'info>>,
+    payout_users: &[Pubkey],
+) -> Result<SellAmountsResult> {
+    let mut result = sell_amounts_on_curve(
+        curve_account,
+        down_orderbook,
+        pass_order_id,
+        input_token_amount,
+        output_sol_min,
+        fee,
+    )?;
+
+    if let Some(up_orderbook) = up_orderbook {
+        result.take_profit_closes = take_profit_closes(
+            curve_account,
+            up_orderbook,
+            false,
+            result.target_price,
+            payout_users,
+        )?;
+    }
+
+    Ok(result)
+}
+
+/// 按链表顺序找出价格已到达止盈价的订单并计算平仓, 注意: 这里为平仓订单归还了借币池
+///
+/// 和止损一样在交易内完成, 但止盈平仓本身也是一笔交易 (做多卖出, 做空买回), 所以:
+/// - 每笔平仓从上一笔平仓后的价格开始计算, 平仓后价格可能回到其他订单的止盈价之外
+/// - 平仓会使价格越过同一订单簿中其他订单的止损起点时跳过该订单, 不连锁触发止损
+/// - 没有提供开仓者账户或还在开仓冷却时间内的订单跳过, 仍可通过平仓指令平仓
+/// - 每笔交易最多平仓 MAX_TAKE_PROFIT_CLOSES_PER_TRADE 个订单
+///
+/// # 参数
+/// * `curve_account` - 曲线账户 (归还借币池)
+/// * `orderbook` - 要检查止盈的订单簿 (买入时是做多订单簿, 卖出时是做空订单簿)
+/// * `is_long` - true=做多订单簿, false=做空订单簿
+/// * `start_price` - 交易完成后的价格
+/// * `payout_users` - 可以接收平仓收入的开仓者地址
+fn take_profit_closes<'info>(
+    curve_account: &mut Account<'info, BorrowingBondingCurve>,
+    orderbook: &AccountInfo<'info>,
+    is_long: bool,
+    start_price: u128,
+    payout_users: &[Pubkey],
+) -> Result<Vec<TakeProfitClose>> {
+    if payout_users.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // 按链表顺序(从最靠近当前价格的订单开始)读取所有订单
+    let mut orders: Vec<MarginOrder> = Vec::new();
+    {
+        let data = orderbook.data.borrow();
+        OrderBookManager::traverse(&data, u16::MAX, 0, |_, order| {
+            orders.push(*order);
+            Ok(true)
+        })?;
+    }
+
+    // 与平仓指令一样, 开仓冷却时间内的订单不能平仓
+    let current_timestamp = Clock::get()?.unix_timestamp as u32;
+
+    let mut price = start_price;
+    let mut closes: Vec<TakeProfitClose> = Vec::new();
+
+    for order in orders.iter() {
+        if closes.len() >= MAX_TAKE_PROFIT_CLOSES_PER_TRADE {
+            break;
+        }
+        if !order.is_take_profit_reached(price)
+            || !payout_users.contains(&order.user)
+            || current_timestamp.saturating_sub(order.start_time) < TRADE_COOLDOWN_SECONDS
+        {
+            continue;
+        }
+
+        // 止损边界: 链表中第一个没有被平仓的其他订单的止损起点
+        let boundary = orders
+            .iter()
+            .find(|o| o.order_id != order.order_id && !closes.iter().any(|c| c.order_id == o.order_id))
+            .map(|o| o.lock_lp_start_price);
+
+        let close = if is_long {
+            // 做多止盈: 从当前价格卖出全部持仓代币, 与 close_long_trade 全平仓相同
+            let Some((end_price, sol_output)) =
+                CurveAMM::sell_from_price_with_token_input(price, order.lock_lp_token_amount)
+            else {
+                continue;
+            };
+            if boundary.is_some_and(|b| end_price < b) {
+                continue;
+            }
+            let sol_after_fee = CurveAMM::calculate_amount_after_fee(sol_output, order.borrow_fee)
+                .ok_or(ErrorCode::CloseLongFeeOverflow)?;
+            let fee_sol = sol_output
+                .checked_sub(sol_after_fee)
+                .ok_or(ErrorCode::CloseLongFeeOverflow)?;
+            // 卖出收入 + 保证金 不够归还借款时不自动平仓
+            let Some(profit_sol) = sol_after_fee
+                .checked_add(order.margin_sol_amount)
+                .and_then(|v| v.checked_sub(order.borrow_amount))
+            else {
+                continue;
+            };
+
+            // 归还借币池
+            curve_account.borrow_sol_reserve = curve_account
+                .borrow_sol_reserve
+                .checked_add(order.borrow_amount)
+                .ok_or(ErrorCode::CloseLongRepaymentOverflow)?;
+
+            TakeProfitClose {
+                order_id: order.order_id,
+                user: order.user,
+                token_amount: order.lock_lp_token_amount,
+                sol_amount: sol_after_fee,
+                fee_sol,
+                profit_sol,
+                target_price: end_price,
+            }
+        } else {
+            // 做空止盈: 从当前价格买回全部借入代币, 与 close_short_trade 全平仓相同
+            let Some((end_price, sol_required)) =
+                CurveAMM::buy_from_price_with_token_output(price, order.lock_lp_token_amount)
+            else {
+                continue;
+            };
+            if boundary.is_some_and(|b| end_price > b) {
+                continue;
+            }
+            let sol_with_fee = CurveAMM::calculate_total_amount_with_fee(sol_required, order.borrow_fee)
+                .ok_or(ErrorCode::CloseShortFeeOverflow)?;
+            let fee_sol = sol_with_fee
+                .checked_sub(sol_required)
+                .ok_or(ErrorCode::CloseShortFeeOverflow)?;
+
+            // 在止损起点买回锁定代币所需的SOL(含手续费) 减去 实际买回的花费(含手续费)
+            let (_close_end_price, close_reduced_sol) = CurveAMM::buy_from_price_with_token_output(
+                order.lock_lp_start_price,
+                order.lock_lp_token_amount,
+            )
+            .ok_or(ErrorCode::PriceCalculationError)?;
+            let close_reduced_sol_with_fee =
+                CurveAMM::calculate_total_amount_with_fee(close_reduced_sol, order.borrow_fee)
+                    .ok_or(ErrorCode::CloseShortFeeOverflow)?;
+            let Some(profit_sol) = close_reduced_sol_with_fee.checked_sub(sol_with_fee) else {
+                continue;
+            };
+
+            // 归还借币池
+            curve_account.borrow_token_reserve = curve_account
+                .borrow_token_reserve
+                .checked_add(order.borrow_amount)
+                .ok_or(ErrorCode::CloseShortRepaymentOverflow)?;
+
+            TakeProfitClose {
+                order_id: order.order_id,
+                user: order.user,
+                token_amount: order.lock_lp_token_amount,
+                sol_amount: sol_required,
+                fee_sol,
+                profit_sol,
+                target_price: end_price,
+            }
+        };
+
+        price = close.target_price;
+        closes.push(close);
+    }
+
+    Ok(closes)
+}
@@ -1,5 +1,6 @@
 use anchor_lang::prelude::*;
 use crate::error::ErrorCode;
+use crate::instructions::orderbook_manager::OrderBookManager;
 
 
 
@@ -71,4 +72,64 @@ pub fn calculate_fee_split(total_fee: u64, fee_split: u8) -> Result<FeeSplitResu
     })
 }
 
+/// 按候选索引查找订单, 每个索引同时检查其前后节点
+///
+/// # 返回值
+/// * `Result<(crate::instructions::structs::MarginOrder, u16)>` - 找到的订单及其索引
+pub fn find_order_by_indices(
+    orderbook_info: &AccountInfo,
+    order_id: u64,
+    close_order_indices: &[u16],
+) -> Result<(crate::instructions::structs::MarginOrder, u16)> {
+    let orderbook_data = orderbook_info.data.borrow();
+
+    for &current_index in close_order_indices {
+        let current_order = match OrderBookManager::get_order(&orderbook_data, current_index) {
+            Ok(order) => order,
+            Err(_) => continue,
+        };
+
+        // 检查当前节点
+        if current_order.order_id == order_id {
+            return Ok((*current_order, current_index));
+        }
+
+        // 检查前一个节点和后一个节点
+        for neighbor_index in [current_order.prev_order, current_order.next_order] {
+            if neighbor_index == u16::MAX {
+                continue;
+            }
+            if let Ok(neighbor) = OrderBookManager::get_order(&orderbook_data, neighbor_index) {
+                if neighbor.order_id == order_id {
+                    return Ok((*neighbor, neighbor_index));
+                }
+            }
+        }
+    }
+
+    Err(ErrorCode::CloseOrderNotFound.into())
+}
 
+/// 计算清算奖励
+///
+/// 只有非开仓者平掉已到期或已触发止盈的订单时才有奖励, 奖励从开仓者的平仓收入中扣除,
+/// 取 KEEPER_REWARD_SOL_AMOUNT 和 平仓收入 * KEEPER_REWARD_MAX_PERCENT% 中的较小值
+///
+/// # 参数
+/// * `is_keeper_close` - 是否由非开仓者平仓
+/// * `user_close_profit` - 开仓者的平仓收入
+///
+/// # 返回值
+/// * `u64` - 清算奖励, 一定小于 user_close_profit (收入大于0时)
+pub fn calculate_keeper_reward(is_keeper_close: bool, user_close_profit: u64) -> u64 {
+    if !is_keeper_close {
+        return 0;
+    }
+
+    // 用 u128 计算避免溢出, 结果不大于 user_close_profit
+    let max_reward = (u128::from(user_close_profit)
+        * u128::from(crate::constants::KEEPER_REWARD_MAX_PERCENT)
+        / 100) as u64;
+
+    crate::constants::KEEPER_REWARD_SOL_AMOUNT.min(max_reward)
+}
@@ -98,6 +98,7 @@ pub mod pinpet {
         margin_sol: u64,                // 保证金数量 (SOL)
         close_price: u128,              // 平仓价格
         close_insert_indices: Vec<u16>, // 开仓时插入订单簿的位置索引
+        take_profit_price: u128,        // 止盈价格 (0 表示不设置止盈)
     ) -> Result<()> {
         instructions::long_short::long_trade(
             ctx,
@@ -106,6 +107,7 @@ pub mod pinpet {
             margin_sol,
             close_price,
             close_insert_indices,
+            take_profit_price,
         )
     }
 
@@ -117,6 +119,7 @@ pub mod pinpet {
         margin_sol: u64,                // 保证金数量 (SOL)
         close_price: u128,              // 平仓价格
         close_insert_indices: Vec<u16>, // 开仓时插入订单簿的位置索引
+        take_profit_price: u128,        // 止盈价格 (0 表示不设置止盈)
     ) -> Result<()> {
         instructions::long_short::short_trade(
             ctx,
@@ -125,6 +128,7 @@ pub mod pinpet {
             margin_sol,
             close_price,
             close_insert_indices,
+            take_profit_price,
         )
     }
 
@@ -229,6 +233,28 @@ pub mod pinpet {
             close_order_indices
         )
     }
+
+    // 追加保证金指令 (只有开仓者可以调用, 订单到期后不能追加)
+    pub fn add_margin(
+        ctx: Context<AddMargin>,
+        is_long: bool,                 // true: 做多订单 false: 做空订单 (订单ID在各自的订单账本内唯一)
+        order_id: u64,                 // 订单的唯一编号
+        close_order_indices: Vec<u16>, // 订单的位置索引 (可以有多个,万一订单被移动了,就会自动找第二位置)
+        add_sol: u64,                  // 追加的保证金数量 (SOL)
+    ) -> Result<()> {
+        instructions::add_margin::add_margin(ctx, is_long, order_id, close_order_indices, add_sol)
+    }
+
+    // 订单延期指令 (只有开仓者可以调用, 按延期时长收取借贷手续费, 订单到期后不能延期)
+    pub fn extend_order(
+        ctx: Context<ExtendOrder>,
+        is_long: bool,                 // true: 做多订单 false: 做空订单 (订单ID在各自的订单账本内唯一)
+        order_id: u64,                 // 订单的唯一编号
+        close_order_indices: Vec<u16>, // 订单的位置索引 (可以有多个,万一订单被移动了,就会自动找第二位置)
+        extend_seconds: u32,           // 延期的秒数
+    ) -> Result<()> {
+        instructions::extend_order::extend_order(ctx, is_long, order_id, close_order_indices, extend_seconds)
+    }
 }
 
 #[derive(Accounts)]
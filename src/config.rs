@@ -1,5 +1,8 @@
 use serde::Deserialize;
 use anyhow::Result;
+use arc_swap::ArcSwap;
+use std::collections::HashMap;
+use std::sync::Arc;
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct Config {
@@ -9,12 +12,262 @@ pub struct Config {
     pub ipfs: IpfsConfig,
     #[serde(default)]
     pub kline: KlineServiceConfig,
+    #[serde(default)]
+    pub webhook: WebhookConfig,
+    /// 运行模式: primary 运行监听器并写库, replica 以只读副本提供查询
+    /// Run mode: primary runs the listener and writes, replica serves queries from a read-only secondary
+    #[serde(default)]
+    pub mode: RunMode,
+    /// 只读副本配置, 仅 replica 模式使用 / Read replica config, used in replica mode only
+    #[serde(default)]
+    pub replica: ReplicaConfig,
+}
+
+/// 运行模式 / Run mode
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RunMode {
+    /// 主实例: 监听链上事件并写入数据库 / Primary: listens for on-chain events and writes the database
+    #[default]
+    Primary,
+    /// 只读副本: 以 RocksDB secondary 打开主实例的数据库, 不启动监听器和 Webhook, 只挂载只读接口
+    /// Read replica: opens the primary's databases as RocksDB secondaries, starts no listener or webhooks, mounts read endpoints only
+    Replica,
+}
+
+/// 只读副本配置 / Read replica configuration
+#[derive(Debug, Deserialize, Clone)]
+pub struct ReplicaConfig {
+    /// secondary 实例的私有目录 (存放其 info log), 不能与主实例数据目录相同
+    /// Private directory of the secondary instances (holds their info logs), must differ from the primary data directories
+    #[serde(default = "default_replica_secondary_path")]
+    pub secondary_path: String,
+    /// 追赶主实例写入的间隔(毫秒) / Interval for catching up with the primary's writes (ms)
+    #[serde(default = "default_replica_catch_up_interval_ms")]
+    pub catch_up_interval_ms: u64,
+}
+
+impl Default for ReplicaConfig {
+    fn default() -> Self {
+        Self {
+            secondary_path: default_replica_secondary_path(),
+            catch_up_interval_ms: default_replica_catch_up_interval_ms(),
+        }
+    }
+}
+
+impl ReplicaConfig {
+    /// 某个数据库的 secondary 目录 / Secondary directory for one database
+    pub fn secondary_dir(&self, name: &str) -> String {
+        std::path::Path::new(&self.secondary_path)
+            .join(name)
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+fn default_replica_secondary_path() -> String {
+    "./data/replica".to_string()
+}
+
+fn default_replica_catch_up_interval_ms() -> u64 {
+    1000
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct ServerConfig {
     pub host: String,
     pub port: u16,
+    /// 限流配置 / Rate limiting config
+    #[serde(default)]
+    pub rate_limit: RateLimitConfig,
+    /// 管理接口 API-Key 列表, 为空时拒绝所有管理请求 / Admin API keys, all admin requests are rejected when empty
+    #[serde(default)]
+    pub admin_keys: Vec<String>,
+    /// 日志级别过滤 (如 "pinpet_server_v2=info"), 为空时使用 RUST_LOG / Log filter, falls back to RUST_LOG when unset
+    #[serde(default)]
+    pub log_level: Option<String>,
+    /// 跨域配置 / CORS config
+    #[serde(default)]
+    pub cors: CorsConfig,
+    /// 是否提供交互式 Swagger UI (/swagger-ui), 需要同时开启 enable_openapi_json
+    /// Serve the interactive Swagger UI (/swagger-ui), requires enable_openapi_json
+    #[serde(default = "default_docs_enabled")]
+    pub enable_swagger_ui: bool,
+    /// 是否提供 OpenAPI JSON (/api-docs/openapi.json) / Serve the OpenAPI JSON (/api-docs/openapi.json)
+    #[serde(default = "default_docs_enabled")]
+    pub enable_openapi_json: bool,
+    /// 同时处理的最大请求数, 超出时立即返回 503 (/health 不受限) / Max requests handled at once, excess requests get 503 immediately (/health exempt)
+    #[serde(default = "default_max_concurrent_requests")]
+    pub max_concurrent_requests: usize,
+}
+
+fn default_docs_enabled() -> bool {
+    true
+}
+
+fn default_max_concurrent_requests() -> usize {
+    1024
+}
+
+/// 跨域配置, 列表中的 "*" 表示允许任意值 (仅用于本地开发)
+/// CORS configuration, "*" in a list allows any value (local development only)
+#[derive(Debug, Deserialize, Clone)]
+pub struct CorsConfig {
+    /// 允许的来源, 为空时不允许跨域请求 / Allowed origins, cross-origin requests are refused when empty
+    #[serde(default)]
+    pub allowed_origins: Vec<String>,
+    #[serde(default = "default_cors_allowed_methods")]
+    pub allowed_methods: Vec<String>,       // 允许的请求方法 / Allowed methods
+    #[serde(default = "default_cors_allowed_headers")]
+    pub allowed_headers: Vec<String>,       // 允许的请求头 / Allowed request headers
+    #[serde(default)]
+    pub allow_credentials: bool,            // 是否允许携带凭证 / Allow credentials
+}
+
+/// 配置中表示任意值的通配符 / Wildcard meaning any value in the config
+pub const CORS_WILDCARD: &str = "*";
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: Vec::new(),
+            allowed_methods: default_cors_allowed_methods(),
+            allowed_headers: default_cors_allowed_headers(),
+            allow_credentials: false,
+        }
+    }
+}
+
+fn default_cors_allowed_methods() -> Vec<String> {
+    ["GET", "POST", "PUT", "DELETE", "OPTIONS"].iter().map(|m| m.to_string()).collect()
+}
+
+fn default_cors_allowed_headers() -> Vec<String> {
+    ["content-type", "x-api-key", "x-request-id"].iter().map(|h| h.to_string()).collect()
+}
+
+impl CorsConfig {
+    /// 列表是否为通配符 / Whether a list is the wildcard
+    pub fn is_wildcard(values: &[String]) -> bool {
+        values.iter().any(|v| v == CORS_WILDCARD)
+    }
+
+    /// 校验跨域配置 / Validate CORS config
+    fn validate(&self, problems: &mut Vec<String>) {
+        let lists: [(&str, &[String]); 3] = [
+            ("allowed_origins", &self.allowed_origins),
+            ("allowed_methods", &self.allowed_methods),
+            ("allowed_headers", &self.allowed_headers),
+        ];
+
+        for (field, values) in lists {
+            if Self::is_wildcard(values) {
+                if values.len() > 1 {
+                    problems.push(format!(
+                        "server.cors.{} 中 \"*\" 不能与其他值混用 / \"*\" cannot be combined with other values",
+                        field
+                    ));
+                }
+                // 浏览器不接受带凭证的通配符响应 / Browsers reject wildcard responses for credentialed requests
+                if self.allow_credentials {
+                    problems.push(format!(
+                        "server.cors.allow_credentials 不能与 {} = [\"*\"] 同时使用 / cannot be combined with {} = [\"*\"]",
+                        field, field
+                    ));
+                }
+                continue;
+            }
+
+            for value in values {
+                let valid = match field {
+                    "allowed_origins" => value.parse::<axum::http::HeaderValue>().is_ok() && reqwest::Url::parse(value).is_ok(),
+                    "allowed_methods" => value.parse::<axum::http::Method>().is_ok(),
+                    _ => value.parse::<axum::http::HeaderName>().is_ok(),
+                };
+                if !valid {
+                    problems.push(format!("server.cors.{} 包含非法值 / contains an invalid value: {}", field, value));
+                }
+            }
+        }
+    }
+}
+
+/// 限流配置 / Rate limiting configuration
+#[derive(Debug, Deserialize, Clone)]
+pub struct RateLimitConfig {
+    #[serde(default = "default_rate_limit_enabled")]
+    pub enabled: bool,                      // 是否启用限流 / Enable rate limiting
+    #[serde(default = "default_requests_per_second")]
+    pub requests_per_second: f64,           // 默认每秒请求数 / Default requests per second
+    #[serde(default = "default_burst")]
+    pub burst: u32,                         // 默认突发容量 / Default burst capacity
+    /// 按路由组覆盖 (db/tokens/orderbook/orderbook_dump/history) / Per route group overrides (db/tokens/orderbook/orderbook_dump/history)
+    #[serde(default)]
+    pub groups: HashMap<String, RateLimitRule>,
+    /// 可信反向代理 (IP 或 CIDR); 只有来自这些地址的连接才读取 X-Forwarded-For / X-Real-IP, 其余按连接对端地址限流
+    /// Trusted reverse proxies (IPs or CIDRs); X-Forwarded-For / X-Real-IP are only read on connections from these
+    /// addresses, every other request is limited by its peer address
+    #[serde(default)]
+    pub trusted_proxies: Vec<String>,
+}
+
+/// 单个路由组的限流规则 / Rate limit rule for one route group
+#[derive(Debug, Deserialize, Clone, Copy)]
+pub struct RateLimitRule {
+    pub requests_per_second: f64,           // 每秒补充令牌数 / Tokens refilled per second
+    pub burst: u32,                         // 桶容量 / Bucket capacity
+}
+
+impl RateLimitConfig {
+    /// 获取路由组的规则, 未配置时使用默认值 / Get the rule for a route group, falling back to defaults
+    pub fn rule_for(&self, group: &str) -> RateLimitRule {
+        self.groups.get(group).copied().unwrap_or(RateLimitRule {
+            requests_per_second: self.requests_per_second,
+            burst: self.burst,
+        })
+    }
+
+    /// 地址是否属于可信代理, 无法解析的条目忽略 (启动时已校验) / Whether an address is a trusted proxy, unparsable entries are ignored (validated at startup)
+    pub fn is_trusted_proxy(&self, ip: std::net::IpAddr) -> bool {
+        self.trusted_proxies
+            .iter()
+            .filter_map(|entry| parse_proxy_net(entry))
+            .any(|net| net.contains(&ip))
+    }
+}
+
+/// 解析可信代理条目, 单个 IP 视为主机网段 / Parse a trusted proxy entry, a bare IP is taken as a host network
+fn parse_proxy_net(entry: &str) -> Option<ipnet::IpNet> {
+    let entry = entry.trim();
+    entry
+        .parse::<ipnet::IpNet>()
+        .ok()
+        .or_else(|| entry.parse::<std::net::IpAddr>().ok().map(ipnet::IpNet::from))
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            requests_per_second: 20.0,
+            burst: 40,
+            groups: HashMap::new(),
+            trusted_proxies: Vec::new(),
+        }
+    }
+}
+
+fn default_rate_limit_enabled() -> bool {
+    true
+}
+
+fn default_requests_per_second() -> f64 {
+    20.0
+}
+
+fn default_burst() -> u32 {
+    40
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -27,6 +280,12 @@ pub struct DatabaseConfig {
     /// OrderBook 数据库性能配置 / OrderBook database performance config
     #[serde(default)]
     pub orderbook_db: OrderBookDbConfig,
+    /// 通用键值接口的最大键长度(字节) / Max key length of the generic key-value endpoints (bytes)
+    #[serde(default = "default_max_kv_key_bytes")]
+    pub max_kv_key_bytes: usize,
+    /// 通用键值接口的最大值大小(字节) / Max value size of the generic key-value endpoints (bytes)
+    #[serde(default = "default_max_kv_value_bytes")]
+    pub max_kv_value_bytes: usize,
 }
 
 /// OrderBook 数据库性能配置 / OrderBook database performance configuration
@@ -85,20 +344,60 @@ fn default_orderbook_max_limit() -> usize {
     60000
 }
 
+fn default_max_kv_key_bytes() -> usize {
+    256
+}
+
+fn default_max_kv_value_bytes() -> usize {
+    64 * 1024
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct SolanaConfig {
-    pub rpc_url: String,                    // Solana RPC URL
+    pub rpc_url: String,                    // Solana RPC URL (主节点 / primary)
+    #[serde(default)]
+    pub rpc_fallback_urls: Vec<String>,     // 备用RPC节点 / Fallback RPC endpoints
+    #[serde(default = "default_rpc_failure_threshold")]
+    pub rpc_failure_threshold: u32,         // 连续失败多少次标记为不健康 / Consecutive failures before marking unhealthy
+    #[serde(default = "default_rpc_cooldown_secs")]
+    pub rpc_cooldown_secs: u64,             // 不健康节点冷却时间(秒) / Unhealthy endpoint cooldown (seconds)
+    #[serde(default = "default_rpc_max_retries")]
+    pub rpc_max_retries: u32,               // 可重试错误的最大重试次数, 0为不重试 / Max retries for retryable errors, 0 disables
+    #[serde(default = "default_rpc_retry_backoff_ms")]
+    pub rpc_retry_backoff_ms: u64,          // 首次重试前的等待(毫秒), 之后逐次翻倍 / Wait before the first retry (ms), doubled on each retry
     pub ws_url: String,                     // Solana WebSocket URL
     pub program_id: String,                 // 程序ID / Program ID
     pub enable_event_listener: bool,        // 是否启用事件监听 / Enable event listener
     pub commitment: String,                 // 承诺级别 / Commitment level: processed/confirmed/finalized
     pub reconnect_interval: u64,            // 重连间隔(秒) / Reconnect interval (seconds)
     pub max_reconnect_attempts: u32,        // 最大重连次数 / Max reconnect attempts
-    pub event_buffer_size: usize,           // 事件缓冲区大小 / Event buffer size
+    pub event_buffer_size: usize,           // 事件队列容量 / Event queue capacity
     pub event_batch_size: usize,            // 事件批处理大小 / Event batch size
     pub ping_interval_seconds: u64,         // WebSocket ping间隔(秒) / WebSocket ping interval
     pub process_failed_transactions: bool,  // 是否处理失败的交易 / Process failed transactions
     pub enable_raw_message_logging: bool,   // 是否记录原始消息 / Enable raw message logging
+    #[serde(default)]
+    pub enable_dry_run: bool,               // 演练模式: 只解析和记录日志, 不写库不推送 / Dry run: parse and log only, no DB writes or pushes
+    #[serde(default)]
+    pub finalize_before_apply: bool,        // 事件等到 finalized 才写库和更新订单簿 / Hold events until finalized before storing them and mutating the order book
+    #[serde(default)]
+    pub revert_orphaned_slots: bool,        // 事件立即应用, slot 被分叉丢弃时回滚订单簿和事件 / Apply events at once and roll back the order book and events of orphaned slots
+}
+
+fn default_rpc_failure_threshold() -> u32 {
+    3
+}
+
+fn default_rpc_cooldown_secs() -> u64 {
+    30
+}
+
+fn default_rpc_max_retries() -> u32 {
+    2
+}
+
+fn default_rpc_retry_backoff_ms() -> u64 {
+    200
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -118,11 +417,19 @@ pub struct KlineServiceConfig {
     #[serde(default = "default_max_subscriptions")]
     pub max_subscriptions_per_client: usize, // 每客户端最大订阅数 / Max subscriptions per client
     #[serde(default = "default_history_limit")]
-    pub history_data_limit: usize,          // 历史数据默认条数 / History data default limit
+    pub history_data_limit: usize,          // 历史数据条数上限(也是默认值) / History data max limit (also the default)
+    #[serde(default = "default_snapshot_candles")]
+    pub snapshot_candles: usize,            // 订阅时立即推送的快照K线数, 0为关闭 / Candles pushed immediately on subscribe, 0 disables
     #[serde(default = "default_ping_interval")]
     pub ping_interval_secs: u64,            // 心跳间隔(秒) / Ping interval (seconds)
     #[serde(default = "default_ping_timeout")]
     pub ping_timeout_secs: u64,             // 心跳超时(秒) / Ping timeout (seconds)
+    #[serde(default = "default_max_aggregated_mints")]
+    pub max_aggregated_mints: usize,        // 非预热 mint 的K线聚合器上限, 超出按 LRU 淘汰 / Max non-watchlist mint aggregators, LRU-evicted beyond it
+    #[serde(default = "default_whale_min_sol")]
+    pub whale_min_sol: u64,                 // whale_trades 频道的单笔 SOL 阈值(lamports) / Per-trade SOL threshold of the whale_trades channel (lamports)
+    #[serde(default)]
+    pub watchlist: Vec<String>,             // 启动即预热K线的 mint, 不依赖订阅 / Mints whose candles are warmed at startup, independent of subscriptions
 }
 
 impl Default for KlineServiceConfig {
@@ -132,8 +439,12 @@ impl Default for KlineServiceConfig {
             connection_timeout_secs: 60,
             max_subscriptions_per_client: 100,
             history_data_limit: 100,
+            snapshot_candles: default_snapshot_candles(),
             ping_interval_secs: 25,
             ping_timeout_secs: 60,
+            max_aggregated_mints: default_max_aggregated_mints(),
+            whale_min_sol: default_whale_min_sol(),
+            watchlist: Vec::new(),
         }
     }
 }
@@ -150,10 +461,25 @@ fn default_max_subscriptions() -> usize {
     100
 }
 
+fn default_max_aggregated_mints() -> usize {
+    1000
+}
+
+fn default_whale_min_sol() -> u64 {
+    10_000_000_000 // 10 SOL
+}
+
 fn default_history_limit() -> usize {
     100
 }
 
+/// 订阅快照K线数上限 / Upper bound for subscribe snapshot candles
+pub const MAX_KLINE_SNAPSHOT_CANDLES: usize = 50;
+
+fn default_snapshot_candles() -> usize {
+    10
+}
+
 fn default_ping_interval() -> u64 {
     25
 }
@@ -162,6 +488,78 @@ fn default_ping_timeout() -> u64 {
     60
 }
 
+/// Webhook 推送配置 / Webhook push configuration
+#[derive(Debug, Deserialize, Clone)]
+pub struct WebhookConfig {
+    #[serde(default)]
+    pub enabled: bool,                      // 是否启用 Webhook / Enable webhooks
+    #[serde(default)]
+    pub secret: String,                     // HMAC-SHA256 签名密钥 / HMAC-SHA256 signing secret
+    #[serde(default = "default_webhook_queue_size")]
+    pub queue_size: usize,                  // 待投递队列容量, 满时丢弃 / Pending delivery queue size, drops when full
+    #[serde(default = "default_webhook_max_retries")]
+    pub max_retries: u32,                   // 失败后最大重试次数 / Max retries after a failure
+    #[serde(default = "default_webhook_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,            // 首次重试等待(毫秒), 之后翻倍 / First retry delay (ms), doubled afterwards
+    #[serde(default = "default_webhook_timeout_secs")]
+    pub request_timeout_secs: u64,          // 单次请求超时(秒) / Per-request timeout (seconds)
+    #[serde(default)]
+    pub endpoints: Vec<WebhookEndpoint>,    // 推送目标 / Delivery targets
+}
+
+/// 单个 Webhook 目标 / A single webhook target
+#[derive(Debug, Deserialize, Clone)]
+pub struct WebhookEndpoint {
+    pub url: String,                        // 接收地址 / Receiver URL
+    /// 订阅的事件类型, 为空表示全部 / Subscribed event types, empty means all
+    /// token_created / buy_sell / long_short / full_close / partial_close / milestone_discount / add_margin / extend_order / liquidation
+    #[serde(default)]
+    pub events: Vec<String>,
+}
+
+/// Webhook 支持的事件类型 / Event types supported by webhooks
+pub const WEBHOOK_EVENT_TYPES: &[&str] = &[
+    "token_created",
+    "buy_sell",
+    "long_short",
+    "full_close",
+    "partial_close",
+    "milestone_discount",
+    "add_margin",
+    "extend_order",
+    "liquidation",
+];
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            secret: String::new(),
+            queue_size: 1000,
+            max_retries: 5,
+            initial_backoff_ms: 500,
+            request_timeout_secs: 10,
+            endpoints: Vec::new(),
+        }
+    }
+}
+
+fn default_webhook_queue_size() -> usize {
+    1000
+}
+
+fn default_webhook_max_retries() -> u32 {
+    5
+}
+
+fn default_webhook_initial_backoff_ms() -> u64 {
+    500
+}
+
+fn default_webhook_timeout_secs() -> u64 {
+    10
+}
+
 impl Config {
     pub fn new() -> Result<Self> {
         let settings = config::Config::builder()
@@ -172,4 +570,330 @@ impl Config {
         let config: Config = settings.try_deserialize()?;
         Ok(config)
     }
+
+    /// 校验配置, 一次性收集所有问题 / Validate config, collecting every problem at once
+    pub fn validate(&self) -> std::result::Result<(), Vec<String>> {
+        let mut problems = Vec::new();
+
+        // 服务器 / Server
+        if self.server.host.trim().is_empty() {
+            problems.push("server.host 不能为空 / server.host must not be empty".to_string());
+        }
+        if self.server.port == 0 {
+            problems.push("server.port 必须在 1..=65535 / server.port must be in 1..=65535".to_string());
+        }
+        if self.server.max_concurrent_requests == 0 {
+            problems.push("server.max_concurrent_requests 必须大于0 / must be > 0".to_string());
+        }
+        // Swagger UI 从 openapi.json 加载文档 / Swagger UI loads the spec from openapi.json
+        if self.server.enable_swagger_ui && !self.server.enable_openapi_json {
+            problems.push(
+                "server.enable_swagger_ui 需要 server.enable_openapi_json / server.enable_swagger_ui requires server.enable_openapi_json"
+                    .to_string(),
+            );
+        }
+        let rate_limit = &self.server.rate_limit;
+        let default_rule = RateLimitRule {
+            requests_per_second: rate_limit.requests_per_second,
+            burst: rate_limit.burst,
+        };
+        for (group, rule) in std::iter::once(("default", &default_rule))
+            .chain(rate_limit.groups.iter().map(|(k, v)| (k.as_str(), v)))
+        {
+            if !(rule.requests_per_second.is_finite() && rule.requests_per_second > 0.0) {
+                problems.push(format!(
+                    "server.rate_limit[{}].requests_per_second 必须大于0 / must be > 0, got {}",
+                    group, rule.requests_per_second
+                ));
+            }
+            if rule.burst == 0 {
+                problems.push(format!(
+                    "server.rate_limit[{}].burst 必须大于0 / must be > 0",
+                    group
+                ));
+            }
+        }
+
+        for entry in &rate_limit.trusted_proxies {
+            if parse_proxy_net(entry).is_none() {
+                problems.push(format!(
+                    "server.rate_limit.trusted_proxies 条目不是 IP 或 CIDR / entry is not an IP or CIDR: {:?}",
+                    entry
+                ));
+            }
+        }
+
+        self.server.cors.validate(&mut problems);
+
+        // 数据库 / Database
+        check_writable_dir("database.rocksdb_path", &self.database.rocksdb_path, &mut problems);
+        check_writable_dir("database.orderbook_db_path", &self.database.orderbook_db_path, &mut problems);
+        if self.database.orderbook_max_limit == 0 {
+            problems.push("database.orderbook_max_limit 必须大于0 / must be > 0".to_string());
+        }
+        if self.database.max_kv_key_bytes == 0 {
+            problems.push("database.max_kv_key_bytes 必须大于0 / must be > 0".to_string());
+        }
+        if self.database.max_kv_value_bytes == 0 {
+            problems.push("database.max_kv_value_bytes 必须大于0 / must be > 0".to_string());
+        }
+
+        // 只读副本 / Read replica
+        if self.mode == RunMode::Replica {
+            check_writable_dir("replica.secondary_path", &self.replica.secondary_path, &mut problems);
+            if self.replica.catch_up_interval_ms == 0 {
+                problems.push("replica.catch_up_interval_ms 必须大于0 / must be > 0".to_string());
+            }
+        }
+
+        // Solana
+        check_url("solana.rpc_url", &self.solana.rpc_url, &["http", "https"], &mut problems);
+        for (i, url) in self.solana.rpc_fallback_urls.iter().enumerate() {
+            check_url(&format!("solana.rpc_fallback_urls[{}]", i), url, &["http", "https"], &mut problems);
+        }
+        if self.solana.rpc_failure_threshold == 0 {
+            problems.push("solana.rpc_failure_threshold 必须大于0 / must be > 0".to_string());
+        }
+        check_url("solana.ws_url", &self.solana.ws_url, &["ws", "wss"], &mut problems);
+        if self.solana.program_id.parse::<solana_sdk::pubkey::Pubkey>().is_err() {
+            problems.push(format!(
+                "solana.program_id 不是合法的公钥 / is not a valid pubkey: {}",
+                self.solana.program_id
+            ));
+        }
+        if !matches!(self.solana.commitment.as_str(), "processed" | "confirmed" | "finalized") {
+            problems.push(format!(
+                "solana.commitment 必须是 processed/confirmed/finalized / must be processed/confirmed/finalized, got {}",
+                self.solana.commitment
+            ));
+        }
+        if self.solana.event_buffer_size == 0 {
+            problems.push("solana.event_buffer_size 必须大于0 / must be > 0".to_string());
+        }
+
+        // IPFS
+        check_url("ipfs.gateway_url", &self.ipfs.gateway_url, &["http", "https"], &mut problems);
+        if self.ipfs.request_timeout_seconds == 0 {
+            problems.push("ipfs.request_timeout_seconds 必须大于0 / must be > 0".to_string());
+        }
+
+        // K线 / K-line
+        if self.kline.history_data_limit == 0 {
+            problems.push("kline.history_data_limit 必须大于0 / must be > 0".to_string());
+        }
+        if self.kline.snapshot_candles > MAX_KLINE_SNAPSHOT_CANDLES {
+            problems.push(format!(
+                "kline.snapshot_candles({}) 不能超过 {} / must not exceed {}",
+                self.kline.snapshot_candles, MAX_KLINE_SNAPSHOT_CANDLES, MAX_KLINE_SNAPSHOT_CANDLES
+            ));
+        }
+        for mint in &self.kline.watchlist {
+            if mint.trim().parse::<solana_sdk::pubkey::Pubkey>().is_err() {
+                problems.push(format!(
+                    "kline.watchlist 包含无效的 mint 地址 / kline.watchlist contains an invalid mint address: {}",
+                    mint
+                ));
+            }
+        }
+        if self.kline.whale_min_sol == 0 {
+            problems.push("kline.whale_min_sol 必须大于0 / must be > 0".to_string());
+        }
+        if self.kline.max_aggregated_mints == 0 {
+            problems.push("kline.max_aggregated_mints 必须大于0 / must be > 0".to_string());
+        }
+        if self.kline.max_subscriptions_per_client == 0 {
+            problems.push("kline.max_subscriptions_per_client 必须大于0 / must be > 0".to_string());
+        }
+        if self.kline.ping_interval_secs == 0 || self.kline.ping_timeout_secs <= self.kline.ping_interval_secs {
+            problems.push(format!(
+                "kline.ping_timeout_secs({}) 必须大于 ping_interval_secs({}) 且间隔不能为0 / must exceed ping_interval_secs, and the interval must be > 0",
+                self.kline.ping_timeout_secs, self.kline.ping_interval_secs
+            ));
+        }
+
+        // Webhook
+        if self.webhook.enabled {
+            if self.webhook.secret.is_empty() {
+                problems.push("webhook.secret 启用时不能为空 / must not be empty when enabled".to_string());
+            }
+            if self.webhook.queue_size == 0 {
+                problems.push("webhook.queue_size 必须大于0 / must be > 0".to_string());
+            }
+            if self.webhook.request_timeout_secs == 0 {
+                problems.push("webhook.request_timeout_secs 必须大于0 / must be > 0".to_string());
+            }
+            for (i, endpoint) in self.webhook.endpoints.iter().enumerate() {
+                check_url(&format!("webhook.endpoints[{}].url", i), &endpoint.url, &["http", "https"], &mut problems);
+                for event in &endpoint.events {
+                    if !WEBHOOK_EVENT_TYPES.contains(&event.as_str()) {
+                        problems.push(format!(
+                            "webhook.endpoints[{}].events 未知事件类型 / unknown event type: {}, expected one of {:?}",
+                            i, event, WEBHOOK_EVENT_TYPES
+                        ));
+                    }
+                }
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems)
+        }
+    }
+}
+
+/// 可热加载的配置子集 (SIGHUP 时替换) / Hot-reloadable config subset (swapped on SIGHUP)
+#[derive(Debug, Clone)]
+pub struct ReloadableConfig {
+    pub kline: KlineServiceConfig,
+    pub rate_limit: RateLimitConfig,
+    pub log_level: Option<String>,
+}
+
+/// 运行时共享的可热加载配置 / Shared hot-reloadable config read at runtime
+pub type LiveConfig = Arc<ArcSwap<ReloadableConfig>>;
+
+impl ReloadableConfig {
+    /// 从完整配置中提取可热加载部分 / Extract the reloadable portion from the full config
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            kline: config.kline.clone(),
+            rate_limit: config.server.rate_limit.clone(),
+            log_level: config.server.log_level.clone(),
+        }
+    }
+
+    /// 创建共享句柄 / Create the shared handle
+    pub fn live(config: &Config) -> LiveConfig {
+        Arc::new(ArcSwap::from_pointee(Self::from_config(config)))
+    }
+
+    /// 列出与新配置的差异 / List differences against a new config
+    pub fn changes(&self, new: &ReloadableConfig) -> Vec<String> {
+        let mut changes = Vec::new();
+        let sections = [
+            ("kline", format!("{:?}", self.kline), format!("{:?}", new.kline)),
+            ("server.rate_limit", format!("{:?}", self.rate_limit), format!("{:?}", new.rate_limit)),
+            ("server.log_level", format!("{:?}", self.log_level), format!("{:?}", new.log_level)),
+        ];
+        for (name, old, new) in sections {
+            if old != new {
+                changes.push(format!("{}: {} -> {}", name, old, new));
+            }
+        }
+        changes
+    }
+}
+
+impl Config {
+    /// 列出修改后需要重启才能生效的配置项 / List changed settings that only take effect after a restart
+    pub fn non_reloadable_changes(&self, new: &Config) -> Vec<&'static str> {
+        let mut changed = Vec::new();
+        if self.server.host != new.server.host || self.server.port != new.server.port {
+            changed.push("server.host/server.port");
+        }
+        if self.server.admin_keys != new.server.admin_keys {
+            changed.push("server.admin_keys");
+        }
+        if self.database.rocksdb_path != new.database.rocksdb_path
+            || self.database.orderbook_db_path != new.database.orderbook_db_path
+        {
+            changed.push("database paths");
+        }
+        if format!("{:?}", self.solana) != format!("{:?}", new.solana) {
+            changed.push("solana");
+        }
+        if format!("{:?}", self.ipfs) != format!("{:?}", new.ipfs) {
+            changed.push("ipfs");
+        }
+        if self.kline.enable_kline_service != new.kline.enable_kline_service
+            || self.kline.ping_interval_secs != new.kline.ping_interval_secs
+            || self.kline.ping_timeout_secs != new.kline.ping_timeout_secs
+        {
+            changed.push("kline.enable_kline_service/kline.ping_*");
+        }
+        if self.kline.watchlist != new.kline.watchlist {
+            changed.push("kline.watchlist");
+        }
+        if format!("{:?}", self.webhook) != format!("{:?}", new.webhook) {
+            changed.push("webhook");
+        }
+        if self.mode != new.mode || format!("{:?}", self.replica) != format!("{:?}", new.replica) {
+            changed.push("mode/replica");
+        }
+        changed
+    }
+}
+
+/// 校验 URL 格式与协议 / Check URL syntax and scheme
+fn check_url(field: &str, value: &str, schemes: &[&str], problems: &mut Vec<String>) {
+    match reqwest::Url::parse(value) {
+        Ok(url) if schemes.contains(&url.scheme()) => {}
+        Ok(url) => problems.push(format!(
+            "{} 协议必须是 {:?} / scheme must be one of {:?}, got {}",
+            field, schemes, schemes, url.scheme()
+        )),
+        Err(e) => problems.push(format!("{} 不是合法的URL / is not a valid URL ({}): {}", field, value, e)),
+    }
+}
+
+/// 校验目录可写 (不存在时创建) / Check directory is writable (created if missing)
+fn check_writable_dir(field: &str, path: &str, problems: &mut Vec<String>) {
+    if path.trim().is_empty() {
+        problems.push(format!("{} 不能为空 / must not be empty", field));
+        return;
+    }
+    if let Err(e) = std::fs::create_dir_all(path) {
+        problems.push(format!("{} 无法创建目录 / cannot create directory {}: {}", field, path, e));
+        return;
+    }
+    let probe = std::path::Path::new(path).join(".write_probe");
+    match std::fs::write(&probe, b"") {
+        Ok(()) => {
+            let _ = std::fs::remove_file(&probe);
+        }
+        Err(e) => problems.push(format!("{} 目录不可写 / directory is not writable {}: {}", field, path, e)),
+    }
+}
+
+/// 测试用最小配置, 不读取 config.toml / Minimal config for tests, does not read config.toml
+#[cfg(test)]
+pub(crate) fn test_config() -> Config {
+    const TEST_CONFIG: &str = r#"
+        [server]
+        host = "127.0.0.1"
+        port = 0
+
+        [database]
+        rocksdb_path = "./data/event"
+        orderbook_db_path = "./data/orderbook"
+
+        [solana]
+        rpc_url = "http://127.0.0.1:8899"
+        ws_url = "ws://127.0.0.1:8900"
+        program_id = "HNaandW3U5sVTsoJaGx61UmX9Siupa6difFY9qRAPXyw"
+        enable_event_listener = false
+        commitment = "confirmed"
+        reconnect_interval = 5
+        max_reconnect_attempts = 1
+        event_buffer_size = 16
+        event_batch_size = 1
+        ping_interval_seconds = 30
+        process_failed_transactions = false
+        enable_raw_message_logging = false
+
+        [ipfs]
+        gateway_url = "http://127.0.0.1:8080/ipfs/"
+        request_timeout_seconds = 1
+        max_retries = 0
+        retry_delay_seconds = 0
+    "#;
+
+    config::Config::builder()
+        .add_source(config::File::from_str(TEST_CONFIG, config::FileFormat::Toml))
+        .build()
+        .unwrap()
+        .try_deserialize()
+        .unwrap()
 }
@@ -0,0 +1,368 @@
+// 曲线状态存储模块 - 每个 mint 的 BorrowingBondingCurve 链下副本
+// Curve state storage module - off-chain copy of each mint's BorrowingBondingCurve
+//
+// 价格与流动池储备量跟随事件的 latest_price 更新 (链上同样用 price_to_reserves 重新计算储备量),
+// 借贷池储备量按开仓借出 / 平仓与清算归还的 borrow_amount 增减; 事件不携带的字段 (fee_split, borrow_duration)
+// 以及服务启动前创建的代币, 通过链上账户快照补齐
+// Price and LP reserves follow the events' latest_price (on-chain also recomputes reserves with price_to_reserves),
+// borrow reserves move by the borrow_amount lent on open / returned on close and liquidation; fields events don't
+// carry (fee_split, borrow_duration) and tokens created before the server started are filled from an on-chain snapshot
+
+use anyhow::Result;
+use borsh::BorshDeserialize;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_with::{serde_as, DisplayFromStr};
+use solana_sdk::pubkey::Pubkey;
+use std::sync::Arc;
+use tracing::debug;
+use utoipa::ToSchema;
+
+use crate::db::kv::KvStore;
+use crate::solana::events::TokenCreatedEvent;
+use crate::util::curve::CurveAMM;
+
+/// 创建代币时借贷池的初始token数量 (create_token.rs) / Initial borrow pool token reserve at token creation (create_token.rs)
+pub const INITIAL_BORROW_TOKEN_RESERVE: u64 = 536_500_000_000_000;
+
+/// 创建代币时虚拟借贷池的初始SOL数量 (create_token.rs) / Initial virtual borrow pool SOL reserve at token creation (create_token.rs)
+pub const INITIAL_BORROW_SOL_RESERVE: u64 = 10_000_000_000_000_000;
+
+/// Anchor 账户判别器长度 / Anchor account discriminator length
+const ACCOUNT_DISCRIMINATOR_LEN: usize = 8;
+
+/// 每个 mint 的曲线状态 / Per-mint curve state
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CurveState {
+    /// 代币mint地址 / Token mint address
+    pub mint: String,
+    /// 曲线账户地址 / Curve account address
+    pub curve_account: String,
+    /// 当前价格 / Current price (u128 as string)
+    #[serde_as(as = "DisplayFromStr")]
+    #[schema(value_type = String)]
+    pub price: u128,
+    /// 流动池token储备 / LP token reserve
+    pub lp_token_reserve: u64,
+    /// 流动池SOL储备 / LP SOL reserve
+    pub lp_sol_reserve: u64,
+    /// 借贷池token储备 (做空借出) / Borrow pool token reserve (lent to shorts)
+    pub borrow_token_reserve: u64,
+    /// 虚拟借贷池SOL储备 (做多借出) / Virtual borrow pool SOL reserve (lent to longs)
+    pub borrow_sol_reserve: u64,
+    /// 现货交易手续费 / Spot trading fee
+    pub swap_fee: u16,
+    /// 保证金交易手续费 / Margin trading fee
+    pub borrow_fee: u16,
+    /// 手续费折扣标志 / Fee discount flag
+    pub fee_discount_flag: u8,
+    /// 合作伙伴手续费分成比例 (0-100), 未从链上同步时为空 / Partner fee split (0-100), None until synced from chain
+    pub fee_split: Option<u8>,
+    /// 贷款时长(秒), 未从链上同步时为空 / Borrow duration (seconds), None until synced from chain
+    pub borrow_duration: Option<u32>,
+    /// 最近一次链上快照的slot, 不晚于该slot的事件已包含在快照中 / Slot of the last on-chain snapshot; events at or before it are already included
+    pub synced_slot: u64,
+    /// 最近一次应用的事件slot / Slot of the last applied event
+    pub last_slot: u64,
+    /// 最后更新时间 / Last update timestamp
+    pub updated_at: i64,
+}
+
+/// 单个事件对曲线状态的影响 / Effect of a single event on the curve state
+#[derive(Debug, Clone, Default)]
+pub struct CurveUpdate {
+    /// 事件后的最新价格 / Latest price after the event
+    pub price: Option<u128>,
+    /// 借贷池SOL储备变化 / Change in borrow SOL reserve
+    pub borrow_sol_delta: i128,
+    /// 借贷池token储备变化 / Change in borrow token reserve
+    pub borrow_token_delta: i128,
+    /// 新的现货手续费 / New spot trading fee
+    pub swap_fee: Option<u16>,
+    /// 新的保证金手续费 / New margin trading fee
+    pub borrow_fee: Option<u16>,
+    /// 新的手续费折扣标志 / New fee discount flag
+    pub fee_discount_flag: Option<u8>,
+}
+
+/// 链上 BorrowingBondingCurve 账户布局 (pdas.rs) / On-chain BorrowingBondingCurve account layout (pdas.rs)
+#[derive(BorshDeserialize)]
+struct BorrowingBondingCurveRaw {
+    lp_token_reserve: u64,
+    lp_sol_reserve: u64,
+    price: u128,
+    borrow_token_reserve: u64,
+    borrow_sol_reserve: u64,
+    swap_fee: u16,
+    borrow_fee: u16,
+    fee_discount_flag: u8,
+    _base_fee_recipient: Pubkey,
+    _fee_recipient: Pubkey,
+    fee_split: u8,
+    borrow_duration: u32,
+    mint: Pubkey,
+}
+
+impl CurveState {
+    /// 从 TokenCreatedEvent 构建初始状态 / Build the initial state from a TokenCreatedEvent
+    pub fn from_token_created(event: &TokenCreatedEvent) -> Self {
+        let (lp_sol_reserve, lp_token_reserve) =
+            CurveAMM::price_to_reserves(event.latest_price).unwrap_or((0, 0));
+
+        Self {
+            mint: event.mint_account.clone(),
+            curve_account: event.curve_account.clone(),
+            price: event.latest_price,
+            lp_token_reserve,
+            lp_sol_reserve,
+            borrow_token_reserve: INITIAL_BORROW_TOKEN_RESERVE,
+            borrow_sol_reserve: INITIAL_BORROW_SOL_RESERVE,
+            swap_fee: event.swap_fee,
+            borrow_fee: event.borrow_fee,
+            fee_discount_flag: event.fee_discount_flag,
+            fee_split: None,
+            borrow_duration: None,
+            synced_slot: 0,
+            last_slot: event.slot,
+            updated_at: Utc::now().timestamp(),
+        }
+    }
+
+    /// 从链上账户数据解析状态 / Decode the state from on-chain account data
+    ///
+    /// `slot` 为读取账户时的上下文slot / `slot` is the context slot the account was read at
+    pub fn from_account_data(mint: &str, curve_account: &str, data: &[u8], slot: u64) -> Result<Self> {
+        if data.len() < ACCOUNT_DISCRIMINATOR_LEN {
+            return Err(anyhow::anyhow!(
+                "曲线账户数据过短 / Curve account data too short: {} bytes",
+                data.len()
+            ));
+        }
+
+        // 账户可能带有尾部空间, 只读取需要的字段 / The account may have trailing space, read only the needed fields
+        let mut buf = &data[ACCOUNT_DISCRIMINATOR_LEN..];
+        let raw = BorrowingBondingCurveRaw::deserialize(&mut buf)
+            .map_err(|e| anyhow::anyhow!("曲线账户解析失败 / Failed to decode curve account: {}", e))?;
+
+        if raw.mint.to_string() != mint {
+            return Err(anyhow::anyhow!(
+                "曲线账户mint不匹配 / Curve account mint mismatch: expected {}, got {}",
+                mint,
+                raw.mint
+            ));
+        }
+
+        Ok(Self {
+            mint: mint.to_string(),
+            curve_account: curve_account.to_string(),
+            price: raw.price,
+            lp_token_reserve: raw.lp_token_reserve,
+            lp_sol_reserve: raw.lp_sol_reserve,
+            borrow_token_reserve: raw.borrow_token_reserve,
+            borrow_sol_reserve: raw.borrow_sol_reserve,
+            swap_fee: raw.swap_fee,
+            borrow_fee: raw.borrow_fee,
+            fee_discount_flag: raw.fee_discount_flag,
+            fee_split: Some(raw.fee_split),
+            borrow_duration: Some(raw.borrow_duration),
+            synced_slot: slot,
+            last_slot: slot,
+            updated_at: Utc::now().timestamp(),
+        })
+    }
+
+    /// 应用事件更新 / Apply an event update
+    fn apply(&mut self, slot: u64, update: &CurveUpdate) {
+        if let Some(price) = update.price {
+            self.price = price;
+            // 与链上一致, 流动池储备量由价格重新计算 / As on-chain, LP reserves are recomputed from the price
+            if let Some((sol_reserve, token_reserve)) = CurveAMM::price_to_reserves(price) {
+                self.lp_sol_reserve = sol_reserve;
+                self.lp_token_reserve = token_reserve;
+            }
+        }
+        self.borrow_sol_reserve = apply_delta(self.borrow_sol_reserve, update.borrow_sol_delta);
+        self.borrow_token_reserve = apply_delta(self.borrow_token_reserve, update.borrow_token_delta);
+        if let Some(swap_fee) = update.swap_fee {
+            self.swap_fee = swap_fee;
+        }
+        if let Some(borrow_fee) = update.borrow_fee {
+            self.borrow_fee = borrow_fee;
+        }
+        if let Some(fee_discount_flag) = update.fee_discount_flag {
+            self.fee_discount_flag = fee_discount_flag;
+        }
+        self.last_slot = self.last_slot.max(slot);
+        self.updated_at = Utc::now().timestamp();
+    }
+}
+
+/// 带符号增量, 结果截断在 u64 范围内 / Signed delta, clamped to the u64 range
+fn apply_delta(value: u64, delta: i128) -> u64 {
+    (i128::from(value) + delta).clamp(0, i128::from(u64::MAX)) as u64
+}
+
+/// 曲线状态存储管理器 / Curve state storage manager
+pub struct CurveStorage {
+    db: Arc<dyn KvStore>,
+}
+
+impl CurveStorage {
+    /// 创建新的曲线状态存储管理器 / Create new curve state storage manager
+    pub fn new(db: Arc<dyn KvStore>) -> Self {
+        Self { db }
+    }
+
+    /// 根据mint获取曲线状态 / Get curve state by mint
+    pub fn get_curve(&self, mint: &str) -> Result<Option<CurveState>> {
+        let key = format!("curve:{}", mint);
+        match self.db.get(key.as_bytes())? {
+            Some(data) => Ok(Some(serde_json::from_slice(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// 保存曲线状态 (覆盖) / Save curve state (overwrites)
+    pub fn save_curve(&self, state: &CurveState) -> Result<()> {
+        let key = format!("curve:{}", state.mint);
+        self.db.put(key.as_bytes(), &serde_json::to_vec(state)?)?;
+        Ok(())
+    }
+
+    /// 由 TokenCreatedEvent 初始化曲线状态, 已存在时跳过, 返回是否写入
+    /// Initialize curve state from a TokenCreatedEvent, skipping if it exists, returns whether it wrote
+    pub fn init_from_token_created(&self, event: &TokenCreatedEvent) -> Result<bool> {
+        if self.get_curve(&event.mint_account)?.is_some() {
+            return Ok(false);
+        }
+        self.save_curve(&CurveState::from_token_created(event))?;
+        Ok(true)
+    }
+
+    /// 应用事件更新, 状态不存在或事件已包含在链上快照中时跳过, 返回是否写入
+    /// Apply an event update, skipping when the state is missing or the event is already in the on-chain snapshot, returns whether it wrote
+    pub fn apply_update(&self, mint: &str, slot: u64, update: &CurveUpdate) -> Result<bool> {
+        let Some(mut state) = self.get_curve(mint)? else {
+            return Ok(false);
+        };
+
+        if slot <= state.synced_slot {
+            debug!(
+                "跳过已包含在链上快照中的曲线更新 / Skipping curve update already in on-chain snapshot: mint={}, slot={}, synced_slot={}",
+                mint, slot, state.synced_slot
+            );
+            return Ok(false);
+        }
+
+        state.apply(slot, update);
+        self.save_curve(&state)?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::kv::MemoryStore;
+    use chrono::TimeZone;
+
+    fn memory_storage() -> CurveStorage {
+        CurveStorage::new(Arc::new(MemoryStore::new()))
+    }
+
+    fn token_created(mint: &str, slot: u64) -> TokenCreatedEvent {
+        TokenCreatedEvent {
+            payer: "Payer".to_string(),
+            mint_account: mint.to_string(),
+            curve_account: "Curve".to_string(),
+            pool_token_account: String::new(),
+            pool_sol_account: String::new(),
+            fee_recipient: String::new(),
+            base_fee_recipient: String::new(),
+            params_account: String::new(),
+            swap_fee: 1000,
+            borrow_fee: 1200,
+            fee_discount_flag: 0,
+            name: "Test".to_string(),
+            symbol: "TST".to_string(),
+            uri: String::new(),
+            up_orderbook: String::new(),
+            down_orderbook: String::new(),
+            latest_price: CurveAMM::get_initial_price().unwrap(),
+            timestamp: Utc.timestamp_opt(0, 0).unwrap(),
+            signature: "sig".to_string(),
+            slot,
+            event_index: 0,
+        }
+    }
+
+    #[test]
+    fn test_token_created_then_updates() {
+        let storage = memory_storage();
+        assert!(storage.init_from_token_created(&token_created("MintA", 10)).unwrap());
+        assert!(!storage.init_from_token_created(&token_created("MintA", 11)).unwrap());
+
+        let state = storage.get_curve("MintA").unwrap().unwrap();
+        assert_eq!(state.borrow_sol_reserve, INITIAL_BORROW_SOL_RESERVE);
+        assert_eq!((state.lp_sol_reserve, state.lp_token_reserve), CurveAMM::price_to_reserves(state.price).unwrap());
+        assert_eq!(state.fee_split, None);
+
+        // 做多开仓借出SOL, 价格上涨 / A long borrows SOL and moves the price up
+        let new_price = state.price * 2;
+        let update = CurveUpdate {
+            price: Some(new_price),
+            borrow_sol_delta: -900_000_000,
+            ..Default::default()
+        };
+        assert!(storage.apply_update("MintA", 12, &update).unwrap());
+
+        let state = storage.get_curve("MintA").unwrap().unwrap();
+        assert_eq!(state.price, new_price);
+        assert_eq!(state.borrow_sol_reserve, INITIAL_BORROW_SOL_RESERVE - 900_000_000);
+        assert_eq!((state.lp_sol_reserve, state.lp_token_reserve), CurveAMM::price_to_reserves(new_price).unwrap());
+        assert_eq!(state.last_slot, 12);
+
+        // 未知mint不写入 / Unknown mints are not written
+        assert!(!storage.apply_update("MintB", 12, &update).unwrap());
+    }
+
+    #[test]
+    fn test_snapshot_skips_included_events() {
+        let storage = memory_storage();
+        let mint = Pubkey::new_unique();
+
+        let mut data = vec![0u8; ACCOUNT_DISCRIMINATOR_LEN];
+        data.extend_from_slice(&1_073_000_000_000_000u64.to_le_bytes()); // lp_token_reserve
+        data.extend_from_slice(&30_000_000_000u64.to_le_bytes()); // lp_sol_reserve
+        data.extend_from_slice(&CurveAMM::get_initial_price().unwrap().to_le_bytes()); // price
+        data.extend_from_slice(&INITIAL_BORROW_TOKEN_RESERVE.to_le_bytes());
+        data.extend_from_slice(&INITIAL_BORROW_SOL_RESERVE.to_le_bytes());
+        data.extend_from_slice(&1000u16.to_le_bytes()); // swap_fee
+        data.extend_from_slice(&1200u16.to_le_bytes()); // borrow_fee
+        data.push(0); // fee_discount_flag
+        data.extend_from_slice(&[0u8; 64]); // base_fee_recipient, fee_recipient
+        data.push(80); // fee_split
+        data.extend_from_slice(&86_400u32.to_le_bytes()); // borrow_duration
+        data.extend_from_slice(mint.as_ref());
+        data.extend_from_slice(&[0u8; 65]); // up_orderbook, down_orderbook, bump
+
+        let mint = mint.to_string();
+        assert!(CurveState::from_account_data("OtherMint", "Curve", &data, 100).is_err());
+        let state = CurveState::from_account_data(&mint, "Curve", &data, 100).unwrap();
+        assert_eq!(state.fee_split, Some(80));
+        assert_eq!(state.borrow_duration, Some(86_400));
+        storage.save_curve(&state).unwrap();
+
+        let update = CurveUpdate {
+            borrow_token_delta: -5_000_000,
+            ..Default::default()
+        };
+        assert!(!storage.apply_update(&mint, 100, &update).unwrap());
+        assert!(storage.apply_update(&mint, 101, &update).unwrap());
+        assert_eq!(
+            storage.get_curve(&mint).unwrap().unwrap().borrow_token_reserve,
+            INITIAL_BORROW_TOKEN_RESERVE - 5_000_000
+        );
+    }
+}
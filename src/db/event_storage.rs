@@ -3,15 +3,22 @@ use anyhow::Result;
 use rocksdb::{WriteBatch, IteratorMode, Direction, DB};
 use serde::{Serialize, Deserialize};
 use utoipa::ToSchema;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use tracing::info;
 
+use crate::db::locks::ShardedLocks;
+use crate::db::undo::{RevertOutcome, UndoJournal};
 use crate::solana::events::PinpetEvent;
-use crate::router::db::PaginatedEvents;
+use crate::router::db::{PaginatedEvents, SlotRangeEvents, UserActivity, UserEventRow};
+use crate::util::PageInfo;
+
+/// 等待终结的缓冲事件键前缀 / Key prefix of buffered events awaiting finality
+const FINALITY_PENDING_PREFIX: &str = "finality_pending:";
 
 /// 事件引用结构 - 用于索引 / Event reference structure - for indexing
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 struct EventRef {
     slot: u64,
     mint: String,
@@ -21,23 +28,330 @@ struct EventRef {
 }
 
 /// 签名引用结构 - 用于签名映射 / Signature reference structure - for signature mapping
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 struct SignatureRef {
     slot: u64,
     mint: String,
     event_type: String,
     idx: u32,
+    /// idx 是否取自交易内事件序号; 旧版按类型计数 (同类型第 n 个事件为 n), 反序列化为 false
+    /// Whether idx is the in-transaction event ordinal; legacy refs counted per type (the nth event of a type got n)
+    /// and deserialize as false
+    #[serde(default)]
+    by_ordinal: bool,
+}
+
+/// 交易索引标记, 与事件在同一批次写入 / Transaction indexed marker, written in the same batch as the events
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SignatureStatus {
+    /// 交易所在 slot / Slot of the transaction
+    pub slot: u64,
+    /// 首次索引时间(毫秒), 早于该标记写入的交易为空 / First indexed time (ms), None for transactions stored before the marker existed
+    pub processed_at: Option<i64>,
+}
+
+/// 事件处理游标键 / Event processing cursor key
+const EVENT_CURSOR_KEY: &str = "event_cursor";
+
+/// 事件处理游标: 已存储事件中的最高 slot 及其交易签名 / Event processing cursor: highest stored slot and its transaction signature
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, ToSchema)]
+#[schema(title = "EventCursor", description = "事件处理游标 / Event processing cursor")]
+pub struct EventCursor {
+    /// 最高已处理 slot / Highest processed slot
+    #[schema(example = 123456789)]
+    pub last_processed_slot: u64,
+    /// 该 slot 中最后写入的交易签名 / Signature of the last transaction stored at that slot
+    pub last_processed_signature: String,
+    /// 更新时间(毫秒) / Updated at (ms)
+    pub updated_at: i64,
+}
+
+/// 全局最近成交摘要 / Global recent trade summary
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(title = "EventSummary", description = "成交事件摘要 / Trade event summary")]
+pub struct EventSummary {
+    /// 交易签名 / Transaction signature
+    pub signature: String,
+    /// 区块高度 / Slot
+    #[schema(example = 123456789)]
+    pub slot: u64,
+    /// 代币 mint 地址 / Token mint address
+    pub mint: String,
+    /// 用户地址 / User address
+    pub user: String,
+    /// 事件类型 / Event type
+    #[schema(example = "BuySell")]
+    pub event_type: String,
+    /// 方向: buy/sell/long/short/close_long/close_short / Direction
+    #[schema(example = "buy")]
+    pub direction: String,
+    /// 最新价格 (u128 字符串) / Latest price (u128 as string)
+    #[schema(example = "79228162514264337593543950336")]
+    pub price: String,
+    /// SOL 数量 / SOL amount
+    pub sol_amount: u64,
+    /// Token 数量 / Token amount
+    pub token_amount: u64,
+    /// 事件时间戳(毫秒) / Event timestamp (ms)
+    pub timestamp: i64,
+}
+
+/// 代币24小时行情摘要 / Token 24h market summary
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(title = "TokenSummary24h", description = "代币24小时行情摘要 / Token 24h market summary")]
+pub struct TokenSummary24h {
+    /// 代币 mint 地址 / Token mint address
+    pub mint: String,
+    /// 最新价格 (u128 字符串, 无事件时为空) / Last price (u128 as string, null without events)
+    pub last_price: Option<String>,
+    /// 24小时开盘价 / 24h open price
+    pub open_24h: Option<String>,
+    /// 24小时最高价 / 24h high price
+    pub high_24h: Option<String>,
+    /// 24小时最低价 / 24h low price
+    pub low_24h: Option<String>,
+    /// 24小时成交额 (lamports) / 24h volume (lamports)
+    pub volume_24h_sol: u64,
+    /// 24小时成交笔数 / 24h trade count
+    pub trade_count_24h: u64,
+    /// 24小时涨跌幅 (%), 无成交时为0 / 24h change (%), 0 when there were no trades
+    pub change_percent_24h: f64,
+    /// 24小时内是否有成交 / Whether there were trades in the last 24h
+    pub has_trades_24h: bool,
+    /// 统计窗口起始时间(毫秒) / Window start (ms)
+    pub window_start: i64,
+}
+
+/// 全局最近成交最大返回条数 / Max number of global recent trades returned
+pub const MAX_RECENT_TRADES: usize = 200;
+
+/// 大额成交查询最多扫描的全局索引条数, 阈值过高时返回不足 limit 条
+/// Max global index entries a large trade query scans; with a very high threshold fewer than limit trades come back
+pub const MAX_WHALE_SCAN: usize = 20_000;
+
+/// 订单被清算的记录 (由 liquidate_indices 派生) / Liquidated order record (derived from liquidate_indices)
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(title = "LiquidationRecord", description = "订单清算记录 / Order liquidation record")]
+pub struct LiquidationRecord {
+    /// 代币 mint 地址 / Token mint address
+    pub mint: String,
+    /// 被清算订单的方向: up/dn / Direction of the liquidated order
+    #[schema(example = "dn")]
+    pub direction: String,
+    /// 被清算的订单 ID / Liquidated order ID
+    pub order_id: u64,
+    /// 订单所有者 / Order owner
+    pub user: String,
+    /// 清算时订单在订单簿中的索引 / Order index in the book at liquidation
+    pub order_index: u16,
+    /// 触发清算的事件类型 / Event type that triggered the liquidation
+    #[schema(example = "BuySell")]
+    pub trigger_event_type: String,
+    /// 触发交易签名 / Triggering transaction signature
+    pub signature: String,
+    /// 区块高度 / Slot
+    pub slot: u64,
+    /// 清算时间戳(毫秒) / Liquidation timestamp (ms)
+    pub timestamp: i64,
+    /// 触发价格 (u128 字符串) / Trigger price (u128 as string)
+    #[schema(example = "79228162514264337593543950336")]
+    pub price: String,
+    /// 清算时的保证金 / Margin at liquidation
+    pub margin_sol_amount: u64,
+    /// 清算时的持仓量 / Position size at liquidation
+    pub position_asset_amount: u64,
+}
+
+/// 清算记录最大返回条数 / Max number of liquidation records returned
+pub const MAX_LIQUIDATIONS: usize = 200;
+
+/// 订单结清 (主动全平或被清算) 时的已实现盈亏 / Realized PnL of an order when it is settled (fully closed or liquidated)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PnlSettlement {
+    /// 订单所有者 / Order owner
+    pub user: String,
+    /// 代币 mint 地址 / Token mint address
+    pub mint: String,
+    /// 订单 ID / Order ID
+    pub order_id: u64,
+    /// 订单累计取回的 SOL: 部分平仓已实现的 realized_sol_amount 加最终平仓的 user_close_profit, 被清算时无最终取回
+    /// SOL the order paid out in total: realized_sol_amount from partial closes plus the final close's user_close_profit,
+    /// a liquidation pays nothing at the end
+    pub payout: u64,
+    /// 开仓保证金 (margin_init_sol_amount) / Opening margin (margin_init_sol_amount)
+    pub margin: u64,
+    /// 是否被清算 / Whether the order was liquidated
+    pub liquidated: bool,
+    /// 结清所在 slot / Slot of the settlement
+    pub slot: u64,
+}
+
+impl PnlSettlement {
+    /// 已实现盈亏 = 取回 - 保证金 (lamports) / Realized PnL = payout - margin (lamports)
+    pub fn pnl(&self) -> i64 {
+        let pnl = i128::from(self.payout) - i128::from(self.margin);
+        pnl.clamp(i128::from(i64::MIN), i128::from(i64::MAX)) as i64
+    }
+}
+
+/// 用户已实现盈亏汇总 / User realized PnL summary
+///
+/// 订单在结清时计入一次: 主动全平, 或被其他交易清算 (取回为0); 部分平仓的取回在订单最终结清时一并计入
+/// An order counts once, when it is settled: fully closed by its owner, or liquidated by another trade (payout 0);
+/// partial close payouts are counted together when the order is finally settled
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, ToSchema)]
+#[schema(title = "PnlSummary", description = "用户已实现盈亏 / User realized PnL")]
+pub struct PnlSummary {
+    /// 用户地址 / User address
+    pub user: String,
+    /// mint 过滤, 为空表示全部代币 / Mint filter, null means all tokens
+    pub mint: Option<String>,
+    /// 已实现盈亏 (lamports, 可能为负) / Realized PnL (lamports, may be negative)
+    #[schema(example = -25000000)]
+    pub realized_pnl: i64,
+    /// 累计取回的 SOL (lamports) / Total SOL paid out (lamports)
+    pub total_payout: u64,
+    /// 累计开仓保证金 (lamports) / Total opening margin (lamports)
+    pub total_margin: u64,
+    /// 已结清的订单数 / Settled orders
+    pub settled_orders: u64,
+    /// 盈利订单数 (盈亏 > 0) / Winning orders (PnL > 0)
+    pub wins: u64,
+    /// 亏损订单数 (盈亏 < 0) / Losing orders (PnL < 0)
+    pub losses: u64,
+    /// 其中被清算的订单数 / Of which liquidated
+    pub liquidations: u64,
+    /// 最后结清所在 slot / Slot of the last settlement
+    pub last_slot: u64,
+}
+
+impl PnlSummary {
+    fn add(&mut self, settlement: &PnlSettlement) {
+        let pnl = settlement.pnl();
+        self.realized_pnl = self.realized_pnl.saturating_add(pnl);
+        self.total_payout = self.total_payout.saturating_add(settlement.payout);
+        self.total_margin = self.total_margin.saturating_add(settlement.margin);
+        self.settled_orders += 1;
+        if pnl > 0 {
+            self.wins += 1;
+        } else if pnl < 0 {
+            self.losses += 1;
+        }
+        if settlement.liquidated {
+            self.liquidations += 1;
+        }
+        self.last_slot = self.last_slot.max(settlement.slot);
+    }
 }
 
+/// 用户跨代币索引回填完成标记 / Marker set once the user cross-mint index backfill has run
+const USER_GLOBAL_INDEX_BACKFILL_MARKER: &str = "event_migration:user_global_index";
+
+/// 用户跨代币活动每页最大条数 / Max page size of the user cross-mint activity
+pub const MAX_USER_ACTIVITY_PAGE_SIZE: u32 = 100;
+
 /// 事件存储服务 / Event storage service
 pub struct EventStorage {
+    /// 仍直接使用 RocksDB, 迁移到 KvStore 的缺口见 db/kv.rs / Still on RocksDB directly, see db/kv.rs for what blocks KvStore
     db: Arc<DB>,
+    /// 撤销日志, 开启时事件写入可按 slot 回滚 / Undo journal; when set, event writes can be rolled back per slot
+    journal: Option<Arc<UndoJournal>>,
+    /// 用户分片锁, 串行化 user_pnl: 的读-改-写, 与同库其他 EventStorage 实例共享
+    /// User shard locks serializing read-modify-writes of user_pnl:, shared with the other EventStorage instances of the DB
+    user_locks: Arc<ShardedLocks>,
+    /// 缓冲事件键序号, 以启动时间为起点保证跨重启递增 / Buffered event key sequence, seeded from the start time so it grows across restarts
+    pending_seq: AtomicU64,
 }
 
 impl EventStorage {
     /// 创建新的事件存储服务 / Create new event storage service
-    pub fn new(db: Arc<DB>) -> Result<Self> {
-        Ok(Self { db })
+    pub fn new(db: Arc<DB>, user_locks: Arc<ShardedLocks>) -> Result<Self> {
+        let pending_seq = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or_default();
+        Ok(Self {
+            db,
+            journal: None,
+            user_locks,
+            pending_seq: AtomicU64::new(pending_seq),
+        })
+    }
+
+    /// 写入事件和清算记录时记录撤销日志 / Record the undo journal when writing events and liquidation records
+    pub fn with_undo_journal(mut self) -> Self {
+        self.journal = Some(Arc::new(UndoJournal::new(self.db.clone())));
+        self
+    }
+
+    /// 撤销某个 slot 写入的事件、索引和清算记录; 未开启撤销日志时什么也不做
+    /// Undo the events, indexes and liquidation records written for a slot; does nothing without an undo journal
+    ///
+    /// 之后的 slot 又改过的键 (例如累计的 user_pnl) 保持不变, 作为冲突返回
+    /// Keys changed again by a later slot (the running user_pnl, say) are left alone and returned as conflicts
+    pub fn revert_slot(&self, slot: u64) -> Result<RevertOutcome> {
+        match &self.journal {
+            Some(journal) => journal.revert_slot_grouped(slot, |_| None),
+            None => Ok(RevertOutcome::default()),
+        }
+    }
+
+    /// 仍有撤销记录的 slot, 重启后据此恢复待终结集合
+    /// Slots that still have undo records, used to restore the unsettled set after a restart
+    pub fn journaled_slots(&self) -> Result<BTreeSet<u64>> {
+        match &self.journal {
+            Some(journal) => journal.journaled_slots(),
+            None => Ok(BTreeSet::new()),
+        }
+    }
+
+    /// 持久化一个等待终结的缓冲事件, 重启后由终结性闸门重新载入
+    /// Persist a buffered event awaiting finality, so the finality gate reloads it after a restart
+    pub fn put_pending_event(&self, event: &PinpetEvent) -> Result<()> {
+        let seq = self.pending_seq.fetch_add(1, Ordering::Relaxed);
+        let key = format!("{}{:010}:{:020}", FINALITY_PENDING_PREFIX, event.slot(), seq);
+        self.db.put(key.as_bytes(), serde_json::to_vec(event)?)?;
+        Ok(())
+    }
+
+    /// 全部持久化的缓冲事件, 按 slot 和到达顺序排列 / Every persisted buffered event, ordered by slot and arrival
+    pub fn pending_events(&self) -> Result<Vec<PinpetEvent>> {
+        let mut events = Vec::new();
+        let iter = self.db.iterator(IteratorMode::From(FINALITY_PENDING_PREFIX.as_bytes(), Direction::Forward));
+        for item in iter {
+            let (key, value) = item?;
+            if !key.starts_with(FINALITY_PENDING_PREFIX.as_bytes()) {
+                break;
+            }
+            events.push(serde_json::from_slice(&value)?);
+        }
+        Ok(events)
+    }
+
+    /// 删除 [first, last] 内已结算的缓冲事件 / Delete the settled buffered events in [first, last]
+    pub fn remove_pending_events(&self, first: u64, last: u64) -> Result<usize> {
+        let start = format!("{}{:010}:", FINALITY_PENDING_PREFIX, first);
+        let end = format!("{}{:010};", FINALITY_PENDING_PREFIX, last);
+        let mut batch = WriteBatch::default();
+        let mut removed = 0;
+        for item in self.db.iterator(IteratorMode::From(start.as_bytes(), Direction::Forward)) {
+            let (key, _) = item?;
+            if key.as_ref() >= end.as_bytes() {
+                break;
+            }
+            batch.delete(&key);
+            removed += 1;
+        }
+        self.db.write(batch)?;
+        Ok(removed)
+    }
+
+    /// slot 已终结, 丢弃它的撤销记录 / The slot is finalized, drop its undo records
+    pub fn commit_slot(&self, slot: u64) -> Result<usize> {
+        match &self.journal {
+            Some(journal) => journal.commit_slot(slot),
+            None => Ok(0),
+        }
     }
 
     /// 生成8位短签名 / Generate 8-character short signature
@@ -54,6 +368,8 @@ impl EventStorage {
             PinpetEvent::FullClose(_) => "fc",
             PinpetEvent::PartialClose(_) => "pc",
             PinpetEvent::MilestoneDiscount(_) => "md",
+            PinpetEvent::AddMargin(_) => "am",
+            PinpetEvent::ExtendOrder(_) => "eo",
         }
     }
 
@@ -82,22 +398,249 @@ impl EventStorage {
             PinpetEvent::MilestoneDiscount(e) => {
                 (e.mint_account.clone(), e.slot, e.signature.clone(), Some(e.payer.clone()))
             },
+            PinpetEvent::AddMargin(e) => {
+                // 只有订单所有者可以追加保证金 / Only the order owner can add margin
+                (e.mint_account.clone(), e.slot, e.signature.clone(), Some(e.payer.clone()))
+            },
+            PinpetEvent::ExtendOrder(e) => {
+                // 只有订单所有者可以延期 / Only the order owner can extend the order
+                (e.mint_account.clone(), e.slot, e.signature.clone(), Some(e.payer.clone()))
+            },
+        }
+    }
+
+    /// 事件时间戳(毫秒) / Event timestamp (ms)
+    fn event_timestamp(event: &PinpetEvent) -> i64 {
+        match event {
+            PinpetEvent::TokenCreated(e) => e.timestamp.timestamp_millis(),
+            PinpetEvent::BuySell(e) => e.timestamp.timestamp_millis(),
+            PinpetEvent::LongShort(e) => e.timestamp.timestamp_millis(),
+            PinpetEvent::FullClose(e) => e.timestamp.timestamp_millis(),
+            PinpetEvent::PartialClose(e) => e.timestamp.timestamp_millis(),
+            PinpetEvent::MilestoneDiscount(e) => e.timestamp.timestamp_millis(),
+            PinpetEvent::AddMargin(e) => e.timestamp.timestamp_millis(),
+            PinpetEvent::ExtendOrder(e) => e.timestamp.timestamp_millis(),
+        }
+    }
+
+    /// 生成成交事件摘要, 非成交事件返回 None / Build trade summary, None for non-trade events
+    fn build_summary(event: &PinpetEvent) -> Option<EventSummary> {
+        let trade = event.trade()?;
+        Some(EventSummary {
+            signature: trade.signature.to_string(),
+            slot: trade.slot,
+            mint: trade.mint.to_string(),
+            user: trade.user.to_string(),
+            event_type: event.type_name().to_string(),
+            direction: trade.side.to_string(),
+            price: trade.price.to_string(),
+            sol_amount: trade.sol_amount,
+            token_amount: trade.token_amount,
+            timestamp: trade.timestamp.timestamp_millis(),
+        })
+    }
+
+    /// 全局倒序索引键, 时间戳取反使正向迭代即为最新优先
+    /// Global reverse-chronological index key, inverted timestamp makes forward iteration newest-first
+    fn global_index_key(summary: &EventSummary, event_type: &str, idx: u32) -> String {
+        let inverted = u64::MAX - summary.timestamp.max(0) as u64;
+        format!("event_global_index:{:020}:{}:{}:{:03}",
+                inverted, summary.signature, event_type, idx)
+    }
+
+    /// 提取订单事件的 (方向, order_id), 非订单事件返回 None
+    /// Extract (direction, order_id) of order events, None for other events
+    ///
+    /// order_id 按订单簿方向分别计数, 因此索引需要带上方向
+    /// order_id is counted per book direction, so the index must include the direction
+    fn order_ref(event: &PinpetEvent) -> Option<(&'static str, u64)> {
+        match event {
+            PinpetEvent::LongShort(e) => {
+                Some((if e.order_type == 1 { "dn" } else { "up" }, e.order_id))
+            },
+            PinpetEvent::FullClose(e) => {
+                Some((if e.is_close_long { "dn" } else { "up" }, e.order_id))
+            },
+            PinpetEvent::PartialClose(e) => {
+                Some((if e.is_close_long { "dn" } else { "up" }, e.order_id))
+            },
+            PinpetEvent::AddMargin(e) => {
+                Some((if e.order_type == 1 { "dn" } else { "up" }, e.order_id))
+            },
+            PinpetEvent::ExtendOrder(e) => {
+                Some((if e.order_type == 1 { "dn" } else { "up" }, e.order_id))
+            },
+            _ => None,
+        }
+    }
+
+    /// 用户跨代币索引键, 值为 EventRef, 倒序时间戳使正向遍历为最新优先
+    /// User cross-mint index key, the value is an EventRef, inverted timestamp makes forward iteration newest-first
+    ///
+    /// user_global_index:{user}:{inverted_ts:020}:{signature}:{type}:{idx3}
+    ///
+    /// 成本: 每个带用户的事件多一个键 (键约 150 字节 + 值约 100 字节), 与 idx_user 同量级;
+    /// 换来的是翻页无需收集并排序该用户的全部索引键
+    /// Cost: one extra key per user-attributed event (~150 byte key + ~100 byte value), on par with idx_user;
+    /// in exchange paging no longer collects and sorts every index key of the user
+    fn user_global_index_key(user: &str, timestamp: i64, signature: &str, event_type: &str, idx: u32) -> String {
+        let inverted = u64::MAX - timestamp.max(0) as u64;
+        format!("user_global_index:{}:{:020}:{}:{}:{:03}",
+                user, inverted, signature, event_type, idx)
+    }
+
+    /// 清算记录键, 倒序时间戳使正向遍历为最新优先
+    /// Liquidation record key, inverted timestamp makes forward iteration newest-first
+    fn liquidation_key(record: &LiquidationRecord) -> String {
+        let inverted = u64::MAX - record.timestamp.max(0) as u64;
+        format!("liquidation:{}:{:020}:{}:{}:{:010}",
+                record.mint, inverted, record.signature, record.direction, record.order_id)
+    }
+
+    /// 存储清算记录 / Store liquidation records
+    pub fn store_liquidations(&self, records: &[LiquidationRecord]) -> Result<()> {
+        if records.is_empty() {
+            return Ok(());
         }
+
+        let mut batch = WriteBatch::default();
+        for record in records {
+            batch.put(Self::liquidation_key(record).as_bytes(), serde_json::to_vec(record)?);
+        }
+        if let Some(journal) = &self.journal {
+            let slot = records.iter().map(|r| r.slot).max().unwrap_or_default();
+            journal.record(&mut batch, slot)?;
+        }
+        self.db.write(batch)?;
+
+        info!("💀 存储 {} 条清算记录 / Stored {} liquidation records", records.len(), records.len());
+        Ok(())
+    }
+
+    /// 用户盈亏汇总键, mint 为空时为跨代币合计 / User PnL summary key, the cross-token total when mint is None
+    ///
+    /// user_pnl:{user} / user_pnl:{user}:{mint}
+    fn user_pnl_key(user: &str, mint: Option<&str>) -> String {
+        match mint {
+            Some(mint) => format!("user_pnl:{}:{}", user, mint),
+            None => format!("user_pnl:{}", user),
+        }
+    }
+
+    /// 把结清订单的盈亏累加到用户合计和按 mint 的汇总, 加入事件的写入批次; 调用方须持有这些用户的锁
+    /// Stage settled orders' PnL onto the user total and per-mint summaries in the events' write batch;
+    /// the caller must hold the users' locks
+    fn stage_pnl_settlements(&self, batch: &mut WriteBatch, settlements: &[PnlSettlement]) -> Result<()> {
+        // 同一批次可能多次更新同一个键, 先在内存中累加 / One batch may update a key several times, accumulate in memory first
+        let mut summaries: HashMap<String, PnlSummary> = HashMap::new();
+        for settlement in settlements {
+            for mint in [None, Some(settlement.mint.as_str())] {
+                let key = Self::user_pnl_key(&settlement.user, mint);
+                let summary = match summaries.entry(key) {
+                    std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+                    std::collections::hash_map::Entry::Vacant(entry) => {
+                        let stored = match self.db.get(entry.key().as_bytes())? {
+                            Some(data) => serde_json::from_slice(&data)?,
+                            None => PnlSummary {
+                                user: settlement.user.clone(),
+                                mint: mint.map(str::to_string),
+                                ..Default::default()
+                            },
+                        };
+                        entry.insert(stored)
+                    }
+                };
+                summary.add(settlement);
+            }
+        }
+
+        for (key, summary) in &summaries {
+            batch.put(key.as_bytes(), serde_json::to_vec(summary)?);
+        }
+        Ok(())
+    }
+
+    /// 查询用户已实现盈亏, 可按 mint 过滤; 没有结清订单时返回全零汇总
+    /// Query a user's realized PnL, optionally for one mint; returns an all-zero summary without settled orders
+    pub fn user_realized_pnl(&self, user: &str, mint: Option<&str>) -> Result<PnlSummary> {
+        match self.db.get(Self::user_pnl_key(user, mint).as_bytes())? {
+            Some(data) => Ok(serde_json::from_slice(&data)?),
+            None => Ok(PnlSummary {
+                user: user.to_string(),
+                mint: mint.map(str::to_string),
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// 按 mint 查询清算记录 (最新优先) / Query liquidation records of a mint (newest first)
+    pub fn query_liquidations(&self, mint: &str, limit: usize) -> Result<Vec<LiquidationRecord>> {
+        let limit = limit.clamp(1, MAX_LIQUIDATIONS);
+        let prefix = format!("liquidation:{}:", mint);
+        let mut records = Vec::with_capacity(limit);
+
+        let iter = self.db.iterator(IteratorMode::From(
+            prefix.as_bytes(),
+            Direction::Forward
+        ));
+
+        for item in iter {
+            let (key, value) = item?;
+            if !key.starts_with(prefix.as_bytes()) {
+                break;
+            }
+
+            if let Ok(record) = serde_json::from_slice::<LiquidationRecord>(&value) {
+                records.push(record);
+                if records.len() >= limit {
+                    break;
+                }
+            }
+        }
+
+        Ok(records)
     }
 
     /// 存储多个事件（同一签名）/ Store multiple events (same signature)
     pub async fn store_events(&self, signature: &str, events: Vec<PinpetEvent>) -> Result<()> {
+        self.store_events_with_settlements(signature, events, &[]).await
+    }
+
+    /// 存储事件, 并在同一批次中累加这些事件结清订单的用户盈亏
+    /// Store events and, in the same batch, add the PnL of the orders they settled to the users' summaries
+    ///
+    /// 持有结清用户的锁后再按 (签名, 事件序号) 检查一次: 事件已存储 (重放、并发的重处理) 时只覆盖事件, 不再计入盈亏,
+    /// 因此盈亏与去重读取的事件记录同成同败, 每个事件只计入一次
+    /// With the settled users' locks held the events are checked again on (signature, event ordinal): when already
+    /// stored (a replay, a concurrent reprocess) only the events are rewritten and the PnL is not added again, so the
+    /// PnL commits or fails together with the event records the dedup reads and every event counts once
+    pub async fn store_events_with_settlements(
+        &self,
+        signature: &str,
+        events: Vec<PinpetEvent>,
+        settlements: &[PnlSettlement],
+    ) -> Result<()> {
         if events.is_empty() {
             return Ok(());
         }
 
+        let _user_guards = self.user_locks.lock_all(settlements.iter().map(|s| s.user.as_str()));
+        let mut settle = !settlements.is_empty();
+        for event in &events {
+            if !settle {
+                break;
+            }
+            settle = !self.contains_event(event)?;
+        }
+
         let sig8 = Self::get_sig8(signature);
         let mut batch = WriteBatch::default();
         let mut sig_refs = Vec::new();
         let mut slot_refs: HashMap<u64, Vec<EventRef>> = HashMap::new();
 
-        // 按事件类型分组计数 / Group count by event type
-        let mut type_counters: HashMap<String, u32> = HashMap::new();
+        // 本批次已用的 (类型, 序号), 未带事件序号的批量调用按类型依次顺延
+        // (type, idx) pairs used in this batch; batch callers without event ordinals get consecutive indices per type
+        let mut used_idx: HashSet<(String, u32)> = HashSet::new();
 
         let events_len = events.len();  // 保存长度以供后面使用 / Save length for later use
 
@@ -105,11 +648,13 @@ impl EventStorage {
             let event_type = Self::get_event_type_code(&event).to_string();
             let (mint, slot, _, user) = Self::extract_event_info(&event);
 
-            // 获取或递增索引 / Get or increment index
-            let idx = type_counters
-                .entry(event_type.clone())
-                .and_modify(|e| *e += 1)
-                .or_insert(1);
+            // 键序号取交易内事件序号 + 1: 多指令交易的每个事件各占一个键, 重放同一事件写回同一个键
+            // The key index is the in-transaction event ordinal + 1: each event of a multi-instruction transaction
+            // gets its own key, and replaying an event writes back to the same key
+            let mut idx = Self::event_key_idx(&event);
+            while !used_idx.insert((event_type.clone(), idx)) {
+                idx += 1;
+            }
 
             let idx_str = format!("{:03}", idx);
             let slot_str = format!("{:010}", slot);
@@ -130,37 +675,114 @@ impl EventStorage {
                 let user_idx = format!("idx_user:{}:{}:{}:{}:{}:{}",
                                       user, slot_str, mint, sig8, event_type, idx_str);
                 batch.put(user_idx.as_bytes(), b"");
+
+                // 3b. 用户跨代币倒序索引 / User cross-mint reverse-chronological index
+                let global_user_idx = Self::user_global_index_key(
+                    &user, Self::event_timestamp(&event), signature, &event_type, idx);
+                let event_ref = EventRef {
+                    slot,
+                    mint: mint.clone(),
+                    sig8: sig8.clone(),
+                    event_type: event_type.clone(),
+                    idx,
+                };
+                batch.put(global_user_idx.as_bytes(), serde_json::to_vec(&event_ref)?);
+            }
+
+            // 4. 全局最近成交索引 (值为摘要, 无需回查) / Global recent trades index (value is the summary, no lookup needed)
+            if let Some(summary) = Self::build_summary(&event) {
+                let global_key = Self::global_index_key(&summary, &event_type, idx);
+                batch.put(global_key.as_bytes(), serde_json::to_vec(&summary)?);
+            }
+
+            // 4b. 订单索引 (开仓/部分平仓/全平仓) / Order index (open / partial close / full close)
+            if let Some((direction, order_id)) = Self::order_ref(&event) {
+                let order_idx = format!("idx_order:{}:{}:{:010}:{}:{}:{}:{}",
+                                       mint, direction, order_id, slot_str, sig8, event_type, idx_str);
+                batch.put(order_idx.as_bytes(), b"");
             }
 
-            // 4. 收集签名引用 / Collect signature references
+            // 5. 收集签名引用 / Collect signature references
             sig_refs.push(SignatureRef {
                 slot,
                 mint: mint.clone(),
                 event_type: event_type.clone(),
-                idx: *idx,
+                idx,
+                by_ordinal: true,
             });
 
-            // 5. 收集slot引用 / Collect slot references
+            // 6. 收集slot引用 / Collect slot references
             slot_refs.entry(slot).or_insert_with(Vec::new).push(EventRef {
                 slot,
                 mint: mint.clone(),
                 sig8: sig8.clone(),
                 event_type: event_type.clone(),
-                idx: *idx,
+                idx,
             });
         }
 
-        // 6. 存储签名映射 / Store signature mapping
+        // 7. 存储签名映射, 与已存储的引用合并 (同一交易的事件可能分多次写入)
+        // 7. Store signature mapping, merged with stored refs (events of one transaction may be written in several calls)
         let sig_map_key = format!("sig_map:{}", signature);
-        let sig_map_data = serde_json::to_vec(&sig_refs)?;
+        let mut merged_sig_refs = self.signature_refs(signature)?;
+        for sig_ref in &sig_refs {
+            if !merged_sig_refs.contains(sig_ref) {
+                merged_sig_refs.push(sig_ref.clone());
+            }
+        }
+        let sig_map_data = serde_json::to_vec(&merged_sig_refs)?;
         batch.put(sig_map_key.as_bytes(), &sig_map_data);
 
-        // 7. 更新slot批量索引 / Update slot batch index
+        // 7b. 交易索引标记, 保留首次索引时间 / Transaction indexed marker, keeping the first indexed time
+        let processed_key = Self::processed_sig_key(signature);
+        if self.db.get(processed_key.as_bytes())?.is_none() {
+            let status = SignatureStatus {
+                slot: sig_refs.iter().map(|r| r.slot).max().unwrap_or_default(),
+                processed_at: Some(chrono::Utc::now().timestamp_millis()),
+            };
+            batch.put(processed_key.as_bytes(), serde_json::to_vec(&status)?);
+        }
+
+        // 7c. 推进事件处理游标, 重处理写入的旧交易不会让它回退
+        // 7c. Advance the event processing cursor; reprocessed older transactions never move it back
+        let max_slot = sig_refs.iter().map(|r| r.slot).max().unwrap_or_default();
+        if self.event_cursor()?.map_or(true, |c| max_slot >= c.last_processed_slot) {
+            let cursor = EventCursor {
+                last_processed_slot: max_slot,
+                last_processed_signature: signature.to_string(),
+                updated_at: chrono::Utc::now().timestamp_millis(),
+            };
+            batch.put(EVENT_CURSOR_KEY.as_bytes(), serde_json::to_vec(&cursor)?);
+        }
+
+        // 8. 更新slot批量索引, 并写入 slot 范围索引 (值为该交易在该 slot 的事件引用)
+        // 8. Update slot batch index and write the slot range index (value is this transaction's refs in that slot)
         for (slot, refs) in slot_refs {
+            let range_key = Self::slot_index_key(slot, signature);
+            let mut range_refs: Vec<EventRef> = match self.db.get(range_key.as_bytes())? {
+                Some(data) => serde_json::from_slice(&data)?,
+                None => Vec::new(),
+            };
+            for event_ref in &refs {
+                if !range_refs.contains(event_ref) {
+                    range_refs.push(event_ref.clone());
+                }
+            }
+            batch.put(range_key.as_bytes(), serde_json::to_vec(&range_refs)?);
             self.update_slot_batch(&mut batch, slot, refs)?;
         }
 
-        // 8. 原子提交所有更改 / Atomically commit all changes
+        // 8b. 结清订单的用户盈亏 / User PnL of settled orders
+        if settle {
+            self.stage_pnl_settlements(&mut batch, settlements)?;
+        }
+
+        // 9. 撤销日志与数据一起原子提交 / The undo record commits atomically with the data
+        if let Some(journal) = &self.journal {
+            journal.record(&mut batch, max_slot)?;
+        }
+
+        // 10. 原子提交所有更改 / Atomically commit all changes
         self.db.write(batch)?;
 
         info!("成功存储 {} 个事件，签名: {} / Successfully stored {} events, signature: {}",
@@ -168,6 +790,56 @@ impl EventStorage {
         Ok(())
     }
 
+    /// 事件键序号: 交易内事件序号 + 1 / Event key index: in-transaction event ordinal + 1
+    fn event_key_idx(event: &PinpetEvent) -> u32 {
+        event.event_index() + 1
+    }
+
+    /// 读取交易的签名映射 / Read a transaction's signature mapping
+    fn signature_refs(&self, signature: &str) -> Result<Vec<SignatureRef>> {
+        match self.db.get(format!("sig_map:{}", signature).as_bytes())? {
+            Some(data) => Ok(serde_json::from_slice(&data)?),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// 交易中的该事件 (签名 + 事件序号) 是否已存储 / Whether this event of the transaction (signature + event ordinal) is stored
+    ///
+    /// 旧版引用按 (类型, 同类型内位置) 编号, 且旧版一次写入整笔交易的全部事件 (位置 1..=n 同时存在),
+    /// 因此存在同类型的旧版引用即说明该事件已存储
+    /// Legacy refs are numbered by (type, position within the type) and the legacy writer stored every event of a
+    /// transaction at once (positions 1..=n exist together), so any legacy ref of the same type means the event is stored
+    pub fn contains_event(&self, event: &PinpetEvent) -> Result<bool> {
+        let event_type = Self::get_event_type_code(event);
+        let idx = Self::event_key_idx(event);
+        Ok(self
+            .signature_refs(event.signature())?
+            .iter()
+            .any(|r| r.event_type == event_type && (!r.by_ordinal || r.idx == idx)))
+    }
+
+    /// slot 范围索引键 / Slot range index key: event_slot_index:{slot:010}:{signature}
+    fn slot_index_key(slot: u64, signature: &str) -> String {
+        format!("event_slot_index:{:010}:{}", slot, signature)
+    }
+
+    /// 已索引的最高 slot, 只做一次倒序定位 / Highest indexed slot, a single reverse seek
+    pub fn latest_indexed_slot(&self) -> Result<Option<u64>> {
+        const PREFIX: &str = "event_slot_index:";
+        // ';' 紧跟在 ':' 之后, 作为整个前缀的上界 / ';' sorts right after ':', bounding the whole prefix
+        let mut iter = self.db.iterator(IteratorMode::From(b"event_slot_index;", Direction::Reverse));
+
+        let Some(item) = iter.next() else {
+            return Ok(None);
+        };
+        let (key, _) = item?;
+        let key_str = String::from_utf8_lossy(&key);
+        Ok(key_str
+            .strip_prefix(PREFIX)
+            .and_then(|rest| rest.split(':').next())
+            .and_then(|slot| slot.parse().ok()))
+    }
+
     /// 更新slot批量索引 / Update slot batch index
     fn update_slot_batch(&self, batch: &mut WriteBatch, slot: u64, new_refs: Vec<EventRef>) -> Result<()> {
         let slot_key = format!("slot_batch:{:010}", slot);
@@ -179,8 +851,12 @@ impl EventStorage {
             Vec::new()
         };
 
-        // 合并新数据 / Merge new data
-        existing_refs.extend(new_refs);
+        // 合并新数据, 重处理时跳过已有引用 / Merge new data, skipping refs already present when reprocessing
+        for event_ref in new_refs {
+            if !existing_refs.contains(&event_ref) {
+                existing_refs.push(event_ref);
+            }
+        }
 
         // 写入更新后的数据 / Write updated data
         let updated_data = serde_json::to_vec(&existing_refs)?;
@@ -237,6 +913,331 @@ impl EventStorage {
         Ok(events)
     }
 
+    /// 按订单查询事件 (slot 升序) / Query events of an order (ascending by slot)
+    pub async fn query_by_order(&self, mint: &str, direction: &str, order_id: u64) -> Result<Vec<PinpetEvent>> {
+        let prefix = format!("idx_order:{}:{}:{:010}:", mint, direction, order_id);
+        let mut events = Vec::new();
+
+        let iter = self.db.iterator(IteratorMode::From(
+            prefix.as_bytes(),
+            Direction::Forward
+        ));
+
+        for item in iter {
+            let (key, _) = item?;
+            let key_str = String::from_utf8_lossy(&key);
+
+            if !key_str.starts_with(&prefix) {
+                break;
+            }
+
+            // idx_order:{mint}:{dir}:{order_id:010}:{slot:010}:{sig8}:{type}:{idx3}
+            let parts: Vec<&str> = key_str.split(':').collect();
+            if parts.len() >= 8 {
+                let slot = parts[4];
+                let sig8 = parts[5];
+                let event_type = parts[6];
+                let idx = parts[7];
+
+                let event_key = format!("event:{}:{}:{}:{}:{}",
+                                       slot, mint, sig8, event_type, idx);
+
+                if let Ok(Some(data)) = self.db.get(event_key.as_bytes()) {
+                    if let Ok(event) = serde_json::from_slice::<PinpetEvent>(&data) {
+                        events.push(event);
+                    }
+                }
+            }
+        }
+
+        Ok(events)
+    }
+
+    /// 查询跨代币的全局最近成交 (最新优先) / Query global recent trades across all tokens (newest first)
+    pub fn recent_trades(&self, limit: usize) -> Result<Vec<EventSummary>> {
+        let limit = limit.clamp(1, MAX_RECENT_TRADES);
+        let prefix = "event_global_index:";
+        let mut trades = Vec::with_capacity(limit);
+
+        let iter = self.db.iterator(IteratorMode::From(
+            prefix.as_bytes(),
+            Direction::Forward
+        ));
+
+        for item in iter {
+            let (key, value) = item?;
+            if !key.starts_with(prefix.as_bytes()) {
+                break;
+            }
+
+            if let Ok(summary) = serde_json::from_slice::<EventSummary>(&value) {
+                trades.push(summary);
+                if trades.len() >= limit {
+                    break;
+                }
+            }
+        }
+
+        Ok(trades)
+    }
+
+    /// 查询跨代币的最近大额成交 (最新优先), 只扫描最近 MAX_WHALE_SCAN 条全局成交
+    /// Query recent large trades across all tokens (newest first), scanning only the latest MAX_WHALE_SCAN global trades
+    ///
+    /// 阈值按单笔成交摘要的 sol_amount (lamports) 比较: 买卖为成交的 SOL, 开仓为保证金, 平仓为取回的 SOL
+    /// The threshold compares each trade summary's sol_amount (lamports): traded SOL for buy/sell,
+    /// margin for opens, SOL returned for closes
+    pub fn recent_large_trades(&self, min_sol: u64, limit: usize) -> Result<Vec<EventSummary>> {
+        let limit = limit.clamp(1, MAX_RECENT_TRADES);
+        let prefix = "event_global_index:";
+        let mut trades = Vec::with_capacity(limit);
+
+        let iter = self.db.iterator(IteratorMode::From(
+            prefix.as_bytes(),
+            Direction::Forward
+        ));
+
+        for item in iter.take(MAX_WHALE_SCAN) {
+            let (key, value) = item?;
+            if !key.starts_with(prefix.as_bytes()) {
+                break;
+            }
+
+            match serde_json::from_slice::<EventSummary>(&value) {
+                Ok(summary) if summary.sol_amount >= min_sol => {
+                    trades.push(summary);
+                    if trades.len() >= limit {
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(trades)
+    }
+
+    /// 提取事件的价格和时间 / Extract price and time from event
+    fn price_and_time(event: &PinpetEvent) -> Option<(u128, chrono::DateTime<chrono::Utc>)> {
+        match event {
+            PinpetEvent::TokenCreated(e) => Some((e.latest_price, e.timestamp)),
+            PinpetEvent::BuySell(e) => Some((e.latest_price, e.timestamp)),
+            PinpetEvent::LongShort(e) => Some((e.latest_price, e.timestamp)),
+            PinpetEvent::FullClose(e) => Some((e.latest_price, e.timestamp)),
+            PinpetEvent::PartialClose(e) => Some((e.latest_price, e.timestamp)),
+            PinpetEvent::MilestoneDiscount(_) | PinpetEvent::AddMargin(_) | PinpetEvent::ExtendOrder(_) => None,
+        }
+    }
+
+    /// 倒序读取 `since` 之后的成交 (价格, 时间, SOL成交量), 最新优先, 最多 `limit` 条, 用于K线快照
+    /// Read trades after `since` as (price, time, SOL volume), newest first, at most `limit`, for K-line snapshots
+    pub fn recent_prices(
+        &self,
+        mint: &str,
+        since: chrono::DateTime<chrono::Utc>,
+        limit: usize,
+    ) -> Result<Vec<(u128, chrono::DateTime<chrono::Utc>, u64)>> {
+        let prefix = format!("idx_mint:{}:", mint);
+        let upper = format!("idx_mint:{};", mint);
+        let mut prices = Vec::new();
+
+        let iter = self.db.iterator(IteratorMode::From(
+            upper.as_bytes(),
+            Direction::Reverse
+        ));
+
+        for item in iter {
+            let (key, _) = item?;
+            let key_str = String::from_utf8_lossy(&key);
+            if !key_str.starts_with(&prefix) {
+                if key_str.as_ref() < prefix.as_str() {
+                    break;
+                }
+                continue;
+            }
+
+            // idx_mint:{mint}:{slot:010}:{sig8}:{type}:{idx3}
+            let parts: Vec<&str> = key_str.split(':').collect();
+            if parts.len() < 6 {
+                continue;
+            }
+            let event_key = format!("event:{}:{}:{}:{}:{}",
+                                   parts[2], mint, parts[3], parts[4], parts[5]);
+            let Some(data) = self.db.get(event_key.as_bytes())? else {
+                continue;
+            };
+            let Ok(event) = serde_json::from_slice::<PinpetEvent>(&data) else {
+                continue;
+            };
+            let Some((price, timestamp)) = Self::price_and_time(&event) else {
+                continue;
+            };
+
+            if timestamp < since {
+                break;
+            }
+            let volume = Self::build_summary(&event).map_or(0, |summary| summary.sol_amount);
+            prices.push((price, timestamp, volume));
+            if prices.len() >= limit {
+                break;
+            }
+        }
+
+        Ok(prices)
+    }
+
+    /// 统计某 mint 的成交事件数 (bs / ls / fc / pc), 只扫描索引键不读取事件
+    /// Count a mint's trade events (bs / ls / fc / pc), scanning index keys only without reading the events
+    pub fn count_mint_trades(&self, mint: &str) -> Result<u64> {
+        let prefix = format!("idx_mint:{}:", mint);
+        let mut count = 0u64;
+
+        let iter = self.db.iterator(IteratorMode::From(prefix.as_bytes(), Direction::Forward));
+        for item in iter {
+            let (key, _) = item?;
+            let key_str = String::from_utf8_lossy(&key);
+            if !key_str.starts_with(&prefix) {
+                break;
+            }
+
+            // idx_mint:{mint}:{slot:010}:{sig8}:{type}:{idx3}
+            let parts: Vec<&str> = key_str.split(':').collect();
+            if parts.len() >= 6 && matches!(parts[4], "bs" | "ls" | "fc" | "pc") {
+                count += 1;
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// 通过倒序回放事件计算24小时行情 / Compute 24h summary by replaying events newest-first
+    pub fn token_summary_24h(&self, mint: &str, now: chrono::DateTime<chrono::Utc>) -> Result<TokenSummary24h> {
+        let window_start = now - chrono::Duration::hours(24);
+        let prefix = format!("idx_mint:{}:", mint);
+        // ';' 紧跟在 ':' 和数字之后, 从前缀末尾开始倒序迭代 / ';' sorts right after digits, so reverse iteration starts at the prefix end
+        let upper = format!("idx_mint:{};", mint);
+
+        let mut last_price: Option<u128> = None;
+        let mut high: Option<u128> = None;
+        let mut low: Option<u128> = None;
+        let mut earliest_in_window: Option<u128> = None;
+        let mut reference_price: Option<u128> = None;
+        let mut volume = 0u64;
+        let mut trade_count = 0u64;
+
+        let iter = self.db.iterator(IteratorMode::From(
+            upper.as_bytes(),
+            Direction::Reverse
+        ));
+
+        for item in iter {
+            let (key, _) = item?;
+            let key_str = String::from_utf8_lossy(&key);
+            if !key_str.starts_with(&prefix) {
+                if key_str.as_ref() < prefix.as_str() {
+                    break;
+                }
+                continue;
+            }
+
+            // idx_mint:{mint}:{slot:010}:{sig8}:{type}:{idx3}
+            let parts: Vec<&str> = key_str.split(':').collect();
+            if parts.len() < 6 {
+                continue;
+            }
+            let event_key = format!("event:{}:{}:{}:{}:{}",
+                                   parts[2], mint, parts[3], parts[4], parts[5]);
+            let Some(data) = self.db.get(event_key.as_bytes())? else {
+                continue;
+            };
+            let Ok(event) = serde_json::from_slice::<PinpetEvent>(&data) else {
+                continue;
+            };
+            let Some((price, timestamp)) = Self::price_and_time(&event) else {
+                continue;
+            };
+
+            last_price.get_or_insert(price);
+
+            if timestamp < window_start {
+                // 窗口前最后一笔价格作为开盘参考 / Last price before the window is the open reference
+                reference_price = Some(price);
+                break;
+            }
+
+            high = Some(high.map_or(price, |h| h.max(price)));
+            low = Some(low.map_or(price, |l| l.min(price)));
+            earliest_in_window = Some(price);
+            if let Some(summary) = Self::build_summary(&event) {
+                volume = volume.saturating_add(summary.sol_amount);
+                trade_count += 1;
+            }
+        }
+
+        let open = reference_price.or(earliest_in_window);
+        let has_trades = trade_count > 0;
+        let change_percent = match (has_trades, open, last_price) {
+            (true, Some(open), Some(last)) if open > 0 => {
+                (last as f64 - open as f64) / open as f64 * 100.0
+            }
+            _ => 0.0,
+        };
+
+        Ok(TokenSummary24h {
+            mint: mint.to_string(),
+            last_price: last_price.map(|p| p.to_string()),
+            open_24h: open.map(|p| p.to_string()),
+            high_24h: high.map(|p| p.to_string()),
+            low_24h: low.map(|p| p.to_string()),
+            volume_24h_sol: volume,
+            trade_count_24h: trade_count,
+            change_percent_24h: change_percent,
+            has_trades_24h: has_trades,
+            window_start: window_start.timestamp_millis(),
+        })
+    }
+
+    /// 读取事件处理游标, 尚未存储任何事件时为空 / Read the event processing cursor, None before any event is stored
+    pub fn event_cursor(&self) -> Result<Option<EventCursor>> {
+        match self.db.get(EVENT_CURSOR_KEY.as_bytes())? {
+            Some(data) => Ok(Some(serde_json::from_slice(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// 手动设置事件处理游标, 可以前移或回退 / Set the event processing cursor by hand, forwards or backwards
+    pub fn set_event_cursor(&self, slot: u64, signature: &str) -> Result<EventCursor> {
+        let cursor = EventCursor {
+            last_processed_slot: slot,
+            last_processed_signature: signature.to_string(),
+            updated_at: chrono::Utc::now().timestamp_millis(),
+        };
+        self.db.put(EVENT_CURSOR_KEY.as_bytes(), serde_json::to_vec(&cursor)?)?;
+        Ok(cursor)
+    }
+
+    /// 交易索引标记键 / Transaction indexed marker key: processed_sig:{signature}
+    fn processed_sig_key(signature: &str) -> String {
+        format!("processed_sig:{}", signature)
+    }
+
+    /// 查询交易是否已被索引, 只读标记不加载事件; 标记缺失时回退到签名映射
+    /// Whether a transaction has been indexed, reading the marker without loading events; falls back to the signature mapping when the marker is missing
+    pub fn signature_status(&self, signature: &str) -> Result<Option<SignatureStatus>> {
+        if let Some(data) = self.db.get(Self::processed_sig_key(signature).as_bytes())? {
+            return Ok(Some(serde_json::from_slice(&data)?));
+        }
+
+        let Some(data) = self.db.get(format!("sig_map:{}", signature).as_bytes())? else {
+            return Ok(None);
+        };
+        let sig_refs: Vec<SignatureRef> = serde_json::from_slice(&data)?;
+        Ok(sig_refs.iter().map(|r| r.slot).max().map(|slot| SignatureStatus {
+            slot,
+            processed_at: None,
+        }))
+    }
+
     /// 按signature查询所有相关事件 / Query all related events by signature
     pub async fn query_by_signature(&self, signature: &str) -> Result<Vec<PinpetEvent>> {
         let sig_map_key = format!("sig_map:{}", signature);
@@ -342,22 +1343,56 @@ impl EventStorage {
         }
     }
 
-    /// 按slot范围查询事件 / Query events by slot range
-    pub async fn query_by_slot_range(&self, start_slot: u64, end_slot: u64) -> Result<Vec<PinpetEvent>> {
-        let mut all_events = Vec::new();
+    /// 按slot范围查询事件, 基于 event_slot_index 索引, 按 slot 升序分页
+    /// Query events in a slot range through the event_slot_index index, paginated in ascending slot order
+    ///
+    /// total 为区间内的事件总数; 索引加入前存储的事件不在结果中
+    /// total is the number of events in the range; events stored before the index existed are not included
+    pub async fn query_by_slot_range(
+        &self,
+        from_slot: u64,
+        to_slot: u64,
+        limit: usize,
+        offset: usize,
+    ) -> Result<SlotRangeEvents> {
+        let start = Self::slot_index_key(from_slot, "");
+        // ';' 紧跟在 ':' 之后, 作为 to_slot 的上界 / ';' sorts right after ':', bounding to_slot
+        let end = format!("event_slot_index:{:010};", to_slot);
+
+        let mut total = 0usize;
+        let mut events = Vec::new();
+        let iter = self.db.iterator(IteratorMode::From(start.as_bytes(), Direction::Forward));
 
-        for slot in start_slot..=end_slot {
-            let events = self.query_by_slot(slot).await?;
-            all_events.extend(events);
-        }
+        for item in iter {
+            let (key, value) = item?;
+            if key.as_ref() >= end.as_bytes() {
+                break;
+            }
 
-        // 按slot排序 / Sort by slot
-        all_events.sort_by_key(|e| {
-            let (_, slot, _, _) = Self::extract_event_info(e);
-            slot
-        });
+            let refs: Vec<EventRef> = serde_json::from_slice(&value)?;
+            for event_ref in refs {
+                total += 1;
+                if total <= offset || events.len() >= limit {
+                    continue;
+                }
+
+                let event_key = format!("event:{:010}:{}:{}:{}:{:03}",
+                                       event_ref.slot, event_ref.mint, event_ref.sig8,
+                                       event_ref.event_type, event_ref.idx);
+                if let Some(data) = self.db.get(event_key.as_bytes())? {
+                    events.push(serde_json::from_slice::<PinpetEvent>(&data)?);
+                }
+            }
+        }
 
-        Ok(all_events)
+        Ok(SlotRangeEvents {
+            events,
+            total: total as u64,
+            from_slot,
+            to_slot,
+            limit,
+            offset,
+        })
     }
 
     /// 按mint_account查询事件（分页）/ Query events by mint_account (paginated)
@@ -402,7 +1437,6 @@ impl EventStorage {
         });
 
         let total = all_keys.len() as u64;
-        let total_pages = ((total as f64) / (page_size as f64)).ceil() as u32;
 
         // 计算分页偏移 / Calculate pagination offset
         let start = ((page - 1) * page_size) as usize;
@@ -431,10 +1465,7 @@ impl EventStorage {
 
         Ok(PaginatedEvents {
             events,
-            total,
-            page,
-            page_size,
-            total_pages,
+            pagination: PageInfo::numbered(page, page_size, total),
         })
     }
 
@@ -490,7 +1521,6 @@ impl EventStorage {
         });
 
         let total = all_keys.len() as u64;
-        let total_pages = ((total as f64) / (page_size as f64)).ceil() as u32;
 
         // 计算分页偏移 / Calculate pagination offset
         let start = ((page - 1) * page_size) as usize;
@@ -520,13 +1550,132 @@ impl EventStorage {
 
         Ok(PaginatedEvents {
             events,
-            total,
-            page,
-            page_size,
-            total_pages,
+            pagination: PageInfo::numbered(page, page_size, total),
+        })
+    }
+
+    /// 按用户查询跨所有代币的事件 (最新优先, 分页) / Query a user's events across every mint (newest first, paginated)
+    ///
+    /// 直接在 user_global_index 上跳过前面的页, 不统计总数, 用 has_more 表示是否还有下一页
+    /// Skips earlier pages directly on user_global_index without counting the total, has_more tells whether another page exists
+    pub fn query_user_activity(&self, user: &str, page: u32, page_size: u32) -> Result<UserActivity> {
+        let page = page.max(1);
+        let page_size = page_size.clamp(1, MAX_USER_ACTIVITY_PAGE_SIZE);
+        let prefix = format!("user_global_index:{}:", user);
+        let skip = (page as usize - 1) * page_size as usize;
+
+        let iter = self.db.iterator(IteratorMode::From(
+            prefix.as_bytes(),
+            Direction::Forward
+        ));
+
+        let mut rows = Vec::with_capacity(page_size as usize);
+        let mut has_more = false;
+        for (position, item) in iter.enumerate() {
+            let (key, value) = item?;
+            if !key.starts_with(prefix.as_bytes()) {
+                break;
+            }
+            if position < skip {
+                continue;
+            }
+            if rows.len() >= page_size as usize {
+                has_more = true;
+                break;
+            }
+
+            let Ok(event_ref) = serde_json::from_slice::<EventRef>(&value) else {
+                continue;
+            };
+            let event_key = format!("event:{:010}:{}:{}:{}:{:03}",
+                                   event_ref.slot, event_ref.mint, event_ref.sig8,
+                                   event_ref.event_type, event_ref.idx);
+            if let Some(data) = self.db.get(event_key.as_bytes())? {
+                if let Ok(event) = serde_json::from_slice::<PinpetEvent>(&data) {
+                    let (_, _, signature, _) = Self::extract_event_info(&event);
+                    rows.push(UserEventRow {
+                        mint: event_ref.mint,
+                        event_type: event.type_name().to_string(),
+                        signature,
+                        slot: event_ref.slot,
+                        timestamp: Self::event_timestamp(&event),
+                        event,
+                    });
+                }
+            }
+        }
+
+        Ok(UserActivity {
+            rows,
+            pagination: PageInfo::open_ended(page, page_size, has_more),
         })
     }
 
+    /// 从 idx_user 回填用户跨代币索引 (一次性迁移), 已执行过时返回 None
+    /// Backfill the user cross-mint index from idx_user (one-time migration), returns None if it already ran
+    ///
+    /// 索引上线前存储的事件不在 user_global_index 中; 回填后写入标记, 之后由 store_events 维护
+    /// Events stored before the index existed are missing from user_global_index; a marker is written afterwards and store_events keeps it up to date
+    pub fn backfill_user_global_index(&self) -> Result<Option<u64>> {
+        if self.db.get(USER_GLOBAL_INDEX_BACKFILL_MARKER.as_bytes())?.is_some() {
+            return Ok(None);
+        }
+
+        let prefix = "idx_user:";
+        let mut batch = WriteBatch::default();
+        let mut backfilled = 0u64;
+
+        let iter = self.db.iterator(IteratorMode::From(
+            prefix.as_bytes(),
+            Direction::Forward
+        ));
+
+        for item in iter {
+            let (key, _) = item?;
+            let key_str = String::from_utf8_lossy(&key).to_string();
+            if !key_str.starts_with(prefix) {
+                break;
+            }
+
+            // idx_user:{user}:{slot:010}:{mint}:{sig8}:{type}:{idx3}
+            let parts: Vec<&str> = key_str.split(':').collect();
+            if parts.len() < 7 {
+                continue;
+            }
+            let (Ok(slot), Ok(idx)) = (parts[2].parse::<u64>(), parts[6].parse::<u32>()) else {
+                continue;
+            };
+
+            let event_key = format!("event:{}:{}:{}:{}:{}",
+                                   parts[2], parts[3], parts[4], parts[5], parts[6]);
+            let Some(data) = self.db.get(event_key.as_bytes())? else {
+                continue;
+            };
+            let Ok(event) = serde_json::from_slice::<PinpetEvent>(&data) else {
+                continue;
+            };
+
+            let (_, _, signature, _) = Self::extract_event_info(&event);
+            let global_user_idx = Self::user_global_index_key(
+                parts[1], Self::event_timestamp(&event), &signature, parts[5], idx);
+            let event_ref = EventRef {
+                slot,
+                mint: parts[3].to_string(),
+                sig8: parts[4].to_string(),
+                event_type: parts[5].to_string(),
+                idx,
+            };
+            batch.put(global_user_idx.as_bytes(), serde_json::to_vec(&event_ref)?);
+            backfilled += 1;
+        }
+
+        batch.put(USER_GLOBAL_INDEX_BACKFILL_MARKER.as_bytes(), chrono::Utc::now().timestamp().to_string().as_bytes());
+        self.db.write(batch)?;
+
+        info!("✅ 用户跨代币索引回填完成 / User cross-mint index backfill complete: events={}", backfilled);
+        Ok(Some(backfilled))
+    }
+
     /// 获取数据库中的总键值对数量 / Get total key-value count in database
     pub fn get_total_key_count(&self) -> Result<u64> {
         let mut count = 0u64;
@@ -576,15 +1725,25 @@ impl EventStorage {
         let mut signature_count = 0;
         let mut slot_count = 0;
         let mut total_kv_size: u64 = 0;
+        let mut domains: HashMap<String, DomainStats> = DATA_DOMAINS
+            .iter()
+            .map(|(name, _)| (name.to_string(), DomainStats::default()))
+            .collect();
 
         let iter = self.db.iterator(IteratorMode::Start);
         for item in iter {
             if let Ok((key, value)) = item {
                 // 累加键值大小 / Accumulate key-value size
-                total_kv_size += key.len() as u64 + value.len() as u64;
+                let kv_size = key.len() as u64 + value.len() as u64;
+                total_kv_size += kv_size;
 
                 let key_str = String::from_utf8_lossy(&key);
 
+                // 按数据域累加 / Accumulate per data domain
+                let domain = domains.entry(key_domain(&key_str).to_string()).or_default();
+                domain.key_count += 1;
+                domain.kv_size_bytes += kv_size;
+
                 if key_str.starts_with("event:") {
                     // 解析事件类型 / Parse event type
                     let parts: Vec<&str> = key_str.split(':').collect();
@@ -604,8 +1763,29 @@ impl EventStorage {
             }
         }
 
+        // 没有列族时 RocksDB 属性只有全库值, 按各域键值大小占比分摊
+        // Without column families the RocksDB properties are whole-DB only, apportion by each domain's share of KV size
+        let estimated_num_keys = self
+            .db
+            .property_int_value("rocksdb.estimate-num-keys")?
+            .unwrap_or(0);
+        let estimated_live_data_size = self
+            .db
+            .property_int_value("rocksdb.estimate-live-data-size")?
+            .unwrap_or(0);
+        for domain in domains.values_mut() {
+            domain.kv_size_mb = domain.kv_size_bytes as f64 / (1024.0 * 1024.0);
+            if total_kv_size > 0 {
+                domain.estimated_live_data_size_bytes = (estimated_live_data_size as u128
+                    * domain.kv_size_bytes as u128
+                    / total_kv_size as u128) as u64;
+            }
+        }
+
         Ok(DatabaseStats {
             total_keys: key_count,
+            estimated_num_keys,
+            estimated_live_data_size_bytes: estimated_live_data_size,
             total_kv_size_bytes: total_kv_size,
             total_kv_size_mb: total_kv_size as f64 / (1024.0 * 1024.0),
             database_size_bytes: db_size_bytes,
@@ -617,10 +1797,56 @@ impl EventStorage {
                 signature_mappings: signature_count,
                 slot_batches: slot_count,
             },
+            domains,
         })
     }
 }
 
+/// 数据域及其键前缀 / Data domains and their key prefixes
+///
+/// K线目前只在内存中聚合, 预留前缀以便落盘后直接统计
+/// Klines are currently aggregated in memory only, the prefix is reserved so they are counted once persisted
+pub const DATA_DOMAINS: &[(&str, &[&str])] = &[
+    (
+        "events",
+        &[
+            "event:",
+            "event_global_index:",
+            "idx_mint:",
+            "idx_user:",
+            "user_global_index:",
+            "idx_order:",
+            "sig_map:",
+            "processed_sig:",
+            "event_cursor",
+            "slot_batch:",
+            "event_slot_index:",
+            "liquidation:",
+            "user_pnl:",
+            "event_migration:",
+            "finality_pending:",
+        ],
+    ),
+    ("orderbook", &["orderbook_", "user_global_orders:"]),
+    ("tokens", &["token:", "token_", "price:"]),
+    ("curve", &["curve:"]),
+    ("kline", &["kline:"]),
+    ("counters", &["counter:"]),
+    ("undo", &["undo:"]),
+];
+
+/// 不属于任何已知数据域的键 / Keys outside every known data domain
+pub const OTHER_DOMAIN: &str = "other";
+
+/// 根据键前缀判断所属数据域 / Resolve the data domain of a key by its prefix
+pub fn key_domain(key: &str) -> &'static str {
+    DATA_DOMAINS
+        .iter()
+        .find(|(_, prefixes)| prefixes.iter().any(|p| key.starts_with(p)))
+        .map(|(name, _)| *name)
+        .unwrap_or(OTHER_DOMAIN)
+}
+
 /// 数据库统计信息 / Database statistics
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[schema(title = "DatabaseStats", description = "数据库键值统计信息")]
@@ -644,6 +1870,33 @@ pub struct DatabaseStats {
     pub event_counts: HashMap<String, u64>,
     /// 索引计数 / Index counts
     pub index_counts: IndexCounts,
+    /// RocksDB 估计的全库键数 / Whole-DB key count estimated by RocksDB
+    #[schema(example = 10000)]
+    pub estimated_num_keys: u64,
+    /// RocksDB 估计的全库存活数据大小 / Whole-DB live data size estimated by RocksDB
+    #[schema(example = 1048576)]
+    pub estimated_live_data_size_bytes: u64,
+    /// 按数据域 (events / orderbook / tokens / curve / kline / counters / undo / other) 的统计
+    /// Statistics per data domain (events / orderbook / tokens / curve / kline / counters / undo / other)
+    pub domains: HashMap<String, DomainStats>,
+}
+
+/// 单个数据域的统计 / Statistics of a single data domain
+#[derive(Debug, Default, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(title = "DomainStats", description = "数据域统计 / Data domain statistics")]
+pub struct DomainStats {
+    /// 键数量 / Number of keys
+    #[schema(example = 5000)]
+    pub key_count: u64,
+    /// 键值总大小（字节）/ Total key-value size (bytes)
+    #[schema(example = 1048576)]
+    pub kv_size_bytes: u64,
+    /// 键值总大小（MB）/ Total key-value size (MB)
+    #[schema(example = 1.0)]
+    pub kv_size_mb: f64,
+    /// 按键值大小占比分摊的存活数据估计（字节）/ Estimated live data size apportioned by KV size share (bytes)
+    #[schema(example = 524288)]
+    pub estimated_live_data_size_bytes: u64,
 }
 
 /// 索引计数 / Index counts
@@ -658,4 +1911,165 @@ pub struct IndexCounts {
     pub signature_mappings: u64,
     #[schema(example = 30)]
     pub slot_batches: u64,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solana::events::BuySellEvent;
+    use chrono::{TimeZone, Utc};
+    use rocksdb::Options;
+
+    fn temp_db() -> (Arc<DB>, std::path::PathBuf) {
+        let path = std::env::temp_dir().join(format!("event_storage_test_{}", uuid::Uuid::new_v4()));
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        crate::db::storage::register_counter_merge(&mut opts);
+        (Arc::new(DB::open(&opts, &path).unwrap()), path)
+    }
+
+    const MINT: &str = "EPjFWaLb3crLvQQf89kiNqEX5jg5Kv431J06Y1AD3ic";
+
+    /// 创建买卖事件, 时间戳随序号递增 / Create a buy/sell event, the timestamp grows with the sequence number
+    fn trade(seq: u64, sol_amount: u64) -> PinpetEvent {
+        PinpetEvent::BuySell(BuySellEvent {
+            payer: "9B5X6wrjJVcXHnbPfZ8wP4k5m9n2q1r7t3u2v5w8x1y".to_string(),
+            mint_account: MINT.to_string(),
+            is_buy: seq % 2 == 0,
+            token_amount: 1_000_000,
+            sol_amount,
+            latest_price: 1_000_000,
+            liquidate_indices: Vec::new(),
+            timestamp: Utc.timestamp_opt(1_735_660_800 + seq as i64, 0).unwrap(),
+            signature: format!("sig{:04}", seq),
+            slot: 1_000 + seq,
+            event_index: 0,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_recent_large_trades_filters_by_sol_amount() {
+        let (db, path) = temp_db();
+        let storage = EventStorage::new(db, Arc::new(ShardedLocks::default())).unwrap();
+
+        let sizes = [5_000_000_000, 20_000_000_000, 10_000_000_000, 1_000_000, 50_000_000_000];
+        for (seq, sol_amount) in sizes.into_iter().enumerate() {
+            let event = trade(seq as u64, sol_amount);
+            storage.store_events(event.signature(), vec![event.clone()]).await.unwrap();
+        }
+
+        // 阈值包含等于的成交, 最新优先 / The threshold is inclusive, newest first
+        let whales = storage.recent_large_trades(10_000_000_000, 50).unwrap();
+        let signatures: Vec<&str> = whales.iter().map(|t| t.signature.as_str()).collect();
+        assert_eq!(signatures, vec!["sig0004", "sig0002", "sig0001"]);
+        assert!(whales.iter().all(|t| t.sol_amount >= 10_000_000_000));
+
+        let whales = storage.recent_large_trades(10_000_000_000, 1).unwrap();
+        assert_eq!(whales.len(), 1);
+        assert_eq!(whales[0].signature, "sig0004");
+
+        assert!(storage.recent_large_trades(u64::MAX, 50).unwrap().is_empty());
+
+        drop(storage);
+        std::fs::remove_dir_all(path).ok();
+    }
+
+    #[tokio::test]
+    async fn test_contains_event_matches_legacy_per_type_refs() {
+        let (db, path) = temp_db();
+        let storage = EventStorage::new(db.clone(), Arc::new(ShardedLocks::default())).unwrap();
+
+        // 旧版签名映射: 交易中第二个事件 (序号 1) 是该类型的第一个, 按类型计数为 idx 1
+        // Legacy signature mapping: the transaction's second event (ordinal 1) was the first of its type, counted as idx 1
+        let mut event = trade(3, 1_000_000);
+        if let PinpetEvent::BuySell(ref mut e) = event {
+            e.event_index = 1;
+        }
+        let event_type = EventStorage::get_event_type_code(&event);
+        let legacy = serde_json::json!([{ "slot": event.slot(), "mint": MINT, "event_type": event_type, "idx": 1 }]);
+        db.put(format!("sig_map:{}", event.signature()), serde_json::to_vec(&legacy).unwrap()).unwrap();
+        assert!(storage.contains_event(&event).unwrap());
+
+        // 新写入的引用按事件序号匹配 / Newly written refs match on the event ordinal
+        let other = trade(4, 1_000_000);
+        storage.store_events(other.signature(), vec![other.clone()]).await.unwrap();
+        assert!(storage.contains_event(&other).unwrap());
+        let mut next = other.clone();
+        if let PinpetEvent::BuySell(ref mut e) = next {
+            e.event_index = 1;
+        }
+        assert!(!storage.contains_event(&next).unwrap());
+
+        drop(storage);
+        std::fs::remove_dir_all(path).ok();
+    }
+
+    #[tokio::test]
+    async fn test_set_event_cursor_moves_back_and_stored_events_advance_it() {
+        let (db, path) = temp_db();
+        let storage = EventStorage::new(db, Arc::new(ShardedLocks::default())).unwrap();
+
+        let event = trade(5, 1_000_000);
+        storage.store_events(event.signature(), vec![event.clone()]).await.unwrap();
+        assert_eq!(storage.event_cursor().unwrap().unwrap().last_processed_slot, 1_005);
+
+        // 手动回退游标 / Move the cursor back by hand
+        let cursor = storage.set_event_cursor(900, "").unwrap();
+        assert_eq!(storage.event_cursor().unwrap(), Some(cursor));
+
+        // 之后存储的更高 slot 交易继续推进它 / Transactions stored at higher slots afterwards advance it again
+        let next = trade(6, 1_000_000);
+        storage.store_events(next.signature(), vec![next.clone()]).await.unwrap();
+        let cursor = storage.event_cursor().unwrap().unwrap();
+        assert_eq!(cursor.last_processed_slot, 1_006);
+        assert_eq!(cursor.last_processed_signature, "sig0006");
+
+        drop(storage);
+        std::fs::remove_dir_all(path).ok();
+    }
+
+    #[tokio::test]
+    async fn test_settlements_commit_once_with_their_event() {
+        let (db, path) = temp_db();
+        let storage = EventStorage::new(db, Arc::new(ShardedLocks::default())).unwrap();
+
+        let event = trade(7, 1_000_000_000);
+        let settlement = PnlSettlement {
+            user: "User1".to_string(),
+            mint: MINT.to_string(),
+            order_id: 3,
+            payout: 150_000_000,
+            margin: 100_000_000,
+            liquidated: false,
+            slot: event.slot(),
+        };
+
+        // 同一事件再次写入 (重放或并发重处理) 不会重复计入盈亏 / Writing the same event again (a replay or concurrent reprocess) does not add the PnL twice
+        for _ in 0..2 {
+            storage
+                .store_events_with_settlements(event.signature(), vec![event.clone()], std::slice::from_ref(&settlement))
+                .await
+                .unwrap();
+        }
+
+        let total = storage.user_realized_pnl("User1", None).unwrap();
+        assert_eq!(total.realized_pnl, 50_000_000);
+        assert_eq!(total.settled_orders, 1);
+        assert_eq!(storage.user_realized_pnl("User1", Some(MINT)).unwrap(), PnlSummary {
+            mint: Some(MINT.to_string()),
+            ..total
+        });
+
+        drop(storage);
+        std::fs::remove_dir_all(path).ok();
+    }
+
+    #[test]
+    fn test_key_domain_resolves_prefixes() {
+        assert_eq!(key_domain("curve:So11111111111111111111111111111111111111112"), "curve");
+        assert_eq!(key_domain("token:abc"), "tokens");
+        assert_eq!(key_domain("event:abc:00001:bs:000"), "events");
+        assert_eq!(key_domain("orderbook_header:abc:dn"), "orderbook");
+        assert_eq!(key_domain("unknown:abc"), OTHER_DOMAIN);
+    }
+}
@@ -0,0 +1,267 @@
+// 键值存储抽象 / Key-value storage abstraction
+//
+// CurveStorage 和 TokenStorage 建立在这个 trait 上, 测试可以换成不落盘的 MemoryStore
+// CurveStorage and TokenStorage sit on this trait, so tests can swap in the disk-free MemoryStore
+//
+// EventStorage 和 OrderBookStorage 仍直接使用 RocksDB, 不在本 trait 的范围内: 它们的写入经过按 Arc<DB> 构造的
+// UndoJournal, OrderBookStorage 还依赖一致性快照、从库追赶以及整个 OrderBookDBManager; 迁移它们需要先把
+// UndoJournal 改为基于 KvStore, 作为单独的工作进行
+// EventStorage and OrderBookStorage stay on RocksDB directly and are out of scope for this trait: their writes go
+// through the UndoJournal, which is built on an Arc<DB>, and OrderBookStorage also relies on consistent snapshots,
+// replica catch-up and the whole OrderBookDBManager; moving them needs the UndoJournal rebuilt on KvStore first and
+// is tracked as separate work
+
+use anyhow::Result;
+use rocksdb::{Direction, IteratorMode, WriteBatch, DB};
+
+/// 批量写入中的单个操作 / A single operation in a batched write
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KvOp {
+    Put(Vec<u8>, Vec<u8>),
+    Delete(Vec<u8>),
+    /// 把 u64 小端增量累加到计数器键上, 语义同 storage::register_counter_merge
+    /// Add a little-endian u64 delta onto a counter key, same semantics as storage::register_counter_merge
+    Merge(Vec<u8>, Vec<u8>),
+}
+
+/// 迭代方向 / Iteration direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KvDirection {
+    /// 从起始键向后 (键 >= 起始键) / Onwards from the start key (keys >= start)
+    Forward,
+    /// 从起始键向前 (键 <= 起始键) / Backwards from the start key (keys <= start)
+    Reverse,
+}
+
+/// 按键顺序产出键值对的迭代器 / Iterator yielding key-value pairs in key order
+pub type KvIter<'a> = Box<dyn Iterator<Item = Result<(Vec<u8>, Vec<u8>)>> + 'a>;
+
+/// 键值存储后端 / Key-value storage backend
+pub trait KvStore: Send + Sync {
+    /// 读取值 / Read a value
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>>;
+
+    /// 写入键值对 / Write a key-value pair
+    fn put(&self, key: &[u8], value: &[u8]) -> Result<()>;
+
+    /// 删除键 / Delete a key
+    fn delete(&self, key: &[u8]) -> Result<()>;
+
+    /// 原子提交一组操作 / Atomically commit a group of operations
+    fn write(&self, ops: Vec<KvOp>) -> Result<()>;
+
+    /// 按键升序返回所有以 prefix 开头的键值对 / All pairs whose key starts with prefix, in ascending key order
+    fn scan_prefix(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>>;
+
+    /// 一次读取多个键, 结果与 keys 一一对应 / Read many keys at once, results line up with keys
+    fn multi_get(&self, keys: &[Vec<u8>]) -> Result<Vec<Option<Vec<u8>>>>;
+
+    /// 从 start 开始按方向迭代, 不限前缀, 由调用方决定何时停止
+    /// Iterate from start in the given direction with no prefix bound; the caller decides when to stop
+    fn iter_from<'a>(&'a self, start: &[u8], direction: KvDirection) -> KvIter<'a>;
+}
+
+impl KvStore for DB {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        Ok(DB::get(self, key)?)
+    }
+
+    fn put(&self, key: &[u8], value: &[u8]) -> Result<()> {
+        DB::put(self, key, value)?;
+        Ok(())
+    }
+
+    fn delete(&self, key: &[u8]) -> Result<()> {
+        DB::delete(self, key)?;
+        Ok(())
+    }
+
+    fn write(&self, ops: Vec<KvOp>) -> Result<()> {
+        let mut batch = WriteBatch::default();
+        for op in ops {
+            match op {
+                KvOp::Put(key, value) => batch.put(key, value),
+                KvOp::Delete(key) => batch.delete(key),
+                KvOp::Merge(key, delta) => batch.merge(key, delta),
+            }
+        }
+        DB::write(self, batch)?;
+        Ok(())
+    }
+
+    fn scan_prefix(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let mut pairs = Vec::new();
+        for item in self.iterator(IteratorMode::From(prefix, Direction::Forward)) {
+            let (key, value) = item?;
+            if !key.starts_with(prefix) {
+                break;
+            }
+            pairs.push((key.to_vec(), value.to_vec()));
+        }
+        Ok(pairs)
+    }
+
+    fn multi_get(&self, keys: &[Vec<u8>]) -> Result<Vec<Option<Vec<u8>>>> {
+        DB::multi_get(self, keys)
+            .into_iter()
+            .map(|value| value.map_err(Into::into))
+            .collect()
+    }
+
+    fn iter_from<'a>(&'a self, start: &[u8], direction: KvDirection) -> KvIter<'a> {
+        let direction = match direction {
+            KvDirection::Forward => Direction::Forward,
+            KvDirection::Reverse => Direction::Reverse,
+        };
+        Box::new(
+            self.iterator(IteratorMode::From(start, direction))
+                .map(|item| item.map(|(key, value)| (key.to_vec(), value.to_vec())).map_err(Into::into)),
+        )
+    }
+}
+
+/// 基于 BTreeMap 的内存存储, 仅用于测试 / BTreeMap-backed in-memory store, test-only
+#[cfg(test)]
+#[derive(Default)]
+pub struct MemoryStore {
+    map: std::sync::RwLock<std::collections::BTreeMap<Vec<u8>, Vec<u8>>>,
+}
+
+#[cfg(test)]
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[cfg(test)]
+impl KvStore for MemoryStore {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        Ok(self.map.read().unwrap().get(key).cloned())
+    }
+
+    fn put(&self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.map.write().unwrap().insert(key.to_vec(), value.to_vec());
+        Ok(())
+    }
+
+    fn delete(&self, key: &[u8]) -> Result<()> {
+        self.map.write().unwrap().remove(key);
+        Ok(())
+    }
+
+    fn write(&self, ops: Vec<KvOp>) -> Result<()> {
+        // 持有写锁完成整批操作, 与 WriteBatch 一样对读者原子可见
+        // Apply the whole batch under the write lock, atomically visible to readers like a WriteBatch
+        let mut map = self.map.write().unwrap();
+        for op in ops {
+            match op {
+                KvOp::Put(key, value) => {
+                    map.insert(key, value);
+                }
+                KvOp::Delete(key) => {
+                    map.remove(&key);
+                }
+                KvOp::Merge(key, delta) => {
+                    let total = map
+                        .get(&key)
+                        .map_or(0, |existing| crate::db::storage::decode_counter(existing))
+                        .saturating_add(crate::db::storage::decode_counter(&delta));
+                    map.insert(key, total.to_le_bytes().to_vec());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn scan_prefix(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        Ok(self
+            .map
+            .read()
+            .unwrap()
+            .range(prefix.to_vec()..)
+            .take_while(|(key, _)| key.starts_with(prefix))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect())
+    }
+
+    fn multi_get(&self, keys: &[Vec<u8>]) -> Result<Vec<Option<Vec<u8>>>> {
+        let map = self.map.read().unwrap();
+        Ok(keys.iter().map(|key| map.get(key).cloned()).collect())
+    }
+
+    fn iter_from<'a>(&'a self, start: &[u8], direction: KvDirection) -> KvIter<'a> {
+        // 在读锁内复制出结果, 迭代期间不持有锁 / Copy the pairs out under the read lock so iteration holds no lock
+        let map = self.map.read().unwrap();
+        let pairs: Vec<(Vec<u8>, Vec<u8>)> = match direction {
+            KvDirection::Forward => map.range(start.to_vec()..).map(|(k, v)| (k.clone(), v.clone())).collect(),
+            KvDirection::Reverse => map.range(..=start.to_vec()).rev().map(|(k, v)| (k.clone(), v.clone())).collect(),
+        };
+        Box::new(pairs.into_iter().map(Ok))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocksdb::Options;
+    use std::sync::Arc;
+
+    /// 对任意后端执行同一组断言 / Run the same assertions against any backend
+    fn exercise(store: &dyn KvStore) {
+        store.put(b"a:1", b"one").unwrap();
+        store
+            .write(vec![
+                KvOp::Put(b"a:2".to_vec(), b"two".to_vec()),
+                KvOp::Put(b"b:1".to_vec(), b"other".to_vec()),
+                KvOp::Delete(b"a:1".to_vec()),
+            ])
+            .unwrap();
+
+        assert_eq!(store.get(b"a:1").unwrap(), None);
+        assert_eq!(store.get(b"a:2").unwrap(), Some(b"two".to_vec()));
+
+        store.put(b"a:0", b"zero").unwrap();
+        let keys: Vec<Vec<u8>> = store.scan_prefix(b"a:").unwrap().into_iter().map(|(k, _)| k).collect();
+        assert_eq!(keys, vec![b"a:0".to_vec(), b"a:2".to_vec()]);
+
+        store.delete(b"b:1").unwrap();
+        assert!(store.scan_prefix(b"b:").unwrap().is_empty());
+
+        let values = store.multi_get(&[b"a:2".to_vec(), b"missing".to_vec(), b"a:0".to_vec()]).unwrap();
+        assert_eq!(values, vec![Some(b"two".to_vec()), None, Some(b"zero".to_vec())]);
+
+        // 反向迭代从不大于起始键的最后一个键开始 / Reverse iteration starts at the last key not above start
+        let keys = |direction| -> Vec<Vec<u8>> {
+            store.iter_from(b"a:1", direction).map(|item| item.unwrap().0).collect()
+        };
+        assert_eq!(keys(KvDirection::Forward), vec![b"a:2".to_vec()]);
+        assert_eq!(keys(KvDirection::Reverse), vec![b"a:0".to_vec()]);
+
+        // 计数器合并与 RocksDB 合并算子一致 / Counter merges match the RocksDB merge operator
+        store
+            .write(vec![
+                KvOp::Merge(b"counter:n".to_vec(), 2u64.to_le_bytes().to_vec()),
+                KvOp::Merge(b"counter:n".to_vec(), 3u64.to_le_bytes().to_vec()),
+            ])
+            .unwrap();
+        assert_eq!(store.get(b"counter:n").unwrap(), Some(5u64.to_le_bytes().to_vec()));
+    }
+
+    #[test]
+    fn test_memory_store() {
+        exercise(&MemoryStore::new());
+    }
+
+    #[test]
+    fn test_rocksdb_store_matches_memory_store() {
+        let path = std::env::temp_dir().join(format!("kv_test_{}", uuid::Uuid::new_v4()));
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        crate::db::storage::register_counter_merge(&mut opts);
+        let db = Arc::new(DB::open(&opts, &path).unwrap());
+        exercise(db.as_ref());
+        drop(db);
+        std::fs::remove_dir_all(path).ok();
+    }
+}
@@ -0,0 +1,101 @@
+// 按键分片的互斥锁 / Key-sharded mutexes
+//
+// 同一个 RocksDB 上的多个存储实例 (监听器、API、K线、重处理各自创建) 必须共享同一组锁才能互斥,
+// 因此锁由 RocksDbStorage 持有, 以 Arc 交给它创建的每个存储实例
+// Several storage instances over one RocksDB (the listener, API, K-line and reprocess each create their own) only
+// exclude each other when they share the same locks, so RocksDbStorage owns them and hands an Arc to every storage
+// instance it creates
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::{Mutex, MutexGuard};
+
+/// 默认分片数量 / Default number of shards
+pub const DEFAULT_LOCK_SHARDS: usize = 64;
+
+/// 分片锁: 同一个键总是落到同一个分片 / Sharded locks: a key always maps to the same shard
+pub struct ShardedLocks {
+    shards: Vec<Mutex<()>>,
+}
+
+impl Default for ShardedLocks {
+    fn default() -> Self {
+        Self::new(DEFAULT_LOCK_SHARDS)
+    }
+}
+
+impl ShardedLocks {
+    /// 创建指定分片数的锁组 / Create a lock set with the given number of shards
+    pub fn new(shards: usize) -> Self {
+        Self {
+            shards: (0..shards.max(1)).map(|_| Mutex::new(())).collect(),
+        }
+    }
+
+    fn shard(&self, key: &str) -> usize {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        (hasher.finish() as usize) % self.shards.len()
+    }
+
+    fn lock_shard(&self, shard: usize) -> MutexGuard<'_, ()> {
+        // 锁内只有同步的 RocksDB 操作, 中毒时继续使用 / Only synchronous RocksDB work happens under the lock, keep going if poisoned
+        self.shards[shard].lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 获取键所在分片的锁 / Lock the shard a key belongs to
+    pub fn lock(&self, key: &str) -> MutexGuard<'_, ()> {
+        self.lock_shard(self.shard(key))
+    }
+
+    /// 获取多个键所在分片的锁, 按分片序号升序加锁以避免死锁
+    /// Lock the shards of several keys, in ascending shard order to avoid deadlocks
+    pub fn lock_all<'a>(&self, keys: impl IntoIterator<Item = &'a str>) -> Vec<MutexGuard<'_, ()>> {
+        let mut shards: Vec<usize> = keys.into_iter().map(|key| self.shard(key)).collect();
+        shards.sort_unstable();
+        shards.dedup();
+        shards.into_iter().map(|shard| self.lock_shard(shard)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_shared_locks_serialize_across_holders() {
+        let locks = Arc::new(ShardedLocks::default());
+        let counter = Arc::new(std::sync::atomic::AtomicU64::new(0));
+
+        // 每个线程模拟一个独立的存储实例, 只共享锁 / Each thread stands in for a separate storage instance sharing only the locks
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let locks = Arc::clone(&locks);
+                let counter = Arc::clone(&counter);
+                std::thread::spawn(move || {
+                    for _ in 0..200 {
+                        let _guard = locks.lock("mint");
+                        // 非原子的读-改-写, 只有锁能保证不丢失更新 / A non-atomic read-modify-write, only the lock prevents lost updates
+                        let value = counter.load(std::sync::atomic::Ordering::Relaxed);
+                        std::thread::yield_now();
+                        counter.store(value + 1, std::sync::atomic::Ordering::Relaxed);
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(counter.load(std::sync::atomic::Ordering::Relaxed), 8 * 200);
+    }
+
+    #[test]
+    fn test_lock_all_dedups_shards() {
+        let locks = ShardedLocks::new(1);
+        // 两个键落在同一分片时只加一次锁, 不会自锁 / Two keys in one shard lock it once instead of deadlocking
+        let guards = locks.lock_all(["a", "b"]);
+        assert_eq!(guards.len(), 1);
+    }
+}
@@ -1,10 +1,20 @@
 pub mod storage;
+pub mod kv;
+pub mod locks;
 pub mod event_storage;
 pub mod token_storage;
 pub mod orderbook_storage;
+pub mod curve_storage;
+pub mod replica;
+pub mod undo;
 pub mod errors;
 
 pub use storage::RocksDbStorage;
-pub use event_storage::{EventStorage, DatabaseStats};
-pub use token_storage::{TokenStorage, TokenDetail, TokenUriData, TokenStats};
-pub use orderbook_storage::OrderBookStorage;
+pub use kv::{KvOp, KvStore};
+pub use locks::ShardedLocks;
+pub use event_storage::{EventStorage, DatabaseStats, DomainStats, EventCursor, LiquidationRecord, PnlSettlement, PnlSummary, SignatureStatus};
+pub use token_storage::{TokenStorage, TokenDetail, TokenUriData, TokenStats, PriceRecord};
+pub use orderbook_storage::{GlobalOrderRow, OrderBookMarket, OrderBookStorage, OrderCursor, RebuildMarker};
+pub use curve_storage::{CurveStorage, CurveState, CurveUpdate};
+pub use replica::{ReplicaStatus, ReplicationReport};
+pub use undo::{RevertOutcome, UndoJournal};
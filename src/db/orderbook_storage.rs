@@ -1,24 +1,152 @@
 // OrderBook 专用数据库管理器 / OrderBook dedicated database manager
 use anyhow::Result;
-use rocksdb::{Options, DB};
-use std::collections::HashMap;
+use rocksdb::{IteratorMode, Options, Snapshot, WriteBatch, DB};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, RwLock};
-use tracing::{info, warn};
+use tracing::{error, info, warn};
+use utoipa::ToSchema;
 
 use crate::config::OrderBookDbConfig;
-use crate::orderbook::OrderBookDBManager;
+use crate::db::undo::{RevertOutcome, UndoJournal};
+use crate::orderbook::{Direction, MarginOrder, OrderBookDBManager, OrderBookHeader};
+
+/// 订单簿 header 键前缀 / Order book header key prefix
+const HEADER_PREFIX: &str = "orderbook_header:";
+
+/// 订单簿槽位键前缀 / Order book slot key prefix
+const SLOT_PREFIX: &str = "orderbook_slot:";
+
+/// 用户活跃订单索引键前缀 / User active order index key prefix
+const USER_ACTIVE_PREFIX: &str = "orderbook_user:";
+
+/// 用户跨代币持仓索引回填完成标记 / Marker set once the user cross-mint position index is backfilled
+const USER_GLOBAL_ORDERS_BACKFILL_MARKER: &str = "orderbook_migration:user_global_orders";
+
+/// 需要重建的订单簿标记键前缀 / Key prefix marking books that need a rebuild
+pub(crate) const REBUILD_PREFIX: &str = "orderbook_rebuild_required:";
+
+/// 订单簿的重建标记键 / Rebuild marker key of a book
+pub(crate) fn rebuild_marker_key(mint: &str, direction: Direction) -> String {
+    format!("{}{}:{}", REBUILD_PREFIX, mint, direction)
+}
+
+/// 订单簿需要重建的标记: 分叉回滚时该订单簿已被之后的 slot 修改而无法安全撤销, 或重处理后与事件回放结果不一致
+/// Marker of a book that needs a rebuild: a fork rollback could not undo it safely because a later slot had already
+/// changed it, or after a reprocess it disagrees with a replay of its events
+#[derive(Debug, Clone, Serialize, serde::Deserialize, ToSchema)]
+pub struct RebuildMarker {
+    /// Token mint 地址 / Token mint address
+    pub mint: String,
+    /// 订单方向 / Order direction
+    pub direction: Direction,
+    /// 触发标记的 slot (未能回滚的 slot, 或重处理区间的结束 slot) / The slot behind the marker (the orphaned slot that could not be rolled back, or the end of the reprocessed window)
+    pub slot: u64,
+    /// 标记原因 / Why the book was marked
+    #[serde(default)]
+    pub reason: String,
+    /// 标记时间 (Unix 秒) / When the marker was written (Unix seconds)
+    pub marked_at: i64,
+}
+
+/// 订单簿键所属的订单簿 `{mint}:{dir}`, 非订单簿键为空 / The book `{mint}:{dir}` an order book key belongs to, None for other keys
+fn book_of(key: &[u8]) -> Option<String> {
+    let key = std::str::from_utf8(key).ok()?;
+    // 各前缀之后 mint 和方向所在的段 / The segments holding mint and direction after each prefix
+    let (rest, skip) = [
+        (HEADER_PREFIX, 0),
+        ("orderbook_active_indices:", 0),
+        (SLOT_PREFIX, 0),
+        ("orderbook_id_map:", 0),
+        ("orderbook_user_closed:", 2),
+        ("orderbook_user:", 1),
+        ("user_global_orders:", 1),
+    ]
+    .into_iter()
+    .find_map(|(prefix, skip)| key.strip_prefix(prefix).map(|rest| (rest, skip)))?;
+    let mut parts = rest.split(':').skip(skip);
+    let mint = parts.next()?;
+    let direction: Direction = parts.next()?.parse().ok()?;
+    Some(format!("{}:{}", mint, direction))
+}
+
+/// 跨市场订单列表中的一行 / One row of the cross-market order listing
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct GlobalOrderRow {
+    /// Token mint 地址 / Token mint address
+    pub mint: String,
+    /// 订单方向: "up"(做空) 或 "dn"(做多) / Order direction: "up"(short) or "dn"(long)
+    pub direction: Direction,
+    /// 订单在链表中的索引 / Order index in the linked list
+    pub index: u16,
+    /// 订单数据 / Order data
+    #[serde(flatten)]
+    pub order: MarginOrder,
+}
+
+/// 跨市场订单列表游标: 上一页最后一行的 (mint, 方向, 槽位索引), 编码为 `{mint}:{direction}:{index}`
+/// Cross-market order listing cursor: (mint, direction, slot index) of the last row of the previous page, encoded as `{mint}:{direction}:{index}`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderCursor {
+    pub mint: String,
+    pub direction: Direction,
+    pub index: u16,
+}
+
+impl OrderCursor {
+    /// 编码为查询参数 / Encode as a query parameter
+    pub fn encode(&self) -> String {
+        format!("{}:{}:{}", self.mint, self.direction, self.index)
+    }
+
+    /// 解析查询参数, 格式错误时返回 None / Parse a query parameter, None when malformed
+    pub fn parse(cursor: &str) -> Option<Self> {
+        let mut parts = cursor.rsplitn(3, ':');
+        let index = parts.next()?.parse().ok()?;
+        let direction = parts.next()?.parse().ok()?;
+        let mint = parts.next().filter(|m| !m.is_empty())?;
+        Some(Self {
+            mint: mint.to_string(),
+            direction,
+            index,
+        })
+    }
+}
+
+/// 活跃杠杆市场 (至少一个方向的订单簿非空) / Active leveraged market (at least one non-empty direction)
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct OrderBookMarket {
+    /// Token mint 地址 / Token mint address
+    pub mint: String,
+    /// 做多(dn)订单数 / Long (dn) order count
+    pub dn_orders: u16,
+    /// 做空(up)订单数 / Short (up) order count
+    pub up_orders: u16,
+    /// 两个方向中最早的到期时间(Unix 秒) / Earliest expiry across both directions (Unix seconds)
+    pub nearest_expiry: Option<u32>,
+}
 
 /// OrderBook 存储管理器 / OrderBook storage manager
 /// 负责初始化独立的 OrderBook 数据库,并为每个 (mint, direction) 创建管理器
 /// Responsible for initializing independent OrderBook database and creating managers for each (mint, direction)
 pub struct OrderBookStorage {
-    /// 独立的 RocksDB 实例 / Independent RocksDB instance
+    /// 独立的 RocksDB 实例, 迁移到 KvStore 的缺口见 db/kv.rs / Independent RocksDB instance, see db/kv.rs for what blocks KvStore
     db: Arc<DB>,
 
     /// 缓存已创建的 OrderBook 管理器 / Cache of created OrderBook managers
     /// Key: "mint:direction" (例如 "EPjFWdd5A....:up" 或 "EPjFWdd5A....:dn")
     /// Key: "mint:direction" (e.g., "EPjFWdd5A....:up" or "EPjFWdd5A....:dn")
     managers: Arc<RwLock<HashMap<String, Arc<OrderBookDBManager>>>>,
+
+    /// 因订单簿已满而未能插入的开仓事件数 / Open-position events that could not be inserted because the book was full
+    capacity_rejections: AtomicU64,
+
+    /// 只读 (secondary), 不初始化缺失的订单簿 / Read-only (secondary), missing books are not initialized
+    read_only: bool,
+
+    /// 撤销日志, 开启时订单簿写入可按 slot 回滚 / Undo journal; when set, order book writes can be rolled back per slot
+    journal: Option<Arc<UndoJournal>>,
 }
 
 impl OrderBookStorage {
@@ -28,6 +156,46 @@ impl OrderBookStorage {
     /// * `config` - OrderBook 数据库性能配置 / OrderBook database performance config
     /// * `db_path` - 数据库路径 / Database path
     pub fn new(config: &OrderBookDbConfig, db_path: &str) -> Result<Self> {
+        let db = DB::open(&Self::db_options(config), db_path)?;
+
+        info!(
+            "🗄️ OrderBook RocksDB initialized successfully / OrderBook RocksDB 初始化成功, path: {}",
+            db_path
+        );
+        info!(
+            "📊 OrderBook DB config: write_buffer={}MB, max_buffers={}, fsync={}, paranoid_checks={}, bg_jobs={}",
+            config.write_buffer_size_mb,
+            config.max_write_buffer_number,
+            config.use_fsync,
+            config.paranoid_checks,
+            config.max_background_jobs
+        );
+
+        Ok(Self::from_db(db, false))
+    }
+
+    /// 以 secondary 只读打开主实例的 OrderBook 数据库 (replica 模式)
+    /// Open the primary's OrderBook database read-only as a secondary (replica mode)
+    ///
+    /// # 参数 / Parameters
+    /// * `db_path` - 主实例的数据库路径 / The primary's database path
+    /// * `secondary_path` - secondary 的私有目录 / Private directory of the secondary
+    pub fn open_secondary(config: &OrderBookDbConfig, db_path: &str, secondary_path: &str) -> Result<Self> {
+        let mut opts = Self::db_options(config);
+        // secondary 要求保持所有文件打开 / Secondaries require keeping every file open
+        opts.set_max_open_files(-1);
+        let db = DB::open_as_secondary(&opts, db_path, secondary_path)?;
+
+        info!(
+            "🗄️ OrderBook RocksDB opened as read-only secondary / OrderBook RocksDB 以只读副本打开, primary: {}, secondary: {}",
+            db_path, secondary_path
+        );
+
+        Ok(Self::from_db(db, true))
+    }
+
+    /// 数据库选项 / Database options
+    fn db_options(config: &OrderBookDbConfig) -> Options {
         let mut opts = Options::default();
         opts.create_if_missing(true);
         opts.create_missing_column_families(true);
@@ -51,39 +219,186 @@ impl OrderBookStorage {
             rocksdb::DBCompressionType::Lz4,    // L2+: 轻压缩 / Light compression
         ]);
 
-        let db = DB::open(&opts, db_path)?;
+        opts
+    }
 
-        info!(
-            "🗄️ OrderBook RocksDB initialized successfully / OrderBook RocksDB 初始化成功, path: {}",
-            db_path
+    fn from_db(db: DB, read_only: bool) -> Self {
+        Self {
+            db: Arc::new(db),
+            managers: Arc::new(RwLock::new(HashMap::new())),
+            capacity_rejections: AtomicU64::new(0),
+            read_only,
+            journal: None,
+        }
+    }
+
+    /// 订单簿写入时记录撤销日志, 需在创建任何管理器之前调用
+    /// Record the undo journal on order book writes; call before any manager is created
+    pub fn with_undo_journal(mut self) -> Self {
+        self.journal = Some(Arc::new(UndoJournal::new(self.db.clone())));
+        self
+    }
+
+    /// 之后的订单簿写入归属于该 slot / Subsequent order book writes belong to this slot
+    pub fn begin_slot(&self, slot: u64) {
+        if let Some(journal) = &self.journal {
+            journal.begin_slot(slot);
+        }
+    }
+
+    /// 撤销某个 slot 对订单簿的全部修改; 未开启撤销日志时什么也不做
+    /// Undo every order book change made in a slot; does nothing without an undo journal
+    ///
+    /// 每个订单簿整体撤销: 若之后的 slot 又修改过该订单簿, 它保持不变并写入重建标记, 而不是只退回一半
+    /// Each book is reverted as a whole: when a later slot changed it again it is left untouched and marked for a
+    /// rebuild instead of being half reverted
+    ///
+    /// 回滚可能删除该 slot 中新建的订单簿, 因此清空管理器缓存, 下次访问时重新初始化
+    /// A rollback may delete books created in that slot, so the manager cache is cleared and books are re-initialized on next access
+    pub fn revert_slot(&self, slot: u64) -> Result<RevertOutcome> {
+        let Some(journal) = &self.journal else {
+            return Ok(RevertOutcome::default());
+        };
+        let outcome = journal.revert_slot_grouped(slot, book_of)?;
+        for book in &outcome.conflicted {
+            let Some((mint, direction)) = book.split_once(':').and_then(|(mint, dir)| Some((mint, dir.parse().ok()?)))
+            else {
+                continue;
+            };
+            self.mark_rebuild(mint, direction, slot, "orphaned slot changed again by a later slot")?;
+        }
+        self.managers.write().unwrap().clear();
+        Ok(outcome)
+    }
+
+    /// 标记订单簿需要重建 / Mark a book as needing a rebuild
+    pub fn mark_rebuild(&self, mint: &str, direction: Direction, slot: u64, reason: &str) -> Result<()> {
+        let marker = RebuildMarker {
+            mint: mint.to_string(),
+            direction,
+            slot,
+            reason: reason.to_string(),
+            marked_at: chrono::Utc::now().timestamp(),
+        };
+        self.db.put(rebuild_marker_key(mint, direction), serde_json::to_vec(&marker)?)?;
+        error!(
+            "❌ 订单簿需要重建 / Order book needs a rebuild: {}:{} (slot {}, {})",
+            mint, direction, slot, reason
         );
-        info!(
-            "📊 OrderBook DB config: write_buffer={}MB, max_buffers={}, fsync={}, paranoid_checks={}, bg_jobs={}",
-            config.write_buffer_size_mb,
-            config.max_write_buffer_number,
-            config.use_fsync,
-            config.paranoid_checks,
-            config.max_background_jobs
+        Ok(())
+    }
+
+    /// 在批次中清除重建标记, 由重建随重写的槽位一起提交 / Clear the rebuild marker in a batch, committed by a rebuild together with the rewritten slots
+    pub(crate) fn clear_rebuild(batch: &mut WriteBatch, mint: &str, direction: Direction) {
+        batch.delete(rebuild_marker_key(mint, direction));
+    }
+
+    /// 所有需要重建的订单簿 / Every book marked for a rebuild
+    pub fn rebuild_required(&self) -> Result<Vec<RebuildMarker>> {
+        let mut markers = Vec::new();
+        for item in self.db.prefix_iterator(REBUILD_PREFIX.as_bytes()) {
+            let (key, value) = item?;
+            if !key.starts_with(REBUILD_PREFIX.as_bytes()) {
+                break;
+            }
+            markers.push(serde_json::from_slice(&value)?);
+        }
+        Ok(markers)
+    }
+
+    /// 仍有撤销记录的 slot, 重启后据此恢复待终结集合
+    /// Slots that still have undo records, used to restore the unsettled set after a restart
+    pub fn journaled_slots(&self) -> Result<BTreeSet<u64>> {
+        match &self.journal {
+            Some(journal) => journal.journaled_slots(),
+            None => Ok(BTreeSet::new()),
+        }
+    }
+
+    /// slot 已终结, 丢弃它的撤销记录 / The slot is finalized, drop its undo records
+    pub fn commit_slot(&self, slot: u64) -> Result<usize> {
+        match &self.journal {
+            Some(journal) => journal.commit_slot(slot),
+            None => Ok(0),
+        }
+    }
+
+    /// 追赶主实例的最新写入, 仅 secondary 可用 / Catch up with the primary's latest writes, secondaries only
+    pub fn catch_up_with_primary(&self) -> Result<()> {
+        self.db.try_catch_up_with_primary()?;
+        Ok(())
+    }
+
+    /// 记录一次因订单簿已满被拒绝的插入, 并输出可告警的错误日志
+    /// Record an insert rejected because the book is full and emit an alertable error log
+    ///
+    /// 链上同样受 MAX_CAPACITY 限制, 订单簿满后该市场无法再开仓, 需要运维介入
+    /// The program is bound by the same MAX_CAPACITY, so a full book means the market cannot take new positions until operators step in
+    pub fn record_capacity_rejection(&self, mint: &str, direction: Direction) {
+        let total = self.capacity_rejections.fetch_add(1, Ordering::Relaxed) + 1;
+        error!(
+            "🚨 ORDERBOOK_AT_CAPACITY 订单簿已满, 无法插入新订单 / Order book is full, cannot insert new orders: mint={}, direction={}, max={}, rejections_total={}",
+            mint,
+            direction,
+            OrderBookHeader::MAX_CAPACITY,
+            total
         );
+    }
 
-        Ok(Self {
-            db: Arc::new(db),
-            managers: Arc::new(RwLock::new(HashMap::new())),
-        })
+    /// 因订单簿已满被拒绝的插入总数 / Total inserts rejected because the book was full
+    pub fn capacity_rejections(&self) -> u64 {
+        self.capacity_rejections.load(Ordering::Relaxed)
+    }
+
+    /// 从用户活跃订单索引回填跨代币持仓索引 (一次性迁移), 已执行过时返回 None
+    /// Backfill the cross-mint position index from the user active order index (one-time migration), returns None if it already ran
+    ///
+    /// 索引上线前开仓的订单不在 user_global_orders 中; 回填后写入标记, 之后由订单簿管理器维护; 须在事件监听器启动前调用
+    /// Orders opened before the index existed are missing from user_global_orders; a marker is written afterwards and the
+    /// order book manager keeps it up to date. Must run before the event listener starts
+    pub fn backfill_user_global_orders(&self) -> Result<Option<u64>> {
+        if self.db.get(USER_GLOBAL_ORDERS_BACKFILL_MARKER.as_bytes())?.is_some() {
+            return Ok(None);
+        }
+
+        let mut batch = rocksdb::WriteBatch::default();
+        let mut backfilled = 0u64;
+        for item in self.db.prefix_iterator(USER_ACTIVE_PREFIX.as_bytes()) {
+            let (key, _) = item?;
+            if !key.starts_with(USER_ACTIVE_PREFIX.as_bytes()) {
+                break;
+            }
+
+            // orderbook_user:{user}:{mint}:{direction}:{start_time:010}:{order_id:020}
+            let key_str = String::from_utf8_lossy(&key);
+            let parts: Vec<&str> = key_str.split(':').collect();
+            if parts.len() != 6 {
+                continue;
+            }
+            let global_key = format!("user_global_orders:{}:{}:{}:{}", parts[1], parts[2], parts[3], parts[5]);
+            batch.put(global_key.as_bytes(), b"");
+            backfilled += 1;
+        }
+
+        batch.put(USER_GLOBAL_ORDERS_BACKFILL_MARKER.as_bytes(), chrono::Utc::now().timestamp().to_string().as_bytes());
+        self.db.write(batch)?;
+
+        info!("✅ 用户跨代币持仓索引回填完成 / User cross-mint position index backfill complete: orders={}", backfilled);
+        Ok(Some(backfilled))
     }
 
     /// 获取或创建 OrderBook 管理器 / Get or create OrderBook manager
     ///
     /// # 参数 / Parameters
     /// * `mint` - Token mint 地址 / Token mint address
-    /// * `direction` - 订单方向: "up"(做空) 或 "dn"(做多) / Order direction: "up"(short) or "dn"(long)
+    /// * `direction` - 订单方向: Up(做空) 或 Dn(做多) / Order direction: Up(short) or Dn(long)
     ///
     /// # 返回值 / Returns
     /// 返回对应的 OrderBookDBManager 实例 / Returns corresponding OrderBookDBManager instance
     pub fn get_or_create_manager(
         &self,
         mint: String,
-        direction: String,
+        direction: Direction,
     ) -> Result<Arc<OrderBookDBManager>> {
         let key = format!("{}:{}", mint, direction);
 
@@ -101,33 +416,36 @@ impl OrderBookStorage {
             &mint[..8], direction
         );
 
-        let manager = Arc::new(OrderBookDBManager::new(
-            self.db.clone(),
-            mint.clone(),
-            direction.clone(),
-        ));
+        let mut manager = OrderBookDBManager::new(self.db.clone(), mint.clone(), direction);
+        if let Some(journal) = &self.journal {
+            manager = manager.with_journal(journal.clone());
+        }
+        let manager = Arc::new(manager);
 
-        // 初始化 OrderBook (如果不存在) / Initialize OrderBook (if not exists)
+        // 初始化 OrderBook (如果不存在), 只读时跳过, 缺失的订单簿查询时返回不存在
         // 使用 "system" 作为 authority
-        match manager.initialize("system".to_string()) {
-            Ok(_) => {
-                info!(
-                    "✅ OrderBook initialized / OrderBook 已初始化: {}:{}",
-                    &mint[..8], direction
-                );
-            }
-            Err(e) => {
-                // 如果已存在,忽略错误 / Ignore error if already exists
-                if e.to_string().contains("already exists") {
+        // Initialize OrderBook (if not exists); skipped when read-only, where a missing book reads as not found
+        if !self.read_only {
+            match manager.initialize("system".to_string()) {
+                Ok(_) => {
                     info!(
-                        "ℹ️ OrderBook already exists / OrderBook 已存在: {}:{}",
+                        "✅ OrderBook initialized / OrderBook 已初始化: {}:{}",
                         &mint[..8], direction
                     );
-                } else {
-                    warn!(
-                        "⚠️ OrderBook initialization warning / OrderBook 初始化警告: {}:{} - {}",
-                        &mint[..8], direction, e
-                    );
+                }
+                Err(e) => {
+                    // 如果已存在,忽略错误 / Ignore error if already exists
+                    if e.to_string().contains("already exists") {
+                        info!(
+                            "ℹ️ OrderBook already exists / OrderBook 已存在: {}:{}",
+                            &mint[..8], direction
+                        );
+                    } else {
+                        warn!(
+                            "⚠️ OrderBook initialization warning / OrderBook 初始化警告: {}:{} - {}",
+                            &mint[..8], direction, e
+                        );
+                    }
                 }
             }
         }
@@ -141,6 +459,183 @@ impl OrderBookStorage {
         Ok(manager)
     }
 
+    /// 扫描 orderbook_header: 前缀, 按 mint 顺序分页列出至少一个方向非空的市场
+    /// Scan the orderbook_header: prefix and page through markets with at least one non-empty direction, in mint order
+    ///
+    /// start_after 为上一页返回的游标 (最后一个mint, 不含), 还有下一页时返回新的游标
+    /// start_after is the cursor from the previous page (last mint, exclusive); a new cursor is returned while more pages remain
+    pub fn list_markets(
+        &self,
+        start_after: Option<&str>,
+        limit: usize,
+    ) -> Result<(Vec<OrderBookMarket>, Option<String>)> {
+        // ';' 紧跟 ':' 之后, 从游标 mint 的所有方向之后开始 / ';' sorts right after ':', so this starts past every direction of the cursor mint
+        let start_key = match start_after {
+            Some(mint) => format!("{}{};", HEADER_PREFIX, mint),
+            None => HEADER_PREFIX.to_string(),
+        };
+
+        let snapshot = self.db.snapshot();
+        let mut markets = Vec::new();
+        let mut current: Option<OrderBookMarket> = None;
+        let mut has_more = false;
+
+        let iter = snapshot.iterator(IteratorMode::From(start_key.as_bytes(), rocksdb::Direction::Forward));
+        for item in iter {
+            let (key, value) = item?;
+            let key_str = String::from_utf8_lossy(&key);
+            let Some(rest) = key_str.strip_prefix(HEADER_PREFIX) else {
+                break;
+            };
+
+            // orderbook_header:{mint}:{direction}
+            let Some((mint, direction)) = rest.rsplit_once(':') else {
+                continue;
+            };
+            let Ok(direction) = direction.parse::<Direction>() else {
+                continue;
+            };
+            let header = OrderBookHeader::from_bytes(&value)?;
+
+            if current.as_ref().map_or(true, |m| m.mint != mint) {
+                if let Some(market) = current.take() {
+                    Self::push_market(&snapshot, &mut markets, market)?;
+                }
+                if markets.len() >= limit {
+                    has_more = true;
+                    break;
+                }
+                current = Some(OrderBookMarket {
+                    mint: mint.to_string(),
+                    ..Default::default()
+                });
+            }
+            if let Some(market) = current.as_mut() {
+                match direction {
+                    Direction::Dn => market.dn_orders = header.total,
+                    Direction::Up => market.up_orders = header.total,
+                }
+            }
+        }
+        if let Some(market) = current.take() {
+            Self::push_market(&snapshot, &mut markets, market)?;
+        }
+
+        let next_cursor = if has_more {
+            markets.last().map(|m| m.mint.clone())
+        } else {
+            None
+        };
+        Ok((markets, next_cursor))
+    }
+
+    /// 按 (mint, 方向, 槽位索引) 顺序分页列出所有订单簿中的订单, 可按方向过滤
+    /// Page through the orders of every order book in (mint, direction, slot index) order, optionally filtered by direction
+    ///
+    /// 先扫描 orderbook_header: 前缀找到各订单簿, 再扫描其槽位; 整页在同一快照上读取。
+    /// start_after 为上一页返回的游标 (不含), 还有下一页时返回新的游标
+    /// Scans the orderbook_header: prefix for books, then each book's slots, all on one snapshot.
+    /// start_after is the cursor from the previous page (exclusive); a new cursor is returned while more pages remain
+    pub fn list_all_orders(
+        &self,
+        direction_filter: Option<Direction>,
+        start_after: Option<&OrderCursor>,
+        limit: usize,
+    ) -> Result<(Vec<GlobalOrderRow>, Option<OrderCursor>)> {
+        let snapshot = self.db.snapshot();
+        let start_key = match start_after {
+            Some(cursor) => format!("{}{}:{}", HEADER_PREFIX, cursor.mint, cursor.direction),
+            None => HEADER_PREFIX.to_string(),
+        };
+
+        let mut rows: Vec<GlobalOrderRow> = Vec::new();
+        let mut has_more = false;
+
+        let headers = snapshot.iterator(IteratorMode::From(start_key.as_bytes(), rocksdb::Direction::Forward));
+        'books: for item in headers {
+            let (key, _value) = item?;
+            let key_str = String::from_utf8_lossy(&key);
+            let Some(rest) = key_str.strip_prefix(HEADER_PREFIX) else {
+                break;
+            };
+
+            // orderbook_header:{mint}:{direction}
+            let Some((mint, direction)) = rest.rsplit_once(':') else {
+                continue;
+            };
+            let Ok(direction) = direction.parse::<Direction>() else {
+                continue;
+            };
+            if direction_filter.is_some_and(|d| d != direction) {
+                continue;
+            }
+
+            // 游标所在订单簿从游标之后的槽位开始 / Resume after the cursor slot in the cursor's own book
+            let first_index = match start_after {
+                Some(cursor) if cursor.mint == mint && cursor.direction == direction => cursor.index as u32 + 1,
+                _ => 0,
+            };
+            let slot_prefix = format!("{}{}:{}:", SLOT_PREFIX, mint, direction);
+            let slot_start = format!("{}{:05}", slot_prefix, first_index);
+
+            let slots = snapshot.iterator(IteratorMode::From(slot_start.as_bytes(), rocksdb::Direction::Forward));
+            for item in slots {
+                let (key, value) = item?;
+                let key_str = String::from_utf8_lossy(&key);
+                let Some(index) = key_str.strip_prefix(&slot_prefix) else {
+                    break;
+                };
+                let Ok(index) = index.parse::<u16>() else {
+                    continue;
+                };
+
+                if rows.len() >= limit {
+                    has_more = true;
+                    break 'books;
+                }
+                rows.push(GlobalOrderRow {
+                    mint: mint.to_string(),
+                    direction,
+                    index,
+                    order: MarginOrder::from_bytes(&value)?,
+                });
+            }
+        }
+
+        let next_cursor = if has_more {
+            rows.last().map(|row| OrderCursor {
+                mint: row.mint.clone(),
+                direction: row.direction,
+                index: row.index,
+            })
+        } else {
+            None
+        };
+        Ok((rows, next_cursor))
+    }
+
+    /// 非空市场补充最早到期时间后加入结果, 直接扫描槽位, 不为每个市场创建管理器
+    /// Fill in the nearest expiry of a non-empty market and add it to the results; scans the slots directly
+    /// instead of creating a manager per market
+    fn push_market(snapshot: &Snapshot<'_>, markets: &mut Vec<OrderBookMarket>, mut market: OrderBookMarket) -> Result<()> {
+        for (direction, total) in [(Direction::Dn, market.dn_orders), (Direction::Up, market.up_orders)] {
+            let slot_prefix = format!("{}{}:{}:", SLOT_PREFIX, market.mint, direction);
+            let slots = snapshot.iterator(IteratorMode::From(slot_prefix.as_bytes(), rocksdb::Direction::Forward));
+            for item in slots.take(total as usize) {
+                let (key, value) = item?;
+                if !key.starts_with(slot_prefix.as_bytes()) {
+                    break;
+                }
+                let expiry = MarginOrder::from_bytes(&value)?.end_time;
+                market.nearest_expiry = Some(market.nearest_expiry.map_or(expiry, |t| t.min(expiry)));
+            }
+        }
+        if market.dn_orders > 0 || market.up_orders > 0 {
+            markets.push(market);
+        }
+        Ok(())
+    }
+
     /// 获取数据库统计信息 / Get database statistics
     pub fn get_stats(&self) -> Result<String> {
         let stats = self.db.property_value("rocksdb.stats")?;
@@ -157,3 +652,146 @@ impl OrderBookStorage {
         self.db.clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::OrderBookDbConfig;
+
+    fn open_storage() -> (OrderBookStorage, std::path::PathBuf) {
+        let path = std::env::temp_dir().join(format!("orderbook_storage_test_{}", uuid::Uuid::new_v4()));
+        let storage = OrderBookStorage::new(&OrderBookDbConfig::default(), path.to_str().unwrap()).unwrap();
+        (storage, path)
+    }
+
+    fn order(order_id: u64, price: u128) -> MarginOrder {
+        MarginOrder {
+            user: "Alice".to_string(),
+            lock_lp_start_price: price,
+            lock_lp_end_price: price + 100,
+            open_price: price + 50,
+            take_profit_price: 0,
+            order_id,
+            lock_lp_sol_amount: 1,
+            lock_lp_token_amount: 1,
+            next_lp_sol_amount: 0,
+            next_lp_token_amount: 0,
+            margin_init_sol_amount: 1,
+            margin_sol_amount: 1,
+            borrow_amount: 1,
+            position_asset_amount: 1,
+            realized_sol_amount: 0,
+            version: 0,
+            start_time: 1735660800,
+            end_time: 1735747200,
+            next_order: u16::MAX,
+            prev_order: u16::MAX,
+            borrow_fee: 0,
+            order_type: 1,
+        }
+    }
+
+    #[test]
+    fn test_backfill_user_global_orders() {
+        let (storage, path) = open_storage();
+        let manager = storage.get_or_create_manager("MintA".to_string(), Direction::Dn).unwrap();
+        manager.insert_after(u16::MAX, &order(7, 1_000_000)).unwrap();
+
+        // 模拟索引上线前开仓的订单 / Simulate an order opened before the index existed
+        let global_key = "user_global_orders:Alice:MintA:dn:00000000000000000007";
+        assert!(storage.db.get(global_key).unwrap().is_some());
+        storage.db.delete(global_key).unwrap();
+
+        assert_eq!(storage.backfill_user_global_orders().unwrap(), Some(1));
+        assert!(storage.db.get(global_key).unwrap().is_some());
+        let (total, _) = crate::orderbook::UserOrderQueryService::new(storage.db())
+            .query_user_global_positions("Alice", 1, 10)
+            .unwrap();
+        assert_eq!(total, 1);
+
+        assert_eq!(storage.backfill_user_global_orders().unwrap(), None);
+
+        drop(storage);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_list_all_orders_pages_across_books() {
+        let (storage, path) = open_storage();
+        let mut next_id = 1;
+        for (mint, direction, count) in [("MintA", Direction::Dn, 3), ("MintA", Direction::Up, 1), ("MintB", Direction::Dn, 2)] {
+            let manager = storage.get_or_create_manager(mint.to_string(), direction).unwrap();
+            for i in 0..count {
+                let after = if i == 0 { u16::MAX } else { i - 1 };
+                manager.insert_after(after, &order(next_id, 1_000_000 - next_id as u128 * 1000)).unwrap();
+                next_id += 1;
+            }
+        }
+
+        // 每页 2 条, 游标跨越订单簿边界 / Two per page, with cursors crossing book boundaries
+        let mut seen = Vec::new();
+        let mut cursor = None;
+        loop {
+            let (rows, next) = storage.list_all_orders(None, cursor.as_ref(), 2).unwrap();
+            assert!(rows.len() <= 2);
+            seen.extend(rows.into_iter().map(|r| (r.mint, r.direction, r.index)));
+            match next {
+                Some(next) => cursor = Some(OrderCursor::parse(&next.encode()).unwrap()),
+                None => break,
+            }
+        }
+        assert_eq!(seen.len(), 6);
+        assert_eq!(seen[0], ("MintA".to_string(), Direction::Dn, 0));
+        assert_eq!(seen[3], ("MintA".to_string(), Direction::Up, 0));
+        assert_eq!(seen[5], ("MintB".to_string(), Direction::Dn, 1));
+
+        // 按方向过滤 / Filter by direction
+        let (rows, next) = storage.list_all_orders(Some(Direction::Up), None, 10).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert!(next.is_none());
+
+        drop(storage);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_list_markets_reads_headers_without_managers() {
+        let (storage, path) = open_storage();
+        let dn = storage.get_or_create_manager("MintA".to_string(), Direction::Dn).unwrap();
+        dn.insert_after(u16::MAX, &order(1, 1_000_000)).unwrap();
+        dn.insert_after(0, &MarginOrder { end_time: 1735700000, ..order(2, 900_000) }).unwrap();
+        let up = storage.get_or_create_manager("MintB".to_string(), Direction::Up).unwrap();
+        up.insert_after(u16::MAX, &order(3, 1_000_000)).unwrap();
+        // 已清空的订单簿不算市场 / An emptied book is not a market
+        let emptied = storage.get_or_create_manager("MintC".to_string(), Direction::Dn).unwrap();
+        emptied.insert_after(u16::MAX, &order(4, 1_000_000)).unwrap();
+        emptied.batch_remove_by_indices_unsafe(&[0], 1, 1_000_000).unwrap();
+        storage.managers.write().unwrap().clear();
+
+        let (markets, next) = storage.list_markets(None, 1).unwrap();
+        assert_eq!(markets.len(), 1);
+        assert_eq!((markets[0].mint.as_str(), markets[0].dn_orders, markets[0].up_orders), ("MintA", 2, 0));
+        assert_eq!(markets[0].nearest_expiry, Some(1735700000));
+
+        let (markets, next) = storage.list_markets(next.as_deref(), 10).unwrap();
+        assert_eq!(markets.len(), 1);
+        assert_eq!((markets[0].mint.as_str(), markets[0].dn_orders, markets[0].up_orders), ("MintB", 0, 1));
+        assert!(next.is_none());
+        assert_eq!(storage.get_manager_count(), 0);
+
+        drop(storage);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_order_cursor_round_trip() {
+        let cursor = OrderCursor {
+            mint: "MintA".to_string(),
+            direction: Direction::Up,
+            index: 42,
+        };
+        assert_eq!(OrderCursor::parse(&cursor.encode()), Some(cursor));
+        assert_eq!(OrderCursor::parse("MintA:sideways:1"), None);
+        assert_eq!(OrderCursor::parse(":dn:1"), None);
+    }
+}
@@ -0,0 +1,131 @@
+// 只读副本追赶 / Read replica catch-up
+//
+// RocksDB secondary 只有调用 try_catch_up_with_primary 后才能看到主实例的新写入; 成功追赶后可见的数据即主实例当时已写入的全部数据,
+// 因此副本的滞后上限就是距上次成功追赶的时间
+// A RocksDB secondary only sees the primary's new writes after try_catch_up_with_primary; after a successful catch-up it sees
+// everything the primary had written at that moment, so the replica's staleness is bounded by the time since the last successful catch-up
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+use super::{OrderBookStorage, RocksDbStorage};
+
+/// 副本追赶状态, 由追赶任务更新, 健康检查读取 / Replica catch-up status, updated by the catch-up task and read by the health check
+#[derive(Debug, Default)]
+pub struct ReplicaStatus {
+    /// 上次成功追赶的时间(毫秒), 0 表示尚未成功 / Last successful catch-up (ms), 0 when none yet
+    last_catch_up_at: AtomicI64,
+    consecutive_failures: AtomicU64,
+    last_error: Mutex<Option<String>>,
+}
+
+/// 副本追赶状态报告 / Replica catch-up status report
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ReplicationReport {
+    /// 上次成功追赶的时间(毫秒), 尚未成功时为空 / Last successful catch-up (ms), null when none yet
+    pub last_catch_up_at: Option<i64>,
+    /// 距上次成功追赶的毫秒数, 即副本数据的滞后上限 / Milliseconds since the last successful catch-up, the upper bound of the replica's staleness
+    pub catch_up_age_ms: Option<i64>,
+    /// 连续追赶失败次数 / Consecutive catch-up failures
+    pub consecutive_failures: u64,
+    /// 最近一次追赶失败的原因, 恢复后清空 / Reason of the latest catch-up failure, cleared on recovery
+    pub last_error: Option<String>,
+}
+
+impl ReplicaStatus {
+    fn record_success(&self, now_ms: i64) {
+        self.last_catch_up_at.store(now_ms, Ordering::Relaxed);
+        self.consecutive_failures.store(0, Ordering::Relaxed);
+        *self.last_error.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    fn record_failure(&self, error: String) -> u64 {
+        *self.last_error.lock().unwrap_or_else(|e| e.into_inner()) = Some(error);
+        self.consecutive_failures.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    /// 当前状态 / Current status
+    pub fn report(&self, now_ms: i64) -> ReplicationReport {
+        let last = self.last_catch_up_at.load(Ordering::Relaxed);
+        let last_catch_up_at = (last > 0).then_some(last);
+        ReplicationReport {
+            last_catch_up_at,
+            catch_up_age_ms: last_catch_up_at.map(|at| now_ms.saturating_sub(at).max(0)),
+            consecutive_failures: self.consecutive_failures.load(Ordering::Relaxed),
+            last_error: self.last_error.lock().unwrap_or_else(|e| e.into_inner()).clone(),
+        }
+    }
+}
+
+/// 两个数据库都追赶成功才算一次成功 / A round succeeds only when both databases catch up
+fn catch_up_once(db_storage: &RocksDbStorage, orderbook_storage: &OrderBookStorage) -> anyhow::Result<()> {
+    db_storage
+        .catch_up_with_primary()
+        .map_err(|e| anyhow::anyhow!("RocksDB: {}", e))?;
+    orderbook_storage
+        .catch_up_with_primary()
+        .map_err(|e| anyhow::anyhow!("OrderBook: {}", e))?;
+    Ok(())
+}
+
+/// 按固定间隔追赶主实例, 失败只记录日志并在下一轮重试
+/// Catch up with the primary at a fixed interval; failures are logged and retried next round
+pub async fn run_catch_up(
+    db_storage: Arc<RocksDbStorage>,
+    orderbook_storage: Arc<OrderBookStorage>,
+    status: Arc<ReplicaStatus>,
+    interval: Duration,
+) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        ticker.tick().await;
+        match catch_up_once(&db_storage, &orderbook_storage) {
+            Ok(()) => {
+                if status.consecutive_failures.load(Ordering::Relaxed) > 0 {
+                    info!("✅ 副本追赶已恢复 / Replica catch-up recovered");
+                }
+                status.record_success(chrono::Utc::now().timestamp_millis());
+            }
+            Err(e) => {
+                let failures = status.record_failure(e.to_string());
+                warn!(
+                    "⚠️ 副本追赶主实例失败 (连续 {} 次) / Replica failed to catch up with the primary ({} in a row): {}",
+                    failures, failures, e
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_tracks_failures_and_recovery() {
+        let status = ReplicaStatus::default();
+        let report = status.report(1_000);
+        assert_eq!(report.last_catch_up_at, None);
+        assert_eq!(report.catch_up_age_ms, None);
+
+        status.record_success(1_000);
+        assert_eq!(status.record_failure("boom".to_string()), 1);
+        assert_eq!(status.record_failure("boom again".to_string()), 2);
+        let report = status.report(4_500);
+        assert_eq!(report.last_catch_up_at, Some(1_000));
+        assert_eq!(report.catch_up_age_ms, Some(3_500));
+        assert_eq!(report.consecutive_failures, 2);
+        assert_eq!(report.last_error.as_deref(), Some("boom again"));
+
+        status.record_success(5_000);
+        let report = status.report(5_200);
+        assert_eq!(report.catch_up_age_ms, Some(200));
+        assert_eq!(report.consecutive_failures, 0);
+        assert_eq!(report.last_error, None);
+    }
+}
@@ -1,14 +1,82 @@
 use anyhow::Result;
-use rocksdb::{Options, DB};
+use rocksdb::{MergeOperands, Options, DB};
 use std::sync::Arc;
 use tracing::info;
 
-use crate::config::Config;
+use crate::config::{Config, RunMode};
+use crate::db::kv::{KvOp, KvStore};
+use crate::db::locks::ShardedLocks;
+
+/// 计数器键前缀 / Counter key prefix
+///
+/// 目前只有成交笔数使用合并计数器, 其余计数不适合只能累加 u64 的合并算子:
+/// Only the trade counts use merge counters so far; the other counts do not fit a merge operator that can only add u64s:
+/// - get_db_stats 的事件/索引数量来自全库扫描, 回滚和裁剪时必须减少
+///   get_db_stats event/index counts come from a full scan and must shrink on rollback and pruning
+/// - PnlSummary 的盈亏有符号且带 last_slot 最大值, 已在用户锁内随事件批次写入
+///   PnlSummary PnL is signed and carries a last_slot maximum; it is already written with the event batch under the user lock
+/// - 订单簿 order_id_counter 分配订单ID时必须读出新值, 且位于订单簿数据库的 header 中
+///   The order book order_id_counter must be read back to assign order ids, and lives in the order book DB's header
+pub const COUNTER_PREFIX: &str = "counter:";
+
+/// 计数器合并: 把 u64 小端增量累加到已有值上 (饱和加法)
+/// Counter merge: add little-endian u64 deltas onto the existing value (saturating)
+///
+/// 只有计数器键会执行 merge, 因此对整个键空间注册是安全的
+/// Only counter keys are ever merged, so registering it for the whole key space is safe
+fn counter_merge(_key: &[u8], existing: Option<&[u8]>, operands: &MergeOperands) -> Option<Vec<u8>> {
+    let total = operands
+        .iter()
+        .fold(existing.map_or(0, decode_counter), |acc, delta| acc.saturating_add(decode_counter(delta)));
+    Some(total.to_le_bytes().to_vec())
+}
+
+/// 解码计数器值, 长度不对时视为0 / Decode a counter value, treated as 0 when the length is wrong
+pub(crate) fn decode_counter(bytes: &[u8]) -> u64 {
+    bytes.try_into().map(u64::from_le_bytes).unwrap_or(0)
+}
+
+/// 为 Options 注册计数器合并算子, 打开数据库前调用 / Register the counter merge operator, call before opening the DB
+pub fn register_counter_merge(opts: &mut Options) {
+    opts.set_merge_operator_associative("counter_add", counter_merge);
+}
+
+/// 计数器完整键 / Full counter key: counter:{name}
+fn counter_key(name: &str) -> String {
+    format!("{}{}", COUNTER_PREFIX, name)
+}
+
+/// 原子累加计数器 / Atomically increment a counter
+pub fn incr_counter(db: &DB, name: &str, delta: u64) -> Result<()> {
+    db.merge(counter_key(name).as_bytes(), delta.to_le_bytes())?;
+    Ok(())
+}
+
+/// 在批量操作中累加计数器, 与其余写入一起原子提交 / Increment a counter inside a batch, committed atomically with the other writes
+pub fn merge_counter(ops: &mut Vec<KvOp>, name: &str, delta: u64) {
+    ops.push(KvOp::Merge(counter_key(name).into_bytes(), delta.to_le_bytes().to_vec()));
+}
+
+/// 在批量操作中把计数器重置为指定值, 用于回填 / Reset a counter to a value inside a batch, used by backfills
+pub fn set_counter(ops: &mut Vec<KvOp>, name: &str, value: u64) {
+    ops.push(KvOp::Put(counter_key(name).into_bytes(), value.to_le_bytes().to_vec()));
+}
+
+/// 读取计数器, 不存在时为0 / Read a counter, 0 when missing
+pub fn read_counter(db: &dyn KvStore, name: &str) -> Result<u64> {
+    Ok(db.get(counter_key(name).as_bytes())?.map_or(0, |v| decode_counter(&v)))
+}
 
 /// RocksDB 存储服务
 pub struct RocksDbStorage {
     pub(crate) db: Arc<DB>,
     config: Config,
+    /// 以 secondary 只读打开 (replica 模式) / Opened read-only as a secondary (replica mode)
+    secondary: bool,
+    /// mint 分片锁, 由本库创建的所有 TokenStorage 共享 / Mint shard locks shared by every TokenStorage created from this DB
+    mint_locks: Arc<ShardedLocks>,
+    /// 用户分片锁, 由本库创建的所有 EventStorage 共享 / User shard locks shared by every EventStorage created from this DB
+    user_locks: Arc<ShardedLocks>,
 }
 
 impl RocksDbStorage {
@@ -72,19 +140,43 @@ impl RocksDbStorage {
         // 10. Optimize memory allocation
         opts.set_arena_block_size(64 * 1024 * 1024); // 64MB arena blocks
 
-        let db = DB::open(&opts, &config.database.rocksdb_path)?;
+        // 11. 计数器合并算子 / Counter merge operator
+        register_counter_merge(&mut opts);
 
-        info!(
-            "🗄️ RocksDB initialized successfully, path: {}",
-            config.database.rocksdb_path
-        );
+        let secondary = config.mode == RunMode::Replica;
+        let db = if secondary {
+            // secondary 要求 max_open_files = -1, 上面已设置 / Secondaries require max_open_files = -1, set above
+            let secondary_dir = config.replica.secondary_dir("event");
+            let db = DB::open_as_secondary(&opts, &config.database.rocksdb_path, &secondary_dir)?;
+            info!(
+                "🗄️ RocksDB opened as read-only secondary, primary: {}, secondary: {}",
+                config.database.rocksdb_path, secondary_dir
+            );
+            db
+        } else {
+            let db = DB::open(&opts, &config.database.rocksdb_path)?;
+            info!(
+                "🗄️ RocksDB initialized successfully, path: {}",
+                config.database.rocksdb_path
+            );
+            db
+        };
 
         Ok(Self {
             db: Arc::new(db),
             config: config.clone(),
+            secondary,
+            mint_locks: Arc::new(ShardedLocks::default()),
+            user_locks: Arc::new(ShardedLocks::default()),
         })
     }
 
+    /// 追赶主实例的最新写入, 仅 secondary 可用 / Catch up with the primary's latest writes, secondaries only
+    pub fn catch_up_with_primary(&self) -> Result<()> {
+        self.db.try_catch_up_with_primary()?;
+        Ok(())
+    }
+
     /// 写入键值对
     pub fn put(&self, key: &str, value: &str) -> Result<()> {
         self.db.put(key.as_bytes(), value.as_bytes())?;
@@ -105,19 +197,129 @@ impl RocksDbStorage {
         Ok(())
     }
 
+    /// 原子累加 counter: 命名空间下的计数器 / Atomically increment a counter in the counter: namespace
+    pub fn incr(&self, key: &str, delta: u64) -> Result<()> {
+        incr_counter(&self.db, key, delta)
+    }
+
+    /// 读取 counter: 命名空间下的计数器 / Read a counter in the counter: namespace
+    pub fn get_counter(&self, key: &str) -> Result<u64> {
+        read_counter(self.db.as_ref(), key)
+    }
+
+    /// 健康检查: 写入、读回并删除探测键; secondary 只读, 只做一次读取
+    /// Health check: write, read back and delete a probe key; a read-only secondary only does a read
+    pub fn health_check(&self) -> Result<()> {
+        const PROBE_KEY: &str = "health:probe";
+        if self.secondary {
+            self.get(PROBE_KEY)?;
+            return Ok(());
+        }
+        let value = chrono::Utc::now().timestamp_millis().to_string();
+        self.put(PROBE_KEY, &value)?;
+        let read_back = self.get(PROBE_KEY)?;
+        self.delete(PROBE_KEY)?;
+        if read_back.as_deref() != Some(value.as_str()) {
+            anyhow::bail!("探测键读回不一致 / Probe key read back mismatch");
+        }
+        Ok(())
+    }
+
     /// 获取数据库统计信息
     pub fn get_stats(&self) -> Result<String> {
         let stats = self.db.property_value("rocksdb.stats")?;
         Ok(stats.unwrap_or_else(|| "No stats available".to_string()))
     }
 
+    /// SST 文件总大小 (磁盘占用) / Total SST file size (on-disk usage)
+    pub fn sst_files_size(&self) -> Result<u64> {
+        let size = self.db.property_int_value("rocksdb.total-sst-files-size")?;
+        Ok(size.unwrap_or(0))
+    }
+
+    /// 对整个键空间执行手动压缩, 回收已删除数据占用的空间 (阻塞, 耗时可能较长)
+    /// Run a manual compaction over the whole key space to reclaim space of deleted data (blocking, may take a while)
+    pub fn compact(&self) {
+        self.db.compact_range(None::<&[u8]>, None::<&[u8]>);
+    }
+
+    /// 数据库配置 / Database config
+    pub fn database_config(&self) -> &crate::config::DatabaseConfig {
+        &self.config.database
+    }
+
     /// 创建事件存储实例 / Create event storage instance
     pub fn create_event_storage(&self) -> Result<crate::db::EventStorage> {
-        crate::db::EventStorage::new(Arc::clone(&self.db))
+        crate::db::EventStorage::new(Arc::clone(&self.db), Arc::clone(&self.user_locks))
     }
 
     /// 创建 Token 存储实例 / Create Token storage instance
     pub fn create_token_storage(&self) -> Result<crate::db::TokenStorage> {
-        crate::db::TokenStorage::new(Arc::clone(&self.db), self.config.clone())
+        crate::db::TokenStorage::new(self.db.clone(), self.config.clone(), Arc::clone(&self.mint_locks))
+    }
+
+    /// 创建曲线状态存储实例 / Create curve state storage instance
+    pub fn create_curve_storage(&self) -> crate::db::CurveStorage {
+        // 方法调用形式先确定 Arc<DB>, 再转换为 Arc<dyn KvStore> / Method-call form fixes Arc<DB> first, then coerces to Arc<dyn KvStore>
+        crate::db::CurveStorage::new(self.db.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_db() -> (Arc<DB>, std::path::PathBuf) {
+        let path = std::env::temp_dir().join(format!("counter_test_{}", uuid::Uuid::new_v4()));
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        register_counter_merge(&mut opts);
+        (Arc::new(DB::open(&opts, &path).unwrap()), path)
+    }
+
+    #[test]
+    fn test_concurrent_incr_is_not_lost() {
+        let (db, path) = temp_db();
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let db = Arc::clone(&db);
+                std::thread::spawn(move || {
+                    for _ in 0..500 {
+                        incr_counter(&db, "trades", 2).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(read_counter(db.as_ref(), "trades").unwrap(), 8 * 500 * 2);
+        assert_eq!(read_counter(db.as_ref(), "missing").unwrap(), 0);
+
+        drop(db);
+        std::fs::remove_dir_all(path).ok();
+    }
+
+    #[test]
+    fn test_batch_merge_commits_with_batch() {
+        let (db, path) = temp_db();
+        incr_counter(&db, "volume", 10).unwrap();
+
+        let mut ops = Vec::new();
+        merge_counter(&mut ops, "volume", 5);
+        merge_counter(&mut ops, "volume", 5);
+        assert_eq!(read_counter(db.as_ref(), "volume").unwrap(), 10);
+        KvStore::write(db.as_ref(), ops).unwrap();
+        assert_eq!(read_counter(db.as_ref(), "volume").unwrap(), 20);
+
+        // 合并结果落盘后仍然正确 / Merged result survives a flush and compaction
+        db.flush().unwrap();
+        db.compact_range(None::<&[u8]>, None::<&[u8]>);
+        assert_eq!(read_counter(db.as_ref(), "volume").unwrap(), 20);
+
+        drop(db);
+        std::fs::remove_dir_all(path).ok();
     }
 }
@@ -1,16 +1,32 @@
 // Token存储模块 - 代币列表键值存储系统
 // Token storage module - Token list key-value storage system
+//
+// 并发安全: token:{mint} 和 price:{mint} 的所有读-改-写 (upsert / record_trade / update_token_fees / update_price) 都先获取该 mint 的分片锁,
+// 同一 mint 的更新串行执行, 不同分片的 mint 互不阻塞; 分片锁由 RocksDbStorage 持有, 所有 TokenStorage 实例共享;
+// 成交笔数用 counter: 命名空间的合并算子累加, 读取时填入 trade_count
+// Concurrency: every read-modify-write of token:{mint} and price:{mint} (upsert / record_trade / update_token_fees /
+// update_price) first takes the mint's shard lock, so updates to one mint are serialized while mints in other shards
+// proceed; the shard locks are owned by RocksDbStorage and shared by every TokenStorage instance; trade counts are added
+// through the counter: namespace merge operator and filled into trade_count on read
+//
+// 规范最新价格: price:{mint} 保存最近一个带 latest_price 事件的价格、slot 和时间, 只随更新的 slot 前进,
+// 报价、强平预估和K线快照都从这里读取, 避免各处价格不一致
+// Canonical latest price: price:{mint} holds the price, slot and time of the newest event carrying latest_price and
+// only moves forward with newer slots; quotes, liquidation estimates and K-line snapshots all read it so prices agree
 
 use crate::config::Config;
+use crate::db::kv::{KvDirection, KvOp, KvStore};
+use crate::db::locks::ShardedLocks;
+use crate::db::storage::{merge_counter, read_counter, set_counter};
 
 use crate::solana::events::TokenCreatedEvent;
 use anyhow::Result;
 use chrono::Utc;
-use rocksdb::{WriteBatch, DB};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use serde_with::{serde_as, DisplayFromStr};
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, MutexGuard};
 use std::time::Duration;
 use tokio::time::sleep;
 use tracing::{debug, error, info, warn};
@@ -42,6 +58,14 @@ pub struct TokenDetail {
     pub created_at: i64,                    // 创建时间Unix时间戳 / Creation Unix timestamp
     pub created_slot: u64,                  // 创建时的slot / Creation slot
     pub updated_at: i64,                    // 最后更新时间 / Last update timestamp
+    #[serde(default)]
+    pub last_seen_slot: u64,                // 最后一次 upsert 的slot / Slot of the last upsert
+    #[serde(default)]
+    pub first_trade_time: Option<i64>,      // 首笔成交时间Unix时间戳 / First trade Unix timestamp
+    #[serde(default)]
+    pub last_trade_time: Option<i64>,       // 最近成交时间Unix时间戳 / Last trade Unix timestamp
+    #[serde(default)]
+    pub trade_count: u64,                   // 累计成交笔数, 读取时来自计数器 / Total trade count, read from its counter
 
     // ===== URI 解析数据 / URI Parsed Data =====
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -98,16 +122,38 @@ pub struct TokenStats {
     pub circulating_supply: Option<String>, // 流通供应量 / Circulating supply (u128 as string)
 }
 
+/// 规范最新价格记录 / Canonical latest price record
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PriceRecord {
+    /// 最新价格 / Latest price (u128 as string)
+    #[serde_as(as = "DisplayFromStr")]
+    #[schema(value_type = String)]
+    pub price: u128,
+    /// 产生该价格的事件 slot / Slot of the event that set the price
+    pub slot: u64,
+    /// 产生该价格的事件时间(秒) / Time of the event that set the price (seconds)
+    pub timestamp: i64,
+}
+
+/// 全局成交笔数的计数器名 / Counter name of the global trade count
+const TOTAL_TRADES_COUNTER: &str = "trades_total";
+
+/// 成交笔数回填迁移完成标记 / Marker set once the trade count backfill migration has run
+const TRADE_COUNT_BACKFILL_MARKER: &str = "token_migration:trade_count_backfill";
+
 /// Token存储管理器 / Token storage manager
 pub struct TokenStorage {
-    db: Arc<DB>,
+    db: Arc<dyn KvStore>,
     config: Config,
     http_client: reqwest::Client,
+    /// 与同库其他 TokenStorage 实例共享的 mint 分片锁 / Mint shard locks shared with the other TokenStorage instances of the DB
+    mint_locks: Arc<ShardedLocks>,
 }
 
 impl TokenStorage {
     /// 创建新的Token存储管理器 / Create new token storage manager
-    pub fn new(db: Arc<DB>, config: Config) -> Result<Self> {
+    pub fn new(db: Arc<dyn KvStore>, config: Config, mint_locks: Arc<ShardedLocks>) -> Result<Self> {
         let timeout = Duration::from_secs(config.ipfs.request_timeout_seconds);
         let http_client = reqwest::Client::builder()
             .timeout(timeout)
@@ -117,16 +163,17 @@ impl TokenStorage {
             db,
             config,
             http_client,
+            mint_locks,
         })
     }
 
-    /// 从TokenCreatedEvent保存Token / Save token from TokenCreatedEvent
-    pub async fn save_token_from_event(&self, event: &TokenCreatedEvent) -> Result<()> {
-        info!(
-            "保存Token详情 / Saving token detail: mint={}, symbol={}",
-            event.mint_account, event.symbol
-        );
+    /// 获取 mint 所在分片的锁 / Lock the shard a mint belongs to
+    fn lock_mint(&self, mint: &str) -> MutexGuard<'_, ()> {
+        self.mint_locks.lock(mint)
+    }
 
+    /// 从TokenCreatedEvent构建Token详情 (含IPFS元数据) / Build token detail from TokenCreatedEvent (with IPFS metadata)
+    pub async fn build_token_detail(&self, event: &TokenCreatedEvent) -> TokenDetail {
         let now = Utc::now().timestamp();
 
         // 构建TokenDetail / Build TokenDetail
@@ -151,6 +198,10 @@ impl TokenStorage {
             created_at: event.timestamp.timestamp(),
             created_slot: event.slot,
             updated_at: now,
+            last_seen_slot: event.slot,
+            first_trade_time: None,
+            last_trade_time: None,
+            trade_count: 0,
             uri_data: None,
             stats: None,
             extras: HashMap::new(),
@@ -173,53 +224,96 @@ impl TokenStorage {
             }
         }
 
-        // 原子写入所有索引 / Atomic write all indexes
-        self.save_token_with_indexes(&detail)?;
+        detail
+    }
+
+    /// 已存储Token的最新slot (created_slot 与 last_seen_slot 取大), 不存在时为空
+    /// Latest slot of a stored token (max of created_slot and last_seen_slot), None when missing
+    pub fn seen_slot(&self, mint: &str) -> Result<Option<u64>> {
+        Ok(self
+            .get_token_by_mint(mint)?
+            .map(|t| t.created_slot.max(t.last_seen_slot)))
+    }
+
+    /// 幂等写入Token: 仅当 `slot` 比已存储的 slot 更新时写入, 返回是否写入
+    /// Idempotent token write: only writes when `slot` is newer than the stored slot, returns whether it wrote
+    ///
+    /// 覆盖时保留已有的 uri_data / stats (传入为空时) 和 extras, 并清理过期的索引键
+    /// When overwriting, keeps existing uri_data / stats (if the incoming ones are empty) and extras, and removes stale index keys
+    pub fn upsert(&self, detail: TokenDetail, slot: u64) -> Result<bool> {
+        self.write_token(detail, slot, false)
+    }
+
+    /// 重处理写入Token: 跳过 slot 去重检查, 用于修正解析错误后覆盖历史数据
+    /// Reprocess token write: bypasses the slot dedup check, used to overwrite history after a parsing fix
+    ///
+    /// last_seen_slot 不会回退, 之后的实时事件仍按原有 slot 去重
+    /// last_seen_slot never moves backwards, so later live events still dedup against the original slot
+    pub fn overwrite(&self, detail: TokenDetail, slot: u64) -> Result<bool> {
+        self.write_token(detail, slot, true)
+    }
+
+    fn write_token(&self, mut detail: TokenDetail, slot: u64, allow_overwrite: bool) -> Result<bool> {
+        let _guard = self.lock_mint(&detail.mint_account);
+        let existing = self.get_token_by_mint(&detail.mint_account)?;
+        let mut last_seen_slot = slot;
+
+        if let Some(ref existing) = existing {
+            let seen = existing.created_slot.max(existing.last_seen_slot);
+            if allow_overwrite {
+                last_seen_slot = last_seen_slot.max(seen);
+            } else if slot <= seen {
+                debug!(
+                    "跳过旧的Token写入 / Skipping stale token write: mint={}, slot={}, seen_slot={}",
+                    detail.mint_account, slot, seen
+                );
+                return Ok(false);
+            }
+
+            if detail.uri_data.is_none() {
+                detail.uri_data = existing.uri_data.clone();
+            }
+            if detail.stats.is_none() {
+                detail.stats = existing.stats.clone();
+            }
+            for (key, value) in &existing.extras {
+                detail.extras.entry(key.clone()).or_insert_with(|| value.clone());
+            }
+            // 成交时间来自交易事件, 与最近成交索引保持一致 / Trade times come from trade events and must match the last-trade index
+            detail.first_trade_time = existing.first_trade_time;
+            detail.last_trade_time = existing.last_trade_time;
+        }
+
+        detail.last_seen_slot = last_seen_slot;
+        self.save_token_with_indexes(&detail, existing.as_ref())?;
 
         info!(
-            "Token详情保存成功 / Token detail saved successfully: mint={}",
-            event.mint_account
+            "Token详情保存成功 / Token detail saved successfully: mint={}, slot={}",
+            detail.mint_account, slot
         );
-        Ok(())
+        Ok(true)
     }
 
-    /// 使用WriteBatch原子保存Token及其索引 / Save token with indexes atomically using WriteBatch
-    fn save_token_with_indexes(&self, detail: &TokenDetail) -> Result<()> {
-        let mut batch = WriteBatch::default();
+    /// 原子保存Token及其索引 / Save token with indexes atomically
+    fn save_token_with_indexes(&self, detail: &TokenDetail, previous: Option<&TokenDetail>) -> Result<()> {
+        let mut batch = Vec::new();
+
+        // 0. 删除旧记录的索引 (键不变时会被下面重新写入) / Delete the previous record's indexes (rewritten below if unchanged)
+        if let Some(previous) = previous {
+            for key in Self::index_keys(previous) {
+                batch.push(KvOp::Delete(key.into_bytes()));
+            }
+        }
 
         // 1. 主存储 / Main storage: token:{mint}
         let main_key = format!("token:{}", detail.mint_account);
         let value = serde_json::to_vec(detail)?;
-        batch.put(main_key.as_bytes(), &value);
+        batch.push(KvOp::Put(main_key.into_bytes(), value));
 
-        // 2. Symbol索引 / Symbol index: token_symbol:{SYMBOL}:{mint}
-        let symbol_key = format!(
-            "token_symbol:{}:{}",
-            detail.symbol.to_uppercase(),
-            detail.mint_account
-        );
-        batch.put(symbol_key.as_bytes(), b"");
-
-        // 3. 创建时间索引 / Creation time index: token_created:{timestamp:010}:{mint}
-        let time_key = format!(
-            "token_created:{:010}:{}",
-            detail.created_at, detail.mint_account
-        );
-        batch.put(time_key.as_bytes(), b"");
-
-        // 4. Slot索引 / Slot index: token_slot:{slot:010}:{mint}
-        let slot_key = format!(
-            "token_slot:{:010}:{}",
-            detail.created_slot, detail.mint_account
-        );
-        batch.put(slot_key.as_bytes(), b"");
-
-        // 5. 创建者索引（可选）/ Creator index (optional): token_payer:{payer}:{timestamp:010}:{mint}
-        let payer_key = format!(
-            "token_payer:{}:{:010}:{}",
-            detail.payer, detail.created_at, detail.mint_account
-        );
-        batch.put(payer_key.as_bytes(), b"");
+        // 2-5. Symbol / 创建时间 / Slot / 创建者索引 / Symbol, creation time, slot and creator indexes
+        for key in Self::index_keys(detail) {
+            batch.push(KvOp::Put(key.into_bytes(), Vec::new()));
+        }
 
         // 原子提交 / Atomic commit
         self.db.write(batch)?;
@@ -231,18 +325,183 @@ impl TokenStorage {
         Ok(())
     }
 
+    /// Token的二级索引键 / Secondary index keys of a token
+    fn index_keys(detail: &TokenDetail) -> [String; 4] {
+        [
+            // Symbol索引 / Symbol index: token_symbol:{SYMBOL}:{mint}
+            format!(
+                "token_symbol:{}:{}",
+                detail.symbol.to_uppercase(),
+                detail.mint_account
+            ),
+            // 创建时间索引 / Creation time index: token_created:{timestamp:010}:{mint}
+            format!(
+                "token_created:{:010}:{}",
+                detail.created_at, detail.mint_account
+            ),
+            // Slot索引 / Slot index: token_slot:{slot:010}:{mint}
+            format!(
+                "token_slot:{:010}:{}",
+                detail.created_slot, detail.mint_account
+            ),
+            // 创建者索引 / Creator index: token_payer:{payer}:{timestamp:010}:{mint}
+            format!(
+                "token_payer:{}:{:010}:{}",
+                detail.payer, detail.created_at, detail.mint_account
+            ),
+        ]
+    }
+
+    /// 单个Token成交笔数的计数器名 / Counter name of a token's trade count: trade_count:{mint}
+    fn trade_count_counter(mint: &str) -> String {
+        format!("trade_count:{}", mint)
+    }
+
+    /// 解析存储的Token并填入计数器字段 / Decode a stored token and fill in its counter fields
+    fn decode_token(&self, data: &[u8]) -> Result<TokenDetail> {
+        let mut detail: TokenDetail = serde_json::from_slice(data)?;
+        detail.trade_count = read_counter(self.db.as_ref(), &Self::trade_count_counter(&detail.mint_account))?;
+        Ok(detail)
+    }
+
+    /// 一次性迁移: 用 `count_trades` (按 mint 统计成交事件) 重置每个Token及全局的成交笔数计数器
+    /// One-time migration: reset every token's and the global trade count counters from `count_trades` (trade events per mint)
+    ///
+    /// 计数器引入前的Token成交笔数偏少, 回填后写入完成标记, 再次调用返回 None; 须在事件监听器启动前调用
+    /// Tokens older than the counters are undercounted; a marker is written after the backfill so later calls return None.
+    /// Must run before the event listener starts
+    pub fn backfill_trade_counts(&self, count_trades: impl Fn(&str) -> Result<u64>) -> Result<Option<u64>> {
+        if self.db.get(TRADE_COUNT_BACKFILL_MARKER.as_bytes())?.is_some() {
+            return Ok(None);
+        }
+
+        let mut batch = Vec::new();
+        let mut backfilled = 0u64;
+        let mut total_trades = 0u64;
+        let mut cursor = None;
+        loop {
+            let (tokens, next_cursor) = self.iter_all(cursor, 500)?;
+            for token in &tokens {
+                let count = count_trades(&token.mint_account)?;
+                set_counter(&mut batch, &Self::trade_count_counter(&token.mint_account), count);
+                total_trades += count;
+                backfilled += 1;
+            }
+            match next_cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+
+        set_counter(&mut batch, TOTAL_TRADES_COUNTER, total_trades);
+        batch.push(KvOp::Put(
+            TRADE_COUNT_BACKFILL_MARKER.as_bytes().to_vec(),
+            Utc::now().timestamp().to_string().into_bytes(),
+        ));
+        self.db.write(batch)?;
+
+        info!(
+            "✅ 成交笔数回填完成 / Trade count backfill complete: tokens={}, total_trades={}",
+            backfilled, total_trades
+        );
+        Ok(Some(backfilled))
+    }
+
+    /// 把一个Token的成交笔数重置为 `count` (重处理后按事件重新统计), 全局计数按差值调整, 返回原值
+    /// Reset one token's trade count to `count` (recounted from events after a reprocess), adjusting the global count
+    /// by the difference; returns the previous value
+    ///
+    /// 计数器只能合并增量, 减少时以读取-写回调整全局计数, 与并发的成交合并之间可能丢失极少量计数
+    /// Counters only merge increments, so a decrease adjusts the global count with a read and write back, which may
+    /// lose a handful of counts to concurrently merged trades
+    pub fn reset_trade_count(&self, mint: &str, count: u64) -> Result<u64> {
+        let _guard = self.lock_mint(mint);
+        let previous = read_counter(self.db.as_ref(), &Self::trade_count_counter(mint))?;
+        let mut batch = Vec::new();
+        set_counter(&mut batch, &Self::trade_count_counter(mint), count);
+        if count > previous {
+            merge_counter(&mut batch, TOTAL_TRADES_COUNTER, count - previous);
+        } else if count < previous {
+            let total = read_counter(self.db.as_ref(), TOTAL_TRADES_COUNTER)?;
+            set_counter(&mut batch, TOTAL_TRADES_COUNTER, total.saturating_sub(previous - count));
+        }
+        self.db.write(batch)?;
+        Ok(previous)
+    }
+
+    /// 全部Token的累计成交笔数 / Total trade count across all tokens
+    pub fn total_trade_count(&self) -> Result<u64> {
+        read_counter(self.db.as_ref(), TOTAL_TRADES_COUNTER)
+    }
+
+    /// 规范最新价格键 / Canonical latest price key: price:{mint}
+    fn price_key(mint: &str) -> String {
+        format!("price:{}", mint)
+    }
+
+    /// 读取规范最新价格 / Read the canonical latest price
+    pub fn get_price(&self, mint: &str) -> Result<Option<PriceRecord>> {
+        match self.db.get(Self::price_key(mint).as_bytes())? {
+            Some(data) => Ok(Some(serde_json::from_slice(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// 更新规范最新价格, 旧 slot 的事件不会覆盖更新的价格, 返回是否写入
+    /// Update the canonical latest price; events from older slots never overwrite a newer price, returns whether it wrote
+    pub fn update_price(&self, mint: &str, price: u128, slot: u64, timestamp: i64) -> Result<bool> {
+        let _guard = self.lock_mint(mint);
+        let mut batch = Vec::new();
+        let written = self.stage_price(&mut batch, mint, price, slot, timestamp)?;
+        self.db.write(batch)?;
+        Ok(written)
+    }
+
+    /// 将价格更新加入批次, 调用方须持有 mint 锁; 同一 slot 内后处理的事件覆盖先处理的
+    /// Stage a price update into the batch, the caller must hold the mint lock; within one slot the later event wins
+    fn stage_price(
+        &self,
+        batch: &mut Vec<KvOp>,
+        mint: &str,
+        price: u128,
+        slot: u64,
+        timestamp: i64,
+    ) -> Result<bool> {
+        if matches!(self.get_price(mint)?, Some(current) if current.slot > slot) {
+            return Ok(false);
+        }
+        let record = PriceRecord { price, slot, timestamp };
+        batch.push(KvOp::Put(Self::price_key(mint).into_bytes(), serde_json::to_vec(&record)?));
+        Ok(true)
+    }
+
+    /// 最近成交索引键 / Last-trade index key: token_last_trade:{timestamp:010}:{mint}
+    fn last_trade_key(timestamp: i64, mint: &str) -> String {
+        format!("token_last_trade:{:010}:{}", timestamp, mint)
+    }
+
     /// 根据mint获取Token详情 / Get token by mint
     pub fn get_token_by_mint(&self, mint: &str) -> Result<Option<TokenDetail>> {
         let key = format!("token:{}", mint);
         match self.db.get(key.as_bytes())? {
-            Some(data) => {
-                let detail: TokenDetail = serde_json::from_slice(&data)?;
-                Ok(Some(detail))
-            }
+            Some(data) => Ok(Some(self.decode_token(&data)?)),
             None => Ok(None),
         }
     }
 
+    /// 一次 multi-get 批量读取Token, 不存在的 mint 不出现在结果中
+    /// Read many tokens with one multi-get; unknown mints are left out of the result
+    pub fn get_tokens_batch(&self, mints: &[String]) -> Result<HashMap<String, TokenDetail>> {
+        let keys: Vec<Vec<u8>> = mints.iter().map(|mint| format!("token:{}", mint).into_bytes()).collect();
+        let mut tokens = HashMap::with_capacity(mints.len());
+        for (mint, value) in mints.iter().zip(self.db.multi_get(&keys)?) {
+            if let Some(data) = value {
+                tokens.insert(mint.clone(), self.decode_token(&data)?);
+            }
+        }
+        Ok(tokens)
+    }
+
     /// 根据symbol查询Token列表 / Get tokens by symbol
     pub fn get_tokens_by_symbol(
         &self,
@@ -257,10 +516,7 @@ impl TokenStorage {
             prefix.clone()
         };
 
-        let iter = self.db.iterator(rocksdb::IteratorMode::From(
-            start_key.as_bytes(),
-            rocksdb::Direction::Forward,
-        ));
+        let iter = self.db.iter_from(start_key.as_bytes(), KvDirection::Forward);
 
         let mut tokens = Vec::new();
         let mut count = 0;
@@ -305,10 +561,7 @@ impl TokenStorage {
             format!("token_created:{:010}:", i64::MAX)
         };
 
-        let iter = self.db.iterator(rocksdb::IteratorMode::From(
-            start_key.as_bytes(),
-            rocksdb::Direction::Reverse,
-        ));
+        let iter = self.db.iter_from(start_key.as_bytes(), KvDirection::Reverse);
 
         let mut tokens = Vec::new();
         let mut count = 0;
@@ -348,10 +601,7 @@ impl TokenStorage {
         let start = format!("token_slot:{:010}:", start_slot);
         let end = format!("token_slot:{:010}:", end_slot + 1);
 
-        let iter = self.db.iterator(rocksdb::IteratorMode::From(
-            start.as_bytes(),
-            rocksdb::Direction::Forward,
-        ));
+        let iter = self.db.iter_from(start.as_bytes(), KvDirection::Forward);
 
         let mut tokens = Vec::new();
 
@@ -468,15 +718,103 @@ impl TokenStorage {
         None
     }
 
+    /// 获取自 since 起有成交的Token, 按最近成交时间倒序 / Get tokens traded since `since`, newest last trade first
+    ///
+    /// before_timestamp 用于分页, 只返回最近成交时间早于它的Token
+    /// before_timestamp is used for paging, only tokens whose last trade is earlier are returned
+    pub fn get_active_tokens(
+        &self,
+        since: i64,
+        limit: usize,
+        before_timestamp: Option<i64>,
+    ) -> Result<Vec<TokenDetail>> {
+        let prefix = "token_last_trade:";
+        let start_key = format!("{}{:010}:", prefix, before_timestamp.unwrap_or(i64::MAX));
+
+        let iter = self.db.iter_from(start_key.as_bytes(), KvDirection::Reverse);
+
+        let mut tokens = Vec::new();
+
+        for item in iter {
+            let (key, _) = item?;
+            let key_str = String::from_utf8_lossy(&key);
+
+            if !key_str.starts_with(prefix) || tokens.len() >= limit {
+                break;
+            }
+
+            // token_last_trade:{timestamp}:{mint}
+            let parts: Vec<&str> = key_str.split(':').collect();
+            if parts.len() < 3 {
+                continue;
+            }
+            match parts[1].parse::<i64>() {
+                Ok(ts) if ts >= since => {}
+                _ => break,
+            }
+            if let Some(detail) = self.get_token_by_mint(parts[2])? {
+                tokens.push(detail);
+            }
+        }
+
+        Ok(tokens)
+    }
+
+    /// 按 mint 顺序分页遍历所有Token / Page through all tokens in mint order
+    ///
+    /// start_after 为上一页返回的游标 (最后一个mint, 不含), 还有下一页时返回新的游标
+    /// start_after is the cursor from the previous page (last mint, exclusive); a new cursor is returned while more pages remain
+    pub fn iter_all(
+        &self,
+        start_after: Option<String>,
+        limit: usize,
+    ) -> Result<(Vec<TokenDetail>, Option<String>)> {
+        let prefix = "token:";
+        let start_key = match &start_after {
+            Some(mint) => format!("{}{}", prefix, mint),
+            None => prefix.to_string(),
+        };
+
+        let iter = self.db.iter_from(start_key.as_bytes(), KvDirection::Forward);
+
+        let mut tokens = Vec::new();
+        let mut has_more = false;
+
+        for item in iter {
+            let (key, value) = item?;
+            let key_str = String::from_utf8_lossy(&key);
+
+            if !key_str.starts_with(prefix) {
+                break;
+            }
+            // 游标本身在上一页已返回 / The cursor itself was returned on the previous page
+            if start_after.is_some() && key_str == start_key {
+                continue;
+            }
+
+            if tokens.len() >= limit {
+                has_more = true;
+                break;
+            }
+
+            tokens.push(self.decode_token(&value)?);
+        }
+
+        let next_cursor = if has_more {
+            tokens.last().map(|t| t.mint_account.clone())
+        } else {
+            None
+        };
+
+        Ok((tokens, next_cursor))
+    }
+
     /// 获取Token总数统计 / Get token count statistics
     pub fn get_token_count(&self) -> Result<u64> {
         let prefix = "token:";
         let mut count = 0u64;
 
-        let iter = self.db.iterator(rocksdb::IteratorMode::From(
-            prefix.as_bytes(),
-            rocksdb::Direction::Forward,
-        ));
+        let iter = self.db.iter_from(prefix.as_bytes(), KvDirection::Forward);
 
         for item in iter {
             let (key, _) = item?;
@@ -492,32 +830,57 @@ impl TokenStorage {
         Ok(count)
     }
 
-    /// 更新Token的latest_price / Update token's latest_price
-    pub fn update_token_price(&self, mint: &str, latest_price: u128) -> Result<()> {
+    /// 记录一笔成交: 更新latest_price、规范最新价格、成交笔数、首笔/最近成交时间及最近成交索引
+    /// Record a trade: update latest_price, the canonical latest price, trade count, first/last trade time and the last-trade index
+    ///
+    /// 事件可能乱序到达, 首笔成交取最小时间, 最近成交取最大时间
+    /// Events may arrive out of order, so the first trade keeps the earliest time and the last trade the latest
+    pub fn record_trade(&self, mint: &str, latest_price: u128, slot: u64, trade_time: i64) -> Result<()> {
+        let _guard = self.lock_mint(mint);
         let key = format!("token:{}", mint);
 
         // 读取现有Token详情 / Read existing token detail
         match self.db.get(key.as_bytes())? {
             Some(data) => {
                 let mut detail: TokenDetail = serde_json::from_slice(&data)?;
+                let mut batch = Vec::new();
 
-                // 更新价格和时间戳 / Update price and timestamp
-                detail.latest_price = latest_price.to_string();
+                // latest_price 与规范价格同步前进 / latest_price advances together with the canonical price
+                if self.stage_price(&mut batch, mint, latest_price, slot, trade_time)? {
+                    detail.latest_price = latest_price.to_string();
+                }
                 detail.updated_at = Utc::now().timestamp();
+                detail.first_trade_time = Some(
+                    detail.first_trade_time.map_or(trade_time, |t| t.min(trade_time)),
+                );
+
+                // 最近成交时间前移时替换索引 / Replace the index when the last trade time moves forward
+                if !matches!(detail.last_trade_time, Some(t) if t >= trade_time) {
+                    if let Some(previous) = detail.last_trade_time {
+                        batch.push(KvOp::Delete(Self::last_trade_key(previous, mint).into_bytes()));
+                    }
+                    batch.push(KvOp::Put(Self::last_trade_key(trade_time, mint).into_bytes(), Vec::new()));
+                    detail.last_trade_time = Some(trade_time);
+                }
+
+                // 规范价格、成交笔数与Token记录一起原子提交 / The canonical price and trade counts commit atomically with the token record
+                merge_counter(&mut batch, &Self::trade_count_counter(mint), 1);
+                merge_counter(&mut batch, TOTAL_TRADES_COUNTER, 1);
 
                 // 写回数据库 / Write back to database
                 let value = serde_json::to_vec(&detail)?;
-                self.db.put(key.as_bytes(), &value)?;
+                batch.push(KvOp::Put(key.into_bytes(), value));
+                self.db.write(batch)?;
 
                 debug!(
-                    "Token价格已更新 / Token price updated: mint={}, latest_price={}",
-                    mint, latest_price
+                    "Token成交已记录 / Token trade recorded: mint={}, latest_price={}, trade_time={}",
+                    mint, latest_price, trade_time
                 );
                 Ok(())
             }
             None => {
                 warn!(
-                    "无法更新价格，Token不存在 / Cannot update price, token not found: mint={}",
+                    "无法记录成交，Token不存在 / Cannot record trade, token not found: mint={}",
                     mint
                 );
                 // 不抛出错误，因为可能事件到达顺序不同 / Don't throw error, events may arrive out of order
@@ -534,6 +897,7 @@ impl TokenStorage {
         borrow_fee: u16,
         fee_discount_flag: u8,
     ) -> Result<()> {
+        let _guard = self.lock_mint(mint);
         let key = format!("token:{}", mint);
 
         // 读取现有Token详情 / Read existing token detail
@@ -568,3 +932,202 @@ impl TokenStorage {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::kv::MemoryStore;
+    use rocksdb::{Options, DB};
+
+    fn temp_storage() -> (Arc<TokenStorage>, std::path::PathBuf) {
+        let path = std::env::temp_dir().join(format!("token_storage_test_{}", uuid::Uuid::new_v4()));
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        crate::db::storage::register_counter_merge(&mut opts);
+        let db = DB::open(&opts, &path).unwrap();
+        (Arc::new(TokenStorage::new(Arc::new(db), crate::config::test_config(), Arc::new(ShardedLocks::default())).unwrap()), path)
+    }
+
+    fn token(mint: &str) -> TokenDetail {
+        TokenDetail {
+            payer: "Payer".to_string(),
+            mint_account: mint.to_string(),
+            curve_account: String::new(),
+            pool_token_account: String::new(),
+            pool_sol_account: String::new(),
+            fee_recipient: String::new(),
+            base_fee_recipient: String::new(),
+            params_account: String::new(),
+            swap_fee: 0,
+            borrow_fee: 0,
+            fee_discount_flag: 0,
+            name: "Test".to_string(),
+            symbol: "TST".to_string(),
+            uri: String::new(),
+            up_orderbook: String::new(),
+            down_orderbook: String::new(),
+            latest_price: "1".to_string(),
+            created_at: 0,
+            created_slot: 1,
+            updated_at: 0,
+            last_seen_slot: 1,
+            first_trade_time: None,
+            last_trade_time: None,
+            trade_count: 0,
+            uri_data: None,
+            stats: None,
+            extras: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_concurrent_trades_keep_exact_count() {
+        let (storage, path) = temp_storage();
+        storage.upsert(token("MintA"), 1).unwrap();
+
+        let threads = 8;
+        let trades_per_thread = 200;
+        let handles: Vec<_> = (0..threads)
+            .map(|t| {
+                let storage = Arc::clone(&storage);
+                std::thread::spawn(move || {
+                    for i in 0..trades_per_thread {
+                        let trade_time = (t * trades_per_thread + i) as i64;
+                        storage.record_trade("MintA", 100, trade_time as u64, trade_time).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let detail = storage.get_token_by_mint("MintA").unwrap().unwrap();
+        assert_eq!(detail.trade_count, (threads * trades_per_thread) as u64);
+        assert_eq!(storage.total_trade_count().unwrap(), (threads * trades_per_thread) as u64);
+        assert_eq!(detail.first_trade_time, Some(0));
+        assert_eq!(detail.last_trade_time, Some((threads * trades_per_thread - 1) as i64));
+
+        // 只保留一条最近成交索引 / Only one last-trade index entry remains
+        let active = storage.get_active_tokens(0, 10, None).unwrap();
+        assert_eq!(active.len(), 1);
+
+        drop(storage);
+        std::fs::remove_dir_all(path).ok();
+    }
+
+    #[test]
+    fn test_overwrite_bypasses_slot_dedup() {
+        let (storage, path) = temp_storage();
+        assert!(storage.upsert(token("MintB"), 10).unwrap());
+
+        // 旧 slot 的普通写入被去重 / A normal write at an older slot is deduplicated
+        let mut fixed = token("MintB");
+        fixed.symbol = "FIXED".to_string();
+        assert!(!storage.upsert(fixed.clone(), 5).unwrap());
+
+        // 重处理写入覆盖数据, 但不回退 last_seen_slot / A reprocess write overwrites but keeps last_seen_slot
+        assert!(storage.overwrite(fixed, 5).unwrap());
+        let detail = storage.get_token_by_mint("MintB").unwrap().unwrap();
+        assert_eq!(detail.symbol, "FIXED");
+        assert_eq!(detail.last_seen_slot, 10);
+        assert!(!storage.upsert(token("MintB"), 10).unwrap());
+
+        drop(storage);
+        std::fs::remove_dir_all(path).ok();
+    }
+
+    #[test]
+    fn test_get_tokens_batch_omits_unknown_mints() {
+        let (storage, path) = temp_storage();
+        storage.upsert(token("MintC"), 1).unwrap();
+        storage.upsert(token("MintD"), 1).unwrap();
+        storage.record_trade("MintD", 100, 5, 5).unwrap();
+
+        let mints = vec!["MintC".to_string(), "Unknown".to_string(), "MintD".to_string()];
+        let tokens = storage.get_tokens_batch(&mints).unwrap();
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(tokens["MintC"].trade_count, 0);
+        assert_eq!(tokens["MintD"].trade_count, 1);
+        assert!(!tokens.contains_key("Unknown"));
+
+        drop(storage);
+        std::fs::remove_dir_all(path).ok();
+    }
+
+    #[test]
+    fn test_price_only_moves_forward_by_slot() {
+        let (storage, path) = temp_storage();
+        storage.upsert(token("MintE"), 1).unwrap();
+        assert!(storage.get_price("MintE").unwrap().is_none());
+
+        storage.record_trade("MintE", 200, 20, 1_000).unwrap();
+        // 乱序到达的旧事件不回退价格, 但仍计入成交 / A late older event doesn't roll the price back but still counts
+        storage.record_trade("MintE", 100, 10, 900).unwrap();
+        let price = storage.get_price("MintE").unwrap().unwrap();
+        assert_eq!((price.price, price.slot, price.timestamp), (200, 20, 1_000));
+        assert_eq!(storage.get_token_by_mint("MintE").unwrap().unwrap().latest_price, "200");
+        assert_eq!(storage.get_token_by_mint("MintE").unwrap().unwrap().trade_count, 2);
+
+        // 同一 slot 内后处理的事件生效 / Within one slot the later event wins
+        assert!(storage.update_price("MintE", 300, 20, 1_001).unwrap());
+        assert!(!storage.update_price("MintE", 50, 19, 1_002).unwrap());
+        assert_eq!(storage.get_price("MintE").unwrap().unwrap().price, 300);
+
+        drop(storage);
+        std::fs::remove_dir_all(path).ok();
+    }
+
+    #[test]
+    fn test_backfill_trade_counts_runs_once() {
+        let (storage, path) = temp_storage();
+        storage.upsert(token("MintF"), 1).unwrap();
+        storage.upsert(token("MintG"), 1).unwrap();
+        storage.record_trade("MintF", 100, 2, 2).unwrap();
+
+        let counts = |mint: &str| Ok(if mint == "MintF" { 5 } else { 3 });
+        assert_eq!(storage.backfill_trade_counts(counts).unwrap(), Some(2));
+        assert_eq!(storage.get_token_by_mint("MintF").unwrap().unwrap().trade_count, 5);
+        assert_eq!(storage.get_token_by_mint("MintG").unwrap().unwrap().trade_count, 3);
+        assert_eq!(storage.total_trade_count().unwrap(), 8);
+
+        // 回填后新成交继续累加, 再次回填不执行 / New trades keep adding after the backfill, a second backfill is a no-op
+        storage.record_trade("MintG", 100, 3, 3).unwrap();
+        assert_eq!(storage.backfill_trade_counts(|_| Ok(0)).unwrap(), None);
+        assert_eq!(storage.get_token_by_mint("MintG").unwrap().unwrap().trade_count, 4);
+        assert_eq!(storage.total_trade_count().unwrap(), 9);
+
+        drop(storage);
+        std::fs::remove_dir_all(path).ok();
+    }
+
+    #[test]
+    fn test_memory_store_backend() {
+        let storage = TokenStorage::new(
+            Arc::new(MemoryStore::new()),
+            crate::config::test_config(),
+            Arc::new(ShardedLocks::default()),
+        )
+        .unwrap();
+        for (i, mint) in ["MintH", "MintI", "MintJ"].into_iter().enumerate() {
+            let mut detail = token(mint);
+            detail.created_at = i as i64;
+            storage.upsert(detail, 1).unwrap();
+        }
+        storage.record_trade("MintI", 100, 2, 2).unwrap();
+        storage.record_trade("MintI", 100, 3, 3).unwrap();
+
+        // 反向索引扫描和分页游标与 RocksDB 后端一致 / Reverse index scans and paging cursors behave as on RocksDB
+        let latest: Vec<String> = storage.get_latest_tokens(2, None).unwrap().into_iter().map(|t| t.mint_account).collect();
+        assert_eq!(latest, vec!["MintJ", "MintI"]);
+        let (page, cursor) = storage.iter_all(None, 2).unwrap();
+        assert_eq!(page.len(), 2);
+        let (rest, cursor) = storage.iter_all(cursor, 2).unwrap();
+        assert_eq!((rest.len(), cursor), (1, None));
+
+        let tokens = storage.get_tokens_batch(&["MintI".to_string(), "Unknown".to_string()]).unwrap();
+        assert_eq!(tokens["MintI"].trade_count, 2);
+        assert_eq!(storage.total_trade_count().unwrap(), 2);
+        assert_eq!(storage.get_active_tokens(0, 10, None).unwrap().len(), 1);
+    }
+}
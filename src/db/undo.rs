@@ -0,0 +1,384 @@
+// 按 slot 的撤销日志 / Per-slot undo journal
+//
+// 提交写入批次前, 把批次中每个键的旧值和新值作为一条撤销记录写进同一个批次, 与数据原子提交;
+// slot 被分叉丢弃 (orphaned) 时按写入的逆序恢复旧值, slot 终结后删除它的撤销记录
+// Before a write batch is committed, the prior and new value of every key in it are written into the same batch as
+// one undo record, committed atomically with the data; when the slot is orphaned by a fork the prior values are
+// restored in reverse write order, and once the slot is finalized its undo records are deleted
+//
+// 合并 (merge) 操作不会被记录 / Merge operations are not recorded
+
+use anyhow::Result;
+use rocksdb::{Direction, IteratorMode, WriteBatch, WriteBatchIterator, DB};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tracing::warn;
+
+/// 撤销记录键前缀 / Undo record key prefix
+const UNDO_PREFIX: &str = "undo:";
+
+/// 一个键在一次写入前后的值, 不存在为空 / A key's value before and after one write, None when absent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct UndoEntry {
+    key: Vec<u8>,
+    before: Option<Vec<u8>>,
+    after: Option<Vec<u8>>,
+}
+
+/// 收集批次中的写入, 同一个键只保留最后一次 / Collect the writes of a batch, keeping only the last one per key
+#[derive(Default)]
+struct BatchWrites(Vec<(Vec<u8>, Option<Vec<u8>>)>);
+
+impl BatchWrites {
+    fn set(&mut self, key: Box<[u8]>, value: Option<Box<[u8]>>) {
+        let value = value.map(Vec::from);
+        match self.0.iter_mut().find(|(k, _)| **k == *key) {
+            Some((_, existing)) => *existing = value,
+            None => self.0.push((key.into(), value)),
+        }
+    }
+}
+
+impl WriteBatchIterator for BatchWrites {
+    fn put(&mut self, key: Box<[u8]>, value: Box<[u8]>) {
+        self.set(key, Some(value));
+    }
+
+    fn delete(&mut self, key: Box<[u8]>) {
+        self.set(key, None);
+    }
+}
+
+/// 按组撤销的结果 / Outcome of a grouped revert
+#[derive(Debug, Default)]
+pub struct RevertOutcome {
+    /// 恢复的键数 / Keys restored
+    pub restored: usize,
+    /// 因之后的 slot 又修改过而未撤销的组 / Groups left alone because a later slot changed them again
+    pub conflicted: BTreeSet<String>,
+}
+
+/// 撤销日志 / Undo journal
+pub struct UndoJournal {
+    db: Arc<DB>,
+    /// 未显式指定 slot 的写入归属的 slot, 0 表示不记录 / Slot of writes that do not name one, 0 disables recording
+    current_slot: AtomicU64,
+    /// 记录序号, 以启动时间为起点保证跨重启递增 / Record sequence, seeded from the start time so it grows across restarts
+    seq: AtomicU64,
+}
+
+impl UndoJournal {
+    pub fn new(db: Arc<DB>) -> Self {
+        let seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or_default();
+        Self {
+            db,
+            current_slot: AtomicU64::new(0),
+            seq: AtomicU64::new(seed),
+        }
+    }
+
+    fn record_key(slot: u64, seq: u64) -> String {
+        format!("{}{:010}:{:020}", UNDO_PREFIX, slot, seq)
+    }
+
+    fn slot_prefix(slot: u64) -> String {
+        format!("{}{:010}:", UNDO_PREFIX, slot)
+    }
+
+    /// 设置之后写入归属的 slot / Set the slot subsequent writes belong to
+    pub fn begin_slot(&self, slot: u64) {
+        self.current_slot.store(slot, Ordering::Relaxed);
+    }
+
+    /// 已结算的 slot 不再接收之后的写入 / A settled slot no longer takes subsequent writes
+    fn end_slot(&self, slot: u64) {
+        let _ = self.current_slot.compare_exchange(slot, 0, Ordering::Relaxed, Ordering::Relaxed);
+    }
+
+    /// 以当前 slot 记录批次 / Record a batch under the current slot
+    pub fn record_current(&self, batch: &mut WriteBatch) -> Result<()> {
+        match self.current_slot.load(Ordering::Relaxed) {
+            0 => Ok(()),
+            slot => self.record(batch, slot),
+        }
+    }
+
+    /// 把批次中每个键的旧值和新值作为撤销记录追加到同一批次
+    /// Append the prior and new value of every key in the batch to the same batch as an undo record
+    pub fn record(&self, batch: &mut WriteBatch, slot: u64) -> Result<()> {
+        let mut writes = BatchWrites::default();
+        batch.iterate(&mut writes);
+        if writes.0.is_empty() {
+            return Ok(());
+        }
+
+        let mut entries = Vec::with_capacity(writes.0.len());
+        for (key, after) in writes.0 {
+            let before = self.db.get(&key)?;
+            entries.push(UndoEntry { key, before, after });
+        }
+
+        let seq = self.seq.fetch_add(1, Ordering::Relaxed);
+        batch.put(Self::record_key(slot, seq).as_bytes(), serde_json::to_vec(&entries)?);
+        Ok(())
+    }
+
+    /// 读取某个 slot 的撤销记录键和内容, 按写入顺序 / Read a slot's undo record keys and entries, in write order
+    fn slot_records(&self, slot: u64) -> Result<Vec<(Box<[u8]>, Vec<UndoEntry>)>> {
+        let prefix = Self::slot_prefix(slot);
+        let mut records = Vec::new();
+        for item in self.db.iterator(IteratorMode::From(prefix.as_bytes(), Direction::Forward)) {
+            let (key, value) = item?;
+            if !key.starts_with(prefix.as_bytes()) {
+                break;
+            }
+            records.push((key, serde_json::from_slice(&value)?));
+        }
+        Ok(records)
+    }
+
+    /// 撤销某个 slot 的全部写入, 返回恢复的键数; 之后其他 slot 又改过的键保持不变
+    /// Undo every write of a slot, returning how many keys were restored; keys changed again by another slot afterwards are left alone
+    pub fn revert_slot(&self, slot: u64) -> Result<usize> {
+        Ok(self.revert_slot_grouped(slot, |_| None)?.restored)
+    }
+
+    /// 按组撤销某个 slot 的写入: 同一组的键要么全部恢复, 要么 (任一键之后又被其他 slot 修改) 全部保持不变
+    /// Undo a slot's writes group by group: the keys of one group are either all restored or, when any of them was
+    /// changed again by another slot afterwards, all left alone
+    ///
+    /// `group_of` 返回键所属的组 (例如一个订单簿), 为空时键自成一组; 冲突的组在结果中返回, 由调用方标记重建
+    /// `group_of` names the group a key belongs to (an order book, say), None makes the key a group of its own; the
+    /// conflicting groups are returned so the caller can mark them for a rebuild
+    pub fn revert_slot_grouped(&self, slot: u64, group_of: impl Fn(&[u8]) -> Option<String>) -> Result<RevertOutcome> {
+        self.end_slot(slot);
+        let records = self.slot_records(slot)?;
+        let mut outcome = RevertOutcome::default();
+        let mut plan: Vec<(String, &UndoEntry)> = Vec::new();
+
+        // 逆序推演, 同一 slot 内多次写入的键逐步退回最初的值
+        // Walk newest first, so a key written several times within the slot steps back to its original value
+        let mut current: HashMap<Vec<u8>, Option<Vec<u8>>> = HashMap::new();
+        for (_, entries) in records.iter().rev() {
+            for entry in entries.iter().rev() {
+                let group = group_of(&entry.key).unwrap_or_else(|| String::from_utf8_lossy(&entry.key).into_owned());
+                let now = match current.get(&entry.key) {
+                    Some(value) => value.clone(),
+                    None => self.db.get(&entry.key)?,
+                };
+                if now != entry.after {
+                    warn!(
+                        "⚠️ slot {} 之后键又被修改, 所在组不撤销 / Key changed after slot {}, its group is not reverted: {}",
+                        slot,
+                        slot,
+                        String::from_utf8_lossy(&entry.key)
+                    );
+                    outcome.conflicted.insert(group);
+                    continue;
+                }
+                current.insert(entry.key.clone(), entry.before.clone());
+                plan.push((group, entry));
+            }
+        }
+
+        let mut batch = WriteBatch::default();
+        for (group, entry) in plan {
+            if outcome.conflicted.contains(&group) {
+                continue;
+            }
+            match &entry.before {
+                Some(value) => batch.put(&entry.key, value),
+                None => batch.delete(&entry.key),
+            }
+            outcome.restored += 1;
+        }
+        for (record_key, _) in &records {
+            batch.delete(record_key);
+        }
+
+        self.db.write(batch)?;
+        if !outcome.conflicted.is_empty() {
+            warn!(
+                "⚠️ 撤销 slot {} 时有 {} 个组冲突 / {} conflicting group(s) while reverting slot {}",
+                slot,
+                outcome.conflicted.len(),
+                outcome.conflicted.len(),
+                slot
+            );
+        }
+        Ok(outcome)
+    }
+
+    /// 仍有撤销记录的 slot (尚未终结或回滚), 用于重启后恢复待终结集合
+    /// Slots that still have undo records (neither finalized nor rolled back), used to restore the unsettled set after a restart
+    pub fn journaled_slots(&self) -> Result<BTreeSet<u64>> {
+        let mut slots = BTreeSet::new();
+        let mut seek = UNDO_PREFIX.to_string();
+        loop {
+            let mut iter = self.db.iterator(IteratorMode::From(seek.as_bytes(), Direction::Forward));
+            let Some(item) = iter.next() else {
+                break;
+            };
+            let (key, _) = item?;
+            let Some(slot) = key
+                .strip_prefix(UNDO_PREFIX.as_bytes())
+                .and_then(|rest| rest.get(..10))
+                .and_then(|slot| std::str::from_utf8(slot).ok())
+                .and_then(|slot| slot.parse::<u64>().ok())
+            else {
+                break;
+            };
+            slots.insert(slot);
+            // 跳到下一个 slot 的记录 / Skip to the next slot's records
+            seek = format!("{}{:010};", UNDO_PREFIX, slot);
+        }
+        Ok(slots)
+    }
+
+    /// 删除某个 slot 的撤销记录 (slot 已终结) / Delete a slot's undo records (the slot is finalized)
+    pub fn commit_slot(&self, slot: u64) -> Result<usize> {
+        self.end_slot(slot);
+        let records = self.slot_records(slot)?;
+        let mut batch = WriteBatch::default();
+        for (record_key, _) in &records {
+            batch.delete(record_key);
+        }
+        self.db.write(batch)?;
+        Ok(records.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocksdb::Options;
+
+    fn open_db() -> (Arc<DB>, std::path::PathBuf) {
+        let path = std::env::temp_dir().join(format!("undo_journal_{}", uuid::Uuid::new_v4()));
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        (Arc::new(DB::open(&opts, &path).unwrap()), path)
+    }
+
+    fn write(db: &DB, journal: &UndoJournal, slot: u64, puts: &[(&str, &str)], deletes: &[&str]) {
+        let mut batch = WriteBatch::default();
+        for (key, value) in puts {
+            batch.put(key.as_bytes(), value.as_bytes());
+        }
+        for key in deletes {
+            batch.delete(key.as_bytes());
+        }
+        journal.record(&mut batch, slot).unwrap();
+        db.write(batch).unwrap();
+    }
+
+    fn get(db: &DB, key: &str) -> Option<String> {
+        db.get(key.as_bytes()).unwrap().map(|v| String::from_utf8(v).unwrap())
+    }
+
+    #[test]
+    fn test_revert_slot_restores_prior_values() {
+        let (db, path) = open_db();
+        let journal = UndoJournal::new(db.clone());
+
+        write(&db, &journal, 10, &[("a", "1"), ("b", "1")], &[]);
+        write(&db, &journal, 11, &[("a", "2"), ("c", "2")], &["b"]);
+        write(&db, &journal, 11, &[("a", "3")], &[]);
+
+        assert_eq!(journal.revert_slot(11).unwrap(), 4);
+        assert_eq!(get(&db, "a").as_deref(), Some("1"));
+        assert_eq!(get(&db, "b").as_deref(), Some("1"));
+        assert_eq!(get(&db, "c"), None);
+
+        // 撤销记录已删除, 再次撤销无操作 / The undo records are gone, reverting again is a no-op
+        assert_eq!(journal.revert_slot(11).unwrap(), 0);
+        assert_eq!(journal.commit_slot(10).unwrap(), 1);
+        assert_eq!(journal.revert_slot(10).unwrap(), 0);
+        assert_eq!(get(&db, "a").as_deref(), Some("1"));
+
+        drop(journal);
+        drop(db);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_revert_keeps_keys_changed_by_later_slots() {
+        let (db, path) = open_db();
+        let journal = UndoJournal::new(db.clone());
+
+        write(&db, &journal, 20, &[("a", "1"), ("b", "1")], &[]);
+        write(&db, &journal, 21, &[("a", "2")], &[]);
+
+        assert_eq!(journal.revert_slot(20).unwrap(), 1);
+        assert_eq!(get(&db, "a").as_deref(), Some("2"));
+        assert_eq!(get(&db, "b"), None);
+
+        drop(journal);
+        drop(db);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_conflicting_group_is_left_whole() {
+        let (db, path) = open_db();
+        let journal = UndoJournal::new(db.clone());
+        let group_of = |key: &[u8]| key.split(|b| *b == b'.').next().map(|g| String::from_utf8_lossy(g).into_owned());
+
+        write(&db, &journal, 30, &[("book.header", "1"), ("book.slot", "1"), ("other.x", "1")], &[]);
+        write(&db, &journal, 31, &[("book.header", "2")], &[]);
+
+        // book 的 header 被 slot 31 改过, 整本保持不变, 不会只退回一半 / slot 31 changed book's header, so the whole book stays instead of half reverting
+        let outcome = journal.revert_slot_grouped(30, group_of).unwrap();
+        assert_eq!(outcome.restored, 1);
+        assert_eq!(outcome.conflicted, BTreeSet::from(["book".to_string()]));
+        assert_eq!(get(&db, "book.header").as_deref(), Some("2"));
+        assert_eq!(get(&db, "book.slot").as_deref(), Some("1"));
+        assert_eq!(get(&db, "other.x"), None);
+
+        drop(journal);
+        drop(db);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_journaled_slots_lists_each_slot_once() {
+        let (db, path) = open_db();
+        let journal = UndoJournal::new(db.clone());
+
+        write(&db, &journal, 40, &[("a", "1")], &[]);
+        write(&db, &journal, 40, &[("b", "1")], &[]);
+        write(&db, &journal, 42, &[("c", "1")], &[]);
+        assert_eq!(journal.journaled_slots().unwrap(), BTreeSet::from([40, 42]));
+
+        journal.commit_slot(40).unwrap();
+        assert_eq!(journal.journaled_slots().unwrap(), BTreeSet::from([42]));
+
+        drop(journal);
+        drop(db);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_current_slot_zero_records_nothing() {
+        let (db, path) = open_db();
+        let journal = UndoJournal::new(db.clone());
+
+        let mut batch = WriteBatch::default();
+        batch.put(b"a", b"1");
+        journal.record_current(&mut batch).unwrap();
+        assert_eq!(batch.len(), 1);
+
+        journal.begin_slot(5);
+        journal.record_current(&mut batch).unwrap();
+        assert_eq!(batch.len(), 2);
+
+        drop(journal);
+        drop(db);
+        let _ = std::fs::remove_dir_all(path);
+    }
+}
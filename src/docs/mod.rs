@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use utoipa::{OpenApi, ToSchema};
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, SecurityScheme};
+use utoipa::{Modify, OpenApi, ToSchema};
 
 /// API 统一响应格式（用于 Swagger 文档）
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -44,55 +45,174 @@ pub struct EmptyResponse {
 
 /// 错误响应格式（用于 Swagger 文档）
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
-#[schema(title = "ErrorApiResponse", description = "错误响应格式")]
+#[schema(
+    title = "ErrorApiResponse",
+    description = "错误响应格式。code 与 HTTP 状态码一致; error_code 为稳定错误码, 客户端应按它分支而不是解析 msg / Error response. code mirrors the HTTP status; error_code is the stable error code clients should branch on instead of parsing msg\n\n\
+| error_code | 含义 / Meaning |\n\
+|---|---|\n\
+| 1000 | 请求无效 / Invalid request |\n\
+| 1001 | 参数错误 / Invalid parameter |\n\
+| 1002 | 请求体过大 / Payload too large |\n\
+| 1003 | 与当前状态冲突 (如订单簿已满、保留键) / Conflicts with the current state (e.g. full order book, reserved key) |\n\
+| 1004 | 不支持的K线间隔 / Unsupported K-line interval |\n\
+| 1005 | mint 不是合法的 Solana 地址 / Mint is not a valid Solana address |\n\
+| 1006 | 超出每客户端订阅上限 / Per-client subscription limit exceeded |\n\
+| 2000 | 资源不存在 / Resource not found |\n\
+| 3000 | 存储读写失败 / Storage read or write failed |\n\
+| 3001 | 服务端内部错误 / Internal server error |\n\
+| 3002 | 业务规则错误 / Business rule error |\n\
+| 3003 | 服务繁忙, 并发请求已满 / Server busy, concurrent requests saturated |\n\
+| 4000 | 上游 RPC 请求失败 / Upstream RPC request failed |\n\
+| 5000 | 未授权 / Unauthorized |\n\
+| 5001 | 禁止访问 / Forbidden |\n\
+| 5002 | 请求过于频繁 / Rate limited |",
+    example = json!({
+        "code": 404,
+        "msg": "Token not found",
+        "data": null,
+        "error_code": 2000
+    })
+)]
 pub struct ErrorApiResponse {
-    /// 响应状态码：非200表示错误
+    /// 响应状态码：与 HTTP 状态码一致
     pub code: u32,
 
     /// 错误消息
     pub msg: String,
 
+    /// 稳定错误码, 见上表 / Stable error code, see the table above
+    #[schema(example = 2000)]
+    pub error_code: Option<u32>,
+
     /// 错误时数据为空
     pub data: Option<Value>,
 }
 
+/// 注册 API-Key 安全方案 / Register the API-key security scheme
+pub struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new(
+                crate::router::auth::API_KEY_HEADER,
+            ))),
+        );
+    }
+}
+
 /// OpenAPI 文档配置
 #[derive(OpenApi)]
 #[openapi(
+    modifiers(&SecurityAddon),
     paths(
         // 路由函数列表
         crate::router::health::health,
+        crate::router::metrics::get_metrics,
+        crate::router::time::get_time,
         crate::router::db::db_put,
         crate::router::db::db_get,
         crate::router::db::db_delete,
+        crate::router::db::db_compact,
+        crate::router::db::query_decode_errors,
+        crate::router::reprocess::reprocess,
+        crate::router::reprocess::reprocess_status,
+        crate::router::orderbook::rebuild_orderbook,
         crate::router::db::db_stats,
         crate::router::db::db_event_stats,
         crate::router::db::query_events_by_mint,
         crate::router::db::query_events_by_user,
+        crate::router::db::query_user_activity,
         crate::router::db::query_events_by_signature,
+        crate::router::db::query_tx_indexed,
+        crate::router::db::query_user_pnl,
+        crate::router::db::get_event_cursor,
+        crate::router::db::set_event_cursor,
+        crate::router::db::query_events_by_slot_range,
+        crate::router::db::query_recent_trades,
+        crate::router::db::query_whale_trades,
+        crate::router::db::query_liquidations,
         // Token 路由 / Token routes
         crate::router::token::get_token_by_mint,
+        crate::router::token::get_tokens_batch,
         crate::router::token::get_tokens_by_symbol,
         crate::router::token::get_latest_tokens,
+        crate::router::token::get_all_tokens,
+        crate::router::token::get_active_tokens,
         crate::router::token::get_tokens_by_slot_range,
         crate::router::token::get_token_stats,
+        crate::router::token::get_token_summary,
+        crate::router::token::get_token_curve,
+        crate::router::token::get_token_price,
+        crate::router::token::get_swap_quote,
+        crate::router::token::get_liquidation_estimate,
         // OrderBook 路由 / OrderBook routes
         crate::router::orderbook::query_orderbook,
         crate::router::orderbook::get_user_active_orders,
+        crate::router::orderbook::get_user_positions,
+        crate::router::orderbook::get_insert_hint,
+        crate::router::orderbook::get_close_hint,
+        crate::router::orderbook::get_order,
+        crate::router::orderbook::get_order_timeline,
+        crate::router::orderbook::get_expiring_orders,
+        crate::router::orderbook::get_expired_orders,
+        crate::router::orderbook::find_orders_by_open_price,
+        crate::router::orderbook::get_orderbook_depth,
+        crate::router::orderbook::get_orderbook_stats,
+        crate::router::orderbook::get_orderbook_summary,
+        crate::router::orderbook::get_onchain_orderbook,
+        crate::router::orderbook::get_markets,
+        crate::router::orderbook::get_all_orders,
+        crate::router::orderbook::dump_orderbook,
         // OrderBook History 路由 / OrderBook History routes
         crate::router::orderbook_history::get_user_history,
+        crate::router::orderbook_history::get_orderbook_at,
     ),
     components(
         schemas(
             // 响应结构体列表
             crate::router::health::HealthResponse,
+            crate::db::ReplicationReport,
+            crate::router::metrics::MetricsResponse,
+            crate::router::time::TimeResponse,
+            crate::solana::client::ChainClock,
+            crate::solana::client::RpcEndpointHealth,
+            crate::solana::client::RpcRetryMetrics,
+            crate::webhook::WebhookMetrics,
+            crate::solana::DryRunMetrics,
+            crate::solana::FinalityMetrics,
+            crate::kline::KlineConnectionMetrics,
+            crate::kline::KlineAggregatorMetrics,
+            crate::solana::EventQueueMetrics,
             crate::router::db::DbRequest,
             crate::router::db::DbResponse,
+            crate::router::db::CompactResponse,
+            crate::router::db::SetCursorRequest,
+            crate::solana::DecodeErrors,
+            crate::solana::DecodeFailure,
+            crate::router::reprocess::ReprocessRequest,
+            crate::router::reprocess::ReprocessStatus,
+            crate::router::reprocess::ReprocessJobState,
+            crate::solana::ReprocessReport,
             crate::router::db::SortOrder,
+            crate::util::PageInfo,
             crate::router::db::PaginatedEvents,
+            crate::router::db::UserActivity,
+            crate::router::db::UserEventRow,
             crate::router::db::EventList,
+            crate::router::db::TxIndexedStatus,
+            crate::db::EventCursor,
+            crate::router::db::SlotRangeEvents,
+            crate::router::db::RecentTrades,
+            crate::db::event_storage::EventSummary,
+            crate::router::db::Liquidations,
+            crate::db::event_storage::LiquidationRecord,
+            crate::db::PnlSummary,
             crate::db::DatabaseStats,
             crate::db::event_storage::IndexCounts,
+            crate::db::event_storage::DomainStats,
             crate::solana::events::PinpetEvent,
             crate::solana::events::TokenCreatedEvent,
             crate::solana::events::BuySellEvent,
@@ -100,12 +220,22 @@ pub struct ErrorApiResponse {
             crate::solana::events::FullCloseEvent,
             crate::solana::events::PartialCloseEvent,
             crate::solana::events::MilestoneDiscountEvent,
+            crate::solana::events::AddMarginEvent,
+            crate::solana::events::ExtendOrderEvent,
             // Token 结构体 / Token structures
             crate::db::TokenDetail,
             crate::db::TokenUriData,
             crate::db::TokenStats,
             crate::router::token::TokenListResponse,
+            crate::router::token::TokensBatchRequest,
+            crate::router::token::TokensBatchResponse,
             crate::router::token::TokenStatsResponse,
+            crate::db::event_storage::TokenSummary24h,
+            crate::db::CurveState,
+            crate::db::PriceRecord,
+            crate::router::token::TokenPriceResponse,
+            crate::router::token::SwapQuoteResponse,
+            crate::router::token::LiquidationEstimateResponse,
             // OrderBook 结构体 / OrderBook structures
             crate::router::orderbook::OrderBookQueryParams,
             crate::router::orderbook::OrderBookHeaderInfo,
@@ -114,22 +244,76 @@ pub struct ErrorApiResponse {
             crate::router::orderbook::UserActiveOrdersParams,
             crate::router::orderbook::UserActiveOrderItem,
             crate::router::orderbook::UserActiveOrdersResponse,
+            crate::router::orderbook::UserPositionsParams,
+            crate::router::orderbook::UserPositionItem,
+            crate::router::orderbook::UserPositionsResponse,
+            crate::router::orderbook::InsertHintParams,
+            crate::router::orderbook::InsertHintResponse,
+            crate::router::orderbook::CloseHintParams,
+            crate::router::orderbook::CloseHintResponse,
+            crate::router::orderbook::OrderLookupParams,
+            crate::router::orderbook::OrderLookupItem,
+            crate::router::orderbook::OrderTimelineParams,
+            crate::router::orderbook::ExpiringOrdersParams,
+            crate::router::orderbook::ExpiringOrderItem,
+            crate::router::orderbook::ExpiringOrdersResponse,
+            crate::router::orderbook::ExpiredOrdersParams,
+            crate::router::orderbook::ExpiredOrderItem,
+            crate::router::orderbook::ExpiredOrdersResponse,
+            crate::router::orderbook::FindOrdersParams,
+            crate::router::orderbook::FindOrderItem,
+            crate::router::orderbook::FindOrdersResponse,
+            crate::router::orderbook::DepthParams,
+            crate::orderbook::DepthBucket,
+            crate::router::orderbook::OrderBookStatsParams,
+            crate::router::orderbook::OrderBookStatsResponse,
+            crate::router::orderbook::OrderBookSummaryParams,
+            crate::router::orderbook::OrderBookSummaryResponse,
+            crate::router::orderbook::OrderBookDumpParams,
+            crate::orderbook::OrderBookConsistency,
+            crate::router::orderbook::OnchainOrderBookParams,
+            crate::router::orderbook::OnchainOrderBookResponse,
+            crate::router::orderbook::MarketsParams,
+            crate::router::orderbook::MarketsResponse,
+            crate::db::OrderBookMarket,
+            crate::router::orderbook::AllOrdersParams,
+            crate::router::orderbook::AllOrdersResponse,
+            crate::db::GlobalOrderRow,
+            crate::orderbook::OnchainOrderBookHeader,
+            crate::orderbook::OrderTimeline,
+            crate::orderbook::OrderTimelineStep,
+            crate::util::pnl::PositionPnl,
             crate::orderbook::MarginOrder,
             // OrderBook History 结构体 / OrderBook History structures
             crate::router::orderbook_history::HistoryQueryParams,
             crate::router::orderbook_history::ClosedOrdersResponse,
+            crate::router::orderbook_history::OrderBookAtParams,
+            crate::router::orderbook_history::OrderBookAtResponse,
             crate::orderbook::ClosedOrderRecord,
             crate::orderbook::CloseInfo,
+            // K线推送消息结构体 (Socket.IO /kline 命名空间) / K-line push message structures (Socket.IO /kline namespace)
+            crate::kline::types::KlineRealtimeData,
+            crate::kline::types::KlineUpdateMessage,
+            crate::kline::types::KlineHistoryResponse,
+            crate::kline::types::EventUpdateMessage,
+            crate::kline::types::EventHistoryResponse,
+            crate::kline::types::TradeEventMessage,
+            crate::kline::types::SubscribedMessage,
+            crate::kline::types::RejectedInterval,
+            crate::kline::types::TokenCreatedMessage,
+            crate::kline::types::SubscribeErrorMessage,
             EmptyResponse,
             ErrorApiResponse,
         )
     ),
     tags(
         (name = "system", description = "系统相关接口 / System related APIs"),
+        (name = "admin", description = "管理接口, 需要 X-API-Key 请求头 / Admin APIs, require the X-API-Key header"),
         (name = "database", description = "数据库相关接口 / Database related APIs"),
         (name = "events", description = "事件查询接口 / Event query APIs"),
         (name = "tokens", description = "Token代币查询接口 / Token query APIs"),
         (name = "OrderBook", description = "OrderBook保证金订单查询接口 / OrderBook margin order query APIs"),
+        (name = "kline", description = include_str!("kline_socket.md")),
     ),
     info(
         title = "Pinpet Server API",
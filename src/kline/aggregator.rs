@@ -0,0 +1,345 @@
+// K线聚合器 - 在内存中维护 mint 的近期K线 / K-line aggregators - keep recent candles of mints in memory
+//
+// 预热列表中的 mint 启动时即回填并常驻; 其余 mint 在首个订阅时回填, 最后一个订阅者离开后释放,
+// 数量超过上限时按最近更新时间 (LRU) 淘汰, 下次访问时再从事件存储重建
+// Watchlist mints are backfilled at startup and stay resident; other mints are backfilled on their first
+// subscription and released once their last subscriber leaves, and beyond the capacity the least recently
+// updated (LRU) mint is evicted, to be rebuilt from event storage on its next access
+
+use crate::kline::data_processor::{interval_secs, KlineDataProcessor};
+use crate::kline::types::{KlineHistoryResponse, KlineRealtimeData, KLINE_INTERVALS};
+use anyhow::Result;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::sync::RwLock;
+use tracing::{debug, info, warn};
+use utoipa::ToSchema;
+
+/// 每个聚合器保留的K线数, 与订阅快照上限一致 / Candles kept per aggregator, same as the subscribe snapshot cap
+pub const AGGREGATOR_CANDLES: usize = crate::config::MAX_KLINE_SNAPSHOT_CANDLES;
+
+/// 单个 (mint, 间隔) 的K线聚合器 / Candle aggregator of a single (mint, interval)
+pub struct CandleAggregator {
+    bucket: u64,                          // 间隔秒数 / Interval length in seconds
+    candles: VecDeque<KlineRealtimeData>, // 时间升序 / Ascending by time
+}
+
+impl CandleAggregator {
+    /// 以回填的K线 (时间升序) 创建聚合器 / Create an aggregator from backfilled candles (ascending)
+    pub fn new(bucket: u64, seed: Vec<KlineRealtimeData>) -> Self {
+        let mut candles: VecDeque<KlineRealtimeData> = seed.into();
+        while candles.len() > AGGREGATOR_CANDLES {
+            candles.pop_front();
+        }
+        Self { bucket, candles }
+    }
+
+    /// 计入一笔成交, 返回该成交所在的K线; 早于窗口的迟到成交被忽略
+    /// Apply a trade, returning the candle it landed in; late trades older than the window are ignored
+    pub fn apply(&mut self, price: f64, volume: f64, timestamp: u64) -> Option<KlineRealtimeData> {
+        let time = timestamp / self.bucket * self.bucket;
+        let last_time = self.candles.back().map(|c| c.time);
+
+        match last_time {
+            // 迟到成交不改变收盘价 / Late trades do not move the close
+            Some(last) if time < last => {
+                let candle = self.candles.iter_mut().find(|c| c.time == time)?;
+                candle.high = candle.high.max(price);
+                candle.low = candle.low.min(price);
+                candle.volume += volume;
+                candle.update_count += 1;
+                Some(candle.clone())
+            }
+            Some(last) if time == last => {
+                let candle = self.candles.back_mut()?;
+                candle.high = candle.high.max(price);
+                candle.low = candle.low.min(price);
+                candle.close = price;
+                candle.volume += volume;
+                candle.update_count += 1;
+                Some(candle.clone())
+            }
+            _ => {
+                if let Some(previous) = self.candles.back_mut() {
+                    previous.is_final = true;
+                    previous.update_type = "final".to_string();
+                }
+                let candle = KlineRealtimeData {
+                    time,
+                    open: price,
+                    high: price,
+                    low: price,
+                    close: price,
+                    volume,
+                    is_final: false,
+                    update_type: "realtime".to_string(),
+                    update_count: 1,
+                };
+                self.candles.push_back(candle.clone());
+                if self.candles.len() > AGGREGATOR_CANDLES {
+                    self.candles.pop_front();
+                }
+                Some(candle)
+            }
+        }
+    }
+
+    /// 最近 `count` 根K线 (时间升序), 只有覆盖 `now` 的K线未收盘
+    /// The latest `count` candles (ascending), only the candle covering `now` is still open
+    pub fn snapshot(&self, now: u64, count: usize) -> Vec<KlineRealtimeData> {
+        let current = now / self.bucket * self.bucket;
+        let skip = self.candles.len().saturating_sub(count);
+        self.candles
+            .iter()
+            .skip(skip)
+            .cloned()
+            .map(|mut candle| {
+                candle.is_final = candle.time < current;
+                candle.update_type = if candle.is_final { "final" } else { "realtime" }.to_string();
+                candle
+            })
+            .collect()
+    }
+}
+
+/// 单个 mint 的各间隔聚合器及最近更新序号 / All interval aggregators of one mint plus its last update tick
+#[derive(Default)]
+struct MintAggregators {
+    intervals: HashMap<String, CandleAggregator>,
+    last_used: u64,
+}
+
+/// 聚合器表, 序号单调递增用于 LRU / Aggregator table, the tick increases monotonically for LRU
+#[derive(Default)]
+struct AggregatorTable {
+    mints: HashMap<String, MintAggregators>,
+    tick: u64,
+}
+
+impl AggregatorTable {
+    /// 取出 mint 的聚合器并标记为最近更新 / Get the mint's aggregators and mark them most recently updated
+    fn touch(&mut self, mint: &str) -> Option<&mut MintAggregators> {
+        self.tick += 1;
+        let tick = self.tick;
+        let entry = self.mints.get_mut(mint)?;
+        entry.last_used = tick;
+        Some(entry)
+    }
+}
+
+/// K线聚合器指标 (用于 /metrics) / Candle aggregator metrics (for /metrics)
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(title = "KlineAggregatorMetrics", description = "K线聚合器统计 / Candle aggregator metrics")]
+pub struct KlineAggregatorMetrics {
+    /// 驻留内存的 mint 数 / Mints resident in memory
+    pub mints: u64,
+    /// 驻留内存的 (mint, 间隔) 聚合器数 / Resident (mint, interval) aggregators
+    pub aggregators: u64,
+    /// 预热列表 mint 数 (不参与淘汰) / Watchlist mints (never evicted)
+    pub watchlist_mints: u64,
+    /// 因超出上限被 LRU 淘汰的 mint 累计数 / Mints evicted by LRU for exceeding the capacity, cumulative
+    pub lru_evictions: u64,
+}
+
+/// 全部 mint 的K线聚合器 / Candle aggregators of all mints
+pub struct KlineAggregators {
+    watchlist: HashSet<String>,
+    table: RwLock<AggregatorTable>,
+    resident_mints: AtomicU64,
+    resident_aggregators: AtomicU64,
+    lru_evictions: AtomicU64,
+}
+
+impl KlineAggregators {
+    /// 创建聚合器集合, `watchlist` 中的 mint 常驻内存 / Create the aggregator set, `watchlist` mints stay resident
+    pub fn new(watchlist: &[String]) -> Self {
+        Self {
+            watchlist: watchlist.iter().map(|mint| mint.trim().to_string()).collect(),
+            table: RwLock::new(AggregatorTable::default()),
+            resident_mints: AtomicU64::new(0),
+            resident_aggregators: AtomicU64::new(0),
+            lru_evictions: AtomicU64::new(0),
+        }
+    }
+
+    /// 是否在预热列表中 / Whether the mint is on the watchlist
+    pub fn is_watched(&self, mint: &str) -> bool {
+        self.watchlist.contains(mint)
+    }
+
+    /// 为预热列表中的每个 mint 回填全部间隔, 返回成功回填的聚合器数
+    /// Backfill every interval of each watchlist mint, returns the number of aggregators backfilled
+    pub async fn preheat(&self, data_processor: &KlineDataProcessor) -> usize {
+        let mut warmed = 0;
+        for mint in &self.watchlist {
+            for interval in KLINE_INTERVALS {
+                // 预热列表不参与淘汰, 上限不影响 / The watchlist is never evicted, the capacity does not apply
+                match self.ensure(data_processor, mint, interval, usize::MAX).await {
+                    Ok(()) => warmed += 1,
+                    Err(e) => warn!("预热K线失败 / Failed to preheat klines for {}:{}: {}", mint, interval, e),
+                }
+            }
+        }
+        info!(
+            "🔥 K线预热完成 / K-line preheat complete: mints={}, aggregators={}",
+            self.watchlist.len(),
+            warmed
+        );
+        warmed
+    }
+
+    /// 确保 (mint, 间隔) 的聚合器存在, 不存在时从事件存储回填, 之后按 `capacity` 淘汰
+    /// Make sure the (mint, interval) aggregator exists, backfilling it from event storage when missing, then evict down to `capacity`
+    async fn ensure(
+        &self,
+        data_processor: &KlineDataProcessor,
+        mint: &str,
+        interval: &str,
+        capacity: usize,
+    ) -> Result<()> {
+        if self.contains(mint, interval).await {
+            return Ok(());
+        }
+
+        let bucket = interval_secs(interval)
+            .ok_or_else(|| anyhow::anyhow!("Invalid interval: {}", interval))?;
+        let seed = data_processor
+            .get_kline_snapshot(mint, interval, AGGREGATOR_CANDLES)
+            .await?;
+
+        let mut table = self.table.write().await;
+        // 回填期间可能已被其他订阅创建, 保留已有的 / Another subscription may have created it meanwhile, keep the existing one
+        table
+            .mints
+            .entry(mint.to_string())
+            .or_default()
+            .intervals
+            .entry(interval.to_string())
+            .or_insert_with(|| CandleAggregator::new(bucket, seed.data));
+        table.touch(mint);
+        self.evict_lru(&mut table, capacity);
+        self.record_sizes(&table);
+        Ok(())
+    }
+
+    async fn contains(&self, mint: &str, interval: &str) -> bool {
+        self.table
+            .read()
+            .await
+            .mints
+            .get(mint)
+            .is_some_and(|entry| entry.intervals.contains_key(interval))
+    }
+
+    /// 非预热列表的 mint 超过 `capacity` 时淘汰最久未更新的 / Evict the least recently updated non-watchlist mints beyond `capacity`
+    fn evict_lru(&self, table: &mut AggregatorTable, capacity: usize) {
+        loop {
+            let candidates = table.mints.iter().filter(|(mint, _)| !self.is_watched(mint));
+            if candidates.clone().count() <= capacity {
+                return;
+            }
+            let Some(oldest) = candidates
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(mint, _)| mint.clone())
+            else {
+                return;
+            };
+            table.mints.remove(&oldest);
+            self.lru_evictions.fetch_add(1, Ordering::Relaxed);
+            debug!("♻️ 淘汰K线聚合器 / Evicted candle aggregators (LRU): mint={}", oldest);
+        }
+    }
+
+    /// 更新驻留数量指标 / Update the resident size gauges
+    fn record_sizes(&self, table: &AggregatorTable) {
+        let aggregators: usize = table.mints.values().map(|entry| entry.intervals.len()).sum();
+        self.resident_mints.store(table.mints.len() as u64, Ordering::Relaxed);
+        self.resident_aggregators.store(aggregators as u64, Ordering::Relaxed);
+    }
+
+    /// 订阅快照: 由聚合器返回最近 `candles` 根K线, 聚合器不存在时先回填, 非预热 mint 最多保留 `capacity` 个
+    /// Subscribe snapshot: the latest `candles` candles from the aggregator, backfilling it first when missing;
+    /// at most `capacity` non-watchlist mints are kept
+    pub async fn snapshot(
+        &self,
+        data_processor: &KlineDataProcessor,
+        mint: &str,
+        interval: &str,
+        candles: usize,
+        capacity: usize,
+    ) -> Result<KlineHistoryResponse> {
+        self.ensure(data_processor, mint, interval, capacity).await?;
+
+        let now = Utc::now().timestamp().max(0) as u64;
+        let data = self
+            .table
+            .write()
+            .await
+            .touch(mint)
+            .and_then(|entry| entry.intervals.get(interval))
+            .map(|aggregator| aggregator.snapshot(now, candles))
+            .unwrap_or_default();
+
+        let total_count = data.len();
+        Ok(KlineHistoryResponse {
+            symbol: mint.to_string(),
+            interval: interval.to_string(),
+            data,
+            has_more: false,
+            total_count,
+            limit: candles,
+        })
+    }
+
+    /// 把成交计入该 mint 已有的聚合器, 返回各间隔更新后的K线; 没有聚合器的 mint 返回空
+    /// Apply a trade to the mint's existing aggregators, returning the updated candle per interval; empty for mints without aggregators
+    pub async fn apply_trade(
+        &self,
+        mint: &str,
+        price: f64,
+        volume: f64,
+        timestamp: u64,
+    ) -> Vec<(String, KlineRealtimeData)> {
+        let mut table = self.table.write().await;
+        let Some(entry) = table.touch(mint) else {
+            return Vec::new();
+        };
+        entry
+            .intervals
+            .iter_mut()
+            .filter_map(|(interval, aggregator)| {
+                aggregator
+                    .apply(price, volume, timestamp)
+                    .map(|candle| (interval.clone(), candle))
+            })
+            .collect()
+    }
+
+    /// 释放没有订阅者的间隔 (预热列表中的 mint 除外) / Release intervals without subscribers (except watchlist mints)
+    pub async fn evict_unsubscribed(&self, mint: &str, subscribed: impl Fn(&str) -> bool) {
+        if self.is_watched(mint) {
+            return;
+        }
+
+        let mut table = self.table.write().await;
+        if let Some(entry) = table.mints.get_mut(mint) {
+            entry.intervals.retain(|interval, _| subscribed(interval));
+            if entry.intervals.is_empty() {
+                table.mints.remove(mint);
+            }
+        }
+        self.record_sizes(&table);
+    }
+
+    /// 聚合器指标 / Aggregator metrics
+    pub fn metrics(&self) -> KlineAggregatorMetrics {
+        KlineAggregatorMetrics {
+            mints: self.resident_mints.load(Ordering::Relaxed),
+            aggregators: self.resident_aggregators.load(Ordering::Relaxed),
+            watchlist_mints: self.watchlist.len() as u64,
+            lru_evictions: self.lru_evictions.load(Ordering::Relaxed),
+        }
+    }
+}
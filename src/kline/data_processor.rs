@@ -1,19 +1,74 @@
 // K线数据处理器 / K-line data processor
-use crate::kline::types::{EventHistoryResponse, EventUpdateMessage, KlineHistoryResponse, KlineRealtimeData};
+use crate::kline::types::{
+    EventHistoryResponse, EventUpdateMessage, KlineConfig, KlineHistoryResponse, KlineRealtimeData, TradeEventMessage,
+};
 use crate::solana::PinpetEvent;
 use anyhow::Result;
+use arc_swap::ArcSwap;
 use chrono::Utc;
 use std::sync::Arc;
 
+/// 快照最多读取的成交数, 防止热门代币扫描过多事件 / Max trades read for a snapshot, bounds the scan on busy tokens
+const SNAPSHOT_MAX_TRADES: usize = 2000;
+
+/// 过滤成交量低于 `min_volume` 的已收盘K线 (粉尘K线), 在聚合之后执行, 不影响存储的数据
+/// Drop closed candles whose volume is below `min_volume` (dust candles); runs after aggregation and never touches stored data
+///
+/// 注意: 过滤后K线序列不再连续, 图表会出现时间空档, 因此默认关闭, 仅在客户端显式传入 min_volume 时生效;
+/// 未收盘的当前K线始终保留
+/// Note: filtered series are no longer contiguous and charts will show time gaps, so this is off by default and only
+/// applies when the client passes min_volume explicitly; the open current candle is always kept
+pub fn filter_dust_candles(response: &mut KlineHistoryResponse, min_volume: Option<f64>) {
+    let Some(min_volume) = min_volume.filter(|v| *v > 0.0) else {
+        return;
+    };
+    response
+        .data
+        .retain(|candle| !candle.is_final || candle.volume >= min_volume);
+    response.total_count = response.data.len();
+}
+
+/// 计算 history 请求实际生效的条数: 未指定时取上限, 超过上限时截断, 小于等于0时报错
+/// Resolve the effective limit of a history request: the max when omitted, clamped to the max, an error when <= 0
+pub fn effective_history_limit(requested: Option<i64>, max: usize) -> std::result::Result<usize, String> {
+    match requested {
+        None => Ok(max),
+        Some(limit) if limit <= 0 => Err(format!("limit 必须大于0 / limit must be > 0, got {}", limit)),
+        Some(limit) => Ok(usize::try_from(limit).map_or(max, |limit| limit.min(max))),
+    }
+}
+
+/// history 请求的 limit 无效, 调用方据此返回参数错误而不是存储错误
+/// The limit of a history request is invalid; callers map it to a parameter error rather than a storage error
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+pub struct InvalidHistoryLimit(pub String);
+
+/// K线间隔秒数 / K-line interval length in seconds
+pub fn interval_secs(interval: &str) -> Option<u64> {
+    match interval {
+        "s1" => Some(1),
+        "s30" => Some(30),
+        "m5" => Some(300),
+        _ => None,
+    }
+}
+
 /// K线数据处理器 / K-line data processor
 pub struct KlineDataProcessor {
     event_storage: Arc<crate::db::EventStorage>,
+    token_storage: Arc<crate::db::TokenStorage>,
+    config: Arc<ArcSwap<KlineConfig>>, // 配置(可热加载), 提供 history 上限 / Configuration (hot-reloadable), supplies the history max
 }
 
 impl KlineDataProcessor {
     /// 创建新的K线数据处理器 / Create new K-line data processor
-    pub fn new(event_storage: Arc<crate::db::EventStorage>) -> Self {
-        Self { event_storage }
+    pub fn new(
+        event_storage: Arc<crate::db::EventStorage>,
+        token_storage: Arc<crate::db::TokenStorage>,
+        config: Arc<ArcSwap<KlineConfig>>,
+    ) -> Self {
+        Self { event_storage, token_storage, config }
     }
 
     /// 从事件提取价格数据 / Extract price from event
@@ -28,42 +83,70 @@ impl KlineDataProcessor {
             PinpetEvent::LongShort(e) => Some(e.latest_price as f64),
             PinpetEvent::FullClose(e) => Some(e.latest_price as f64),
             PinpetEvent::PartialClose(e) => Some(e.latest_price as f64),
-            _ => None,
+            // 费率变化、追加保证金和订单延期不成交 / Fee changes, margin top-ups and extensions are not trades
+            PinpetEvent::MilestoneDiscount(_) | PinpetEvent::AddMargin(_) | PinpetEvent::ExtendOrder(_) => None,
         }
     }
 
+    /// 提取成交的 (价格, SOL成交量, 事件时间秒), 口径与K线快照一致
+    /// Extract a trade as (price, SOL volume, event time in seconds), on the same basis as K-line snapshots
+    pub fn extract_trade_point(event: &PinpetEvent) -> Option<(f64, f64, u64)> {
+        let price = Self::extract_price_from_event(event)?;
+        let (volume, event_time) = match (Self::to_trade_message(event), event) {
+            (Some(trade), _) => (trade.sol_amount as f64, trade.event_time),
+            (None, PinpetEvent::TokenCreated(e)) => (0.0, e.timestamp.timestamp_millis()),
+            (None, _) => return None,
+        };
+        Some((price, volume, (event_time / 1000).max(0) as u64))
+    }
+
     /// 从事件获取mint地址 / Get mint address from event
     pub fn get_mint_from_event(event: &PinpetEvent) -> String {
-        match event {
-            PinpetEvent::TokenCreated(e) => e.mint_account.clone(),
-            PinpetEvent::BuySell(e) => e.mint_account.clone(),
-            PinpetEvent::LongShort(e) => e.mint_account.clone(),
-            PinpetEvent::FullClose(e) => e.mint_account.clone(),
-            PinpetEvent::PartialClose(e) => e.mint_account.clone(),
-            PinpetEvent::MilestoneDiscount(e) => e.mint_account.clone(),
-        }
+        event.mint().to_string()
     }
 
     /// 获取事件类型名称 / Get event type name
     pub fn get_event_type_name(event: &PinpetEvent) -> String {
-        match event {
-            PinpetEvent::TokenCreated(_) => "TokenCreated".to_string(),
-            PinpetEvent::BuySell(_) => "BuySell".to_string(),
-            PinpetEvent::LongShort(_) => "LongShort".to_string(),
-            PinpetEvent::FullClose(_) => "FullClose".to_string(),
-            PinpetEvent::PartialClose(_) => "PartialClose".to_string(),
-            PinpetEvent::MilestoneDiscount(_) => "MilestoneDiscount".to_string(),
-        }
+        event.type_name().to_string()
+    }
+
+    /// 将成交类事件转换为精简推送消息, 非成交事件返回 None
+    /// Convert a trade event into a compact push message, None for non-trade events
+    pub fn to_trade_message(event: &PinpetEvent) -> Option<TradeEventMessage> {
+        let trade = event.trade()?;
+        Some(TradeEventMessage {
+            symbol: trade.mint.to_string(),
+            event_type: event.type_name().to_string(),
+            side: trade.side.to_string(),
+            user: trade.user.to_string(),
+            sol_amount: trade.sol_amount,
+            token_amount: trade.token_amount,
+            price: trade.price.to_string(),
+            order_id: trade.order_id,
+            signature: trade.signature.to_string(),
+            slot: trade.slot,
+            event_time: trade.timestamp.timestamp_millis(),
+            timestamp: Utc::now().timestamp_millis() as u64,
+        })
     }
 
     /// 获取历史K线数据 / Get historical K-line data
+    ///
+    /// `requested` 按配置的 history_data_limit 截断, 所有调用方 (socket 与日后的 REST) 共用同一上限;
+    /// 小于等于0时返回 [`InvalidHistoryLimit`]
+    /// `requested` is clamped to the configured history_data_limit so every caller (socket and any future REST
+    /// route) shares one cap; <= 0 returns [`InvalidHistoryLimit`]
+    ///
     /// Note: 新项目暂时返回空数据,因为还没有实现K线聚合存储 / Returns empty for now as K-line aggregation storage is not implemented yet
     pub async fn get_kline_history(
         &self,
         _symbol: &str,
         _interval: &str,
-        _limit: usize,
+        requested: Option<i64>,
     ) -> Result<KlineHistoryResponse> {
+        let limit = effective_history_limit(requested, self.config.load().history_data_limit)
+            .map_err(InvalidHistoryLimit)?;
+
         // TODO: 实现真正的K线历史数据查询 / TODO: Implement real K-line history query
         // 现在返回空数据 / Return empty data for now
         Ok(KlineHistoryResponse {
@@ -72,6 +155,82 @@ impl KlineDataProcessor {
             data: Vec::new(),
             has_more: false,
             total_count: 0,
+            limit,
+        })
+    }
+
+    /// 订阅快照: 由最近成交聚合出最多 `candles` 根K线 (按时间升序, 当前时间所在的K线未收盘)
+    /// Subscribe snapshot: aggregate recent trades into at most `candles` candles (ascending, the candle covering now is still open)
+    pub async fn get_kline_snapshot(
+        &self,
+        symbol: &str,
+        interval: &str,
+        candles: usize,
+    ) -> Result<KlineHistoryResponse> {
+        let bucket = interval_secs(interval)
+            .ok_or_else(|| anyhow::anyhow!("Invalid interval: {}", interval))?;
+        let now = Utc::now();
+        let current = now.timestamp().max(0) as u64 / bucket * bucket;
+        let window_start = current.saturating_sub(bucket * (candles as u64).saturating_sub(1));
+        let since = chrono::DateTime::<Utc>::from_timestamp(window_start as i64, 0).unwrap_or(now);
+
+        let prices = self
+            .event_storage
+            .recent_prices(symbol, since, SNAPSHOT_MAX_TRADES)?;
+        let truncated = prices.len() >= SNAPSHOT_MAX_TRADES;
+
+        // 倒序读取, 从最旧的成交开始聚合 / Read newest first, aggregate from the oldest trade
+        let mut data: Vec<KlineRealtimeData> = Vec::new();
+        for (price, timestamp, volume) in prices.into_iter().rev() {
+            let price = price as f64;
+            let volume = volume as f64;
+            let time = timestamp.timestamp().max(0) as u64 / bucket * bucket;
+            match data.last_mut() {
+                Some(candle) if candle.time == time => {
+                    candle.high = candle.high.max(price);
+                    candle.low = candle.low.min(price);
+                    candle.close = price;
+                    candle.volume += volume;
+                    candle.update_count += 1;
+                }
+                _ => data.push(KlineRealtimeData {
+                    time,
+                    open: price,
+                    high: price,
+                    low: price,
+                    close: price,
+                    volume, // SOL成交量 (同 volume_24h_sol) / SOL volume (same unit as volume_24h_sol)
+                    is_final: true,
+                    update_type: "final".to_string(),
+                    update_count: 1,
+                }),
+            }
+        }
+
+        // 成交数达到上限时最旧的K线不完整, 丢弃 / The oldest candle is incomplete when the trade cap was hit, drop it
+        if truncated && data.len() > 1 {
+            data.remove(0);
+        }
+        if let Some(candle) = data.last_mut().filter(|c| c.time == current) {
+            candle.is_final = false;
+            candle.update_type = "realtime".to_string();
+            // 未收盘K线以规范最新价格收盘, 与报价接口一致 / The open candle closes at the canonical latest price, matching the quote endpoints
+            if let Some(record) = self.token_storage.get_price(symbol)? {
+                let price = record.price as f64;
+                candle.close = price;
+                candle.high = candle.high.max(price);
+                candle.low = candle.low.min(price);
+            }
+        }
+
+        let total_count = data.len();
+        Ok(KlineHistoryResponse {
+            symbol: symbol.to_string(),
+            interval: interval.to_string(),
+            data,
+            has_more: false,
+            total_count,
+            limit: candles,
         })
     }
 
@@ -91,7 +250,7 @@ impl KlineDataProcessor {
             .into_iter()
             .map(|event| EventUpdateMessage {
                 symbol: symbol.to_string(),
-                event_type: Self::get_event_type_name(&event),
+                event_type: event.type_name().to_string(),
                 event_data: event,
                 timestamp: Utc::now().timestamp_millis() as u64,
             })
@@ -122,3 +281,39 @@ impl KlineDataProcessor {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solana::events::FullCloseEvent;
+
+    #[test]
+    fn test_full_close_trade_belongs_to_order_owner() {
+        let event = PinpetEvent::FullClose(FullCloseEvent {
+            payer: "Keeper".to_string(),
+            user_sol_account: "Owner".to_string(),
+            mint_account: "Mint".to_string(),
+            is_close_long: true,
+            final_token_amount: 1_000,
+            final_sol_amount: 2_000,
+            user_close_profit: 2_000,
+            latest_price: 42,
+            order_id: 7,
+            order_index: 0,
+            liquidate_indices: vec![0],
+            keeper_reward: 10,
+            timestamp: Utc::now(),
+            signature: "sig".to_string(),
+            slot: 9,
+            event_index: 0,
+        });
+
+        // 推送消息与成交摘要索引使用同一映射 / The push message and the trade summary index share one mapping
+        let message = KlineDataProcessor::to_trade_message(&event).unwrap();
+        assert_eq!(message.user, "Owner");
+        assert_eq!(message.side, "close_long");
+        assert_eq!(message.event_type, "FullClose");
+        assert_eq!(message.sol_amount, 2_000);
+        assert_eq!(message.order_id, Some(7));
+    }
+}
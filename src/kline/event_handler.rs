@@ -1,8 +1,9 @@
 // K线事件处理器 - 包装现有事件处理器并添加K线推送功能
 // K-line event handler - Wraps existing event handler and adds K-line push functionality
 
-use crate::kline::{data_processor::KlineDataProcessor, socket_service::KlineSocketService};
-use crate::solana::{EventHandler, PinpetEvent};
+use crate::kline::{data_processor::KlineDataProcessor, socket_service::KlineSocketService, tasks::stopped};
+use crate::solana::events::TokenCreatedEvent;
+use crate::solana::{EventHandler, EventObserver, EventQueue, PinpetEvent};
 use anyhow::Result;
 use async_trait::async_trait;
 use chrono::Utc;
@@ -11,21 +12,45 @@ use tracing::{debug, info, warn};
 
 /// K线事件处理器 - 装饰器模式包装EventHandler
 /// K-line event handler - Decorator pattern wrapping EventHandler
+///
+/// K线推送不是关键路径: 事件经有界队列交给后台任务推送, 队列满时丢弃并告警
+/// K-line push is not on the critical path: events go through a bounded queue to a background task, dropped with a warning when full
 pub struct KlineEventHandler {
-    inner: Arc<dyn EventHandler>,           // 内部事件处理器 / Inner event handler
-    kline_service: Arc<KlineSocketService>, // K线推送服务 / K-line push service
+    inner: Arc<dyn EventHandler>, // 内部事件处理器 / Inner event handler
+    kline_queue: EventQueue,      // K线推送队列 / K-line push queue
 }
 
 impl KlineEventHandler {
-    /// 创建新的K线事件处理器 / Create new K-line event handler
+    /// 创建新的K线事件处理器并启动推送任务 / Create new K-line event handler and start the push task
     pub fn new(
         inner: Arc<dyn EventHandler>,
         kline_service: Arc<KlineSocketService>,
+        queue_capacity: usize,
     ) -> Self {
-        Self {
-            inner,
-            kline_service,
-        }
+        let (kline_queue, mut receiver) = EventQueue::bounded("kline", queue_capacity);
+
+        // 停止信号只在两条消息之间生效, 不会打断正在发送的推送
+        // The stop signal only takes effect between messages, never interrupting a push in flight
+        let service = kline_service.clone();
+        kline_service.tasks().spawn("kline_push", |mut signal| async move {
+            loop {
+                tokio::select! {
+                    _ = stopped(&mut signal) => break,
+                    event = receiver.recv() => match event {
+                        Some(event) => push_kline(&service, &event).await,
+                        None => break,
+                    },
+                }
+            }
+            info!("K线推送任务停止 / K-line push task stopped");
+        });
+
+        Self { inner, kline_queue }
+    }
+
+    /// 获取K线推送队列句柄 (用于读取队列指标) / Get the K-line queue handle (for reading queue metrics)
+    pub fn kline_queue(&self) -> EventQueue {
+        self.kline_queue.clone()
     }
 }
 
@@ -48,45 +73,78 @@ impl EventHandler for KlineEventHandler {
             // 即使内部处理失败,也继续进行K线推送 / Continue with K-line push even if inner handler fails
         }
 
-        // 2. 广播交易事件 (所有事件都推送)
-        // 2. Broadcast trading event (all events are pushed)
-        info!("广播交易事件 / Broadcasting trading event");
-        if let Err(e) = self.kline_service.broadcast_event_update(&event).await {
-            warn!("广播交易事件失败 / Failed to broadcast event update: {}", e);
-        }
+        // 2. 交给K线推送任务, 队列满时丢弃 / Hand over to the K-line push task, dropped when the queue is full
+        self.kline_queue.try_send(event);
 
-        // 3. 如果事件包含价格数据,生成并广播K线更新
-        // 3. If event contains price data, generate and broadcast K-line update
-        if let Some(price) = KlineDataProcessor::extract_price_from_event(&event) {
-            let mint = KlineDataProcessor::get_mint_from_event(&event);
-            let timestamp = Utc::now().timestamp() as u64;
-
-            // 为每个支持的时间间隔生成K线数据 / Generate K-line data for each supported interval
-            let intervals = ["s1", "s30", "m5"];
-            for interval in intervals {
-                // 生成K线数据 (简化版,直接使用KlineRealtimeData)
-                // Generate K-line data (simplified, use KlineRealtimeData directly)
-                let kline_data = crate::kline::types::KlineRealtimeData {
-                    time: timestamp,
-                    open: price,
-                    high: price,
-                    low: price,
-                    close: price,
-                    volume: 0.0,
-                    is_final: false,
-                    update_type: "realtime".to_string(),
-                    update_count: 1,
-                };
-
-                // 广播K线更新 / Broadcast K-line update
-                info!(
-                    "广播K线更新 / Broadcasting K-line update: mint={}, interval={}, price={}",
-                    mint, interval, price
-                );
+        Ok(())
+    }
+}
 
-                if let Err(e) = self
-                    .kline_service
-                    .broadcast_kline_update(&mint, interval, &kline_data)
+/// 新币推送观察者 - 新Token首次写入后推送到 token_created 频道
+/// New token feed observer - pushes to the token_created channel after a new token is first written
+pub struct NewTokenFeed {
+    kline_service: Arc<KlineSocketService>,
+}
+
+impl NewTokenFeed {
+    pub fn new(kline_service: Arc<KlineSocketService>) -> Self {
+        Self { kline_service }
+    }
+}
+
+impl EventObserver for NewTokenFeed {
+    fn on_new_token(&self, event: &TokenCreatedEvent) {
+        // 回调不能阻塞事件处理, 推送放到后台任务 / The callback must not block event processing, push in a background task
+        let kline_service = self.kline_service.clone();
+        let event = event.clone();
+        tokio::spawn(async move {
+            if let Err(e) = kline_service.broadcast_new_token(&event).await {
+                warn!("推送新币失败 / Failed to broadcast new token: {}", e);
+            }
+        });
+    }
+}
+
+/// 推送单个事件的交易流和K线更新 / Push the trade stream and K-line updates for one event
+async fn push_kline(kline_service: &KlineSocketService, event: &PinpetEvent) {
+    // 1. 广播交易事件 (所有事件都推送)
+    // 1. Broadcast trading event (all events are pushed)
+    info!("广播交易事件 / Broadcasting trading event");
+    if let Err(e) = kline_service.broadcast_event_update(event).await {
+        warn!("广播交易事件失败 / Failed to broadcast event update: {}", e);
+    }
+
+    // 2. 推送精简成交流 (仅成交类事件) / Push compact trade stream (trade events only)
+    if let Err(e) = kline_service.broadcast_trade_event(event).await {
+        warn!("推送成交流失败 / Failed to broadcast trade event: {}", e);
+    }
+
+    // 2b. 推送大额成交 (单笔 SOL 不低于 kline.whale_min_sol) / Push large trades (per-trade SOL at least kline.whale_min_sol)
+    if let Err(e) = kline_service.broadcast_whale_trade(event).await {
+        warn!("推送大额成交失败 / Failed to broadcast large trade: {}", e);
+    }
+
+    // 3. 如果事件包含价格数据,生成并广播K线更新
+    // 3. If event contains price data, generate and broadcast K-line update
+    if let Some(price) = KlineDataProcessor::extract_price_from_event(event) {
+        let mint = KlineDataProcessor::get_mint_from_event(event);
+        let timestamp = Utc::now().timestamp() as u64;
+
+        // 有聚合器的 mint (预热列表或已被订阅) 推送聚合后的K线
+        // Mints with aggregators (watchlist or subscribed) push the aggregated candle
+        let aggregated = match KlineDataProcessor::extract_trade_point(event) {
+            Some((price, volume, event_time)) => {
+                kline_service.apply_trade(&mint, price, volume, event_time).await
+            }
+            None => Vec::new(),
+        };
+
+        // 为每个支持的时间间隔生成K线数据 / Generate K-line data for each supported interval
+        let intervals = ["s1", "s30", "m5"];
+        for interval in intervals {
+            if let Some((_, kline_data)) = aggregated.iter().find(|(i, _)| i == interval) {
+                if let Err(e) = kline_service
+                    .broadcast_kline_update(&mint, interval, kline_data)
                     .await
                 {
                     warn!(
@@ -94,13 +152,42 @@ impl EventHandler for KlineEventHandler {
                         mint, interval, e
                     );
                 }
+                continue;
             }
-        } else {
-            debug!(
-                "事件不包含价格数据,跳过K线推送 / Event does not contain price data, skipping K-line push"
+
+            // 生成K线数据 (简化版,直接使用KlineRealtimeData)
+            // Generate K-line data (simplified, use KlineRealtimeData directly)
+            let kline_data = crate::kline::types::KlineRealtimeData {
+                time: timestamp,
+                open: price,
+                high: price,
+                low: price,
+                close: price,
+                volume: 0.0,
+                is_final: false,
+                update_type: "realtime".to_string(),
+                update_count: 1,
+            };
+
+            // 广播K线更新 / Broadcast K-line update
+            info!(
+                "广播K线更新 / Broadcasting K-line update: mint={}, interval={}, price={}",
+                mint, interval, price
             );
-        }
 
-        Ok(())
+            if let Err(e) = kline_service
+                .broadcast_kline_update(&mint, interval, &kline_data)
+                .await
+            {
+                warn!(
+                    "广播K线更新失败 / Failed to broadcast K-line update for {}:{}: {}",
+                    mint, interval, e
+                );
+            }
+        }
+    } else {
+        debug!(
+            "事件不包含价格数据,跳过K线推送 / Event does not contain price data, skipping K-line push"
+        );
     }
 }
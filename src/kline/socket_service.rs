@@ -2,21 +2,236 @@
 // 基于 SocketIoxide 0.17 实现 / Based on SocketIoxide 0.17
 
 use crate::kline::{
-    data_processor::KlineDataProcessor,
+    aggregator::KlineAggregators,
+    data_processor::{filter_dust_candles, InvalidHistoryLimit, KlineDataProcessor},
     subscription::SubscriptionManager,
+    tasks::{stopped, KlineTasks},
     types::*,
 };
-use crate::db::EventStorage;
+use crate::db::{EventStorage, TokenStorage};
+use crate::solana::events::TokenCreatedEvent;
 use crate::solana::PinpetEvent;
+use crate::util::error_code::ErrorCode;
 use anyhow::Result;
+use arc_swap::ArcSwap;
+use bytes::Bytes;
 use chrono::Utc;
 use socketioxide::extract::{Data, SocketRef};
+use socketioxide::socket::DisconnectReason;
 use socketioxide::SocketIo;
+use solana_sdk::pubkey::Pubkey;
 use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Instant;
+use serde::{Deserialize, Serialize};
 use tokio::sync::RwLock;
 use tracing::{debug, info, warn};
+use utoipa::ToSchema;
+
+/// 成交流在订阅管理器中使用的伪间隔, 与K线订阅共享每客户端上限
+/// Pseudo-interval for the trade stream in the subscription manager, sharing the per-client limit with K-line subscriptions
+pub const TRADE_STREAM_INTERVAL: &str = "trades";
+
+/// 新币推送在订阅管理器中的键 (全局频道, 不属于任何 mint), 同样计入每客户端上限
+/// Subscription manager keys for the new token feed (a global channel, not tied to a mint), also counted against the per-client limit
+pub const NEW_TOKENS_MINT: &str = "*";
+pub const NEW_TOKENS_INTERVAL: &str = "new_tokens";
+
+/// 大额成交推送在订阅管理器中的键 (全局频道), 计入每客户端上限
+/// Subscription manager keys for the large trade feed (a global channel), counted against the per-client limit
+pub const WHALE_TRADES_MINT: &str = "*";
+pub const WHALE_TRADES_INTERVAL: &str = "whale_trades";
+
+/// 停止时等待后台任务退出的时限 / How long shutdown waits for background tasks to exit
+const KLINE_SHUTDOWN_GRACE: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// 新币推送房间名 / New token feed room name
+const NEW_TOKENS_ROOM: &str = "new_tokens";
+
+/// 大额成交推送房间名 / Large trade feed room name
+const WHALE_TRADES_ROOM: &str = "whale_trades";
+
+/// 成交流房间名 / Trade stream room name
+fn trade_room(mint: &str) -> String {
+    format!("trades:{}", mint)
+}
+
+/// 断开原因分类, 用于区分客户端问题与服务端主动断开
+/// Disconnect reason category, used to tell client bugs apart from the server dropping sockets
+fn disconnect_category(reason: &DisconnectReason) -> &'static str {
+    match reason {
+        DisconnectReason::HeartbeatTimeout => "timeout",
+        DisconnectReason::TransportClose | DisconnectReason::ClientNSDisconnect => "client_close",
+        DisconnectReason::ServerNSDisconnect | DisconnectReason::ClosingServer => "server_close",
+        DisconnectReason::TransportError
+        | DisconnectReason::MultipleHttpPollingError
+        | DisconnectReason::PacketParsingError => "error",
+    }
+}
+
+/// 推送负载编码, 每个连接在握手时通过查询参数 `encoding` 选择, 默认 JSON
+/// Push payload encoding, chosen per connection by the `encoding` handshake query param, JSON by default
+///
+/// 仅数据推送 (K线、快照、历史、成交、新币) 使用所选编码, 确认和错误消息始终为 JSON;
+/// MessagePack 负载以带字段名的 map 编码, 作为 Socket.IO 二进制附件发送
+/// Only data pushes (candles, snapshots, history, trades, new tokens) use the chosen encoding, acks and errors stay JSON;
+/// MessagePack payloads are encoded as maps with field names and sent as Socket.IO binary attachments
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayloadEncoding {
+    Json,
+    MessagePack,
+}
+
+impl PayloadEncoding {
+    /// 全部编码, 广播时逐一发送 / Every encoding, broadcasts send to each
+    pub const ALL: [PayloadEncoding; 2] = [PayloadEncoding::Json, PayloadEncoding::MessagePack];
+
+    /// 从握手查询串解析编码, 未知值回退到 JSON / Parse the encoding from the handshake query string, unknown values fall back to JSON
+    fn from_query(query: Option<&str>) -> Self {
+        let value = query
+            .unwrap_or_default()
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find_map(|(key, value)| (key == "encoding").then_some(value));
+        match value.map(str::to_ascii_lowercase).as_deref() {
+            Some("msgpack") | Some("messagepack") => PayloadEncoding::MessagePack,
+            _ => PayloadEncoding::Json,
+        }
+    }
+
+    /// 连接协商的编码 / The encoding negotiated by a connection
+    fn of(socket: &SocketRef) -> Self {
+        Self::from_query(socket.req_parts().uri.query())
+    }
+
+    /// 编码名, 在连接成功消息中回显 / Encoding name, echoed in the connection success message
+    fn name(self) -> &'static str {
+        match self {
+            PayloadEncoding::Json => "json",
+            PayloadEncoding::MessagePack => "msgpack",
+        }
+    }
+
+    /// 按编码区分的房间名, JSON 沿用原房间名 / Per-encoding room name, JSON keeps the original room name
+    fn room(self, base: &str) -> String {
+        match self {
+            PayloadEncoding::Json => base.to_string(),
+            PayloadEncoding::MessagePack => format!("{}#msgpack", base),
+        }
+    }
+}
+
+/// 按编码序列化后的负载 / A payload serialized for one encoding
+enum EncodedPayload<'a, T: Serialize> {
+    Json(&'a T),
+    MessagePack(Bytes),
+}
+
+impl<'a, T: Serialize> EncodedPayload<'a, T> {
+    fn encode(encoding: PayloadEncoding, data: &'a T) -> Result<Self> {
+        Ok(match encoding {
+            PayloadEncoding::Json => EncodedPayload::Json(data),
+            PayloadEncoding::MessagePack => EncodedPayload::MessagePack(Bytes::from(rmp_serde::to_vec_named(data)?)),
+        })
+    }
+
+    fn emit_to(&self, socket: &SocketRef, event: &'static str) -> Result<()> {
+        match self {
+            EncodedPayload::Json(data) => socket.emit(event, *data)?,
+            EncodedPayload::MessagePack(bytes) => socket.emit(event, bytes)?,
+        }
+        Ok(())
+    }
+
+    async fn broadcast_to(&self, io: &SocketIo, room: String, event: &'static str) -> Result<()> {
+        let ns = io
+            .of("/kline")
+            .ok_or_else(|| anyhow::anyhow!("Namespace /kline not found"))?;
+        match self {
+            EncodedPayload::Json(data) => ns.to(room).emit(event, *data).await?,
+            EncodedPayload::MessagePack(bytes) => ns.to(room).emit(event, bytes).await?,
+        }
+        Ok(())
+    }
+}
+
+/// 以连接协商的编码发送数据推送 / Emit a data push in the encoding the connection negotiated
+fn emit_payload<T: Serialize>(socket: &SocketRef, event: &'static str, data: &T) -> Result<()> {
+    EncodedPayload::encode(PayloadEncoding::of(socket), data)?.emit_to(socket, event)
+}
+
+/// 加入房间的对应编码版本 / Join the encoding's variant of a room
+fn join_room(socket: &SocketRef, base: &str) {
+    socket.join(PayloadEncoding::of(socket).room(base));
+}
+
+/// 离开房间的对应编码版本 / Leave the encoding's variant of a room
+fn leave_room(socket: &SocketRef, base: &str) {
+    socket.leave(PayloadEncoding::of(socket).room(base));
+}
+
+/// K线连接统计 / K-line connection statistics
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct KlineConnectionMetrics {
+    /// 当前连接数 / Currently connected sockets
+    pub connected: u64,
+    /// 累计连接数 / Total connections
+    pub connects_total: u64,
+    /// 心跳超时断开 / Disconnected by heartbeat timeout
+    pub disconnects_timeout: u64,
+    /// 客户端主动断开 / Closed by the client
+    pub disconnects_client_close: u64,
+    /// 服务端主动断开 / Closed by the server
+    pub disconnects_server_close: u64,
+    /// 传输或协议错误断开 / Disconnected by a transport or protocol error
+    pub disconnects_error: u64,
+}
+
+/// K线连接计数器, 连接与断开处理器共享 / K-line connection counters shared by the connect and disconnect handlers
+#[derive(Debug, Default)]
+pub struct KlineConnectionStats {
+    connected: AtomicU64,
+    connects_total: AtomicU64,
+    disconnects_timeout: AtomicU64,
+    disconnects_client_close: AtomicU64,
+    disconnects_server_close: AtomicU64,
+    disconnects_error: AtomicU64,
+}
+
+impl KlineConnectionStats {
+    fn record_connect(&self) {
+        self.connected.fetch_add(1, Ordering::Relaxed);
+        self.connects_total.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn record_disconnect(&self, category: &str) {
+        // 连接数不会低于 0 / The gauge never drops below zero
+        let _ = self
+            .connected
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| Some(n.saturating_sub(1)));
+        let counter = match category {
+            "timeout" => &self.disconnects_timeout,
+            "client_close" => &self.disconnects_client_close,
+            "server_close" => &self.disconnects_server_close,
+            _ => &self.disconnects_error,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 当前统计快照 / Current statistics snapshot
+    pub fn metrics(&self) -> KlineConnectionMetrics {
+        KlineConnectionMetrics {
+            connected: self.connected.load(Ordering::Relaxed),
+            connects_total: self.connects_total.load(Ordering::Relaxed),
+            disconnects_timeout: self.disconnects_timeout.load(Ordering::Relaxed),
+            disconnects_client_close: self.disconnects_client_close.load(Ordering::Relaxed),
+            disconnects_server_close: self.disconnects_server_close.load(Ordering::Relaxed),
+            disconnects_error: self.disconnects_error.load(Ordering::Relaxed),
+        }
+    }
+}
 
 /// K线Socket服务 / K-line Socket service
 pub struct KlineSocketService {
@@ -24,42 +239,107 @@ pub struct KlineSocketService {
     event_storage: Arc<EventStorage>,                        // 事件存储 / Event storage
     subscriptions: Arc<RwLock<SubscriptionManager>>,         // 订阅管理器 / Subscription manager
     data_processor: Arc<KlineDataProcessor>,                 // 数据处理器 / Data processor
-    config: KlineConfig,                                     // 配置 / Configuration
+    aggregators: Arc<KlineAggregators>,                      // K线聚合器 / Candle aggregators
+    config: Arc<ArcSwap<KlineConfig>>,                       // 配置(可热加载) / Configuration (hot-reloadable)
+    connection_stats: Arc<KlineConnectionStats>,             // 连接统计 / Connection statistics
+    tasks: KlineTasks,                                       // 后台任务 / Background tasks
 }
 
 impl KlineSocketService {
     /// 创建新的Socket服务并返回服务实例和Layer / Create new Socket service and return (Service, Layer)
     pub fn new(
         event_storage: Arc<EventStorage>,
-        config: KlineConfig,
+        token_storage: Arc<TokenStorage>,
+        config: Arc<ArcSwap<KlineConfig>>,
+        watchlist: &[String],
     ) -> Result<(Self, socketioxide::layer::SocketIoLayer)> {
+        // 心跳参数在构建时固定, 修改需重启 / Ping settings are fixed at build time, changes need a restart
+        let initial = config.load();
+
         // 创建 SocketIoxide 实例 / Create SocketIoxide instance
         let (layer, io) = SocketIo::builder()
-            .ping_interval(std::time::Duration::from_secs(config.ping_interval_secs))
-            .ping_timeout(std::time::Duration::from_secs(config.ping_timeout_secs))
+            .ping_interval(std::time::Duration::from_secs(initial.ping_interval_secs))
+            .ping_timeout(std::time::Duration::from_secs(initial.ping_timeout_secs))
             .max_payload(1024 * 1024) // 1MB 最大负载 / 1MB max payload
             .build_layer();
 
-        let data_processor = Arc::new(KlineDataProcessor::new(event_storage.clone()));
+        let data_processor = Arc::new(KlineDataProcessor::new(
+            event_storage.clone(),
+            token_storage,
+            config.clone(),
+        ));
 
         let service = Self {
             socketio: io,
             event_storage: event_storage.clone(),
             subscriptions: Arc::new(RwLock::new(SubscriptionManager::new(
-                config.max_subscriptions_per_client,
+                initial.max_subscriptions_per_client,
             ))),
             data_processor,
+            aggregators: Arc::new(KlineAggregators::new(watchlist)),
             config,
+            connection_stats: Arc::new(KlineConnectionStats::default()),
+            tasks: KlineTasks::new(),
         };
 
         Ok((service, layer))
     }
 
+    /// K线聚合器 (用于 /metrics) / Candle aggregators (for /metrics)
+    pub fn aggregators(&self) -> Arc<KlineAggregators> {
+        Arc::clone(&self.aggregators)
+    }
+
+    /// 连接统计 (用于 /metrics) / Connection statistics (for /metrics)
+    pub fn connection_stats(&self) -> Arc<KlineConnectionStats> {
+        Arc::clone(&self.connection_stats)
+    }
+
+    /// 后台回填预热列表中 mint 的K线聚合器, 停止时放弃未完成的回填
+    /// Backfill the candle aggregators of the watchlist mints in the background, abandoning it on shutdown
+    pub fn start_preheat(&self) {
+        let aggregators = Arc::clone(&self.aggregators);
+        let data_processor = Arc::clone(&self.data_processor);
+        self.tasks.spawn("kline_preheat", |mut signal| async move {
+            tokio::select! {
+                _ = stopped(&mut signal) => {
+                    info!("K线预热已取消 / K-line preheat cancelled");
+                }
+                _ = aggregators.preheat(&data_processor) => {}
+            }
+        });
+    }
+
+    /// 后台任务集合 (推送任务等在此登记) / Background task set (the push task and others register here)
+    pub fn tasks(&self) -> &KlineTasks {
+        &self.tasks
+    }
+
+    /// 停止全部后台任务, 推送任务发完当前消息后退出 / Stop every background task, the push task exits after its current message
+    pub async fn shutdown(&self) {
+        self.tasks.shutdown(KLINE_SHUTDOWN_GRACE).await;
+    }
+
+    /// 把成交计入聚合器, 返回各间隔更新后的K线 (仅限已有聚合器的 mint)
+    /// Apply a trade to the aggregators, returning the updated candle per interval (only mints with aggregators)
+    pub async fn apply_trade(
+        &self,
+        mint: &str,
+        price: f64,
+        volume: f64,
+        timestamp: u64,
+    ) -> Vec<(String, KlineRealtimeData)> {
+        self.aggregators.apply_trade(mint, price, volume, timestamp).await
+    }
+
     /// 设置Socket事件处理器 / Setup Socket event handlers
     pub fn setup_socket_handlers(&self) {
         let subscriptions = Arc::clone(&self.subscriptions);
+        let aggregators = Arc::clone(&self.aggregators);
+        let connection_stats = Arc::clone(&self.connection_stats);
         let event_storage = Arc::clone(&self.event_storage);
         let data_processor = Arc::clone(&self.data_processor);
+        let config = Arc::clone(&self.config);
 
         // 设置默认命名空间（避免default namespace not found错误）/ Setup default namespace (avoid default namespace not found error)
         self.socketio.ns("/", |_socket: SocketRef| {
@@ -69,11 +349,19 @@ impl KlineSocketService {
         // K线命名空间 - 合并所有事件处理器到一个命名空间 / K-line namespace - merge all event handlers into one namespace
         self.socketio.ns("/kline", {
             let subscriptions = subscriptions.clone();
+            let aggregators = aggregators.clone();
             let event_storage = event_storage.clone();
             let data_processor = data_processor.clone();
+            let config = config.clone();
+            let connection_stats = connection_stats.clone();
 
             move |socket: SocketRef| {
-                info!("🔌 New client connected to /kline: {}", socket.id);
+                connection_stats.record_connect();
+                info!(
+                    socket_id = %socket.id,
+                    connected = connection_stats.metrics().connected,
+                    "🔌 K线客户端已连接 / K-line client connected"
+                );
 
                 // 保存 socket_id 用于后续使用 / Save socket_id for later use
                 let socket_id = socket.id.to_string();
@@ -93,7 +381,8 @@ impl KlineSocketService {
                     "client_id": socket_id,
                     "server_time": Utc::now().timestamp(),
                     "supported_symbols": [],
-                    "supported_intervals": ["s1", "s30", "m5"]
+                    "supported_intervals": ["s1", "s30", "m5"],
+                    "encoding": PayloadEncoding::of(&socket).name()
                 });
 
                 if let Err(e) = socket.emit("connection_success", &welcome_msg) {
@@ -104,15 +393,20 @@ impl KlineSocketService {
                 socket.on("subscribe", {
                     let subscriptions = subscriptions.clone();
                     let data_processor = data_processor.clone();
+                    let aggregators = aggregators.clone();
+                    let config = config.clone();
 
                     move |socket: SocketRef, Data(data): Data<SubscribeRequest>| {
                         let subscriptions = subscriptions.clone();
                         let data_processor = data_processor.clone();
+                        let aggregators = aggregators.clone();
+                        let config = config.load_full();
 
                         tokio::spawn(async move {
+                            let requested = data.requested_intervals();
                             info!(
-                                "📊 Subscribe request from {}: {} {}",
-                                socket.id, data.symbol, data.interval
+                                "📊 Subscribe request from {}: {} {:?}",
+                                socket.id, data.symbol, requested
                             );
 
                             // 更新客户端活动 / Update client activity
@@ -121,32 +415,38 @@ impl KlineSocketService {
                                 manager.update_activity(&socket.id.to_string());
                             }
 
-                            // 验证订阅请求 / Validate subscribe request
-                            if let Err(e) = validate_subscribe_request(&data) {
-                                let _ = socket.emit(
-                                    "error",
-                                    &serde_json::json!({
-                                        "code": 1001,
-                                        "message": e.to_string()
-                                    }),
-                                );
-                                return;
-                            }
+                            // 规范化并验证订阅请求 / Normalize and validate the subscribe request
+                            let requested_label = requested.join(",");
+                            let (mint, intervals, rejected) = match normalize_subscribe_request(&data) {
+                                Ok(normalized) => normalized,
+                                Err(code) => {
+                                    emit_subscribe_error(
+                                        &socket,
+                                        code,
+                                        &data.symbol,
+                                        Some(&requested_label),
+                                        data.subscription_id.clone(),
+                                    );
+                                    return;
+                                }
+                            };
 
-                            // 添加订阅 / Add subscription
+                            // 原子地添加全部间隔的订阅 / Atomically add subscriptions for all intervals
                             {
                                 let mut manager = subscriptions.write().await;
-                                if let Err(e) = manager.add_subscription(
+                                // 使用当前生效的订阅上限 / Use the currently effective subscription limit
+                                manager.max_subscriptions_per_client = config.max_subscriptions_per_client;
+                                if let Err(code) = manager.add_subscriptions(
                                     &socket.id.to_string(),
-                                    &data.symbol,
-                                    &data.interval,
+                                    &mint,
+                                    &intervals,
                                 ) {
-                                    let _ = socket.emit(
-                                        "error",
-                                        &serde_json::json!({
-                                            "code": 1002,
-                                            "message": e.to_string()
-                                        }),
+                                    emit_subscribe_error(
+                                        &socket,
+                                        code,
+                                        &data.symbol,
+                                        Some(&requested_label),
+                                        data.subscription_id.clone(),
                                     );
                                     return;
                                 }
@@ -155,52 +455,95 @@ impl KlineSocketService {
                                 manager.update_activity(&socket.id.to_string());
                             }
 
-                            // 加入对应的房间 / Join corresponding room
-                            let room_name = format!("kline:{}:{}", data.symbol, data.interval);
-                            info!("🏠 Client {} joining room: {}", socket.id, room_name);
-                            socket.join(room_name.clone());
-
-                            // 检查订阅者状态 / Check subscriber status
-                            {
-                                let manager = subscriptions.read().await;
-                                let subscribers =
-                                    manager.get_subscribers(&data.symbol, &data.interval);
-                                info!(
-                                    "📈 Current subscribers for {}:{}: {:?}",
-                                    data.symbol, data.interval, subscribers
-                                );
-                                info!("📋 Total active connections: {}", manager.connections.len());
+                            // 加入对应的房间 / Join corresponding rooms
+                            for interval in &intervals {
+                                let room_name = format!("kline:{}:{}", mint, interval);
+                                info!("🏠 Client {} joining room: {}", socket.id, room_name);
+                                join_room(&socket, &room_name);
                             }
 
-                            // 推送历史K线数据 / Push historical K-line data
-                            if let Ok(history) = data_processor
-                                .get_kline_history(&data.symbol, &data.interval, 100)
-                                .await
-                            {
-                                if let Err(e) = socket.emit("history_data", &history) {
-                                    warn!("Failed to send history data: {}", e);
-                                } else {
-                                    // 更新历史数据发送计数 / Update history data sent count
+                            // 一条ACK列出接受和拒绝的间隔, 先于历史数据发送
+                            // One ACK listing accepted and rejected intervals, sent before any history data
+                            let _ = socket.emit(
+                                "subscribed",
+                                &SubscribedMessage {
+                                    mint: mint.clone(),
+                                    interval: intervals[0].clone(),
+                                    intervals: intervals.clone(),
+                                    rejected,
+                                    subscription_id: data.subscription_id.clone(),
+                                },
+                            );
+
+                            for interval in &intervals {
+                                // 先推送小快照 (当前K线和最近几根), 图表无需等待下一笔成交即可渲染;
+                                // 快照来自聚合器, 预热列表中的 mint 无需回填
+                                // Push a small snapshot first (current and recent candles) so charts render without waiting for the next trade;
+                                // the snapshot comes from the aggregator, watchlist mints need no backfill
+                                if config.snapshot_candles > 0 {
+                                    match aggregators
+                                        .snapshot(
+                                            &data_processor,
+                                            &mint,
+                                            interval,
+                                            config.snapshot_candles,
+                                            config.max_aggregated_mints,
+                                        )
+                                        .await
                                     {
-                                        let mut manager = subscriptions.write().await;
-                                        manager.increment_history_data_sent(&socket.id.to_string());
+                                        Ok(mut snapshot) => {
+                                            filter_dust_candles(&mut snapshot, data.min_volume);
+                                            if let Err(e) = emit_payload(&socket, "kline_snapshot", &snapshot) {
+                                                warn!("Failed to send kline snapshot: {}", e);
+                                            }
+                                        }
+                                        Err(e) => warn!("Failed to build kline snapshot for {}:{}: {}", mint, interval, e),
+                                    }
+                                }
+
+                                // 检查订阅者状态 / Check subscriber status
+                                {
+                                    let manager = subscriptions.read().await;
+                                    let subscribers = manager.get_subscribers(&mint, interval);
+                                    info!(
+                                        "📈 Current subscribers for {}:{}: {:?}",
+                                        mint, interval, subscribers
+                                    );
+                                    info!("📋 Total active connections: {}", manager.connections.len());
+                                }
+
+                                // 推送历史K线数据 / Push historical K-line data
+                                if let Ok(mut history) = data_processor
+                                    .get_kline_history(&mint, interval, None)
+                                    .await
+                                {
+                                    filter_dust_candles(&mut history, data.min_volume);
+                                    if let Err(e) = emit_payload(&socket, "history_data", &history) {
+                                        warn!("Failed to send history data: {}", e);
+                                    } else {
+                                        // 更新历史数据发送计数 / Update history data sent count
+                                        {
+                                            let mut manager = subscriptions.write().await;
+                                            manager.increment_history_data_sent(&socket.id.to_string());
+                                        }
                                     }
                                 }
                             }
 
-                            // 推送历史交易事件数据 (300条) / Push historical event data (300 records)
-                            info!("📡 Sending historical event data for mint: {}", data.symbol);
+                            // 推送历史交易事件数据 (300条), 与间隔无关只发送一次
+                            // Push historical event data (300 records), interval independent so sent once
+                            info!("📡 Sending historical event data for mint: {}", mint);
                             if let Ok(event_history) = data_processor
-                                .get_event_history(&data.symbol, 300)
+                                .get_event_history(&mint, 300)
                                 .await
                             {
-                                if let Err(e) = socket.emit("history_event_data", &event_history) {
+                                if let Err(e) = emit_payload(&socket, "history_event_data", &event_history) {
                                     warn!("Failed to send history event data: {}", e);
                                 } else {
                                     info!(
                                         "✅ Successfully sent {} historical events for mint: {}",
                                         event_history.data.len(),
-                                        data.symbol
+                                        mint
                                     );
                                     // 更新历史数据发送计数 / Update history data sent count
                                     {
@@ -209,20 +552,22 @@ impl KlineSocketService {
                                     }
                                 }
                             } else {
-                                warn!("❌ Failed to get historical event data for mint: {}", data.symbol);
+                                warn!("❌ Failed to get historical event data for mint: {}", mint);
                             }
 
-                            // 确认订阅成功 / Confirm subscription success
-                            let _ = socket.emit(
-                                "subscription_confirmed",
-                                &serde_json::json!({
-                                    "symbol": data.symbol,
-                                    "interval": data.interval,
-                                    "subscription_id": data.subscription_id,
-                                    "success": true,
-                                    "message": "订阅成功 / Subscription successful"
-                                }),
-                            );
+                            // 旧版确认消息 (每个间隔一条), 保留兼容已有客户端 / Legacy confirmations (one per interval), kept for existing clients
+                            for interval in &intervals {
+                                let _ = socket.emit(
+                                    "subscription_confirmed",
+                                    &serde_json::json!({
+                                        "symbol": mint,
+                                        "interval": interval,
+                                        "subscription_id": data.subscription_id,
+                                        "success": true,
+                                        "message": "订阅成功 / Subscription successful"
+                                    }),
+                                );
+                            }
                         });
                     }
                 });
@@ -230,9 +575,11 @@ impl KlineSocketService {
                 // 取消订阅事件处理器 / Unsubscribe event handler
                 socket.on("unsubscribe", {
                     let subscriptions = subscriptions.clone();
+                    let aggregators = aggregators.clone();
 
                     move |socket: SocketRef, Data(data): Data<UnsubscribeRequest>| {
                         let subscriptions = subscriptions.clone();
+                        let aggregators = aggregators.clone();
 
                         tokio::spawn(async move {
                             info!(
@@ -250,10 +597,11 @@ impl KlineSocketService {
                                 );
                                 manager.update_activity(&socket.id.to_string());
                             }
+                            release_aggregators(&subscriptions, &aggregators, &data.symbol).await;
 
                             // 离开对应的房间 / Leave corresponding room
                             let room_name = format!("kline:{}:{}", data.symbol, data.interval);
-                            socket.leave(room_name);
+                            leave_room(&socket, &room_name);
 
                             // 确认取消订阅 / Confirm unsubscribe success
                             let _ = socket.emit(
@@ -291,16 +639,14 @@ impl KlineSocketService {
                                 manager.update_activity(&socket.id.to_string());
                             }
 
+                            // 超过上限时截断, 生效条数在响应的 limit 中返回 / Clamped to the max, the effective value is echoed in the response limit
                             match data_processor
-                                .get_kline_history(
-                                    &data.symbol,
-                                    &data.interval,
-                                    data.limit.unwrap_or(100),
-                                )
+                                .get_kline_history(&data.symbol, &data.interval, data.limit)
                                 .await
                             {
-                                Ok(history) => {
-                                    if let Err(e) = socket.emit("history_data", &history) {
+                                Ok(mut history) => {
+                                    filter_dust_candles(&mut history, data.min_volume);
+                                    if let Err(e) = emit_payload(&socket, "history_data", &history) {
                                         warn!("Failed to send history data: {}", e);
                                     } else {
                                         // 更新历史数据发送计数 / Update history data sent count
@@ -311,10 +657,15 @@ impl KlineSocketService {
                                     }
                                 }
                                 Err(e) => {
+                                    let code = if e.is::<InvalidHistoryLimit>() {
+                                        ErrorCode::InvalidParameter
+                                    } else {
+                                        ErrorCode::Storage
+                                    };
                                     let _ = socket.emit(
                                         "error",
                                         &serde_json::json!({
-                                            "code": 1003,
+                                            "code": code.as_u32(),
                                             "message": e.to_string()
                                         }),
                                     );
@@ -324,19 +675,294 @@ impl KlineSocketService {
                     }
                 });
 
+                // 成交流订阅处理器 / Trade stream subscribe handler
+                socket.on("subscribe_events", {
+                    let subscriptions = subscriptions.clone();
+                    let config = config.clone();
+
+                    move |socket: SocketRef, Data(data): Data<EventSubscribeRequest>| {
+                        let subscriptions = subscriptions.clone();
+                        let max_subscriptions = config.load().max_subscriptions_per_client;
+
+                        tokio::spawn(async move {
+                            info!("📊 Trade stream subscribe request from {}: {}", socket.id, data.symbol);
+
+                            let mint = match normalize_mint(&data.symbol) {
+                                Ok(mint) => mint,
+                                Err(code) => {
+                                    emit_subscribe_error(&socket, code, &data.symbol, None, data.subscription_id.clone());
+                                    return;
+                                }
+                            };
+
+                            {
+                                let mut manager = subscriptions.write().await;
+                                manager.max_subscriptions_per_client = max_subscriptions;
+                                if let Err(code) = manager.add_subscription(
+                                    &socket.id.to_string(),
+                                    &mint,
+                                    TRADE_STREAM_INTERVAL,
+                                ) {
+                                    emit_subscribe_error(&socket, code, &data.symbol, None, data.subscription_id.clone());
+                                    return;
+                                }
+                                manager.update_activity(&socket.id.to_string());
+                            }
+
+                            join_room(&socket, &trade_room(&mint));
+
+                            let _ = socket.emit(
+                                "subscribed",
+                                &SubscribedMessage {
+                                    mint: mint.clone(),
+                                    interval: TRADE_STREAM_INTERVAL.to_string(),
+                                    intervals: vec![TRADE_STREAM_INTERVAL.to_string()],
+                                    rejected: Vec::new(),
+                                    subscription_id: data.subscription_id.clone(),
+                                },
+                            );
+
+                            // 旧版确认消息, 保留兼容已有客户端 / Legacy confirmation, kept for existing clients
+                            let _ = socket.emit(
+                                "events_subscription_confirmed",
+                                &serde_json::json!({
+                                    "symbol": mint,
+                                    "subscription_id": data.subscription_id,
+                                    "success": true,
+                                    "message": "成交流订阅成功 / Trade stream subscription successful"
+                                }),
+                            );
+                        });
+                    }
+                });
+
+                // 成交流取消订阅处理器 / Trade stream unsubscribe handler
+                socket.on("unsubscribe_events", {
+                    let subscriptions = subscriptions.clone();
+
+                    move |socket: SocketRef, Data(data): Data<EventSubscribeRequest>| {
+                        let subscriptions = subscriptions.clone();
+
+                        tokio::spawn(async move {
+                            info!("🚫 Trade stream unsubscribe request from {}: {}", socket.id, data.symbol);
+
+                            {
+                                let mut manager = subscriptions.write().await;
+                                manager.remove_subscription(
+                                    &socket.id.to_string(),
+                                    &data.symbol,
+                                    TRADE_STREAM_INTERVAL,
+                                );
+                                manager.update_activity(&socket.id.to_string());
+                            }
+
+                            leave_room(&socket, &trade_room(&data.symbol));
+
+                            let _ = socket.emit(
+                                "events_unsubscribe_confirmed",
+                                &serde_json::json!({
+                                    "symbol": data.symbol,
+                                    "subscription_id": data.subscription_id,
+                                    "success": true,
+                                    "message": "取消成交流订阅成功 / Trade stream unsubscribe successful"
+                                }),
+                            );
+                        });
+                    }
+                });
+
+                // 新币推送订阅处理器 / New token feed subscribe handler
+                socket.on("subscribe_new_tokens", {
+                    let subscriptions = subscriptions.clone();
+                    let config = config.clone();
+
+                    move |socket: SocketRef, Data(data): Data<NewTokensSubscribeRequest>| {
+                        let subscriptions = subscriptions.clone();
+                        let max_subscriptions = config.load().max_subscriptions_per_client;
+
+                        tokio::spawn(async move {
+                            info!("🆕 New token feed subscribe request from {}", socket.id);
+
+                            {
+                                let mut manager = subscriptions.write().await;
+                                manager.max_subscriptions_per_client = max_subscriptions;
+                                if let Err(code) = manager.add_subscription(
+                                    &socket.id.to_string(),
+                                    NEW_TOKENS_MINT,
+                                    NEW_TOKENS_INTERVAL,
+                                ) {
+                                    emit_subscribe_error(&socket, code, NEW_TOKENS_MINT, None, data.subscription_id.clone());
+                                    return;
+                                }
+                                manager.update_activity(&socket.id.to_string());
+                            }
+
+                            join_room(&socket, NEW_TOKENS_ROOM);
+
+                            let _ = socket.emit(
+                                "subscribed",
+                                &SubscribedMessage {
+                                    mint: NEW_TOKENS_MINT.to_string(),
+                                    interval: NEW_TOKENS_INTERVAL.to_string(),
+                                    intervals: vec![NEW_TOKENS_INTERVAL.to_string()],
+                                    rejected: Vec::new(),
+                                    subscription_id: data.subscription_id,
+                                },
+                            );
+                        });
+                    }
+                });
+
+                // 新币推送取消订阅处理器 / New token feed unsubscribe handler
+                socket.on("unsubscribe_new_tokens", {
+                    let subscriptions = subscriptions.clone();
+
+                    move |socket: SocketRef, Data(data): Data<NewTokensSubscribeRequest>| {
+                        let subscriptions = subscriptions.clone();
+
+                        tokio::spawn(async move {
+                            info!("🚫 New token feed unsubscribe request from {}", socket.id);
+
+                            {
+                                let mut manager = subscriptions.write().await;
+                                manager.remove_subscription(
+                                    &socket.id.to_string(),
+                                    NEW_TOKENS_MINT,
+                                    NEW_TOKENS_INTERVAL,
+                                );
+                                manager.update_activity(&socket.id.to_string());
+                            }
+
+                            leave_room(&socket, NEW_TOKENS_ROOM);
+
+                            let _ = socket.emit(
+                                "new_tokens_unsubscribe_confirmed",
+                                &serde_json::json!({
+                                    "subscription_id": data.subscription_id,
+                                    "success": true,
+                                    "message": "取消新币推送订阅成功 / New token feed unsubscribe successful"
+                                }),
+                            );
+                        });
+                    }
+                });
+
+                // 大额成交推送订阅处理器 / Large trade feed subscribe handler
+                socket.on("subscribe_whale_trades", {
+                    let subscriptions = subscriptions.clone();
+                    let config = config.clone();
+
+                    move |socket: SocketRef, Data(data): Data<NewTokensSubscribeRequest>| {
+                        let subscriptions = subscriptions.clone();
+                        let max_subscriptions = config.load().max_subscriptions_per_client;
+
+                        tokio::spawn(async move {
+                            info!("🐋 Large trade feed subscribe request from {}", socket.id);
+
+                            {
+                                let mut manager = subscriptions.write().await;
+                                manager.max_subscriptions_per_client = max_subscriptions;
+                                if let Err(code) = manager.add_subscription(
+                                    &socket.id.to_string(),
+                                    WHALE_TRADES_MINT,
+                                    WHALE_TRADES_INTERVAL,
+                                ) {
+                                    emit_subscribe_error(&socket, code, WHALE_TRADES_MINT, None, data.subscription_id.clone());
+                                    return;
+                                }
+                                manager.update_activity(&socket.id.to_string());
+                            }
+
+                            join_room(&socket, WHALE_TRADES_ROOM);
+
+                            let _ = socket.emit(
+                                "subscribed",
+                                &SubscribedMessage {
+                                    mint: WHALE_TRADES_MINT.to_string(),
+                                    interval: WHALE_TRADES_INTERVAL.to_string(),
+                                    intervals: vec![WHALE_TRADES_INTERVAL.to_string()],
+                                    rejected: Vec::new(),
+                                    subscription_id: data.subscription_id,
+                                },
+                            );
+                        });
+                    }
+                });
+
+                // 大额成交推送取消订阅处理器 / Large trade feed unsubscribe handler
+                socket.on("unsubscribe_whale_trades", {
+                    let subscriptions = subscriptions.clone();
+
+                    move |socket: SocketRef, Data(data): Data<NewTokensSubscribeRequest>| {
+                        let subscriptions = subscriptions.clone();
+
+                        tokio::spawn(async move {
+                            info!("🚫 Large trade feed unsubscribe request from {}", socket.id);
+
+                            {
+                                let mut manager = subscriptions.write().await;
+                                manager.remove_subscription(
+                                    &socket.id.to_string(),
+                                    WHALE_TRADES_MINT,
+                                    WHALE_TRADES_INTERVAL,
+                                );
+                                manager.update_activity(&socket.id.to_string());
+                            }
+
+                            leave_room(&socket, WHALE_TRADES_ROOM);
+
+                            let _ = socket.emit(
+                                "whale_trades_unsubscribe_confirmed",
+                                &serde_json::json!({
+                                    "subscription_id": data.subscription_id,
+                                    "success": true,
+                                    "message": "取消大额成交订阅成功 / Large trade feed unsubscribe successful"
+                                }),
+                            );
+                        });
+                    }
+                });
+
                 // 连接断开事件处理器 / Disconnect event handler
                 socket.on_disconnect({
                     let subscriptions = subscriptions.clone();
+                    let aggregators = aggregators.clone();
+                    let connection_stats = connection_stats.clone();
 
-                    move |socket: SocketRef| {
+                    move |socket: SocketRef, reason: DisconnectReason| {
                         let subscriptions = subscriptions.clone();
+                        let aggregators = aggregators.clone();
+                        let category = disconnect_category(&reason);
+                        connection_stats.record_disconnect(category);
+                        let connected = connection_stats.metrics().connected;
 
                         tokio::spawn(async move {
-                            info!("🔌 Client disconnected: {}", socket.id);
-
                             // 清理客户端连接 / Clean up client connection
-                            let mut manager = subscriptions.write().await;
-                            manager.remove_client(&socket.id.to_string());
+                            let removed = subscriptions.write().await.remove_client(&socket.id.to_string());
+                            let (held_subscriptions, connected_secs) = removed
+                                .as_ref()
+                                .map(|c| (c.subscription_count, c.connection_time.elapsed().as_secs()))
+                                .unwrap_or_default();
+
+                            // 最后一个订阅者离开时释放聚合器 / Release aggregators once their last subscriber leaves
+                            let mints: HashSet<&str> = removed
+                                .iter()
+                                .flat_map(|c| c.subscriptions.iter())
+                                .filter_map(|key| key.split_once(':').map(|(mint, _)| mint))
+                                .collect();
+                            for mint in mints {
+                                release_aggregators(&subscriptions, &aggregators, mint).await;
+                            }
+
+                            info!(
+                                socket_id = %socket.id,
+                                reason = category,
+                                detail = %reason,
+                                held_subscriptions,
+                                connected_secs,
+                                connected,
+                                "🔌 K线客户端已断开 / K-line client disconnected"
+                            );
                         });
                     }
                 });
@@ -344,6 +970,25 @@ impl KlineSocketService {
         });
     }
 
+    /// 把数据推送发到房间的每个编码版本, 每种编码只序列化一次; 没有订阅者的编码版本直接跳过, 不做序列化
+    /// Send a data push to every encoding variant of a room, serializing once per encoding; variants without subscribers are skipped before serializing
+    async fn broadcast_payload<T: Serialize>(&self, room: &str, event: &'static str, data: &T) -> Result<()> {
+        for encoding in PayloadEncoding::ALL {
+            let encoded_room = encoding.room(room);
+            let has_subscribers = self
+                .socketio
+                .of("/kline")
+                .is_some_and(|ns| !ns.to(encoded_room.clone()).sockets().is_empty());
+            if !has_subscribers {
+                continue;
+            }
+            EncodedPayload::encode(encoding, data)?
+                .broadcast_to(&self.socketio, encoded_room, event)
+                .await?;
+        }
+        Ok(())
+    }
+
     /// 广播K线更新到订阅者 / Broadcast K-line update to subscribers
     pub async fn broadcast_kline_update(
         &self,
@@ -387,13 +1032,7 @@ impl KlineSocketService {
         }
 
         // 发送到 /kline 命名空间的房间 / Send to /kline namespace room
-        let result = self
-            .socketio
-            .of("/kline")
-            .ok_or_else(|| anyhow::anyhow!("Namespace /kline not found"))?
-            .to(room_name.clone())
-            .emit("kline_data", &update_message)
-            .await;
+        let result = self.broadcast_payload(&room_name, "kline_data", &update_message).await;
 
         match result {
             Ok(_) => {
@@ -439,13 +1078,7 @@ impl KlineSocketService {
         for interval in intervals {
             let room_name = format!("kline:{}:{}", mint_account, interval);
 
-            let result = self
-                .socketio
-                .of("/kline")
-                .ok_or_else(|| anyhow::anyhow!("Namespace /kline not found"))?
-                .to(room_name.clone())
-                .emit("event_data", &event_message)
-                .await;
+            let result = self.broadcast_payload(&room_name, "event_data", &event_message).await;
 
             match result {
                 Ok(_) => {
@@ -465,19 +1098,121 @@ impl KlineSocketService {
         Ok(())
     }
 
+    /// 推送实时成交到成交流订阅者 / Push a live trade to trade stream subscribers
+    pub async fn broadcast_trade_event(&self, event: &PinpetEvent) -> Result<()> {
+        let Some(message) = KlineDataProcessor::to_trade_message(event) else {
+            return Ok(());
+        };
+
+        let room_name = trade_room(&message.symbol);
+        let subscribers = {
+            let manager = self.subscriptions.read().await;
+            manager.get_subscribers(&message.symbol, TRADE_STREAM_INTERVAL)
+        };
+        if subscribers.is_empty() {
+            debug!("成交流无订阅者, 跳过 / No trade stream subscribers, skipping: {}", room_name);
+            return Ok(());
+        }
+
+        self.broadcast_payload(&room_name, "trade_event", &message)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to broadcast trade event to room {}: {}", room_name, e))?;
+
+        debug!(
+            "✅ Trade event broadcasted to room {} ({} subscribers)",
+            room_name,
+            subscribers.len()
+        );
+        Ok(())
+    }
+
+    /// 推送单笔 SOL 数量不低于 kline.whale_min_sol 的成交到大额成交订阅者
+    /// Push a trade whose SOL amount is at least kline.whale_min_sol to large trade feed subscribers
+    pub async fn broadcast_whale_trade(&self, event: &PinpetEvent) -> Result<()> {
+        let Some(message) = KlineDataProcessor::to_trade_message(event) else {
+            return Ok(());
+        };
+        if message.sol_amount < self.config.load().whale_min_sol {
+            return Ok(());
+        }
+
+        let subscribers = {
+            let manager = self.subscriptions.read().await;
+            manager.get_subscribers(WHALE_TRADES_MINT, WHALE_TRADES_INTERVAL)
+        };
+        if subscribers.is_empty() {
+            debug!("大额成交推送无订阅者, 跳过 / No large trade feed subscribers, skipping: {}", message.signature);
+            return Ok(());
+        }
+
+        self.broadcast_payload(WHALE_TRADES_ROOM, "whale_trade", &message)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to broadcast large trade {}: {}", message.signature, e))?;
+
+        debug!(
+            "✅ Large trade {} ({} lamports) broadcasted ({} subscribers)",
+            message.signature,
+            message.sol_amount,
+            subscribers.len()
+        );
+        Ok(())
+    }
+
+    /// 推送新币到新币推送订阅者 / Push a new token to new token feed subscribers
+    pub async fn broadcast_new_token(&self, event: &TokenCreatedEvent) -> Result<()> {
+        let subscribers = {
+            let manager = self.subscriptions.read().await;
+            manager.get_subscribers(NEW_TOKENS_MINT, NEW_TOKENS_INTERVAL)
+        };
+        if subscribers.is_empty() {
+            debug!("新币推送无订阅者, 跳过 / No new token feed subscribers, skipping: {}", event.mint_account);
+            return Ok(());
+        }
+
+        let message = TokenCreatedMessage {
+            mint: event.mint_account.clone(),
+            name: event.name.clone(),
+            symbol: event.symbol.clone(),
+            uri: event.uri.clone(),
+            creator: event.payer.clone(),
+            signature: event.signature.clone(),
+            slot: event.slot,
+            created_at: event.timestamp.timestamp_millis(),
+            timestamp: Utc::now().timestamp_millis() as u64,
+        };
+
+        self.broadcast_payload(NEW_TOKENS_ROOM, "token_created", &message)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to broadcast new token {}: {}", event.mint_account, e))?;
+
+        debug!(
+            "✅ New token {} broadcasted ({} subscribers)",
+            event.mint_account,
+            subscribers.len()
+        );
+        Ok(())
+    }
+
     /// 获取服务统计信息 / Get service statistics
     pub async fn get_service_stats(&self) -> serde_json::Value {
+        let aggregators = self.aggregators.metrics();
         let manager = self.subscriptions.read().await;
+        let config = self.config.load();
 
         serde_json::json!({
             "active_connections": manager.connections.len(),
             "total_subscriptions": manager.client_subscriptions.values().map(|s| s.len()).sum::<usize>(),
             "monitored_mints": manager.mint_subscribers.len(),
+            "aggregated_mints": aggregators.mints,
+            "aggregators": aggregators.aggregators,
             "config": {
-                "connection_timeout": self.config.connection_timeout_secs,
-                "max_subscriptions_per_client": self.config.max_subscriptions_per_client,
-                "ping_interval": self.config.ping_interval_secs,
-                "ping_timeout": self.config.ping_timeout_secs
+                "connection_timeout": config.connection_timeout_secs,
+                "max_subscriptions_per_client": config.max_subscriptions_per_client,
+                "history_data_limit": config.history_data_limit,
+                "ping_interval": config.ping_interval_secs,
+                "ping_timeout": config.ping_timeout_secs,
+                "max_aggregated_mints": config.max_aggregated_mints,
+                "whale_min_sol": config.whale_min_sol
             }
         })
     }
@@ -533,20 +1268,158 @@ impl KlineSocketService {
     }
 }
 
-/// 验证订阅请求 / Validate subscribe request
-fn validate_subscribe_request(req: &SubscribeRequest) -> Result<()> {
-    // 验证时间间隔 / Validate interval
-    if !["s1", "s30", "m5"].contains(&req.interval.as_str()) {
-        return Err(anyhow::anyhow!(
-            "Invalid interval: {}, must be one of: s1, s30, m5",
-            req.interval
-        ));
+/// 释放 mint 上已没有K线订阅者的聚合器 (预热列表除外)
+/// Release the mint's aggregators that no longer have K-line subscribers (watchlist excepted)
+async fn release_aggregators(
+    subscriptions: &RwLock<SubscriptionManager>,
+    aggregators: &KlineAggregators,
+    mint: &str,
+) {
+    let subscribed: HashSet<&str> = {
+        let manager = subscriptions.read().await;
+        KLINE_INTERVALS
+            .iter()
+            .copied()
+            .filter(|interval| !manager.get_subscribers(mint, interval).is_empty())
+            .collect()
+    };
+    aggregators
+        .evict_unsubscribed(mint, |interval| subscribed.contains(interval))
+        .await;
+}
+
+/// 规范化 mint 地址 (去除空白) 并校验为合法的 Solana 地址 / Normalize a mint address (trim whitespace) and check it is a valid Solana address
+fn normalize_mint(mint: &str) -> std::result::Result<String, SubscribeErrorCode> {
+    let mint = mint.trim();
+    if Pubkey::from_str(mint).is_err() {
+        return Err(SubscribeErrorCode::InvalidMint);
+    }
+    Ok(mint.to_string())
+}
+
+/// 规范化并验证订阅请求, 返回 (mint, 接受的间隔, 拒绝的间隔); 没有可接受的间隔时返回错误
+/// Normalize and validate a subscribe request, returns (mint, accepted intervals, rejected intervals);
+/// errors when no interval is acceptable
+fn normalize_subscribe_request(
+    req: &SubscribeRequest,
+) -> std::result::Result<(String, Vec<String>, Vec<RejectedInterval>), SubscribeErrorCode> {
+    let mint = normalize_mint(&req.symbol)?;
+
+    let mut accepted: Vec<String> = Vec::new();
+    let mut rejected = Vec::new();
+    for raw in req.requested_intervals() {
+        let interval = raw.trim().to_ascii_lowercase();
+        if !KLINE_INTERVALS.contains(&interval.as_str()) {
+            rejected.push(RejectedInterval {
+                interval: raw.to_string(),
+                code: SubscribeErrorCode::UnknownInterval.error_code().as_u32(),
+            });
+        } else if !accepted.contains(&interval) {
+            accepted.push(interval);
+        }
+    }
+
+    if accepted.is_empty() {
+        return Err(SubscribeErrorCode::UnknownInterval);
+    }
+    Ok((mint, accepted, rejected))
+}
+
+/// 发送订阅失败消息 / Emit a subscription failure message
+fn emit_subscribe_error(
+    socket: &SocketRef,
+    code: SubscribeErrorCode,
+    mint: &str,
+    interval: Option<&str>,
+    subscription_id: Option<String>,
+) {
+    let message = match code {
+        SubscribeErrorCode::UnknownInterval => format!(
+            "不支持的间隔 / Unknown interval: {}, 可选 / must be one of: {}",
+            interval.unwrap_or_default(),
+            KLINE_INTERVALS.join(", ")
+        ),
+        SubscribeErrorCode::LimitExceeded => "超出订阅上限 / Subscription limit exceeded".to_string(),
+        SubscribeErrorCode::InvalidMint => format!("无效的 mint 地址 / Invalid mint address: {}", mint),
+        SubscribeErrorCode::NotConnected => "连接已断开 / Connection closed".to_string(),
+    };
+    debug!("订阅失败 / Subscription failed: {} {:?} ({:?})", socket.id, code, message);
+
+    let _ = socket.emit(
+        "error",
+        &SubscribeErrorMessage {
+            code: code.error_code().as_u32(),
+            message,
+            mint: mint.to_string(),
+            interval: interval.map(str::to_string),
+            subscription_id,
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encoding_negotiated_from_query() {
+        assert_eq!(PayloadEncoding::from_query(None), PayloadEncoding::Json);
+        assert_eq!(PayloadEncoding::from_query(Some("EIO=4&transport=websocket")), PayloadEncoding::Json);
+        assert_eq!(
+            PayloadEncoding::from_query(Some("EIO=4&encoding=msgpack&transport=websocket")),
+            PayloadEncoding::MessagePack
+        );
+        assert_eq!(PayloadEncoding::from_query(Some("encoding=MessagePack")), PayloadEncoding::MessagePack);
+        assert_eq!(PayloadEncoding::from_query(Some("encoding=cbor")), PayloadEncoding::Json);
+
+        assert_eq!(PayloadEncoding::Json.room("kline:abc:s1"), "kline:abc:s1");
+        assert_eq!(PayloadEncoding::MessagePack.room("kline:abc:s1"), "kline:abc:s1#msgpack");
     }
 
-    // 验证symbol格式（基本的Solana地址格式检查）/ Validate symbol format (basic Solana address format check)
-    if req.symbol.len() < 32 || req.symbol.len() > 44 {
-        return Err(anyhow::anyhow!("Invalid symbol format"));
+    #[test]
+    fn test_subscribe_errors_use_numeric_error_codes() {
+        let request: SubscribeRequest = serde_json::from_value(serde_json::json!({
+            "symbol": " So11111111111111111111111111111111111111112 ",
+            "intervals": ["s1", "h1"],
+        }))
+        .unwrap();
+        let (mint, intervals, rejected) = normalize_subscribe_request(&request).unwrap();
+        assert_eq!(mint, "So11111111111111111111111111111111111111112");
+        assert_eq!(intervals, vec!["s1".to_string()]);
+        assert_eq!(rejected.len(), 1);
+        assert_eq!(rejected[0].code, 1004);
+
+        assert_eq!(normalize_mint("not-a-mint"), Err(SubscribeErrorCode::InvalidMint));
+        assert_eq!(SubscribeErrorCode::InvalidMint.error_code().as_u32(), 1005);
+        assert_eq!(SubscribeErrorCode::LimitExceeded.error_code().as_u32(), 1006);
+        assert_eq!(SubscribeErrorCode::NotConnected.error_code(), ErrorCode::Conflict);
     }
 
-    Ok(())
+    #[test]
+    fn test_msgpack_payload_keeps_field_names_and_is_smaller() {
+        let message = KlineUpdateMessage {
+            symbol: "So11111111111111111111111111111111111111112".to_string(),
+            interval: "s1".to_string(),
+            subscription_id: None,
+            data: KlineRealtimeData {
+                time: 1_700_000_000,
+                open: 0.000123,
+                high: 0.000125,
+                low: 0.000121,
+                close: 0.000124,
+                volume: 15_000.5,
+                is_final: false,
+                update_type: "realtime".to_string(),
+                update_count: 3,
+            },
+            timestamp: 1_700_000_000_123,
+        };
+
+        let EncodedPayload::MessagePack(packed) = EncodedPayload::encode(PayloadEncoding::MessagePack, &message).unwrap() else {
+            panic!("expected a MessagePack payload");
+        };
+        let decoded: serde_json::Value = rmp_serde::from_slice(&packed).unwrap();
+        assert_eq!(decoded, serde_json::to_value(&message).unwrap());
+        assert!(packed.len() < serde_json::to_vec(&message).unwrap().len());
+    }
 }
@@ -1,8 +1,9 @@
 // 订阅管理器 / Subscription manager
-use anyhow::Result;
 use std::collections::{HashMap, HashSet};
 use std::time::Instant;
 
+use super::types::SubscribeErrorCode;
+
 /// 客户端连接信息 / Client connection information
 #[derive(Debug, Clone)]
 pub struct ClientConnection {
@@ -63,37 +64,61 @@ impl SubscriptionManager {
     }
 
     /// 添加订阅 / Add subscription
-    pub fn add_subscription(&mut self, socket_id: &str, mint: &str, interval: &str) -> Result<()> {
+    pub fn add_subscription(
+        &mut self,
+        socket_id: &str,
+        mint: &str,
+        interval: &str,
+    ) -> Result<(), SubscribeErrorCode> {
+        self.add_subscriptions(socket_id, mint, &[interval.to_string()])
+    }
+
+    /// 原子地添加同一 mint 的多个间隔订阅: 每个新间隔计入上限, 超限时一个都不添加
+    /// Atomically add subscriptions for several intervals of one mint: each new interval counts against the limit,
+    /// and none are added when the limit would be exceeded
+    pub fn add_subscriptions(
+        &mut self,
+        socket_id: &str,
+        mint: &str,
+        intervals: &[String],
+    ) -> Result<(), SubscribeErrorCode> {
         // 检查客户端是否存在 / Check if client exists
         let client = self
             .connections
             .get_mut(socket_id)
-            .ok_or_else(|| anyhow::anyhow!("Client not found"))?;
+            .ok_or(SubscribeErrorCode::NotConnected)?;
 
-        // 检查订阅数量限制 / Check subscription limit
-        if client.subscription_count >= self.max_subscriptions_per_client {
-            return Err(anyhow::anyhow!("Subscription limit exceeded"));
+        // 检查订阅数量限制, 重复订阅不计入 / Check subscription limit, re-subscribing does not count
+        let new_keys: HashSet<String> = intervals
+            .iter()
+            .map(|interval| format!("{}:{}", mint, interval))
+            .filter(|key| !client.subscriptions.contains(key))
+            .collect();
+        if client.subscription_count + new_keys.len() > self.max_subscriptions_per_client {
+            return Err(SubscribeErrorCode::LimitExceeded);
         }
 
-        let subscription_key = format!("{}:{}", mint, interval);
-
-        // 添加到客户端订阅列表 / Add to client subscription list
-        if client.subscriptions.insert(subscription_key.clone()) {
-            client.subscription_count += 1;
-
-            // 添加到全局索引 / Add to global index
-            self.mint_subscribers
-                .entry(mint.to_string())
-                .or_default()
-                .entry(interval.to_string())
-                .or_default()
-                .insert(socket_id.to_string());
-
-            // 添加到反向索引 / Add to reverse index
-            self.client_subscriptions
-                .entry(socket_id.to_string())
-                .or_default()
-                .insert(subscription_key);
+        for interval in intervals {
+            let subscription_key = format!("{}:{}", mint, interval);
+
+            // 添加到客户端订阅列表 / Add to client subscription list
+            if client.subscriptions.insert(subscription_key.clone()) {
+                client.subscription_count += 1;
+
+                // 添加到全局索引 / Add to global index
+                self.mint_subscribers
+                    .entry(mint.to_string())
+                    .or_default()
+                    .entry(interval.to_string())
+                    .or_default()
+                    .insert(socket_id.to_string());
+
+                // 添加到反向索引 / Add to reverse index
+                self.client_subscriptions
+                    .entry(socket_id.to_string())
+                    .or_default()
+                    .insert(subscription_key);
+            }
         }
 
         Ok(())
@@ -140,8 +165,10 @@ impl SubscriptionManager {
             .unwrap_or_default()
     }
 
-    /// 移除客户端 / Remove client
-    pub fn remove_client(&mut self, socket_id: &str) {
+    /// 移除客户端, 返回移除前的连接信息 (含持有的订阅数) / Remove client, returning its connection info before removal (including held subscriptions)
+    pub fn remove_client(&mut self, socket_id: &str) -> Option<ClientConnection> {
+        let removed = self.connections.get(socket_id).cloned();
+
         // 获取该客户端的所有订阅 / Get all subscriptions of this client
         if let Some(subscriptions) = self.client_subscriptions.remove(socket_id) {
             for subscription_key in subscriptions {
@@ -155,6 +182,7 @@ impl SubscriptionManager {
 
         // 移除连接记录 / Remove connection record
         self.connections.remove(socket_id);
+        removed
     }
 
     /// 更新活动时间 / Update activity time
@@ -0,0 +1,120 @@
+// K线后台任务管理 - 统一的停止信号和等待退出
+// K-line background task management - shared stop signal and waiting for exit
+
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+/// 停止信号接收端, 任务在自己的循环中 select 它, 保证当前消息发完再退出
+/// Stop signal receiver; tasks select on it in their own loop so the current message is fully sent before exiting
+pub type ShutdownSignal = watch::Receiver<bool>;
+
+/// 等待停止信号, 发送端被丢弃也视为停止 / Wait for the stop signal, a dropped sender also counts as stop
+pub async fn stopped(signal: &mut ShutdownSignal) {
+    let _ = signal.wait_for(|stop| *stop).await;
+}
+
+/// K线后台任务集合 / K-line background task set
+pub struct KlineTasks {
+    shutdown: watch::Sender<bool>,
+    handles: Mutex<Vec<(&'static str, JoinHandle<()>)>>,
+}
+
+impl Default for KlineTasks {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl KlineTasks {
+    /// 创建任务集合 / Create the task set
+    pub fn new() -> Self {
+        Self {
+            shutdown: watch::channel(false).0,
+            handles: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// 启动后台任务, 任务收到停止信号后应尽快返回 / Spawn a background task, which should return promptly once signalled
+    pub fn spawn<F, Fut>(&self, name: &'static str, task: F)
+    where
+        F: FnOnce(ShutdownSignal) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let handle = tokio::spawn(task(self.shutdown.subscribe()));
+        self.handles
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push((name, handle));
+    }
+
+    /// 发送停止信号并等待全部任务退出, 超过 `grace` 仍未退出的任务被中止, 返回被中止的任务数
+    /// Signal stop and wait for every task to exit; tasks still running after `grace` are aborted, returns the number aborted
+    pub async fn shutdown(&self, grace: Duration) -> usize {
+        self.shutdown.send_replace(true);
+        let handles = std::mem::take(&mut *self.handles.lock().unwrap_or_else(|e| e.into_inner()));
+
+        let deadline = tokio::time::Instant::now() + grace;
+        let mut aborted = 0;
+        for (name, mut handle) in handles {
+            if tokio::time::timeout_at(deadline, &mut handle).await.is_err() {
+                warn!("K线后台任务未在时限内退出, 已中止 / K-line background task did not stop in time, aborted: {}", name);
+                handle.abort();
+                aborted += 1;
+            }
+        }
+        info!("🛑 K线后台任务已停止 / K-line background tasks stopped (aborted={})", aborted);
+        aborted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_shutdown_stops_looping_tasks_promptly() {
+        let tasks = KlineTasks::new();
+        let (tx, mut rx) = tokio::sync::mpsc::channel::<u32>(8);
+
+        // 模拟推送循环: 在消息和停止信号之间 select / Mimic the push loop: select between messages and the stop signal
+        tasks.spawn("push", |mut signal| async move {
+            loop {
+                tokio::select! {
+                    _ = stopped(&mut signal) => break,
+                    message = rx.recv() => {
+                        if message.is_none() {
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+        // 模拟长时间回填 / Mimic a long backfill
+        tasks.spawn("preheat", |mut signal| async move {
+            tokio::select! {
+                _ = stopped(&mut signal) => {}
+                _ = tokio::time::sleep(Duration::from_secs(3600)) => {}
+            }
+        });
+        tx.send(1).await.unwrap();
+
+        let started = std::time::Instant::now();
+        assert_eq!(tasks.shutdown(Duration::from_secs(5)).await, 0);
+        assert!(started.elapsed() < Duration::from_secs(1));
+        drop(tx);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_aborts_tasks_ignoring_the_signal() {
+        let tasks = KlineTasks::new();
+        tasks.spawn("stuck", |_signal| async move {
+            tokio::time::sleep(Duration::from_secs(3600)).await;
+        });
+
+        assert_eq!(tasks.shutdown(Duration::from_millis(50)).await, 1);
+    }
+}
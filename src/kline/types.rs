@@ -1,4 +1,5 @@
 // K线数据类型定义 / K-line data type definitions
+use crate::util::error_code::ErrorCode;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
@@ -34,6 +35,7 @@ pub struct KlineHistoryResponse {
     pub data: Vec<KlineRealtimeData>, // K线数据列表 / K-line data list
     pub has_more: bool,              // 是否有更多数据 / Has more data
     pub total_count: usize,          // 总数量 / Total count
+    pub limit: usize,                // 实际生效的条数上限 (已按 history_data_limit 截断) / Effective limit (clamped to history_data_limit)
 }
 
 /// 交易事件推送消息 / Event update message
@@ -54,11 +56,123 @@ pub struct EventHistoryResponse {
     pub total_count: usize,              // 总数量 / Total count
 }
 
+/// 实时成交推送消息 (精简载荷) / Live trade push message (compact payload)
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct TradeEventMessage {
+    pub symbol: String,                  // mint地址 / mint address
+    pub event_type: String,              // 事件类型名称 / Event type name
+    pub side: String,                    // buy/sell/long/short/close_long/close_short 方向 / Side
+    pub user: String,                    // 用户地址 / User address
+    pub sol_amount: u64,                 // SOL数量 / SOL amount
+    pub token_amount: u64,               // Token数量 / Token amount
+    pub price: String,                   // 最新价格(u128字符串) / Latest price (u128 as string)
+    pub order_id: Option<u64>,           // 订单ID(保证金事件) / Order ID (margin events)
+    pub signature: String,               // 交易签名 / Transaction signature
+    pub slot: u64,                       // 区块高度 / Slot
+    pub event_time: i64,                 // 事件时间戳(毫秒) / Event timestamp (ms)
+    pub timestamp: u64,                  // 推送时间戳(毫秒) / Push timestamp (ms)
+}
+
+/// 新币推送消息 (`token_created`) / New token push message (`token_created`)
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct TokenCreatedMessage {
+    pub mint: String,                    // mint地址 / mint address
+    pub name: String,                    // 代币名称 / Token name
+    pub symbol: String,                  // 代币符号 / Token symbol
+    pub uri: String,                     // 元数据URI / Metadata URI
+    pub creator: String,                 // 创建者地址 / Creator address
+    pub signature: String,               // 交易签名 / Transaction signature
+    pub slot: u64,                       // 区块高度 / Slot
+    pub created_at: i64,                 // 创建时间戳(毫秒) / Creation timestamp (ms)
+    pub timestamp: u64,                  // 推送时间戳(毫秒) / Push timestamp (ms)
+}
+
+/// Socket.IO新币推送订阅请求 / Socket.IO new token feed subscribe request
+#[derive(Debug, Default, Deserialize)]
+pub struct NewTokensSubscribeRequest {
+    #[serde(default)]
+    pub subscription_id: Option<String>, // 客户端订阅ID / Client subscription ID
+}
+
+/// Socket.IO成交流订阅请求 / Socket.IO trade stream subscribe request
+#[derive(Debug, Deserialize)]
+pub struct EventSubscribeRequest {
+    pub symbol: String,                  // mint地址 / mint address
+    pub subscription_id: Option<String>, // 客户端订阅ID / Client subscription ID
+}
+
 /// Socket.IO订阅请求 / Socket.IO subscribe request
 #[derive(Debug, Deserialize)]
 pub struct SubscribeRequest {
     pub symbol: String,                  // mint_account mint地址 / mint address
-    pub interval: String,                // s1, s30, m5 时间间隔 / time interval
+    #[serde(default)]
+    pub interval: String,                // s1, s30, m5 单个时间间隔 (兼容旧版) / Single time interval (legacy)
+    #[serde(default)]
+    pub intervals: Vec<String>,          // 多个时间间隔, 一次订阅 / Multiple time intervals in one message
+    pub subscription_id: Option<String>, // 客户端订阅ID / Client subscription ID
+    #[serde(default)]
+    pub min_volume: Option<f64>,         // 过滤成交量低于此值的K线, 默认不过滤 / Drop candles below this volume, off by default
+}
+
+impl SubscribeRequest {
+    /// 请求的全部间隔 (interval 与 intervals 合并) / All requested intervals (interval merged with intervals)
+    pub fn requested_intervals(&self) -> Vec<&str> {
+        let mut requested: Vec<&str> = self.intervals.iter().map(String::as_str).collect();
+        if !self.interval.is_empty() || requested.is_empty() {
+            requested.insert(0, self.interval.as_str());
+        }
+        requested
+    }
+}
+
+/// 支持的K线间隔 / Supported K-line intervals
+pub const KLINE_INTERVALS: &[&str] = &["s1", "s30", "m5"];
+
+/// 订阅失败原因, 以 ErrorCode 数值发送给客户端 / Subscription failure reason, sent to clients as its numeric ErrorCode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubscribeErrorCode {
+    UnknownInterval, // 不支持的间隔 / Unsupported interval
+    LimitExceeded,   // 超出每客户端订阅上限 / Per-client subscription limit exceeded
+    InvalidMint,     // mint 不是合法的 Solana 地址 / Mint is not a valid Solana address
+    NotConnected,    // 连接已断开 / Connection already closed
+}
+
+impl SubscribeErrorCode {
+    /// 对应的稳定错误码 / The matching stable error code
+    pub const fn error_code(self) -> ErrorCode {
+        match self {
+            SubscribeErrorCode::UnknownInterval => ErrorCode::UnknownInterval,
+            SubscribeErrorCode::LimitExceeded => ErrorCode::SubscriptionLimitExceeded,
+            SubscribeErrorCode::InvalidMint => ErrorCode::InvalidMint,
+            SubscribeErrorCode::NotConnected => ErrorCode::Conflict,
+        }
+    }
+}
+
+/// 被拒绝的订阅间隔 / Rejected subscription interval
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RejectedInterval {
+    pub interval: String,                // 客户端发送的间隔 / Interval as sent by the client
+    pub code: u32,                       // 拒绝原因错误码 / Rejection error code
+}
+
+/// 订阅成功确认 (`subscribed` 消息), 回显规范化后的订阅 / Subscription ACK (`subscribed` message), echoing the normalized subscription
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SubscribedMessage {
+    pub mint: String,                    // 规范化后的 mint 地址 / Normalized mint address
+    pub interval: String,                // 第一个已接受的间隔, 成交流为 "trades" / First accepted interval, "trades" for the trade stream
+    pub intervals: Vec<String>,          // 全部已接受的间隔 / All accepted intervals
+    pub rejected: Vec<RejectedInterval>, // 被拒绝的间隔 / Rejected intervals
+    pub subscription_id: Option<String>, // 客户端订阅ID / Client subscription ID
+}
+
+/// 订阅失败消息 (`error` 消息) / Subscription failure message (`error` message)
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SubscribeErrorMessage {
+    pub code: u32,                       // 稳定错误码, 见 ErrorCode / Stable error code, see ErrorCode
+    pub message: String,                 // 可读错误信息 / Human-readable message
+    pub mint: String,                    // 客户端发送的 mint / Mint as sent by the client
+    pub interval: Option<String>,        // 客户端发送的间隔 / Interval as sent by the client
     pub subscription_id: Option<String>, // 客户端订阅ID / Client subscription ID
 }
 
@@ -75,8 +189,10 @@ pub struct UnsubscribeRequest {
 pub struct HistoryRequest {
     pub symbol: String,        // mint地址 / mint address
     pub interval: String,      // 时间间隔 / time interval
-    pub limit: Option<usize>,  // 返回数量限制 / Return limit
+    pub limit: Option<i64>,    // 返回数量限制, 必须大于0, 超过 history_data_limit 时截断 / Return limit, must be > 0, clamped to history_data_limit
     pub from: Option<u64>,     // 开始时间戳(秒) / Start timestamp (seconds)
+    #[serde(default)]
+    pub min_volume: Option<f64>, // 过滤成交量低于此值的K线, 默认不过滤 / Drop candles below this volume, off by default
 }
 
 /// K线配置 / K-line configuration
@@ -84,9 +200,27 @@ pub struct HistoryRequest {
 pub struct KlineConfig {
     pub connection_timeout_secs: u64,        // 连接超时时间(秒) / Connection timeout (seconds)
     pub max_subscriptions_per_client: usize, // 每客户端最大订阅数 / Max subscriptions per client
-    pub history_data_limit: usize,           // 历史数据默认条数 / History data default limit
+    pub history_data_limit: usize,           // 历史数据条数上限(也是默认值) / History data max limit (also the default)
+    pub snapshot_candles: usize,             // 订阅快照K线数 / Subscribe snapshot candles
     pub ping_interval_secs: u64,             // 心跳间隔(秒) / Ping interval (seconds)
     pub ping_timeout_secs: u64,              // 心跳超时(秒) / Ping timeout (seconds)
+    pub max_aggregated_mints: usize,         // 非预热 mint 聚合器上限 (LRU) / Max non-watchlist mint aggregators (LRU)
+    pub whale_min_sol: u64,                  // 大额成交推送阈值(lamports) / Large trade push threshold (lamports)
+}
+
+impl From<&crate::config::KlineServiceConfig> for KlineConfig {
+    fn from(config: &crate::config::KlineServiceConfig) -> Self {
+        Self {
+            connection_timeout_secs: config.connection_timeout_secs,
+            max_subscriptions_per_client: config.max_subscriptions_per_client,
+            history_data_limit: config.history_data_limit,
+            snapshot_candles: config.snapshot_candles,
+            ping_interval_secs: config.ping_interval_secs,
+            ping_timeout_secs: config.ping_timeout_secs,
+            max_aggregated_mints: config.max_aggregated_mints,
+            whale_min_sol: config.whale_min_sol,
+        }
+    }
 }
 
 impl Default for KlineConfig {
@@ -95,8 +229,11 @@ impl Default for KlineConfig {
             connection_timeout_secs: 60,
             max_subscriptions_per_client: 100,
             history_data_limit: 100,
+            snapshot_candles: 10,
             ping_interval_secs: 25,
             ping_timeout_secs: 60,
+            max_aggregated_mints: 1000,
+            whale_min_sol: 10_000_000_000,
         }
     }
 }
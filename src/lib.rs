@@ -9,6 +9,7 @@ pub mod orderbook;
 pub mod router;
 pub mod solana;
 pub mod util;
+pub mod webhook;
 
 // Re-export commonly used types
 // 重导出常用类型
@@ -4,21 +4,29 @@ mod docs;
 mod kline;
 mod orderbook;
 mod router;
+mod selftest;
 mod solana;
 mod util;
+mod webhook;
 
+use anyhow::Context;
+use arc_swap::ArcSwap;
 use axum::Router;
 use std::sync::Arc;
-use tower_http::cors::{Any, CorsLayer};
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, fmt};
+use tracing_subscriber::{layer::SubscriberExt, reload, util::SubscriberInitExt, fmt, EnvFilter, Registry};
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
+/// OpenAPI JSON 路径 / OpenAPI JSON path
+const OPENAPI_JSON_PATH: &str = "/api-docs/openapi.json";
+
 #[tokio::main]
-async fn main() {
+async fn main() -> anyhow::Result<()> {
+    let args = selftest::CliArgs::parse()?;
+
     // 初始化日志 / Initialize logging
     // 创建日志目录(如果不存在) / Create logs directory if it doesn't exist
-    std::fs::create_dir_all("logs").expect("无法创建 logs 目录 / Cannot create logs directory");
+    std::fs::create_dir_all("logs").context("无法创建 logs 目录 / Cannot create logs directory")?;
 
     // 配置文件日志输出 / Configure file logging
     let file_appender = tracing_appender::rolling::daily("logs", "pinpet-server.log");
@@ -27,9 +35,10 @@ async fn main() {
     // 配置控制台日志输出 / Configure console logging
     let (non_blocking_stdout, _guard2) = tracing_appender::non_blocking(std::io::stdout());
 
-    // 环境过滤器 / Environment filter
-    let env_filter = tracing_subscriber::EnvFilter::try_from_default_env()
+    // 环境过滤器 (可热加载) / Environment filter (hot-reloadable)
+    let env_filter = EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| "pinpet_server_v2=debug,tower_http=debug".into());
+    let (env_filter, log_filter_handle) = reload::Layer::new(env_filter);
 
     // 初始化订阅器,同时输出到文件和控制台 / Initialize subscriber with both file and console output
     tracing_subscriber::registry()
@@ -49,143 +58,293 @@ async fn main() {
     tracing::info!("启动 Pinpet Server v2...");
     tracing::info!("📝 日志输出到: logs/pinpet-server.log.* / Logging to: logs/pinpet-server.log.*");
 
+    // 自检模式: 校验环境后退出, 不启动监听 / Self-test mode: validate the environment and exit without serving
+    if args.selftest {
+        return selftest::run().await;
+    }
+
+    // 启动失败时记录完整错误链, 返回错误使进程以非0状态码退出
+    // On startup failure log the full error chain; returning the error makes the process exit non-zero
+    let result = run(log_filter_handle).await;
+    if let Err(ref e) = result {
+        tracing::error!("❌ 服务器异常退出 / Server exited with error: {:#}", e);
+    }
+    result
+}
+
+/// 初始化存储、监听器和路由并运行 HTTP 服务 / Initialize storage, listeners and routes, then serve HTTP
+async fn run(log_filter_handle: reload::Handle<EnvFilter, Registry>) -> anyhow::Result<()> {
     // 加载配置
-    let config = match config::Config::new() {
-        Ok(config) => config,
-        Err(e) => {
-            tracing::error!("❌ 配置加载失败: {}", e);
-            std::process::exit(1);
+    let config = config::Config::new().context("配置加载失败 / Failed to load config")?;
+    if let Err(problems) = config.validate() {
+        tracing::error!("❌ 配置校验失败, 共 {} 个问题 / Config validation failed with {} problem(s):", problems.len(), problems.len());
+        for problem in &problems {
+            tracing::error!("  - {}", problem);
         }
-    };
+        anyhow::bail!("配置校验失败 / Config validation failed: {}", problems.join("; "));
+    }
     tracing::info!("✅ 配置加载成功");
 
+    // 可热加载的配置子集 / Hot-reloadable config subset
+    let live_config = config::ReloadableConfig::live(&config);
+    apply_log_level(&log_filter_handle, config.server.log_level.as_deref());
+
     // 初始化 RocksDB
-    let db_storage = match db::RocksDbStorage::new(&config) {
-        Ok(storage) => Arc::new(storage),
-        Err(e) => {
-            tracing::error!("❌ RocksDB 初始化失败: {}", e);
-            std::process::exit(1);
-        }
-    };
+    let db_storage = Arc::new(db::RocksDbStorage::new(&config).with_context(|| {
+        format!("RocksDB 初始化失败 / Failed to initialize RocksDB at {}", config.database.rocksdb_path)
+    })?);
     tracing::info!("✅ RocksDB 初始化成功");
 
+    let replica = config.mode == config::RunMode::Replica;
+    if replica {
+        tracing::info!("📖 以只读副本模式运行, 不启动监听器和 Webhook / Running as a read replica, no listener or webhooks");
+    }
+
+    // 一次性迁移: 在监听器启动前从 mint 事件索引回填成交笔数, 从 user 事件索引回填跨代币索引; 只读副本由主实例完成
+    // One-time migrations: before the listener starts, backfill trade counts from the mint event index
+    // and the cross-mint index from the user event index; read replicas leave this to the primary
+    if !replica {
+        let token_storage = db_storage
+            .create_token_storage()
+            .context("Token 存储创建失败(迁移) / Failed to create Token storage (migration)")?;
+        let event_storage = db_storage
+            .create_event_storage()
+            .context("事件存储创建失败(迁移) / Failed to create event storage (migration)")?;
+        token_storage
+            .backfill_trade_counts(|mint| event_storage.count_mint_trades(mint))
+            .context("成交笔数回填失败 / Failed to backfill trade counts")?;
+        event_storage
+            .backfill_user_global_index()
+            .context("用户跨代币索引回填失败 / Failed to backfill the user cross-mint index")?;
+    }
+
+    // 事件立即应用并在 slot 被分叉丢弃时回滚, 订单簿和事件存储需要记录撤销日志
+    // Events are applied at once and rolled back when their slot is orphaned; the order book and event stores journal their writes
+    let revert_orphaned_slots = !replica
+        && config.solana.enable_event_listener
+        && !config.solana.enable_dry_run
+        && !config.solana.finalize_before_apply
+        && config.solana.revert_orphaned_slots;
+
     // 初始化 OrderBook 专用数据库 / Initialize OrderBook dedicated database
-    let orderbook_storage = match db::OrderBookStorage::new(
-        &config.database.orderbook_db,
-        &config.database.orderbook_db_path,
-    ) {
-        Ok(storage) => Arc::new(storage),
-        Err(e) => {
-            tracing::error!("❌ OrderBook 数据库初始化失败 / Failed to initialize OrderBook database: {}", e);
-            std::process::exit(1);
+    let orderbook_storage = Arc::new(
+        if replica {
+            db::OrderBookStorage::open_secondary(
+                &config.database.orderbook_db,
+                &config.database.orderbook_db_path,
+                &config.replica.secondary_dir("orderbook"),
+            )
+        } else {
+            db::OrderBookStorage::new(
+                &config.database.orderbook_db,
+                &config.database.orderbook_db_path,
+            )
+            .map(|storage| if revert_orphaned_slots { storage.with_undo_journal() } else { storage })
         }
-    };
+        .with_context(|| {
+            format!(
+                "OrderBook 数据库初始化失败 / Failed to initialize OrderBook database at {}",
+                config.database.orderbook_db_path
+            )
+        })?,
+    );
     tracing::info!("✅ OrderBook 数据库初始化成功 / OrderBook database initialized successfully");
 
+    // 一次性迁移: 从用户活跃订单索引回填跨代币持仓索引 / One-time migration: backfill the cross-mint position index from the user active order index
+    if !replica {
+        orderbook_storage
+            .backfill_user_global_orders()
+            .context("用户跨代币持仓索引回填失败 / Failed to backfill the user cross-mint position index")?;
+    }
+
+    // 只读副本定期追赶主实例的写入, 状态通过 /health 暴露
+    // Read replicas periodically catch up with the primary's writes, the status is exposed via /health
+    let replica_status = if replica {
+        let status = Arc::new(db::ReplicaStatus::default());
+        tokio::spawn(db::replica::run_catch_up(
+            db_storage.clone(),
+            orderbook_storage.clone(),
+            status.clone(),
+            std::time::Duration::from_millis(config.replica.catch_up_interval_ms),
+        ));
+        Some(status)
+    } else {
+        None
+    };
+
     // 初始化 K线推送服务 (如果启用) / Initialize K-line WebSocket service (if enabled)
     let (kline_socket_service, socketio_layer) = if config.kline.enable_kline_service {
         tracing::info!("🚀 初始化 K线 WebSocket 服务 / Initializing K-line WebSocket service");
 
         // 创建K线配置 / Create K-line config
-        let kline_config = kline::KlineConfig {
-            connection_timeout_secs: config.kline.connection_timeout_secs,
-            max_subscriptions_per_client: config.kline.max_subscriptions_per_client,
-            history_data_limit: config.kline.history_data_limit,
-            ping_interval_secs: config.kline.ping_interval_secs,
-            ping_timeout_secs: config.kline.ping_timeout_secs,
-        };
+        let kline_config = Arc::new(ArcSwap::from_pointee(kline::KlineConfig::from(&config.kline)));
 
         // 创建事件存储实例 (用于K线服务查询历史数据) / Create event storage instance (for K-line service to query history)
-        let event_storage_for_kline = match db_storage.create_event_storage() {
-            Ok(storage) => Arc::new(storage),
-            Err(e) => {
-                tracing::error!("❌ 事件存储创建失败(K线) / Failed to create event storage (K-line): {}", e);
-                std::process::exit(1);
-            }
-        };
+        let event_storage_for_kline = Arc::new(
+            db_storage
+                .create_event_storage()
+                .context("事件存储创建失败(K线) / Failed to create event storage (K-line)")?,
+        );
+
+        // 创建Token存储实例 (K线快照读取规范最新价格) / Create token storage instance (K-line snapshots read the canonical latest price)
+        let token_storage_for_kline = Arc::new(
+            db_storage
+                .create_token_storage()
+                .context("Token存储创建失败(K线) / Failed to create token storage (K-line)")?,
+        );
 
         // 创建K线推送服务 / Create K-line socket service
-        let (kline_service, layer) = match kline::KlineSocketService::new(
+        let (kline_service, layer) = kline::KlineSocketService::new(
             event_storage_for_kline,
-            kline_config,
-        ) {
-            Ok((service, layer)) => (Arc::new(service), Some(layer)),
-            Err(e) => {
-                tracing::error!("❌ K线 Socket 服务创建失败 / Failed to create K-line socket service: {}", e);
-                std::process::exit(1);
-            }
-        };
+            token_storage_for_kline,
+            kline_config.clone(),
+            &config.kline.watchlist,
+        )
+        .context("K线 Socket 服务创建失败 / Failed to create K-line socket service")?;
+        let kline_service = Arc::new(kline_service);
 
         // 设置事件处理器 / Setup event handlers
         kline_service.setup_socket_handlers();
 
+        // 后台预热K线, 不阻塞启动 / Preheat watchlist candles in the background without blocking startup
+        if !config.kline.watchlist.is_empty() {
+            kline_service.start_preheat();
+        }
+
         tracing::info!("✅ K线 WebSocket 服务初始化成功 / K-line WebSocket service initialized");
-        (Some(kline_service), layer)
+        (Some((kline_service, kline_config)), Some(layer))
     } else {
         tracing::info!("ℹ️ K线 WebSocket 服务已禁用 / K-line WebSocket service disabled");
         (None, None)
     };
+    let kline_live_config = kline_socket_service.as_ref().map(|(_, cfg)| cfg.clone());
+    let kline_socket_service = kline_socket_service.map(|(service, _)| service);
 
-    // 初始化 Solana 事件监听器 / Initialize Solana event listener
-    if config.solana.enable_event_listener {
+    // 创建 Solana 客户端 (带故障切换的RPC节点池) / Create Solana client (RPC endpoint pool with failover)
+    let solana_client = Arc::new(
+        solana::SolanaClient::from_config(&config.solana)
+            .context("Solana 客户端创建失败 / Failed to create Solana client")?,
+    );
+
+    // 初始化 Webhook 推送 (如果启用, 只读副本不推送) / Initialize webhook delivery (if enabled, never on read replicas)
+    let webhook_dispatcher = if config.webhook.enabled && !replica {
+        Some(
+            webhook::WebhookDispatcher::start(&config.webhook)
+                .context("Webhook 初始化失败 / Failed to initialize webhooks")?,
+        )
+    } else {
+        None
+    };
+
+    // 事件观察者 (缓存失效等), 在此注册后由存储处理链和重处理共用
+    // Event observers (cache invalidation, ...), registered here and shared by the storage chain and reprocessing
+    let persisted_events = Arc::new(solana::PersistedEventCounts::default());
+    let mut event_observers = solana::EventObservers::new();
+    event_observers.register(persisted_events.clone());
+    // 新Token首次写入时推送到新币频道 / Push first-written tokens to the new token feed
+    if let Some(ref kline_service) = kline_socket_service {
+        event_observers.register(Arc::new(kline::NewTokenFeed::new(kline_service.clone())));
+    }
+    let event_observers = Arc::new(event_observers);
+    tracing::info!("已注册 {} 个事件观察者 / {} event observer(s) registered", event_observers.len(), event_observers.len());
+
+    // 事件队列句柄 (用于 /metrics) / Event queue handles (for /metrics)
+    let mut event_queues = Vec::new();
+    // 演练模式处理器 (用于 /metrics) / Dry-run handler (for /metrics)
+    let mut dry_run_handler = None;
+    // 终结性闸门 (用于 /metrics) / Finality gate (for /metrics)
+    let mut finality_gate = None;
+    // 事件解码诊断, 未启用监听器时为空诊断 / Event decode diagnostics, empty when the listener is disabled
+    let mut decode_diagnostics = Arc::new(solana::DecodeDiagnostics::default());
+
+    // 初始化 Solana 事件监听器 (只读副本不启动) / Initialize Solana event listener (never on read replicas)
+    if replica {
+        tracing::info!("⏭️ 只读副本不启动 Solana 事件监听器 / Read replica, Solana event listener not started");
+    } else if config.solana.enable_event_listener {
         tracing::info!("🚀 初始化 Solana 事件监听器 / Initializing Solana event listener");
 
-        // 创建 Solana 客户端 / Create Solana client
-        let solana_client = match solana::SolanaClient::new(config.solana.rpc_url.clone()) {
-            Ok(client) => Arc::new(client),
-            Err(e) => {
-                tracing::error!("❌ Solana 客户端创建失败 / Failed to create Solana client: {}", e);
-                std::process::exit(1);
+        // 演练模式只解析和记录事件, 不创建存储/K线/Webhook处理链
+        // Dry run only parses and logs events, without the storage / K-line / webhook handler chain
+        let event_handler: Arc<dyn solana::EventHandler> = if config.solana.enable_dry_run {
+            tracing::warn!("🧪 Solana 事件监听器以演练模式运行, 事件不会写入 / Solana event listener running in dry-run mode, events will not be persisted");
+            let handler = Arc::new(solana::DryRunEventHandler::new());
+            dry_run_handler = Some(handler.clone());
+            handler
+        } else {
+            // 创建事件存储实例 / Create event storage instance
+            let mut event_storage = db_storage
+                .create_event_storage()
+                .context("事件存储创建失败 / Failed to create event storage")?;
+            if revert_orphaned_slots {
+                event_storage = event_storage.with_undo_journal();
             }
-        };
-
-        // 创建事件存储实例 / Create event storage instance
-        let event_storage = match db_storage.create_event_storage() {
-            Ok(storage) => Arc::new(storage),
-            Err(e) => {
-                tracing::error!("❌ 事件存储创建失败 / Failed to create event storage: {}", e);
-                std::process::exit(1);
+            let event_storage = Arc::new(event_storage);
+
+            // 创建 Token 存储实例 / Create token storage instance
+            let token_storage = Arc::new(
+                db_storage
+                    .create_token_storage()
+                    .context("Token 存储创建失败 / Failed to create Token storage")?,
+            );
+
+            // 创建存储事件处理器 / Create storage event handler
+            let mut storage_handler = solana::StorageEventHandler::new(
+                event_storage.clone(),
+                token_storage.clone(),
+                orderbook_storage.clone(),
+            );
+            if let Some(ref dispatcher) = webhook_dispatcher {
+                storage_handler = storage_handler.with_webhook(dispatcher.clone());
             }
-        };
-
-        // 创建 Token 存储实例 / Create token storage instance
-        let token_storage = match db_storage.create_token_storage() {
-            Ok(storage) => Arc::new(storage),
-            Err(e) => {
-                tracing::error!("❌ Token 存储创建失败 / Failed to create Token storage: {}", e);
-                std::process::exit(1);
+            // 曲线状态存储, 缺失的字段从链上读取 / Curve state storage, missing fields are read from chain
+            storage_handler = storage_handler
+                .with_curve_storage(Arc::new(db_storage.create_curve_storage()))
+                .with_solana_client(solana_client.clone())
+                .with_observers(event_observers.clone());
+            // 终结性闸门只包住存储处理器, K线推送不受影响 / The finality gate only wraps the storage handler, K-line pushes are unaffected
+            let storage_handler: Arc<dyn solana::EventHandler> = if config.solana.finalize_before_apply || revert_orphaned_slots {
+                let storage_handler = Arc::new(storage_handler);
+                let gate = Arc::new(if revert_orphaned_slots {
+                    // 事件立即应用, slot 被分叉丢弃时回滚 / Events apply at once and are rolled back when their slot is orphaned
+                    let rollback = solana::SlotRollback::new(orderbook_storage.clone(), event_storage);
+                    solana::FinalityGate::optimistic(storage_handler, rollback)?
+                } else {
+                    // 事件等到 finalized 才写库和更新订单簿, 缓冲的事件持久化以便重启后恢复
+                    // Events wait for finality before storage and order book updates; buffered events are persisted to survive restarts
+                    solana::FinalityGate::new(storage_handler).with_pending_store(event_storage)?
+                });
+                tokio::spawn(gate.clone().run(solana_client.clone(), solana::finality::FINALITY_POLL_INTERVAL));
+                finality_gate = Some(gate.clone());
+                gate
+            } else {
+                Arc::new(storage_handler)
+            };
+
+            // 如果启用了K线服务,创建K线事件处理器包装器 / If K-line service is enabled, create K-line event handler wrapper
+            if let Some(ref kline_service) = kline_socket_service {
+                // 创建K线事件处理器,包装StorageEventHandler / Create K-line event handler wrapping StorageEventHandler
+                let kline_handler = kline::KlineEventHandler::new(
+                    storage_handler,
+                    kline_service.clone(),
+                    config.solana.event_buffer_size,
+                );
+                event_queues.push(kline_handler.kline_queue());
+                Arc::new(kline_handler)
+            } else {
+                // 不使用K线服务,直接使用 StorageEventHandler / Without K-line service, use StorageEventHandler directly
+                storage_handler
             }
         };
 
-        // 创建存储事件处理器 / Create storage event handler
-        let storage_handler = Arc::new(solana::StorageEventHandler::new(
-            event_storage,
-            token_storage.clone(),
-            orderbook_storage.clone(),
-        ));
-
-        // 如果启用了K线服务,创建K线事件处理器包装器 / If K-line service is enabled, create K-line event handler wrapper
-        let event_handler: Arc<dyn solana::EventHandler> = if let Some(ref kline_service) = kline_socket_service {
-            // 创建K线事件处理器,包装StorageEventHandler / Create K-line event handler wrapping StorageEventHandler
-            Arc::new(kline::KlineEventHandler::new(
-                storage_handler,
-                kline_service.clone(),
-            ))
-        } else {
-            // 不使用K线服务,直接使用 StorageEventHandler / Without K-line service, use StorageEventHandler directly
-            storage_handler
-        };
-
         // 创建事件监听器管理器 / Create event listener manager
         let mut listener_manager = solana::EventListenerManager::new();
 
-        if let Err(e) = listener_manager.initialize(
-            config.solana.clone(),
-            solana_client,
-            event_handler,
-        ) {
-            tracing::error!("❌ 事件监听器初始化失败 / Failed to initialize event listener: {}", e);
-            std::process::exit(1);
+        listener_manager
+            .initialize(config.solana.clone(), solana_client.clone(), event_handler)
+            .context("事件监听器初始化失败 / Failed to initialize event listener")?;
+        event_queues.extend(listener_manager.event_queue());
+        if let Some(diagnostics) = listener_manager.decode_diagnostics() {
+            decode_diagnostics = diagnostics;
         }
 
         // 在后台启动事件监听器 / Start event listener in background
@@ -200,55 +359,101 @@ async fn main() {
         tracing::info!("⏭️ Solana 事件监听器已禁用 / Solana event listener disabled");
     }
 
-    // 创建 CORS 层
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
+    // 创建 CORS 层 (server.cors) / Create CORS layer (server.cors)
+    let cors = router::cors::cors_layer(&config.server.cors)
+        .context("CORS 配置无效 / Invalid CORS config")?;
 
     // 创建 Token 存储实例 (用于API查询) / Create token storage instance (for API queries)
-    let token_storage_for_api = match db_storage.create_token_storage() {
-        Ok(storage) => Arc::new(storage),
-        Err(e) => {
-            tracing::error!("❌ Token 存储创建失败(API) / Failed to create Token storage (API): {}", e);
-            std::process::exit(1);
-        }
-    };
+    let token_storage_for_api = Arc::new(
+        db_storage
+            .create_token_storage()
+            .context("Token 存储创建失败(API) / Failed to create Token storage (API)")?,
+    );
+
+    // 创建事件存储实例 (用于API查询) / Create event storage instance (for API queries)
+    let event_storage_for_api = Arc::new(
+        db_storage
+            .create_event_storage()
+            .context("事件存储创建失败(API) / Failed to create event storage (API)")?,
+    );
 
     // 创建路由
     let api_router = router::create_router(
         db_storage,
         token_storage_for_api,
+        event_storage_for_api,
         orderbook_storage.clone(),
+        solana_client,
+        webhook_dispatcher,
+        event_queues,
+        dry_run_handler,
+        finality_gate,
+        kline_socket_service.as_ref().map(|s| s.connection_stats()),
+        kline_socket_service.as_ref().map(|s| s.aggregators()),
+        decode_diagnostics,
+        persisted_events,
+        event_observers,
+        &config.server,
+        &config.solana,
+        live_config.clone(),
+        config.mode,
+        replica_status,
     );
 
-    // 创建 Swagger UI
-    let swagger_ui = SwaggerUi::new("/swagger-ui")
-        .url("/api-docs/openapi.json", docs::ApiDoc::openapi());
+    // API 文档: Swagger UI 自带 openapi.json, 关闭 UI 时可只提供 JSON
+    // API docs: Swagger UI ships openapi.json, with the UI off the JSON can still be served alone
+    let docs_router = if config.server.enable_swagger_ui {
+        Router::new().merge(SwaggerUi::new("/swagger-ui").url(OPENAPI_JSON_PATH, docs::ApiDoc::openapi()))
+    } else if config.server.enable_openapi_json {
+        let openapi = docs::ApiDoc::openapi();
+        Router::new().route(
+            OPENAPI_JSON_PATH,
+            axum::routing::get(move || std::future::ready(axum::Json(openapi.clone()))),
+        )
+    } else {
+        Router::new()
+    };
 
     // 组合所有路由 / Combine all routes
     let app = if let Some(layer) = socketio_layer {
         // 如果有Socket.IO层,添加到路由 / If Socket.IO layer exists, add to router
         Router::new()
-            .merge(swagger_ui)
+            .merge(docs_router)
             .merge(api_router)
             .layer(cors)
             .layer(layer)
     } else {
         // 没有Socket.IO层 / No Socket.IO layer
         Router::new()
-            .merge(swagger_ui)
+            .merge(docs_router)
             .merge(api_router)
             .layer(cors)
     };
 
+    // SIGHUP 热加载配置 / Hot-reload config on SIGHUP
+    #[cfg(unix)]
+    tokio::spawn(watch_sighup(
+        config.clone(),
+        live_config,
+        kline_live_config,
+        log_filter_handle,
+    ));
+    #[cfg(not(unix))]
+    let _ = (live_config, kline_live_config, log_filter_handle);
+
     // 绑定地址
     let addr = format!("{}:{}", config.server.host, config.server.port);
-    let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
+    let listener = tokio::net::TcpListener::bind(&addr)
+        .await
+        .with_context(|| format!("端口绑定失败 / Failed to bind {}", addr))?;
 
     tracing::info!("服务器启动成功！");
     tracing::info!("访问 http://localhost:{}/health 测试接口", config.server.port);
-    tracing::info!("访问 http://localhost:{}/swagger-ui 查看 API 文档", config.server.port);
+    if config.server.enable_swagger_ui {
+        tracing::info!("访问 http://localhost:{}/swagger-ui 查看 API 文档", config.server.port);
+    } else if config.server.enable_openapi_json {
+        tracing::info!("访问 http://localhost:{}{} 获取 OpenAPI 文档 (Swagger UI 已关闭)", config.server.port, OPENAPI_JSON_PATH);
+    }
     tracing::info!("访问 http://localhost:{}/db/* 测试数据库接口", config.server.port);
 
     if config.kline.enable_kline_service {
@@ -259,5 +464,139 @@ async fn main() {
     }
 
     // 启动服务器
-    axum::serve(listener, app).await.unwrap();
+    // 需要对端地址用于按 IP 限流 / Peer address is needed for per-IP rate limiting
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal())
+    .await
+    .context("HTTP 服务异常退出 / HTTP server failed")?;
+
+    // HTTP 停止后再停K线后台任务, 推送任务发完当前消息再退出
+    // Stop the K-line background tasks after HTTP, the push task finishes its current message first
+    if let Some(kline_service) = kline_socket_service {
+        kline_service.shutdown().await;
+    }
+
+    tracing::info!("👋 服务已停止 / Server stopped");
+    Ok(())
+}
+
+/// 等待 Ctrl+C 或 SIGTERM / Wait for Ctrl+C or SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("❌ 监听 Ctrl+C 失败 / Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("❌ 监听 SIGTERM 失败 / Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+    tracing::info!("🛑 收到停止信号, 开始优雅关闭 / Shutdown signal received, shutting down gracefully");
+}
+
+/// 应用日志级别配置, 为空时保持当前过滤器 / Apply log level config, keeping the current filter when unset
+fn apply_log_level(handle: &reload::Handle<EnvFilter, Registry>, level: Option<&str>) {
+    let Some(level) = level else {
+        return;
+    };
+    match EnvFilter::try_new(level) {
+        Ok(filter) => {
+            if let Err(e) = handle.reload(filter) {
+                tracing::error!("❌ 日志级别更新失败 / Failed to update log level: {}", e);
+            }
+        }
+        Err(e) => tracing::error!("❌ 日志级别无效 / Invalid log level {}: {}", level, e),
+    }
+}
+
+/// 监听 SIGHUP, 重新读取配置文件并替换可热加载部分
+/// Listen for SIGHUP, re-read the config file and swap the reloadable portion
+#[cfg(unix)]
+async fn watch_sighup(
+    mut current: config::Config,
+    live_config: config::LiveConfig,
+    kline_live_config: Option<Arc<ArcSwap<kline::KlineConfig>>>,
+    log_filter_handle: reload::Handle<EnvFilter, Registry>,
+) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangup = match signal(SignalKind::hangup()) {
+        Ok(s) => s,
+        Err(e) => {
+            tracing::error!("❌ 无法监听 SIGHUP / Failed to listen for SIGHUP: {}", e);
+            return;
+        }
+    };
+
+    while hangup.recv().await.is_some() {
+        tracing::info!("🔄 收到 SIGHUP, 重新加载配置 / SIGHUP received, reloading config");
+
+        let new_config = match config::Config::new() {
+            Ok(c) => c,
+            Err(e) => {
+                tracing::error!("❌ 配置重新加载失败, 保持原配置 / Config reload failed, keeping current config: {}", e);
+                continue;
+            }
+        };
+        if let Err(problems) = new_config.validate() {
+            tracing::error!("❌ 新配置校验失败, 保持原配置 / New config is invalid, keeping current config:");
+            for problem in &problems {
+                tracing::error!("  - {}", problem);
+            }
+            continue;
+        }
+
+        for field in current.non_reloadable_changes(&new_config) {
+            tracing::warn!("⚠️ {} 不支持热加载, 需重启生效 / {} is not hot-reloadable, restart required", field, field);
+        }
+
+        let reloadable = config::ReloadableConfig::from_config(&new_config);
+        let changes = live_config.load().changes(&reloadable);
+        if changes.is_empty() {
+            tracing::info!("ℹ️ 可热加载配置无变化 / No reloadable config changes");
+            continue;
+        }
+        for change in &changes {
+            tracing::info!("  ✏️ {}", change);
+        }
+
+        apply_log_level(&log_filter_handle, reloadable.log_level.as_deref());
+        if let Some(ref kline_config) = kline_live_config {
+            kline_config.store(Arc::new(kline::KlineConfig::from(&reloadable.kline)));
+        }
+        live_config.store(Arc::new(reloadable));
+
+        // 保留启动时的不可热加载项, 只替换可热加载部分 / Keep startup-only settings, swap only the reloadable part
+        current.kline = config::KlineServiceConfig {
+            enable_kline_service: current.kline.enable_kline_service,
+            ping_interval_secs: current.kline.ping_interval_secs,
+            ping_timeout_secs: current.kline.ping_timeout_secs,
+            watchlist: current.kline.watchlist.clone(),
+            ..new_config.kline
+        };
+        current.server.rate_limit = new_config.server.rate_limit;
+        current.server.log_level = new_config.server.log_level;
+
+        tracing::info!("✅ 配置热加载完成 / Config hot-reload complete");
+    }
 }
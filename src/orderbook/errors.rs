@@ -71,10 +71,47 @@ pub enum OrderBookError {
     #[error("Invalid order_id: {0}")]
     InvalidOrderId(String),
 
+    /// 锁定价格区间方向与订单簿方向不一致 / Lock price range does not match the order book direction
+    #[error("Invalid lock price range for '{direction}': start={start}, end={end}")]
+    InvalidPriceRange {
+        direction: String,
+        start: u128,
+        end: u128,
+    },
+
+    /// 回放所需的历史事件缺失 / Historical events required for replay are missing
+    #[error("History unavailable: {0}")]
+    HistoryUnavailable(String),
+
+    /// 回放窗口内事件过多 / Too many events in the replay window
+    #[error("Replay window exceeded: more than {max} events")]
+    ReplayWindowExceeded { max: usize },
+
     /// 通用错误 / Generic error
     #[error("{0}")]
     Generic(String),
 }
 
+impl OrderBookError {
+    /// 对应的 HTTP 状态码 / Corresponding HTTP status code
+    ///
+    /// 资源不存在为 404, 请求参数问题为 400, 容量已满为 409, 其余为 500
+    /// 404 for missing resources, 400 for bad request parameters, 409 when full, 500 otherwise
+    pub fn status_code(&self) -> u16 {
+        match self {
+            OrderBookError::NotFound { .. }
+            | OrderBookError::OrderNotFound(_)
+            | OrderBookError::OrderIdNotFound(_)
+            | OrderBookError::HistoryUnavailable(_) => 404,
+            OrderBookError::InvalidSlotIndex { .. }
+            | OrderBookError::InvalidDirection(_)
+            | OrderBookError::InvalidPriceRange { .. }
+            | OrderBookError::ReplayWindowExceeded { .. } => 400,
+            OrderBookError::ExceedsMaxCapacity { .. } => 409,
+            _ => 500,
+        }
+    }
+}
+
 /// Result 类型别名 / Result type alias
 pub type Result<T> = std::result::Result<T, OrderBookError>;
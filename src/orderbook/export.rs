@@ -0,0 +1,120 @@
+// 订单簿流式导出 - 按链表顺序分块输出 JSON 数组, 内存占用与订单簿大小无关
+// Streaming order book export - emits a JSON array in linked-list order chunk by chunk, memory stays flat regardless of book size
+
+use std::sync::Arc;
+
+use futures_util::stream::{self, Stream};
+
+use super::errors::{OrderBookError, Result};
+use super::manager::OrderBookDBManager;
+
+/// 每个分块包含的订单数 / Orders per emitted chunk
+pub const EXPORT_CHUNK_ORDERS: u32 = 256;
+
+/// 导出游标, 记录下一块从哪里开始 / Export cursor, remembers where the next chunk starts
+struct ExportCursor {
+    manager: Arc<OrderBookDBManager>,
+    chunk_orders: u32,
+    /// 下一块的起始索引, 首块为 u16::MAX (从 head 开始) / Start index of the next chunk, u16::MAX (from head) for the first one
+    next: u16,
+    /// 下一块首个订单应有的 order_id, 首块为空 / order_id the next chunk's first order must have, None for the first chunk
+    next_order_id: Option<u64>,
+    /// 首块读取时的订单数, 最多导出这么多 / Order count when the first chunk was read; at most this many are exported
+    limit: Option<u64>,
+    started: bool,
+    finished: bool,
+    written: u64,
+}
+
+impl ExportCursor {
+    /// 读取下一块并编码为 JSON 片段, 导出结束后返回 None
+    /// Read the next chunk and encode it as a JSON fragment, None once the export is complete
+    fn next_chunk(&mut self) -> Result<Option<Vec<u8>>> {
+        if self.finished {
+            return Ok(None);
+        }
+
+        let mut buf = Vec::new();
+        if !self.started {
+            buf.push(b'[');
+            self.started = true;
+        }
+
+        let limit = match self.limit {
+            Some(limit) => limit,
+            None => *self.limit.insert(self.manager.load_header()?.total as u64),
+        };
+        let remaining = limit.saturating_sub(self.written);
+        if remaining == 0 {
+            buf.push(b']');
+            self.finished = true;
+            return Ok(Some(buf));
+        }
+
+        // 两块之间的删除会把链表尾部订单换到被删的槽位; 续读前确认起始槽位仍是上一块记下的订单
+        // A removal between chunks swaps the tail order into the freed slot; before resuming, confirm the start slot
+        // still holds the order recorded by the previous chunk
+        let expected_id = self.next_order_id;
+        let written = &mut self.written;
+        let mut first = true;
+        let result = self.manager.traverse(self.next, self.chunk_orders.min(remaining as u32), |index, order| {
+            if std::mem::take(&mut first) && expected_id.is_some_and(|id| id != order.order_id) {
+                return Err(OrderBookError::Generic(format!(
+                    "order book changed during export: slot {} no longer holds order {}",
+                    index,
+                    expected_id.unwrap_or_default()
+                )));
+            }
+            if *written > 0 {
+                buf.push(b',');
+            }
+            serde_json::to_writer(&mut buf, order)?;
+            *written += 1;
+            Ok(true)
+        })?;
+
+        if result.done {
+            buf.push(b']');
+            self.finished = true;
+        } else {
+            self.next = result.next;
+            self.next_order_id = Some(self.manager.get_order(result.next)?.order_id);
+        }
+        Ok(Some(buf))
+    }
+}
+
+/// 按链表顺序流式导出全部活跃订单, 整体为一个 MarginOrder JSON 数组
+/// Stream every active order in linked-list order; the concatenated chunks form one JSON array of MarginOrder
+///
+/// 各分块在不同时刻读取: 最多导出首块读取时的订单数, 每块续读前校验起始订单的 order_id,
+/// 订单簿在两块之间被删除改动时以错误结束而不是跳过或重复订单; 出错后流结束, 已输出的 JSON 不完整
+/// Chunks are read at different times: at most the order count seen by the first chunk is exported and each chunk
+/// re-checks its first order_id before resuming, so a removal between chunks ends the stream with an error instead of
+/// skipping or duplicating orders; the stream ends after an error, leaving the JSON written so far incomplete
+pub fn export_stream(
+    manager: Arc<OrderBookDBManager>,
+    chunk_orders: u32,
+) -> impl Stream<Item = Result<Vec<u8>>> + Send + 'static {
+    let cursor = ExportCursor {
+        manager,
+        chunk_orders: chunk_orders.max(1),
+        next: u16::MAX,
+        next_order_id: None,
+        limit: None,
+        started: false,
+        finished: false,
+        written: 0,
+    };
+
+    stream::unfold(cursor, |mut cursor| async move {
+        match cursor.next_chunk() {
+            Ok(Some(chunk)) => Some((Ok(chunk), cursor)),
+            Ok(None) => None,
+            Err(e) => {
+                cursor.finished = true;
+                Some((Err(e), cursor))
+            }
+        }
+    })
+}
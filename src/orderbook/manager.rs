@@ -3,11 +3,46 @@
 
 use crate::orderbook::{
     errors::{OrderBookError, Result},
-    types::{MarginOrder, MarginOrderUpdateData, OrderBookHeader, TraversalResult},
+    types::{
+        DepthBucket, Direction, MarginOrder, MarginOrderUpdateData, OrderBookConsistency, OrderBookHeader,
+        TraversalResult,
+    },
 };
+use crate::db::orderbook_storage::{rebuild_marker_key, OrderBookStorage};
+use crate::db::undo::UndoJournal;
+use crate::util::curve::{CurveAMM, MAX_CLOSE_INSERT_INDICES};
+use crate::util::time::unix_now_u32;
 use rocksdb::{WriteBatch, DB};
 use std::sync::{Arc, Mutex};
-use tracing::{info, warn};
+use tracing::{error, info, warn};
+
+/// 相邻两个订单锁定区间之间的 (SOL, token) 流动性, 与链上计算 next_lp_* 的方式一致
+/// (SOL, token) liquidity between the lock ranges of two adjacent orders, computed the same way the program sets next_lp_*
+///
+/// * `end_price` - 前一个订单的 lock_lp_end_price / The previous order's lock_lp_end_price
+/// * `next_start_price` - 后一个订单的 lock_lp_start_price / The next order's lock_lp_start_price
+pub(crate) fn gap_liquidity(direction: Direction, end_price: u128, next_start_price: u128) -> Option<(u64, u64)> {
+    match direction {
+        // 做多订单簿价格向下 / Long book prices go down
+        Direction::Dn => {
+            let (token, sol) = CurveAMM::sell_from_price_to_price(end_price, next_start_price)?;
+            Some((sol, token))
+        }
+        // 做空订单簿价格向上 / Short book prices go up
+        Direction::Up => CurveAMM::buy_from_price_to_price(end_price, next_start_price),
+    }
+}
+
+/// 插入一个订单后的 total, 已达 MAX_CAPACITY 时返回 ExceedsMaxCapacity (先于 u16 溢出检查)
+/// The total after inserting one order; ExceedsMaxCapacity once MAX_CAPACITY is reached (ahead of the u16 overflow)
+fn next_total(total: u16) -> Result<u16> {
+    if total as u32 >= OrderBookHeader::MAX_CAPACITY {
+        return Err(OrderBookError::ExceedsMaxCapacity {
+            max: OrderBookHeader::MAX_CAPACITY,
+        });
+    }
+    Ok(total + 1)
+}
 
 /// OrderBook 数据库管理器
 /// OrderBook database manager
@@ -20,27 +55,56 @@ pub struct OrderBookDBManager {
     /// Associated mint address 
     mint: String,
 
-    /// 订单方向: "up"(做空) 或 "dn"(做多)
-    /// Order direction: "up"(short) or "dn"(long)
-    direction: String,
+    /// 订单方向: Up(做空) 或 Dn(做多)
+    /// Order direction: Up(short) or Dn(long)
+    direction: Direction,
 
     /// 操作锁 - 确保插入和删除操作不会并发执行
     /// Operation lock - ensures insert and delete operations don't execute concurrently
     operation_lock: Mutex<()>,
+
+    /// 撤销日志, 开启时每次写入都记录旧值以便回滚被分叉丢弃的 slot
+    /// Undo journal; when set, every write records prior values so an orphaned slot can be rolled back
+    journal: Option<Arc<UndoJournal>>,
 }
 
 impl OrderBookDBManager {
     /// 创建新的 OrderBookDBManager
     /// Create new OrderBookDBManager
-    pub fn new(db: Arc<DB>, mint: String, direction: String) -> Self {
+    pub fn new(db: Arc<DB>, mint: String, direction: Direction) -> Self {
         Self {
             db,
             mint,
             direction,
             operation_lock: Mutex::new(()),
+            journal: None,
         }
     }
 
+    /// 写入时记录撤销日志 / Record the undo journal on writes
+    pub fn with_journal(mut self, journal: Arc<UndoJournal>) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    /// 提交批次, 开启撤销日志时一并写入撤销记录 / Commit a batch, together with its undo record when journaling
+    fn commit(&self, mut batch: WriteBatch) -> Result<()> {
+        if let Some(journal) = &self.journal {
+            journal
+                .record_current(&mut batch)
+                .map_err(|e| OrderBookError::Generic(format!("undo journal: {}", e)))?;
+        }
+        self.db.write(batch)?;
+        Ok(())
+    }
+
+    /// 写入单个键 / Write a single key
+    fn put(&self, key: &[u8], value: &[u8]) -> Result<()> {
+        let mut batch = WriteBatch::default();
+        batch.put(key, value);
+        self.commit(batch)
+    }
+
     // ==================== 键生成辅助函数 / Key Generation Helpers ====================
 
     /// 生成 header 键
@@ -124,10 +188,28 @@ impl OrderBookDBManager {
         )
     }
 
+    /// 生成用户全局持仓索引键 (跨所有 mint)
+    /// Generate user global position index key (across all mints)
+    ///
+    /// 格式: user_global_orders:{user}:{mint}:{direction}:{order_id:020}
+    /// Format: user_global_orders:{user}:{mint}:{direction}:{order_id:020}
+    fn user_global_key(&self, user: &str, order_id: u64) -> String {
+        format!(
+            "user_global_orders:{}:{}:{}:{:020}",
+            user, self.mint, self.direction, order_id
+        )
+    }
+
+    /// 生成用户全局持仓索引前缀(用于前缀扫描)
+    /// Generate user global position index prefix (for prefix scan)
+    pub fn user_global_prefix(user_address: &str) -> String {
+        format!("user_global_orders:{}:", user_address)
+    }
+
     // ==================== 用户索引维护 / User Index Maintenance ====================
 
-    /// 添加用户活跃订单索引 (内部使用)
-    /// Add user active order index (internal use)
+    /// 添加用户活跃订单索引及全局持仓索引 (内部使用)
+    /// Add user active order index and global position index (internal use)
     fn add_user_active_index(
         &self,
         batch: &mut WriteBatch,
@@ -137,10 +219,14 @@ impl OrderBookDBManager {
     ) {
         let key = self.user_active_key(user, start_time, order_id);
         batch.put(key.as_bytes(), b""); // 值为空,仅作索引 / Value is empty, only for indexing
+
+        // 同步维护全局持仓索引 / Maintain global position index alongside
+        let global_key = self.user_global_key(user, order_id);
+        batch.put(global_key.as_bytes(), b"");
     }
 
-    /// 删除用户活跃订单索引 (内部使用)
-    /// Remove user active order index (internal use)
+    /// 删除用户活跃订单索引及全局持仓索引 (内部使用)
+    /// Remove user active order index and global position index (internal use)
     fn remove_user_active_index(
         &self,
         batch: &mut WriteBatch,
@@ -150,6 +236,9 @@ impl OrderBookDBManager {
     ) {
         let key = self.user_active_key(user, start_time, order_id);
         batch.delete(key.as_bytes());
+
+        let global_key = self.user_global_key(user, order_id);
+        batch.delete(global_key.as_bytes());
     }
 
     // ==================== 初始化 / Initialization ====================
@@ -168,32 +257,25 @@ impl OrderBookDBManager {
             );
             return Err(OrderBookError::AlreadyExists {
                 mint: self.mint.clone(),
-                direction: self.direction.clone(),
+                direction: self.direction.to_string(),
             });
         }
 
         // 创建新的 header
         // Create new header
-        let order_type = match self.direction.as_str() {
-            "dn" => 1,
-            "up" => 2,
-            _ => {
-                return Err(OrderBookError::InvalidDirection(self.direction.clone()));
-            }
-        };
+        let order_type = self.direction.order_type();
 
         let header = OrderBookHeader::new(order_type, authority);
 
         // 写入数据库
         // Write to database
-        self.db.put(header_key.as_bytes(), &header.to_bytes()?)?;
+        self.put(header_key.as_bytes(), &header.to_bytes()?)?;
 
         // 初始化活跃索引列表为空
         // Initialize active indices list as empty
         let active_key = self.active_indices_key();
         let empty_vec: Vec<u16> = vec![];
-        self.db
-            .put(active_key.as_bytes(), &serde_json::to_vec(&empty_vec)?)?;
+        self.put(active_key.as_bytes(), &serde_json::to_vec(&empty_vec)?)?;
 
         info!(
             "✅ OrderBook initialized: {}:{}",
@@ -206,22 +288,97 @@ impl OrderBookDBManager {
 
     /// 加载 OrderBook header
     /// Load OrderBook header
+    ///
+    /// header 字段自相矛盾时记录错误日志但仍返回, 以便 rebuild 等修复路径可以继续执行
+    /// A self-contradictory header is logged as an error but still returned, so repair paths such as rebuild can run
     pub fn load_header(&self) -> Result<OrderBookHeader> {
         let key = self.header_key();
         match self.db.get(key.as_bytes())? {
-            Some(data) => Ok(OrderBookHeader::from_bytes(&data)?),
+            Some(data) => {
+                let header = OrderBookHeader::from_bytes(&data)?;
+                let problems = header.consistency_problems();
+                if !problems.is_empty() {
+                    error!(
+                        "❌ OrderBook header 不一致 / OrderBook header inconsistent: {}:{} ({})",
+                        self.mint,
+                        self.direction,
+                        problems.join("; ")
+                    );
+                }
+                Ok(header)
+            }
             None => Err(OrderBookError::NotFound {
                 mint: self.mint.clone(),
-                direction: self.direction.clone(),
+                direction: self.direction.to_string(),
             }),
         }
     }
 
+    /// 检查 header 与已存储槽位是否一致 (扫描该订单簿全部槽位)
+    /// Check that the header agrees with the stored slots (scans every slot of this book)
+    ///
+    /// 除 header 自身的矛盾外, 还检查槽位数等于 total、槽位索引 `< total` 且数据可以解析
+    /// Besides the header's own contradictions, checks the slot count equals total, slot indices are `< total` and data parses
+    pub fn check_consistency(&self) -> Result<OrderBookConsistency> {
+        let header = self.load_header()?;
+        let mut problems = header.consistency_problems();
+
+        let slot_prefix = format!("orderbook_slot:{}:{}:", self.mint, self.direction);
+        let mut stored_slots: u32 = 0;
+        for item in self.db.prefix_iterator(slot_prefix.as_bytes()) {
+            let (key, value) = item?;
+            if !key.starts_with(slot_prefix.as_bytes()) {
+                break;
+            }
+            stored_slots += 1;
+
+            let key_str = String::from_utf8_lossy(&key);
+            match key_str[slot_prefix.len()..].parse::<u16>() {
+                Ok(index) if index >= header.total => {
+                    problems.push(format!("slot {} stored beyond total {}", index, header.total));
+                }
+                Ok(index) => {
+                    if let Err(e) = MarginOrder::from_bytes(&value) {
+                        problems.push(format!("slot {} unreadable ({} bytes): {}", index, value.len(), e));
+                    }
+                }
+                Err(_) => problems.push(format!("malformed slot key: {}", key_str)),
+            }
+        }
+        if stored_slots != header.total as u32 {
+            problems.push(format!("{} slots stored but total is {}", stored_slots, header.total));
+        }
+
+        // 分叉回滚或重处理留下的重建标记 / Rebuild marker left by a fork rollback or a reprocess
+        let marker_key = rebuild_marker_key(&self.mint, self.direction);
+        if let Some(marker) = self.db.get(marker_key.as_bytes())? {
+            let reason = serde_json::from_slice::<serde_json::Value>(&marker)
+                .ok()
+                .and_then(|m| m.get("reason").and_then(|r| r.as_str()).map(str::to_string))
+                .unwrap_or_default();
+            problems.push(format!("marked for rebuild: {}", reason));
+        }
+
+        if !problems.is_empty() {
+            error!(
+                "❌ OrderBook 数据不一致 / OrderBook data inconsistent: {}:{} ({})",
+                self.mint,
+                self.direction,
+                problems.join("; ")
+            );
+        }
+        Ok(OrderBookConsistency {
+            stored_slots,
+            consistent: problems.is_empty(),
+            problems,
+        })
+    }
+
     /// 更新 OrderBook header
     /// Update OrderBook header
     fn save_header(&self, header: &OrderBookHeader) -> Result<()> {
         let key = self.header_key();
-        self.db.put(key.as_bytes(), &header.to_bytes()?)?;
+        self.put(key.as_bytes(), &header.to_bytes()?)?;
         Ok(())
     }
 
@@ -256,17 +413,23 @@ impl OrderBookDBManager {
         }
     }
 
-    /// 通过 order_id 获取订单
-    /// Get order by order_id
-    pub fn get_order_by_id(&self, order_id: u64) -> Result<MarginOrder> {
-        // 1. 通过 ID 映射获取 index
-        // 1. Get index through ID mapping
+    /// 通过 ID 映射获取订单的当前索引
+    /// Get the order's current index through the ID mapping
+    pub fn index_of_order_id(&self, order_id: u64) -> Result<u16> {
         let id_key = self.id_map_key(order_id);
         let index_bytes = self
             .db
             .get(id_key.as_bytes())?
             .ok_or(OrderBookError::OrderIdNotFound(order_id))?;
-        let index: u16 = serde_json::from_slice(&index_bytes)?;
+        Ok(serde_json::from_slice(&index_bytes)?)
+    }
+
+    /// 通过 order_id 获取订单
+    /// Get order by order_id
+    pub fn get_order_by_id(&self, order_id: u64) -> Result<MarginOrder> {
+        // 1. 通过 ID 映射获取 index
+        // 1. Get index through ID mapping
+        let index = self.index_of_order_id(order_id)?;
 
         // 2. 获取订单
         // 2. Get order
@@ -283,8 +446,11 @@ impl OrderBookDBManager {
         }
     }
 
-    /// 获取所有活跃订单
-    /// Get all active orders
+    /// 获取所有活跃订单, 按活跃索引列表的顺序 (取决于插入/删除历史, 不是价格顺序)
+    /// Get all active orders in active-indices order (depends on insert/delete history, not price order)
+    ///
+    /// 需要价格顺序时使用 `get_all_active_orders_sorted`
+    /// Use `get_all_active_orders_sorted` when price order matters
     pub fn get_all_active_orders(&self) -> Result<Vec<(u16, MarginOrder)>> {
         let indices = self.load_active_indices()?;
         let mut orders = Vec::with_capacity(indices.len());
@@ -297,6 +463,19 @@ impl OrderBookDBManager {
         Ok(orders)
     }
 
+    /// 获取所有活跃订单, 从 head 沿链表遍历, 按价格排序 (做多递减, 做空递增)
+    /// Get all active orders by walking the linked list from head, in price order (descending for long, ascending for short)
+    pub fn get_all_active_orders_sorted(&self) -> Result<Vec<(u16, MarginOrder)>> {
+        let mut orders = Vec::new();
+
+        self.traverse(u16::MAX, 0, |index, order| {
+            orders.push((index, order.clone()));
+            Ok(true)
+        })?;
+
+        Ok(orders)
+    }
+
     // ==================== 插入操作 / Insert Operations ====================
 
     /// 在指定节点之后插入订单
@@ -330,14 +509,7 @@ impl OrderBookDBManager {
 
         // ✅ 验证容量
         // ✅ Validate capacity
-        let new_total = old_total
-            .checked_add(1)
-            .ok_or_else(|| OrderBookError::Overflow("total overflow".to_string()))?;
-        if new_total as u32 > OrderBookHeader::MAX_CAPACITY {
-            return Err(OrderBookError::ExceedsMaxCapacity {
-                max: OrderBookHeader::MAX_CAPACITY,
-            });
-        }
+        let new_total = next_total(old_total)?;
 
         // 使用 WriteBatch 保证原子性
         // Use WriteBatch to ensure atomicity
@@ -388,12 +560,12 @@ impl OrderBookDBManager {
             if order_id >= header.order_id_counter {
                 header.order_id_counter = order_id + 1;
             }
-            header.last_modified = chrono::Utc::now().timestamp() as u32;
+            header.last_modified = unix_now_u32();
             self.save_header_batch(&mut batch, &header)?;
 
             // 原子提交
             // Atomic commit
-            self.db.write(batch)?;
+            self.commit(batch)?;
 
             info!(
                 "✅ Inserted first order: index=0, order_id={}",
@@ -482,12 +654,12 @@ impl OrderBookDBManager {
         if order_id >= header.order_id_counter {
             header.order_id_counter = order_id + 1;
         }
-        header.last_modified = chrono::Utc::now().timestamp() as u32;
+        header.last_modified = unix_now_u32();
         self.save_header_batch(&mut batch, &header)?;
 
         // 原子提交
         // Atomic commit
-        self.db.write(batch)?;
+        self.commit(batch)?;
 
         info!(
             "✅ Inserted order: index={}, order_id={}",
@@ -531,14 +703,7 @@ impl OrderBookDBManager {
 
         // ✅ 验证容量
         // ✅ Validate capacity
-        let new_total = old_total
-            .checked_add(1)
-            .ok_or_else(|| OrderBookError::Overflow("total overflow".to_string()))?;
-        if new_total as u32 > OrderBookHeader::MAX_CAPACITY {
-            return Err(OrderBookError::ExceedsMaxCapacity {
-                max: OrderBookHeader::MAX_CAPACITY,
-            });
-        }
+        let new_total = next_total(old_total)?;
 
         // 使用 WriteBatch 保证原子性
         // Use WriteBatch to ensure atomicity
@@ -630,12 +795,12 @@ impl OrderBookDBManager {
         if order_id >= header.order_id_counter {
             header.order_id_counter = order_id + 1;
         }
-        header.last_modified = chrono::Utc::now().timestamp() as u32;
+        header.last_modified = unix_now_u32();
         self.save_header_batch(&mut batch, &header)?;
 
         // 原子提交
         // Atomic commit
-        self.db.write(batch)?;
+        self.commit(batch)?;
 
         info!(
             "✅ Inserted order before: index={}, order_id={}",
@@ -733,7 +898,7 @@ impl OrderBookDBManager {
         };
 
         // ✅ 新增: 获取当前时间戳 / Get current timestamp
-        let now = chrono::Utc::now().timestamp() as u32;
+        let now = unix_now_u32();
 
         for &remove_index in &sorted_indices {
             // 3.1 读取被删除节点
@@ -758,7 +923,7 @@ impl OrderBookDBManager {
                 &removed_order.user,
                 now,
                 &self.mint,
-                &self.direction,
+                self.direction.as_str(),
                 removed_order_id,
             );
             batch.put(close_key.as_bytes(), &serde_json::to_vec(&close_record)?);
@@ -936,6 +1101,7 @@ impl OrderBookDBManager {
             // Traverse from head to find the real tail node
             if new_total > 0 {
                 let mut current = header.head;
+                let mut steps: u16 = 0;
                 loop {
                     if current >= new_total {
                         // tail 无效,设置为第一个有效节点作为临时值
@@ -967,11 +1133,14 @@ impl OrderBookDBManager {
                     }
                     current = order.next_order;
 
-                    // 防止无限循环
-                    // Prevent infinite loop
-                    if current == header.head {
-                        header.tail = u16::MAX;
-                        break;
+                    // 防止无限循环: 合法链表最多 new_total 个节点, 超出说明存在环
+                    // Prevent infinite loop: a valid list has at most new_total nodes, more means a cycle
+                    steps += 1;
+                    if steps >= new_total {
+                        return Err(OrderBookError::InvalidAccountData(format!(
+                            "cycle detected while recovering tail: revisited index {} after {} steps",
+                            current, steps
+                        )));
                     }
                 }
             } else {
@@ -979,23 +1148,42 @@ impl OrderBookDBManager {
             }
         }
 
-        header.last_modified = chrono::Utc::now().timestamp() as u32;
+        header.last_modified = unix_now_u32();
         self.save_header_batch(&mut batch, &header)?;
 
         // 3.8 更新活跃索引列表
         // 3.8 Update active indices list
-        // ✅ Bug #3 修复: 删除和移动操作后,active_indices 应该是 [0..new_total)
-        // ✅ Bug #3 Fix: After delete and move operations, active_indices should be [0..new_total)
-        // 因为我们使用了移动末尾节点的策略,删除后所有有效索引都是连续的
-        // Since we use move-tail strategy, all valid indices are consecutive after deletion
-        let active_indices: Vec<u16> = (0..new_total).collect();
+        // 从实际链表 head→tail 重建, 而不是假设索引连续为 [0..new_total)
+        // Rebuild from the actual linked list head→tail instead of assuming contiguous [0..new_total)
+        let mut active_indices: Vec<u16> = Vec::with_capacity(new_total as usize);
+        let mut visited = vec![false; new_total as usize];
+        let mut current = header.head;
+        while current != u16::MAX {
+            if current >= new_total || visited[current as usize] {
+                return Err(OrderBookError::InvalidAccountData(format!(
+                    "linked list broken after batch remove: reached index {} (total {})",
+                    current, new_total
+                )));
+            }
+            visited[current as usize] = true;
+            active_indices.push(current);
+            current = get_order_cached(&order_cache, current)?.next_order;
+        }
+        if active_indices.len() != new_total as usize {
+            return Err(OrderBookError::InvalidAccountData(format!(
+                "active indices mismatch after batch remove: {} reachable, expected {}",
+                active_indices.len(),
+                new_total
+            )));
+        }
+        active_indices.sort_unstable();
 
         let active_key = self.active_indices_key();
         batch.put(active_key.as_bytes(), &serde_json::to_vec(&active_indices)?);
 
         // 4. 原子提交
         // 4. Atomic commit
-        self.db.write(batch)?;
+        self.commit(batch)?;
 
         info!("✅ Batch removed {} orders", delete_count);
         Ok(())
@@ -1144,7 +1332,7 @@ impl OrderBookDBManager {
         header.tail = u16::MAX;
         header.total = 0;
         header.total_capacity = 0;
-        header.last_modified = chrono::Utc::now().timestamp() as u32;
+        header.last_modified = unix_now_u32();
         self.save_header_batch(&mut batch, &header)?;
 
         // 清空活跃索引列表
@@ -1157,7 +1345,7 @@ impl OrderBookDBManager {
 
         // 原子提交
         // Atomic commit
-        self.db.write(batch)?;
+        self.commit(batch)?;
 
         info!("✅ Removed all orders");
         Ok(())
@@ -1171,7 +1359,7 @@ impl OrderBookDBManager {
         current_price: u128,
     ) -> Result<()> {
         let mut batch = WriteBatch::default();
-        let now = chrono::Utc::now().timestamp() as u32;
+        let now = unix_now_u32();
 
         // 删除所有订单槽位和 ID 映射,并保存关闭记录
         // Delete all order slots and ID mappings, and save close records
@@ -1185,7 +1373,7 @@ impl OrderBookDBManager {
                 &order.user,
                 now,
                 &self.mint,
-                &self.direction,
+                self.direction.as_str(),
                 order.order_id,
             );
             batch.put(close_key.as_bytes(), &serde_json::to_vec(&close_record)?);
@@ -1225,7 +1413,7 @@ impl OrderBookDBManager {
         );
 
         // 原子提交 / Atomic commit
-        self.db.write(batch)?;
+        self.commit(batch)?;
 
         info!("✅ Removed all orders, saved close records");
         Ok(())
@@ -1252,6 +1440,26 @@ impl OrderBookDBManager {
     ) -> Result<()> {
         // 1. 读取并验证
         // 1. Read and validate
+        let mut order = self.get_order_for_update(update_index, order_id)?;
+
+        // 2. 应用更新(只更新非 None 的字段)
+        // 2. Apply updates (only update non-None fields)
+        Self::apply_update(&mut order, update_data);
+
+        // 3. 写回数据库
+        // 3. Write back to database
+        let slot_key = self.slot_key(update_index);
+        self.put(slot_key.as_bytes(), &order.to_bytes()?)?;
+
+        info!(
+            "✅ Updated order: index={}, order_id={}",
+            update_index, order_id
+        );
+        Ok(())
+    }
+
+    /// 读取待更新的订单, 校验索引范围和 order_id / Read the order to update, checking the index range and order_id
+    fn get_order_for_update(&self, update_index: u16, order_id: u64) -> Result<MarginOrder> {
         let header = self.load_header()?;
 
         // 验证索引范围
@@ -1265,16 +1473,18 @@ impl OrderBookDBManager {
 
         // 读取订单并验证 order_id
         // Read order and validate order_id
-        let mut order = self.get_order(update_index)?;
+        let order = self.get_order(update_index)?;
         if order.order_id != order_id {
             return Err(OrderBookError::OrderIdMismatch {
                 expected: order_id,
                 actual: order.order_id,
             });
         }
+        Ok(order)
+    }
 
-        // 2. 应用更新(只更新非 None 的字段)
-        // 2. Apply updates (only update non-None fields)
+    /// 应用更新(只更新非 None 的字段)并递增版本号 / Apply an update (non-None fields only) and bump the version
+    fn apply_update(order: &mut MarginOrder, update_data: &MarginOrderUpdateData) {
         if let Some(lock_lp_start_price) = update_data.lock_lp_start_price {
             order.lock_lp_start_price = lock_lp_start_price;
         }
@@ -1321,19 +1531,200 @@ impl OrderBookDBManager {
         // 更新版本号
         // Update version number
         order.version += 1;
+    }
 
-        // 3. 写回数据库
-        // 3. Write back to database
-        let slot_key = self.slot_key(update_index);
-        self.db.put(slot_key.as_bytes(), &order.to_bytes()?)?;
+    /// 更新订单, 起点价格变化时按链上 close_long_trade / close_short_trade 重新计算前节点的 next_lp_*,
+    /// 终点价格变化时按链上 add_margin 重新计算本订单的 next_lp_*
+    /// Update an order and, when its start price changes, recompute the predecessor's next_lp_* like the program's
+    /// close_long_trade / close_short_trade do; when its end price changes, recompute the order's own next_lp_* like
+    /// add_margin does
+    ///
+    /// 部分平仓会把订单起点移到平仓后的价格, 前节点到本订单之间的区间随之变化; 追加保证金还会移动终点;
+    /// 只用 update_order 会让镜像的 next_lp_* 偏离链上
+    /// A partial close moves the order's start to the post-close price, which changes the gap after the predecessor;
+    /// adding margin also moves the end. Plain update_order would leave the mirror's next_lp_* out of sync with the chain
+    pub fn update_order_with_neighbor_recalc(
+        &self,
+        update_index: u16,
+        order_id: u64,
+        update_data: &MarginOrderUpdateData,
+    ) -> Result<()> {
+        let mut order = self.get_order_for_update(update_index, order_id)?;
+        let old_end_price = order.lock_lp_end_price;
+        Self::apply_update(&mut order, update_data);
+
+        // 终点价格影响本订单之后的区间, 链表尾部之后是无限空间
+        // The end price affects the gap after this order; the space after the tail is unbounded
+        if order.lock_lp_end_price != old_end_price && order.next_order != u16::MAX {
+            let next = self.get_order(order.next_order)?;
+            let (next_lp_sol_amount, next_lp_token_amount) =
+                gap_liquidity(self.direction, order.lock_lp_end_price, next.lock_lp_start_price).ok_or_else(|| {
+                    OrderBookError::Overflow(format!(
+                        "gap liquidity between index {} (end={}) and index {} (start={})",
+                        update_index, order.lock_lp_end_price, order.next_order, next.lock_lp_start_price
+                    ))
+                })?;
+            order.next_lp_sol_amount = next_lp_sol_amount;
+            order.next_lp_token_amount = next_lp_token_amount;
+        }
+
+        // 本订单和前节点在同一批次中写入 / The order and its predecessor are written in one batch
+        let mut batch = WriteBatch::default();
+        batch.put(self.slot_key(update_index).as_bytes(), order.to_bytes()?);
+
+        // 只有起点价格影响前节点之后的区间, 链表头部没有前节点
+        // Only the start price affects the gap after the predecessor; the head has no predecessor
+        let prev_index = order.prev_order;
+        if let Some(new_start_price) = update_data.lock_lp_start_price.filter(|_| prev_index != u16::MAX) {
+            let mut prev = self.get_order(prev_index)?;
+            let (next_lp_sol_amount, next_lp_token_amount) =
+                gap_liquidity(self.direction, prev.lock_lp_end_price, new_start_price).ok_or_else(|| {
+                    OrderBookError::Overflow(format!(
+                        "gap liquidity between index {} (end={}) and index {} (start={})",
+                        prev_index, prev.lock_lp_end_price, update_index, new_start_price
+                    ))
+                })?;
+            Self::apply_update(
+                &mut prev,
+                &MarginOrderUpdateData {
+                    next_lp_sol_amount: Some(next_lp_sol_amount),
+                    next_lp_token_amount: Some(next_lp_token_amount),
+                    ..Default::default()
+                },
+            );
+            batch.put(self.slot_key(prev_index).as_bytes(), prev.to_bytes()?);
+        }
 
+        self.commit(batch)?;
         info!(
-            "✅ Updated order: index={}, order_id={}",
+            "✅ Updated order with neighbor recalc: index={}, order_id={}",
             update_index, order_id
         );
         Ok(())
     }
 
+    // ==================== 重建操作 / Rebuild Operations ====================
+
+    /// 重建(压缩/修复)订单簿
+    /// Rebuild (compact/repair) the order book
+    ///
+    /// 按链表顺序读取全部活跃订单, 在单个 WriteBatch 中:
+    /// Reads all active orders in linked-list order, then in a single WriteBatch:
+    /// - 将订单连续重写到槽位 0..n 并重新链接 / rewrites them contiguously into slots 0..n and relinks them
+    /// - 重新生成 ID 映射和活跃索引列表 / regenerates the ID map and active indices list
+    /// - 将 total_capacity 重置为 total / resets total_capacity to total
+    ///
+    /// order_id 与 order_id_counter 保持不变。链表外的孤立槽位会被删除, 并清理其用户索引。
+    /// order_id and order_id_counter are preserved. Orphan slots outside the list are deleted along with their user indices.
+    /// 重建标记在同一批次中清除 / The rebuild marker is cleared in the same batch.
+    ///
+    /// # 错误 / Errors
+    /// 链表存在环或指向缺失槽位时返回错误, 不写入任何数据
+    /// Returns an error without writing anything if the list has a cycle or points to a missing slot
+    pub fn rebuild(&self) -> Result<()> {
+        // 获取操作锁 / Acquire operation lock
+        let _lock = self.operation_lock.lock().unwrap();
+
+        let mut header = self.load_header()?;
+
+        // 1. 按链表顺序读取订单 / Read orders in linked-list order
+        let mut orders: Vec<MarginOrder> = Vec::new();
+        let mut visited = std::collections::HashSet::new();
+        let mut current = header.head;
+        while current != u16::MAX {
+            if !visited.insert(current) {
+                return Err(OrderBookError::InvalidAccountData(format!(
+                    "cycle detected at index {} while rebuilding",
+                    current
+                )));
+            }
+            let order = self.get_order(current)?;
+            current = order.next_order;
+            orders.push(order);
+        }
+        let live_ids: std::collections::HashSet<u64> = orders.iter().map(|o| o.order_id).collect();
+
+        let mut batch = WriteBatch::default();
+
+        // 2. 清除旧槽位和旧 ID 映射 / Clear old slots and old ID mappings
+        let slot_prefix = format!("orderbook_slot:{}:{}:", self.mint, self.direction);
+        let mut orphan_count = 0;
+        for item in self.db.prefix_iterator(slot_prefix.as_bytes()) {
+            let (key, value) = item?;
+            if !key.starts_with(slot_prefix.as_bytes()) {
+                break;
+            }
+            batch.delete(&key);
+
+            // 孤立订单: 清理其用户索引 / Orphan order: clean up its user indices
+            if let Ok(order) = MarginOrder::from_bytes(&value) {
+                if !live_ids.contains(&order.order_id) {
+                    self.remove_user_active_index(&mut batch, &order.user, order.start_time, order.order_id);
+                    orphan_count += 1;
+                }
+            }
+        }
+
+        let id_map_prefix = format!("orderbook_id_map:{}:{}:", self.mint, self.direction);
+        for item in self.db.prefix_iterator(id_map_prefix.as_bytes()) {
+            let (key, _value) = item?;
+            if !key.starts_with(id_map_prefix.as_bytes()) {
+                break;
+            }
+            batch.delete(&key);
+        }
+
+        // 3. 连续重写槽位与 ID 映射 / Rewrite slots and ID mappings contiguously
+        let new_total = orders.len() as u16;
+        for (i, order) in orders.iter_mut().enumerate() {
+            let index = i as u16;
+            order.prev_order = if index == 0 { u16::MAX } else { index - 1 };
+            order.next_order = if index + 1 == new_total { u16::MAX } else { index + 1 };
+            order.version += 1;
+
+            let slot_key = self.slot_key(index);
+            batch.put(slot_key.as_bytes(), &order.to_bytes()?);
+            let id_key = self.id_map_key(order.order_id);
+            batch.put(id_key.as_bytes(), &serde_json::to_vec(&index)?);
+        }
+
+        // 4. 活跃索引列表与 header / Active indices list and header
+        let active_indices: Vec<u16> = (0..new_total).collect();
+        let active_key = self.active_indices_key();
+        batch.put(active_key.as_bytes(), &serde_json::to_vec(&active_indices)?);
+
+        let (head, tail) = if new_total == 0 {
+            (u16::MAX, u16::MAX)
+        } else {
+            (0, new_total - 1)
+        };
+        header.head = head;
+        header.tail = tail;
+        header.total = new_total;
+        header.total_capacity = new_total as u32;
+        header.last_modified = unix_now_u32();
+        self.save_header_batch(&mut batch, &header)?;
+
+        // 重建完成后不再需要重建标记 / The rebuild marker is no longer needed once rebuilt
+        OrderBookStorage::clear_rebuild(&mut batch, &self.mint, self.direction);
+
+        // 原子提交, 开启撤销日志时可随所在 slot 回滚
+        // Atomic commit, rolled back with its slot when journaling
+        self.commit(batch)?;
+
+        if orphan_count > 0 {
+            warn!(
+                "⚠️ Rebuild dropped {} orphan slots: {}:{}",
+                orphan_count, self.mint, self.direction
+            );
+        }
+        info!(
+            "✅ OrderBook rebuilt: {}:{}, total={}",
+            self.mint, self.direction, new_total
+        );
+        Ok(())
+    }
+
     // ==================== 遍历操作 / Traverse Operations ====================
 
     /// 遍历订单(不可变,支持批量和续传)
@@ -1424,6 +1815,94 @@ impl OrderBookDBManager {
         }
     }
 
+    /// 查找在 `within_secs` 秒内到期 (含已到期) 的订单, 按 end_time 升序
+    /// Find orders expiring within `within_secs` seconds (including already expired ones), ascending by end_time
+    ///
+    /// 链表按价格而非时间排序, 需要完整遍历一次
+    /// The list is ordered by price rather than time, so this walks the whole list
+    ///
+    /// # 参数 / Parameters
+    /// * `now` - 当前 Unix 时间戳(秒) / Current Unix timestamp (seconds)
+    /// * `within_secs` - 剩余时间上限(秒) / Max remaining time (seconds)
+    pub fn find_expiring_orders(&self, now: i64, within_secs: u64) -> Result<Vec<(u16, MarginOrder)>> {
+        let deadline = now.saturating_add(within_secs.min(i64::MAX as u64) as i64);
+        let mut expiring = Vec::new();
+
+        self.traverse(u16::MAX, 0, |index, order| {
+            if (order.end_time as i64) <= deadline {
+                expiring.push((index, order.clone()));
+            }
+            Ok(true)
+        })?;
+
+        expiring.sort_by_key(|(index, order)| (order.end_time, *index));
+        Ok(expiring)
+    }
+
+    /// 查找已到期 (`end_time <= now`) 的订单, 到期最久的在前
+    /// Find expired orders (`end_time <= now`), longest expired first
+    pub fn find_expired_orders(&self, now: i64) -> Result<Vec<(u16, MarginOrder)>> {
+        self.find_expiring_orders(now, 0)
+    }
+
+    /// 活跃订单中最早的 end_time, 空订单簿时为空 (完整遍历一次)
+    /// Earliest end_time among active orders, None for an empty book (walks the whole list)
+    pub fn nearest_expiry(&self) -> Result<Option<u32>> {
+        let mut nearest: Option<u32> = None;
+        self.traverse(u16::MAX, 0, |_, order| {
+            nearest = Some(nearest.map_or(order.end_time, |t| t.min(order.end_time)));
+            Ok(true)
+        })?;
+        Ok(nearest)
+    }
+
+    /// 按价格区间聚合订单簿锁定的流动性 (深度图), 区间按价格升序
+    /// Aggregate the book's locked liquidity into price buckets (depth profile), buckets in ascending price
+    ///
+    /// 价格范围取 head 与 tail 订单的锁定区间端点, 均分为至多 `buckets` 个区间;
+    /// 每个订单按 lock_lp_start_price 归入一个区间, 聚合只需一次遍历
+    /// The price range spans the lock range endpoints of the head and tail orders and is split into at most `buckets` equal buckets;
+    /// each order falls into one bucket by its lock_lp_start_price, so aggregation takes a single traversal
+    pub fn depth_profile(&self, buckets: usize) -> Result<Vec<DepthBucket>> {
+        let header = self.load_header()?;
+        if header.head == u16::MAX || buckets == 0 {
+            return Ok(Vec::new());
+        }
+
+        let head = self.get_order(header.head)?;
+        let tail = self.get_order(header.tail)?;
+        let endpoints = [
+            head.lock_lp_start_price,
+            head.lock_lp_end_price,
+            tail.lock_lp_start_price,
+            tail.lock_lp_end_price,
+        ];
+        let low = endpoints.iter().copied().min().unwrap_or(0);
+        let high = endpoints.iter().copied().max().unwrap_or(0);
+
+        let span = (high - low).saturating_add(1);
+        let width = span.div_ceil(buckets as u128).max(1);
+        let count = span.div_ceil(width) as usize;
+        let mut profile: Vec<DepthBucket> = (0..count as u128)
+            .map(|i| DepthBucket {
+                price_low: low + i * width,
+                price_high: (low + i * width).saturating_add(width - 1).min(high),
+                ..Default::default()
+            })
+            .collect();
+
+        self.traverse(u16::MAX, 0, |_, order| {
+            let price = order.lock_lp_start_price.clamp(low, high);
+            let bucket = &mut profile[(((price - low) / width) as usize).min(count - 1)];
+            bucket.sol = bucket.sol.saturating_add(order.lock_lp_sol_amount);
+            bucket.token = bucket.token.saturating_add(order.lock_lp_token_amount);
+            bucket.orders += 1;
+            Ok(true)
+        })?;
+
+        Ok(profile)
+    }
+
     /// 获取指定插入位置的前后邻居节点索引
     /// Get insert neighbors for specified position
     ///
@@ -1475,6 +1954,131 @@ impl OrderBookDBManager {
         Ok((Some(insert_pos), next_idx))
     }
 
+    /// 为新开仓订单推荐 close_insert_indices
+    /// Suggest close_insert_indices for opening a new order
+    ///
+    /// 与链上 long_trade / short_trade 的重叠校验一致:
+    /// Consistent with the overlap checks of on-chain long_trade / short_trade:
+    /// - "dn"(做多): 价格从 head 向 tail 递减, 新订单须满足 start < prev.end 且 end > next.start
+    /// - "dn"(long): prices descend from head to tail, new order needs start < prev.end and end > next.start
+    /// - "up"(做空): 价格从 head 向 tail 递增, 新订单须位于 prev 之上、next 之下
+    /// - "up"(short): prices ascend from head to tail, new order must lie above prev and below next
+    ///
+    /// # 参数 / Parameters
+    /// * `lock_start_price` - 新订单锁定区间起始价(开仓价) / Lock range start price (open price)
+    /// * `lock_end_price` - 新订单锁定区间结束价(平仓价) / Lock range end price (close price)
+    ///
+    /// # 返回值 / Returns
+    /// 按可能性排序的候选插入位置(u16::MAX = 插入到头部), 最多 `MAX_CLOSE_INSERT_INDICES` 个。
+    /// 第一个为当前正确位置, 其后为前驱节点依次向前, 以应对前驱订单在交易确认前被平仓。
+    /// 与已有订单重叠时返回空列表。
+    /// Candidate insert positions ordered by likelihood (u16::MAX = insert at head), at most
+    /// `MAX_CLOSE_INSERT_INDICES`. The first is the correct position now, followed by earlier
+    /// predecessors in case the predecessor is closed before the transaction lands.
+    /// Returns an empty list if the range overlaps existing orders.
+    pub fn suggest_insert_indices(
+        &self,
+        lock_start_price: u128,
+        lock_end_price: u128,
+    ) -> Result<Vec<u16>> {
+        let is_down = self.direction == Direction::Dn;
+        let range_ok = if is_down {
+            lock_start_price > lock_end_price
+        } else {
+            lock_start_price < lock_end_price
+        };
+        if !range_ok {
+            return Err(OrderBookError::InvalidPriceRange {
+                direction: self.direction.to_string(),
+                start: lock_start_price,
+                end: lock_end_price,
+            });
+        }
+
+        // 新订单可以排在该订单之后 / New order may be placed after this order
+        let fits_after = |prev: &MarginOrder| {
+            if is_down {
+                lock_start_price < prev.lock_lp_end_price
+            } else {
+                lock_start_price >= prev.lock_lp_end_price
+                    && lock_end_price >= prev.lock_lp_start_price
+            }
+        };
+        // 新订单可以排在该订单之前 / New order may be placed before this order
+        let fits_before = |next: &MarginOrder| {
+            if is_down {
+                lock_end_price > next.lock_lp_start_price
+            } else {
+                lock_end_price <= next.lock_lp_start_price
+                    && lock_start_price <= next.lock_lp_end_price
+            }
+        };
+
+        // 链表按价格有序, 收集可排在新订单之前的前缀, 遇到第一个不满足的节点即停止
+        // The list is price-ordered: collect the prefix that can precede the new order, stop at the first node that cannot
+        let mut predecessors: Vec<u16> = Vec::new();
+        let mut successor: Option<MarginOrder> = None;
+        self.traverse(u16::MAX, 0, |index, order| {
+            if fits_after(order) {
+                predecessors.push(index);
+                Ok(true)
+            } else {
+                successor = Some(order.clone());
+                Ok(false)
+            }
+        })?;
+
+        if let Some(next) = &successor {
+            if !fits_before(next) {
+                return Ok(Vec::new());
+            }
+        }
+
+        // 当前位置优先, 然后依次向前回退, 最后是头部
+        // Current position first, then fall back through earlier predecessors, head last
+        let mut candidates: Vec<u16> = predecessors.iter().rev().copied().collect();
+        candidates.push(u16::MAX);
+        candidates.truncate(MAX_CLOSE_INSERT_INDICES);
+
+        Ok(candidates)
+    }
+
+    /// 为平仓推荐 close_order_indices
+    /// Suggest close_order_indices for closing an order
+    ///
+    /// 链上 close_long_trade / close_short_trade 对每个索引同时检查该节点及其 prev/next,
+    /// 因此传入订单当前索引及其前后邻居, 即使订单在交易确认前因尾部搬移换了槽位,
+    /// 仍能通过邻居的指针找到它。
+    /// On-chain close_long_trade / close_short_trade check each index together with its prev/next,
+    /// so passing the order's current index plus its neighbors still finds the order through the
+    /// neighbors' links if a move-tail compaction relocates it before the transaction lands.
+    ///
+    /// # 返回值 / Returns
+    /// `[index, prev, next]` (不存在的邻居省略 / missing neighbors omitted)
+    pub fn suggest_close_indices(&self, order_id: u64) -> Result<Vec<u16>> {
+        let id_key = self.id_map_key(order_id);
+        let index_bytes = self
+            .db
+            .get(id_key.as_bytes())?
+            .ok_or(OrderBookError::OrderIdNotFound(order_id))?;
+        let index: u16 = serde_json::from_slice(&index_bytes)?;
+        let order = self.get_order(index)?;
+
+        Ok(Self::close_indices_for(index, &order))
+    }
+
+    /// 由已读取的订单构建 close_order_indices, 规则同 [`Self::suggest_close_indices`]
+    /// Build close_order_indices from an already loaded order, same rule as [`Self::suggest_close_indices`]
+    pub fn close_indices_for(index: u16, order: &MarginOrder) -> Vec<u16> {
+        let mut indices = vec![index];
+        for neighbor in [order.prev_order, order.next_order] {
+            if neighbor != u16::MAX {
+                indices.push(neighbor);
+            }
+        }
+        indices
+    }
+
     // ==================== 已关闭订单辅助函数 / Closed Order Helper Functions ====================
 
     /// 构建订单关闭记录
@@ -1524,7 +2128,7 @@ impl OrderBookDBManager {
         let open_price = order.open_price;
         let position_size = order.position_asset_amount;
 
-        let price_diff = if self.direction == "dn" {
+        let price_diff = if self.direction == Direction::Dn {
             // 做多 / Long
             close_price as i128 - open_price as i128
         } else {
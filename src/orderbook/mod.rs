@@ -3,7 +3,11 @@
 
 pub mod closed_orders;
 pub mod errors;
+pub mod export;
 pub mod manager;
+pub mod onchain;
+pub mod replay;
+pub mod timeline;
 pub mod types;
 pub mod user_query;
 
@@ -11,10 +15,14 @@ pub mod user_query;
 // 重导出主要类型
 pub use closed_orders::ClosedOrdersQuery;
 pub use errors::{OrderBookError, Result};
+pub use export::{export_stream, EXPORT_CHUNK_ORDERS};
 pub use manager::OrderBookDBManager;
+pub use onchain::{decode_orderbook_account, orderbook_pda, OnchainOrderBook, OnchainOrderBookHeader};
+pub use replay::{replay_orderbook_at, ReplayedOrderBook, MAX_REPLAY_EVENTS};
+pub use timeline::{build_order_timeline, OrderTimeline, OrderTimelineStep};
 pub use types::{
-    ClosedOrderRecord, CloseInfo, CloseReason, MarginOrder, MarginOrderUpdateData,
-    OrderBookHeader, TraversalResult,
+    ClosedOrderRecord, CloseInfo, CloseReason, DepthBucket, Direction, MarginOrder, MarginOrderUpdateData,
+    OrderBookConsistency, OrderBookHeader, TraversalResult,
 };
 pub use user_query::UserOrderQueryService;
 
@@ -0,0 +1,175 @@
+// 链上 OrderBook 账户解码 - 按合约的 zero-copy 布局直接读取账户字节
+// On-chain OrderBook account decoding - read the account bytes directly using the program's zero-copy layout
+//
+// 布局与 programs/pinpet/src/instructions/structs.rs 中的 OrderBook / MarginOrder 一致,
+// 与 RocksDB 镜像无关, 用于排查链上与镜像的差异
+// The layout mirrors OrderBook / MarginOrder in programs/pinpet/src/instructions/structs.rs;
+// it is independent of the RocksDB mirror and used to debug divergence between chain and mirror
+
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use utoipa::ToSchema;
+
+use crate::orderbook::{
+    errors::{OrderBookError, Result},
+    types::{Direction, MarginOrder},
+};
+
+/// Anchor 账户鉴别器长度 / Anchor account discriminator length
+const DISCRIMINATOR_LEN: usize = 8;
+
+/// OrderBook 头部大小 (repr(C), 8 字节对齐) / OrderBook header size (repr(C), 8-byte aligned)
+pub const ONCHAIN_HEADER_SIZE: usize = 104;
+
+/// MarginOrder 槽位大小 / MarginOrder slot size
+pub const ONCHAIN_ORDER_SIZE: usize = 192;
+
+/// 链上 OrderBook 头部 / On-chain OrderBook header
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct OnchainOrderBookHeader {
+    /// 版本号 / Version number
+    pub version: u8,
+    /// 订单类型(1=做多/dn, 2=做空/up) / Order type (1=long/dn, 2=short/up)
+    pub order_type: u8,
+    /// PDA bump
+    pub bump: u8,
+    /// 协议管理员 / Authority
+    pub authority: String,
+    /// 订单 ID 计数器 / Order ID counter
+    pub order_id_counter: u64,
+    /// 账本创建时间戳(秒) / Created timestamp (seconds)
+    pub created_at: u32,
+    /// 最后修改时间戳(秒) / Last modified timestamp (seconds)
+    pub last_modified: u32,
+    /// 总容量 / Total capacity
+    pub total_capacity: u32,
+    /// 链表头索引 / Linked list head index
+    pub head: u16,
+    /// 链表尾索引 / Linked list tail index
+    pub tail: u16,
+    /// 当前订单总数 / Current order count
+    pub total: u16,
+}
+
+/// 解码后的链上 OrderBook / Decoded on-chain OrderBook
+#[derive(Debug, Clone)]
+pub struct OnchainOrderBook {
+    pub header: OnchainOrderBookHeader,
+    /// 前 total 个槽位, 按索引排列 / The first `total` slots, in index order
+    pub orders: Vec<(u16, MarginOrder)>,
+}
+
+/// 推导 OrderBook PDA: seeds = ["up_orderbook" | "down_orderbook", mint]
+/// Derive the OrderBook PDA: seeds = ["up_orderbook" | "down_orderbook", mint]
+pub fn orderbook_pda(program_id: &Pubkey, mint: &Pubkey, direction: Direction) -> Pubkey {
+    let seed: &[u8] = match direction {
+        Direction::Up => b"up_orderbook",
+        Direction::Dn => b"down_orderbook",
+    };
+    Pubkey::find_program_address(&[seed, mint.as_ref()], program_id).0
+}
+
+/// 解码账户数据, 账户大小必须与 total_capacity 一致
+/// Decode account data; the account size must match total_capacity
+pub fn decode_orderbook_account(data: &[u8]) -> Result<OnchainOrderBook> {
+    if data.len() < DISCRIMINATOR_LEN + ONCHAIN_HEADER_SIZE {
+        return Err(OrderBookError::InvalidAccountData(format!(
+            "account too small for an OrderBook header: {} bytes",
+            data.len()
+        )));
+    }
+
+    let header_bytes = &data[DISCRIMINATOR_LEN..DISCRIMINATOR_LEN + ONCHAIN_HEADER_SIZE];
+    let header = OnchainOrderBookHeader {
+        version: header_bytes[0],
+        order_type: header_bytes[1],
+        bump: header_bytes[2],
+        authority: read_pubkey(header_bytes, 8).to_string(),
+        order_id_counter: read_u64(header_bytes, 40),
+        created_at: read_u32(header_bytes, 48),
+        last_modified: read_u32(header_bytes, 52),
+        total_capacity: read_u32(header_bytes, 56),
+        head: read_u16(header_bytes, 60),
+        tail: read_u16(header_bytes, 62),
+        total: read_u16(header_bytes, 64),
+    };
+
+    let expected_len =
+        DISCRIMINATOR_LEN + ONCHAIN_HEADER_SIZE + header.total_capacity as usize * ONCHAIN_ORDER_SIZE;
+    if data.len() != expected_len {
+        return Err(OrderBookError::InvalidAccountData(format!(
+            "unexpected OrderBook account size: {} bytes, expected {} for capacity {}",
+            data.len(),
+            expected_len,
+            header.total_capacity
+        )));
+    }
+    if u32::from(header.total) > header.total_capacity {
+        return Err(OrderBookError::InvalidAccountData(format!(
+            "OrderBook total {} exceeds capacity {}",
+            header.total, header.total_capacity
+        )));
+    }
+
+    let slots = &data[DISCRIMINATOR_LEN + ONCHAIN_HEADER_SIZE..];
+    let orders = (0..header.total)
+        .map(|index| {
+            let start = index as usize * ONCHAIN_ORDER_SIZE;
+            (index, decode_order(&slots[start..start + ONCHAIN_ORDER_SIZE]))
+        })
+        .collect();
+
+    Ok(OnchainOrderBook { header, orders })
+}
+
+/// 解码单个 MarginOrder 槽位 (192 字节) / Decode a single MarginOrder slot (192 bytes)
+fn decode_order(slot: &[u8]) -> MarginOrder {
+    // 止盈价为小端 96 位整数 / The take-profit price is a little-endian 96-bit integer
+    let mut take_profit = [0u8; 16];
+    take_profit[..12].copy_from_slice(&slot[179..191]);
+
+    MarginOrder {
+        user: read_pubkey(slot, 0).to_string(),
+        lock_lp_start_price: read_u128(slot, 32),
+        lock_lp_end_price: read_u128(slot, 48),
+        open_price: read_u128(slot, 64),
+        take_profit_price: u128::from_le_bytes(take_profit),
+        order_id: read_u64(slot, 80),
+        lock_lp_sol_amount: read_u64(slot, 88),
+        lock_lp_token_amount: read_u64(slot, 96),
+        next_lp_sol_amount: read_u64(slot, 104),
+        next_lp_token_amount: read_u64(slot, 112),
+        margin_init_sol_amount: read_u64(slot, 120),
+        margin_sol_amount: read_u64(slot, 128),
+        borrow_amount: read_u64(slot, 136),
+        position_asset_amount: read_u64(slot, 144),
+        realized_sol_amount: read_u64(slot, 152),
+        version: read_u32(slot, 160),
+        start_time: read_u32(slot, 164),
+        end_time: read_u32(slot, 168),
+        next_order: read_u16(slot, 172),
+        prev_order: read_u16(slot, 174),
+        borrow_fee: read_u16(slot, 176),
+        order_type: slot[178],
+    }
+}
+
+fn read_pubkey(bytes: &[u8], offset: usize) -> Pubkey {
+    Pubkey::new_from_array(bytes[offset..offset + 32].try_into().unwrap())
+}
+
+fn read_u128(bytes: &[u8], offset: usize) -> u128 {
+    u128::from_le_bytes(bytes[offset..offset + 16].try_into().unwrap())
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes(bytes[offset..offset + 2].try_into().unwrap())
+}
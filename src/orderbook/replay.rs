@@ -0,0 +1,344 @@
+// OrderBook 历史回放 - 按时间点重建订单簿
+// OrderBook History Replay - Point-in-time order book reconstruction
+//
+// 在内存中按事件顺序重放与 StorageEventHandler 相同的链表操作
+// Replays the same linked-list operations as StorageEventHandler in memory, in event order
+
+use chrono::{DateTime, Utc};
+
+use crate::orderbook::{
+    errors::{OrderBookError, Result},
+    manager::gap_liquidity,
+    types::{Direction, MarginOrder},
+};
+use crate::solana::events::PinpetEvent;
+use crate::util::curve::CurveAMM;
+
+/// 单次回放允许处理的最大事件数 / Maximum number of events a single replay may process
+pub const MAX_REPLAY_EVENTS: usize = 100_000;
+
+/// 回放结果 / Replay result
+#[derive(Debug, Clone)]
+pub struct ReplayedOrderBook {
+    /// 按链表顺序排列的 (索引, 订单) / (index, order) in linked-list order
+    pub orders: Vec<(u16, MarginOrder)>,
+
+    /// 实际回放的事件数 / Number of events replayed
+    pub replayed_events: usize,
+}
+
+/// 将 mint 的事件回放到指定时间点, 返回该时刻的活跃订单
+/// Replay a mint's events up to a point in time and return the orders active at that moment
+///
+/// # 参数 / Parameters
+/// * `events` - 该 mint 的全部事件, 按 slot 升序 / All events of the mint, ascending by slot
+/// * `direction` - Up(做空) 或 Dn(做多) / Up(short) or Dn(long)
+/// * `until` - 回放截止时间(含) / Replay cutoff time (inclusive)
+///
+/// # 错误 / Errors
+/// - 第一个事件不是 TokenCreated (更早的事件已缺失) / The first event is not TokenCreated (earlier events are missing)
+/// - 截止时间前的事件超过 `MAX_REPLAY_EVENTS` / More than `MAX_REPLAY_EVENTS` events before the cutoff
+pub fn replay_orderbook_at(
+    events: &[PinpetEvent],
+    direction: Direction,
+    until: DateTime<Utc>,
+) -> Result<ReplayedOrderBook> {
+    let mut book = ReplayBook::new(direction);
+
+    // 订单簿随 TokenCreated 创建, 缺少它说明历史不完整
+    // The book is created with TokenCreated, missing it means the history is incomplete
+    match events.first() {
+        Some(PinpetEvent::TokenCreated(_)) => {}
+        Some(_) => {
+            return Err(OrderBookError::HistoryUnavailable(
+                "events before the replay window are missing (no TokenCreated event)".to_string(),
+            ));
+        }
+        None => {
+            return Err(OrderBookError::HistoryUnavailable(
+                "no events stored for this mint".to_string(),
+            ));
+        }
+    }
+
+    let mut replayed_events = 0;
+    for event in events {
+        if event_timestamp(event) > until {
+            break;
+        }
+        if replayed_events >= MAX_REPLAY_EVENTS {
+            return Err(OrderBookError::ReplayWindowExceeded {
+                max: MAX_REPLAY_EVENTS,
+            });
+        }
+        book.apply(event)?;
+        replayed_events += 1;
+    }
+
+    Ok(ReplayedOrderBook {
+        orders: book.into_orders(),
+        replayed_events,
+    })
+}
+
+/// 事件时间戳 / Event timestamp
+fn event_timestamp(event: &PinpetEvent) -> DateTime<Utc> {
+    match event {
+        PinpetEvent::TokenCreated(e) => e.timestamp,
+        PinpetEvent::BuySell(e) => e.timestamp,
+        PinpetEvent::LongShort(e) => e.timestamp,
+        PinpetEvent::FullClose(e) => e.timestamp,
+        PinpetEvent::PartialClose(e) => e.timestamp,
+        PinpetEvent::MilestoneDiscount(e) => e.timestamp,
+        PinpetEvent::AddMargin(e) => e.timestamp,
+        PinpetEvent::ExtendOrder(e) => e.timestamp,
+    }
+}
+
+/// 内存中的单方向订单簿 (槽位 + 双向链表, 与 OrderBookDBManager 布局一致)
+/// In-memory single-direction order book (slots + doubly linked list, same layout as OrderBookDBManager)
+struct ReplayBook {
+    direction: Direction,
+    slots: Vec<MarginOrder>,
+    head: u16,
+    tail: u16,
+}
+
+impl ReplayBook {
+    fn new(direction: Direction) -> Self {
+        Self {
+            direction,
+            slots: Vec::new(),
+            head: u16::MAX,
+            tail: u16::MAX,
+        }
+    }
+
+    /// 应用单个事件, 与 StorageEventHandler 的处理一致
+    /// Apply a single event, consistent with StorageEventHandler
+    fn apply(&mut self, event: &PinpetEvent) -> Result<()> {
+        match event {
+            PinpetEvent::LongShort(e) => {
+                let order_direction = if e.order_type == 1 { Direction::Dn } else { Direction::Up };
+                if order_direction == self.direction {
+                    self.insert_event_order(e);
+                } else if !e.liquidate_indices.is_empty() {
+                    // 做多清算 up, 做空清算 dn / Long liquidates up, short liquidates dn
+                    self.remove_indices(&e.liquidate_indices)?;
+                }
+            }
+            PinpetEvent::BuySell(e) => {
+                let direction = if e.is_buy { Direction::Up } else { Direction::Dn };
+                if direction == self.direction {
+                    self.remove_indices(&e.liquidate_indices)?;
+                }
+            }
+            PinpetEvent::FullClose(e) => {
+                let direction = if e.is_close_long { Direction::Dn } else { Direction::Up };
+                if direction == self.direction {
+                    self.remove_indices(&e.liquidate_indices)?;
+                }
+            }
+            PinpetEvent::PartialClose(e) => {
+                let direction = if e.is_close_long { Direction::Dn } else { Direction::Up };
+                if direction == self.direction {
+                    if let Some(order) = self.slots.get_mut(e.order_index as usize) {
+                        if order.order_id == e.order_id {
+                            order.lock_lp_start_price = e.lock_lp_start_price;
+                            order.lock_lp_end_price = e.lock_lp_end_price;
+                            order.lock_lp_sol_amount = e.lock_lp_sol_amount;
+                            order.lock_lp_token_amount = e.lock_lp_token_amount;
+                            order.end_time = e.end_time;
+                            order.margin_sol_amount = e.margin_sol_amount;
+                            order.borrow_amount = e.borrow_amount;
+                            order.position_asset_amount = e.position_asset_amount;
+                            order.borrow_fee = e.borrow_fee;
+                            order.realized_sol_amount = e.realized_sol_amount;
+                            order.version += 1;
+                        }
+                    }
+                    self.remove_indices(&e.liquidate_indices)?;
+                }
+            }
+            PinpetEvent::AddMargin(e) => {
+                let direction = if e.order_type == 1 { Direction::Dn } else { Direction::Up };
+                if direction == self.direction {
+                    if let Some(order) = self.slots.get_mut(e.order_index as usize) {
+                        if order.order_id == e.order_id {
+                            order.lock_lp_start_price = e.lock_lp_start_price;
+                            order.lock_lp_end_price = e.lock_lp_end_price;
+                            order.lock_lp_sol_amount = e.lock_lp_sol_amount;
+                            order.lock_lp_token_amount = e.lock_lp_token_amount;
+                            order.margin_sol_amount = e.margin_sol_amount;
+                            order.version += 1;
+                        }
+                    }
+                }
+            }
+            PinpetEvent::ExtendOrder(e) => {
+                let direction = if e.order_type == 1 { Direction::Dn } else { Direction::Up };
+                if direction == self.direction {
+                    if let Some(order) = self.slots.get_mut(e.order_index as usize) {
+                        if order.order_id == e.order_id {
+                            order.end_time = e.end_time;
+                            order.version += 1;
+                        }
+                    }
+                }
+            }
+            PinpetEvent::TokenCreated(_) | PinpetEvent::MilestoneDiscount(_) => {}
+        }
+        Ok(())
+    }
+
+    /// 插入开仓事件的订单 / Insert the order of an open-position event
+    fn insert_event_order(&mut self, e: &crate::solana::events::LongShortEvent) {
+        let order = MarginOrder {
+            user: e.payer.clone(),
+            lock_lp_start_price: e.lock_lp_start_price,
+            lock_lp_end_price: e.lock_lp_end_price,
+            open_price: e.open_price,
+            take_profit_price: e.take_profit_price,
+            order_id: e.order_id,
+            lock_lp_sol_amount: e.lock_lp_sol_amount,
+            lock_lp_token_amount: e.lock_lp_token_amount,
+            next_lp_sol_amount: 0,
+            next_lp_token_amount: 0,
+            margin_init_sol_amount: e.margin_sol_amount,
+            margin_sol_amount: e.margin_sol_amount,
+            borrow_amount: e.borrow_amount,
+            position_asset_amount: e.position_asset_amount,
+            realized_sol_amount: 0,
+            version: 1,
+            start_time: e.start_time,
+            end_time: e.end_time,
+            next_order: u16::MAX,
+            prev_order: u16::MAX,
+            borrow_fee: e.borrow_fee,
+            order_type: e.order_type,
+        };
+
+        // 与 StorageEventHandler 相同的插入位置推导, order_index 为 0 时插入到头部
+        // Same insert position derivation as StorageEventHandler, order_index 0 inserts at head
+        let total = self.slots.len() as u16;
+        let after = if total == 0 || e.order_index == 0 {
+            u16::MAX
+        } else if e.order_index >= total {
+            self.tail
+        } else {
+            e.order_index - 1
+        };
+        self.insert_after(after, order);
+    }
+
+    /// 在 after 之后插入, u16::MAX 表示插入到头部, 新订单总是占用末尾槽位
+    /// Insert after `after`, u16::MAX inserts at head; the new order always takes the last slot
+    fn insert_after(&mut self, after: u16, mut order: MarginOrder) {
+        let new_index = self.slots.len() as u16;
+        let after = if after != u16::MAX && after >= new_index {
+            self.tail
+        } else {
+            after
+        };
+
+        if after == u16::MAX {
+            order.prev_order = u16::MAX;
+            order.next_order = self.head;
+            if self.head != u16::MAX {
+                self.slots[self.head as usize].prev_order = new_index;
+            } else {
+                self.tail = new_index;
+            }
+            self.head = new_index;
+        } else {
+            let old_next = self.slots[after as usize].next_order;
+            order.prev_order = after;
+            order.next_order = old_next;
+            self.slots[after as usize].next_order = new_index;
+            if old_next != u16::MAX {
+                self.slots[old_next as usize].prev_order = new_index;
+            } else {
+                self.tail = new_index;
+            }
+        }
+        self.slots.push(order);
+    }
+
+    /// 与 batch_remove_by_indices_unsafe 相同: 降序删除, 末尾节点搬移到被删除的槽位
+    /// Same as batch_remove_by_indices_unsafe: delete in descending order, moving the last slot into the freed one
+    fn remove_indices(&mut self, indices: &[u16]) -> Result<()> {
+        let mut sorted = indices.to_vec();
+        sorted.sort_unstable_by(|a, b| b.cmp(a));
+        sorted.dedup();
+
+        for index in sorted {
+            let total = self.slots.len() as u16;
+            if index >= total {
+                return Err(OrderBookError::InvalidSlotIndex { index, total });
+            }
+
+            // 摘除节点 / Unlink node
+            let (prev, next) = {
+                let removed = &self.slots[index as usize];
+                (removed.prev_order, removed.next_order)
+            };
+            if prev != u16::MAX {
+                self.slots[prev as usize].next_order = next;
+            } else {
+                self.head = next;
+            }
+            if next != u16::MAX {
+                self.slots[next as usize].prev_order = prev;
+            } else {
+                self.tail = prev;
+            }
+
+            // 末尾槽位搬移到被删除位置 / Move the last slot into the freed position
+            let last = total - 1;
+            self.slots.swap_remove(index as usize);
+            if index < last {
+                let (moved_prev, moved_next) = {
+                    let order = &self.slots[index as usize];
+                    (order.prev_order, order.next_order)
+                };
+                if moved_prev != u16::MAX {
+                    self.slots[moved_prev as usize].next_order = index;
+                }
+                if moved_next != u16::MAX {
+                    self.slots[moved_next as usize].prev_order = index;
+                }
+                if self.head == last {
+                    self.head = index;
+                }
+                if self.tail == last {
+                    self.tail = index;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 按链表顺序输出订单, 并用曲线数学重新计算相邻订单之间的流动性
+    /// Emit orders in linked-list order, recomputing the liquidity between adjacent orders with the curve math
+    fn into_orders(self) -> Vec<(u16, MarginOrder)> {
+        let mut orders = Vec::with_capacity(self.slots.len());
+        let mut current = self.head;
+        while current != u16::MAX && orders.len() < self.slots.len() {
+            let mut order = self.slots[current as usize].clone();
+            let (next_lp_sol, next_lp_token) = if order.next_order == u16::MAX {
+                // 尾部之后是无限空间 / Unbounded space after the tail
+                (CurveAMM::MAX_U64, CurveAMM::MAX_U64)
+            } else {
+                let next = &self.slots[order.next_order as usize];
+                gap_liquidity(self.direction, order.lock_lp_end_price, next.lock_lp_start_price).unwrap_or((0, 0))
+            };
+            order.next_lp_sol_amount = next_lp_sol;
+            order.next_lp_token_amount = next_lp_token;
+
+            let next = order.next_order;
+            orders.push((current, order));
+            current = next;
+        }
+        orders
+    }
+}
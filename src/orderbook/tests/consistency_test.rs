@@ -0,0 +1,78 @@
+// 订单簿一致性检查测试
+// Order Book Consistency Check Tests
+
+use super::*;
+use crate::orderbook::OrderBookError;
+
+const MINT: &str = "EPjFWaLb3crLvQQf89kiNqEX5jg5Kv431J06Y1AD3ic";
+
+#[test]
+fn test_header_consistency_problems() {
+    let mut header = OrderBookHeader::new(1, "authority".to_string());
+    assert!(header.consistency_problems().is_empty());
+
+    header.total = 2;
+    header.total_capacity = 2;
+    header.head = 0;
+    header.tail = 1;
+    assert!(header.consistency_problems().is_empty());
+
+    header.tail = 2;
+    header.total_capacity = 1;
+    let problems = header.consistency_problems();
+    assert_eq!(problems.len(), 2);
+
+    header.tail = u16::MAX;
+    header.total_capacity = 2;
+    assert_eq!(header.consistency_problems().len(), 1);
+}
+
+#[test]
+fn test_check_consistency_detects_stray_and_truncated_slots() {
+    let (db, temp_path) = create_test_db();
+    let manager = OrderBookDBManager::new(db.clone(), MINT.to_string(), Direction::Dn);
+    manager.initialize("authority".to_string()).unwrap();
+    manager.insert_after(u16::MAX, &create_test_order_with_id("Alice", 1, 2000000, 2100000)).unwrap();
+    manager.insert_after(0, &create_test_order_with_id("Alice", 2, 1000000, 1100000)).unwrap();
+
+    let report = manager.check_consistency().unwrap();
+    assert!(report.consistent, "{:?}", report.problems);
+    assert_eq!(report.stored_slots, 2);
+
+    // 截断的槽位数据 / Truncated slot data
+    db.put(format!("orderbook_slot:{}:dn:{:05}", MINT, 1).as_bytes(), b"{\"user\":").unwrap();
+    // header 之外的多余槽位 / Stray slot beyond the header's total
+    let stray = create_test_order_with_id("Alice", 3, 500000, 600000).to_bytes().unwrap();
+    db.put(format!("orderbook_slot:{}:dn:{:05}", MINT, 5).as_bytes(), &stray).unwrap();
+
+    let report = manager.check_consistency().unwrap();
+    assert!(!report.consistent);
+    assert_eq!(report.stored_slots, 3);
+    assert!(report.problems.iter().any(|p| p.contains("slot 1 unreadable")));
+    assert!(report.problems.iter().any(|p| p.contains("slot 5 stored beyond total 2")));
+    assert!(report.problems.iter().any(|p| p.contains("3 slots stored but total is 2")));
+
+    cleanup_test_db(&temp_path);
+}
+
+#[test]
+fn test_full_book_rejects_insert_with_capacity_error() {
+    let (db, temp_path) = create_test_db();
+    let manager = OrderBookDBManager::new(db.clone(), MINT.to_string(), Direction::Dn);
+    manager.initialize("authority".to_string()).unwrap();
+
+    let mut header = manager.load_header().unwrap();
+    assert!(!header.at_capacity());
+
+    // 直接写入满载 header, 不必真的插入 65535 个订单 / Store a full header directly instead of inserting 65535 orders
+    header.total = OrderBookHeader::MAX_CAPACITY as u16;
+    header.total_capacity = OrderBookHeader::MAX_CAPACITY;
+    assert!(header.at_capacity());
+    db.put(format!("orderbook_header:{}:dn", MINT).as_bytes(), header.to_bytes().unwrap())
+        .unwrap();
+
+    let result = manager.insert_after(u16::MAX, &create_test_order_with_id("Alice", 1, 2000000, 2100000));
+    assert!(matches!(result, Err(OrderBookError::ExceedsMaxCapacity { .. })));
+
+    cleanup_test_db(&temp_path);
+}
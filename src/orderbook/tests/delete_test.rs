@@ -2,6 +2,7 @@
 // Delete Operation Tests
 
 use super::*;
+use crate::orderbook::OrderBookError;
 
 /// 辅助函数: 插入多个订单
 fn insert_orders(manager: &OrderBookDBManager, count: usize) {
@@ -231,3 +232,79 @@ fn test_delete_empty_array() {
 
     cleanup_test_db(&temp_path);
 }
+
+#[test]
+fn test_active_indices_match_walkable_nodes_after_middle_delete() {
+    let (manager, temp_path) = create_test_manager();
+    manager
+        .initialize("9B5X6wrjJVcXHnbPfZ8wP4k5m9n2q1r7t3u2v5w8x1y".to_string())
+        .unwrap();
+
+    for i in 0..5u16 {
+        let mut order = create_test_order(&format!("User{}", i), (i as u128 + 1) * 1000000);
+        order.order_id = i as u64 + 1;
+        let after = if i == 0 { u16::MAX } else { i - 1 };
+        manager.insert_after(after, &order).unwrap();
+    }
+
+    // 删除中间节点, 末尾节点被搬移到 index 2 / Delete a middle node, the tail moves into index 2
+    manager.batch_remove_by_indices_unsafe(&[2], 1, 1500000).unwrap();
+
+    let mut walked = Vec::new();
+    manager
+        .traverse(u16::MAX, 0, |index, _order| {
+            walked.push(index);
+            Ok(true)
+        })
+        .unwrap();
+    walked.sort_unstable();
+
+    let header = manager.load_header().unwrap();
+    assert_eq!(header.total, 4);
+    assert_eq!(walked.len(), header.total as usize);
+    assert_eq!(manager.load_active_indices().unwrap(), walked);
+
+    cleanup_test_db(&temp_path);
+}
+
+#[test]
+fn test_tail_recovery_errors_on_cycle() {
+    let (db, temp_path) = create_test_db();
+    let mint = "EPjFWaLb3crLvQQf89kiNqEX5jg5Kv431J06Y1AD3ic";
+    let manager = OrderBookDBManager::new(db.clone(), mint.to_string(), Direction::Dn);
+    manager
+        .initialize("9B5X6wrjJVcXHnbPfZ8wP4k5m9n2q1r7t3u2v5w8x1y".to_string())
+        .unwrap();
+
+    for i in 0..6u16 {
+        let mut order = create_test_order(&format!("User{}", i), (i as u128 + 1) * 1000000);
+        order.order_id = i as u64 + 1;
+        let after = if i == 0 { u16::MAX } else { i - 1 };
+        manager.insert_after(after, &order).unwrap();
+    }
+
+    // 破坏链表: 3 -> 2 形成不经过 head 的环, 并让 header.tail 越界以触发尾指针恢复
+    // Corrupt the chain: 3 -> 2 forms a cycle that skips head, and push header.tail out of range to trigger tail recovery
+    let mut order3 = manager.get_order(3).unwrap();
+    order3.next_order = 2;
+    db.put(
+        format!("orderbook_slot:{}:dn:{:05}", mint, 3).as_bytes(),
+        order3.to_bytes().unwrap(),
+    )
+    .unwrap();
+    let mut header = manager.load_header().unwrap();
+    header.tail = 9;
+    db.put(
+        format!("orderbook_header:{}:dn", mint).as_bytes(),
+        header.to_bytes().unwrap(),
+    )
+    .unwrap();
+
+    let result = manager.batch_remove_by_indices_unsafe(&[0], 1, 1500000);
+    assert!(matches!(result, Err(OrderBookError::InvalidAccountData(_))));
+
+    // 失败的删除不会写入任何数据 / The failed removal writes nothing
+    assert_eq!(manager.load_header().unwrap().total, 6);
+
+    cleanup_test_db(&temp_path);
+}
@@ -0,0 +1,50 @@
+// 订单簿深度图测试
+// Order Book Depth Profile Tests
+
+use super::*;
+
+#[test]
+fn test_depth_profile_sums_orders_into_buckets() {
+    let (manager, temp_path) = create_test_manager();
+    manager.initialize("authority".to_string()).unwrap();
+
+    // 做多订单簿价格从 head 向 tail 递减, 价格范围 [1000, 1999]
+    // Long book prices descend from head to tail, range [1000, 1999]
+    // (锁定起点, 锁定终点, SOL, token) / (lock start, lock end, SOL, token)
+    let orders = [(1900, 1999, 10, 100), (1800, 1899, 20, 200), (1200, 1299, 30, 300), (1000, 1099, 40, 400)];
+    let mut prev = u16::MAX;
+    for (i, (start, end, sol, token)) in orders.into_iter().enumerate() {
+        let order = MarginOrder {
+            lock_lp_sol_amount: sol,
+            lock_lp_token_amount: token,
+            ..create_test_order_with_id("Alice", i as u64 + 1, start, end)
+        };
+        prev = manager.insert_after(prev, &order).unwrap().0;
+    }
+
+    let profile = manager.depth_profile(2).unwrap();
+    assert_eq!(profile.len(), 2);
+    assert_eq!((profile[0].price_low, profile[0].price_high), (1000, 1499));
+    assert_eq!((profile[1].price_low, profile[1].price_high), (1500, 1999));
+    assert_eq!((profile[0].sol, profile[0].token, profile[0].orders), (70, 700, 2));
+    assert_eq!((profile[1].sol, profile[1].token, profile[1].orders), (30, 300, 2));
+
+    // 合计不随区间数变化 / Totals do not depend on the bucket count
+    let fine = manager.depth_profile(7).unwrap();
+    assert!(fine.len() <= 7);
+    assert_eq!(fine.iter().map(|b| b.sol).sum::<u64>(), 100);
+    assert_eq!(fine.first().unwrap().price_low, 1000);
+    assert_eq!(fine.last().unwrap().price_high, 1999);
+
+    cleanup_test_db(&temp_path);
+}
+
+#[test]
+fn test_depth_profile_empty_book() {
+    let (manager, temp_path) = create_test_manager();
+    manager.initialize("authority".to_string()).unwrap();
+
+    assert!(manager.depth_profile(10).unwrap().is_empty());
+
+    cleanup_test_db(&temp_path);
+}
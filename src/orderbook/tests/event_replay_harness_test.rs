@@ -0,0 +1,926 @@
+// 订单簿事件回放校验工具
+// Order Book Event Replay Verification Harness
+//
+// 把 LongShort / PartialClose / FullClose 事件序列送入服务端真实的事件处理路径 (StorageEventHandler),
+// 再与一个只用 Vec<MarginOrder> 表示的参考模型逐项比对: 槽位内容、链表顺序、head/tail、活跃索引和 order_id 映射。
+// 事件实现了 Deserialize, 抓取到的主网事件序列可以用 serde_json 读成 Vec<PinpetEvent> 后直接交给 verify_replay。
+//
+// Feeds a sequence of LongShort / PartialClose / FullClose events through the server's real event path
+// (StorageEventHandler), then compares the result against a reference model that is just a Vec<MarginOrder>:
+// slot contents, list order, head/tail, active indices and the order_id map.
+// Events implement Deserialize, so captured mainnet sequences can be read into Vec<PinpetEvent> with serde_json
+// and handed to verify_replay as-is.
+
+use crate::config::test_config;
+use crate::db::{EventStorage, OrderBookStorage, ShardedLocks, TokenStorage};
+use crate::orderbook::manager::gap_liquidity;
+use crate::orderbook::{Direction, MarginOrder};
+use crate::solana::events::{
+    AddMarginEvent, EventParser, ExtendOrderEvent, FullCloseEvent, LongShortEvent, PartialCloseEvent, PinpetEvent,
+    ADD_MARGIN_EVENT_DISCRIMINATOR, BUY_SELL_EVENT_DISCRIMINATOR, EXTEND_ORDER_EVENT_DISCRIMINATOR,
+};
+use crate::solana::finality::{FinalityGate, SlotRollback};
+use crate::solana::{EventHandler, StorageEventHandler};
+use crate::util::curve::CurveAMM;
+use base64::engine::Engine;
+use chrono::{TimeZone, Utc};
+use rocksdb::{Options, DB};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::cleanup_test_db;
+
+const MINT: &str = "EPjFWaLb3crLvQQf89kiNqEX5jg5Kv431J06Y1AD3ic";
+
+// ==================== 参考模型 / Reference model ====================
+
+/// 参考订单簿: 槽位就是 Vec 下标, 链表顺序单独用 order_id 列表表示
+/// Reference book: slots are Vec positions, list order is kept separately as a list of order_ids
+#[derive(Default)]
+struct ReferenceBook {
+    slots: Vec<MarginOrder>,
+    sequence: Vec<u64>,
+}
+
+impl ReferenceBook {
+    /// 在 after 槽位的订单之后插入, None 表示插入到头部; 新订单占用末尾槽位
+    /// Insert after the order in slot `after`, None inserts at head; the new order takes the last slot
+    fn insert(&mut self, after: Option<u16>, order: MarginOrder) {
+        let position = match after {
+            None => 0,
+            Some(slot) => {
+                let after_id = self.slots[slot as usize].order_id;
+                self.sequence.iter().position(|&id| id == after_id).unwrap() + 1
+            }
+        };
+        self.sequence.insert(position, order.order_id);
+        self.slots.push(order);
+    }
+
+    /// 降序删除, 末尾槽位补到被删除的位置 (swap_remove)
+    /// Delete in descending order, the last slot fills the freed one (swap_remove)
+    fn remove(&mut self, indices: &[u16]) {
+        let mut sorted = indices.to_vec();
+        sorted.sort_unstable_by(|a, b| b.cmp(a));
+        sorted.dedup();
+        for index in sorted {
+            let removed = self.slots.swap_remove(index as usize);
+            self.sequence.retain(|&id| id != removed.order_id);
+        }
+    }
+
+    /// 按 order_id 查找槽位 / Find the slot of an order_id
+    fn slot_of(&self, order_id: u64) -> u16 {
+        self.slots.iter().position(|o| o.order_id == order_id).unwrap() as u16
+    }
+
+    /// 与 update_order_with_neighbor_recalc 相同地重新计算 next_lp_*: 终点价格变化且不是尾部时重算本订单,
+    /// 有前节点时总是重算前节点。插入和删除不改动 next_lp_* (新订单为 0), 尾部也不会被写成 MAX_U64
+    /// Recompute next_lp_* the way update_order_with_neighbor_recalc does: the order's own when its end price moved
+    /// and it is not the tail, the predecessor's whenever there is one. Inserts and removals leave next_lp_* alone
+    /// (new orders hold 0), and the tail is never rewritten to MAX_U64
+    fn recalc_next_lp(&mut self, direction: Direction, slot: u16, old_end_price: u128) {
+        let order = self.slots[slot as usize].clone();
+        let position = self.sequence.iter().position(|&id| id == order.order_id).unwrap();
+
+        if let Some(&next_id) = self.sequence.get(position + 1) {
+            if order.lock_lp_end_price != old_end_price {
+                let next_start_price = self.slots[self.slot_of(next_id) as usize].lock_lp_start_price;
+                let (sol, token) = gap_liquidity(direction, order.lock_lp_end_price, next_start_price).unwrap();
+                let own = &mut self.slots[slot as usize];
+                own.next_lp_sol_amount = sol;
+                own.next_lp_token_amount = token;
+            }
+        }
+        if position > 0 {
+            let prev_slot = self.slot_of(self.sequence[position - 1]) as usize;
+            let (sol, token) =
+                gap_liquidity(direction, self.slots[prev_slot].lock_lp_end_price, order.lock_lp_start_price).unwrap();
+            self.slots[prev_slot].next_lp_sol_amount = sol;
+            self.slots[prev_slot].next_lp_token_amount = token;
+        }
+    }
+}
+
+/// 每个方向一本参考订单簿, 按与 StorageEventHandler 相同的规则应用事件
+/// One reference book per direction, applying events by the same rules as StorageEventHandler
+#[derive(Default)]
+struct ReferenceModel {
+    books: HashMap<Direction, ReferenceBook>,
+}
+
+impl ReferenceModel {
+    fn book(&mut self, direction: Direction) -> &mut ReferenceBook {
+        self.books.entry(direction).or_default()
+    }
+
+    fn apply(&mut self, event: &PinpetEvent) {
+        match event {
+            PinpetEvent::LongShort(e) => {
+                let (direction, liquidate_direction) = if e.order_type == 1 {
+                    (Direction::Dn, Direction::Up)
+                } else {
+                    (Direction::Up, Direction::Dn)
+                };
+                let book = self.book(direction);
+                let total = book.slots.len() as u16;
+                let after = if total == 0 || e.order_index == 0 {
+                    None
+                } else if e.order_index >= total {
+                    Some(book.slot_of(*book.sequence.last().unwrap()))
+                } else {
+                    Some(e.order_index - 1)
+                };
+                book.insert(after, order_from_event(e));
+                self.book(liquidate_direction).remove(&e.liquidate_indices);
+            }
+            PinpetEvent::PartialClose(e) => {
+                let direction = if e.is_close_long { Direction::Dn } else { Direction::Up };
+                let book = self.book(direction);
+                let Some(order) = book.slots.get_mut(e.order_index as usize) else {
+                    return;
+                };
+                // order_id 不符时服务端整条事件失败, 清算也不执行 / On an order_id mismatch the server fails the whole event, liquidations included
+                if order.order_id != e.order_id {
+                    return;
+                }
+                let old_end_price = order.lock_lp_end_price;
+                order.lock_lp_start_price = e.lock_lp_start_price;
+                order.lock_lp_end_price = e.lock_lp_end_price;
+                order.lock_lp_sol_amount = e.lock_lp_sol_amount;
+                order.lock_lp_token_amount = e.lock_lp_token_amount;
+                order.end_time = e.end_time;
+                order.margin_sol_amount = e.margin_sol_amount;
+                order.borrow_amount = e.borrow_amount;
+                order.position_asset_amount = e.position_asset_amount;
+                order.borrow_fee = e.borrow_fee;
+                order.realized_sol_amount = e.realized_sol_amount;
+                book.recalc_next_lp(direction, e.order_index, old_end_price);
+                book.remove(&e.liquidate_indices);
+            }
+            PinpetEvent::FullClose(e) => {
+                self.book(if e.is_close_long { Direction::Dn } else { Direction::Up })
+                    .remove(&e.liquidate_indices);
+            }
+            PinpetEvent::BuySell(e) => {
+                self.book(if e.is_buy { Direction::Up } else { Direction::Dn })
+                    .remove(&e.liquidate_indices);
+            }
+            PinpetEvent::AddMargin(e) => {
+                let direction = if e.order_type == 1 { Direction::Dn } else { Direction::Up };
+                let book = self.book(direction);
+                let Some(order) = book.slots.get_mut(e.order_index as usize) else {
+                    return;
+                };
+                if order.order_id != e.order_id {
+                    return;
+                }
+                let old_end_price = order.lock_lp_end_price;
+                order.lock_lp_start_price = e.lock_lp_start_price;
+                order.lock_lp_end_price = e.lock_lp_end_price;
+                order.lock_lp_sol_amount = e.lock_lp_sol_amount;
+                order.lock_lp_token_amount = e.lock_lp_token_amount;
+                order.margin_sol_amount = e.margin_sol_amount;
+                book.recalc_next_lp(direction, e.order_index, old_end_price);
+            }
+            PinpetEvent::ExtendOrder(e) => {
+                let book = self.book(if e.order_type == 1 { Direction::Dn } else { Direction::Up });
+                if let Some(order) = book.slots.get_mut(e.order_index as usize) {
+                    if order.order_id == e.order_id {
+                        order.end_time = e.end_time;
+                    }
+                }
+            }
+            PinpetEvent::TokenCreated(_) | PinpetEvent::MilestoneDiscount(_) => {}
+        }
+    }
+}
+
+fn order_from_event(e: &LongShortEvent) -> MarginOrder {
+    MarginOrder {
+        user: e.payer.clone(),
+        lock_lp_start_price: e.lock_lp_start_price,
+        lock_lp_end_price: e.lock_lp_end_price,
+        open_price: e.open_price,
+        take_profit_price: e.take_profit_price,
+        order_id: e.order_id,
+        lock_lp_sol_amount: e.lock_lp_sol_amount,
+        lock_lp_token_amount: e.lock_lp_token_amount,
+        next_lp_sol_amount: 0,
+        next_lp_token_amount: 0,
+        margin_init_sol_amount: e.margin_sol_amount,
+        margin_sol_amount: e.margin_sol_amount,
+        borrow_amount: e.borrow_amount,
+        position_asset_amount: e.position_asset_amount,
+        realized_sol_amount: 0,
+        version: 0,
+        start_time: e.start_time,
+        end_time: e.end_time,
+        next_order: u16::MAX,
+        prev_order: u16::MAX,
+        borrow_fee: e.borrow_fee,
+        order_type: e.order_type,
+    }
+}
+
+// ==================== 回放与比对 / Replay and comparison ====================
+
+/// 用临时数据库搭建与线上相同的 StorageEventHandler, `journaled` 时两个存储都记录撤销日志
+/// Build a StorageEventHandler wired like production, on temp databases; with `journaled` both stores keep an undo journal
+fn create_handler(journaled: bool) -> (StorageEventHandler, Arc<OrderBookStorage>, Arc<EventStorage>, Vec<String>) {
+    let event_path = std::env::temp_dir().join(format!("replay_harness_event_{}", Uuid::new_v4()));
+    let orderbook_path = std::env::temp_dir().join(format!("replay_harness_orderbook_{}", Uuid::new_v4()));
+
+    let mut opts = Options::default();
+    opts.create_if_missing(true);
+    crate::db::storage::register_counter_merge(&mut opts);
+    let db = Arc::new(DB::open(&opts, &event_path).expect("Failed to open test DB"));
+
+    let mut event_storage = EventStorage::new(db.clone(), Arc::new(ShardedLocks::default())).unwrap();
+    let mut orderbook_storage = OrderBookStorage::new(&Default::default(), &orderbook_path.to_string_lossy()).unwrap();
+    if journaled {
+        event_storage = event_storage.with_undo_journal();
+        orderbook_storage = orderbook_storage.with_undo_journal();
+    }
+    let event_storage = Arc::new(event_storage);
+    let token_storage = Arc::new(TokenStorage::new(db, test_config(), Arc::new(ShardedLocks::default())).unwrap());
+    let orderbook_storage = Arc::new(orderbook_storage);
+    let handler = StorageEventHandler::new(event_storage.clone(), token_storage, orderbook_storage.clone());
+
+    let paths = vec![
+        event_path.to_string_lossy().to_string(),
+        orderbook_path.to_string_lossy().to_string(),
+    ];
+    (handler, orderbook_storage, event_storage, paths)
+}
+
+/// 比较一个方向的数据库订单簿与参考模型 / Compare one direction of the database book with the reference model
+fn assert_book_matches(storage: &OrderBookStorage, direction: Direction, expected: &ReferenceBook, step: usize) {
+    let manager = storage.get_or_create_manager(MINT.to_string(), direction).unwrap();
+    let header = manager.load_header().unwrap();
+    let ctx = format!("direction={}, after event #{}", direction, step);
+
+    assert_eq!(header.total as usize, expected.slots.len(), "total mismatch ({})", ctx);
+    assert!(header.consistency_problems().is_empty(), "header problems {:?} ({})", header.consistency_problems(), ctx);
+
+    // 槽位内容 / Slot contents
+    for (index, want) in expected.slots.iter().enumerate() {
+        let got = manager.get_order(index as u16).unwrap();
+        assert_eq!(got.order_id, want.order_id, "order_id at slot {} ({})", index, ctx);
+        assert_eq!(got.user, want.user, "user at slot {} ({})", index, ctx);
+        assert_eq!(
+            (got.lock_lp_start_price, got.lock_lp_end_price, got.lock_lp_sol_amount, got.lock_lp_token_amount),
+            (want.lock_lp_start_price, want.lock_lp_end_price, want.lock_lp_sol_amount, want.lock_lp_token_amount),
+            "locked range at slot {} ({})",
+            index,
+            ctx
+        );
+        assert_eq!(
+            (got.next_lp_sol_amount, got.next_lp_token_amount),
+            (want.next_lp_sol_amount, want.next_lp_token_amount),
+            "next_lp at slot {} ({})",
+            index,
+            ctx
+        );
+        assert_eq!(
+            (got.margin_sol_amount, got.borrow_amount, got.position_asset_amount, got.realized_sol_amount, got.end_time),
+            (want.margin_sol_amount, want.borrow_amount, want.position_asset_amount, want.realized_sol_amount, want.end_time),
+            "position at slot {} ({})",
+            index,
+            ctx
+        );
+        assert_eq!(manager.get_order_by_id(want.order_id).unwrap().order_id, want.order_id, "id map ({})", ctx);
+    }
+    assert!(manager.get_order(expected.slots.len() as u16).is_err(), "stale slot past total ({})", ctx);
+
+    // 链表顺序与前后指针 / List order and prev/next pointers
+    let mut walked = Vec::new();
+    let mut prev = u16::MAX;
+    manager
+        .traverse(u16::MAX, 0, |index, order| {
+            assert_eq!(order.prev_order, prev, "prev pointer of slot {} ({})", index, ctx);
+            prev = index;
+            walked.push(order.order_id);
+            Ok(true)
+        })
+        .unwrap();
+    assert_eq!(walked, expected.sequence, "list order ({})", ctx);
+
+    let (want_head, want_tail) = match (expected.sequence.first(), expected.sequence.last()) {
+        (Some(&head), Some(&tail)) => (expected.slot_of(head), expected.slot_of(tail)),
+        _ => (u16::MAX, u16::MAX),
+    };
+    assert_eq!((header.head, header.tail), (want_head, want_tail), "head/tail ({})", ctx);
+
+    // 活跃索引 / Active indices
+    let mut active = manager.load_active_indices().unwrap();
+    active.sort_unstable();
+    assert_eq!(active, (0..expected.slots.len() as u16).collect::<Vec<_>>(), "active indices ({})", ctx);
+}
+
+/// 通过服务端事件路径回放事件, 每个事件后都与参考模型比对
+/// Replay events through the server's event path, comparing with the reference model after every event
+async fn verify_replay(events: Vec<PinpetEvent>) {
+    let (handler, storage, _, paths) = create_handler(false);
+    let mut model = ReferenceModel::default();
+
+    for (step, event) in events.into_iter().enumerate() {
+        model.apply(&event);
+        handler.handle_event(event).await.unwrap();
+        for direction in [Direction::Dn, Direction::Up] {
+            assert_book_matches(&storage, direction, model.book(direction), step);
+        }
+    }
+
+    drop(handler);
+    drop(storage);
+    for path in paths {
+        cleanup_test_db(&path);
+    }
+}
+
+// ==================== 事件构造 / Event builders ====================
+
+/// 第 rank 档的锁定区间: 做多向下排列, 做空向上排列, 相邻档之间留有间隔
+/// Locked range of band `rank`: long bands go down, short bands go up, with a gap between neighbours
+fn band(direction: Direction, rank: u128) -> (u128, u128) {
+    let p = CurveAMM::get_initial_price().unwrap();
+    match direction {
+        Direction::Dn => (p * (1000 - 40 * rank) / 1000, p * (1000 - 40 * rank - 20) / 1000),
+        Direction::Up => (p * (1000 + 40 * rank) / 1000, p * (1000 + 40 * rank + 20) / 1000),
+    }
+}
+
+fn open(order_id: u64, direction: Direction, rank: u128, order_index: u16, liquidate_indices: Vec<u16>) -> PinpetEvent {
+    let (start, end) = band(direction, rank);
+    PinpetEvent::LongShort(LongShortEvent {
+        payer: format!("User{}", order_id),
+        mint_account: MINT.to_string(),
+        order_id,
+        order_index,
+        latest_price: start,
+        open_price: start,
+        order_type: if direction == Direction::Dn { 1 } else { 2 },
+        lock_lp_start_price: start,
+        lock_lp_end_price: end,
+        lock_lp_sol_amount: 1_000_000_000,
+        lock_lp_token_amount: 5_000_000_000,
+        start_time: order_id as u32,
+        end_time: order_id as u32 + 86400,
+        margin_sol_amount: 100_000_000,
+        borrow_amount: 900_000_000,
+        position_asset_amount: 5_000_000_000,
+        borrow_fee: 1000,
+        liquidate_indices,
+        take_profit_price: 0,
+        timestamp: Utc.timestamp_opt(order_id as i64, 0).unwrap(),
+        signature: format!("sig_open_{}", order_id),
+        slot: order_id,
+        event_index: 0,
+    })
+}
+
+fn partial_close(
+    order_id: u64,
+    direction: Direction,
+    rank: u128,
+    order_index: u16,
+    remaining: u64,
+    liquidate_indices: Vec<u16>,
+) -> PinpetEvent {
+    let (start, end) = band(direction, rank);
+    PinpetEvent::PartialClose(PartialCloseEvent {
+        payer: format!("User{}", order_id),
+        user_sol_account: format!("User{}", order_id),
+        mint_account: MINT.to_string(),
+        is_close_long: direction == Direction::Dn,
+        final_token_amount: 5_000_000_000 - remaining,
+        final_sol_amount: 500_000_000,
+        user_close_profit: 10_000_000,
+        latest_price: start,
+        order_id,
+        order_index,
+        order_type: if direction == Direction::Dn { 1 } else { 2 },
+        user: format!("User{}", order_id),
+        lock_lp_start_price: start,
+        lock_lp_end_price: end,
+        lock_lp_sol_amount: 500_000_000,
+        lock_lp_token_amount: remaining,
+        start_time: order_id as u32,
+        end_time: order_id as u32 + 43200,
+        margin_sol_amount: 60_000_000,
+        borrow_amount: 450_000_000,
+        position_asset_amount: remaining,
+        borrow_fee: 1000,
+        realized_sol_amount: 10_000_000,
+        liquidate_indices,
+        timestamp: Utc.timestamp_opt(1000 + order_id as i64, 0).unwrap(),
+        signature: format!("sig_pc_{}", order_id),
+        slot: 1000 + order_id,
+        event_index: 0,
+    })
+}
+
+/// 追加保证金把第 rank 档的区间移离当前价格半个间隔 / Adding margin moves band `rank` half a gap away from the price
+fn add_margin(order_id: u64, direction: Direction, rank: u128, order_index: u16, add_sol: u64) -> PinpetEvent {
+    let p = CurveAMM::get_initial_price().unwrap();
+    let (start, end) = match direction {
+        Direction::Dn => (p * (1000 - 40 * rank - 10) / 1000, p * (1000 - 40 * rank - 30) / 1000),
+        Direction::Up => (p * (1000 + 40 * rank + 10) / 1000, p * (1000 + 40 * rank + 30) / 1000),
+    };
+    PinpetEvent::AddMargin(AddMarginEvent {
+        payer: format!("User{}", order_id),
+        mint_account: MINT.to_string(),
+        order_id,
+        order_index,
+        order_type: if direction == Direction::Dn { 1 } else { 2 },
+        add_sol_amount: add_sol,
+        margin_sol_amount: 100_000_000 + add_sol,
+        lock_lp_start_price: start,
+        lock_lp_end_price: end,
+        lock_lp_sol_amount: 1_100_000_000,
+        lock_lp_token_amount: 5_000_000_000,
+        latest_price: p,
+        timestamp: Utc.timestamp_opt(500 + order_id as i64, 0).unwrap(),
+        signature: format!("sig_am_{}", order_id),
+        slot: 500 + order_id,
+        event_index: 0,
+    })
+}
+
+fn extend(order_id: u64, direction: Direction, order_index: u16, extend_seconds: u32) -> PinpetEvent {
+    let old_end_time = order_id as u32 + 86400;
+    PinpetEvent::ExtendOrder(ExtendOrderEvent {
+        payer: format!("User{}", order_id),
+        mint_account: MINT.to_string(),
+        order_id,
+        order_index,
+        order_type: if direction == Direction::Dn { 1 } else { 2 },
+        extend_seconds,
+        old_end_time,
+        end_time: old_end_time + extend_seconds,
+        fee_sol_amount: 1_000_000,
+        latest_price: CurveAMM::get_initial_price().unwrap(),
+        timestamp: Utc.timestamp_opt(600 + order_id as i64, 0).unwrap(),
+        signature: format!("sig_eo_{}", order_id),
+        slot: 600 + order_id,
+        event_index: 0,
+    })
+}
+
+fn full_close(order_id: u64, direction: Direction, order_index: u16, liquidate_indices: Vec<u16>) -> PinpetEvent {
+    PinpetEvent::FullClose(FullCloseEvent {
+        payer: format!("User{}", order_id),
+        user_sol_account: format!("User{}", order_id),
+        mint_account: MINT.to_string(),
+        is_close_long: direction == Direction::Dn,
+        final_token_amount: 5_000_000_000,
+        final_sol_amount: 1_000_000_000,
+        user_close_profit: 0,
+        latest_price: CurveAMM::get_initial_price().unwrap(),
+        order_id,
+        order_index,
+        liquidate_indices,
+        keeper_reward: 0,
+        timestamp: Utc.timestamp_opt(2000 + order_id as i64, 0).unwrap(),
+        signature: format!("sig_fc_{}", order_id),
+        slot: 2000 + order_id,
+        event_index: 0,
+    })
+}
+
+// ==================== 回归场景 / Regression scenarios ====================
+
+/// 删除末尾槽位和删除中间槽位 (末尾节点搬移) 交替进行
+/// Alternate deleting the last slot and deleting a middle slot (tail node moves in)
+#[tokio::test]
+async fn test_replay_delete_and_move_tail() {
+    let dn = Direction::Dn;
+    verify_replay(vec![
+        open(1, dn, 0, 0, vec![]),
+        open(2, dn, 1, 1, vec![]),
+        open(3, dn, 2, 2, vec![]),
+        open(4, dn, 3, 3, vec![]),
+        open(5, dn, 4, 4, vec![]),
+        // 删除最后一个槽位, 也是链表尾部 / Delete the last slot, which is also the list tail
+        full_close(5, dn, 4, vec![4]),
+        // 删除头部, 尾部槽位 3 搬到槽位 0 / Delete the head, tail slot 3 moves into slot 0
+        full_close(1, dn, 0, vec![0]),
+        // 搬移后的节点被部分平仓 / Partial close of the moved node
+        partial_close(4, dn, 3, 0, 2_000_000_000, vec![]),
+        // 一次删除多个槽位, 包含搬移目标 / Delete several slots at once, including a move target
+        full_close(2, dn, 1, vec![1, 0]),
+        full_close(3, dn, 0, vec![0]),
+    ])
+    .await;
+}
+
+/// 头部、中间、尾部插入与清算交错, 尾指针必须跟随搬移
+/// Interleave head, middle and tail inserts with liquidations; the tail pointer must follow moves
+#[tokio::test]
+async fn test_replay_inserts_and_liquidations_track_tail() {
+    let dn = Direction::Dn;
+    verify_replay(vec![
+        open(10, dn, 4, 0, vec![]),
+        // 插入到头部之前 / Insert before head
+        open(11, dn, 1, 0, vec![]),
+        // order_index 超过总数时追加到尾部 / order_index past total appends at tail
+        open(12, dn, 8, 9, vec![]),
+        // 插入到槽位 0 之后 (order 10, 链表中间) / Insert after slot 0 (order 10, mid-list)
+        open(13, dn, 6, 1, vec![]),
+        // 插入到槽位 1 之后 (order 11, 头部之后) / Insert after slot 1 (order 11, right after head)
+        open(14, dn, 2, 2, vec![]),
+        // 平仓尾部订单, 同时清算头部 / Close the tail order and liquidate the head at the same time
+        full_close(12, dn, 2, vec![2, 1]),
+        // 部分平仓被搬移过的节点并清算它的前节点, 它会再次被搬移 / Partial close a moved node and liquidate its predecessor, moving it again
+        partial_close(13, dn, 6, 1, 1_000_000_000, vec![0]),
+        open(15, dn, 9, 2, vec![]),
+    ])
+    .await;
+}
+
+/// 开空单清算做多订单簿, 两个方向各自保持一致
+/// Opening shorts liquidates the long book; both directions stay consistent
+#[tokio::test]
+async fn test_replay_cross_direction_liquidations() {
+    let (dn, up) = (Direction::Dn, Direction::Up);
+    verify_replay(vec![
+        open(20, dn, 0, 0, vec![]),
+        open(21, dn, 1, 1, vec![]),
+        open(22, dn, 2, 2, vec![]),
+        open(23, up, 0, 0, vec![2]),
+        open(24, up, 1, 1, vec![0]),
+        // 开多清算做空订单簿的头部 / Opening a long liquidates the head of the short book
+        open(25, dn, 3, 1, vec![0]),
+        full_close(24, up, 0, vec![0]),
+        full_close(21, dn, 0, vec![1, 0]),
+    ])
+    .await;
+}
+
+/// 追加保证金后被搬移、部分平仓和清算, 两个方向各自保持一致
+/// Margin top-ups followed by moves, partial closes and liquidations; both directions stay consistent
+#[tokio::test]
+async fn test_replay_add_margin_then_move_and_close() {
+    let (dn, up) = (Direction::Dn, Direction::Up);
+    verify_replay(vec![
+        open(30, dn, 0, 0, vec![]),
+        open(31, dn, 1, 1, vec![]),
+        open(32, dn, 2, 2, vec![]),
+        open(33, up, 0, 0, vec![]),
+        add_margin(31, dn, 1, 1, 40_000_000),
+        add_margin(33, up, 0, 0, 25_000_000),
+        // 删除头部, 追加过保证金的节点之外的尾部槽位被搬移 / Delete the head, moving the tail slot
+        full_close(30, dn, 0, vec![0]),
+        add_margin(32, dn, 2, 0, 10_000_000),
+        partial_close(31, dn, 1, 1, 2_000_000_000, vec![]),
+        // order_id 不符的追加保证金不改变订单簿 / A top-up with a mismatched order_id leaves the book unchanged
+        add_margin(99, dn, 1, 1, 10_000_000),
+    ])
+    .await;
+}
+
+/// 延期只改变到期时间, 之后的搬移和部分平仓照常
+/// Extensions only change the expiry; later moves and partial closes proceed as usual
+#[tokio::test]
+async fn test_replay_extend_then_move_and_close() {
+    let (dn, up) = (Direction::Dn, Direction::Up);
+    verify_replay(vec![
+        open(40, dn, 0, 0, vec![]),
+        open(41, dn, 1, 1, vec![]),
+        open(42, up, 0, 0, vec![]),
+        extend(41, dn, 1, 3600),
+        extend(42, up, 0, 7200),
+        // 删除头部, 延期过的订单搬移到槽位 0 / Delete the head, the extended order moves into slot 0
+        full_close(40, dn, 0, vec![0]),
+        extend(41, dn, 0, 7200),
+        // order_id 不符的延期不改变订单簿 / An extension with a mismatched order_id leaves the book unchanged
+        extend(99, up, 0, 600),
+    ])
+    .await;
+}
+
+// ==================== 多指令交易 / Multi-instruction transactions ====================
+
+/// 与 test_config() 一致的程序ID / Program id matching test_config()
+const PROGRAM_ID: &str = "HNaandW3U5sVTsoJaGx61UmX9Siupa6difFY9qRAPXyw";
+
+/// 一条 BuySell 事件的 Program data 日志 / Program data log of one BuySell event
+fn buy_log(payer: &Pubkey, mint: &Pubkey, token_amount: u64) -> String {
+    let mut data = BUY_SELL_EVENT_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(payer.as_ref());
+    data.extend_from_slice(mint.as_ref());
+    data.push(1); // is_buy
+    data.extend_from_slice(&token_amount.to_le_bytes());
+    data.extend_from_slice(&1_000_000u64.to_le_bytes());
+    data.extend_from_slice(&CurveAMM::get_initial_price().unwrap().to_le_bytes());
+    data.extend_from_slice(&0u32.to_le_bytes()); // 空的 liquidate_indices / empty liquidate_indices
+    format!("Program data: {}", base64::engine::general_purpose::STANDARD.encode(&data))
+}
+
+/// 一条 AddMargin 事件的 Program data 日志 / Program data log of one AddMargin event
+fn add_margin_log(payer: &Pubkey, mint: &Pubkey, order_id: u64, add_sol: u64) -> String {
+    let p = CurveAMM::get_initial_price().unwrap();
+    let mut data = ADD_MARGIN_EVENT_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(payer.as_ref());
+    data.extend_from_slice(mint.as_ref());
+    data.extend_from_slice(&order_id.to_le_bytes());
+    data.extend_from_slice(&3u16.to_le_bytes()); // order_index
+    data.push(1); // order_type
+    data.extend_from_slice(&add_sol.to_le_bytes());
+    data.extend_from_slice(&(100_000_000 + add_sol).to_le_bytes());
+    data.extend_from_slice(&(p * 95 / 100).to_le_bytes());
+    data.extend_from_slice(&(p * 85 / 100).to_le_bytes());
+    data.extend_from_slice(&1_100_000_000u64.to_le_bytes());
+    data.extend_from_slice(&5_000_000_000u64.to_le_bytes());
+    data.extend_from_slice(&p.to_le_bytes());
+    format!("Program data: {}", base64::engine::general_purpose::STANDARD.encode(&data))
+}
+
+/// AddMargin 事件按链上布局解码 / AddMargin events decode with the on-chain layout
+#[test]
+fn test_add_margin_event_decodes() {
+    let payer = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let logs = vec![
+        format!("Program {} invoke [1]", PROGRAM_ID),
+        "Program log: Instruction: AddMargin".to_string(),
+        add_margin_log(&payer, &mint, 17, 40_000_000),
+        format!("Program {} success", PROGRAM_ID),
+    ];
+
+    let parser = EventParser::new(PROGRAM_ID).unwrap();
+    let events = parser.parse_events_with_call_stack(&logs, "AddMarginSig", 42).unwrap();
+    let [PinpetEvent::AddMargin(event)] = events.as_slice() else {
+        panic!("expected one AddMargin event: {:?}", events);
+    };
+    let p = CurveAMM::get_initial_price().unwrap();
+    assert_eq!(event.payer, payer.to_string());
+    assert_eq!(event.mint_account, mint.to_string());
+    assert_eq!((event.order_id, event.order_index, event.order_type), (17, 3, 1));
+    assert_eq!((event.add_sol_amount, event.margin_sol_amount), (40_000_000, 140_000_000));
+    assert_eq!((event.lock_lp_start_price, event.lock_lp_end_price), (p * 95 / 100, p * 85 / 100));
+    assert_eq!((event.lock_lp_sol_amount, event.lock_lp_token_amount), (1_100_000_000, 5_000_000_000));
+    assert_eq!(event.latest_price, p);
+    assert_eq!(parser.diagnostics().report(10).total, 0);
+}
+
+/// ExtendOrder 事件按链上布局解码 / ExtendOrder events decode with the on-chain layout
+#[test]
+fn test_extend_order_event_decodes() {
+    let payer = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let p = CurveAMM::get_initial_price().unwrap();
+    let mut data = EXTEND_ORDER_EVENT_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(payer.as_ref());
+    data.extend_from_slice(mint.as_ref());
+    data.extend_from_slice(&17u64.to_le_bytes());
+    data.extend_from_slice(&3u16.to_le_bytes()); // order_index
+    data.push(2); // order_type
+    data.extend_from_slice(&3600u32.to_le_bytes());
+    data.extend_from_slice(&86_400u32.to_le_bytes());
+    data.extend_from_slice(&90_000u32.to_le_bytes());
+    data.extend_from_slice(&1_500_000u64.to_le_bytes());
+    data.extend_from_slice(&p.to_le_bytes());
+    let logs = vec![
+        format!("Program {} invoke [1]", PROGRAM_ID),
+        "Program log: Instruction: ExtendOrder".to_string(),
+        format!("Program data: {}", base64::engine::general_purpose::STANDARD.encode(&data)),
+        format!("Program {} success", PROGRAM_ID),
+    ];
+
+    let parser = EventParser::new(PROGRAM_ID).unwrap();
+    let events = parser.parse_events_with_call_stack(&logs, "ExtendOrderSig", 42).unwrap();
+    let [PinpetEvent::ExtendOrder(event)] = events.as_slice() else {
+        panic!("expected one ExtendOrder event: {:?}", events);
+    };
+    assert_eq!(event.payer, payer.to_string());
+    assert_eq!((event.order_id, event.order_index, event.order_type), (17, 3, 2));
+    assert_eq!((event.extend_seconds, event.old_end_time, event.end_time), (3600, 86_400, 90_000));
+    assert_eq!(event.fee_sol_amount, 1_500_000);
+    assert_eq!(event.latest_price, p);
+    assert_eq!(parser.diagnostics().report(10).total, 0);
+}
+
+/// 一笔交易里的两条买入指令: 两个事件都被存储, 重放整笔交易不会重复计数
+/// Two buy instructions in one transaction: both events are stored, and replaying the transaction double-counts nothing
+#[tokio::test]
+async fn test_two_trade_transaction_stores_both_events_once() {
+    let (handler, orderbook_storage, event_storage, paths) = create_handler(false);
+    let payer = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let signature = "TwoTradeSig1111111111111111111111111111111111";
+
+    let logs = vec![
+        format!("Program {} invoke [1]", PROGRAM_ID),
+        "Program log: Instruction: Buy".to_string(),
+        buy_log(&payer, &mint, 1_000_000),
+        format!("Program {} success", PROGRAM_ID),
+        format!("Program {} invoke [1]", PROGRAM_ID),
+        "Program log: Instruction: Buy".to_string(),
+        buy_log(&payer, &mint, 2_000_000),
+        format!("Program {} success", PROGRAM_ID),
+    ];
+
+    let parser = EventParser::new(PROGRAM_ID).unwrap();
+    let events = parser.parse_events_with_call_stack(&logs, signature, 42).unwrap();
+    assert_eq!(events.iter().map(|e| e.event_index()).collect::<Vec<_>>(), vec![0, 1]);
+
+    // 第二轮模拟重启后重放同一笔交易 / The second round simulates replaying the transaction after a restart
+    for _ in 0..2 {
+        for event in events.clone() {
+            handler.handle_event(event).await.unwrap();
+        }
+
+        let stored = event_storage.query_by_signature(signature).await.unwrap();
+        let mut amounts: Vec<u64> = stored
+            .iter()
+            .map(|e| match e {
+                PinpetEvent::BuySell(e) => e.token_amount,
+                other => panic!("unexpected event: {:?}", other),
+            })
+            .collect();
+        amounts.sort();
+        assert_eq!(amounts, vec![1_000_000, 2_000_000]);
+        assert_eq!(event_storage.count_mint_trades(&mint.to_string()).unwrap(), 2);
+    }
+
+    drop(handler);
+    drop(orderbook_storage);
+    drop(event_storage);
+    for path in paths {
+        cleanup_test_db(&path);
+    }
+}
+
+// ==================== 分叉回滚 / Fork rollback ====================
+
+/// 被分叉丢弃的 slot 中已应用的开仓和清算被回滚, 规范分叉上的 slot 保持不变
+/// Opens and liquidations already applied from an orphaned slot are rolled back, slots on the canonical fork are kept
+#[tokio::test]
+async fn test_orphaned_slot_reverts_applied_inserts() {
+    let (handler, storage, event_storage, paths) = create_handler(true);
+    let gate = FinalityGate::optimistic(
+        Arc::new(handler),
+        SlotRollback::new(storage.clone(), event_storage.clone()),
+    )
+    .unwrap();
+    let (dn, up) = (Direction::Dn, Direction::Up);
+    let mut model = ReferenceModel::default();
+
+    // slot 30, 31 在规范分叉上 / Slots 30 and 31 are on the canonical fork
+    for event in [open(30, dn, 0, 0, vec![]), open(31, dn, 2, 1, vec![])] {
+        model.apply(&event);
+        gate.handle_event(event).await.unwrap();
+    }
+
+    // slot 32 在中间插入做多单, slot 33 开第一张空单并清算 order 30; 两个 slot 随后被丢弃
+    // Slot 32 inserts a long mid-list, slot 33 opens the first short and liquidates order 30; both slots are orphaned later
+    for event in [open(32, dn, 1, 1, vec![]), open(33, up, 0, 0, vec![0])] {
+        gate.handle_event(event).await.unwrap();
+    }
+    let manager = storage.get_or_create_manager(MINT.to_string(), dn).unwrap();
+    assert_eq!(manager.load_header().unwrap().total, 2);
+    assert_eq!(gate.unsettled_len(), 4);
+
+    gate.settle_range(30, 33, &HashSet::from([30, 31])).await.unwrap();
+    assert_eq!(gate.reverted_slots(), 2);
+    assert_eq!(gate.revert_conflicts(), 0);
+    assert!(storage.rebuild_required().unwrap().is_empty());
+    assert_eq!(gate.unsettled_len(), 0);
+    for direction in [dn, up] {
+        assert_book_matches(&storage, direction, model.book(direction), 0);
+    }
+    assert!(manager.check_consistency().unwrap().consistent);
+
+    // 事件、订单索引和游标回到 slot 31 / Events, order index and cursor are back at slot 31
+    assert_eq!(event_storage.query_by_signature("sig_open_31").await.unwrap().len(), 1);
+    assert!(event_storage.query_by_signature("sig_open_32").await.unwrap().is_empty());
+    assert!(event_storage.query_by_signature("sig_open_33").await.unwrap().is_empty());
+    assert!(event_storage.query_by_order(MINT, "dn", 32).await.unwrap().is_empty());
+    assert!(event_storage.query_liquidations(MINT, 10).unwrap().is_empty());
+    assert_eq!(event_storage.event_cursor().unwrap().unwrap().last_processed_slot, 31);
+
+    // 规范分叉继续推进, 被丢弃的交易重新打包后可以再次应用
+    // The canonical fork moves on, and an orphaned transaction re-included later applies again
+    let mut relanded = open(32, dn, 1, 1, vec![]);
+    if let PinpetEvent::LongShort(ref mut e) = relanded {
+        e.slot = 34;
+    }
+    model.apply(&relanded);
+    gate.handle_event(relanded).await.unwrap();
+    for direction in [dn, up] {
+        assert_book_matches(&storage, direction, model.book(direction), 1);
+    }
+    assert_eq!(event_storage.query_by_signature("sig_open_32").await.unwrap().len(), 1);
+
+    drop(gate);
+    drop(manager);
+    drop(storage);
+    drop(event_storage);
+    for path in paths {
+        cleanup_test_db(&path);
+    }
+}
+
+/// 被丢弃的 slot 之后同一订单簿又被修改时, 订单簿整体保持不变并标记重建; 待终结的 slot 在重启后从撤销日志恢复
+/// When a later slot changed the same book after an orphaned slot, the book is left whole and marked for a rebuild;
+/// unsettled slots are restored from the undo journals after a restart
+#[tokio::test]
+async fn test_conflicting_rollback_marks_book_for_rebuild() {
+    let (handler, storage, event_storage, paths) = create_handler(true);
+    let handler = Arc::new(handler);
+    let dn = Direction::Dn;
+
+    let gate = FinalityGate::optimistic(
+        handler.clone(),
+        SlotRollback::new(storage.clone(), event_storage.clone()),
+    )
+    .unwrap();
+    for event in [open(40, dn, 0, 0, vec![]), open(41, dn, 1, 1, vec![])] {
+        gate.handle_event(event).await.unwrap();
+    }
+
+    // 模拟重启: 新闸门从撤销日志恢复两个待终结 slot / Simulate a restart: a fresh gate restores both unsettled slots from the undo journals
+    drop(gate);
+    let gate = FinalityGate::optimistic(handler, SlotRollback::new(storage.clone(), event_storage.clone())).unwrap();
+    assert_eq!(gate.unsettled_len(), 2);
+
+    // 只有 slot 40 被丢弃, 但 slot 41 已修改同一订单簿 / Only slot 40 is orphaned, but slot 41 already changed the same book
+    gate.settle_range(40, 41, &HashSet::from([41])).await.unwrap();
+    assert_eq!(gate.reverted_slots(), 1);
+    assert!(gate.revert_conflicts() >= 1);
+    assert_eq!(gate.unsettled_len(), 0);
+
+    // 订单簿没有退回一半, 而是原样保留并标记 / The book is not half reverted but kept as is and marked
+    let manager = storage.get_or_create_manager(MINT.to_string(), dn).unwrap();
+    assert_eq!(manager.load_header().unwrap().total, 2);
+    let markers = storage.rebuild_required().unwrap();
+    assert_eq!(markers.len(), 1);
+    assert_eq!((markers[0].mint.as_str(), markers[0].direction, markers[0].slot), (MINT, dn, 40));
+    assert!(!manager.check_consistency().unwrap().consistent);
+
+    drop(gate);
+    drop(manager);
+    drop(storage);
+    drop(event_storage);
+    for path in paths {
+        cleanup_test_db(&path);
+    }
+}
+
+/// 全平和被清算的订单计入用户已实现盈亏, 部分平仓的取回在全平时一并计入, 重放不重复计入
+/// Fully closed and liquidated orders count toward the user's realized PnL, partial close payouts are counted at the
+/// full close, and replays are not counted twice
+#[tokio::test]
+async fn test_settled_orders_update_user_pnl() {
+    let (handler, storage, event_storage, paths) = create_handler(false);
+    let (dn, up) = (Direction::Dn, Direction::Up);
+
+    let mut close = full_close(1, dn, 0, vec![0]);
+    if let PinpetEvent::FullClose(ref mut e) = close {
+        e.user_close_profit = 150_000_000;
+    }
+    let events = vec![
+        open(1, dn, 0, 0, vec![]),
+        open(2, dn, 1, 1, vec![]),
+        partial_close(1, dn, 0, 0, 2_500_000_000, vec![]),
+        close.clone(),
+        // 做空开仓清算做多簿中移到 index 0 的 order 2 / The short open liquidates order 2, moved to index 0 of the long book
+        open(3, up, 0, 0, vec![0]),
+        close,
+    ];
+    for event in events {
+        handler.handle_event(event).await.unwrap();
+    }
+
+    // 取回 = 部分平仓 10_000_000 + 全平 150_000_000, 保证金 100_000_000
+    // Payout = 10_000_000 from the partial close + 150_000_000 at the full close, margin 100_000_000
+    let winner = event_storage.user_realized_pnl("User1", None).unwrap();
+    assert_eq!(winner.realized_pnl, 60_000_000);
+    assert_eq!(winner.total_payout, 160_000_000);
+    assert_eq!((winner.settled_orders, winner.wins, winner.losses, winner.liquidations), (1, 1, 0, 0));
+    assert_eq!(event_storage.user_realized_pnl("User1", Some(MINT)).unwrap().realized_pnl, 60_000_000);
+
+    let liquidated = event_storage.user_realized_pnl("User2", None).unwrap();
+    assert_eq!(liquidated.realized_pnl, -100_000_000);
+    assert_eq!((liquidated.settled_orders, liquidated.wins, liquidated.losses, liquidated.liquidations), (1, 0, 1, 1));
+
+    // 仍持仓的用户和其他 mint 为全零汇总 / A user still holding and another mint get an all-zero summary
+    let open_only = event_storage.user_realized_pnl("User3", None).unwrap();
+    assert_eq!((open_only.settled_orders, open_only.realized_pnl), (0, 0));
+    let other_mint = event_storage
+        .user_realized_pnl("User1", Some("So11111111111111111111111111111111111111112"))
+        .unwrap();
+    assert_eq!(other_mint.settled_orders, 0);
+    assert_eq!(other_mint.mint.as_deref(), Some("So11111111111111111111111111111111111111112"));
+
+    drop(handler);
+    drop(storage);
+    drop(event_storage);
+    for path in paths {
+        cleanup_test_db(&path);
+    }
+}
@@ -0,0 +1,85 @@
+// 即将到期订单查询测试
+// Expiring Orders Query Tests
+
+use super::*;
+
+const NOW: i64 = 1_735_700_000;
+
+/// 辅助函数: 按给定 end_time 依次插入订单
+fn insert_with_end_times(manager: &OrderBookDBManager, end_times: &[u32]) {
+    for (i, end_time) in end_times.iter().enumerate() {
+        let mut order = create_test_order(&format!("User{}", i), (i as u128 + 1) * 1000000);
+        order.end_time = *end_time;
+        order.order_id = i as u64 + 1;
+        let after = if i == 0 { u16::MAX } else { (i - 1) as u16 };
+        manager.insert_after(after, &order).unwrap();
+    }
+}
+
+#[test]
+fn test_find_expiring_orders_sorted_by_end_time() {
+    let (manager, temp_path) = create_test_manager();
+    manager.initialize("authority".to_string()).unwrap();
+
+    let now = NOW as u32;
+    // 价格顺序与到期顺序不一致 / Price order differs from expiry order
+    insert_with_end_times(&manager, &[now + 7200, now + 600, now - 100, now + 60, now + 86400]);
+
+    let expiring = manager.find_expiring_orders(NOW, 3600).unwrap();
+    let users: Vec<&str> = expiring.iter().map(|(_, o)| o.user.as_str()).collect();
+    assert_eq!(users, vec!["User2", "User3", "User1"]);
+
+    // 索引与链表位置一致 / Indices match the list positions
+    assert_eq!(expiring[0].0, 2);
+    assert_eq!(expiring[1].0, 3);
+
+    cleanup_test_db(&temp_path);
+}
+
+#[test]
+fn test_find_expiring_orders_empty_book() {
+    let (manager, temp_path) = create_test_manager();
+    manager.initialize("authority".to_string()).unwrap();
+
+    assert!(manager.find_expiring_orders(NOW, u64::MAX).unwrap().is_empty());
+
+    cleanup_test_db(&temp_path);
+}
+
+#[test]
+fn test_nearest_expiry() {
+    let (manager, temp_path) = create_test_manager();
+    manager.initialize("authority".to_string()).unwrap();
+    assert_eq!(manager.nearest_expiry().unwrap(), None);
+
+    let now = NOW as u32;
+    insert_with_end_times(&manager, &[now + 7200, now + 600, now + 86400]);
+    assert_eq!(manager.nearest_expiry().unwrap(), Some(now + 600));
+
+    cleanup_test_db(&temp_path);
+}
+
+#[test]
+fn test_find_expired_orders_with_close_indices() {
+    let (manager, temp_path) = create_test_manager();
+    manager.initialize("authority".to_string()).unwrap();
+
+    let now = NOW as u32;
+    insert_with_end_times(&manager, &[now + 600, now - 10, now, now - 3600]);
+
+    // 到期最久的在前, end_time == now 视为已到期 / Longest expired first, end_time == now counts as expired
+    let expired = manager.find_expired_orders(NOW).unwrap();
+    let users: Vec<&str> = expired.iter().map(|(_, o)| o.user.as_str()).collect();
+    assert_eq!(users, vec!["User3", "User1", "User2"]);
+
+    // 与逐个查询的平仓索引一致 / Matches the per-order close hint
+    for (index, order) in &expired {
+        assert_eq!(
+            OrderBookDBManager::close_indices_for(*index, order),
+            manager.suggest_close_indices(order.order_id).unwrap()
+        );
+    }
+    assert_eq!(OrderBookDBManager::close_indices_for(expired[0].0, &expired[0].1), vec![3, 2]);
+
+    cleanup_test_db(&temp_path);
+}
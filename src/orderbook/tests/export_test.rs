@@ -0,0 +1,85 @@
+// 流式导出测试
+// Streaming Export Tests
+
+use super::*;
+use crate::orderbook::export_stream;
+use futures_util::StreamExt;
+
+/// 辅助函数: 收集整个导出流 / Helper: collect the whole export stream
+async fn collect_export(manager: Arc<OrderBookDBManager>, chunk_orders: u32) -> (Vec<u8>, usize) {
+    let chunks: Vec<Vec<u8>> = export_stream(manager, chunk_orders)
+        .map(|chunk| chunk.unwrap())
+        .collect()
+        .await;
+    (chunks.concat(), chunks.len())
+}
+
+#[tokio::test]
+async fn test_export_stream_matches_list_order() {
+    let (manager, temp_path) = create_test_manager();
+    let authority = "9B5X6wrjJVcXHnbPfZ8wP4k5m9n2q1r7t3u2v5w8x1y".to_string();
+    manager.initialize(authority).unwrap();
+
+    // 做多订单簿价格从 head 向 tail 递减 / Long book prices descend from head to tail
+    for i in 0..5u64 {
+        let mut order = create_test_order(&format!("User{}", i), (10 - i as u128) * 1000000);
+        order.order_id = i + 1;
+        manager.insert_after(if i == 0 { u16::MAX } else { (i - 1) as u16 }, &order).unwrap();
+    }
+
+    let mut expected = Vec::new();
+    manager
+        .traverse(u16::MAX, 0, |_, order| {
+            expected.push(order.order_id);
+            Ok(true)
+        })
+        .unwrap();
+
+    // 5 个订单每块 2 个, 共 3 块 / 5 orders at 2 per chunk make 3 chunks
+    let (json, chunk_count) = collect_export(Arc::new(manager), 2).await;
+    assert_eq!(chunk_count, 3);
+
+    let orders: Vec<MarginOrder> = serde_json::from_slice(&json).unwrap();
+    let ids: Vec<u64> = orders.iter().map(|o| o.order_id).collect();
+    assert_eq!(ids, expected);
+
+    cleanup_test_db(&temp_path);
+}
+
+#[tokio::test]
+async fn test_export_stream_empty_book() {
+    let (manager, temp_path) = create_test_manager();
+    let authority = "9B5X6wrjJVcXHnbPfZ8wP4k5m9n2q1r7t3u2v5w8x1y".to_string();
+    manager.initialize(authority).unwrap();
+
+    let (json, chunk_count) = collect_export(Arc::new(manager), 2).await;
+    assert_eq!(chunk_count, 1);
+    assert_eq!(json, b"[]");
+
+    cleanup_test_db(&temp_path);
+}
+
+#[tokio::test]
+async fn test_export_stream_detects_removal_between_chunks() {
+    let (manager, temp_path) = create_test_manager();
+    let authority = "9B5X6wrjJVcXHnbPfZ8wP4k5m9n2q1r7t3u2v5w8x1y".to_string();
+    manager.initialize(authority).unwrap();
+
+    for i in 0..5u64 {
+        let mut order = create_test_order(&format!("User{}", i), (10 - i as u128) * 1000000);
+        order.order_id = i + 1;
+        manager.insert_after(if i == 0 { u16::MAX } else { (i - 1) as u16 }, &order).unwrap();
+    }
+
+    let manager = Arc::new(manager);
+    let mut stream = Box::pin(export_stream(manager.clone(), 2));
+    stream.next().await.unwrap().unwrap();
+
+    // 删除下一块的起始订单 (index 2), 尾部订单被换入该槽位 / Remove the next chunk's first order (index 2); the tail order is swapped into its slot
+    manager.batch_remove_by_indices_unsafe(&[2], 1, 5000000).unwrap();
+
+    assert!(stream.next().await.unwrap().is_err());
+    assert!(stream.next().await.is_none());
+
+    cleanup_test_db(&temp_path);
+}
@@ -0,0 +1,49 @@
+// 按开仓价查找用户订单测试
+// Find User Orders by Open Price Tests
+
+use super::*;
+use crate::orderbook::UserOrderQueryService;
+
+#[test]
+fn test_find_user_orders_by_open_price() {
+    let (db, temp_path) = create_test_db();
+    let mint = "MintAaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa".to_string();
+    let manager = OrderBookDBManager::new(db.clone(), mint.clone(), Direction::Dn);
+    manager
+        .initialize("9B5X6wrjJVcXHnbPfZ8wP4k5m9n2q1r7t3u2v5w8x1y".to_string())
+        .unwrap();
+
+    // Alice 有三个订单, Bob 有一个与 Alice 开仓价相同的订单; 开仓价为锁定起点 + 50_000
+    // Alice has three orders, Bob has one at the same open price as one of Alice's; open price is lock start + 50_000
+    manager.insert_after(u16::MAX, &create_test_order_with_id("Alice", 1, 950_000, 1_050_000)).unwrap();
+    manager.insert_after(0, &create_test_order_with_id("Alice", 2, 950_300, 1_050_300)).unwrap();
+    manager.insert_after(1, &create_test_order_with_id("Bob", 3, 950_000, 1_050_000)).unwrap();
+    manager.insert_after(2, &create_test_order_with_id("Alice", 4, 4_950_000, 5_050_000)).unwrap();
+
+    let service = UserOrderQueryService::new(db.clone());
+
+    // 精确匹配只返回 Alice 的订单 / Exact match only returns Alice's order
+    let exact = service
+        .find_user_orders_by_open_price("Alice", &mint, "dn", 1_000_000, 0)
+        .unwrap();
+    assert_eq!(exact.len(), 1);
+    assert_eq!(exact[0].1.order_id, 1);
+
+    // 容差内的多个匹配按价差排序 / Several matches within tolerance come closest first
+    let near = service
+        .find_user_orders_by_open_price("Alice", &mint, "dn", 1_000_250, 500)
+        .unwrap();
+    let ids: Vec<u64> = near.iter().map(|(_, o)| o.order_id).collect();
+    assert_eq!(ids, vec![2, 1]);
+    for (index, order) in &near {
+        assert_eq!(manager.get_order(*index).unwrap().order_id, order.order_id);
+    }
+
+    // 其他方向没有订单 / Nothing in the other direction
+    let other = service
+        .find_user_orders_by_open_price("Alice", &mint, "up", 1_000_000, u128::MAX)
+        .unwrap();
+    assert!(other.is_empty());
+
+    cleanup_test_db(&temp_path);
+}
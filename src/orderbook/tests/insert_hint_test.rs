@@ -0,0 +1,116 @@
+// 开仓插入位置推荐测试
+// Insert Position Suggestion Tests
+
+use super::*;
+use crate::orderbook::OrderBookError;
+
+#[test]
+fn test_suggest_insert_indices_down_book() {
+    let (manager, temp_path) = create_test_manager();
+    manager
+        .initialize("9B5X6wrjJVcXHnbPfZ8wP4k5m9n2q1r7t3u2v5w8x1y".to_string())
+        .unwrap();
+
+    // 空订单簿只能插入到头部 / Empty book can only insert at head
+    assert_eq!(manager.suggest_insert_indices(1000, 900).unwrap(), vec![u16::MAX]);
+
+    // dn 订单簿价格从 head 向 tail 递减 / dn book prices descend from head to tail
+    let (a, _) = manager.insert_after(u16::MAX, &create_test_order_with_id("UserA", 1, 1000, 900)).unwrap();
+    let (b, _) = manager.insert_after(a, &create_test_order_with_id("UserB", 2, 800, 700)).unwrap();
+    let (c, _) = manager.insert_after(b, &create_test_order_with_id("UserC", 3, 600, 500)).unwrap();
+
+    // 头部 / Head
+    assert_eq!(manager.suggest_insert_indices(1100, 1050).unwrap(), vec![u16::MAX]);
+    // A 与 B 之间, 其后回退到头部 / Between A and B, then fall back to head
+    assert_eq!(manager.suggest_insert_indices(850, 820).unwrap(), vec![a, u16::MAX]);
+    // 尾部, 依次回退 / Tail, falling back through predecessors
+    assert_eq!(
+        manager.suggest_insert_indices(450, 400).unwrap(),
+        vec![c, b, a, u16::MAX]
+    );
+
+    // 与 B 重叠 / Overlaps B
+    assert!(manager.suggest_insert_indices(880, 750).unwrap().is_empty());
+
+    // 方向不符的区间 / Range in the wrong direction
+    assert!(matches!(
+        manager.suggest_insert_indices(100, 200),
+        Err(OrderBookError::InvalidPriceRange { .. })
+    ));
+
+    cleanup_test_db(&temp_path);
+}
+
+#[test]
+fn test_suggest_insert_indices_up_book() {
+    let (db, temp_path) = create_test_db();
+    let manager = OrderBookDBManager::new(
+        db,
+        "EPjFWaLb3crLvQQf89kiNqEX5jg5Kv431J06Y1AD3ic".to_string(),
+        Direction::Up,
+    );
+    manager
+        .initialize("9B5X6wrjJVcXHnbPfZ8wP4k5m9n2q1r7t3u2v5w8x1y".to_string())
+        .unwrap();
+
+    // up 订单簿价格从 head 向 tail 递增 / up book prices ascend from head to tail
+    let (a, _) = manager.insert_after(u16::MAX, &create_test_order_with_id("UserA", 1, 100, 200)).unwrap();
+    let (b, _) = manager.insert_after(a, &create_test_order_with_id("UserB", 2, 300, 400)).unwrap();
+
+    assert_eq!(manager.suggest_insert_indices(10, 50).unwrap(), vec![u16::MAX]);
+    assert_eq!(manager.suggest_insert_indices(200, 300).unwrap(), vec![a, u16::MAX]);
+    assert_eq!(manager.suggest_insert_indices(500, 600).unwrap(), vec![b, a, u16::MAX]);
+    assert!(manager.suggest_insert_indices(150, 250).unwrap().is_empty());
+
+    cleanup_test_db(&temp_path);
+}
+
+#[test]
+fn test_suggest_insert_indices_caps_candidates() {
+    let (manager, temp_path) = create_test_manager();
+    manager
+        .initialize("9B5X6wrjJVcXHnbPfZ8wP4k5m9n2q1r7t3u2v5w8x1y".to_string())
+        .unwrap();
+
+    let mut prev = u16::MAX;
+    for i in 0..30u128 {
+        let start = 100_000 - i * 100;
+        let (index, _) = manager
+            .insert_after(prev, &create_test_order_with_id("User", i as u64 + 1, start, start - 50))
+            .unwrap();
+        prev = index;
+    }
+
+    let indices = manager.suggest_insert_indices(1000, 900).unwrap();
+    assert_eq!(indices.len(), crate::util::curve::MAX_CLOSE_INSERT_INDICES);
+    assert_eq!(indices[0], prev);
+
+    cleanup_test_db(&temp_path);
+}
+
+#[test]
+fn test_suggest_close_indices() {
+    let (manager, temp_path) = create_test_manager();
+    manager
+        .initialize("9B5X6wrjJVcXHnbPfZ8wP4k5m9n2q1r7t3u2v5w8x1y".to_string())
+        .unwrap();
+
+    let (a, id_a) = manager.insert_after(u16::MAX, &create_test_order_with_id("UserA", 1, 1000, 900)).unwrap();
+    let (b, id_b) = manager.insert_after(a, &create_test_order_with_id("UserB", 2, 800, 700)).unwrap();
+    let (c, id_c) = manager.insert_after(b, &create_test_order_with_id("UserC", 3, 600, 500)).unwrap();
+
+    assert_eq!(manager.suggest_close_indices(id_a).unwrap(), vec![a, b]);
+    assert_eq!(manager.suggest_close_indices(id_b).unwrap(), vec![b, a, c]);
+    assert_eq!(manager.suggest_close_indices(id_c).unwrap(), vec![c, b]);
+
+    // 删除 A 后 C 被搬移到 A 的槽位 / After deleting A, C moves into A's slot
+    manager.batch_remove_by_indices_unsafe(&[a], 1, 900).unwrap();
+    assert_eq!(manager.suggest_close_indices(id_c).unwrap(), vec![a, b]);
+
+    assert!(matches!(
+        manager.suggest_close_indices(id_a),
+        Err(OrderBookError::OrderIdNotFound(id)) if id == id_a
+    ));
+
+    cleanup_test_db(&temp_path);
+}
@@ -1,7 +1,7 @@
 // OrderBook 测试模块
 // OrderBook Test Module
 
-use crate::orderbook::{MarginOrder, OrderBookDBManager, OrderBookHeader};
+use crate::orderbook::{Direction, MarginOrder, OrderBookDBManager, OrderBookHeader};
 use rocksdb::{Options, DB};
 use std::sync::Arc;
 use uuid::Uuid;
@@ -27,7 +27,7 @@ pub fn cleanup_test_db(path: &str) {
 pub fn create_test_manager() -> (OrderBookDBManager, String) {
     let (db, temp_path) = create_test_db();
     let mint = "EPjFWaLb3crLvQQf89kiNqEX5jg5Kv431J06Y1AD3ic".to_string();
-    let direction = "dn".to_string();
+    let direction = Direction::Dn;
     let manager = OrderBookDBManager::new(db, mint, direction);
     (manager, temp_path)
 }
@@ -40,6 +40,7 @@ pub fn create_test_order(user: &str, price: u128) -> MarginOrder {
         lock_lp_start_price: price,
         lock_lp_end_price: price + 100000,
         open_price: price + 50000,
+        take_profit_price: 0,
         order_id: 0, // 会被自动分配 / Will be auto-assigned
         lock_lp_sol_amount: 1000000000,
         lock_lp_token_amount: 5000000000,
@@ -60,6 +61,16 @@ pub fn create_test_order(user: &str, price: u128) -> MarginOrder {
     }
 }
 
+/// 创建带事件 order_id 和指定锁定区间的测试订单, 其余字段同 create_test_order
+/// Create a test order with an event order_id and the given lock range; other fields as in create_test_order
+pub fn create_test_order_with_id(user: &str, order_id: u64, start: u128, end: u128) -> MarginOrder {
+    let mut order = create_test_order(user, start);
+    order.order_id = order_id;
+    order.lock_lp_start_price = start;
+    order.lock_lp_end_price = end;
+    order
+}
+
 mod insert_test;
 mod delete_test;
 mod update_test;
@@ -67,3 +78,16 @@ mod traverse_test;
 mod stress_test;
 mod bug_verification_test;
 mod order_id_fix_test;
+mod user_global_index_test;
+mod insert_hint_test;
+mod rebuild_test;
+mod replay_test;
+mod timeline_test;
+mod expiring_test;
+mod serialization_test;
+mod onchain_test;
+mod find_by_open_price_test;
+mod depth_test;
+mod consistency_test;
+mod event_replay_harness_test;
+mod export_test;
@@ -0,0 +1,81 @@
+// 链上 OrderBook 账户解码测试
+// On-chain OrderBook account decoding tests
+
+use crate::orderbook::onchain::{ONCHAIN_HEADER_SIZE, ONCHAIN_ORDER_SIZE};
+use crate::orderbook::{decode_orderbook_account, OrderBookError};
+use solana_sdk::pubkey::Pubkey;
+
+/// 按合约布局构造账户数据 / Build account data using the program layout
+fn build_account(capacity: u32, total: u16) -> (Vec<u8>, Pubkey, Pubkey) {
+    let authority = Pubkey::new_unique();
+    let user = Pubkey::new_unique();
+    let mut data = vec![0u8; 8 + ONCHAIN_HEADER_SIZE + capacity as usize * ONCHAIN_ORDER_SIZE];
+
+    let header = &mut data[8..8 + ONCHAIN_HEADER_SIZE];
+    header[0] = 1;
+    header[1] = 1;
+    header[2] = 254;
+    header[8..40].copy_from_slice(authority.as_ref());
+    header[40..48].copy_from_slice(&7u64.to_le_bytes());
+    header[48..52].copy_from_slice(&1_700_000_000u32.to_le_bytes());
+    header[56..60].copy_from_slice(&capacity.to_le_bytes());
+    header[60..62].copy_from_slice(&0u16.to_le_bytes());
+    header[62..64].copy_from_slice(&total.saturating_sub(1).to_le_bytes());
+    header[64..66].copy_from_slice(&total.to_le_bytes());
+
+    for index in 0..total as usize {
+        let start = 8 + ONCHAIN_HEADER_SIZE + index * ONCHAIN_ORDER_SIZE;
+        let slot = &mut data[start..start + ONCHAIN_ORDER_SIZE];
+        slot[0..32].copy_from_slice(user.as_ref());
+        slot[32..48].copy_from_slice(&(1_000_000u128 + index as u128).to_le_bytes());
+        slot[80..88].copy_from_slice(&(index as u64 + 1).to_le_bytes());
+        slot[136..144].copy_from_slice(&900u64.to_le_bytes());
+        slot[168..172].copy_from_slice(&1_700_086_400u32.to_le_bytes());
+        slot[172..174].copy_from_slice(&u16::MAX.to_le_bytes());
+        slot[178] = 1;
+        // 止盈价 96 位 / 96-bit take-profit price
+        slot[179..191].copy_from_slice(&(5_000_000u128.to_le_bytes()[..12]));
+    }
+
+    (data, authority, user)
+}
+
+#[test]
+fn test_decode_orderbook_account() {
+    let (data, authority, user) = build_account(4, 2);
+    let book = decode_orderbook_account(&data).unwrap();
+
+    assert_eq!(book.header.version, 1);
+    assert_eq!(book.header.bump, 254);
+    assert_eq!(book.header.authority, authority.to_string());
+    assert_eq!(book.header.order_id_counter, 7);
+    assert_eq!(book.header.total_capacity, 4);
+    assert_eq!(book.header.total, 2);
+
+    assert_eq!(book.orders.len(), 2);
+    let (index, order) = &book.orders[1];
+    assert_eq!(*index, 1);
+    assert_eq!(order.user, user.to_string());
+    assert_eq!(order.lock_lp_start_price, 1_000_001);
+    assert_eq!(order.order_id, 2);
+    assert_eq!(order.borrow_amount, 900);
+    assert_eq!(order.end_time, 1_700_086_400);
+    assert_eq!(order.next_order, u16::MAX);
+    assert_eq!(order.order_type, 1);
+    assert_eq!(order.take_profit_price, 5_000_000);
+}
+
+#[test]
+fn test_decode_rejects_unexpected_size() {
+    let (mut data, _, _) = build_account(4, 2);
+    data.pop();
+    assert!(matches!(
+        decode_orderbook_account(&data),
+        Err(OrderBookError::InvalidAccountData(_))
+    ));
+
+    assert!(matches!(
+        decode_orderbook_account(&[0u8; 16]),
+        Err(OrderBookError::InvalidAccountData(_))
+    ));
+}
@@ -10,14 +10,6 @@
 use super::*;
 use crate::orderbook::errors::OrderBookError;
 
-/// 辅助函数: 创建带指定 order_id 的测试订单
-/// Helper function: Create test order with specified order_id
-fn create_order_with_id(user: &str, order_id: u64, price: u128) -> MarginOrder {
-    let mut order = create_test_order(user, price);
-    order.order_id = order_id;
-    order
-}
-
 // ==================== 测试 1: 使用事件中的 order_id ====================
 // ==================== Test 1: Use order_id from event ====================
 
@@ -31,7 +23,7 @@ fn test_insert_with_event_order_id() {
 
     // 创建订单,order_id = 100 (模拟来自事件)
     // Create order with order_id = 100 (simulating from event)
-    let order = create_order_with_id("UserA", 100, 1000000);
+    let order = create_test_order_with_id("UserA", 100, 1000000, 1100000);
 
     // 插入订单
     // Insert order
@@ -69,7 +61,7 @@ fn test_insert_with_zero_order_id_should_fail() {
 
     // 创建订单,order_id = 0 (无效)
     // Create order with order_id = 0 (invalid)
-    let order = create_order_with_id("UserA", 0, 1000000);
+    let order = create_test_order_with_id("UserA", 0, 1000000, 1100000);
 
     // 插入应该失败
     // Insert should fail
@@ -107,7 +99,7 @@ fn test_insert_multiple_orders_with_non_sequential_ids() {
 
     // 插入 order_id = 100
     // Insert order_id = 100
-    let order1 = create_order_with_id("UserA", 100, 1000000);
+    let order1 = create_test_order_with_id("UserA", 100, 1000000, 1100000);
     let (index1, id1) = manager.insert_after(u16::MAX, &order1).unwrap();
     assert_eq!(id1, 100);
     assert_eq!(index1, 0);
@@ -119,7 +111,7 @@ fn test_insert_multiple_orders_with_non_sequential_ids() {
 
     // 插入 order_id = 200 (跳过了 101-199)
     // Insert order_id = 200 (skipped 101-199)
-    let order2 = create_order_with_id("UserB", 200, 2000000);
+    let order2 = create_test_order_with_id("UserB", 200, 2000000, 2100000);
     let (index2, id2) = manager.insert_after(0, &order2).unwrap();
     assert_eq!(id2, 200);
     assert_eq!(index2, 1);
@@ -131,7 +123,7 @@ fn test_insert_multiple_orders_with_non_sequential_ids() {
 
     // 插入 order_id = 150 (在之前的范围内,但比 counter 小)
     // Insert order_id = 150 (within previous range, but less than counter)
-    let order3 = create_order_with_id("UserC", 150, 1500000);
+    let order3 = create_test_order_with_id("UserC", 150, 1500000, 1600000);
     let (index3, id3) = manager.insert_after(1, &order3).unwrap();
     assert_eq!(id3, 150);
     assert_eq!(index3, 2);
@@ -165,7 +157,7 @@ fn test_order_id_counter_is_metadata_only() {
 
     // 第一个订单: order_id = 1000
     // First order: order_id = 1000
-    let order1 = create_order_with_id("UserA", 1000, 1000000);
+    let order1 = create_test_order_with_id("UserA", 1000, 1000000, 1100000);
     manager.insert_after(u16::MAX, &order1).unwrap();
 
     // counter 应该是 1001
@@ -175,7 +167,7 @@ fn test_order_id_counter_is_metadata_only() {
 
     // 第二个订单: order_id = 500 (小于 counter)
     // Second order: order_id = 500 (less than counter)
-    let order2 = create_order_with_id("UserB", 500, 2000000);
+    let order2 = create_test_order_with_id("UserB", 500, 2000000, 2100000);
     manager.insert_after(0, &order2).unwrap();
 
     // counter 应该保持 1001 (不变)
@@ -185,7 +177,7 @@ fn test_order_id_counter_is_metadata_only() {
 
     // 第三个订单: order_id = 2000 (大于 counter)
     // Third order: order_id = 2000 (greater than counter)
-    let order3 = create_order_with_id("UserC", 2000, 3000000);
+    let order3 = create_test_order_with_id("UserC", 2000, 3000000, 3100000);
     manager.insert_after(1, &order3).unwrap();
 
     // counter 应该更新为 2001
@@ -216,12 +208,12 @@ fn test_insert_before_with_event_order_id() {
 
     // 插入第一个订单: order_id = 100
     // Insert first order: order_id = 100
-    let order1 = create_order_with_id("UserA", 100, 1000000);
+    let order1 = create_test_order_with_id("UserA", 100, 1000000, 1100000);
     manager.insert_after(u16::MAX, &order1).unwrap();
 
     // 在第一个订单之前插入: order_id = 50
     // Insert before first order: order_id = 50
-    let order2 = create_order_with_id("UserB", 50, 2000000);
+    let order2 = create_test_order_with_id("UserB", 50, 2000000, 2100000);
     let (index, assigned_id) = manager.insert_before(0, &order2).unwrap();
 
     // 验证返回的 order_id 是 50
@@ -256,12 +248,12 @@ fn test_insert_before_with_zero_order_id_should_fail() {
 
     // 先插入一个有效订单
     // First insert a valid order
-    let order1 = create_order_with_id("UserA", 100, 1000000);
+    let order1 = create_test_order_with_id("UserA", 100, 1000000, 1100000);
     manager.insert_after(u16::MAX, &order1).unwrap();
 
     // 尝试插入 order_id = 0 的订单
     // Try to insert order with order_id = 0
-    let order2 = create_order_with_id("UserB", 0, 2000000);
+    let order2 = create_test_order_with_id("UserB", 0, 2000000, 2100000);
     let result = manager.insert_before(0, &order2);
 
     // 验证应该失败
@@ -298,7 +290,8 @@ fn test_large_scale_random_order_ids() {
     let mut max_order_id = 0u64;
 
     for (i, &order_id) in order_ids.iter().enumerate() {
-        let order = create_order_with_id(&format!("User{}", i), order_id, 1000000 + i as u128 * 100000);
+        let price = 1000000 + i as u128 * 100000;
+        let order = create_test_order_with_id(&format!("User{}", i), order_id, price, price + 100000);
 
         if i == 0 {
             manager.insert_after(u16::MAX, &order).unwrap();
@@ -362,3 +355,32 @@ fn test_old_behavior_no_longer_works() {
     cleanup_test_db(&temp_path);
     println!("✅ test_old_behavior_no_longer_works passed");
 }
+
+// ==================== 测试 9: 按 order_id 查找当前索引 ====================
+// ==================== Test 9: Look up the current index by order_id ====================
+
+#[test]
+fn test_index_of_order_id_follows_removal() {
+    // 测试: 删除前面的订单后, order_id 对应的索引随 swap-remove 更新
+    // Test: After removing an earlier order, the index for an order_id follows the swap-remove
+    let (manager, temp_path) = create_test_manager();
+    let authority = "9B5X6wrjJVcXHnbPfZ8wP4k5m9n2q1r7t3u2v5w8x1y".to_string();
+    manager.initialize(authority).unwrap();
+
+    manager.insert_after(u16::MAX, &create_test_order_with_id("UserA", 10, 1000000, 1100000)).unwrap();
+    manager.insert_after(0, &create_test_order_with_id("UserB", 20, 2000000, 2100000)).unwrap();
+    manager.insert_after(1, &create_test_order_with_id("UserC", 30, 3000000, 3100000)).unwrap();
+    assert_eq!(manager.index_of_order_id(30).unwrap(), 2);
+
+    // 删除索引 0, 末尾的订单 30 被移动到索引 0
+    // Remove index 0, the last order 30 moves into index 0
+    manager.batch_remove_by_indices_unsafe(&[0], 1, 1000000).unwrap();
+    assert_eq!(manager.index_of_order_id(30).unwrap(), 0);
+    assert_eq!(manager.index_of_order_id(20).unwrap(), 1);
+
+    let result = manager.index_of_order_id(10);
+    assert!(matches!(result, Err(OrderBookError::OrderIdNotFound(10))));
+
+    cleanup_test_db(&temp_path);
+    println!("✅ test_index_of_order_id_follows_removal passed");
+}
@@ -0,0 +1,110 @@
+// 订单簿重建测试
+// Order Book Rebuild Tests
+
+use super::*;
+use crate::db::orderbook_storage::rebuild_marker_key;
+use crate::db::undo::UndoJournal;
+
+/// 按链表顺序收集 (索引, order_id) / Collect (index, order_id) in linked-list order
+fn walk(manager: &OrderBookDBManager) -> Vec<(u16, u64)> {
+    let mut walked = Vec::new();
+    manager
+        .traverse(u16::MAX, 0, |index, order| {
+            walked.push((index, order.order_id));
+            Ok(true)
+        })
+        .unwrap();
+    walked
+}
+
+#[test]
+fn test_rebuild_compacts_in_list_order() {
+    let (manager, temp_path) = create_test_manager();
+    manager
+        .initialize("9B5X6wrjJVcXHnbPfZ8wP4k5m9n2q1r7t3u2v5w8x1y".to_string())
+        .unwrap();
+
+    // 中间插入使槽位顺序与链表顺序不同 / Middle inserts make slot order differ from list order
+    for (after, id) in [(u16::MAX, 10u64), (0, 20), (0, 30), (1, 40), (2, 50)] {
+        let mut order = create_test_order(&format!("User{}", id), id as u128 * 1000);
+        order.order_id = id;
+        manager.insert_after(after, &order).unwrap();
+    }
+    manager.batch_remove_by_indices_unsafe(&[1], 1, 1500000).unwrap();
+
+    let before: Vec<u64> = walk(&manager).into_iter().map(|(_, id)| id).collect();
+    let counter = manager.load_header().unwrap().order_id_counter;
+
+    manager.rebuild().unwrap();
+
+    // 链表顺序与 order_id 不变, 索引连续 / List order and order_ids unchanged, indices contiguous
+    let after = walk(&manager);
+    let ids: Vec<u64> = after.iter().map(|(_, id)| *id).collect();
+    assert_eq!(ids, before);
+    let indices: Vec<u16> = after.iter().map(|(index, _)| *index).collect();
+    assert_eq!(indices, (0..before.len() as u16).collect::<Vec<_>>());
+
+    let header = manager.load_header().unwrap();
+    assert_eq!(header.head, 0);
+    assert_eq!(header.tail, before.len() as u16 - 1);
+    assert_eq!(header.total, before.len() as u16);
+    assert_eq!(header.total_capacity, header.total as u32);
+    assert_eq!(header.order_id_counter, counter);
+    assert_eq!(manager.load_active_indices().unwrap(), indices);
+
+    for (index, id) in after {
+        let order = manager.get_order_by_id(id).unwrap();
+        assert_eq!(order.order_id, id);
+        assert_eq!(manager.get_order(index).unwrap().order_id, id);
+    }
+
+    cleanup_test_db(&temp_path);
+}
+
+#[test]
+fn test_rebuild_empty_book() {
+    let (manager, temp_path) = create_test_manager();
+    manager
+        .initialize("9B5X6wrjJVcXHnbPfZ8wP4k5m9n2q1r7t3u2v5w8x1y".to_string())
+        .unwrap();
+
+    manager.rebuild().unwrap();
+
+    let header = manager.load_header().unwrap();
+    assert_eq!(header.total, 0);
+    assert_eq!(header.head, u16::MAX);
+    assert_eq!(header.tail, u16::MAX);
+    assert!(manager.load_active_indices().unwrap().is_empty());
+
+    cleanup_test_db(&temp_path);
+}
+
+#[test]
+fn test_rebuild_clears_marker_and_is_journaled() {
+    let (db, temp_path) = create_test_db();
+    let mint = "EPjFWaLb3crLvQQf89kiNqEX5jg5Kv431J06Y1AD3ic";
+    let journal = Arc::new(UndoJournal::new(db.clone()));
+    let manager = OrderBookDBManager::new(db.clone(), mint.to_string(), Direction::Dn).with_journal(journal.clone());
+    manager
+        .initialize("9B5X6wrjJVcXHnbPfZ8wP4k5m9n2q1r7t3u2v5w8x1y".to_string())
+        .unwrap();
+    let mut order = create_test_order("User10", 10_000);
+    order.order_id = 10;
+    manager.insert_after(u16::MAX, &order).unwrap();
+
+    let marker_key = rebuild_marker_key(mint, Direction::Dn);
+    db.put(marker_key.as_bytes(), br#"{"reason":"test"}"#).unwrap();
+    assert!(!manager.check_consistency().unwrap().consistent);
+
+    // 重建清除标记 / The rebuild clears the marker
+    journal.begin_slot(7);
+    manager.rebuild().unwrap();
+    assert!(db.get(marker_key.as_bytes()).unwrap().is_none());
+    assert!(manager.check_consistency().unwrap().consistent);
+
+    // 重建写入了撤销日志, 回滚 slot 后标记恢复 / The rebuild is journaled, reverting its slot restores the marker
+    assert!(journal.revert_slot(7).unwrap() > 0);
+    assert!(db.get(marker_key.as_bytes()).unwrap().is_some());
+
+    cleanup_test_db(&temp_path);
+}
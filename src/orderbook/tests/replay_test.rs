@@ -0,0 +1,139 @@
+// 订单簿历史回放测试
+// Order Book History Replay Tests
+
+use crate::orderbook::{replay_orderbook_at, Direction, OrderBookError};
+use crate::solana::events::{BuySellEvent, LongShortEvent, PinpetEvent, TokenCreatedEvent};
+use crate::util::curve::CurveAMM;
+use chrono::{DateTime, TimeZone, Utc};
+
+const MINT: &str = "EPjFWaLb3crLvQQf89kiNqEX5jg5Kv431J06Y1AD3ic";
+
+fn at(secs: i64) -> DateTime<Utc> {
+    Utc.timestamp_opt(secs, 0).unwrap()
+}
+
+fn token_created(secs: i64) -> PinpetEvent {
+    PinpetEvent::TokenCreated(TokenCreatedEvent {
+        payer: "Creator".to_string(),
+        mint_account: MINT.to_string(),
+        curve_account: String::new(),
+        pool_token_account: String::new(),
+        pool_sol_account: String::new(),
+        fee_recipient: String::new(),
+        base_fee_recipient: String::new(),
+        params_account: String::new(),
+        swap_fee: 1000,
+        borrow_fee: 1000,
+        fee_discount_flag: 0,
+        name: "Test".to_string(),
+        symbol: "TST".to_string(),
+        uri: String::new(),
+        up_orderbook: String::new(),
+        down_orderbook: String::new(),
+        latest_price: CurveAMM::get_initial_price().unwrap(),
+        timestamp: at(secs),
+        signature: "sig_created".to_string(),
+        slot: secs as u64,
+        event_index: 0,
+    })
+}
+
+fn open_long(secs: i64, order_id: u64, order_index: u16, start: u128, end: u128) -> PinpetEvent {
+    PinpetEvent::LongShort(LongShortEvent {
+        payer: format!("User{}", order_id),
+        mint_account: MINT.to_string(),
+        order_id,
+        order_index,
+        latest_price: start,
+        open_price: start,
+        order_type: 1,
+        lock_lp_start_price: start,
+        lock_lp_end_price: end,
+        lock_lp_sol_amount: 1_000_000_000,
+        lock_lp_token_amount: 5_000_000_000,
+        start_time: secs as u32,
+        end_time: secs as u32 + 86400,
+        margin_sol_amount: 100_000_000,
+        borrow_amount: 900_000_000,
+        position_asset_amount: 5_000_000_000,
+        borrow_fee: 1000,
+        liquidate_indices: vec![],
+        take_profit_price: 0,
+        timestamp: at(secs),
+        signature: format!("sig_open_{}", order_id),
+        slot: secs as u64,
+        event_index: 0,
+    })
+}
+
+fn sell_with_liquidations(secs: i64, liquidate_indices: Vec<u16>) -> PinpetEvent {
+    PinpetEvent::BuySell(BuySellEvent {
+        payer: "Trader".to_string(),
+        mint_account: MINT.to_string(),
+        is_buy: false,
+        token_amount: 1_000_000,
+        sol_amount: 1_000,
+        latest_price: CurveAMM::get_initial_price().unwrap() / 2,
+        liquidate_indices,
+        timestamp: at(secs),
+        signature: format!("sig_sell_{}", secs),
+        slot: secs as u64,
+        event_index: 0,
+    })
+}
+
+fn events() -> Vec<PinpetEvent> {
+    let p = CurveAMM::get_initial_price().unwrap();
+    vec![
+        token_created(0),
+        open_long(10, 1, 0, p, p * 9 / 10),
+        open_long(20, 2, 1, p * 8 / 10, p * 7 / 10),
+        sell_with_liquidations(30, vec![0]),
+    ]
+}
+
+#[test]
+fn test_replay_reconstructs_book_at_each_point_in_time() {
+    let events = events();
+
+    let before = replay_orderbook_at(&events, Direction::Dn, at(5)).unwrap();
+    assert!(before.orders.is_empty());
+    assert_eq!(before.replayed_events, 1);
+
+    let one = replay_orderbook_at(&events, Direction::Dn, at(15)).unwrap();
+    let ids: Vec<u64> = one.orders.iter().map(|(_, o)| o.order_id).collect();
+    assert_eq!(ids, vec![1]);
+
+    let two = replay_orderbook_at(&events, Direction::Dn, at(25)).unwrap();
+    let ids: Vec<u64> = two.orders.iter().map(|(_, o)| o.order_id).collect();
+    assert_eq!(ids, vec![1, 2]);
+    // 相邻订单间的流动性由曲线数学重算, 尾部为无限空间
+    // Liquidity between adjacent orders is recomputed with the curve math, the tail is unbounded
+    assert!(two.orders[0].1.next_lp_sol_amount > 0);
+    assert!(two.orders[0].1.next_lp_token_amount > 0);
+    assert_eq!(two.orders[1].1.next_lp_sol_amount, CurveAMM::MAX_U64);
+
+    // 清算 index 0 后订单 2 被搬移到 index 0 / After liquidating index 0, order 2 moves into index 0
+    let after = replay_orderbook_at(&events, Direction::Dn, at(35)).unwrap();
+    assert_eq!(after.orders.len(), 1);
+    assert_eq!(after.orders[0].0, 0);
+    assert_eq!(after.orders[0].1.order_id, 2);
+    assert_eq!(after.replayed_events, 4);
+
+    // 做空订单簿不受影响 / Short book is unaffected
+    assert!(replay_orderbook_at(&events, Direction::Up, at(35)).unwrap().orders.is_empty());
+}
+
+#[test]
+fn test_replay_errors_when_history_is_incomplete() {
+    let events = events();
+
+    let result = replay_orderbook_at(&events[1..], Direction::Dn, at(35));
+    assert!(matches!(result, Err(OrderBookError::HistoryUnavailable(_))));
+
+    let result = replay_orderbook_at(&[], Direction::Dn, at(35));
+    assert!(matches!(result, Err(OrderBookError::HistoryUnavailable(_))));
+
+    let result = "sideways".parse::<Direction>();
+    assert!(matches!(result, Err(OrderBookError::InvalidDirection(_))));
+}
@@ -0,0 +1,75 @@
+// MarginOrder 序列化测试
+// MarginOrder serialization tests
+
+use super::*;
+use crate::orderbook::types::MARGIN_ORDER_LAYOUT_VERSION;
+use rand::Rng;
+
+/// 生成所有字段随机的订单 / Build an order with every field randomized
+fn random_order(rng: &mut impl Rng) -> MarginOrder {
+    MarginOrder {
+        user: format!("User{}", rng.gen::<u64>()),
+        lock_lp_start_price: rng.gen(),
+        lock_lp_end_price: rng.gen(),
+        open_price: rng.gen(),
+        take_profit_price: rng.gen(),
+        order_id: rng.gen(),
+        lock_lp_sol_amount: rng.gen(),
+        lock_lp_token_amount: rng.gen(),
+        next_lp_sol_amount: rng.gen(),
+        next_lp_token_amount: rng.gen(),
+        margin_init_sol_amount: rng.gen(),
+        margin_sol_amount: rng.gen(),
+        borrow_amount: rng.gen(),
+        position_asset_amount: rng.gen(),
+        realized_sol_amount: rng.gen(),
+        version: rng.gen(),
+        start_time: rng.gen(),
+        end_time: rng.gen(),
+        next_order: rng.gen(),
+        prev_order: rng.gen(),
+        borrow_fee: rng.gen(),
+        order_type: rng.gen(),
+    }
+}
+
+#[test]
+fn test_random_orders_round_trip() {
+    let mut rng = rand::thread_rng();
+
+    for _ in 0..1000 {
+        let order = random_order(&mut rng);
+        let bytes = order.to_bytes().unwrap();
+        assert_eq!(bytes[0], MARGIN_ORDER_LAYOUT_VERSION);
+
+        let decoded = MarginOrder::from_bytes(&bytes).unwrap();
+        // u128 以字符串存储, 比较 JSON 值即可覆盖全部字段 / u128 is stored as a string, comparing JSON values covers every field
+        assert_eq!(
+            serde_json::to_value(&decoded).unwrap(),
+            serde_json::to_value(&order).unwrap()
+        );
+    }
+}
+
+#[test]
+fn test_reads_unversioned_layout() {
+    // 版本 0 是不带前缀的 JSON / Version 0 is JSON without a prefix
+    let mut order = create_test_order("LegacyUser", 1_000_000);
+    order.order_id = 42;
+    let legacy_bytes = serde_json::to_vec(&order).unwrap();
+
+    let decoded = MarginOrder::from_bytes(&legacy_bytes).unwrap();
+    assert_eq!(decoded.order_id, 42);
+    assert_eq!(decoded.user, "LegacyUser");
+    assert_eq!(decoded.lock_lp_start_price, order.lock_lp_start_price);
+}
+
+#[test]
+fn test_rejects_unknown_layout_version() {
+    let order = create_test_order("FutureUser", 1_000_000);
+    let mut bytes = order.to_bytes().unwrap();
+    bytes[0] = MARGIN_ORDER_LAYOUT_VERSION + 1;
+
+    assert!(MarginOrder::from_bytes(&bytes).is_err());
+    assert!(MarginOrder::from_bytes(&[]).is_err());
+}
@@ -0,0 +1,236 @@
+// 订单生命周期时间线测试
+// Order Lifecycle Timeline Tests
+
+use crate::orderbook::build_order_timeline;
+use crate::orderbook::timeline::{ORDER_STATUS_CLOSED, ORDER_STATUS_LIQUIDATED, ORDER_STATUS_OPEN};
+use crate::solana::events::{
+    AddMarginEvent, ExtendOrderEvent, FullCloseEvent, LongShortEvent, PartialCloseEvent, PinpetEvent,
+};
+use chrono::{TimeZone, Utc};
+
+const MINT: &str = "EPjFWaLb3crLvQQf89kiNqEX5jg5Kv431J06Y1AD3ic";
+const USER: &str = "UserA";
+const MARGIN: u64 = 100_000_000;
+
+fn open_long(order_id: u64, slot: u64) -> PinpetEvent {
+    PinpetEvent::LongShort(LongShortEvent {
+        payer: USER.to_string(),
+        mint_account: MINT.to_string(),
+        order_id,
+        order_index: 0,
+        latest_price: 1_000_000,
+        open_price: 1_000_000,
+        order_type: 1,
+        lock_lp_start_price: 1_000_000,
+        lock_lp_end_price: 900_000,
+        lock_lp_sol_amount: 1_000_000_000,
+        lock_lp_token_amount: 5_000_000_000,
+        start_time: slot as u32,
+        end_time: slot as u32 + 86400,
+        margin_sol_amount: MARGIN,
+        borrow_amount: 900_000_000,
+        position_asset_amount: 5_000_000_000,
+        borrow_fee: 1000,
+        liquidate_indices: vec![],
+        take_profit_price: 0,
+        timestamp: Utc.timestamp_opt(slot as i64, 0).unwrap(),
+        signature: format!("sig_open_{}", order_id),
+        slot,
+        event_index: 0,
+    })
+}
+
+fn partial_close(order_id: u64, slot: u64, profit: u64, remaining: u64) -> PinpetEvent {
+    PinpetEvent::PartialClose(PartialCloseEvent {
+        payer: USER.to_string(),
+        user_sol_account: USER.to_string(),
+        mint_account: MINT.to_string(),
+        is_close_long: true,
+        final_token_amount: 5_000_000_000 - remaining,
+        final_sol_amount: 500_000_000,
+        user_close_profit: profit,
+        latest_price: 1_100_000,
+        order_id,
+        order_index: 0,
+        order_type: 1,
+        user: USER.to_string(),
+        lock_lp_start_price: 1_000_000,
+        lock_lp_end_price: 900_000,
+        lock_lp_sol_amount: 500_000_000,
+        lock_lp_token_amount: remaining,
+        start_time: 0,
+        end_time: 86400,
+        margin_sol_amount: MARGIN,
+        borrow_amount: 450_000_000,
+        position_asset_amount: remaining,
+        borrow_fee: 1000,
+        realized_sol_amount: profit,
+        liquidate_indices: vec![],
+        timestamp: Utc.timestamp_opt(slot as i64, 0).unwrap(),
+        signature: format!("sig_pc_{}_{}", order_id, slot),
+        slot,
+        event_index: 0,
+    })
+}
+
+fn add_margin(order_id: u64, slot: u64, add_sol: u64) -> PinpetEvent {
+    PinpetEvent::AddMargin(AddMarginEvent {
+        payer: USER.to_string(),
+        mint_account: MINT.to_string(),
+        order_id,
+        order_index: 0,
+        order_type: 1,
+        add_sol_amount: add_sol,
+        margin_sol_amount: MARGIN + add_sol,
+        lock_lp_start_price: 950_000,
+        lock_lp_end_price: 850_000,
+        lock_lp_sol_amount: 1_000_000_000,
+        lock_lp_token_amount: 5_000_000_000,
+        latest_price: 1_000_000,
+        timestamp: Utc.timestamp_opt(slot as i64, 0).unwrap(),
+        signature: format!("sig_am_{}", order_id),
+        slot,
+        event_index: 0,
+    })
+}
+
+fn extend(order_id: u64, slot: u64, fee: u64) -> PinpetEvent {
+    PinpetEvent::ExtendOrder(ExtendOrderEvent {
+        payer: USER.to_string(),
+        mint_account: MINT.to_string(),
+        order_id,
+        order_index: 0,
+        order_type: 1,
+        extend_seconds: 3600,
+        old_end_time: 10 + 86400,
+        end_time: 10 + 86400 + 3600,
+        fee_sol_amount: fee,
+        latest_price: 1_000_000,
+        timestamp: Utc.timestamp_opt(slot as i64, 0).unwrap(),
+        signature: format!("sig_eo_{}", order_id),
+        slot,
+        event_index: 0,
+    })
+}
+
+fn full_close(order_id: u64, slot: u64, profit: u64) -> PinpetEvent {
+    PinpetEvent::FullClose(FullCloseEvent {
+        payer: "Keeper".to_string(),
+        user_sol_account: USER.to_string(),
+        mint_account: MINT.to_string(),
+        is_close_long: true,
+        final_token_amount: 2_000_000_000,
+        final_sol_amount: 400_000_000,
+        user_close_profit: profit,
+        latest_price: 1_200_000,
+        order_id,
+        order_index: 0,
+        liquidate_indices: vec![0],
+        keeper_reward: 0,
+        timestamp: Utc.timestamp_opt(slot as i64, 0).unwrap(),
+        signature: format!("sig_fc_{}", order_id),
+        slot,
+        event_index: 0,
+    })
+}
+
+#[test]
+fn test_timeline_open_with_partial_closes() {
+    let events = vec![
+        open_long(7, 10),
+        partial_close(7, 20, 30_000_000, 3_000_000_000),
+        partial_close(7, 30, 20_000_000, 2_000_000_000),
+    ];
+
+    let timeline = build_order_timeline(7, &events, true);
+
+    assert_eq!(timeline.status, ORDER_STATUS_OPEN);
+    assert_eq!(timeline.user.as_deref(), Some(USER));
+    let actions: Vec<&str> = timeline.steps.iter().map(|s| s.action.as_str()).collect();
+    assert_eq!(actions, vec!["open", "partial_close", "partial_close"]);
+    assert_eq!(timeline.steps[0].realized_pnl_sol, 0);
+    assert_eq!(timeline.steps[1].realized_pnl_sol, 30_000_000);
+    assert_eq!(timeline.steps[2].realized_pnl_sol, 50_000_000);
+    assert_eq!(timeline.steps[2].position_asset_amount, 2_000_000_000);
+    assert_eq!(timeline.realized_pnl_sol, 50_000_000);
+}
+
+#[test]
+fn test_timeline_full_close_settles_margin() {
+    let events = vec![
+        open_long(7, 10),
+        partial_close(7, 20, 30_000_000, 2_000_000_000),
+        full_close(7, 30, 150_000_000),
+    ];
+
+    let timeline = build_order_timeline(7, &events, false);
+
+    assert_eq!(timeline.status, ORDER_STATUS_CLOSED);
+    let last = timeline.steps.last().unwrap();
+    assert_eq!(last.action, "full_close");
+    assert_eq!(last.position_asset_amount, 0);
+    // 30M + 150M - 100M 保证金 / margin
+    assert_eq!(last.realized_pnl_sol, 80_000_000);
+    assert_eq!(timeline.realized_pnl_sol, 80_000_000);
+}
+
+#[test]
+fn test_timeline_liquidated_loses_margin() {
+    let events = vec![open_long(7, 10)];
+
+    let timeline = build_order_timeline(7, &events, false);
+
+    assert_eq!(timeline.status, ORDER_STATUS_LIQUIDATED);
+    assert_eq!(timeline.steps.len(), 1);
+    assert_eq!(timeline.realized_pnl_sol, -(MARGIN as i64));
+}
+
+#[test]
+fn test_timeline_ignores_other_orders() {
+    let events = vec![open_long(7, 10), open_long(8, 11), full_close(8, 12, 1)];
+
+    let timeline = build_order_timeline(7, &events, true);
+
+    assert_eq!(timeline.status, ORDER_STATUS_OPEN);
+    assert_eq!(timeline.steps.len(), 1);
+    assert_eq!(timeline.steps[0].signature, "sig_open_7");
+}
+
+#[test]
+fn test_timeline_added_margin_is_settled_with_the_order() {
+    let events = vec![
+        open_long(7, 10),
+        add_margin(7, 20, 40_000_000),
+        full_close(7, 30, 150_000_000),
+    ];
+
+    let timeline = build_order_timeline(7, &events, false);
+
+    let actions: Vec<&str> = timeline.steps.iter().map(|s| s.action.as_str()).collect();
+    assert_eq!(actions, vec!["open", "add_margin", "full_close"]);
+    assert_eq!(timeline.steps[1].sol_amount, 40_000_000);
+    assert_eq!(timeline.steps[1].position_asset_amount, 5_000_000_000);
+    // 150M - (100M + 40M) 保证金 / margin
+    assert_eq!(timeline.realized_pnl_sol, 10_000_000);
+}
+
+#[test]
+fn test_timeline_extension_fees_count_as_realized_loss() {
+    let events = vec![
+        open_long(7, 10),
+        extend(7, 20, 2_000_000),
+        partial_close(7, 30, 30_000_000, 3_000_000_000),
+    ];
+
+    let timeline = build_order_timeline(7, &events, true);
+
+    let actions: Vec<&str> = timeline.steps.iter().map(|s| s.action.as_str()).collect();
+    assert_eq!(actions, vec!["open", "extend", "partial_close"]);
+    assert_eq!(timeline.steps[1].sol_amount, 2_000_000);
+    assert_eq!(timeline.steps[1].realized_pnl_sol, -2_000_000);
+    assert_eq!(timeline.realized_pnl_sol, 28_000_000);
+
+    // 清算后保证金也计入亏损 / After liquidation the margin is lost as well
+    let liquidated = build_order_timeline(7, &events, false);
+    assert_eq!(liquidated.realized_pnl_sol, 28_000_000 - MARGIN as i64);
+}
@@ -251,3 +251,69 @@ fn test_get_order_by_id() {
 
     cleanup_test_db(&temp_path);
 }
+
+/// 辅助函数: 按给定方向构造一个活跃索引顺序与价格顺序不同的订单簿, 返回 (manager, 路径)
+/// Helper: build a book of the given direction whose active-indices order differs from price order
+fn build_scrambled_book(direction: Direction) -> (OrderBookDBManager, String) {
+    let (db, temp_path) = create_test_db();
+    let mint = "EPjFWaLb3crLvQQf89kiNqEX5jg5Kv431J06Y1AD3ic".to_string();
+    let manager = OrderBookDBManager::new(db, mint, direction);
+    manager.initialize("9B5X6wrjJVcXHnbPfZ8wP4k5m9n2q1r7t3u2v5w8x1y".to_string()).unwrap();
+
+    // 价格按链表方向排列: 做多 head 最高, 做空 head 最低
+    // Prices follow the list direction: highest at head for long, lowest at head for short
+    let price = |rank: u128| match direction {
+        Direction::Dn => (10 - rank) * 1000000,
+        Direction::Up => (rank + 1) * 1000000,
+    };
+    let order = |id: u64, rank: u128| {
+        let mut order = create_test_order(&format!("User{}", id), price(rank));
+        order.order_id = id;
+        order
+    };
+
+    // 链表: rank 0..=4; 索引顺序: 2, 4, 0, 3, 1 / List: rank 0..=4; index order: 2, 4, 0, 3, 1
+    manager.insert_after(u16::MAX, &order(1, 2)).unwrap(); // idx 0
+    manager.insert_after(0, &order(2, 4)).unwrap(); // idx 1
+    manager.insert_before(0, &order(3, 0)).unwrap(); // idx 2, 新 head / new head
+    manager.insert_after(0, &order(4, 3)).unwrap(); // idx 3
+    manager.insert_after(2, &order(5, 1)).unwrap(); // idx 4
+
+    // 删除后末尾订单被移入空位, 索引顺序进一步打乱 / Removal moves the last order into the hole, scrambling indices further
+    manager.batch_remove_by_indices_unsafe(&[0], 1, price(2)).unwrap();
+
+    (manager, temp_path)
+}
+
+#[test]
+fn test_get_all_active_orders_sorted_is_price_monotonic() {
+    for direction in [Direction::Dn, Direction::Up] {
+        let (manager, temp_path) = build_scrambled_book(direction);
+
+        let sorted = manager.get_all_active_orders_sorted().unwrap();
+        let unsorted = manager.get_all_active_orders().unwrap();
+        assert_eq!(sorted.len(), 4);
+        assert_eq!(unsorted.len(), 4);
+
+        let prices: Vec<u128> = sorted.iter().map(|(_, o)| o.lock_lp_start_price).collect();
+        let monotonic = match direction {
+            Direction::Dn => prices.windows(2).all(|w| w[0] > w[1]),
+            Direction::Up => prices.windows(2).all(|w| w[0] < w[1]),
+        };
+        assert!(monotonic, "{} prices not in list order: {:?}", direction, prices);
+
+        // 两个版本包含相同的订单, 只是顺序不同 / Both variants hold the same orders, only the order differs
+        let mut sorted_ids: Vec<u64> = sorted.iter().map(|(_, o)| o.order_id).collect();
+        let mut unsorted_ids: Vec<u64> = unsorted.iter().map(|(_, o)| o.order_id).collect();
+        sorted_ids.sort_unstable();
+        unsorted_ids.sort_unstable();
+        assert_eq!(sorted_ids, unsorted_ids);
+
+        // 返回的索引与 get_order 一致 / Returned indices agree with get_order
+        for (index, order) in &sorted {
+            assert_eq!(manager.get_order(*index).unwrap().order_id, order.order_id);
+        }
+
+        cleanup_test_db(&temp_path);
+    }
+}
@@ -3,6 +3,7 @@
 
 use super::*;
 use crate::orderbook::MarginOrderUpdateData;
+use crate::util::curve::CurveAMM;
 
 #[test]
 fn test_update_single_field() {
@@ -164,3 +165,139 @@ fn test_multiple_updates_increment_version() {
 
     cleanup_test_db(&temp_path);
 }
+
+#[test]
+fn test_take_profit_price_defaults_for_stored_orders() {
+    // 加字段前存储的订单没有 take_profit_price / Orders stored before the field existed lack take_profit_price
+    let mut order = create_test_order("OldUser", 1_000_000);
+    order.take_profit_price = 2_000_000;
+    let mut value = serde_json::to_value(&order).unwrap();
+    assert_eq!(value["take_profit_price"], "2000000");
+    value.as_object_mut().unwrap().remove("take_profit_price");
+
+    let legacy = MarginOrder::from_bytes(&serde_json::to_vec(&value).unwrap()).unwrap();
+    assert_eq!(legacy.take_profit_price, 0);
+    assert_eq!(legacy.open_price, order.open_price);
+}
+
+/// 插入两个相邻订单 (价格以初始价格的百分比表示), 返回后一个订单的 (index, order_id)
+/// Insert two adjacent orders (prices as percentages of the initial price), returning the second order's (index, order_id)
+fn insert_adjacent_pair(manager: &OrderBookDBManager, percents: [u128; 4]) -> (u16, u64) {
+    let p = CurveAMM::get_initial_price().unwrap();
+    let mut first = create_test_order("UserA", p * percents[0] / 100);
+    first.lock_lp_end_price = p * percents[1] / 100;
+    let mut second = create_test_order("UserB", p * percents[2] / 100);
+    second.lock_lp_end_price = p * percents[3] / 100;
+
+    let (first_index, _) = manager.insert_after(u16::MAX, &first).unwrap();
+    manager.insert_after(first_index, &second).unwrap()
+}
+
+#[test]
+fn test_partial_close_recalculates_prev_liquidity_long() {
+    let (manager, temp_path) = create_test_manager();
+    manager.initialize("authority".to_string()).unwrap();
+    let p = CurveAMM::get_initial_price().unwrap();
+
+    // 做多订单簿价格向下 / Long book prices go down
+    let (index, order_id) = insert_adjacent_pair(&manager, [100, 90, 80, 70]);
+
+    // 部分平仓把起点下移到平仓后的价格 / A partial close moves the start down to the post-close price
+    let new_start = p * 75 / 100;
+    let update = MarginOrderUpdateData {
+        lock_lp_start_price: Some(new_start),
+        ..Default::default()
+    };
+    manager.update_order_with_neighbor_recalc(index, order_id, &update).unwrap();
+
+    // 链上 close_long_trade 对同一区间 sell_from_price_to_price(p*90%, p*75%) 的结果
+    // What the program's close_long_trade yields for the same gap, sell_from_price_to_price(p*90%, p*75%)
+    let prev = manager.get_order(0).unwrap();
+    assert_eq!(prev.next_lp_sol_amount, 2_479_736_828);
+    assert_eq!(prev.next_lp_token_amount, 107_952_367_894_047);
+    assert_eq!(manager.get_order(index).unwrap().lock_lp_start_price, new_start);
+
+    cleanup_test_db(&temp_path);
+}
+
+#[test]
+fn test_partial_close_recalculates_prev_liquidity_short() {
+    let (db, temp_path) = create_test_db();
+    let manager = OrderBookDBManager::new(db, "ShortMint".to_string(), Direction::Up);
+    manager.initialize("authority".to_string()).unwrap();
+    let p = CurveAMM::get_initial_price().unwrap();
+
+    // 做空订单簿价格向上 / Short book prices go up
+    let (index, order_id) = insert_adjacent_pair(&manager, [100, 110, 120, 130]);
+
+    let new_start = p * 125 / 100;
+    let update = MarginOrderUpdateData {
+        lock_lp_start_price: Some(new_start),
+        ..Default::default()
+    };
+    manager.update_order_with_neighbor_recalc(index, order_id, &update).unwrap();
+
+    // 链上 close_short_trade 对同一区间 buy_from_price_to_price(p*110%, p*125%) 的结果
+    // What the program's close_short_trade yields for the same gap, buy_from_price_to_price(p*110%, p*125%)
+    let prev = manager.get_order(0).unwrap();
+    assert_eq!(prev.next_lp_sol_amount, 2_076_754_217);
+    assert_eq!(prev.next_lp_token_amount, 63_344_982_317_611);
+
+    cleanup_test_db(&temp_path);
+}
+
+#[test]
+fn test_neighbor_recalc_skips_head_and_unchanged_range() {
+    let (manager, temp_path) = create_test_manager();
+    manager.initialize("authority".to_string()).unwrap();
+    let p = CurveAMM::get_initial_price().unwrap();
+    let (index, order_id) = insert_adjacent_pair(&manager, [100, 90, 80, 70]);
+    let head = manager.get_order(0).unwrap();
+
+    // 不改起点价格时前节点不变 / The predecessor is untouched when the start price doesn't change
+    let update = MarginOrderUpdateData {
+        margin_sol_amount: Some(1),
+        ..Default::default()
+    };
+    manager.update_order_with_neighbor_recalc(index, order_id, &update).unwrap();
+    assert_eq!(manager.get_order(0).unwrap().version, head.version);
+
+    // 头部订单没有前节点 / The head has no predecessor
+    let update = MarginOrderUpdateData {
+        lock_lp_start_price: Some(p * 95 / 100),
+        ..Default::default()
+    };
+    manager.update_order_with_neighbor_recalc(0, head.order_id, &update).unwrap();
+    assert_eq!(manager.get_order(0).unwrap().next_lp_sol_amount, head.next_lp_sol_amount);
+
+    cleanup_test_db(&temp_path);
+}
+
+#[test]
+fn test_add_margin_recalculates_own_liquidity_long() {
+    let (manager, temp_path) = create_test_manager();
+    manager.initialize("authority".to_string()).unwrap();
+    let p = CurveAMM::get_initial_price().unwrap();
+    let (index, _) = insert_adjacent_pair(&manager, [100, 90, 80, 70]);
+    let head = manager.get_order(0).unwrap();
+    let tail = manager.get_order(index).unwrap();
+
+    // 追加保证金把头部区间下移, 终点靠近下一个订单 / Adding margin shifts the head's range down, its end nearing the next order
+    let update = MarginOrderUpdateData {
+        lock_lp_start_price: Some(p * 95 / 100),
+        lock_lp_end_price: Some(p * 85 / 100),
+        margin_sol_amount: Some(head.margin_sol_amount + 50_000_000),
+        ..Default::default()
+    };
+    manager.update_order_with_neighbor_recalc(0, head.order_id, &update).unwrap();
+
+    // 链上 add_margin 对同一区间 sell_from_price_to_price(p*85%, p*80%) 的结果
+    // What the program's add_margin yields for the same gap, sell_from_price_to_price(p*85%, p*80%)
+    let head = manager.get_order(0).unwrap();
+    assert_eq!(head.next_lp_sol_amount, 825_817_642);
+    assert_eq!(head.next_lp_token_amount, 35_818_563_731_547);
+    // 尾部订单之后的区间不受影响 / The gap after the tail is untouched
+    assert_eq!(manager.get_order(index).unwrap().version, tail.version);
+
+    cleanup_test_db(&temp_path);
+}
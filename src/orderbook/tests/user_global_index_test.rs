@@ -0,0 +1,77 @@
+// 用户全局持仓索引测试
+// User Global Position Index Tests
+
+use super::*;
+use crate::orderbook::UserOrderQueryService;
+
+#[test]
+fn test_global_index_spans_mints_and_directions() {
+    let (db, temp_path) = create_test_db();
+    let authority = "9B5X6wrjJVcXHnbPfZ8wP4k5m9n2q1r7t3u2v5w8x1y".to_string();
+
+    let mint_a = "MintAaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa".to_string();
+    let mint_b = "MintBbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb".to_string();
+    let long_a = OrderBookDBManager::new(db.clone(), mint_a.clone(), Direction::Dn);
+    let short_b = OrderBookDBManager::new(db.clone(), mint_b.clone(), Direction::Up);
+    long_a.initialize(authority.clone()).unwrap();
+    short_b.initialize(authority).unwrap();
+
+    // Alice 在两个市场各有一个订单, Bob 只有一个
+    long_a.insert_after(u16::MAX, &create_test_order_with_id("Alice", 1, 1000000, 1100000)).unwrap();
+    long_a.insert_after(0, &create_test_order_with_id("Bob", 2, 2000000, 2100000)).unwrap();
+    short_b.insert_after(u16::MAX, &create_test_order_with_id("Alice", 3, 3000000, 3100000)).unwrap();
+
+    let service = UserOrderQueryService::new(db.clone());
+    let (total, positions) = service.query_user_global_positions("Alice", 1, 10).unwrap();
+    assert_eq!(total, 2);
+    assert_eq!(positions.len(), 2);
+    assert!(positions.iter().any(|(m, d, _, o)| *m == mint_a && d == "dn" && o.user == "Alice"));
+    assert!(positions.iter().any(|(m, d, _, o)| *m == mint_b && d == "up" && o.user == "Alice"));
+
+    // 分页 / Pagination
+    let (total, page2) = service.query_user_global_positions("Alice", 2, 1).unwrap();
+    assert_eq!(total, 2);
+    assert_eq!(page2.len(), 1);
+
+    // 超大页码不溢出 / Huge page numbers do not overflow
+    let (total, beyond) = service.query_user_global_positions("Alice", u32::MAX, u32::MAX).unwrap();
+    assert_eq!(total, 2);
+    assert!(beyond.is_empty());
+
+    let (total, _) = service.query_user_global_positions("Bob", 1, 10).unwrap();
+    assert_eq!(total, 1);
+
+    cleanup_test_db(&temp_path);
+}
+
+#[test]
+fn test_global_index_removed_on_delete() {
+    let (db, temp_path) = create_test_db();
+    let manager = OrderBookDBManager::new(
+        db.clone(),
+        "MintAaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa".to_string(),
+        Direction::Dn,
+    );
+    manager
+        .initialize("9B5X6wrjJVcXHnbPfZ8wP4k5m9n2q1r7t3u2v5w8x1y".to_string())
+        .unwrap();
+
+    manager.insert_after(u16::MAX, &create_test_order_with_id("Alice", 4, 1000000, 1100000)).unwrap();
+    manager.insert_after(0, &create_test_order_with_id("Bob", 5, 2000000, 2100000)).unwrap();
+
+    // 删除 Alice 的订单后, Bob 的订单被移动到 index 0
+    // After deleting Alice's order, Bob's order moves to index 0
+    manager.batch_remove_by_indices_unsafe(&[0], 1, 1500000).unwrap();
+
+    let service = UserOrderQueryService::new(db.clone());
+    let (total, positions) = service.query_user_global_positions("Alice", 1, 10).unwrap();
+    assert_eq!(total, 0);
+    assert!(positions.is_empty());
+
+    let (total, positions) = service.query_user_global_positions("Bob", 1, 10).unwrap();
+    assert_eq!(total, 1);
+    assert_eq!(positions[0].2, 0);
+    assert_eq!(positions[0].3.user, "Bob");
+
+    cleanup_test_db(&temp_path);
+}
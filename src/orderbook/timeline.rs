@@ -0,0 +1,223 @@
+// 订单生命周期时间线 - 按 order_id 汇总开仓/追加保证金/延期/部分平仓/全平仓事件
+// Order Lifecycle Timeline - Aggregates open / add margin / extend / partial close / full close events by order_id
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::solana::events::PinpetEvent;
+
+/// 订单仍在订单簿中 / Order is still in the book
+pub const ORDER_STATUS_OPEN: &str = "open";
+/// 存在全平仓事件 / A full close event exists
+pub const ORDER_STATUS_CLOSED: &str = "closed";
+/// 无全平仓事件但已不在订单簿中 / No full close event but no longer in the book
+pub const ORDER_STATUS_LIQUIDATED: &str = "liquidated";
+
+/// 时间线中的一步 / A single step in the timeline
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct OrderTimelineStep {
+    /// 动作: open / add_margin / extend / partial_close / full_close / Action
+    #[schema(example = "partial_close")]
+    pub action: String,
+
+    /// 交易签名 / Transaction signature
+    pub signature: String,
+
+    /// 区块高度 / Slot
+    pub slot: u64,
+
+    /// 事件时间戳(毫秒) / Event timestamp (ms)
+    pub timestamp: i64,
+
+    /// 事件发生时的最新价格 (u128 字符串) / Latest price at the event (u128 as string)
+    pub price: String,
+
+    /// 本步 token 数量: 开仓为持仓量, 平仓为成交量
+    /// Token amount of this step: position size on open, traded amount on close
+    pub token_amount: u64,
+
+    /// 本步 SOL 数量: 开仓为保证金, 追加保证金为追加额, 延期为手续费, 平仓为成交额
+    /// SOL amount of this step: margin on open, added margin on add_margin, the fee on extend, traded SOL on close
+    pub sol_amount: u64,
+
+    /// 本步之后剩余的持仓量 / Position remaining after this step
+    pub position_asset_amount: u64,
+
+    /// 本步支付给用户的 SOL / SOL paid to the user in this step
+    pub close_profit_sol: u64,
+
+    /// 截至本步的已实现盈亏(SOL, 带符号) / Realized PnL up to this step (SOL, signed)
+    pub realized_pnl_sol: i64,
+}
+
+/// 订单生命周期时间线 / Order lifecycle timeline
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct OrderTimeline {
+    /// 订单 ID / Order ID
+    pub order_id: u64,
+
+    /// 开仓用户(无事件时为空) / Order owner (None without events)
+    pub user: Option<String>,
+
+    /// 最终状态: open / closed / liquidated / Final status
+    #[schema(example = "open")]
+    pub status: String,
+
+    /// 按时间排序的状态变化 / Chronological state changes
+    pub steps: Vec<OrderTimelineStep>,
+
+    /// 最终已实现盈亏(SOL, 带符号) / Final realized PnL (SOL, signed)
+    pub realized_pnl_sol: i64,
+}
+
+/// 根据 order_id 的事件构建时间线
+/// Build the timeline from an order's events
+///
+/// 部分平仓支付的 SOL 计为已实现盈利 (与链上 realized_sol_amount 累加一致);
+/// 延期手续费计为已实现亏损; 全平仓或被清算时保证金结清, 已实现盈亏 = 累计支付 - 延期手续费 - 保证金 (含追加的保证金)
+/// SOL paid on partial closes counts as realized profit (matching the on-chain realized_sol_amount accumulation) and
+/// extension fees as realized loss; once fully closed or liquidated the margin is settled,
+/// realized PnL = total paid - extension fees - margin (including added margin)
+///
+/// # 参数 / Parameters
+/// * `order_id` - 订单 ID / Order ID
+/// * `events` - 该订单的事件, 按 slot 升序; 其他订单的事件会被忽略
+///   The order's events, ascending by slot; events of other orders are ignored
+/// * `in_book` - 订单当前是否仍在订单簿中 / Whether the order is currently still in the book
+pub fn build_order_timeline(order_id: u64, events: &[PinpetEvent], in_book: bool) -> OrderTimeline {
+    let mut steps = Vec::new();
+    let mut user = None;
+    let mut margin: u64 = 0;
+    let mut total_paid: u64 = 0;
+    let mut fees: u64 = 0;
+    let mut position: u64 = 0;
+    let mut fully_closed = false;
+
+    for event in events {
+        match event {
+            PinpetEvent::LongShort(e) if e.order_id == order_id => {
+                user = Some(e.payer.clone());
+                margin = e.margin_sol_amount;
+                position = e.position_asset_amount;
+                steps.push(OrderTimelineStep {
+                    action: "open".to_string(),
+                    signature: e.signature.clone(),
+                    slot: e.slot,
+                    timestamp: e.timestamp.timestamp_millis(),
+                    price: e.latest_price.to_string(),
+                    token_amount: e.position_asset_amount,
+                    sol_amount: e.margin_sol_amount,
+                    position_asset_amount: e.position_asset_amount,
+                    close_profit_sol: 0,
+                    realized_pnl_sol: 0,
+                });
+            }
+            PinpetEvent::PartialClose(e) if e.order_id == order_id => {
+                user.get_or_insert_with(|| e.user.clone());
+                // 部分平仓不改变保证金, 开仓事件缺失时以此为准
+                // Partial closes keep the margin, use it when the open event is missing
+                margin = e.margin_sol_amount;
+                position = e.position_asset_amount;
+                total_paid = total_paid.saturating_add(e.user_close_profit);
+                steps.push(OrderTimelineStep {
+                    action: "partial_close".to_string(),
+                    signature: e.signature.clone(),
+                    slot: e.slot,
+                    timestamp: e.timestamp.timestamp_millis(),
+                    price: e.latest_price.to_string(),
+                    token_amount: e.final_token_amount,
+                    sol_amount: e.final_sol_amount,
+                    position_asset_amount: e.position_asset_amount,
+                    close_profit_sol: e.user_close_profit,
+                    realized_pnl_sol: open_pnl(total_paid, fees),
+                });
+            }
+            PinpetEvent::AddMargin(e) if e.order_id == order_id => {
+                user.get_or_insert_with(|| e.payer.clone());
+                margin = e.margin_sol_amount;
+                steps.push(OrderTimelineStep {
+                    action: "add_margin".to_string(),
+                    signature: e.signature.clone(),
+                    slot: e.slot,
+                    timestamp: e.timestamp.timestamp_millis(),
+                    price: e.latest_price.to_string(),
+                    token_amount: 0,
+                    sol_amount: e.add_sol_amount,
+                    position_asset_amount: position,
+                    close_profit_sol: 0,
+                    realized_pnl_sol: open_pnl(total_paid, fees),
+                });
+            }
+            PinpetEvent::ExtendOrder(e) if e.order_id == order_id => {
+                user.get_or_insert_with(|| e.payer.clone());
+                fees = fees.saturating_add(e.fee_sol_amount);
+                steps.push(OrderTimelineStep {
+                    action: "extend".to_string(),
+                    signature: e.signature.clone(),
+                    slot: e.slot,
+                    timestamp: e.timestamp.timestamp_millis(),
+                    price: e.latest_price.to_string(),
+                    token_amount: 0,
+                    sol_amount: e.fee_sol_amount,
+                    position_asset_amount: position,
+                    close_profit_sol: 0,
+                    realized_pnl_sol: open_pnl(total_paid, fees),
+                });
+            }
+            PinpetEvent::FullClose(e) if e.order_id == order_id => {
+                user.get_or_insert_with(|| e.user_sol_account.clone());
+                total_paid = total_paid.saturating_add(e.user_close_profit);
+                fully_closed = true;
+                steps.push(OrderTimelineStep {
+                    action: "full_close".to_string(),
+                    signature: e.signature.clone(),
+                    slot: e.slot,
+                    timestamp: e.timestamp.timestamp_millis(),
+                    price: e.latest_price.to_string(),
+                    token_amount: e.final_token_amount,
+                    sol_amount: e.final_sol_amount,
+                    position_asset_amount: 0,
+                    close_profit_sol: e.user_close_profit,
+                    realized_pnl_sol: settled_pnl(total_paid, fees, margin),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    let status = if fully_closed {
+        ORDER_STATUS_CLOSED
+    } else if in_book {
+        ORDER_STATUS_OPEN
+    } else {
+        ORDER_STATUS_LIQUIDATED
+    };
+
+    let realized_pnl_sol = if status == ORDER_STATUS_OPEN {
+        open_pnl(total_paid, fees)
+    } else {
+        settled_pnl(total_paid, fees, margin)
+    };
+
+    OrderTimeline {
+        order_id,
+        user,
+        status: status.to_string(),
+        steps,
+        realized_pnl_sol,
+    }
+}
+
+/// 保证金结清前的盈亏 / PnL before the margin is settled
+fn open_pnl(total_paid: u64, fees: u64) -> i64 {
+    clamp_i64(total_paid as i128 - fees as i128)
+}
+
+/// 保证金结清后的盈亏 / PnL after the margin is settled
+fn settled_pnl(total_paid: u64, fees: u64, margin: u64) -> i64 {
+    clamp_i64(total_paid as i128 - fees as i128 - margin as i128)
+}
+
+fn clamp_i64(value: i128) -> i64 {
+    value.clamp(i64::MIN as i128, i64::MAX as i128) as i64
+}
@@ -3,8 +3,72 @@
 
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, DisplayFromStr};
+use std::fmt;
+use std::str::FromStr;
 use utoipa::ToSchema;
 
+use super::errors::OrderBookError;
+use crate::util::time::unix_now_u32;
+
+/// 订单簿方向, 同时决定存储键的命名空间
+/// Order book direction, which also determines the storage key namespace
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+pub enum Direction {
+    /// 做多订单簿 (order_type=1), 价格从 head 向 tail 递减
+    /// Long order book (order_type=1), prices descend from head to tail
+    #[serde(rename = "dn")]
+    Dn,
+    /// 做空订单簿 (order_type=2), 价格从 head 向 tail 递增
+    /// Short order book (order_type=2), prices ascend from head to tail
+    #[serde(rename = "up")]
+    Up,
+}
+
+impl Direction {
+    /// 存储键和接口中使用的字符串 / String used in storage keys and the API
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Direction::Dn => "dn",
+            Direction::Up => "up",
+        }
+    }
+
+    /// 对应的链上 order_type (1=做多, 2=做空) / Matching on-chain order_type (1=long, 2=short)
+    pub fn order_type(&self) -> u8 {
+        match self {
+            Direction::Dn => 1,
+            Direction::Up => 2,
+        }
+    }
+
+    /// 由链上 order_type 得到方向 / Direction from an on-chain order_type
+    pub fn from_order_type(order_type: u8) -> Option<Self> {
+        match order_type {
+            1 => Some(Direction::Dn),
+            2 => Some(Direction::Up),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Direction {
+    type Err = OrderBookError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dn" => Ok(Direction::Dn),
+            "up" => Ok(Direction::Up),
+            other => Err(OrderBookError::InvalidDirection(other.to_string())),
+        }
+    }
+}
+
 /// OrderBook 头部元数据
 /// OrderBook header metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -62,7 +126,7 @@ impl OrderBookHeader {
     /// 创建新的 OrderBook header
     /// Create new OrderBook header
     pub fn new(order_type: u8, authority: String) -> Self {
-        let now = chrono::Utc::now().timestamp() as u32;
+        let now = unix_now_u32();
         Self {
             version: Self::CURRENT_VERSION,
             order_type,
@@ -88,6 +152,51 @@ impl OrderBookHeader {
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, serde_json::Error> {
         serde_json::from_slice(bytes)
     }
+
+    /// 订单数已达 MAX_CAPACITY, 不能再插入新订单
+    /// The order count has reached MAX_CAPACITY, no new orders can be inserted
+    pub fn at_capacity(&self) -> bool {
+        self.total as u32 >= Self::MAX_CAPACITY
+    }
+
+    /// header 自身字段之间的矛盾, 为空表示一致
+    /// Contradictions between the header's own fields, empty when consistent
+    ///
+    /// 检查 `total <= total_capacity`, head/tail 为 u16::MAX 或 `< total`, 且空链表与 total == 0 同时成立
+    /// Checks `total <= total_capacity`, head/tail are u16::MAX or `< total`, and an empty list goes with total == 0
+    pub fn consistency_problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.total as u32 > self.total_capacity {
+            problems.push(format!("total {} > total_capacity {}", self.total, self.total_capacity));
+        }
+        for (name, index) in [("head", self.head), ("tail", self.tail)] {
+            if index != u16::MAX && index >= self.total {
+                problems.push(format!("{} {} >= total {}", name, index, self.total));
+            }
+        }
+        let empty_list = self.head == u16::MAX || self.tail == u16::MAX;
+        if empty_list != (self.total == 0) || (self.head == u16::MAX) != (self.tail == u16::MAX) {
+            problems.push(format!(
+                "head {} / tail {} disagree with total {}",
+                self.head, self.tail, self.total
+            ));
+        }
+        problems
+    }
+}
+
+/// 订单簿 header 与已存储槽位的一致性检查结果
+/// Consistency check of an order book header against its stored slots
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct OrderBookConsistency {
+    /// 实际存储的槽位数 / Number of slots actually stored
+    pub stored_slots: u32,
+
+    /// 是否一致 / Whether the header and slots agree
+    pub consistent: bool,
+
+    /// 发现的问题 / Problems found
+    pub problems: Vec<String>,
 }
 
 /// 保证金订单槽位结构
@@ -119,6 +228,13 @@ pub struct MarginOrder {
     #[schema(value_type = String)]
     pub open_price: u128,
 
+    /// 止盈价格 (Q64.64 格式, 0 表示未设置; 旧版本存储的订单没有该字段, 读取时默认为 0)
+    /// Take-profit price (Q64.64 format, 0 means not set; orders stored before this field existed default to 0)
+    #[serde_as(as = "DisplayFromStr")]
+    #[serde(default)]
+    #[schema(value_type = String)]
+    pub take_profit_price: u128,
+
     // ========== 8-byte 对齐字段 (u64) ==========
     /// 订单唯一标识符(全局递增,由 OrderBook.order_id_counter 分配)
     /// Order unique identifier (globally increasing, assigned by OrderBook.order_id_counter)
@@ -167,8 +283,8 @@ pub struct MarginOrder {
     /// Order version number (incremented on each update)
     pub version: u32,
 
-    /// 订单开始时间戳 (Unix timestamp, 秒)
-    /// Order start timestamp (Unix timestamp, seconds)
+    /// 订单开始时间戳 (Unix timestamp, 秒; u32 在 2106 年溢出, 见 util::time)
+    /// Order start timestamp (Unix timestamp, seconds; u32 overflows in 2106, see util::time)
     pub start_time: u32,
 
     /// 贷款到期时间戳 (Unix timestamp, 秒),到期后可被任何用户平仓
@@ -198,17 +314,35 @@ pub struct MarginOrder {
     pub order_type: u8,
 }
 
+/// MarginOrder 存储布局版本, 字段变化时递增并在 from_bytes 中保留旧版本的读取
+/// MarginOrder storage layout version; bump it when fields change and keep reading older versions in from_bytes
+///
+/// - 0: 无版本前缀的 JSON (最早的存储格式) / Unprefixed JSON (the original stored format)
+/// - 1: 版本字节 + JSON / Version byte + JSON
+pub const MARGIN_ORDER_LAYOUT_VERSION: u8 = 1;
+
 impl MarginOrder {
-    /// 序列化为字节
-    /// Serialize to bytes
+    /// 序列化为字节: 布局版本字节 + JSON
+    /// Serialize to bytes: layout version byte + JSON
     pub fn to_bytes(&self) -> Result<Vec<u8>, serde_json::Error> {
-        serde_json::to_vec(self)
+        let mut bytes = vec![MARGIN_ORDER_LAYOUT_VERSION];
+        serde_json::to_writer(&mut bytes, self)?;
+        Ok(bytes)
     }
 
-    /// 从字节反序列化
-    /// Deserialize from bytes
+    /// 从字节反序列化, 兼容所有已知布局版本
+    /// Deserialize from bytes, accepting every known layout version
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, serde_json::Error> {
-        serde_json::from_slice(bytes)
+        match bytes.first() {
+            // 版本 0: JSON 对象直接以 '{' 开头 / Version 0: the JSON object starts directly with '{'
+            Some(b'{') => serde_json::from_slice(bytes),
+            Some(&MARGIN_ORDER_LAYOUT_VERSION) => serde_json::from_slice(&bytes[1..]),
+            Some(version) => Err(serde::de::Error::custom(format!(
+                "unknown MarginOrder layout version: {}",
+                version
+            ))),
+            None => Err(serde::de::Error::custom("empty MarginOrder bytes")),
+        }
     }
 }
 
@@ -251,6 +385,30 @@ pub struct TraversalResult {
     pub done: bool,
 }
 
+/// 深度图中的一个价格区间 / One price bucket of the depth profile
+#[serde_as]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct DepthBucket {
+    /// 区间下界(含, u128 字符串) / Bucket lower bound (inclusive, u128 as string)
+    #[serde_as(as = "DisplayFromStr")]
+    #[schema(value_type = String)]
+    pub price_low: u128,
+
+    /// 区间上界(含, u128 字符串) / Bucket upper bound (inclusive, u128 as string)
+    #[serde_as(as = "DisplayFromStr")]
+    #[schema(value_type = String)]
+    pub price_high: u128,
+
+    /// 区间内订单锁定的 SOL 合计 / Total SOL locked by orders in the bucket
+    pub sol: u64,
+
+    /// 区间内订单锁定的 Token 合计 / Total tokens locked by orders in the bucket
+    pub token: u64,
+
+    /// 区间内订单数 / Number of orders in the bucket
+    pub orders: u32,
+}
+
 // ==================== 已关闭订单相关数据结构 / Closed Order Related Structures ====================
 
 /// 已关闭订单快照 - 完整数据
@@ -1,7 +1,7 @@
 // OrderBook 用户查询服务
 // OrderBook User Query Service
 
-use crate::orderbook::{MarginOrder, Result, OrderBookError};
+use crate::orderbook::{MarginOrder, OrderBookDBManager, Result, OrderBookError};
 use rocksdb::{DB, IteratorMode};
 use std::sync::Arc;
 use tracing::warn;
@@ -85,7 +85,7 @@ impl UserOrderQueryService {
 
         // 3. 分页
         // 3. Pagination
-        let skip = ((page - 1) * page_size) as usize;
+        let skip = (page.saturating_sub(1) as usize).saturating_mul(page_size as usize);
         let take = page_size as usize;
         let page_keys: Vec<_> = all_keys.into_iter().skip(skip).take(take).collect();
 
@@ -148,4 +148,131 @@ impl UserOrderQueryService {
 
         Ok((total, orders))
     }
+
+    /// 按开仓价模糊查找用户订单, 供丢失 order_id 的客户端找回订单
+    /// Find a user's orders by approximate open price, for clients that lost their order_id
+    ///
+    /// 只遍历用户在该 mint/方向下的订单索引, 不扫描整个订单簿
+    /// Only walks the user's index for this mint/direction, never the whole book
+    ///
+    /// # 返回值 / Returns
+    /// `|open_price - target| <= tolerance` 的订单 (index, order), 按价差升序, 价差相同时按 order_id 升序
+    /// Orders (index, order) with `|open_price - target| <= tolerance`, closest first, ties by order_id
+    pub fn find_user_orders_by_open_price(
+        &self,
+        user: &str,
+        mint: &str,
+        direction: &str,
+        open_price: u128,
+        tolerance: u128,
+    ) -> Result<Vec<(u16, MarginOrder)>> {
+        let (_, orders) = self.query_user_active_orders(user, Some(mint), Some(direction), 1, u32::MAX)?;
+
+        let mut matches: Vec<(u16, MarginOrder)> = orders
+            .into_iter()
+            .filter(|(_, _, _, order)| order.open_price.abs_diff(open_price) <= tolerance)
+            .map(|(_, _, index, order)| (index, order))
+            .collect();
+        matches.sort_by_key(|(_, order)| (order.open_price.abs_diff(open_price), order.order_id));
+
+        Ok(matches)
+    }
+
+    /// 查询用户在所有 mint 上的持仓(基于全局持仓索引)
+    /// Query user's positions across all mints (based on the global position index)
+    ///
+    /// # 参数 / Parameters
+    /// * `user` - 用户地址
+    /// * `page` - 页码 (从 1 开始)
+    /// * `page_size` - 每页数量
+    ///
+    /// # 返回值 / Returns
+    /// (总数, 订单列表: (mint, direction, index, order))
+    /// (total count, order list: (mint, direction, index, order))
+    pub fn query_user_global_positions(
+        &self,
+        user: &str,
+        page: u32,
+        page_size: u32,
+    ) -> Result<(u32, Vec<(String, String, u16, MarginOrder)>)> {
+        // ⭐ 在同一快照上完成扫描与读取 / Scan and read on the same snapshot
+        let snapshot = self.db.snapshot();
+        let prefix = OrderBookDBManager::user_global_prefix(user);
+
+        // 1. 前缀扫描: user_global_orders:{user}:
+        // 1. Prefix scan: user_global_orders:{user}:
+        let mut all_keys = Vec::new();
+        let iter = snapshot.iterator(IteratorMode::From(
+            prefix.as_bytes(),
+            rocksdb::Direction::Forward,
+        ));
+
+        for item in iter {
+            let (key, _value) = item?;
+            let key_str = String::from_utf8_lossy(&key).to_string();
+
+            if !key_str.starts_with(&prefix) {
+                break;
+            }
+
+            all_keys.push(key_str);
+        }
+
+        let total = all_keys.len() as u32;
+
+        // 2. 分页
+        // 2. Pagination
+        let skip = (page.saturating_sub(1) as usize).saturating_mul(page_size as usize);
+        let take = page_size as usize;
+        let page_keys: Vec<_> = all_keys.into_iter().skip(skip).take(take).collect();
+
+        // 3. 解析键并在快照上查询订单数据
+        // 3. Parse keys and query order data on snapshot
+        let mut orders = Vec::new();
+        for key in page_keys {
+            // 解析键: user_global_orders:{user}:{mint}:{direction}:{order_id}
+            // Parse key: user_global_orders:{user}:{mint}:{direction}:{order_id}
+            let parts: Vec<&str> = key.split(':').collect();
+            if parts.len() != 5 {
+                continue; // 跳过格式错误的键 / Skip malformed keys
+            }
+
+            let mint = parts[2];
+            let direction = parts[3];
+            let order_id_str = parts[4];
+
+            let order_id: u64 = order_id_str.parse().map_err(|_| {
+                OrderBookError::InvalidAccountData(format!("Invalid order_id: {}", order_id_str))
+            })?;
+
+            let id_key = format!("orderbook_id_map:{}:{}:{:010}", mint, direction, order_id);
+            let index: u16 = match snapshot.get(id_key.as_bytes())? {
+                Some(bytes) => serde_json::from_slice(&bytes)?,
+                None => {
+                    warn!(
+                        "orderbook_id_map missing for order_id {} in snapshot (user={}, mint={}, direction={})",
+                        order_id, user, mint, direction
+                    );
+                    continue;
+                }
+            };
+
+            let slot_key = format!("orderbook_slot:{}:{}:{:05}", mint, direction, index);
+            let order_bytes = match snapshot.get(slot_key.as_bytes())? {
+                Some(bytes) => bytes,
+                None => {
+                    warn!(
+                        "orderbook_slot missing for index {} in snapshot (user={}, mint={}, direction={}, order_id={})",
+                        index, user, mint, direction, order_id
+                    );
+                    continue;
+                }
+            };
+            let order = MarginOrder::from_bytes(&order_bytes)?;
+
+            orders.push((mint.to_string(), direction.to_string(), index, order));
+        }
+
+        Ok((total, orders))
+    }
 }
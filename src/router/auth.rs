@@ -0,0 +1,86 @@
+// API-Key 鉴权中间件 / API-key authentication middleware
+//
+// 仅挂载在受保护的子路由上, 公共只读接口不受影响
+// Only attached to the protected sub-router, public read endpoints stay open
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+use tracing::warn;
+
+use crate::util::result::ApiError;
+
+/// API-Key 请求头 / API-key request header
+pub const API_KEY_HEADER: &str = "X-API-Key";
+
+/// 管理员密钥集合 / Admin key set
+#[derive(Debug, Clone)]
+pub struct AdminKeys {
+    keys: Arc<Vec<String>>,
+}
+
+impl AdminKeys {
+    /// 创建密钥集合, 忽略空字符串 / Create key set, ignoring empty strings
+    pub fn new(keys: &[String]) -> Self {
+        let keys = keys
+            .iter()
+            .map(|k| k.trim().to_string())
+            .filter(|k| !k.is_empty())
+            .collect();
+        Self {
+            keys: Arc::new(keys),
+        }
+    }
+
+    /// 是否未配置任何密钥 / Whether no key is configured
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// 校验密钥, 遍历全部密钥且逐字节常量时间比较
+    /// Verify key, scanning every configured key with constant-time byte comparison
+    pub fn verify(&self, candidate: &str) -> bool {
+        self.keys
+            .iter()
+            .fold(false, |matched, key| constant_time_eq(key.as_bytes(), candidate.as_bytes()) | matched)
+    }
+}
+
+/// 常量时间比较, 耗时只取决于长度 / Constant-time comparison, timing depends only on length
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b.iter()).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// 鉴权中间件 / Authentication middleware
+pub async fn require_api_key(
+    State(keys): State<AdminKeys>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let provided = request
+        .headers()
+        .get(API_KEY_HEADER)
+        .and_then(|v| v.to_str().ok());
+
+    match provided {
+        Some(key) if keys.verify(key) => next.run(request).await,
+        Some(_) => {
+            warn!(
+                "🔒 API-Key 无效 / Invalid API key: path={}",
+                request.uri().path()
+            );
+            unauthorized("API-Key 无效 / Invalid API key")
+        }
+        None => unauthorized("缺少 X-API-Key 请求头 / Missing X-API-Key header"),
+    }
+}
+
+fn unauthorized(msg: &str) -> Response {
+    ApiError::Unauthorized(msg.to_string()).into_response()
+}
@@ -0,0 +1,119 @@
+// 并发限制中间件 / Concurrency limiting middleware
+//
+// 全局信号量限制同时处理的请求数, 满载时立即返回 503 而不是排队, 避免流量突增时内存无限增长
+// A global semaphore caps requests handled at once; when saturated it answers 503 immediately instead of queueing,
+// so a traffic spike cannot grow memory without bound
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tracing::warn;
+
+use crate::util::result::ApiError;
+
+/// 全局并发限制器 / Global concurrency limiter
+pub struct ConcurrencyLimiter {
+    permits: Arc<Semaphore>,
+    max: usize,
+}
+
+impl ConcurrencyLimiter {
+    /// 创建最多同时处理 max 个请求的限制器 / Create a limiter allowing at most max requests at once
+    pub fn new(max: usize) -> Arc<Self> {
+        let max = max.max(1);
+        Arc::new(Self {
+            permits: Arc::new(Semaphore::new(max)),
+            max,
+        })
+    }
+}
+
+/// 并发限制中间件, 许可在处理函数返回响应时释放 (流式响应体的发送不计入)
+/// Concurrency limiting middleware; the permit is released once the handler returns its response (streaming a body does not count)
+pub async fn limit_concurrency(
+    State(limiter): State<Arc<ConcurrencyLimiter>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Ok(_permit) = limiter.permits.clone().try_acquire_owned() else {
+        warn!(
+            "🚧 并发请求已满 / Concurrent requests saturated: max={}, path={}",
+            limiter.max,
+            request.uri().path()
+        );
+        return ApiError::ServiceUnavailable(
+            "服务繁忙, 请稍后重试 / Server busy, please retry later".to_string(),
+        )
+        .into_response();
+    };
+
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::Body,
+        http::{Request as HttpRequest, StatusCode},
+        middleware,
+        routing::get,
+        Router,
+    };
+    use tokio::sync::Notify;
+    use tower::ServiceExt;
+
+    /// 与 create_router 相同的结构: /slow 挂在并发上限之后, /health 在上限之后合并
+    /// Same shape as create_router: /slow sits behind the cap, /health is merged after it
+    fn test_router(limiter: Arc<ConcurrencyLimiter>, release: Arc<Notify>) -> Router {
+        Router::new()
+            .route(
+                "/slow",
+                get(move || {
+                    let release = release.clone();
+                    async move {
+                        release.notified().await;
+                        "done"
+                    }
+                }),
+            )
+            .layer(middleware::from_fn_with_state(limiter, limit_concurrency))
+            .merge(Router::new().route("/health", get(|| async { "ok" })))
+    }
+
+    fn get_request(path: &str) -> HttpRequest<Body> {
+        HttpRequest::builder().uri(path).body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_saturated_limit_returns_503_and_exempts_health() {
+        let limiter = ConcurrencyLimiter::new(1);
+        let release = Arc::new(Notify::new());
+        let router = test_router(limiter.clone(), release.clone());
+
+        // 第一个请求占住唯一的许可 / The first request holds the only permit
+        let held = tokio::spawn(router.clone().oneshot(get_request("/slow")));
+        while limiter.permits.available_permits() > 0 {
+            tokio::task::yield_now().await;
+        }
+
+        let rejected = router.clone().oneshot(get_request("/slow")).await.unwrap();
+        assert_eq!(rejected.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let health = router.clone().oneshot(get_request("/health")).await.unwrap();
+        assert_eq!(health.status(), StatusCode::OK);
+
+        // 第一个请求完成后许可被释放 / The permit is released once the first request completes
+        release.notify_one();
+        assert_eq!(held.await.unwrap().unwrap().status(), StatusCode::OK);
+        assert_eq!(limiter.permits.available_permits(), 1);
+
+        release.notify_one();
+        let next = router.oneshot(get_request("/slow")).await.unwrap();
+        assert_eq!(next.status(), StatusCode::OK);
+    }
+}
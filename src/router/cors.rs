@@ -0,0 +1,54 @@
+// 跨域中间件 / CORS middleware
+//
+// 根据 server.cors 构建 CorsLayer; 配置在启动时已由 Config::validate 校验
+// Builds the CorsLayer from server.cors; the config is already checked by Config::validate at startup
+
+use anyhow::Result;
+use axum::http::{HeaderName, HeaderValue, Method};
+use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, Any, CorsLayer};
+
+use crate::config::CorsConfig;
+
+/// 根据配置构建跨域层 / Build the CORS layer from config
+pub fn cors_layer(config: &CorsConfig) -> Result<CorsLayer> {
+    let origin: AllowOrigin = if CorsConfig::is_wildcard(&config.allowed_origins) {
+        Any.into()
+    } else {
+        parse_all::<HeaderValue>("allowed_origins", &config.allowed_origins)?.into()
+    };
+
+    let methods: AllowMethods = if CorsConfig::is_wildcard(&config.allowed_methods) {
+        Any.into()
+    } else {
+        parse_all::<Method>("allowed_methods", &config.allowed_methods)?.into()
+    };
+
+    let headers: AllowHeaders = if CorsConfig::is_wildcard(&config.allowed_headers) {
+        Any.into()
+    } else {
+        parse_all::<HeaderName>("allowed_headers", &config.allowed_headers)?.into()
+    };
+
+    Ok(CorsLayer::new()
+        .allow_origin(origin)
+        .allow_methods(methods)
+        .allow_headers(headers)
+        .allow_credentials(config.allow_credentials))
+}
+
+/// 解析配置列表中的每一项 / Parse every entry of a config list
+fn parse_all<T>(field: &str, values: &[String]) -> Result<Vec<T>>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    values
+        .iter()
+        .map(|value| {
+            value.parse::<T>().map_err(|e| {
+                anyhow::anyhow!("server.cors.{} 包含非法值 / contains an invalid value: {} ({})", field, value, e)
+            })
+        })
+        .collect()
+}
+
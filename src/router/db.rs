@@ -1,21 +1,35 @@
 use axum::{
-    extract::{Query, State},
+    extract::{Path, Query, State},
     routing::{get, post},
     Json, Router,
 };
 use serde::{Deserialize, Serialize};
 use utoipa::{IntoParams, ToSchema};
 
-use crate::util::{ok_result, ApiResult};
-use crate::db::DatabaseStats;
+use crate::util::result::{ApiError, CommonResult};
+use crate::util::validate::{invalid, validate_mint_field};
+use crate::util::PageInfo;
+use crate::config::DatabaseConfig;
+use crate::db::{DatabaseStats, PnlSummary};
+use crate::db::event_storage::{EventSummary, LiquidationRecord, MAX_LIQUIDATIONS, MAX_RECENT_TRADES};
+use crate::solana::decode_diagnostics::DECODE_FAILURE_BUFFER_SIZE;
 use crate::solana::events::PinpetEvent;
+use crate::solana::{DecodeDiagnostics, DecodeErrors, SolanaClient};
+use std::sync::Arc;
+use validator::{Validate, ValidationError};
+
+use super::extract::ValidatedQuery;
+
+/// 通用键值接口可访问的键前缀, 事件/订单簿/Token 等业务数据的键不在其中
+/// Key prefixes the generic key-value endpoints may access; event/orderbook/token keys are excluded
+pub const DB_SCRATCH_KEY_PREFIXES: &[&str] = &["user:"];
 
 /// 数据库操作请求
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[schema(title = "DbRequest", description = "数据库操作请求")]
 pub struct DbRequest {
-    /// 键
-    #[schema(example = "test_key")]
+    /// 键, 必须以 user: 开头 / Key, must start with user:
+    #[schema(example = "user:test_key")]
     pub key: String,
 
     /// 值 (可选，用于写入操作)
@@ -28,7 +42,7 @@ pub struct DbRequest {
 #[schema(title = "DbResponse", description = "数据库响应")]
 pub struct DbResponse {
     /// 键
-    #[schema(example = "test_key")]
+    #[schema(example = "user:test_key")]
     pub key: String,
 
     /// 值
@@ -54,19 +68,22 @@ impl Default for SortOrder {
 }
 
 /// 按 Mint 查询请求参数 / Query by mint request parameters
-#[derive(Debug, Deserialize, IntoParams)]
+#[derive(Debug, Deserialize, IntoParams, Validate)]
 #[into_params(parameter_in = Query)]
 pub struct QueryByMintParams {
     /// 代币 mint account 地址 / Token mint account address
     #[param(example = "So11111111111111111111111111111111111111112")]
+    #[validate(custom(function = "validate_mint_field"))]
     pub mint: String,
     /// 页码（从1开始）/ Page number (starts from 1)
     #[param(example = 1, minimum = 1)]
     #[serde(default = "default_page")]
+    #[validate(range(min = 1, message = "must be at least 1"))]
     pub page: u32,
     /// 每页数量 / Page size
     #[param(example = 20, minimum = 1, maximum = 100)]
     #[serde(default = "default_page_size")]
+    #[validate(range(min = 1, max = 100, message = "must be between 1 and 100"))]
     pub page_size: u32,
     /// 排序方向 / Sort order
     #[param(example = "desc")]
@@ -75,22 +92,26 @@ pub struct QueryByMintParams {
 }
 
 /// 按 User 查询请求参数 / Query by user request parameters
-#[derive(Debug, Deserialize, IntoParams)]
+#[derive(Debug, Deserialize, IntoParams, Validate)]
 #[into_params(parameter_in = Query)]
 pub struct QueryByUserParams {
     /// 用户钱包地址 / User wallet address
     #[param(example = "7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU")]
+    #[validate(length(min = 1, message = "must not be empty"))]
     pub user: String,
     /// 可选的 mint 过滤 / Optional mint filter
     #[param(example = "So11111111111111111111111111111111111111112")]
+    #[validate(custom(function = "validate_mint_field"))]
     pub mint: Option<String>,
     /// 页码（从1开始）/ Page number (starts from 1)
     #[param(example = 1, minimum = 1)]
     #[serde(default = "default_page")]
+    #[validate(range(min = 1, message = "must be at least 1"))]
     pub page: u32,
     /// 每页数量 / Page size
     #[param(example = 20, minimum = 1, maximum = 100)]
     #[serde(default = "default_page_size")]
+    #[validate(range(min = 1, max = 100, message = "must be between 1 and 100"))]
     pub page_size: u32,
     /// 排序方向 / Sort order
     #[param(example = "desc")]
@@ -98,33 +119,196 @@ pub struct QueryByUserParams {
     pub sort: SortOrder,
 }
 
+/// 按 User 跨代币查询请求参数 / Query by user across mints request parameters
+#[derive(Debug, Deserialize, IntoParams, Validate)]
+#[into_params(parameter_in = Query)]
+pub struct QueryUserActivityParams {
+    /// 用户钱包地址 / User wallet address
+    #[param(example = "7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU")]
+    #[validate(length(min = 1, message = "must not be empty"))]
+    pub user: String,
+    /// 页码（从1开始）/ Page number (starts from 1)
+    #[param(example = 1, minimum = 1)]
+    #[serde(default = "default_page")]
+    #[validate(range(min = 1, message = "must be at least 1"))]
+    pub page: u32,
+    /// 每页数量（最大100）/ Page size (max 100)
+    #[param(example = 20, minimum = 1, maximum = 100)]
+    #[serde(default = "default_page_size")]
+    #[validate(range(min = 1, max = 100, message = "must be between 1 and 100"))]
+    pub page_size: u32,
+}
+
 /// 按 Signature 查询请求参数 / Query by signature request parameters
-#[derive(Debug, Deserialize, IntoParams)]
+#[derive(Debug, Deserialize, IntoParams, Validate)]
 #[into_params(parameter_in = Query)]
 pub struct QueryBySignatureParams {
     /// 交易签名 / Transaction signature
     #[param(example = "5VERv8NMvzbJMEkV8xnrLkEaWRtSz9CosKDYjCJjBRnbJLgp8uirBgmQpjKhoR4tjF3ZpRzrFmBV6UjKdiSZkQUW")]
+    #[validate(length(min = 1, message = "must not be empty"))]
     pub signature: String,
 }
 
+/// 按 slot 范围查询请求参数 / Query by slot range request parameters
+#[derive(Debug, Deserialize, IntoParams, Validate)]
+#[into_params(parameter_in = Query)]
+#[validate(schema(function = "validate_slot_range"))]
+pub struct QueryBySlotRangeParams {
+    /// 起始 slot (含) / Start slot (inclusive)
+    #[param(example = 250000000)]
+    pub from: u64,
+    /// 结束 slot (含) / End slot (inclusive)
+    #[param(example = 250001000)]
+    pub to: u64,
+    /// 每页数量（最大100）/ Page size (max 100)
+    #[param(example = 20, minimum = 1, maximum = 100)]
+    #[serde(default = "default_slot_range_limit")]
+    #[validate(range(min = 1, max = 100, message = "must be between 1 and 100"))]
+    pub limit: usize,
+    /// 跳过的事件数 / Number of events to skip
+    #[param(example = 0, minimum = 0)]
+    #[serde(default)]
+    pub offset: usize,
+}
+
+/// 全局最近成交请求参数 / Global recent trades request parameters
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RecentTradesParams {
+    /// 返回数量（最大200）/ Number of trades (max 200)
+    #[param(example = 50, minimum = 1, maximum = 200)]
+    #[serde(default = "default_recent_limit")]
+    pub limit: usize,
+}
+
+/// 大额成交请求参数 / Large trades request parameters
+#[derive(Debug, Deserialize, IntoParams, Validate)]
+#[into_params(parameter_in = Query)]
+pub struct WhaleTradesParams {
+    /// 单笔成交的最小 SOL 数量 (lamports), 买卖为成交的 SOL, 开仓为保证金, 平仓为取回的 SOL
+    /// Minimum SOL per trade (lamports): traded SOL for buy/sell, margin for opens, SOL returned for closes
+    #[param(example = 10000000000, minimum = 1)]
+    #[validate(range(min = 1, message = "must be at least 1"))]
+    pub min_sol: u64,
+    /// 返回数量（最大200）/ Number of trades (max 200)
+    #[param(example = 50, minimum = 1, maximum = 200)]
+    #[serde(default = "default_recent_limit")]
+    #[validate(range(min = 1, max = 200, message = "must be between 1 and 200"))]
+    pub limit: usize,
+}
+
+/// 用户已实现盈亏请求参数 / User realized PnL request parameters
+#[derive(Debug, Deserialize, IntoParams, Validate)]
+#[into_params(parameter_in = Query)]
+pub struct UserPnlParams {
+    /// 可选的 mint 过滤, 为空时返回跨代币合计 / Optional mint filter, the cross-token total when omitted
+    #[param(example = "So11111111111111111111111111111111111111112")]
+    #[validate(custom(function = "validate_mint_field"))]
+    pub mint: Option<String>,
+}
+
+/// 清算记录请求参数 / Liquidation records request parameters
+#[derive(Debug, Deserialize, IntoParams, Validate)]
+#[into_params(parameter_in = Query)]
+pub struct LiquidationsParams {
+    /// Token mint 地址 / Token mint address
+    #[validate(custom(function = "validate_mint_field"))]
+    pub mint: String,
+    /// 返回数量（最大200）/ Number of records (max 200)
+    #[param(example = 50, minimum = 1, maximum = 200)]
+    #[serde(default = "default_recent_limit")]
+    pub limit: usize,
+}
+
+/// 解码失败请求参数 / Decode failures request parameters
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DecodeErrorsParams {
+    /// 返回的失败样本数（最大100）/ Number of failure samples (max 100)
+    #[param(example = 20, minimum = 1, maximum = 100)]
+    #[serde(default = "default_recent_limit")]
+    pub limit: usize,
+}
+
+/// 清算记录响应 / Liquidation records response
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(title = "Liquidations", description = "清算记录 / Liquidation records")]
+pub struct Liquidations {
+    /// 清算记录（最新优先）/ Liquidation records (newest first)
+    pub liquidations: Vec<LiquidationRecord>,
+}
+
+/// 全局最近成交响应 / Global recent trades response
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(title = "RecentTrades", description = "全局最近成交 / Global recent trades")]
+pub struct RecentTrades {
+    /// 成交列表（最新优先）/ Trades (newest first)
+    pub trades: Vec<EventSummary>,
+}
+
 /// 分页事件响应 / Paginated event response
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[schema(title = "PaginatedEvents", description = "分页事件响应")]
 pub struct PaginatedEvents {
     /// 事件列表 / Event list
     pub events: Vec<PinpetEvent>,
-    /// 总数量 / Total count
+    /// 分页信息 / Pagination fields
+    #[serde(flatten)]
+    pub pagination: PageInfo,
+}
+
+/// 用户跨代币活动中的一行 / A row of the user cross-mint activity
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(title = "UserEventRow", description = "用户事件行 / User event row")]
+pub struct UserEventRow {
+    /// 代币 mint 地址 / Token mint address
+    pub mint: String,
+    /// 事件类型 / Event type
+    #[schema(example = "BuySell")]
+    pub event_type: String,
+    /// 交易签名 / Transaction signature
+    pub signature: String,
+    /// 区块高度 / Slot
+    #[schema(example = 123456789)]
+    pub slot: u64,
+    /// 事件时间戳(毫秒) / Event timestamp (ms)
+    pub timestamp: i64,
+    /// 完整事件 / Full event
+    pub event: PinpetEvent,
+}
+
+/// 用户跨代币活动响应 / User cross-mint activity response
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(title = "UserActivity", description = "用户跨代币活动 / User cross-mint activity")]
+pub struct UserActivity {
+    /// 事件行（最新优先）/ Event rows (newest first)
+    pub rows: Vec<UserEventRow>,
+    /// 分页信息 / Pagination fields
+    #[serde(flatten)]
+    pub pagination: PageInfo,
+}
+
+/// 按 slot 范围的事件响应 / Slot range event response
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(title = "SlotRangeEvents", description = "按 slot 范围的事件响应 / Slot range event response")]
+pub struct SlotRangeEvents {
+    /// 事件列表（slot 升序）/ Event list (ascending by slot)
+    pub events: Vec<PinpetEvent>,
+    /// 区间内的事件总数 / Total events in the range
     #[schema(example = 100)]
     pub total: u64,
-    /// 当前页码 / Current page
-    #[schema(example = 1)]
-    pub page: u32,
+    /// 起始 slot (含) / Start slot (inclusive)
+    #[schema(example = 250000000)]
+    pub from_slot: u64,
+    /// 结束 slot (含) / End slot (inclusive)
+    #[schema(example = 250001000)]
+    pub to_slot: u64,
     /// 每页数量 / Page size
     #[schema(example = 20)]
-    pub page_size: u32,
-    /// 总页数 / Total pages
-    #[schema(example = 5)]
-    pub total_pages: u32,
+    pub limit: usize,
+    /// 跳过的事件数 / Events skipped
+    #[schema(example = 0)]
+    pub offset: usize,
 }
 
 /// 事件列表响应 / Event list response
@@ -135,20 +319,49 @@ pub struct EventList {
     pub events: Vec<PinpetEvent>,
 }
 
+/// 交易索引状态响应 / Transaction indexed status response
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(title = "TxIndexedStatus", description = "交易索引状态 / Transaction indexed status")]
+pub struct TxIndexedStatus {
+    /// 服务端是否已索引该交易的事件 / Whether the server has indexed this transaction's events
+    pub indexed: bool,
+    /// 交易所在 slot, 未索引时为空 / Slot of the transaction, null when not indexed
+    #[schema(example = 123456789)]
+    pub slot: Option<u64>,
+    /// 首次索引时间(毫秒), 未索引或索引早于该字段引入时为空 / First indexed time (ms), null when not indexed or indexed before this field existed
+    pub processed_at: Option<i64>,
+}
+
 fn default_page() -> u32 { 1 }
 fn default_page_size() -> u32 { 20 }
+fn default_recent_limit() -> usize { 50 }
+fn default_slot_range_limit() -> usize { 20 }
+
+fn validate_slot_range(params: &QueryBySlotRangeParams) -> Result<(), ValidationError> {
+    if params.from > params.to {
+        return Err(invalid("slot_range", "from 不能大于 to / from must not exceed to"));
+    }
+    Ok(())
+}
 
 /// 写入数据到 RocksDB
 #[utoipa::path(
     post,
     path = "/db/put",
-    tag = "database",
+    tag = "admin",
+    security(("api_key" = [])),
     summary = "写入数据",
-    description = "向 RocksDB 写入键值对",
+    description = "向 RocksDB 写入键值对, 仅限 user: 命名空间 / Write a key-value pair, restricted to the user: namespace",
     request_body = DbRequest,
     responses(
         (status = 200, description = "写入成功",
          body = crate::docs::ApiResponse<DbResponse>),
+        (status = 401, description = "未授权 / Unauthorized",
+         body = crate::docs::ErrorApiResponse),
+        (status = 403, description = "键不在 user: 命名空间内 / Key outside the user: namespace",
+         body = crate::docs::ErrorApiResponse),
+        (status = 413, description = "键或值超过大小限制 / Key or value exceeds the size limit",
+         body = crate::docs::ErrorApiResponse),
         (status = 500, description = "服务器内部错误",
          body = crate::docs::ErrorApiResponse,
          example = json!({
@@ -162,31 +375,33 @@ fn default_page_size() -> u32 { 20 }
 pub async fn db_put(
     State(db): State<std::sync::Arc<crate::db::RocksDbStorage>>,
     Json(req): Json<DbRequest>,
-) -> ApiResult {
-    let result = db.put(&req.key, req.value.as_deref().unwrap_or(""));
-
-    match result {
-        Ok(_) => Ok(ok_result::<DbResponse>(Ok(DbResponse {
-            key: req.key.clone(),
-            value: req.value,
-        }))),
-        Err(e) => Ok(ok_result::<DbResponse>(Err(
-            crate::util::result::ApiError::InternalError(e.to_string()),
-        ))),
-    }
+) -> Result<Json<CommonResult<DbResponse>>, ApiError> {
+    check_scratch_key(&req.key)?;
+    check_kv_size(db.database_config(), &req)?;
+    db.put(&req.key, req.value.as_deref().unwrap_or(""))?;
+
+    Ok(Json(CommonResult::ok(DbResponse {
+        key: req.key,
+        value: req.value,
+    })))
 }
 
 /// 从 RocksDB 读取数据
 #[utoipa::path(
     post,
     path = "/db/get",
-    tag = "database",
+    tag = "admin",
+    security(("api_key" = [])),
     summary = "读取数据",
-    description = "从 RocksDB 读取键对应的值",
+    description = "从 RocksDB 读取键对应的值, 仅限 user: 命名空间 / Read a key, restricted to the user: namespace",
     request_body = DbRequest,
     responses(
         (status = 200, description = "读取成功",
          body = crate::docs::ApiResponse<DbResponse>),
+        (status = 401, description = "未授权 / Unauthorized",
+         body = crate::docs::ErrorApiResponse),
+        (status = 403, description = "键不在 user: 命名空间内 / Key outside the user: namespace",
+         body = crate::docs::ErrorApiResponse),
         (status = 404, description = "未找到",
          body = crate::docs::ErrorApiResponse,
          example = json!({
@@ -202,31 +417,29 @@ pub async fn db_put(
 pub async fn db_get(
     State(db): State<std::sync::Arc<crate::db::RocksDbStorage>>,
     Json(req): Json<DbRequest>,
-) -> ApiResult {
-    let result = db.get(&req.key);
-
-    match result {
-        Ok(value) => Ok(ok_result::<DbResponse>(Ok(DbResponse {
-            key: req.key.clone(),
-            value,
-        }))),
-        Err(e) => Ok(ok_result::<DbResponse>(Err(
-            crate::util::result::ApiError::InternalError(e.to_string()),
-        ))),
-    }
+) -> Result<Json<CommonResult<DbResponse>>, ApiError> {
+    check_scratch_key(&req.key)?;
+    let value = db.get(&req.key)?;
+
+    Ok(Json(CommonResult::ok(DbResponse { key: req.key, value })))
 }
 
 /// 从 RocksDB 删除数据
 #[utoipa::path(
     post,
     path = "/db/delete",
-    tag = "database",
+    tag = "admin",
+    security(("api_key" = [])),
     summary = "删除数据",
-    description = "从 RocksDB 删除键值对",
+    description = "从 RocksDB 删除键值对, 仅限 user: 命名空间 / Delete a key, restricted to the user: namespace",
     request_body = DbRequest,
     responses(
         (status = 200, description = "删除成功",
          body = crate::docs::ApiResponse<DbResponse>),
+        (status = 401, description = "未授权 / Unauthorized",
+         body = crate::docs::ErrorApiResponse),
+        (status = 403, description = "键不在 user: 命名空间内 / Key outside the user: namespace",
+         body = crate::docs::ErrorApiResponse),
         (status = 500, description = "服务器内部错误",
          body = crate::docs::ErrorApiResponse)
     )
@@ -234,27 +447,140 @@ pub async fn db_get(
 pub async fn db_delete(
     State(db): State<std::sync::Arc<crate::db::RocksDbStorage>>,
     Json(req): Json<DbRequest>,
-) -> ApiResult {
-    let result = db.delete(&req.key);
-
-    match result {
-        Ok(_) => Ok(ok_result::<DbResponse>(Ok(DbResponse {
-            key: req.key.clone(),
-            value: None,
-        }))),
-        Err(e) => Ok(ok_result::<DbResponse>(Err(
-            crate::util::result::ApiError::InternalError(e.to_string()),
-        ))),
+) -> Result<Json<CommonResult<DbResponse>>, ApiError> {
+    check_scratch_key(&req.key)?;
+    db.delete(&req.key)?;
+
+    Ok(Json(CommonResult::ok(DbResponse {
+        key: req.key,
+        value: None,
+    })))
+}
+
+/// 拒绝访问业务数据的键 / Reject keys that belong to business data
+fn check_scratch_key(key: &str) -> Result<(), ApiError> {
+    let allowed = DB_SCRATCH_KEY_PREFIXES
+        .iter()
+        .any(|prefix| key.len() > prefix.len() && key.starts_with(prefix));
+    if allowed {
+        Ok(())
+    } else {
+        Err(ApiError::Forbidden(format!(
+            "键必须以 {:?} 之一开头 / Key must start with one of {:?}: {}",
+            DB_SCRATCH_KEY_PREFIXES, DB_SCRATCH_KEY_PREFIXES, key
+        )))
+    }
+}
+
+/// 拒绝超过 database.max_kv_key_bytes / max_kv_value_bytes 的写入
+/// Reject writes exceeding database.max_kv_key_bytes / max_kv_value_bytes
+fn check_kv_size(config: &DatabaseConfig, req: &DbRequest) -> Result<(), ApiError> {
+    if req.key.len() > config.max_kv_key_bytes {
+        return Err(ApiError::PayloadTooLarge(format!(
+            "键长度 {} 超过上限 {} / Key length {} exceeds the limit {}",
+            req.key.len(), config.max_kv_key_bytes, req.key.len(), config.max_kv_key_bytes
+        )));
+    }
+    let value_len = req.value.as_deref().map_or(0, str::len);
+    if value_len > config.max_kv_value_bytes {
+        return Err(ApiError::PayloadTooLarge(format!(
+            "值大小 {} 超过上限 {} / Value size {} exceeds the limit {}",
+            value_len, config.max_kv_value_bytes, value_len, config.max_kv_value_bytes
+        )));
     }
+    Ok(())
+}
+
+/// 管理路由的请求体上限: 键和值上限之和, 值按 JSON 转义最多翻倍估算, 另留 4KB 给其余字段
+/// Request body limit of the admin routes: key plus value limits, assuming JSON escaping at most doubles the value, plus 4KB for the rest
+pub fn kv_body_limit(config: &DatabaseConfig) -> usize {
+    config.max_kv_key_bytes + config.max_kv_value_bytes * 2 + 4096
+}
+
+/// 手动压缩结果 / Manual compaction result
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(title = "CompactResponse", description = "手动压缩结果 / Manual compaction result")]
+pub struct CompactResponse {
+    /// 压缩前数据库大小 (同 DatabaseStats.database_size_bytes) / Database size before compaction (as in DatabaseStats.database_size_bytes)
+    #[schema(example = 1048576)]
+    pub before_database_size_bytes: u64,
+    /// 压缩后数据库大小 / Database size after compaction
+    #[schema(example = 524288)]
+    pub after_database_size_bytes: u64,
+    /// 压缩前 SST 文件总大小 / Total SST file size before compaction
+    #[schema(example = 2097152)]
+    pub before_sst_files_size_bytes: u64,
+    /// 压缩后 SST 文件总大小 / Total SST file size after compaction
+    #[schema(example = 1048576)]
+    pub after_sst_files_size_bytes: u64,
+    /// 回收的磁盘空间 (SST 大小差值) / Disk space reclaimed (SST size difference)
+    #[schema(example = 1048576)]
+    pub reclaimed_bytes: u64,
+    /// 耗时(毫秒) / Duration (ms)
+    #[schema(example = 1200)]
+    pub duration_ms: u64,
+}
+
+/// 手动压缩 RocksDB, 回收已删除事件/订单占用的磁盘空间
+#[utoipa::path(
+    post,
+    path = "/db/compact",
+    tag = "admin",
+    security(("api_key" = [])),
+    summary = "手动压缩数据库",
+    description = "对整个键空间执行 RocksDB 手动压缩, 回收已删除数据占用的磁盘空间, 并返回压缩前后的大小。大库上可能耗时较长 / Run a manual RocksDB compaction over the whole key space to reclaim disk from deleted data, returning sizes before and after. May take a while on large databases",
+    responses(
+        (status = 200, description = "压缩完成",
+         body = crate::docs::ApiResponse<CompactResponse>),
+        (status = 401, description = "未授权 / Unauthorized",
+         body = crate::docs::ErrorApiResponse),
+        (status = 500, description = "服务器内部错误",
+         body = crate::docs::ErrorApiResponse)
+    )
+)]
+pub async fn db_compact(
+    State(db): State<std::sync::Arc<crate::db::RocksDbStorage>>,
+) -> Result<Json<CommonResult<CompactResponse>>, ApiError> {
+    // 压缩是阻塞操作, 放到阻塞线程池执行 / Compaction blocks, run it on the blocking pool
+    let response = tokio::task::spawn_blocking(move || -> anyhow::Result<CompactResponse> {
+        let event_storage = db.create_event_storage()?;
+        let before_database_size_bytes = event_storage.get_estimated_db_size()?;
+        let before_sst_files_size_bytes = db.sst_files_size()?;
+
+        let started = std::time::Instant::now();
+        db.compact();
+        let duration_ms = started.elapsed().as_millis() as u64;
+
+        let after_database_size_bytes = event_storage.get_estimated_db_size()?;
+        let after_sst_files_size_bytes = db.sst_files_size()?;
+
+        tracing::info!(
+            "🗜️ 手动压缩完成 / Manual compaction finished: sst {} -> {} bytes, {} ms",
+            before_sst_files_size_bytes, after_sst_files_size_bytes, duration_ms
+        );
+
+        Ok(CompactResponse {
+            before_database_size_bytes,
+            after_database_size_bytes,
+            before_sst_files_size_bytes,
+            after_sst_files_size_bytes,
+            reclaimed_bytes: before_sst_files_size_bytes.saturating_sub(after_sst_files_size_bytes),
+            duration_ms,
+        })
+    })
+    .await
+    .map_err(|e| ApiError::InternalError(format!("压缩任务失败 / Compaction task failed: {}", e)))??;
+
+    Ok(Json(CommonResult::ok(response)))
 }
 
 /// 获取 RocksDB 统计信息
 #[utoipa::path(
     get,
-    path = "/db/stats",
+    path = "/db/rocksdb_stats",
     tag = "database",
-    summary = "获取统计信息",
-    description = "获取 RocksDB 的统计信息",
+    summary = "获取 RocksDB 原始统计",
+    description = "获取 RocksDB 的原始统计文本 (rocksdb.stats) / Raw RocksDB statistics text (rocksdb.stats)",
     responses(
         (status = 200, description = "获取成功",
          body = crate::docs::ApiResponse<String>),
@@ -262,24 +588,19 @@ pub async fn db_delete(
          body = crate::docs::ErrorApiResponse)
     )
 )]
-pub async fn db_stats(State(db): State<std::sync::Arc<crate::db::RocksDbStorage>>) -> ApiResult {
-    let result = db.get_stats();
-
-    match result {
-        Ok(stats) => Ok(ok_result::<String>(Ok(stats))),
-        Err(e) => Ok(ok_result::<String>(Err(
-            crate::util::result::ApiError::InternalError(e.to_string()),
-        ))),
-    }
+pub async fn db_stats(
+    State(db): State<std::sync::Arc<crate::db::RocksDbStorage>>,
+) -> Result<Json<CommonResult<String>>, ApiError> {
+    Ok(Json(CommonResult::ok(db.get_stats()?)))
 }
 
 /// 获取数据库键值统计信息 - 调试接口 / Get database key-value statistics - debug interface
 #[utoipa::path(
     get,
-    path = "/db/event_stats",
+    path = "/db/stats",
     tag = "database",
     summary = "获取数据库键值统计信息",
-    description = "获取 RocksDB 中所有键值对的数量和大小统计（调试功能）",
+    description = "获取 RocksDB 中所有键值对的数量和大小统计, 含按数据域 (events / orderbook / tokens / kline / counters) 的键数和大小估计（调试功能）; /db/event_stats 为兼容旧客户端的别名 / Key counts and sizes of the whole RocksDB, including per data domain (events / orderbook / tokens / kline / counters) estimates (debug); /db/event_stats is an alias kept for existing clients",
     responses(
         (status = 200, description = "获取成功",
          body = crate::docs::ApiResponse<DatabaseStats>),
@@ -287,28 +608,15 @@ pub async fn db_stats(State(db): State<std::sync::Arc<crate::db::RocksDbStorage>
          body = crate::docs::ErrorApiResponse)
     )
 )]
-pub async fn db_event_stats(State(db): State<std::sync::Arc<crate::db::RocksDbStorage>>) -> ApiResult {
-    // 创建事件存储实例 / Create event storage instance
-    let event_storage = match db.create_event_storage() {
-        Ok(storage) => storage,
-        Err(e) => {
-            return Ok(ok_result::<DatabaseStats>(Err(
-                crate::util::result::ApiError::InternalError(
-                    format!("创建事件存储失败 / Failed to create event storage: {}", e)
-                ),
-            )))
-        }
-    };
+pub async fn db_event_stats(
+    State(db): State<std::sync::Arc<crate::db::RocksDbStorage>>,
+) -> Result<Json<CommonResult<DatabaseStats>>, ApiError> {
+    let event_storage = event_storage(&db)?;
 
     // 获取数据库统计信息 / Get database statistics
-    let result = event_storage.get_db_stats();
+    let stats = event_storage.get_db_stats()?;
 
-    match result {
-        Ok(stats) => Ok(ok_result::<DatabaseStats>(Ok(stats))),
-        Err(e) => Ok(ok_result::<DatabaseStats>(Err(
-            crate::util::result::ApiError::InternalError(e.to_string()),
-        ))),
-    }
+    Ok(Json(CommonResult::ok(stats)))
 }
 
 /// 按 Mint 查询事件 / Query events by mint
@@ -322,40 +630,27 @@ pub async fn db_event_stats(State(db): State<std::sync::Arc<crate::db::RocksDbSt
     responses(
         (status = 200, description = "查询成功",
          body = crate::docs::ApiResponse<PaginatedEvents>),
+        (status = 400, description = "参数无效 / Invalid parameters",
+         body = crate::docs::ErrorApiResponse),
         (status = 500, description = "服务器内部错误",
          body = crate::docs::ErrorApiResponse)
     )
 )]
 pub async fn query_events_by_mint(
     State(db): State<std::sync::Arc<crate::db::RocksDbStorage>>,
-    Query(params): Query<QueryByMintParams>,
-) -> ApiResult {
-    // 创建事件存储实例 / Create event storage instance
-    let event_storage = match db.create_event_storage() {
-        Ok(storage) => storage,
-        Err(e) => {
-            return Ok(ok_result::<PaginatedEvents>(Err(
-                crate::util::result::ApiError::InternalError(
-                    format!("创建事件存储失败 / Failed to create event storage: {}", e)
-                ),
-            )))
-        }
-    };
+    ValidatedQuery(params): ValidatedQuery<QueryByMintParams>,
+) -> Result<Json<CommonResult<PaginatedEvents>>, ApiError> {
+    let event_storage = event_storage(&db)?;
 
     // 查询事件 / Query events
-    let result = event_storage.query_by_mint_paginated(
+    let paginated = event_storage.query_by_mint_paginated(
         &params.mint,
         params.page,
         params.page_size,
         params.sort == SortOrder::Asc,
-    ).await;
+    ).await?;
 
-    match result {
-        Ok(paginated) => Ok(ok_result::<PaginatedEvents>(Ok(paginated))),
-        Err(e) => Ok(ok_result::<PaginatedEvents>(Err(
-            crate::util::result::ApiError::InternalError(e.to_string()),
-        ))),
-    }
+    Ok(Json(CommonResult::ok(paginated)))
 }
 
 /// 按 User 查询事件 / Query events by user
@@ -369,41 +664,138 @@ pub async fn query_events_by_mint(
     responses(
         (status = 200, description = "查询成功",
          body = crate::docs::ApiResponse<PaginatedEvents>),
+        (status = 400, description = "参数无效 / Invalid parameters",
+         body = crate::docs::ErrorApiResponse),
         (status = 500, description = "服务器内部错误",
          body = crate::docs::ErrorApiResponse)
     )
 )]
 pub async fn query_events_by_user(
     State(db): State<std::sync::Arc<crate::db::RocksDbStorage>>,
-    Query(params): Query<QueryByUserParams>,
-) -> ApiResult {
-    // 创建事件存储实例 / Create event storage instance
-    let event_storage = match db.create_event_storage() {
-        Ok(storage) => storage,
-        Err(e) => {
-            return Ok(ok_result::<PaginatedEvents>(Err(
-                crate::util::result::ApiError::InternalError(
-                    format!("创建事件存储失败 / Failed to create event storage: {}", e)
-                ),
-            )))
-        }
-    };
+    ValidatedQuery(params): ValidatedQuery<QueryByUserParams>,
+) -> Result<Json<CommonResult<PaginatedEvents>>, ApiError> {
+    let event_storage = event_storage(&db)?;
 
     // 查询事件 / Query events
-    let result = event_storage.query_by_user_paginated(
+    let paginated = event_storage.query_by_user_paginated(
         &params.user,
         params.mint.as_deref(),
         params.page,
         params.page_size,
         params.sort == SortOrder::Asc,
-    ).await;
+    ).await?;
 
-    match result {
-        Ok(paginated) => Ok(ok_result::<PaginatedEvents>(Ok(paginated))),
-        Err(e) => Ok(ok_result::<PaginatedEvents>(Err(
-            crate::util::result::ApiError::InternalError(e.to_string()),
-        ))),
-    }
+    Ok(Json(CommonResult::ok(paginated)))
+}
+
+/// 按 User 查询跨代币活动 / Query a user's activity across mints
+#[utoipa::path(
+    get,
+    path = "/db/events/by_user/all",
+    tag = "events",
+    summary = "按 User 查询跨代币活动 / User activity across mints",
+    description = "查询用户在所有代币上的事件，按事件时间最新优先分页，每行带 mint 和事件类型 / A user's events across every token, paginated newest-first by event time, each row tagged with mint and event type",
+    params(QueryUserActivityParams),
+    responses(
+        (status = 200, description = "查询成功",
+         body = crate::docs::ApiResponse<UserActivity>),
+        (status = 400, description = "参数无效 / Invalid parameters",
+         body = crate::docs::ErrorApiResponse),
+        (status = 500, description = "服务器内部错误",
+         body = crate::docs::ErrorApiResponse)
+    )
+)]
+pub async fn query_user_activity(
+    State(db): State<std::sync::Arc<crate::db::RocksDbStorage>>,
+    ValidatedQuery(params): ValidatedQuery<QueryUserActivityParams>,
+) -> Result<Json<CommonResult<UserActivity>>, ApiError> {
+    let event_storage = event_storage(&db)?;
+
+    let activity = event_storage.query_user_activity(&params.user, params.page, params.page_size)?;
+
+    Ok(Json(CommonResult::ok(activity)))
+}
+
+/// 全局最近成交 / Global recent trades
+#[utoipa::path(
+    get,
+    path = "/db/events/recent",
+    tag = "events",
+    summary = "全局最近成交 / Global recent trades",
+    description = "跨所有代币查询最近的成交（买卖、开仓、平仓），最新优先 / Most recent trades across all tokens (buy/sell, open, close), newest first",
+    params(RecentTradesParams),
+    responses(
+        (status = 200, description = "查询成功",
+         body = crate::docs::ApiResponse<RecentTrades>),
+        (status = 500, description = "服务器内部错误",
+         body = crate::docs::ErrorApiResponse)
+    )
+)]
+pub async fn query_recent_trades(
+    State(db): State<std::sync::Arc<crate::db::RocksDbStorage>>,
+    Query(params): Query<RecentTradesParams>,
+) -> Result<Json<CommonResult<RecentTrades>>, ApiError> {
+    let event_storage = event_storage(&db)?;
+
+    let trades = event_storage.recent_trades(params.limit.min(MAX_RECENT_TRADES))?;
+
+    Ok(Json(CommonResult::ok(RecentTrades { trades })))
+}
+
+/// 全局最近大额成交 / Global recent large trades
+#[utoipa::path(
+    get,
+    path = "/db/events/whales",
+    tag = "events",
+    summary = "全局最近大额成交 / Global recent large trades",
+    description = "跨所有代币查询单笔 SOL 数量不低于 min_sol (lamports) 的最近成交，最新优先; 只扫描最近 20000 条成交, 阈值过高时可能少于 limit 条 / Recent trades across all tokens whose per-trade SOL amount is at least min_sol (lamports), newest first; only the latest 20000 trades are scanned, so a very high threshold may return fewer than limit",
+    params(WhaleTradesParams),
+    responses(
+        (status = 200, description = "查询成功",
+         body = crate::docs::ApiResponse<RecentTrades>),
+        (status = 400, description = "参数无效 / Invalid parameters",
+         body = crate::docs::ErrorApiResponse),
+        (status = 500, description = "服务器内部错误",
+         body = crate::docs::ErrorApiResponse)
+    )
+)]
+pub async fn query_whale_trades(
+    State(db): State<std::sync::Arc<crate::db::RocksDbStorage>>,
+    ValidatedQuery(params): ValidatedQuery<WhaleTradesParams>,
+) -> Result<Json<CommonResult<RecentTrades>>, ApiError> {
+    let event_storage = event_storage(&db)?;
+
+    let trades = event_storage.recent_large_trades(params.min_sol, params.limit)?;
+
+    Ok(Json(CommonResult::ok(RecentTrades { trades })))
+}
+
+/// 按 mint 查询清算记录 / Query liquidation records by mint
+#[utoipa::path(
+    get,
+    path = "/db/events/liquidations",
+    tag = "events",
+    summary = "按 mint 查询清算记录 / Liquidation records by mint",
+    description = "查询被 liquidate_indices 强制移除的订单，标注 order_id 和用户，最新优先 / Orders force-removed via liquidate_indices, tagged with order_id and user, newest first",
+    params(LiquidationsParams),
+    responses(
+        (status = 200, description = "查询成功",
+         body = crate::docs::ApiResponse<Liquidations>),
+        (status = 400, description = "参数无效 / Invalid parameters",
+         body = crate::docs::ErrorApiResponse),
+        (status = 500, description = "服务器内部错误",
+         body = crate::docs::ErrorApiResponse)
+    )
+)]
+pub async fn query_liquidations(
+    State(db): State<std::sync::Arc<crate::db::RocksDbStorage>>,
+    ValidatedQuery(params): ValidatedQuery<LiquidationsParams>,
+) -> Result<Json<CommonResult<Liquidations>>, ApiError> {
+    let event_storage = event_storage(&db)?;
+
+    let liquidations = event_storage.query_liquidations(&params.mint, params.limit.min(MAX_LIQUIDATIONS))?;
+
+    Ok(Json(CommonResult::ok(Liquidations { liquidations })))
 }
 
 /// 按 Signature 查询事件 / Query events by signature
@@ -417,46 +809,277 @@ pub async fn query_events_by_user(
     responses(
         (status = 200, description = "查询成功",
          body = crate::docs::ApiResponse<EventList>),
+        (status = 400, description = "参数无效 / Invalid parameters",
+         body = crate::docs::ErrorApiResponse),
         (status = 500, description = "服务器内部错误",
          body = crate::docs::ErrorApiResponse)
     )
 )]
 pub async fn query_events_by_signature(
     State(db): State<std::sync::Arc<crate::db::RocksDbStorage>>,
-    Query(params): Query<QueryBySignatureParams>,
-) -> ApiResult {
-    // 创建事件存储实例 / Create event storage instance
-    let event_storage = match db.create_event_storage() {
-        Ok(storage) => storage,
-        Err(e) => {
-            return Ok(ok_result::<EventList>(Err(
-                crate::util::result::ApiError::InternalError(
-                    format!("创建事件存储失败 / Failed to create event storage: {}", e)
-                ),
-            )))
-        }
-    };
+    ValidatedQuery(params): ValidatedQuery<QueryBySignatureParams>,
+) -> Result<Json<CommonResult<EventList>>, ApiError> {
+    let event_storage = event_storage(&db)?;
 
     // 查询事件 / Query events
-    let result = event_storage.query_by_signature(&params.signature).await;
+    let events = event_storage.query_by_signature(&params.signature).await?;
 
-    match result {
-        Ok(events) => Ok(ok_result::<EventList>(Ok(EventList { events }))),
-        Err(e) => Ok(ok_result::<EventList>(Err(
-            crate::util::result::ApiError::InternalError(e.to_string()),
-        ))),
-    }
+    Ok(Json(CommonResult::ok(EventList { events })))
+}
+
+/// 查询交易是否已被索引 / Query whether a transaction has been indexed
+#[utoipa::path(
+    get,
+    path = "/tx/{signature}/indexed",
+    tag = "events",
+    summary = "交易索引状态 / Transaction indexed status",
+    description = "只读取索引标记, 不返回事件内容; 客户端提交交易后可低成本轮询, 确认服务端状态已反映该交易 / Reads only the indexed marker without event payloads; clients can poll it cheaply after submitting a transaction to confirm server-side state reflects it",
+    params(
+        ("signature" = String, Path, description = "交易签名 / Transaction signature")
+    ),
+    responses(
+        (status = 200, description = "查询成功",
+         body = crate::docs::ApiResponse<TxIndexedStatus>),
+        (status = 500, description = "服务器内部错误",
+         body = crate::docs::ErrorApiResponse)
+    )
+)]
+pub async fn query_tx_indexed(
+    State(db): State<std::sync::Arc<crate::db::RocksDbStorage>>,
+    Path(signature): Path<String>,
+) -> Result<Json<CommonResult<TxIndexedStatus>>, ApiError> {
+    let event_storage = event_storage(&db)?;
+
+    let status = event_storage.signature_status(&signature)?;
+
+    Ok(Json(CommonResult::ok(TxIndexedStatus {
+        indexed: status.is_some(),
+        slot: status.map(|s| s.slot),
+        processed_at: status.and_then(|s| s.processed_at),
+    })))
 }
 
-/// 创建数据库路由
+/// 查询用户已实现盈亏 / Query a user's realized PnL
+#[utoipa::path(
+    get,
+    path = "/users/{user}/pnl",
+    tag = "events",
+    summary = "用户已实现盈亏 / User realized PnL",
+    description = "订单结清时计入一次: 主动全平的取回为部分平仓累计的 realized_sol_amount 加最终的 user_close_profit, 被清算的取回只有部分平仓累计部分; 盈亏 = 取回 - 开仓保证金 (lamports), 盈亏为正计为盈利, 为负计为亏损。只统计服务端处理过的事件 / An order counts once, when it is settled: a full close pays out the realized_sol_amount of earlier partial closes plus the final user_close_profit, a liquidation pays out only the partial close part; PnL = payout - opening margin (lamports), positive counts as a win and negative as a loss. Only events the server processed are counted",
+    params(
+        ("user" = String, Path, description = "用户钱包地址 / User wallet address"),
+        UserPnlParams
+    ),
+    responses(
+        (status = 200, description = "查询成功",
+         body = crate::docs::ApiResponse<PnlSummary>),
+        (status = 400, description = "参数无效 / Invalid parameters",
+         body = crate::docs::ErrorApiResponse),
+        (status = 500, description = "服务器内部错误",
+         body = crate::docs::ErrorApiResponse)
+    )
+)]
+pub async fn query_user_pnl(
+    State(db): State<std::sync::Arc<crate::db::RocksDbStorage>>,
+    Path(user): Path<String>,
+    ValidatedQuery(params): ValidatedQuery<UserPnlParams>,
+) -> Result<Json<CommonResult<PnlSummary>>, ApiError> {
+    let event_storage = event_storage(&db)?;
+
+    let summary = event_storage.user_realized_pnl(&user, params.mint.as_deref())?;
+
+    Ok(Json(CommonResult::ok(summary)))
+}
+
+/// 按 slot 范围查询事件 / Query events by slot range
+#[utoipa::path(
+    get,
+    path = "/db/events/by_slot",
+    tag = "events",
+    summary = "按 slot 范围查询事件 / Query events by slot range",
+    description = "查询 [from, to] slot 区间内的事件，按 slot 升序，用 limit/offset 分页并返回总数；便于与链上数据按 slot 对账 / Events in the [from, to] slot range, ascending by slot, paginated with limit/offset and returning the total; handy for reconciling against slot-indexed on-chain data",
+    params(QueryBySlotRangeParams),
+    responses(
+        (status = 200, description = "查询成功",
+         body = crate::docs::ApiResponse<SlotRangeEvents>),
+        (status = 400, description = "slot 区间无效 / Invalid slot range",
+         body = crate::docs::ErrorApiResponse),
+        (status = 500, description = "服务器内部错误",
+         body = crate::docs::ErrorApiResponse)
+    )
+)]
+pub async fn query_events_by_slot_range(
+    State(db): State<std::sync::Arc<crate::db::RocksDbStorage>>,
+    ValidatedQuery(params): ValidatedQuery<QueryBySlotRangeParams>,
+) -> Result<Json<CommonResult<SlotRangeEvents>>, ApiError> {
+    let event_storage = event_storage(&db)?;
+
+    let events = event_storage
+        .query_by_slot_range(params.from, params.to, params.limit, params.offset)
+        .await?;
+
+    Ok(Json(CommonResult::ok(events)))
+}
+
+/// 创建事件存储实例 / Create event storage instance
+fn event_storage(db: &crate::db::RocksDbStorage) -> Result<crate::db::EventStorage, ApiError> {
+    db.create_event_storage().map_err(|e| {
+        ApiError::Storage(format!("创建事件存储失败 / Failed to create event storage: {}", e))
+    })
+}
+
+/// 创建数据库路由 (公共只读) / Create database routes (public, read-only)
 pub fn routes() -> Router<std::sync::Arc<crate::db::RocksDbStorage>> {
     Router::new()
-        .route("/db/put", post(db_put))
-        .route("/db/get", post(db_get))
-        .route("/db/delete", post(db_delete))
-        .route("/db/stats", get(db_stats))
+        .route("/db/stats", get(db_event_stats))
         .route("/db/event_stats", get(db_event_stats))
+        .route("/db/rocksdb_stats", get(db_stats))
         .route("/db/events/by_mint", get(query_events_by_mint))
         .route("/db/events/by_user", get(query_events_by_user))
+        .route("/db/events/by_user/all", get(query_user_activity))
         .route("/db/events/by_signature", get(query_events_by_signature))
+        .route("/tx/:signature/indexed", get(query_tx_indexed))
+        .route("/users/:user/pnl", get(query_user_pnl))
+        .route("/db/events/by_slot", get(query_events_by_slot_range))
+        .route("/db/events/recent", get(query_recent_trades))
+        .route("/db/events/whales", get(query_whale_trades))
+        .route("/db/events/liquidations", get(query_liquidations))
+}
+
+/// 查询事件解码失败 / Query event decode failures
+#[utoipa::path(
+    get,
+    path = "/db/decode-errors",
+    tag = "admin",
+    security(("api_key" = [])),
+    summary = "事件解码失败 / Event decode failures",
+    description = "按事件类型统计 EventParser 的解码失败, 并返回最近的失败样本 (含原始 base64 日志), 用于发现链上程序结构变更 / Per-event-type EventParser decode failure counts plus the latest failure samples (with raw base64 logs), for catching on-chain schema drift",
+    params(DecodeErrorsParams),
+    responses(
+        (status = 200, description = "查询成功",
+         body = crate::docs::ApiResponse<DecodeErrors>),
+        (status = 401, description = "未授权 / Unauthorized",
+         body = crate::docs::ErrorApiResponse)
+    )
+)]
+pub async fn query_decode_errors(
+    State(diagnostics): State<Arc<DecodeDiagnostics>>,
+    Query(params): Query<DecodeErrorsParams>,
+) -> Result<Json<CommonResult<DecodeErrors>>, ApiError> {
+    let report = diagnostics.report(params.limit.min(DECODE_FAILURE_BUFFER_SIZE));
+    Ok(Json(CommonResult::ok(report)))
+}
+
+/// 查询事件处理游标 / Query the event processing cursor
+#[utoipa::path(
+    get,
+    path = "/admin/cursor",
+    tag = "admin",
+    security(("api_key" = [])),
+    summary = "事件处理游标 / Event processing cursor",
+    description = "返回已存储事件中的最高 slot 及其交易签名, 尚未存储任何事件时 data 为 null。游标只用于观察, 启动时不会据此补数据; 重放历史区间请使用 /admin/reprocess / Returns the highest stored slot and its transaction signature, data is null before any event is stored. The cursor is informational, nothing backfills from it at startup; replay a historical window with /admin/reprocess",
+    responses(
+        (status = 200, description = "查询成功",
+         body = crate::docs::ApiResponse<crate::db::EventCursor>),
+        (status = 401, description = "未授权 / Unauthorized",
+         body = crate::docs::ErrorApiResponse),
+        (status = 500, description = "服务器内部错误",
+         body = crate::docs::ErrorApiResponse)
+    )
+)]
+pub async fn get_event_cursor(
+    State(state): State<CursorState>,
+) -> Result<Json<CommonResult<Option<crate::db::EventCursor>>>, ApiError> {
+    let event_storage = event_storage(&state.db)?;
+
+    let cursor = event_storage.event_cursor()?;
+
+    Ok(Json(CommonResult::ok(cursor)))
+}
+
+/// 事件处理游标接口的共享状态 / Shared state of the event processing cursor endpoints
+#[derive(Clone)]
+pub struct CursorState {
+    pub db: Arc<crate::db::RocksDbStorage>,
+    /// 用于读取链上最新 slot / Used to read the chain tip slot
+    pub solana_client: Arc<SolanaClient>,
+}
+
+/// 设置事件处理游标请求 / Set event processing cursor request
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(title = "SetCursorRequest", description = "设置事件处理游标请求 / Set event processing cursor request")]
+pub struct SetCursorRequest {
+    /// 新的最高已处理 slot, 不能超过链上最新 slot / New highest processed slot, must not exceed the chain tip
+    #[schema(example = 123456789)]
+    pub last_processed_slot: u64,
+    /// 该 slot 中最后处理的交易签名, 未知时留空 / Signature of the last transaction processed at that slot, empty when unknown
+    #[serde(default)]
+    pub last_processed_signature: String,
+}
+
+/// 设置事件处理游标
+#[utoipa::path(
+    post,
+    path = "/admin/cursor",
+    tag = "admin",
+    security(("api_key" = [])),
+    summary = "设置事件处理游标 / Set the event processing cursor",
+    description = "恢复场景下手动设置游标, 拒绝超过链上最新 slot 的值。之后存储的更高 slot 交易会继续推进它; 降低游标本身不会重放任何交易, 启动时也不据此补数据, 重放被跳过的区间请使用 /admin/reprocess / Set the cursor by hand in recovery scenarios; values past the chain tip are refused. Transactions stored at higher slots afterwards keep advancing it; lowering it does not replay anything by itself and nothing backfills from it at startup, replay the skipped window with /admin/reprocess",
+    request_body = SetCursorRequest,
+    responses(
+        (status = 200, description = "设置成功 / Cursor set",
+         body = crate::docs::ApiResponse<crate::db::EventCursor>),
+        (status = 400, description = "slot 超过链上最新 slot / Slot is past the chain tip",
+         body = crate::docs::ErrorApiResponse),
+        (status = 401, description = "未授权 / Unauthorized",
+         body = crate::docs::ErrorApiResponse),
+        (status = 502, description = "读取链上最新 slot 失败 / Failed to read the chain tip",
+         body = crate::docs::ErrorApiResponse),
+        (status = 500, description = "服务器内部错误",
+         body = crate::docs::ErrorApiResponse)
+    )
+)]
+pub async fn set_event_cursor(
+    State(state): State<CursorState>,
+    Json(req): Json<SetCursorRequest>,
+) -> Result<Json<CommonResult<crate::db::EventCursor>>, ApiError> {
+    let tip = state.solana_client.get_slot().await.map_err(|e| {
+        ApiError::Upstream(format!("读取链上最新 slot 失败 / Failed to read the chain tip: {}", e))
+    })?;
+    if req.last_processed_slot > tip {
+        return Err(ApiError::BadRequest(format!(
+            "slot {} 超过链上最新 slot {} / slot {} is past the chain tip {}",
+            req.last_processed_slot, tip, req.last_processed_slot, tip
+        )));
+    }
+
+    let event_storage = event_storage(&state.db)?;
+    let previous = event_storage.event_cursor()?;
+    let cursor = event_storage.set_event_cursor(req.last_processed_slot, &req.last_processed_signature)?;
+    tracing::warn!(
+        "⚠️ 事件处理游标已手动设置 / Event processing cursor set by hand: {:?} -> {}",
+        previous.map(|c| c.last_processed_slot),
+        cursor.last_processed_slot
+    );
+
+    Ok(Json(CommonResult::ok(cursor)))
+}
+
+/// 创建数据库管理路由 (需要 API-Key) / Create database admin routes (API key required)
+pub fn admin_routes() -> Router<std::sync::Arc<crate::db::RocksDbStorage>> {
+    Router::new()
+        .route("/db/put", post(db_put))
+        .route("/db/get", post(db_get))
+        .route("/db/delete", post(db_delete))
+        .route("/db/compact", post(db_compact))
+}
+
+/// 创建事件处理游标管理路由 (需要 API-Key) / Create event processing cursor admin routes (API key required)
+pub fn cursor_routes() -> Router<CursorState> {
+    Router::new().route("/admin/cursor", get(get_event_cursor).post(set_event_cursor))
+}
+
+/// 创建解码诊断管理路由 (需要 API-Key) / Create decode diagnostics admin routes (API key required)
+pub fn decode_error_routes() -> Router<Arc<DecodeDiagnostics>> {
+    Router::new().route("/db/decode-errors", get(query_decode_errors))
 }
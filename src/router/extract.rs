@@ -0,0 +1,41 @@
+// 带校验的请求提取器 / Validating request extractors
+//
+// 查询参数先按 serde 反序列化, 再按结构体上的 validator 规则校验; 两步失败都返回 400 参数错误 (error_code 1001),
+// 消息中列出所有无效字段, 处理函数内不再需要零散的手动检查
+// Query parameters are deserialized with serde and then checked against the validator rules on the struct; either
+// failure returns a 400 parameter error (error_code 1001) listing every invalid field, so handlers no longer need
+// scattered manual checks
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Query},
+    http::request::Parts,
+};
+use serde::de::DeserializeOwned;
+use validator::Validate;
+
+use crate::util::result::ApiError;
+use crate::util::validate::describe_errors;
+
+/// 反序列化并校验查询参数 / Deserialize and validate query parameters
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidatedQuery<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for ValidatedQuery<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(params) = Query::<T>::from_request_parts(parts, state)
+            .await
+            .map_err(|rejection| ApiError::RequestParamError(rejection.body_text()))?;
+        params
+            .validate()
+            .map_err(|errors| ApiError::RequestParamError(describe_errors(&errors)))?;
+        Ok(Self(params))
+    }
+}
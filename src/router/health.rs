@@ -1,28 +1,68 @@
-use axum::{routing::get, Router};
-use serde::{Deserialize, Serialize};
+use axum::{extract::State, routing::get, Router};
+use serde::Serialize;
 use serde_json::json;
+use std::sync::Arc;
+use tracing::warn;
 use utoipa::ToSchema;
 
+use crate::db::{EventStorage, ReplicaStatus, ReplicationReport};
+use crate::solana::SolanaClient;
+use crate::util::time::unix_now;
 use crate::util::{ok_result, ApiResult};
 
+/// 缓存的链上 slot 在该秒数内视为最新, 否则重新查询 / A cached chain slot younger than this (seconds) is reused, otherwise re-queried
+const CHAIN_SLOT_MAX_AGE_SECS: i64 = 5;
+
+/// 查询链上 slot 的超时, 超时不影响存活探测 / Timeout of the chain slot lookup; a timeout does not fail the liveness probe
+const CHAIN_SLOT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// 健康检查的共享状态 / Shared state for the health check
+#[derive(Clone)]
+pub struct HealthState {
+    pub event_storage: Arc<EventStorage>,
+    /// 用于读取链上最新 slot / Used to read the chain tip slot
+    pub solana_client: Arc<SolanaClient>,
+    /// 只读副本的追赶状态, 主实例为空 / Catch-up status of a read replica, None on the primary
+    pub replica_status: Option<Arc<ReplicaStatus>>,
+}
+
 /// Health check 响应数据
-#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[derive(Debug, Serialize, ToSchema)]
 #[schema(
     title = "HealthResponse",
     description = "健康检查响应数据",
     example = json!({
         "status": "ok",
-        "version": "0.1.0"
+        "version": "0.1.0",
+        "last_processed_slot": 123456789,
+        "chain_slot": 123456800,
+        "slot_lag": 11,
+        "replication": null
     })
 )]
 pub struct HealthResponse {
-    /// 服务状态
+    /// 服务状态: ok, 或只读副本追赶失败时为 degraded / Service status: ok, or degraded while a read replica fails to catch up
     #[schema(example = "ok")]
     pub status: String,
 
     /// 服务版本
     #[schema(example = "0.1.0")]
     pub version: String,
+
+    /// 本实例可见的最高已索引 slot; 副本上即主实例在上次成功追赶时已处理到的 slot
+    /// Highest indexed slot visible to this instance; on a replica, the slot the primary had processed at the last successful catch-up
+    pub last_processed_slot: Option<u64>,
+
+    /// 链上最新 slot, RPC 不可用时为空 / Chain tip slot, null when RPC is unavailable
+    pub chain_slot: Option<u64>,
+
+    /// 链上最新 slot 减去本实例可见的事件游标 slot; 副本上包含追赶滞后和主实例的处理滞后
+    /// Chain tip slot minus the event cursor slot visible to this instance; on a replica it includes both the catch-up
+    /// lag and the primary's own processing lag
+    pub slot_lag: Option<u64>,
+
+    /// 只读副本的追赶状态, 主实例为空 / Catch-up status of a read replica, null on the primary
+    pub replication: Option<ReplicationReport>,
 }
 
 /// Health check 接口
@@ -31,7 +71,7 @@ pub struct HealthResponse {
     path = "/health",
     tag = "system",
     summary = "健康检查",
-    description = "检查服务是否正常运行",
+    description = "检查服务是否正常运行; slot_lag 为链上最新 slot 与本实例可见的事件游标之差; 只读副本额外返回追赶状态, catch_up_age_ms 即副本数据的滞后上限 / Checks the service is running; slot_lag is the chain tip minus the event cursor visible to this instance; read replicas also report catch-up status, where catch_up_age_ms bounds the replica's staleness",
     responses(
         (status = 200, description = "服务正常",
          body = crate::docs::ApiResponse<HealthResponse>),
@@ -45,16 +85,63 @@ pub struct HealthResponse {
         )
     )
 )]
-pub async fn health() -> ApiResult {
+pub async fn health(State(state): State<HealthState>) -> ApiResult {
+    // 读取失败不影响存活探测 / A failed read must not fail the liveness probe
+    let last_processed_slot = state.event_storage.latest_indexed_slot().unwrap_or_else(|e| {
+        warn!("⚠️ 读取最高已索引 slot 失败 / Failed to read the highest indexed slot: {}", e);
+        None
+    });
+    let cursor_slot = match state.event_storage.event_cursor() {
+        Ok(cursor) => cursor.map(|c| c.last_processed_slot).or(last_processed_slot),
+        Err(e) => {
+            warn!("⚠️ 读取事件游标失败 / Failed to read the event cursor: {}", e);
+            last_processed_slot
+        }
+    };
+    let chain_slot = chain_slot(&state.solana_client).await;
+    let slot_lag = chain_slot.zip(cursor_slot).map(|(tip, cursor)| tip.saturating_sub(cursor));
+
+    let replication = state
+        .replica_status
+        .as_ref()
+        .map(|status| status.report(chrono::Utc::now().timestamp_millis()));
+    let degraded = replication.as_ref().is_some_and(|r| r.consecutive_failures > 0);
+
     let response = HealthResponse {
-        status: "ok".to_string(),
+        status: if degraded { "degraded" } else { "ok" }.to_string(),
         version: env!("CARGO_PKG_VERSION").to_string(),
+        last_processed_slot,
+        chain_slot,
+        slot_lag,
+        replication,
     };
 
     Ok(ok_result(Ok(response)))
 }
 
+/// 链上最新 slot: 优先用客户端最近观察到的 slot, 过旧时带超时查询 getSlot, 失败时退回旧值
+/// Chain tip slot: the client's latest observed slot when fresh, otherwise getSlot with a timeout, falling back to the stale value on failure
+async fn chain_slot(client: &SolanaClient) -> Option<u64> {
+    let cached = client.chain_clock();
+    if let Some(ref clock) = cached {
+        if unix_now() - clock.slot_observed_at <= CHAIN_SLOT_MAX_AGE_SECS {
+            return Some(clock.slot);
+        }
+    }
+    match tokio::time::timeout(CHAIN_SLOT_TIMEOUT, client.get_slot()).await {
+        Ok(Ok(slot)) => Some(slot),
+        Ok(Err(e)) => {
+            warn!("⚠️ 读取链上 slot 失败 / Failed to read the chain slot: {}", e);
+            cached.map(|clock| clock.slot)
+        }
+        Err(_) => {
+            warn!("⚠️ 读取链上 slot 超时 / Timed out reading the chain slot");
+            cached.map(|clock| clock.slot)
+        }
+    }
+}
+
 /// 创建健康检查路由
-pub fn routes() -> Router {
+pub fn routes() -> Router<HealthState> {
     Router::new().route("/health", get(health))
 }
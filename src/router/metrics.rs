@@ -0,0 +1,97 @@
+// 运行指标接口 / Runtime metrics endpoint
+
+use axum::{extract::State, routing::get, Router};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+use crate::db::OrderBookStorage;
+use crate::kline::{KlineAggregatorMetrics, KlineAggregators, KlineConnectionMetrics, KlineConnectionStats};
+use crate::solana::client::{RpcEndpointHealth, RpcRetryMetrics};
+use crate::solana::{
+    DryRunEventHandler, DryRunMetrics, EventQueue, EventQueueMetrics, FinalityGate, FinalityMetrics,
+    PersistedEventCounts, SolanaClient,
+};
+use crate::util::{ok_result, ApiResult};
+use crate::webhook::{WebhookDispatcher, WebhookMetrics};
+
+/// 指标接口状态 / Metrics endpoint state
+#[derive(Clone)]
+pub struct MetricsState {
+    pub solana_client: Arc<SolanaClient>,
+    pub orderbook_storage: Arc<OrderBookStorage>,
+    /// 未启用 Webhook 时为空 / None when webhooks are disabled
+    pub webhook: Option<Arc<WebhookDispatcher>>,
+    /// 事件处理队列, 未启用监听器时为空 / Event processing queues, empty when the listener is disabled
+    pub event_queues: Vec<EventQueue>,
+    /// 演练模式处理器, 未启用时为空 / Dry-run handler, None when dry run is disabled
+    pub dry_run: Option<Arc<DryRunEventHandler>>,
+    /// 终结性闸门, 未启用时为空 / Finality gate, None when disabled
+    pub finality: Option<Arc<FinalityGate>>,
+    /// K线连接统计, 未启用K线服务时为空 / K-line connection stats, None when the K-line service is disabled
+    pub kline: Option<Arc<KlineConnectionStats>>,
+    /// K线聚合器, 未启用K线服务时为空 / Candle aggregators, None when the K-line service is disabled
+    pub kline_aggregators: Option<Arc<KlineAggregators>>,
+    /// 按类型统计的已持久化事件数 (事件观察者) / Persisted events per type (an event observer)
+    pub persisted_events: Arc<PersistedEventCounts>,
+}
+
+/// 运行指标响应 / Runtime metrics response
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(title = "MetricsResponse", description = "运行指标 / Runtime metrics")]
+pub struct MetricsResponse {
+    /// RPC节点健康状态 / RPC endpoint health
+    pub rpc_endpoints: Vec<RpcEndpointHealth>,
+    /// RPC重试统计 / RPC retry metrics
+    pub rpc_retries: RpcRetryMetrics,
+    /// Webhook 投递统计, 未启用时为空 / Webhook delivery metrics, null when disabled
+    pub webhooks: Option<WebhookMetrics>,
+    /// 事件队列积压 (ingest / kline) / Event queue backlog (ingest / kline)
+    pub event_queues: Vec<EventQueueMetrics>,
+    /// 演练模式统计, 未启用时为空 / Dry-run metrics, null when dry run is disabled
+    pub dry_run: Option<DryRunMetrics>,
+    /// 终结性闸门统计 (缓冲/待终结/回滚), 未启用时为空 / Finality gate metrics (buffered, unsettled, rolled back), null when disabled
+    pub finality: Option<FinalityMetrics>,
+    /// K线 WebSocket 连接数与断开原因统计, 未启用时为空 / K-line WebSocket connection gauge and disconnect reasons, null when disabled
+    pub kline_connections: Option<KlineConnectionMetrics>,
+    /// 驻留内存的K线聚合器数与 LRU 淘汰统计, 未启用时为空 / Resident candle aggregators and LRU evictions, null when disabled
+    pub kline_aggregators: Option<KlineAggregatorMetrics>,
+    /// 因订单簿已满被拒绝的开仓插入数, 大于0说明有市场无法再开仓 / Open-position inserts rejected because the book was full; above 0 means some market cannot take new positions
+    pub orderbook_capacity_rejections: u64,
+    /// 本次启动以来按事件类型统计的已持久化事件数 / Events persisted since startup, per event type
+    pub persisted_events: BTreeMap<String, u64>,
+}
+
+/// 获取运行指标 / Get runtime metrics
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "system",
+    summary = "运行指标 / Runtime metrics",
+    description = "返回 RPC 节点池的健康状态与重试统计、Webhook 投递统计、事件队列积压、演练模式统计、终结性闸门统计、K线连接统计、K线聚合器数量、订单簿满载拒绝数和按类型统计的已持久化事件数 / Returns RPC endpoint pool health and retry counts, webhook delivery metrics, event queue backlog, dry-run metrics, finality gate metrics, K-line connection metrics, the candle aggregator count, order book capacity rejections and persisted events per type",
+    responses(
+        (status = 200, description = "获取成功 / Success",
+         body = crate::docs::ApiResponse<MetricsResponse>),
+    )
+)]
+pub async fn get_metrics(State(state): State<MetricsState>) -> ApiResult {
+    let response = MetricsResponse {
+        rpc_endpoints: state.solana_client.endpoint_health(),
+        rpc_retries: state.solana_client.retry_metrics(),
+        webhooks: state.webhook.as_ref().map(|w| w.metrics()),
+        event_queues: state.event_queues.iter().map(|q| q.metrics()).collect(),
+        dry_run: state.dry_run.as_ref().map(|d| d.metrics()),
+        finality: state.finality.as_ref().map(|f| f.metrics()),
+        kline_connections: state.kline.as_ref().map(|k| k.metrics()),
+        kline_aggregators: state.kline_aggregators.as_ref().map(|a| a.metrics()),
+        orderbook_capacity_rejections: state.orderbook_storage.capacity_rejections(),
+        persisted_events: state.persisted_events.snapshot(),
+    };
+    Ok(ok_result(Ok(response)))
+}
+
+/// 创建指标路由 / Create metrics routes
+pub fn routes() -> Router<MetricsState> {
+    Router::new().route("/metrics", get(get_metrics))
+}
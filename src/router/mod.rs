@@ -1,27 +1,158 @@
+pub mod auth;
+pub mod concurrency;
+pub mod cors;
 pub mod db;
+pub mod extract;
 pub mod health;
+pub mod metrics;
 pub mod orderbook;
 pub mod orderbook_history;
+pub mod rate_limit;
+pub mod request_id;
+pub mod reprocess;
+pub mod time;
 pub mod token;
 
-use axum::Router;
+use axum::{extract::DefaultBodyLimit, middleware, Router};
 use std::sync::Arc;
 
+use crate::config::{LiveConfig, RunMode, ServerConfig, SolanaConfig};
+use auth::AdminKeys;
+use concurrency::ConcurrencyLimiter;
+use rate_limit::RateLimiter;
+
+/// 为路由组挂载限流中间件 / Attach the rate limiting middleware to a route group
+fn with_rate_limit(router: Router, live: &LiveConfig, group: &str) -> Router {
+    router.layer(middleware::from_fn_with_state(
+        RateLimiter::for_group(live.clone(), group),
+        rate_limit::rate_limit,
+    ))
+}
+
 /// 创建所有路由
+#[allow(clippy::too_many_arguments)]
 pub fn create_router(
     db: Arc<crate::db::RocksDbStorage>,
     token_storage: Arc<crate::db::TokenStorage>,
+    event_storage: Arc<crate::db::EventStorage>,
     orderbook_storage: Arc<crate::db::OrderBookStorage>,
+    solana_client: Arc<crate::solana::SolanaClient>,
+    webhook: Option<Arc<crate::webhook::WebhookDispatcher>>,
+    event_queues: Vec<crate::solana::EventQueue>,
+    dry_run: Option<Arc<crate::solana::DryRunEventHandler>>,
+    finality: Option<Arc<crate::solana::FinalityGate>>,
+    kline_connections: Option<Arc<crate::kline::KlineConnectionStats>>,
+    kline_aggregators: Option<Arc<crate::kline::KlineAggregators>>,
+    decode_diagnostics: Arc<crate::solana::DecodeDiagnostics>,
+    persisted_events: Arc<crate::solana::PersistedEventCounts>,
+    event_observers: Arc<crate::solana::EventObservers>,
+    server: &ServerConfig,
+    solana: &SolanaConfig,
+    live: LiveConfig,
+    mode: RunMode,
+    replica_status: Option<Arc<crate::db::ReplicaStatus>>,
 ) -> Router {
     // 创建 Token 状态
     let token_state = token::TokenState {
         token_storage: token_storage.clone(),
+        event_storage: event_storage.clone(),
+        curve_storage: Arc::new(db.create_curve_storage()),
+    };
+
+    // 配置校验已保证程序ID合法 / Config validation guarantees a valid program id
+    let orderbook_program_id = solana.program_id.parse().unwrap_or_default();
+
+    let orderbook_state = orderbook::OrderBookState {
+        orderbook_storage: orderbook_storage.clone(),
+        token_storage: token_storage.clone(),
+        event_storage: event_storage.clone(),
+        solana_client: solana_client.clone(),
+        program_id: orderbook_program_id,
+    };
+
+    // 受保护的管理子路由, 需要 X-API-Key; 只读副本不挂载 (写库、重处理)
+    // Protected admin sub-router, requires X-API-Key; not mounted on read replicas (DB writes, reprocessing)
+    let admin_router = if mode == RunMode::Primary {
+        let admin_keys = AdminKeys::new(&server.admin_keys);
+        if admin_keys.is_empty() {
+            tracing::warn!("⚠️ 未配置 admin_keys, 管理接口将拒绝所有请求 / No admin_keys configured, admin endpoints will reject all requests");
+        }
+        let admin_router = db::admin_routes()
+            .with_state(db.clone())
+            .merge(db::decode_error_routes().with_state(decode_diagnostics))
+            .merge(db::cursor_routes().with_state(db::CursorState {
+                db: db.clone(),
+                solana_client: solana_client.clone(),
+            }))
+            .merge(reprocess::admin_routes().with_state(reprocess::ReprocessState::new(
+                db.clone(),
+                orderbook_storage.clone(),
+                solana_client.clone(),
+                event_observers,
+                solana,
+            )))
+            .merge(orderbook::admin_routes().with_state(orderbook_state.clone()))
+            .layer(DefaultBodyLimit::max(db::kv_body_limit(db.database_config())))
+            .layer(middleware::from_fn_with_state(admin_keys, auth::require_api_key));
+        with_rate_limit(admin_router, &live, "admin")
+    } else {
+        Router::new()
     };
 
+    // /health 不限流, 其余路由组各自独立限流 / /health is exempt, other route groups are limited independently
     Router::new()
-        .merge(health::routes())
-        .merge(db::routes().with_state(db))
-        .merge(token::routes().with_state(token_state))
-        .merge(orderbook::routes().with_state(orderbook_storage.clone()))
-        .merge(orderbook_history::routes().with_state(orderbook_storage))
+        .merge(with_rate_limit(
+            metrics::routes().with_state(metrics::MetricsState {
+                solana_client: solana_client.clone(),
+                orderbook_storage: orderbook_storage.clone(),
+                webhook,
+                event_queues,
+                dry_run,
+                finality,
+                kline: kline_connections,
+                kline_aggregators,
+                persisted_events,
+            }),
+            &live,
+            "metrics",
+        ))
+        .merge(with_rate_limit(
+            time::routes().with_state(solana_client.clone()),
+            &live,
+            "time",
+        ))
+        .merge(admin_router)
+        .merge(with_rate_limit(db::routes().with_state(db), &live, "db"))
+        .merge(with_rate_limit(token::routes().with_state(token_state), &live, "tokens"))
+        .merge(with_rate_limit(
+            orderbook::routes().with_state(orderbook_state.clone()),
+            &live,
+            "orderbook",
+        ))
+        .merge(with_rate_limit(
+            orderbook::dump_routes().with_state(orderbook_state),
+            &live,
+            "orderbook_dump",
+        ))
+        .merge(with_rate_limit(
+            orderbook_history::routes().with_state(orderbook_history::OrderBookHistoryState {
+                orderbook_storage,
+                event_storage: event_storage.clone(),
+            }),
+            &live,
+            "history",
+        ))
+        // 全局并发上限, 在 /health 合并之前挂载使负载均衡探测不受影响
+        // Global concurrency cap, layered before /health is merged so load balancer probes are unaffected
+        .layer(middleware::from_fn_with_state(
+            ConcurrencyLimiter::new(server.max_concurrent_requests),
+            concurrency::limit_concurrency,
+        ))
+        .merge(health::routes().with_state(health::HealthState {
+            event_storage,
+            solana_client,
+            replica_status,
+        }))
+        // 最外层: 为每个请求生成 request_id / Outermost: assign a request_id to every request
+        .layer(middleware::from_fn(request_id::request_id))
 }
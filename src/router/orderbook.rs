@@ -1,43 +1,102 @@
 // OrderBook 查询接口 / OrderBook query endpoints
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
-    routing::get,
+    body::Body,
+    extract::{Path, State},
+    http::header,
+    response::{IntoResponse, Response},
+    routing::{get, post},
     Json, Router,
 };
 use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::str::FromStr;
 use std::sync::Arc;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 use utoipa::{IntoParams, ToSchema};
 
-use crate::db::OrderBookStorage;
-use crate::orderbook::{MarginOrder, UserOrderQueryService};
-use crate::util::result::CommonResult;
+use crate::db::{EventStorage, GlobalOrderRow, OrderBookMarket, OrderBookStorage, OrderCursor, TokenStorage};
+use crate::orderbook::{
+    build_order_timeline, decode_orderbook_account, export_stream, orderbook_pda, DepthBucket, Direction, MarginOrder,
+    OnchainOrderBookHeader, OrderBookConsistency, OrderBookDBManager, OrderBookError, OrderBookHeader, OrderTimeline,
+    UserOrderQueryService, EXPORT_CHUNK_ORDERS,
+};
+use crate::solana::SolanaClient;
+use crate::util::pnl::{estimate_position_pnl, PositionPnl};
+use crate::util::result::{ApiError, CommonResult};
+use crate::util::PageInfo;
+use crate::util::time::{cooldown_remaining_secs, unix_now, unix_now_u32};
+use crate::util::validate::{validate_direction, validate_mint, validate_mint_field};
+use validator::Validate;
+
+use super::extract::ValidatedQuery;
+
+/// OrderBook 查询的共享状态 / Shared state for OrderBook queries
+#[derive(Clone)]
+pub struct OrderBookState {
+    pub orderbook_storage: Arc<OrderBookStorage>,
+    /// 用于读取各 mint 的当前价格 / Used to read each mint's current price
+    pub token_storage: Arc<TokenStorage>,
+    /// 用于读取订单的事件历史 / Used to read an order's event history
+    pub event_storage: Arc<EventStorage>,
+    /// 用于读取链上订单簿账户 / Used to read on-chain order book accounts
+    pub solana_client: Arc<SolanaClient>,
+    /// 推导订单簿 PDA 的程序ID / Program id the order book PDAs are derived from
+    pub program_id: Pubkey,
+}
 
 /// 创建 OrderBook 路由 / Create OrderBook routes
-pub fn routes() -> Router<Arc<OrderBookStorage>> {
+pub fn routes() -> Router<OrderBookState> {
     Router::new()
         .route("/api/orderbook/:mint/:direction", get(query_orderbook))
         .route("/api/orderbook/user/:user_address/active", get(get_user_active_orders))
+        .route("/api/orderbook/user/positions", get(get_user_positions))
+        .route("/api/orderbook/insert-hint", get(get_insert_hint))
+        .route("/api/orderbook/close-hint", get(get_close_hint))
+        .route("/api/orderbook/order/:order_id", get(get_order))
+        .route("/api/orderbook/order/:order_id/timeline", get(get_order_timeline))
+        .route("/api/orderbook/expiring", get(get_expiring_orders))
+        .route("/api/orderbook/expired", get(get_expired_orders))
+        .route("/api/orderbook/find", get(find_orders_by_open_price))
+        .route("/api/orderbook/depth", get(get_orderbook_depth))
+        .route("/api/orderbook/stats", get(get_orderbook_stats))
+        .route("/api/orderbook/summary", get(get_orderbook_summary))
+        .route("/api/orderbook/markets", get(get_markets))
+        .route("/api/orderbook/all", get(get_all_orders))
+}
+
+/// 创建订单簿管理路由 (需要 API-Key) / Create order book admin routes (API key required)
+pub fn admin_routes() -> Router<OrderBookState> {
+    Router::new().route("/admin/orderbook/rebuild", post(rebuild_orderbook))
+}
+
+/// 整本导出路由, 单独限流 / Full export routes, rate limited separately
+pub fn dump_routes() -> Router<OrderBookState> {
+    Router::new()
+        .route("/api/orderbook/dump", get(dump_orderbook))
+        .route("/api/orderbook/onchain", get(get_onchain_orderbook))
 }
 
 /// OrderBook 查询参数 / OrderBook query parameters
-#[derive(Debug, Deserialize, IntoParams, ToSchema)]
+#[derive(Debug, Deserialize, IntoParams, ToSchema, Validate)]
+#[into_params(parameter_in = Query)]
 pub struct OrderBookQueryParams {
     /// 页码(从 1 开始) / Page number (starting from 1)
     #[serde(default = "default_page")]
-    pub page: usize,
+    #[validate(range(min = 1, message = "must be at least 1"))]
+    pub page: u32,
 
-    /// 每页数量(默认 100) / Page size (default 100)
+    /// 每页数量(默认 100, 最大 1000) / Page size (default 100, max 1000)
     #[serde(default = "default_page_size")]
-    pub page_size: usize,
+    #[validate(range(min = 1, max = 1000, message = "must be between 1 and 1000"))]
+    pub page_size: u32,
 }
 
-fn default_page() -> usize {
+fn default_page() -> u32 {
     1
 }
 
-fn default_page_size() -> usize {
+fn default_page_size() -> u32 {
     100
 }
 
@@ -75,6 +134,23 @@ pub struct OrderBookHeaderInfo {
     pub total: u16,
 }
 
+impl From<&OrderBookHeader> for OrderBookHeaderInfo {
+    fn from(header: &OrderBookHeader) -> Self {
+        Self {
+            version: header.version,
+            order_type: header.order_type,
+            authority: header.authority.clone(),
+            order_id_counter: header.order_id_counter,
+            created_at: header.created_at,
+            last_modified: header.last_modified,
+            total_capacity: header.total_capacity,
+            head: header.head,
+            tail: header.tail,
+            total: header.total,
+        }
+    }
+}
+
 /// OrderBook 订单详情(包含索引) / OrderBook order detail (with index)
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct OrderBookOrderDetail {
@@ -135,6 +211,7 @@ pub struct OrderBookQueryResponse {
     ),
     responses(
         (status = 200, description = "查询成功 / Query successful", body = OrderBookQueryResponse),
+        (status = 400, description = "mint、direction 或分页参数无效 / Invalid mint, direction or pagination"),
         (status = 404, description = "OrderBook 不存在 / OrderBook not found"),
         (status = 500, description = "服务器错误 / Server error")
     ),
@@ -142,40 +219,28 @@ pub struct OrderBookQueryResponse {
 )]
 pub async fn query_orderbook(
     Path((mint, direction)): Path<(String, String)>,
-    Query(params): Query<OrderBookQueryParams>,
-    State(orderbook_storage): State<Arc<OrderBookStorage>>,
-) -> Result<Json<CommonResult<OrderBookQueryResponse>>, (StatusCode, String)> {
+    ValidatedQuery(params): ValidatedQuery<OrderBookQueryParams>,
+    State(state): State<OrderBookState>,
+) -> Result<Json<CommonResult<OrderBookQueryResponse>>, ApiError> {
     info!(
         "📊 查询 OrderBook / Query OrderBook: mint={}, direction={}, page={}, page_size={}",
         &mint[..8.min(mint.len())], direction, params.page, params.page_size
     );
 
+    validate_mint(&mint)?;
+
     // 验证 direction 参数 / Validate direction parameter
-    if direction != "up" && direction != "dn" {
-        error!("❌ 无效的 direction 参数 / Invalid direction parameter: {}", direction);
-        return Err((
-            StatusCode::BAD_REQUEST,
-            format!("Invalid direction: {}, expected 'up' or 'dn'", direction),
-        ));
-    }
+    let direction = parse_direction(&direction)?;
 
-    // 验证分页参数 / Validate pagination parameters
-    let page = if params.page < 1 { 1 } else { params.page };
-    let page_size = if params.page_size < 1 {
-        100
-    } else {
-        params.page_size
-    };
+    let page = params.page as usize;
+    let page_size = params.page_size as usize;
 
     // 获取 OrderBook 管理器 / Get OrderBook manager
-    let manager = match orderbook_storage.get_or_create_manager(mint.clone(), direction.clone()) {
+    let manager = match state.orderbook_storage.get_or_create_manager(mint.clone(), direction) {
         Ok(m) => m,
         Err(e) => {
             error!("❌ 获取 OrderBook 管理器失败 / Failed to get OrderBook manager: {}", e);
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to get OrderBook manager: {}", e),
-            ));
+            return Err(ApiError::Storage(format!("Failed to get OrderBook manager: {}", e)));
         }
     };
 
@@ -184,33 +249,19 @@ pub async fn query_orderbook(
         Ok(h) => h,
         Err(e) => {
             error!("❌ 加载 OrderBook header 失败 / Failed to load OrderBook header: {}", e);
-            return Err((
-                StatusCode::NOT_FOUND,
-                format!("OrderBook not found: {}:{}", mint, direction),
-            ));
+            return Err(orderbook_error("Failed to load OrderBook", e));
         }
     };
 
     // 构造 header 响应 / Construct header response
-    let header_info = OrderBookHeaderInfo {
-        version: header.version,
-        order_type: header.order_type,
-        authority: header.authority.clone(),
-        order_id_counter: header.order_id_counter,
-        created_at: header.created_at,
-        last_modified: header.last_modified,
-        total_capacity: header.total_capacity,
-        head: header.head,
-        tail: header.tail,
-        total: header.total,
-    };
+    let header_info = OrderBookHeaderInfo::from(&header);
 
     // 计算分页 / Calculate pagination
     let total_count = header.total;
     let total_pages = if total_count == 0 {
         0
     } else {
-        ((total_count as usize + page_size - 1) / page_size).max(1)
+        (total_count as usize).div_ceil(page_size)
     };
 
     // 如果链表为空,直接返回 / If linked list is empty, return directly
@@ -228,7 +279,7 @@ pub async fn query_orderbook(
     }
 
     // 计算起始位置 / Calculate start position
-    let skip = (page - 1) * page_size;
+    let skip = (page - 1).saturating_mul(page_size);
 
     // 收集订单 / Collect orders
     let mut orders = Vec::new();
@@ -266,10 +317,7 @@ pub async fn query_orderbook(
     // 检查遍历结果 / Check traverse result
     if let Err(e) = traverse_result {
         error!("❌ 遍历 OrderBook 失败 / Failed to traverse OrderBook: {}", e);
-        return Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Failed to traverse OrderBook: {}", e),
-        ));
+        return Err(orderbook_error("Failed to traverse OrderBook", e));
     }
 
     let returned_count = orders.len();
@@ -293,23 +341,27 @@ pub async fn query_orderbook(
 // ==================== 用户活跃订单查询 / User Active Orders Query ====================
 
 /// 用户活跃订单查询参数 / User active orders query parameters
-#[derive(Debug, Deserialize, IntoParams, ToSchema)]
+#[derive(Debug, Deserialize, IntoParams, ToSchema, Validate)]
 #[into_params(parameter_in = Query)]
 pub struct UserActiveOrdersParams {
     /// 可选: 按 mint 过滤 / Optional: Filter by mint
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[validate(custom(function = "validate_mint_field"))]
     pub mint: Option<String>,
 
     /// 可选: 按方向过滤 ("up" 或 "dn") / Optional: Filter by direction ("up" or "dn")
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[validate(custom(function = "validate_direction"))]
     pub direction: Option<String>,
 
     /// 页码(从 1 开始) / Page number (starts from 1)
     #[serde(default = "default_user_page")]
+    #[validate(range(min = 1, message = "must be at least 1"))]
     pub page: u32,
 
-    /// 每页数量 / Page size
+    /// 每页数量(默认 20, 最大 100) / Page size (default 20, max 100)
     #[serde(default = "default_user_page_size")]
+    #[validate(range(min = 1, max = 100, message = "must be between 1 and 100"))]
     pub page_size: u32,
 }
 
@@ -334,6 +386,17 @@ pub struct UserActiveOrderItem {
     /// Order index in linked list (dynamic, changes with delete operations, should not be cached)
     pub index: u16,
 
+    /// 按当前价格估算的平仓价值与未实现盈亏, 价格未知时为空
+    /// Liquidation value and unrealized PnL at the current price, null if price unknown
+    pub pnl: Option<PositionPnl>,
+
+    /// 平仓冷却剩余秒数, 按服务器时间计算 / Remaining close cooldown in seconds, computed from server time
+    pub cooldown_remaining_secs: u32,
+
+    /// 冷却已结束, 平仓不会因冷却失败 (到期、止盈等其他规则仍然适用)
+    /// Cooldown is over, a close will not revert for cooldown (other rules such as expiry and take-profit still apply)
+    pub closable: bool,
+
     /// 订单完整数据 / Complete order data
     #[serde(flatten)]
     pub order: MarginOrder,
@@ -342,17 +405,12 @@ pub struct UserActiveOrderItem {
 /// 用户活跃订单响应 / User active orders response
 #[derive(Debug, Serialize, ToSchema)]
 pub struct UserActiveOrdersResponse {
-    /// 总订单数 / Total order count
-    pub total: u32,
-
     /// 当前页订单列表 / Current page order list
     pub orders: Vec<UserActiveOrderItem>,
 
-    /// 当前页码 / Current page
-    pub page: u32,
-
-    /// 每页数量 / Page size
-    pub page_size: u32,
+    /// 分页信息 / Pagination fields
+    #[serde(flatten)]
+    pub pagination: PageInfo,
 }
 
 /// 查询用户活跃订单 / Query user active orders
@@ -365,10 +423,11 @@ pub struct UserActiveOrdersResponse {
 /// - `mint`: 可选,按 mint 过滤 / Optional, filter by mint
 /// - `direction`: 可选,按方向过滤 ("up" 或 "dn") / Optional, filter by direction ("up" or "dn")
 /// - `page`: 页码(从 1 开始,默认 1) / Page number (starting from 1, default 1)
-/// - `page_size`: 每页数量(默认 20) / Page size (default 20)
+/// - `page_size`: 每页数量(默认 20,最大 100) / Page size (default 20, max 100)
 ///
 /// # 返回值 / Returns
-/// 返回用户的活跃订单列表 / Returns user's active order list
+/// 返回用户的活跃订单列表, 每个订单附带按当前价格估算的未实现盈亏
+/// Returns user's active order list, each with unrealized PnL at the current price
 #[utoipa::path(
     get,
     path = "/api/orderbook/user/{user_address}/active",
@@ -385,9 +444,9 @@ pub struct UserActiveOrdersResponse {
 )]
 pub async fn get_user_active_orders(
     Path(user_address): Path<String>,
-    Query(params): Query<UserActiveOrdersParams>,
-    State(orderbook_storage): State<Arc<OrderBookStorage>>,
-) -> Result<Json<CommonResult<UserActiveOrdersResponse>>, (StatusCode, String)> {
+    ValidatedQuery(params): ValidatedQuery<UserActiveOrdersParams>,
+    State(state): State<OrderBookState>,
+) -> Result<Json<CommonResult<UserActiveOrdersResponse>>, ApiError> {
     info!(
         "👤 查询用户活跃订单 / Query user active orders: user={}, mint={:?}, direction={:?}, page={}, page_size={}",
         &user_address[..8.min(user_address.len())],
@@ -397,72 +456,1521 @@ pub async fn get_user_active_orders(
         params.page_size
     );
 
-    // 验证分页参数 / Validate pagination parameters
-    let page = if params.page < 1 { 1 } else { params.page };
-    let page_size = if params.page_size < 1 {
-        20
-    } else if params.page_size > 100 {
-        100
-    } else {
-        params.page_size
-    };
+    let page = params.page;
+    let page_size = params.page_size;
 
     // 验证 direction 参数 / Validate direction parameter
-    if let Some(ref direction) = params.direction {
-        if direction != "up" && direction != "dn" {
-            error!("❌ 无效的 direction 参数 / Invalid direction parameter: {}", direction);
-            return Err((
-                StatusCode::BAD_REQUEST,
-                format!("Invalid direction: {}, expected 'up' or 'dn'", direction),
-            ));
-        }
-    }
+    let direction = params.direction.as_deref().map(parse_direction).transpose()?;
 
     // 创建查询服务 / Create query service
-    let query_service = UserOrderQueryService::new(orderbook_storage.db());
+    let query_service = UserOrderQueryService::new(state.orderbook_storage.db());
 
     // 查询用户活跃订单 / Query user active orders
     let (total, orders) = match query_service.query_user_active_orders(
         &user_address,
         params.mint.as_deref(),
-        params.direction.as_deref(),
+        direction.as_ref().map(Direction::as_str),
         page,
         page_size,
     ) {
         Ok(result) => result,
         Err(e) => {
             error!("❌ 查询用户活跃订单失败 / Failed to query user active orders: {}", e);
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Query failed: {}", e),
-            ));
+            return Err(orderbook_error("Query failed", e));
         }
     };
 
-    // 构建响应 / Construct response
+    // 构建响应, 每个 mint 只读取一次当前价格 / Construct response, reading each mint's price only once
+    let mut prices: HashMap<String, Option<u128>> = HashMap::new();
+    let now = unix_now_u32();
     let items: Vec<UserActiveOrderItem> = orders
         .into_iter()
-        .map(|(mint, direction, index, order)| UserActiveOrderItem {
-            mint,
-            direction,
-            index,
-            order,
+        .map(|(mint, direction, index, order)| {
+            let price = *prices
+                .entry(mint.clone())
+                .or_insert_with(|| current_price(&state.token_storage, &mint));
+            let cooldown_remaining_secs = cooldown_remaining_secs(order.start_time, now);
+            UserActiveOrderItem {
+                pnl: price.and_then(|p| estimate_position_pnl(&order, p)),
+                cooldown_remaining_secs,
+                closable: cooldown_remaining_secs == 0,
+                mint,
+                direction,
+                index,
+                order,
+            }
         })
         .collect();
 
-    let response = UserActiveOrdersResponse {
+    info!(
+        "✅ 查询成功 / Query successful: user={}, total={}, returned={}",
+        &user_address[..8.min(user_address.len())],
         total,
+        items.len()
+    );
+
+    Ok(Json(CommonResult::ok(UserActiveOrdersResponse {
         orders: items,
-        page,
-        page_size,
+        pagination: PageInfo::numbered(page, page_size, total as u64),
+    })))
+}
+
+// ==================== 用户全局持仓查询 / User Global Positions Query ====================
+
+/// 用户全局持仓查询参数 / User global positions query parameters
+#[derive(Debug, Deserialize, IntoParams, ToSchema, Validate)]
+#[into_params(parameter_in = Query)]
+pub struct UserPositionsParams {
+    /// 用户地址 / User address
+    #[validate(length(min = 1, message = "must not be empty"))]
+    pub user: String,
+
+    /// 页码(从 1 开始) / Page number (starts from 1)
+    #[serde(default = "default_user_page")]
+    #[validate(range(min = 1, message = "must be at least 1"))]
+    pub page: u32,
+
+    /// 每页数量(默认 20, 最大 100) / Page size (default 20, max 100)
+    #[serde(default = "default_user_page_size")]
+    #[validate(range(min = 1, max = 100, message = "must be between 1 and 100"))]
+    pub page_size: u32,
+}
+
+/// 用户持仓响应项 / User position response item
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UserPositionItem {
+    /// Mint 地址 / Mint address
+    pub mint: String,
+
+    /// 订单方向: "up" 或 "dn" / Order direction: "up" or "dn"
+    pub direction: String,
+
+    /// 订单在链表中的当前索引 (动态,不应缓存)
+    /// Order index in linked list (dynamic, should not be cached)
+    pub index: u16,
+
+    /// 该 mint 的当前价格(u128 字符串),未知时为空
+    /// Current price of the mint (u128 as string), null if unknown
+    pub current_price: Option<String>,
+
+    /// 按当前价格估算的平仓价值与未实现盈亏, 价格未知时为空
+    /// Liquidation value and unrealized PnL at the current price, null if price unknown
+    pub pnl: Option<PositionPnl>,
+
+    /// 订单完整数据 / Complete order data
+    #[serde(flatten)]
+    pub order: MarginOrder,
+}
+
+/// 用户全局持仓响应 / User global positions response
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UserPositionsResponse {
+    /// 当前页持仓列表 / Current page position list
+    pub positions: Vec<UserPositionItem>,
+
+    /// 分页信息 / Pagination fields
+    #[serde(flatten)]
+    pub pagination: PageInfo,
+}
+
+/// 查询用户在所有 Token 上的持仓 / Query user's positions across all tokens
+///
+/// 基于全局持仓索引列出用户的全部未平仓保证金订单,并按各 mint 当前价格附带未实现盈亏
+/// Lists all open margin orders of a user via the global position index, with unrealized PnL at each mint's current price
+///
+/// # 参数 / Parameters
+/// - `user`: 用户地址 / User address
+/// - `page`: 页码(从 1 开始,默认 1) / Page number (starting from 1, default 1)
+/// - `page_size`: 每页数量(默认 20,最大 100) / Page size (default 20, max 100)
+///
+/// # 返回值 / Returns
+/// 返回用户的持仓列表 / Returns user's position list
+#[utoipa::path(
+    get,
+    path = "/api/orderbook/user/positions",
+    params(UserPositionsParams),
+    responses(
+        (status = 200, description = "查询成功 / Query successful", body = UserPositionsResponse),
+        (status = 400, description = "参数错误 / Bad Request"),
+        (status = 500, description = "服务器错误 / Server Error")
+    ),
+    tag = "OrderBook"
+)]
+pub async fn get_user_positions(
+    ValidatedQuery(params): ValidatedQuery<UserPositionsParams>,
+    State(state): State<OrderBookState>,
+) -> Result<Json<CommonResult<UserPositionsResponse>>, ApiError> {
+    info!(
+        "👤 查询用户全局持仓 / Query user global positions: user={}, page={}, page_size={}",
+        &params.user[..8.min(params.user.len())],
+        params.page,
+        params.page_size
+    );
+
+    let page = params.page;
+    let page_size = params.page_size;
+
+    let query_service = UserOrderQueryService::new(state.orderbook_storage.db());
+    let (total, orders) = match query_service.query_user_global_positions(&params.user, page, page_size) {
+        Ok(result) => result,
+        Err(e) => {
+            error!("❌ 查询用户全局持仓失败 / Failed to query user global positions: {}", e);
+            return Err(orderbook_error("Query failed", e));
+        }
     };
 
+    // 每个 mint 只读取一次当前价格 / Read each mint's current price only once
+    let mut prices: HashMap<String, Option<u128>> = HashMap::new();
+    let mut positions = Vec::with_capacity(orders.len());
+    for (mint, direction, index, order) in orders {
+        let price = *prices
+            .entry(mint.clone())
+            .or_insert_with(|| current_price(&state.token_storage, &mint));
+
+        positions.push(UserPositionItem {
+            mint,
+            direction,
+            index,
+            current_price: price.map(|p| p.to_string()),
+            pnl: price.and_then(|p| estimate_position_pnl(&order, p)),
+            order,
+        });
+    }
+
     info!(
         "✅ 查询成功 / Query successful: user={}, total={}, returned={}",
-        &user_address[..8.min(user_address.len())],
+        &params.user[..8.min(params.user.len())],
         total,
-        response.orders.len()
+        positions.len()
+    );
+
+    Ok(Json(CommonResult::ok(UserPositionsResponse {
+        positions,
+        pagination: PageInfo::numbered(page, page_size, total as u64),
+    })))
+}
+
+/// 将 OrderBookError 映射为带真实状态码的 HTTP 错误
+/// Map an OrderBookError to an HTTP error carrying its real status code
+fn orderbook_error(context: &str, e: OrderBookError) -> ApiError {
+    ApiError::from_status(e.status_code(), format!("{}: {}", context, e))
+}
+
+/// 在接口边界解析 direction, 无效值返回 400 / Parse direction at the API edge, invalid values return 400
+pub(crate) fn parse_direction(direction: &str) -> Result<Direction, ApiError> {
+    direction.parse().map_err(|e: OrderBookError| {
+        error!("❌ 无效的 direction 参数 / Invalid direction parameter: {}", direction);
+        ApiError::BadRequest(e.to_string())
+    })
+}
+
+/// 从 Token 存储读取 mint 的最新价格 / Read a mint's latest price from token storage
+fn current_price(token_storage: &TokenStorage, mint: &str) -> Option<u128> {
+    match token_storage.get_token_by_mint(mint) {
+        Ok(Some(token)) => token.latest_price.parse().ok(),
+        Ok(None) => None,
+        Err(e) => {
+            warn!("⚠️ 读取 Token 价格失败 / Failed to read token price: mint={}, {}", mint, e);
+            None
+        }
+    }
+}
+
+// ==================== 开仓插入位置推荐 / Open Order Insert Hint ====================
+
+/// 插入位置推荐查询参数 / Insert hint query parameters
+#[derive(Debug, Deserialize, IntoParams, ToSchema, Validate)]
+#[into_params(parameter_in = Query)]
+pub struct InsertHintParams {
+    /// Token mint 地址 / Token mint address
+    #[validate(custom(function = "validate_mint_field"))]
+    pub mint: String,
+
+    /// 订单方向: "up"(做空) 或 "dn"(做多) / Order direction: "up"(short) or "dn"(long)
+    #[validate(custom(function = "validate_direction"))]
+    pub direction: String,
+
+    /// 锁定区间起始价(u128 字符串) / Lock range start price (u128 as string)
+    pub lock_start_price: String,
+
+    /// 锁定区间结束价(u128 字符串) / Lock range end price (u128 as string)
+    pub lock_end_price: String,
+}
+
+/// 插入位置推荐响应 / Insert hint response
+#[derive(Debug, Serialize, ToSchema)]
+pub struct InsertHintResponse {
+    /// 按可能性排序的 close_insert_indices (65535 = 插入到头部), 为空表示区间与已有订单重叠
+    /// close_insert_indices ordered by likelihood (65535 = insert at head), empty means the range overlaps existing orders
+    pub close_insert_indices: Vec<u16>,
+
+    /// 订单簿当前订单数 / Current order count of the order book
+    pub total: u16,
+}
+
+/// 推荐开仓的 close_insert_indices / Suggest close_insert_indices for opening an order
+///
+/// 按链上 long_trade / short_trade 的重叠规则, 在镜像订单簿中查找新订单的插入位置
+/// Finds the insert position of a new order in the mirrored order book using the overlap rules of on-chain long_trade / short_trade
+///
+/// # 参数 / Parameters
+/// - `mint`: Token mint 地址 / Token mint address
+/// - `direction`: "dn"(做多, start > end) 或 "up"(做空, start < end) / "dn"(long, start > end) or "up"(short, start < end)
+/// - `lock_start_price` / `lock_end_price`: 新订单锁定区间 / Lock range of the new order
+///
+/// # 返回值 / Returns
+/// 最多 21 个候选索引, 可直接作为 close_insert_indices 使用
+/// Up to 21 candidate indices, usable directly as close_insert_indices
+#[utoipa::path(
+    get,
+    path = "/api/orderbook/insert-hint",
+    params(InsertHintParams),
+    responses(
+        (status = 200, description = "查询成功 / Query successful", body = InsertHintResponse),
+        (status = 400, description = "参数错误 / Bad Request"),
+        (status = 404, description = "OrderBook 不存在 / OrderBook not found"),
+        (status = 500, description = "服务器错误 / Server Error")
+    ),
+    tag = "OrderBook"
+)]
+pub async fn get_insert_hint(
+    ValidatedQuery(params): ValidatedQuery<InsertHintParams>,
+    State(state): State<OrderBookState>,
+) -> Result<Json<CommonResult<InsertHintResponse>>, ApiError> {
+    info!(
+        "📍 查询插入位置 / Query insert hint: mint={}, direction={}, start={}, end={}",
+        &params.mint[..8.min(params.mint.len())],
+        params.direction,
+        params.lock_start_price,
+        params.lock_end_price
+    );
+
+    let direction = parse_direction(&params.direction)?;
+
+    let parse_price = |name: &str, value: &str| {
+        value.parse::<u128>().map_err(|_| {
+            ApiError::BadRequest(format!("Invalid {}: {}", name, value))
+        })
+    };
+    let lock_start_price = parse_price("lock_start_price", &params.lock_start_price)?;
+    let lock_end_price = parse_price("lock_end_price", &params.lock_end_price)?;
+
+    let manager = state
+        .orderbook_storage
+        .get_or_create_manager(params.mint.clone(), direction)
+        .map_err(|e| {
+            error!("❌ 获取 OrderBook 管理器失败 / Failed to get OrderBook manager: {}", e);
+            ApiError::Storage(format!("Failed to get OrderBook manager: {}", e))
+        })?;
+
+    let header = manager
+        .load_header()
+        .map_err(|e| orderbook_error("Failed to load OrderBook", e))?;
+
+    let close_insert_indices = manager
+        .suggest_insert_indices(lock_start_price, lock_end_price)
+        .map_err(|e| {
+            error!("❌ 计算插入位置失败 / Failed to suggest insert indices: {}", e);
+            orderbook_error("Failed to suggest insert indices", e)
+        })?;
+
+    Ok(Json(CommonResult::ok(InsertHintResponse {
+        close_insert_indices,
+        total: header.total,
+    })))
+}
+
+// ==================== 平仓索引推荐 / Close Order Index Hint ====================
+
+/// 平仓索引推荐查询参数 / Close hint query parameters
+#[derive(Debug, Deserialize, IntoParams, ToSchema, Validate)]
+#[into_params(parameter_in = Query)]
+pub struct CloseHintParams {
+    /// Token mint 地址 / Token mint address
+    #[validate(custom(function = "validate_mint_field"))]
+    pub mint: String,
+
+    /// 订单方向: "up"(做空) 或 "dn"(做多) / Order direction: "up"(short) or "dn"(long)
+    #[validate(custom(function = "validate_direction"))]
+    pub direction: String,
+
+    /// 要平仓的订单 ID / Order ID to close
+    pub order_id: u64,
+}
+
+/// 平仓索引推荐响应 / Close hint response
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CloseHintResponse {
+    /// 订单当前索引及其前后邻居, 可直接作为 close_order_indices 使用
+    /// The order's current index plus its neighbors, usable directly as close_order_indices
+    pub close_order_indices: Vec<u16>,
+}
+
+/// 推荐平仓的 close_order_indices / Suggest close_order_indices for closing an order
+///
+/// 返回订单当前索引及 prev/next 邻居, 与链上平仓指令的查找回退逻辑一致
+/// Returns the order's current index plus prev/next neighbors, matching the lookup fallback of the on-chain close instructions
+#[utoipa::path(
+    get,
+    path = "/api/orderbook/close-hint",
+    params(CloseHintParams),
+    responses(
+        (status = 200, description = "查询成功 / Query successful", body = CloseHintResponse),
+        (status = 400, description = "参数错误 / Bad Request"),
+        (status = 404, description = "订单不存在 / Order not found"),
+        (status = 500, description = "服务器错误 / Server Error")
+    ),
+    tag = "OrderBook"
+)]
+pub async fn get_close_hint(
+    ValidatedQuery(params): ValidatedQuery<CloseHintParams>,
+    State(state): State<OrderBookState>,
+) -> Result<Json<CommonResult<CloseHintResponse>>, ApiError> {
+    info!(
+        "📍 查询平仓索引 / Query close hint: mint={}, direction={}, order_id={}",
+        &params.mint[..8.min(params.mint.len())],
+        params.direction,
+        params.order_id
     );
 
-    Ok(Json(CommonResult::ok(response)))
+    let direction = parse_direction(&params.direction)?;
+
+    let manager = state
+        .orderbook_storage
+        .get_or_create_manager(params.mint.clone(), direction)
+        .map_err(|e| {
+            error!("❌ 获取 OrderBook 管理器失败 / Failed to get OrderBook manager: {}", e);
+            ApiError::Storage(format!("Failed to get OrderBook manager: {}", e))
+        })?;
+
+    let close_order_indices = manager
+        .suggest_close_indices(params.order_id)
+        .map_err(|e| {
+            error!("❌ 计算平仓索引失败 / Failed to suggest close indices: {}", e);
+            orderbook_error("Failed to suggest close indices", e)
+        })?;
+
+    Ok(Json(CommonResult::ok(CloseHintResponse { close_order_indices })))
+}
+
+// ==================== 单个订单查询 / Single Order Lookup ====================
+
+/// 单个订单查询参数 / Single order lookup parameters
+#[derive(Debug, Deserialize, IntoParams, ToSchema, Validate)]
+#[into_params(parameter_in = Query)]
+pub struct OrderLookupParams {
+    /// Token mint 地址 / Token mint address
+    #[validate(custom(function = "validate_mint_field"))]
+    pub mint: String,
+
+    /// 订单方向: "up"(做空) 或 "dn"(做多), order_id 按方向分别编号
+    /// Order direction: "up"(short) or "dn"(long), order ids are numbered per direction
+    #[validate(custom(function = "validate_direction"))]
+    pub direction: String,
+}
+
+/// 单个订单查询响应 / Single order lookup response
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OrderLookupItem {
+    /// 订单在链表中的当前索引 (随删除操作变化) / Current index in the linked list (changes with deletes)
+    pub index: u16,
+
+    /// 平仓冷却剩余秒数, 按服务器时间计算 / Remaining close cooldown in seconds, computed from server time
+    pub cooldown_remaining_secs: u32,
+
+    /// 冷却已结束, 平仓不会因冷却失败 (到期、止盈等其他规则仍然适用)
+    /// Cooldown is over, a close will not revert for cooldown (other rules such as expiry and take-profit still apply)
+    pub closable: bool,
+
+    /// 订单完整数据 / Complete order data
+    #[serde(flatten)]
+    pub order: MarginOrder,
+}
+
+/// 按 order_id 查询活跃订单
+/// Look up an active order by order_id
+///
+/// 返回订单当前索引、数据和平仓冷却状态, 客户端可在提交平仓交易前确认冷却已结束
+/// Returns the order's current index, data and close cooldown, so clients can confirm the cooldown is over before submitting a close
+#[utoipa::path(
+    get,
+    path = "/api/orderbook/order/{order_id}",
+    params(
+        ("order_id" = u64, Path, description = "订单 ID / Order ID"),
+        OrderLookupParams
+    ),
+    responses(
+        (status = 200, description = "查询成功 / Query successful", body = OrderLookupItem),
+        (status = 400, description = "参数错误 / Bad Request"),
+        (status = 404, description = "订单不在订单簿中 / Order not in the book"),
+        (status = 500, description = "服务器错误 / Server Error")
+    ),
+    tag = "OrderBook"
+)]
+pub async fn get_order(
+    Path(order_id): Path<u64>,
+    ValidatedQuery(params): ValidatedQuery<OrderLookupParams>,
+    State(state): State<OrderBookState>,
+) -> Result<Json<CommonResult<OrderLookupItem>>, ApiError> {
+    let direction = parse_direction(&params.direction)?;
+
+    let manager = state
+        .orderbook_storage
+        .get_or_create_manager(params.mint.clone(), direction)
+        .map_err(|e| {
+            error!("❌ 获取 OrderBook 管理器失败 / Failed to get OrderBook manager: {}", e);
+            ApiError::Storage(format!("Failed to get OrderBook manager: {}", e))
+        })?;
+
+    let index = manager
+        .index_of_order_id(order_id)
+        .map_err(|e| orderbook_error("Failed to query order", e))?;
+    let order = manager
+        .get_order(index)
+        .map_err(|e| orderbook_error("Failed to query order", e))?;
+
+    let cooldown_remaining_secs = cooldown_remaining_secs(order.start_time, unix_now_u32());
+    Ok(Json(CommonResult::ok(OrderLookupItem {
+        index,
+        cooldown_remaining_secs,
+        closable: cooldown_remaining_secs == 0,
+        order,
+    })))
+}
+
+// ==================== 订单时间线 / Order Timeline ====================
+
+/// 订单时间线查询参数 / Order timeline query parameters
+#[derive(Debug, Deserialize, IntoParams, ToSchema, Validate)]
+#[into_params(parameter_in = Query)]
+pub struct OrderTimelineParams {
+    /// Token mint 地址 / Token mint address
+    #[validate(custom(function = "validate_mint_field"))]
+    pub mint: String,
+
+    /// 订单方向: "up"(做空) 或 "dn"(做多), order_id 按方向分别编号
+    /// Order direction: "up"(short) or "dn"(long), order ids are numbered per direction
+    #[validate(custom(function = "validate_direction"))]
+    pub direction: String,
+}
+
+/// 查询订单生命周期时间线
+/// Query an order's lifecycle timeline
+///
+/// 按时间返回开仓、部分平仓、全平仓记录及每步的已实现盈亏;
+/// 存在全平仓为 closed, 仍在订单簿中为 open, 否则视为 liquidated
+/// Returns open, partial close and full close records in time order with realized PnL per step;
+/// closed if a full close exists, open if still in the book, otherwise liquidated
+#[utoipa::path(
+    get,
+    path = "/api/orderbook/order/{order_id}/timeline",
+    params(
+        ("order_id" = u64, Path, description = "订单 ID / Order ID"),
+        OrderTimelineParams
+    ),
+    responses(
+        (status = 200, description = "查询成功 / Query successful", body = OrderTimeline),
+        (status = 400, description = "参数错误 / Bad Request"),
+        (status = 404, description = "订单不存在 / Order not found"),
+        (status = 500, description = "服务器错误 / Server Error")
+    ),
+    tag = "OrderBook"
+)]
+pub async fn get_order_timeline(
+    Path(order_id): Path<u64>,
+    ValidatedQuery(params): ValidatedQuery<OrderTimelineParams>,
+    State(state): State<OrderBookState>,
+) -> Result<Json<CommonResult<OrderTimeline>>, ApiError> {
+    info!(
+        "🧾 查询订单时间线 / Query order timeline: mint={}, direction={}, order_id={}",
+        &params.mint[..8.min(params.mint.len())],
+        params.direction,
+        order_id
+    );
+
+    let direction = parse_direction(&params.direction)?;
+
+    let events = state
+        .event_storage
+        .query_by_order(&params.mint, direction.as_str(), order_id)
+        .await
+        .map_err(|e| {
+            error!("❌ 读取订单事件失败 / Failed to load order events: {}", e);
+            ApiError::Storage(format!("Failed to load order events: {}", e))
+        })?;
+
+    let manager = state
+        .orderbook_storage
+        .get_or_create_manager(params.mint.clone(), direction)
+        .map_err(|e| {
+            error!("❌ 获取 OrderBook 管理器失败 / Failed to get OrderBook manager: {}", e);
+            ApiError::Storage(format!("Failed to get OrderBook manager: {}", e))
+        })?;
+
+    let in_book = match manager.get_order_by_id(order_id) {
+        Ok(_) => true,
+        Err(OrderBookError::OrderIdNotFound(_)) => false,
+        Err(e) => {
+            error!("❌ 查询订单失败 / Failed to query order: {}", e);
+            return Err(orderbook_error("Failed to query order", e));
+        }
+    };
+
+    if events.is_empty() && !in_book {
+        return Err(orderbook_error(
+            "Failed to build order timeline",
+            OrderBookError::OrderIdNotFound(order_id),
+        ));
+    }
+
+    Ok(Json(CommonResult::ok(build_order_timeline(order_id, &events, in_book))))
+}
+
+// ==================== 即将到期订单 / Expiring Orders ====================
+
+/// 单次最多返回的即将到期订单数 / Max expiring orders returned per request
+pub const MAX_EXPIRING_ORDERS: usize = 500;
+
+/// 即将到期订单查询参数 / Expiring orders query parameters
+#[derive(Debug, Deserialize, IntoParams, ToSchema, Validate)]
+#[into_params(parameter_in = Query)]
+pub struct ExpiringOrdersParams {
+    /// Token mint 地址 / Token mint address
+    #[validate(custom(function = "validate_mint_field"))]
+    pub mint: String,
+
+    /// 订单方向: "up"(做空) 或 "dn"(做多) / Order direction: "up"(short) or "dn"(long)
+    #[validate(custom(function = "validate_direction"))]
+    pub direction: String,
+
+    /// 剩余时间上限(秒), 已到期的订单也会返回 / Max remaining time (seconds), already expired orders are included
+    #[param(example = 3600)]
+    pub within_secs: u64,
+
+    /// 返回数量(默认 100, 最大 500) / Number of orders (default 100, max 500)
+    #[serde(default = "default_expiring_limit")]
+    #[param(example = 100, minimum = 1, maximum = 500)]
+    pub limit: usize,
+}
+
+fn default_expiring_limit() -> usize {
+    100
+}
+
+/// 即将到期订单项 / Expiring order item
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ExpiringOrderItem {
+    /// 订单在链表中的索引 / Order index in the linked list
+    pub index: u16,
+
+    /// 距 end_time 的剩余秒数, 已到期为负数 / Seconds until end_time, negative once expired
+    pub seconds_remaining: i64,
+
+    /// 订单数据 / Order data
+    #[serde(flatten)]
+    pub order: MarginOrder,
+}
+
+/// 即将到期订单响应 / Expiring orders response
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ExpiringOrdersResponse {
+    /// 查询时间(Unix 秒) / Query time (Unix seconds)
+    pub now: i64,
+
+    /// 满足条件的订单总数 (截断前) / Total matching orders (before truncation)
+    pub total: usize,
+
+    /// 是否因 limit 被截断 / Whether the result was truncated by limit
+    pub truncated: bool,
+
+    /// 订单列表, 最先到期的在前 / Orders, soonest expiring first
+    pub orders: Vec<ExpiringOrderItem>,
+}
+
+/// 查询即将到期的订单 / Query orders expiring soon
+///
+/// 返回 `end_time - now <= within_secs` 的活跃订单, 最先到期的在前, 每个订单附带剩余秒数;
+/// 订单簿按价格排序, 因此会完整遍历一次, 结果最多返回 500 条
+/// Returns active orders with `end_time - now <= within_secs`, soonest first, each with its remaining seconds;
+/// the book is price-ordered so this walks it fully, and at most 500 orders are returned
+#[utoipa::path(
+    get,
+    path = "/api/orderbook/expiring",
+    params(ExpiringOrdersParams),
+    responses(
+        (status = 200, description = "查询成功 / Query successful", body = ExpiringOrdersResponse),
+        (status = 400, description = "参数错误 / Bad Request"),
+        (status = 500, description = "服务器错误 / Server Error")
+    ),
+    tag = "OrderBook"
+)]
+pub async fn get_expiring_orders(
+    ValidatedQuery(params): ValidatedQuery<ExpiringOrdersParams>,
+    State(state): State<OrderBookState>,
+) -> Result<Json<CommonResult<ExpiringOrdersResponse>>, ApiError> {
+    info!(
+        "⏳ 查询即将到期订单 / Query expiring orders: mint={}, direction={}, within_secs={}",
+        &params.mint[..8.min(params.mint.len())],
+        params.direction,
+        params.within_secs
+    );
+
+    let direction = parse_direction(&params.direction)?;
+
+    let manager = state
+        .orderbook_storage
+        .get_or_create_manager(params.mint.clone(), direction)
+        .map_err(|e| {
+            error!("❌ 获取 OrderBook 管理器失败 / Failed to get OrderBook manager: {}", e);
+            ApiError::Storage(format!("Failed to get OrderBook manager: {}", e))
+        })?;
+
+    let now = unix_now();
+    let expiring = manager
+        .find_expiring_orders(now, params.within_secs)
+        .map_err(|e| {
+            error!("❌ 查询即将到期订单失败 / Failed to find expiring orders: {}", e);
+            orderbook_error("Failed to find expiring orders", e)
+        })?;
+
+    let total = expiring.len();
+    let limit = params.limit.clamp(1, MAX_EXPIRING_ORDERS);
+    let orders = expiring
+        .into_iter()
+        .take(limit)
+        .map(|(index, order)| ExpiringOrderItem {
+            index,
+            seconds_remaining: order.end_time as i64 - now,
+            order,
+        })
+        .collect();
+
+    Ok(Json(CommonResult::ok(ExpiringOrdersResponse {
+        now,
+        total,
+        truncated: total > limit,
+        orders,
+    })))
+}
+
+// ==================== 已到期订单 / Expired Orders ====================
+
+/// 已到期订单查询参数 / Expired orders query parameters
+#[derive(Debug, Deserialize, IntoParams, ToSchema, Validate)]
+#[into_params(parameter_in = Query)]
+pub struct ExpiredOrdersParams {
+    /// Token mint 地址 / Token mint address
+    #[validate(custom(function = "validate_mint_field"))]
+    pub mint: String,
+
+    /// 订单方向: "up"(做空) 或 "dn"(做多) / Order direction: "up"(short) or "dn"(long)
+    #[validate(custom(function = "validate_direction"))]
+    pub direction: String,
+
+    /// 判定到期的时间(Unix 秒), 默认为服务器当前时间; keeper 可传入链上 Clock 时间
+    /// Time expiry is judged against (Unix seconds), defaults to server time; keepers can pass the on-chain Clock time
+    #[param(example = 1735700000)]
+    pub now: Option<i64>,
+
+    /// 返回数量(默认 100, 最大 500) / Number of orders (default 100, max 500)
+    #[serde(default = "default_expiring_limit")]
+    #[param(example = 100, minimum = 1, maximum = 500)]
+    pub limit: usize,
+}
+
+/// 已到期订单项 / Expired order item
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ExpiredOrderItem {
+    /// 订单在链表中的索引 / Order index in the linked list
+    pub index: u16,
+
+    /// 已到期的秒数 / Seconds since end_time
+    pub expired_secs: i64,
+
+    /// 推荐的 close_order_indices, 规则同 /api/orderbook/close-hint
+    /// Suggested close_order_indices, same rule as /api/orderbook/close-hint
+    pub close_order_indices: Vec<u16>,
+
+    /// 订单数据 / Order data
+    #[serde(flatten)]
+    pub order: MarginOrder,
+}
+
+/// 已到期订单响应 / Expired orders response
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ExpiredOrdersResponse {
+    /// 判定到期的时间(Unix 秒) / Time expiry was judged against (Unix seconds)
+    pub now: i64,
+
+    /// 已到期订单总数 (截断前) / Total expired orders (before truncation)
+    pub total: usize,
+
+    /// 是否因 limit 被截断 / Whether the result was truncated by limit
+    pub truncated: bool,
+
+    /// 订单列表, 到期最久的在前 / Orders, longest expired first
+    pub orders: Vec<ExpiredOrderItem>,
+}
+
+/// 查询已到期订单及其平仓索引 / Query expired orders with their close indices
+///
+/// 返回 `end_time <= now` 的活跃订单, 到期最久的在前, 每个订单附带 close_order_indices,
+/// 供 keeper 直接构建平仓交易; 索引基于查询时的订单簿, 前一笔平仓确认后可能移动
+/// Returns active orders with `end_time <= now`, longest expired first, each with close_order_indices
+/// so a keeper can build close transactions directly; indices reflect the book at query time and may shift once an earlier close lands
+#[utoipa::path(
+    get,
+    path = "/api/orderbook/expired",
+    params(ExpiredOrdersParams),
+    responses(
+        (status = 200, description = "查询成功 / Query successful", body = ExpiredOrdersResponse),
+        (status = 400, description = "参数错误 / Bad Request"),
+        (status = 500, description = "服务器错误 / Server Error")
+    ),
+    tag = "OrderBook"
+)]
+pub async fn get_expired_orders(
+    ValidatedQuery(params): ValidatedQuery<ExpiredOrdersParams>,
+    State(state): State<OrderBookState>,
+) -> Result<Json<CommonResult<ExpiredOrdersResponse>>, ApiError> {
+    info!(
+        "⌛ 查询已到期订单 / Query expired orders: mint={}, direction={}, now={:?}",
+        &params.mint[..8.min(params.mint.len())],
+        params.direction,
+        params.now
+    );
+
+    let direction = parse_direction(&params.direction)?;
+
+    let manager = state
+        .orderbook_storage
+        .get_or_create_manager(params.mint.clone(), direction)
+        .map_err(|e| {
+            error!("❌ 获取 OrderBook 管理器失败 / Failed to get OrderBook manager: {}", e);
+            ApiError::Storage(format!("Failed to get OrderBook manager: {}", e))
+        })?;
+
+    let now = params.now.unwrap_or_else(unix_now);
+    let expired = manager
+        .find_expired_orders(now)
+        .map_err(|e| {
+            error!("❌ 查询已到期订单失败 / Failed to find expired orders: {}", e);
+            orderbook_error("Failed to find expired orders", e)
+        })?;
+
+    let total = expired.len();
+    let limit = params.limit.clamp(1, MAX_EXPIRING_ORDERS);
+    let orders = expired
+        .into_iter()
+        .take(limit)
+        .map(|(index, order)| ExpiredOrderItem {
+            index,
+            expired_secs: now - order.end_time as i64,
+            close_order_indices: OrderBookDBManager::close_indices_for(index, &order),
+            order,
+        })
+        .collect();
+
+    Ok(Json(CommonResult::ok(ExpiredOrdersResponse {
+        now,
+        total,
+        truncated: total > limit,
+        orders,
+    })))
+}
+
+// ==================== 按开仓价查找订单 / Find Orders by Open Price ====================
+
+/// 按开仓价查找订单参数 / Find orders by open price parameters
+#[derive(Debug, Deserialize, IntoParams, ToSchema, Validate)]
+#[into_params(parameter_in = Query)]
+pub struct FindOrdersParams {
+    /// Token mint 地址 / Token mint address
+    #[validate(custom(function = "validate_mint_field"))]
+    pub mint: String,
+
+    /// 订单方向: "up"(做空) 或 "dn"(做多) / Order direction: "up"(short) or "dn"(long)
+    #[validate(custom(function = "validate_direction"))]
+    pub direction: String,
+
+    /// 用户地址 / User address
+    #[validate(length(min = 1, message = "must not be empty"))]
+    pub user: String,
+
+    /// 开仓价(u128 字符串) / Open price (u128 as string)
+    pub open_price: String,
+
+    /// 允许的价差(u128 字符串, 与开仓价同单位, 默认 0 即精确匹配)
+    /// Allowed price difference (u128 as string, same unit as open_price, default 0 = exact match)
+    pub tolerance: Option<String>,
+}
+
+/// 按开仓价查找的订单项 / Order item found by open price
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FindOrderItem {
+    /// 订单在链表中的索引 / Order index in the linked list
+    pub index: u16,
+
+    /// 与请求开仓价的差值(u128 字符串) / Difference from the requested open price (u128 as string)
+    pub price_diff: String,
+
+    /// 订单数据 (含 order_id) / Order data (including order_id)
+    #[serde(flatten)]
+    pub order: MarginOrder,
+}
+
+/// 按开仓价查找订单响应 / Find orders by open price response
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FindOrdersResponse {
+    /// 匹配的订单, 价差最小的在前; 多个匹配时由客户端按 order_id 区分
+    /// Matching orders, closest first; with several matches the client disambiguates by order_id
+    pub orders: Vec<FindOrderItem>,
+}
+
+/// 按开仓价查找用户订单 / Find a user's orders by open price
+///
+/// 供未保存 order_id 的客户端找回订单: 遍历用户在该 mint/方向下的订单索引,
+/// 返回 `|open_price - 请求值| <= tolerance` 的全部订单
+/// Recovery path for clients that did not persist order ids: walks the user's index for this mint/direction
+/// and returns every order with `|open_price - requested| <= tolerance`
+#[utoipa::path(
+    get,
+    path = "/api/orderbook/find",
+    params(FindOrdersParams),
+    responses(
+        (status = 200, description = "查询成功 / Query successful", body = FindOrdersResponse),
+        (status = 400, description = "参数错误 / Bad Request"),
+        (status = 500, description = "服务器错误 / Server Error")
+    ),
+    tag = "OrderBook"
+)]
+pub async fn find_orders_by_open_price(
+    ValidatedQuery(params): ValidatedQuery<FindOrdersParams>,
+    State(state): State<OrderBookState>,
+) -> Result<Json<CommonResult<FindOrdersResponse>>, ApiError> {
+    info!(
+        "🔎 按开仓价查找订单 / Find orders by open price: mint={}, direction={}, user={}, open_price={}, tolerance={:?}",
+        &params.mint[..8.min(params.mint.len())],
+        params.direction,
+        &params.user[..8.min(params.user.len())],
+        params.open_price,
+        params.tolerance
+    );
+
+    let direction = parse_direction(&params.direction)?;
+
+    let parse_price = |name: &str, value: &str| {
+        value.parse::<u128>().map_err(|_| {
+            ApiError::BadRequest(format!("Invalid {}: {}", name, value))
+        })
+    };
+    let open_price = parse_price("open_price", &params.open_price)?;
+    let tolerance = params
+        .tolerance
+        .as_deref()
+        .map(|value| parse_price("tolerance", value))
+        .transpose()?
+        .unwrap_or(0);
+
+    let query_service = UserOrderQueryService::new(state.orderbook_storage.db());
+    let matches = query_service
+        .find_user_orders_by_open_price(&params.user, &params.mint, direction.as_str(), open_price, tolerance)
+        .map_err(|e| {
+            error!("❌ 按开仓价查找订单失败 / Failed to find orders by open price: {}", e);
+            orderbook_error("Failed to find orders", e)
+        })?;
+
+    let orders = matches
+        .into_iter()
+        .map(|(index, order)| FindOrderItem {
+            index,
+            price_diff: order.open_price.abs_diff(open_price).to_string(),
+            order,
+        })
+        .collect();
+
+    Ok(Json(CommonResult::ok(FindOrdersResponse { orders })))
+}
+
+// ==================== 深度图 / Depth Profile ====================
+
+/// 深度图最大区间数 / Maximum number of depth buckets
+pub const MAX_DEPTH_BUCKETS: usize = 200;
+
+/// 深度图查询参数 / Depth profile query parameters
+#[derive(Debug, Deserialize, IntoParams, ToSchema, Validate)]
+#[into_params(parameter_in = Query)]
+pub struct DepthParams {
+    /// Token mint 地址 / Token mint address
+    #[validate(custom(function = "validate_mint_field"))]
+    pub mint: String,
+
+    /// 订单方向: "up"(做空) 或 "dn"(做多) / Order direction: "up"(short) or "dn"(long)
+    #[validate(custom(function = "validate_direction"))]
+    pub direction: String,
+
+    /// 价格区间数(默认 20, 最大 200) / Number of price buckets (default 20, max 200)
+    #[serde(default = "default_depth_buckets")]
+    #[param(example = 20, minimum = 1, maximum = 200)]
+    pub buckets: usize,
+}
+
+fn default_depth_buckets() -> usize {
+    20
+}
+
+/// 查询订单簿深度图 / Query the order book depth profile
+///
+/// 将 head 与 tail 之间的价格范围均分为若干区间, 汇总每个区间内订单锁定的 SOL 与 Token, 按价格升序返回;
+/// 订单簿为空时返回空数组
+/// Splits the price range between head and tail into buckets and sums the SOL and tokens locked by the orders in each,
+/// returned in ascending price; an empty book returns an empty array
+#[utoipa::path(
+    get,
+    path = "/api/orderbook/depth",
+    params(DepthParams),
+    responses(
+        (status = 200, description = "查询成功 / Query successful", body = Vec<DepthBucket>),
+        (status = 400, description = "参数错误 / Bad Request"),
+        (status = 500, description = "服务器错误 / Server Error")
+    ),
+    tag = "OrderBook"
+)]
+pub async fn get_orderbook_depth(
+    ValidatedQuery(params): ValidatedQuery<DepthParams>,
+    State(state): State<OrderBookState>,
+) -> Result<Json<CommonResult<Vec<DepthBucket>>>, ApiError> {
+    info!(
+        "📶 查询订单簿深度 / Query order book depth: mint={}, direction={}, buckets={}",
+        &params.mint[..8.min(params.mint.len())],
+        params.direction,
+        params.buckets
+    );
+
+    let direction = parse_direction(&params.direction)?;
+
+    let manager = state
+        .orderbook_storage
+        .get_or_create_manager(params.mint.clone(), direction)
+        .map_err(|e| {
+            error!("❌ 获取 OrderBook 管理器失败 / Failed to get OrderBook manager: {}", e);
+            ApiError::Storage(format!("Failed to get OrderBook manager: {}", e))
+        })?;
+
+    let buckets = params.buckets.clamp(1, MAX_DEPTH_BUCKETS);
+    let profile = manager.depth_profile(buckets).map_err(|e| {
+        error!("❌ 计算订单簿深度失败 / Failed to compute order book depth: {}", e);
+        orderbook_error("Failed to compute depth", e)
+    })?;
+
+    Ok(Json(CommonResult::ok(profile)))
+}
+
+// ==================== 订单簿统计与一致性 / Order Book Stats and Consistency ====================
+
+/// 订单簿统计查询参数 / Order book stats query parameters
+#[derive(Debug, Deserialize, IntoParams, ToSchema, Validate)]
+#[into_params(parameter_in = Query)]
+pub struct OrderBookStatsParams {
+    /// Token mint 地址 / Token mint address
+    #[validate(custom(function = "validate_mint_field"))]
+    pub mint: String,
+
+    /// 订单方向: "up"(做空) 或 "dn"(做多) / Order direction: "up"(short) or "dn"(long)
+    #[validate(custom(function = "validate_direction"))]
+    pub direction: String,
+}
+
+/// 订单簿统计响应 / Order book stats response
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OrderBookStatsResponse {
+    /// OrderBook Header 信息 / OrderBook header info
+    pub header: OrderBookHeaderInfo,
+
+    /// 订单数已达上限, 该市场无法再开仓 / The order count has reached the limit, the market cannot take new positions
+    pub at_capacity: bool,
+
+    /// header 与槽位的一致性 / Consistency between the header and its slots
+    #[serde(flatten)]
+    pub consistency: OrderBookConsistency,
+}
+
+/// 查询订单簿统计与数据一致性 / Query order book stats and data consistency
+///
+/// 返回 header 信息与 `at_capacity` (订单簿已满, 无法再开仓), 并扫描全部槽位检查 total/total_capacity/head/tail 与实际存储是否一致;
+/// `consistent` 为 false 时 `problems` 列出具体问题, 可用 POST /admin/orderbook/rebuild 修复
+/// Returns the header and `at_capacity` (the book is full and cannot take new positions), and scans every slot to check
+/// total/total_capacity/head/tail against what is stored; when `consistent` is false, `problems` lists what is wrong and POST /admin/orderbook/rebuild can repair it
+#[utoipa::path(
+    get,
+    path = "/api/orderbook/stats",
+    params(OrderBookStatsParams),
+    responses(
+        (status = 200, description = "查询成功 / Query successful", body = OrderBookStatsResponse),
+        (status = 400, description = "参数错误 / Bad Request"),
+        (status = 404, description = "OrderBook 不存在 / OrderBook not found"),
+        (status = 500, description = "服务器错误 / Server Error")
+    ),
+    tag = "OrderBook"
+)]
+pub async fn get_orderbook_stats(
+    ValidatedQuery(params): ValidatedQuery<OrderBookStatsParams>,
+    State(state): State<OrderBookState>,
+) -> Result<Json<CommonResult<OrderBookStatsResponse>>, ApiError> {
+    let direction = parse_direction(&params.direction)?;
+
+    let manager = state
+        .orderbook_storage
+        .get_or_create_manager(params.mint.clone(), direction)
+        .map_err(|e| {
+            error!("❌ 获取 OrderBook 管理器失败 / Failed to get OrderBook manager: {}", e);
+            ApiError::Storage(format!("Failed to get OrderBook manager: {}", e))
+        })?;
+
+    let header = manager
+        .load_header()
+        .map_err(|e| orderbook_error("Failed to load OrderBook", e))?;
+    let consistency = manager.check_consistency().map_err(|e| {
+        error!("❌ 检查订单簿一致性失败 / Failed to check order book consistency: {}", e);
+        orderbook_error("Failed to check consistency", e)
+    })?;
+
+    Ok(Json(CommonResult::ok(OrderBookStatsResponse {
+        header: OrderBookHeaderInfo::from(&header),
+        at_capacity: header.at_capacity(),
+        consistency,
+    })))
+}
+
+/// 重建订单簿并清除重建标记
+#[utoipa::path(
+    post,
+    path = "/admin/orderbook/rebuild",
+    tag = "admin",
+    security(("api_key" = [])),
+    summary = "重建订单簿 / Rebuild an order book",
+    description = "按链表顺序把活跃订单连续重写到槽位 0..n, 重新生成 ID 映射与活跃索引, 删除链表外的孤立槽位, 并清除分叉回滚或重处理留下的重建标记; 返回重建后的一致性检查结果。链表有环或指向缺失槽位时不写入任何数据 / Rewrites the active orders contiguously into slots 0..n in list order, regenerates the ID map and active indices, drops orphan slots outside the list and clears the rebuild marker left by a fork rollback or a reprocess; returns the consistency check after the rebuild. Nothing is written when the list has a cycle or points to a missing slot",
+    params(OrderBookStatsParams),
+    responses(
+        (status = 200, description = "重建完成 / Rebuilt",
+         body = crate::docs::ApiResponse<OrderBookConsistency>),
+        (status = 400, description = "参数错误 / Bad Request",
+         body = crate::docs::ErrorApiResponse),
+        (status = 401, description = "未授权 / Unauthorized",
+         body = crate::docs::ErrorApiResponse),
+        (status = 500, description = "服务器错误 / Server Error",
+         body = crate::docs::ErrorApiResponse)
+    )
+)]
+pub async fn rebuild_orderbook(
+    ValidatedQuery(params): ValidatedQuery<OrderBookStatsParams>,
+    State(state): State<OrderBookState>,
+) -> Result<Json<CommonResult<OrderBookConsistency>>, ApiError> {
+    let direction = parse_direction(&params.direction)?;
+
+    let manager = state
+        .orderbook_storage
+        .get_or_create_manager(params.mint.clone(), direction)
+        .map_err(|e| {
+            error!("❌ 获取 OrderBook 管理器失败 / Failed to get OrderBook manager: {}", e);
+            ApiError::Storage(format!("Failed to get OrderBook manager: {}", e))
+        })?;
+
+    manager.rebuild().map_err(|e| {
+        error!("❌ 重建订单簿失败 / Failed to rebuild order book: {}:{}, {}", params.mint, direction, e);
+        orderbook_error("Failed to rebuild", e)
+    })?;
+    info!("🔧 管理接口重建订单簿 / Order book rebuilt via admin API: {}:{}", params.mint, direction);
+
+    let consistency = manager
+        .check_consistency()
+        .map_err(|e| orderbook_error("Failed to check consistency", e))?;
+    Ok(Json(CommonResult::ok(consistency)))
+}
+
+// ==================== 多空汇总 / Long-Short Summary ====================
+
+/// 多空汇总查询参数 / Long-short summary query parameters
+#[derive(Debug, Deserialize, IntoParams, ToSchema, Validate)]
+#[into_params(parameter_in = Query)]
+pub struct OrderBookSummaryParams {
+    /// Token mint 地址 / Token mint address
+    #[validate(custom(function = "validate_mint_field"))]
+    pub mint: String,
+}
+
+/// 多空汇总响应 / Long-short summary response
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OrderBookSummaryResponse {
+    /// Token mint 地址 / Token mint address
+    pub mint: String,
+
+    /// 做多订单数 (dn 订单簿) / Long order count (dn book)
+    pub long_orders: u16,
+
+    /// 做空订单数 (up 订单簿) / Short order count (up book)
+    pub short_orders: u16,
+
+    /// 做多借出的 SOL 总量 (lamports) / Total SOL borrowed by longs (lamports)
+    pub long_total_borrow: u64,
+
+    /// 做空借出的 Token 总量 / Total tokens borrowed by shorts
+    pub short_total_borrow: u64,
+}
+
+/// 单个方向的订单数与借款总量 / Order count and total borrow of one direction
+fn direction_summary(state: &OrderBookState, mint: &str, direction: Direction) -> Result<(u16, u64), ApiError> {
+    let manager = state
+        .orderbook_storage
+        .get_or_create_manager(mint.to_string(), direction)
+        .map_err(|e| {
+            error!("❌ 获取 OrderBook 管理器失败 / Failed to get OrderBook manager: {}", e);
+            ApiError::Storage(format!("Failed to get OrderBook manager: {}", e))
+        })?;
+
+    let header = manager
+        .load_header()
+        .map_err(|e| orderbook_error("Failed to load OrderBook", e))?;
+    let orders = manager
+        .get_all_active_orders()
+        .map_err(|e| orderbook_error("Failed to query orders", e))?;
+    let total_borrow = orders
+        .iter()
+        .fold(0u64, |sum, (_, order)| sum.saturating_add(order.borrow_amount));
+
+    Ok((header.total, total_borrow))
+}
+
+/// 查询某个 mint 的多空汇总
+/// Query the long-short summary of a mint
+///
+/// 一次返回两个方向的订单数和借款总量, 客户端无需分别请求 up/dn 订单簿
+/// Returns the order count and total borrow of both directions in one call, so clients don't need separate up/dn requests
+#[utoipa::path(
+    get,
+    path = "/api/orderbook/summary",
+    params(OrderBookSummaryParams),
+    responses(
+        (status = 200, description = "查询成功 / Query successful", body = OrderBookSummaryResponse),
+        (status = 500, description = "服务器错误 / Server Error")
+    ),
+    tag = "OrderBook"
+)]
+pub async fn get_orderbook_summary(
+    ValidatedQuery(params): ValidatedQuery<OrderBookSummaryParams>,
+    State(state): State<OrderBookState>,
+) -> Result<Json<CommonResult<OrderBookSummaryResponse>>, ApiError> {
+    let (long_orders, long_total_borrow) = direction_summary(&state, &params.mint, Direction::Dn)?;
+    let (short_orders, short_total_borrow) = direction_summary(&state, &params.mint, Direction::Up)?;
+
+    Ok(Json(CommonResult::ok(OrderBookSummaryResponse {
+        mint: params.mint,
+        long_orders,
+        short_orders,
+        long_total_borrow,
+        short_total_borrow,
+    })))
+}
+
+// ==================== 流式导出 / Streaming Dump ====================
+
+/// 流式导出查询参数 / Streaming dump query parameters
+#[derive(Debug, Deserialize, IntoParams, ToSchema, Validate)]
+#[into_params(parameter_in = Query)]
+pub struct OrderBookDumpParams {
+    /// Token mint 地址 / Token mint address
+    #[validate(custom(function = "validate_mint_field"))]
+    pub mint: String,
+
+    /// 订单方向: "up"(做空) 或 "dn"(做多) / Order direction: "up"(short) or "dn"(long)
+    #[validate(custom(function = "validate_direction"))]
+    pub direction: String,
+}
+
+/// 流式导出完整订单簿
+/// Stream a full order book dump
+///
+/// 按链表顺序分块输出 MarginOrder JSON 数组, 服务端内存占用与订单簿大小无关; 不是一致快照, 导出中途出错时响应体被截断
+/// Streams a JSON array of MarginOrder in linked-list order chunk by chunk, so server memory stays flat regardless of book size;
+/// not a consistent snapshot, and the body is cut short if an error happens mid-stream
+#[utoipa::path(
+    get,
+    path = "/api/orderbook/dump",
+    params(OrderBookDumpParams),
+    responses(
+        (status = 200, description = "导出成功 / Dump streamed", body = Vec<MarginOrder>),
+        (status = 400, description = "参数错误 / Bad Request"),
+        (status = 404, description = "OrderBook 不存在 / OrderBook not found"),
+        (status = 500, description = "服务器错误 / Server Error")
+    ),
+    tag = "OrderBook"
+)]
+pub async fn dump_orderbook(
+    ValidatedQuery(params): ValidatedQuery<OrderBookDumpParams>,
+    State(state): State<OrderBookState>,
+) -> Result<Response, ApiError> {
+    let direction = parse_direction(&params.direction)?;
+
+    let manager = state
+        .orderbook_storage
+        .get_or_create_manager(params.mint.clone(), direction)
+        .map_err(|e| {
+            error!("❌ 获取 OrderBook 管理器失败 / Failed to get OrderBook manager: {}", e);
+            ApiError::Storage(format!("Failed to get OrderBook manager: {}", e))
+        })?;
+
+    // 开始输出前先校验 header, 这类错误仍以普通错误响应返回
+    // Validate the header before streaming so these failures still come back as a regular error response
+    manager
+        .load_header()
+        .map_err(|e| orderbook_error("Failed to load OrderBook", e))?;
+
+    let body = Body::from_stream(export_stream(manager, EXPORT_CHUNK_ORDERS));
+    Ok(([(header::CONTENT_TYPE, "application/json")], body).into_response())
+}
+
+// ==================== 活跃市场 / Active Markets ====================
+
+/// 活跃市场查询参数 / Active markets query parameters
+#[derive(Debug, Deserialize, IntoParams, ToSchema, Validate)]
+#[into_params(parameter_in = Query)]
+pub struct MarketsParams {
+    /// 上一页返回的游标 / Cursor returned by the previous page
+    pub cursor: Option<String>,
+
+    /// 每页数量(默认 50, 最大 200) / Items per page (default 50, max 200)
+    #[serde(default = "default_markets_limit")]
+    #[param(example = 50, minimum = 1, maximum = 200)]
+    #[validate(range(min = 1, max = 200, message = "must be between 1 and 200"))]
+    pub limit: usize,
+}
+
+fn default_markets_limit() -> usize {
+    50
+}
+
+/// 活跃市场响应 / Active markets response
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MarketsResponse {
+    /// 至少一个方向订单簿非空的市场, 按 mint 排序 / Markets with at least one non-empty direction, in mint order
+    pub markets: Vec<OrderBookMarket>,
+
+    /// 分页信息 / Pagination fields
+    #[serde(flatten)]
+    pub pagination: PageInfo,
+}
+
+/// 列出有活跃订单簿的市场 / List markets with an active order book
+///
+/// 扫描所有订单簿 header, 返回 up 或 dn 方向 total > 0 的 mint, 附带各方向订单数和最早到期时间
+/// Scans every order book header and returns mints whose up or dn book has total > 0, with per-direction counts and the nearest expiry
+#[utoipa::path(
+    get,
+    path = "/api/orderbook/markets",
+    params(MarketsParams),
+    responses(
+        (status = 200, description = "查询成功 / Query successful", body = MarketsResponse),
+        (status = 400, description = "参数错误 / Bad Request"),
+        (status = 500, description = "服务器错误 / Server Error")
+    ),
+    tag = "OrderBook"
+)]
+pub async fn get_markets(
+    ValidatedQuery(params): ValidatedQuery<MarketsParams>,
+    State(state): State<OrderBookState>,
+) -> Result<Json<CommonResult<MarketsResponse>>, ApiError> {
+    let limit = params.limit;
+    let (markets, next_cursor) = state
+        .orderbook_storage
+        .list_markets(params.cursor.as_deref(), limit)
+        .map_err(|e| {
+            error!("❌ 查询活跃市场失败 / Failed to list markets: {}", e);
+            ApiError::Storage(format!("Failed to list markets: {}", e))
+        })?;
+
+    Ok(Json(CommonResult::ok(MarketsResponse {
+        markets,
+        pagination: PageInfo::cursor(limit as u32, next_cursor),
+    })))
+}
+
+// ==================== 全市场订单 / All Orders ====================
+
+/// 全市场订单查询参数 / All orders query parameters
+#[derive(Debug, Deserialize, IntoParams, ToSchema, Validate)]
+#[into_params(parameter_in = Query)]
+pub struct AllOrdersParams {
+    /// 上一页返回的游标 `{mint}:{direction}:{index}` / Cursor `{mint}:{direction}:{index}` returned by the previous page
+    pub cursor: Option<String>,
+
+    /// 每页数量(默认 100, 最大 500) / Items per page (default 100, max 500)
+    #[serde(default = "default_all_orders_page_size")]
+    #[param(example = 100, minimum = 1, maximum = 500)]
+    #[validate(range(min = 1, max = 500, message = "must be between 1 and 500"))]
+    pub page_size: usize,
+
+    /// 可选, 按订单类型过滤 (1=做多/dn, 2=做空/up) / Optional order type filter (1=long/dn, 2=short/up)
+    pub order_type: Option<u8>,
+}
+
+fn default_all_orders_page_size() -> usize {
+    100
+}
+
+/// 全市场订单响应 / All orders response
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AllOrdersResponse {
+    /// 订单, 按 (mint, 方向, 槽位索引) 排序 / Orders in (mint, direction, slot index) order
+    pub orders: Vec<GlobalOrderRow>,
+
+    /// 分页信息 / Pagination fields
+    #[serde(flatten)]
+    pub pagination: PageInfo,
+}
+
+/// 分页浏览所有市场的订单 / Page through the orders of every market
+///
+/// 供运营后台和分析使用: 依次遍历每个订单簿的槽位, 每行带 mint 和方向;
+/// 跨多个订单簿, 因此用游标而非页码续读
+/// For admin dashboards and analytics: walks the slots of every order book in turn, tagging each row with its mint and direction;
+/// since it spans many books it resumes from a cursor rather than a page number
+#[utoipa::path(
+    get,
+    path = "/api/orderbook/all",
+    params(AllOrdersParams),
+    responses(
+        (status = 200, description = "查询成功 / Query successful", body = AllOrdersResponse),
+        (status = 400, description = "参数错误 / Bad Request"),
+        (status = 500, description = "服务器错误 / Server Error")
+    ),
+    tag = "OrderBook"
+)]
+pub async fn get_all_orders(
+    ValidatedQuery(params): ValidatedQuery<AllOrdersParams>,
+    State(state): State<OrderBookState>,
+) -> Result<Json<CommonResult<AllOrdersResponse>>, ApiError> {
+    let direction = params
+        .order_type
+        .map(|order_type| {
+            Direction::from_order_type(order_type)
+                .ok_or_else(|| ApiError::BadRequest(format!("Invalid order_type: {}", order_type)))
+        })
+        .transpose()?;
+    let cursor = params
+        .cursor
+        .as_deref()
+        .map(|cursor| {
+            OrderCursor::parse(cursor).ok_or_else(|| ApiError::BadRequest(format!("Invalid cursor: {}", cursor)))
+        })
+        .transpose()?;
+
+    let page_size = params.page_size;
+    let (orders, next_cursor) = state
+        .orderbook_storage
+        .list_all_orders(direction, cursor.as_ref(), page_size)
+        .map_err(|e| {
+            error!("❌ 查询全市场订单失败 / Failed to list all orders: {}", e);
+            ApiError::Storage(format!("Failed to list all orders: {}", e))
+        })?;
+
+    Ok(Json(CommonResult::ok(AllOrdersResponse {
+        orders,
+        pagination: PageInfo::cursor(page_size as u32, next_cursor.map(|c| c.encode())),
+    })))
+}
+
+// ==================== 链上订单簿 / On-chain Order Book ====================
+
+/// 链上订单簿查询参数 / On-chain order book query parameters
+#[derive(Debug, Deserialize, IntoParams, ToSchema, Validate)]
+#[into_params(parameter_in = Query)]
+pub struct OnchainOrderBookParams {
+    /// Token mint 地址 / Token mint address
+    #[validate(custom(function = "validate_mint_field"))]
+    pub mint: String,
+
+    /// 订单方向: "up"(做空) 或 "dn"(做多) / Order direction: "up"(short) or "dn"(long)
+    #[validate(custom(function = "validate_direction"))]
+    pub direction: String,
+}
+
+/// 链上订单簿响应 / On-chain order book response
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OnchainOrderBookResponse {
+    /// 订单簿 PDA 地址 / Order book PDA address
+    pub address: String,
+
+    /// 读取账户时的 slot / Slot the account was read at
+    pub slot: u64,
+
+    /// 账户数据大小(字节) / Account data size (bytes)
+    pub data_len: usize,
+
+    /// 链上头部 / On-chain header
+    pub header: OnchainOrderBookHeader,
+
+    /// 前 total 个槽位, 按索引排列 / The first `total` slots, in index order
+    pub orders: Vec<OrderBookOrderDetail>,
+}
+
+/// 查询链上订单簿原始数据 / Query the raw on-chain order book
+///
+/// 推导 OrderBook PDA, 通过 RPC 读取账户并按合约布局解码头部和全部槽位;
+/// 只读且不依赖 RocksDB 镜像, 用于排查镜像与链上的差异
+/// Derives the OrderBook PDA, reads the account over RPC and decodes the header and every slot with the program layout;
+/// read-only and independent of the RocksDB mirror, for debugging divergence between mirror and chain
+#[utoipa::path(
+    get,
+    path = "/api/orderbook/onchain",
+    params(OnchainOrderBookParams),
+    responses(
+        (status = 200, description = "查询成功 / Query successful", body = OnchainOrderBookResponse),
+        (status = 400, description = "参数错误 / Bad Request"),
+        (status = 404, description = "链上账户不存在 / On-chain account not found"),
+        (status = 500, description = "账户大小异常 / Unexpected account size"),
+        (status = 502, description = "RPC 错误 / RPC error")
+    ),
+    tag = "OrderBook"
+)]
+pub async fn get_onchain_orderbook(
+    ValidatedQuery(params): ValidatedQuery<OnchainOrderBookParams>,
+    State(state): State<OrderBookState>,
+) -> Result<Json<CommonResult<OnchainOrderBookResponse>>, ApiError> {
+    info!(
+        "⛓️ 查询链上订单簿 / Query on-chain order book: mint={}, direction={}",
+        &params.mint[..8.min(params.mint.len())],
+        params.direction
+    );
+
+    let direction = parse_direction(&params.direction)?;
+    let mint = Pubkey::from_str(&params.mint)
+        .map_err(|_| ApiError::BadRequest(format!("Invalid mint: {}", params.mint)))?;
+    let address = orderbook_pda(&state.program_id, &mint, direction).to_string();
+
+    let (slot, data) = state
+        .solana_client
+        .get_account_info(&address)
+        .await
+        .map_err(|e| {
+            error!("❌ 读取链上订单簿失败 / Failed to fetch on-chain order book: {}", e);
+            ApiError::Upstream(format!("Failed to fetch on-chain order book: {}", e))
+        })?
+        .ok_or_else(|| ApiError::NotFound(format!("On-chain order book account not found: {}", address)))?;
+
+    let book = decode_orderbook_account(&data).map_err(|e| {
+        error!("❌ 解码链上订单簿失败 / Failed to decode on-chain order book: {}: {}", address, e);
+        orderbook_error("Failed to decode on-chain order book", e)
+    })?;
+
+    Ok(Json(CommonResult::ok(OnchainOrderBookResponse {
+        address,
+        slot,
+        data_len: data.len(),
+        header: book.header,
+        orders: book
+            .orders
+            .into_iter()
+            .map(|(index, order)| OrderBookOrderDetail { index, order })
+            .collect(),
+    })))
 }
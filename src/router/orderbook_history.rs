@@ -2,9 +2,7 @@
 // OrderBook User Trading History Query Endpoints
 
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
-    response::IntoResponse,
+    extract::{Path, State},
     routing::get,
     Json, Router,
 };
@@ -13,41 +11,60 @@ use std::sync::Arc;
 use tracing::{error, info};
 use utoipa::{IntoParams, ToSchema};
 
-use crate::db::OrderBookStorage;
+use crate::db::{EventStorage, OrderBookStorage};
 use crate::orderbook::closed_orders::ClosedOrdersQuery;
 use crate::orderbook::types::ClosedOrderRecord;
-use crate::util::result::CommonResult;
+use crate::orderbook::{replay_orderbook_at, MAX_REPLAY_EVENTS};
+use crate::router::extract::ValidatedQuery;
+use crate::router::orderbook::{parse_direction, OrderBookOrderDetail};
+use crate::util::result::{ApiError, CommonResult};
+use crate::util::validate::{validate_direction, validate_mint_field};
+use crate::util::PageInfo;
+use validator::Validate;
+
+/// OrderBook History 的共享状态 / Shared state for OrderBook History
+#[derive(Clone)]
+pub struct OrderBookHistoryState {
+    pub orderbook_storage: Arc<OrderBookStorage>,
+    /// 用于历史回放 / Used for history replay
+    pub event_storage: Arc<EventStorage>,
+}
 
 /// 创建 OrderBook History 路由 / Create OrderBook History routes
-pub fn routes() -> Router<Arc<OrderBookStorage>> {
+pub fn routes() -> Router<OrderBookHistoryState> {
     Router::new()
         .route(
             "/api/orderbook/user/:user_address/history",
             get(get_user_history),
         )
+        .route("/api/orderbook/history/at", get(get_orderbook_at))
 }
 
 /// 查询参数 - 分页
 /// Query parameters - Pagination
-#[derive(Debug, Deserialize, IntoParams, ToSchema)]
+#[derive(Debug, Deserialize, IntoParams, ToSchema, Validate)]
 #[into_params(parameter_in = Query)]
 pub struct HistoryQueryParams {
     /// 页码(从1开始)
     /// Page number (starting from 1)
     #[serde(default = "default_page")]
-    pub page: usize,
+    #[validate(range(min = 1, message = "must be at least 1"))]
+    pub page: u32,
 
     /// 每页大小(默认20,最大100)
     /// Page size (default 20, max 100)
     #[serde(default = "default_page_size")]
-    pub page_size: usize,
+    #[validate(range(min = 1, max = 100, message = "must be between 1 and 100"))]
+    pub page_size: u32,
 
     /// 可选: 按 mint 过滤
     /// Optional: filter by mint
+    #[validate(custom(function = "validate_mint_field"))]
     pub mint: Option<String>,
 
     /// 可选: 按方向过滤 ("up" 或 "dn")
     /// Optional: filter by direction ("up" or "dn")
+    #[validate(custom(function = "validate_direction"))]
     pub direction: Option<String>,
 
     /// 可选: 开始时间戳
@@ -59,11 +76,11 @@ pub struct HistoryQueryParams {
     pub end_time: Option<u32>,
 }
 
-fn default_page() -> usize {
+fn default_page() -> u32 {
     1
 }
 
-fn default_page_size() -> usize {
+fn default_page_size() -> u32 {
     20
 }
 
@@ -71,21 +88,14 @@ fn default_page_size() -> usize {
 /// Response data - Closed orders list
 #[derive(Debug, Serialize, ToSchema)]
 pub struct ClosedOrdersResponse {
-    /// 总数量
-    /// Total count
-    pub total: usize,
-
-    /// 当前页码
-    /// Current page
-    pub page: usize,
-
-    /// 每页大小
-    /// Page size
-    pub page_size: usize,
-
     /// 订单记录列表
     /// Order records list
     pub records: Vec<ClosedOrderRecord>,
+
+    /// 分页信息
+    /// Pagination fields
+    #[serde(flatten)]
+    pub pagination: PageInfo,
 }
 
 // ==================== API 端点 / API Endpoints ====================
@@ -114,9 +124,9 @@ pub struct ClosedOrdersResponse {
 )]
 pub async fn get_user_history(
     Path(user_address): Path<String>,
-    Query(params): Query<HistoryQueryParams>,
-    State(orderbook_storage): State<Arc<OrderBookStorage>>,
-) -> impl IntoResponse {
+    ValidatedQuery(params): ValidatedQuery<HistoryQueryParams>,
+    State(state): State<OrderBookHistoryState>,
+) -> Result<Json<CommonResult<ClosedOrdersResponse>>, ApiError> {
     info!(
         "📊 查询用户交易历史 / Query user history: user={}, mint={:?}, direction={:?}, page={}, page_size={}",
         &user_address[..8.min(user_address.len())],
@@ -126,38 +136,18 @@ pub async fn get_user_history(
         params.page_size
     );
 
-    // 验证参数 / Validate parameters
-    let page_size = params.page_size.min(100).max(1);
-    let page = params.page.max(1);
-
-    // 验证 direction 参数 / Validate direction parameter
-    if let Some(ref direction) = params.direction {
-        if direction != "up" && direction != "dn" {
-            error!("❌ 无效的 direction 参数 / Invalid direction parameter: {}", direction);
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(CommonResult::<()>::error(
-                    400,
-                    format!("Invalid direction: {}, expected 'up' or 'dn'", direction),
-                )),
-            )
-                .into_response();
-        }
-    }
+    let page_size = params.page_size as usize;
+    let page = params.page as usize;
 
     // 创建查询实例 / Create query instance
-    let query = ClosedOrdersQuery::new(orderbook_storage.db());
+    let query = ClosedOrdersQuery::new(state.orderbook_storage.db());
 
     // 执行查询 / Execute query
     let records = match query.query_user_closed_orders(&user_address, None) {
         Ok(r) => r,
         Err(e) => {
             error!("❌ 查询失败 / Query failed: {}", e);
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(CommonResult::<()>::error(500, e.to_string())),
-            )
-                .into_response();
+            return Err(ApiError::from_status(e.status_code(), e.to_string()));
         }
     };
 
@@ -189,7 +179,7 @@ pub async fn get_user_history(
 
     // 分页 / Pagination
     let total = filtered.len();
-    let start_idx = (page - 1) * page_size;
+    let start_idx = (page - 1).saturating_mul(page_size);
 
     let page_records: Vec<ClosedOrderRecord> = filtered
         .into_iter()
@@ -197,19 +187,144 @@ pub async fn get_user_history(
         .take(page_size)
         .collect();
 
-    let response = ClosedOrdersResponse {
+    info!(
+        "✅ 查询成功 / Query successful: user={}, total={}, returned={}",
+        &user_address[..8.min(user_address.len())],
         total,
-        page,
-        page_size,
+        page_records.len()
+    );
+
+    Ok(Json(CommonResult::ok(ClosedOrdersResponse {
         records: page_records,
+        pagination: PageInfo::numbered(page as u32, page_size as u32, total as u64),
+    })))
+}
+
+// ==================== 历史订单簿快照 / Historical Order Book Snapshot ====================
+
+/// 历史订单簿查询参数 / Historical order book query parameters
+#[derive(Debug, Deserialize, IntoParams, ToSchema, Validate)]
+#[into_params(parameter_in = Query)]
+pub struct OrderBookAtParams {
+    /// Token mint 地址 / Token mint address
+    #[validate(custom(function = "validate_mint_field"))]
+    pub mint: String,
+
+    /// 订单方向: "up"(做空) 或 "dn"(做多) / Order direction: "up"(short) or "dn"(long)
+    #[validate(custom(function = "validate_direction"))]
+    pub direction: String,
+
+    /// 时间点(Unix 秒, 含) / Point in time (Unix seconds, inclusive)
+    pub timestamp: i64,
+}
+
+/// 历史订单簿快照响应 / Historical order book snapshot response
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OrderBookAtResponse {
+    /// Token mint 地址 / Token mint address
+    pub mint: String,
+
+    /// 订单方向 / Order direction
+    pub direction: String,
+
+    /// 查询的时间点(Unix 秒) / Queried point in time (Unix seconds)
+    pub timestamp: i64,
+
+    /// 回放的事件数 / Number of replayed events
+    pub replayed_events: usize,
+
+    /// 该时刻的订单数 / Order count at that moment
+    pub total: usize,
+
+    /// 该时刻的活跃订单(链表顺序) / Orders active at that moment (linked-list order)
+    pub orders: Vec<OrderBookOrderDetail>,
+}
+
+/// 查询某一时间点的订单簿
+/// Query the order book at a point in time
+///
+/// # 中文说明 / Chinese Description
+/// 回放该 mint 已存储的事件直到指定时间, 重建当时的订单簿; 最多回放 100000 个事件,
+/// 若该 mint 早期事件缺失则返回 404
+///
+/// # English Description
+/// Replays the mint's stored events up to the given time to rebuild the order book as it was then;
+/// replays at most 100000 events and returns 404 if the mint's early events are missing
+#[utoipa::path(
+    get,
+    path = "/api/orderbook/history/at",
+    params(OrderBookAtParams),
+    responses(
+        (status = 200, description = "查询成功 / Query successful", body = OrderBookAtResponse),
+        (status = 400, description = "参数错误或回放窗口过大 / Invalid parameters or replay window too large"),
+        (status = 404, description = "历史事件缺失 / History unavailable"),
+        (status = 500, description = "服务器错误 / Server error")
+    ),
+    tag = "OrderBook"
+)]
+pub async fn get_orderbook_at(
+    ValidatedQuery(params): ValidatedQuery<OrderBookAtParams>,
+    State(state): State<OrderBookHistoryState>,
+) -> Result<Json<CommonResult<OrderBookAtResponse>>, ApiError> {
+    info!(
+        "🕰️ 查询历史订单簿 / Query historical order book: mint={}, direction={}, timestamp={}",
+        &params.mint[..8.min(params.mint.len())],
+        params.direction,
+        params.timestamp
+    );
+
+    let direction = parse_direction(&params.direction)?;
+
+    let until = match chrono::DateTime::from_timestamp(params.timestamp, 0) {
+        Some(t) => t,
+        None => {
+            return Err(ApiError::BadRequest(format!("Invalid timestamp: {}", params.timestamp)));
+        }
     };
 
+    // 多取一个事件用于判断是否超出回放窗口 / Fetch one extra event to detect an exceeded replay window
+    let events = match state
+        .event_storage
+        .query_by_mint(&params.mint, Some(MAX_REPLAY_EVENTS + 1))
+        .await
+    {
+        Ok(events) => events,
+        Err(e) => {
+            error!("❌ 读取事件失败 / Failed to load events: {}", e);
+            return Err(ApiError::Storage(e.to_string()));
+        }
+    };
+
+    let replayed = match replay_orderbook_at(&events, direction, until) {
+        Ok(r) => r,
+        Err(e) => {
+            error!("❌ 回放失败 / Replay failed: {}", e);
+            return Err(ApiError::from_status(e.status_code(), e.to_string()));
+        }
+    };
+
+    let orders: Vec<OrderBookOrderDetail> = replayed
+        .orders
+        .into_iter()
+        .map(|(index, order)| OrderBookOrderDetail { index, order })
+        .collect();
+
     info!(
-        "✅ 查询成功 / Query successful: user={}, total={}, returned={}",
-        &user_address[..8.min(user_address.len())],
-        total,
-        response.records.len()
+        "✅ 回放完成 / Replay completed: mint={}, direction={}, events={}, orders={}",
+        &params.mint[..8.min(params.mint.len())],
+        params.direction,
+        replayed.replayed_events,
+        orders.len()
     );
 
-    (StatusCode::OK, Json(CommonResult::ok(response))).into_response()
+    let response = OrderBookAtResponse {
+        mint: params.mint,
+        direction: params.direction,
+        timestamp: params.timestamp,
+        replayed_events: replayed.replayed_events,
+        total: orders.len(),
+        orders,
+    };
+
+    Ok(Json(CommonResult::ok(response)))
 }
@@ -0,0 +1,221 @@
+// 限流中间件 / Rate limiting middleware
+//
+// 按客户端 IP 的令牌桶限流, 每个路由组一个独立的限流器
+// Token-bucket rate limiting per client IP, one independent limiter per route group
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderMap, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tracing::warn;
+
+use crate::config::{LiveConfig, RateLimitConfig};
+use crate::util::result::ApiError;
+
+/// 超过该数量时清理空闲的桶 / Prune idle buckets once the map grows beyond this size
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+/// 单个客户端的令牌桶 / Token bucket for a single client
+#[derive(Debug, Clone, Copy)]
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+/// 路由组限流器, 规则每次从可热加载配置读取 / Rate limiter for one route group, rule read from live config on every check
+pub struct RateLimiter {
+    group: String,
+    live: LiveConfig,
+    buckets: Mutex<HashMap<IpAddr, TokenBucket>>,
+}
+
+impl RateLimiter {
+    /// 创建指定路由组的限流器 / Create limiter for a route group
+    pub fn for_group(live: LiveConfig, group: &str) -> Arc<Self> {
+        Arc::new(Self {
+            group: group.to_string(),
+            live,
+            buckets: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// 当前是否启用限流 / Whether rate limiting is currently enabled
+    pub fn enabled(&self) -> bool {
+        self.live.load().rate_limit.enabled
+    }
+
+    /// 尝试消耗一个令牌, 失败时返回需要等待的秒数
+    /// Try to take one token; on failure return the seconds to wait
+    pub fn check(&self, ip: IpAddr) -> Result<(), u64> {
+        let rule = self.live.load().rate_limit.rule_for(&self.group);
+        let rate = rule.requests_per_second.max(f64::MIN_POSITIVE);
+        let burst = rule.burst.max(1) as f64;
+        let now = Instant::now();
+
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+
+        if buckets.len() >= MAX_TRACKED_CLIENTS {
+            // 已经回满的桶等同于新桶, 可以安全丢弃 / Fully refilled buckets equal fresh ones and can be dropped
+            buckets.retain(|_, b| {
+                b.tokens + now.duration_since(b.last_refill).as_secs_f64() * rate < burst
+            });
+        }
+
+        let bucket = buckets.entry(ip).or_insert(TokenBucket {
+            tokens: burst,
+            last_refill: now,
+        });
+
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(burst);
+        bucket.last_refill = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            let wait = ((1.0 - bucket.tokens) / rate).ceil() as u64;
+            Err(wait.max(1))
+        }
+    }
+
+    /// 路由组名称 / Route group name
+    pub fn group(&self) -> &str {
+        &self.group
+    }
+}
+
+/// 解析客户端 IP: 默认取连接对端地址; 对端是可信代理时, 取 X-Forwarded-For 中从右往左第一个非可信代理的地址, 其次 X-Real-IP
+/// Resolve the client IP: the peer address by default; when the peer is a trusted proxy, the rightmost
+/// X-Forwarded-For address that is not itself a trusted proxy, then X-Real-IP
+///
+/// 客户端可以任意伪造这些头, 因此只信任代理追加的部分 / Clients can forge these headers freely, so only the part appended by proxies is trusted
+fn client_ip(headers: &HeaderMap, peer: Option<SocketAddr>, config: &RateLimitConfig) -> Option<IpAddr> {
+    let peer = peer.map(|addr| addr.ip())?;
+    if !config.is_trusted_proxy(peer) {
+        return Some(peer);
+    }
+
+    let forwarded = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(str::trim)
+        .collect::<Vec<_>>();
+    let mut forwarded_client = None;
+    for hop in forwarded.iter().rev() {
+        let Ok(ip) = hop.parse::<IpAddr>() else {
+            // 无法解析的一跳之后的内容都不可信 / Nothing left of an unparsable hop can be trusted
+            break;
+        };
+        forwarded_client = Some(ip);
+        if !config.is_trusted_proxy(ip) {
+            break;
+        }
+    }
+
+    forwarded_client
+        .or_else(|| {
+            headers
+                .get("x-real-ip")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.trim().parse().ok())
+        })
+        .or(Some(peer))
+}
+
+/// 限流中间件 / Rate limiting middleware
+pub async fn rate_limit(
+    State(limiter): State<Arc<RateLimiter>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| *addr);
+
+    if !limiter.enabled() {
+        return next.run(request).await;
+    }
+
+    // 无法识别客户端时放行 / Let requests through when the client cannot be identified
+    let Some(ip) = client_ip(request.headers(), peer, &limiter.live.load().rate_limit) else {
+        return next.run(request).await;
+    };
+
+    match limiter.check(ip) {
+        Ok(()) => next.run(request).await,
+        Err(retry_after) => {
+            warn!(
+                "🚦 请求被限流 / Request rate limited: ip={}, group={}, path={}",
+                ip,
+                limiter.group(),
+                request.uri().path()
+            );
+            let mut response = ApiError::TooManyRequests(
+                "请求过于频繁, 请稍后重试 / Too many requests, please retry later".to_string(),
+            )
+            .into_response();
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+            response
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(trusted: &[&str]) -> RateLimitConfig {
+        RateLimitConfig {
+            trusted_proxies: trusted.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(*name, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    fn peer(ip: &str) -> Option<SocketAddr> {
+        Some(SocketAddr::new(ip.parse().unwrap(), 40000))
+    }
+
+    #[test]
+    fn test_untrusted_peer_ignores_forwarding_headers() {
+        let spoofed = headers(&[("x-forwarded-for", "1.2.3.4"), ("x-real-ip", "5.6.7.8")]);
+        let ip = client_ip(&spoofed, peer("203.0.113.9"), &config(&[]));
+        assert_eq!(ip, Some("203.0.113.9".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_trusted_proxy_uses_rightmost_untrusted_hop() {
+        let config = config(&["10.0.0.0/8", "127.0.0.1"]);
+        // 客户端伪造的最左一跳被忽略 / The leftmost hop forged by the client is ignored
+        let forwarded = headers(&[("x-forwarded-for", "9.9.9.9, 198.51.100.7, 10.1.2.3")]);
+        let ip = client_ip(&forwarded, peer("127.0.0.1"), &config);
+        assert_eq!(ip, Some("198.51.100.7".parse().unwrap()));
+
+        let real_ip = headers(&[("x-real-ip", "198.51.100.8")]);
+        let ip = client_ip(&real_ip, peer("10.0.0.2"), &config);
+        assert_eq!(ip, Some("198.51.100.8".parse().unwrap()));
+
+        // 代理未带头时按代理地址限流 / Without headers the proxy address is used
+        let ip = client_ip(&HeaderMap::new(), peer("10.0.0.2"), &config);
+        assert_eq!(ip, Some("10.0.0.2".parse().unwrap()));
+    }
+}
@@ -0,0 +1,242 @@
+// 历史交易重处理管理接口 / Historical transaction reprocess admin endpoint
+
+use axum::{
+    extract::State,
+    routing::{get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use tracing::error;
+use utoipa::ToSchema;
+
+use crate::config::SolanaConfig;
+use crate::db::{OrderBookStorage, RocksDbStorage};
+use crate::solana::{
+    EventObservers, EventParser, ReprocessReport, Reprocessor, SolanaClient, StorageEventHandler,
+};
+use crate::util::result::{ApiError, CommonResult};
+
+/// 单次重处理允许的最大 slot 跨度 (约 5.5 小时) / Maximum slot span per reprocess request (about 5.5 hours)
+pub const MAX_REPROCESS_SLOTS: u64 = 50_000;
+
+/// 重处理接口状态 / Reprocess endpoint state
+#[derive(Clone)]
+pub struct ReprocessState {
+    pub db: Arc<RocksDbStorage>,
+    pub orderbook_storage: Arc<OrderBookStorage>,
+    pub solana_client: Arc<SolanaClient>,
+    /// 覆盖后同样通知观察者, 使缓存失效 / Observers are notified on overwrite too, so caches get invalidated
+    pub event_observers: Arc<EventObservers>,
+    pub program_id: String,
+    pub process_failed_transactions: bool,
+    /// 当前/最近一次任务状态, 同一时间只允许一个任务 / Current or last job status; only one job may run at a time
+    job: Arc<Mutex<ReprocessStatus>>,
+}
+
+impl ReprocessState {
+    pub fn new(
+        db: Arc<RocksDbStorage>,
+        orderbook_storage: Arc<OrderBookStorage>,
+        solana_client: Arc<SolanaClient>,
+        event_observers: Arc<EventObservers>,
+        solana: &SolanaConfig,
+    ) -> Self {
+        Self {
+            db,
+            orderbook_storage,
+            solana_client,
+            event_observers,
+            program_id: solana.program_id.clone(),
+            process_failed_transactions: solana.process_failed_transactions,
+            job: Arc::new(Mutex::new(ReprocessStatus::default())),
+        }
+    }
+
+    fn status(&self) -> ReprocessStatus {
+        self.job.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn update(&self, f: impl FnOnce(&mut ReprocessStatus)) {
+        f(&mut self.job.lock().unwrap_or_else(|e| e.into_inner()));
+    }
+}
+
+/// 重处理任务状态 / Reprocess job state
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReprocessJobState {
+    /// 未运行过 / Never run
+    #[default]
+    Idle,
+    /// 运行中 / Running
+    Running,
+    /// 已完成 / Finished
+    Finished,
+    /// 失败 / Failed
+    Failed,
+}
+
+/// 重处理任务状态 / Reprocess job status
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+#[schema(title = "ReprocessStatus", description = "历史交易重处理任务状态 / Historical transaction reprocess job status")]
+pub struct ReprocessStatus {
+    /// 任务状态 / Job state
+    pub state: ReprocessJobState,
+    /// 起始 slot (含) / Start slot (inclusive)
+    #[schema(example = 250000000)]
+    pub from_slot: Option<u64>,
+    /// 结束 slot (含) / End slot (inclusive)
+    #[schema(example = 250001000)]
+    pub to_slot: Option<u64>,
+    /// 开始时间 (Unix 秒) / Start time (Unix seconds)
+    pub started_at: Option<i64>,
+    /// 结束时间 (Unix 秒) / End time (Unix seconds)
+    pub finished_at: Option<i64>,
+    /// 完成时的结果 / Report once finished
+    pub report: Option<ReprocessReport>,
+    /// 失败原因 / Failure reason
+    pub error: Option<String>,
+}
+
+/// 重处理请求 / Reprocess request
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(title = "ReprocessRequest", description = "历史交易重处理请求 / Historical transaction reprocess request")]
+pub struct ReprocessRequest {
+    /// 起始 slot (含) / Start slot (inclusive)
+    #[schema(example = 250000000)]
+    pub from_slot: u64,
+    /// 结束 slot (含) / End slot (inclusive)
+    #[schema(example = 250001000)]
+    pub to_slot: u64,
+}
+
+/// 启动重处理指定 slot 区间内的历史交易, 在后台执行
+#[utoipa::path(
+    post,
+    path = "/admin/reprocess",
+    tag = "admin",
+    security(("api_key" = [])),
+    summary = "重处理历史交易 / Reprocess historical transactions",
+    description = "修复解析错误后, 在后台通过 RPC 拉取 [from_slot, to_slot] 区间内的程序交易并按 slot 从旧到新重新经过存储处理链, 立即返回任务状态, 通过 GET /admin/reprocess/status 轮询结果。已存储的交易只覆盖事件记录和Token详情, 未存储的交易完整处理; 结束后涉及的 mint 重新读取链上曲线状态、按事件重新统计成交笔数, 与事件回放不一致的订单簿标记为需要重建; 不推送 Webhook 和 K线。单次最多 50000 个 slot, 同一时间只允许一个任务 / After a parsing fix, fetch program transactions in [from_slot, to_slot] over RPC in the background and replay them oldest first through the storage handler chain; returns the job status immediately, poll GET /admin/reprocess/status for the result. Already stored transactions only overwrite their event records and token details, missing ones are fully processed; afterwards the touched mints re-read their curve state from chain, recount trades from events, and order books that disagree with the event replay are marked for a rebuild; no webhooks or K-line pushes. At most 50000 slots per request and one job at a time",
+    request_body = ReprocessRequest,
+    responses(
+        (status = 200, description = "任务已启动 / Job started",
+         body = crate::docs::ApiResponse<ReprocessStatus>),
+        (status = 400, description = "slot 区间无效 / Invalid slot range",
+         body = crate::docs::ErrorApiResponse),
+        (status = 401, description = "未授权 / Unauthorized",
+         body = crate::docs::ErrorApiResponse),
+        (status = 409, description = "已有重处理任务在运行 / A reprocess job is already running",
+         body = crate::docs::ErrorApiResponse),
+        (status = 500, description = "服务器内部错误",
+         body = crate::docs::ErrorApiResponse)
+    )
+)]
+pub async fn reprocess(
+    State(state): State<ReprocessState>,
+    Json(req): Json<ReprocessRequest>,
+) -> Result<Json<CommonResult<ReprocessStatus>>, ApiError> {
+    if req.from_slot > req.to_slot {
+        return Err(ApiError::BadRequest(
+            "from_slot 不能大于 to_slot / from_slot must not exceed to_slot".to_string(),
+        ));
+    }
+    if req.to_slot - req.from_slot >= MAX_REPROCESS_SLOTS {
+        return Err(ApiError::BadRequest(format!(
+            "slot 区间过大, 最多 {} 个 / Slot range too large, at most {}",
+            MAX_REPROCESS_SLOTS, MAX_REPROCESS_SLOTS
+        )));
+    }
+
+    let started = {
+        let mut job = state.job.lock().unwrap_or_else(|e| e.into_inner());
+        if job.state == ReprocessJobState::Running {
+            return Err(ApiError::Conflict(
+                "已有重处理任务在运行 / A reprocess job is already running".to_string(),
+            ));
+        }
+        *job = ReprocessStatus {
+            state: ReprocessJobState::Running,
+            from_slot: Some(req.from_slot),
+            to_slot: Some(req.to_slot),
+            started_at: Some(chrono::Utc::now().timestamp()),
+            ..Default::default()
+        };
+        job.clone()
+    };
+
+    tokio::spawn(async move {
+        let result = run_job(&state, req.from_slot, req.to_slot).await;
+        state.update(|job| {
+            job.finished_at = Some(chrono::Utc::now().timestamp());
+            match result {
+                Ok(report) => {
+                    job.state = ReprocessJobState::Finished;
+                    job.report = Some(report);
+                }
+                Err(e) => {
+                    error!("❌ 重处理失败 / Reprocess failed: {}", e);
+                    job.state = ReprocessJobState::Failed;
+                    job.error = Some(e.to_string());
+                }
+            }
+        });
+    });
+
+    Ok(Json(CommonResult::ok(started)))
+}
+
+/// 查询重处理任务状态
+#[utoipa::path(
+    get,
+    path = "/admin/reprocess/status",
+    tag = "admin",
+    security(("api_key" = [])),
+    summary = "重处理任务状态 / Reprocess job status",
+    description = "当前或最近一次重处理任务的状态, 完成后包含结果 / Status of the current or last reprocess job, with its report once finished",
+    responses(
+        (status = 200, description = "任务状态 / Job status",
+         body = crate::docs::ApiResponse<ReprocessStatus>),
+        (status = 401, description = "未授权 / Unauthorized",
+         body = crate::docs::ErrorApiResponse)
+    )
+)]
+pub async fn reprocess_status(State(state): State<ReprocessState>) -> Json<CommonResult<ReprocessStatus>> {
+    Json(CommonResult::ok(state.status()))
+}
+
+/// 执行重处理并修正涉及 mint 的派生状态 / Run the reprocess and correct the derived state of the touched mints
+async fn run_job(state: &ReprocessState, from_slot: u64, to_slot: u64) -> anyhow::Result<ReprocessReport> {
+    // 独立的覆盖模式处理链, 不接入 Webhook / K线 / Dedicated overwrite-mode handler chain, without webhooks / K-line
+    let handler = StorageEventHandler::new(
+        Arc::new(state.db.create_event_storage()?),
+        Arc::new(state.db.create_token_storage()?),
+        state.orderbook_storage.clone(),
+    )
+    .with_curve_storage(Arc::new(state.db.create_curve_storage()))
+    .with_solana_client(state.solana_client.clone())
+    .with_observers(state.event_observers.clone())
+    .with_overwrite();
+    let parser = EventParser::new(&state.program_id)?;
+
+    let mut report = Reprocessor::new(
+        &state.solana_client,
+        &parser,
+        &handler,
+        &state.program_id,
+        state.process_failed_transactions,
+    )
+    .run(from_slot, to_slot)
+    .await?;
+
+    handler.resync_reprocessed_mints(to_slot, &mut report).await;
+    Ok(report)
+}
+
+/// 创建重处理管理路由 (需要 API-Key) / Create reprocess admin routes (API key required)
+pub fn admin_routes() -> Router<ReprocessState> {
+    Router::new()
+        .route("/admin/reprocess", post(reprocess))
+        .route("/admin/reprocess/status", get(reprocess_status))
+}
@@ -0,0 +1,72 @@
+// 请求 ID 与结构化日志中间件 / Request-id and structured logging middleware
+//
+// 每个请求都在带 request_id 的 tracing span 中执行, 错误路径上的日志也会带上该 id
+// Every request runs inside a tracing span carrying request_id, so error-path logs carry it too
+
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use std::time::Instant;
+use tracing::{error, info, info_span, warn, Instrument};
+use uuid::Uuid;
+
+/// 请求 ID 请求头 / Request-id header
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// 最大接受的外部请求 ID 长度 / Max accepted length for an incoming request id
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// 请求 ID, 存放在请求扩展中供处理函数读取 / Request id, stored in request extensions for handlers
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+/// 读取合法的外部请求 ID, 否则生成新的 / Honor a valid incoming request id, otherwise generate one
+fn resolve_request_id(request: &Request) -> String {
+    request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|v| {
+            !v.is_empty()
+                && v.len() <= MAX_REQUEST_ID_LEN
+                && v.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        })
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string())
+}
+
+/// 请求 ID 中间件 / Request-id middleware
+pub async fn request_id(mut request: Request, next: Next) -> Response {
+    let id = resolve_request_id(&request);
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    request.extensions_mut().insert(RequestId(id.clone()));
+
+    let span = info_span!("request", request_id = %id, method = %method, path = %path);
+    let started = Instant::now();
+
+    let mut response = next.run(request).instrument(span.clone()).await;
+
+    let status = response.status();
+    let elapsed_ms = started.elapsed().as_millis();
+    span.in_scope(|| {
+        if status.is_server_error() {
+            error!(status = status.as_u16(), elapsed_ms, "❌ 请求失败 / Request failed");
+        } else if status.is_client_error() {
+            warn!(status = status.as_u16(), elapsed_ms, "⚠️ 请求被拒绝 / Request rejected");
+        } else {
+            info!(status = status.as_u16(), elapsed_ms, "✅ 请求完成 / Request completed");
+        }
+    });
+
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response
+            .headers_mut()
+            .insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+    }
+    response
+}
@@ -0,0 +1,61 @@
+// 服务器时间接口 / Server time endpoint
+
+use axum::{extract::State, routing::get, Router};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::warn;
+use utoipa::ToSchema;
+
+use crate::solana::client::ChainClock;
+use crate::solana::SolanaClient;
+use crate::util::time::unix_now;
+use crate::util::{ok_result, ApiResult};
+
+/// 链上时钟超过该秒数未更新时, 请求会先刷新一次 slot / Refresh the slot first when the chain clock is older than this many seconds
+const CHAIN_CLOCK_REFRESH_SECS: i64 = 30;
+
+/// 服务器时间响应 / Server time response
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(title = "TimeResponse", description = "服务器时间与链上时钟 / Server time and on-chain clock")]
+pub struct TimeResponse {
+    /// 服务器当前时间(Unix 秒), 与订单簿 start_time / end_time 同单位
+    /// Server's current time (Unix seconds), same unit as order book start_time / end_time
+    pub server_time: i64,
+    /// 最近观察到的链上 slot 和区块时间, RPC 不可用时为空
+    /// Latest observed on-chain slot and block time, null while RPC is unavailable
+    pub chain: Option<ChainClock>,
+}
+
+/// 获取服务器时间 / Get server time
+#[utoipa::path(
+    get,
+    path = "/time",
+    tag = "system",
+    summary = "服务器时间 / Server time",
+    description = "返回服务器当前 Unix 时间(秒)和最近观察到的链上 slot/区块时间, 供客户端对齐冷却时间和订单到期时间 / Returns the server's current Unix time (seconds) and the latest observed on-chain slot/block time, so clients can reconcile cooldowns and order expiry",
+    responses(
+        (status = 200, description = "获取成功 / Success",
+         body = crate::docs::ApiResponse<TimeResponse>),
+    )
+)]
+pub async fn get_time(State(solana_client): State<Arc<SolanaClient>>) -> ApiResult {
+    let stale = solana_client
+        .chain_clock()
+        .map_or(true, |clock| unix_now() - clock.slot_observed_at > CHAIN_CLOCK_REFRESH_SECS);
+    if stale {
+        if let Err(e) = solana_client.get_slot().await {
+            warn!("⚠️ 刷新链上 slot 失败 / Failed to refresh on-chain slot: {}", e);
+        }
+    }
+
+    let response = TimeResponse {
+        server_time: unix_now(),
+        chain: solana_client.chain_clock(),
+    };
+    Ok(ok_result(Ok(response)))
+}
+
+/// 创建服务器时间路由 / Create server time routes
+pub fn routes() -> Router<Arc<SolanaClient>> {
+    Router::new().route("/time", get(get_time))
+}
@@ -1,23 +1,32 @@
 // Token查询路由处理器 / Token query route handlers
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
-    response::IntoResponse,
-    routing::get,
+    routing::{get, post},
     Json,
     Router,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
 use utoipa::{IntoParams, ToSchema};
 
-use crate::db::TokenStorage;
-use crate::util::CommonResult;
+use crate::db::event_storage::TokenSummary24h;
+use crate::db::{CurveState, CurveStorage, EventStorage, PriceRecord, TokenDetail, TokenStorage};
+use crate::util::curve::{CurveAMM, MIN_TRADE_TOKEN_AMOUNT};
+use crate::util::margin::{estimate_liquidation, PositionSide};
+use crate::util::result::{ApiError, CommonResult};
+use crate::util::validate::{invalid, validate_mint};
+use crate::util::PageInfo;
+use validator::{Validate, ValidationError};
+
+use super::extract::ValidatedQuery;
 
 /// Token查询的共享状态 / Shared state for token queries 
 #[derive(Clone)]
 pub struct TokenState {
     pub token_storage: Arc<TokenStorage>,
+    pub event_storage: Arc<EventStorage>,
+    pub curve_storage: Arc<CurveStorage>,
 }
 
 /// 根据mint查询Token参数 / Get token by mint parameters
@@ -28,29 +37,33 @@ pub struct GetTokenByMintParams {
 }
 
 /// 根据symbol查询Token列表参数 / Get tokens by symbol parameters
-#[derive(Debug, Deserialize, IntoParams)]
+#[derive(Debug, Deserialize, IntoParams, Validate)]
 pub struct GetTokensBySymbolParams {
     /// Token符号 / Token symbol
+    #[validate(length(min = 1, message = "must not be empty"))]
     pub symbol: String,
     /// 每页数量(默认20,最大100) / Items per page (default 20, max 100)
     #[serde(default = "default_limit")]
+    #[validate(range(min = 1, max = 100, message = "must be between 1 and 100"))]
     pub limit: usize,
     /// 游标(用于分页) / Cursor (for pagination)
     pub cursor: Option<String>,
 }
 
 /// 获取最新Token列表参数 / Get latest tokens parameters
-#[derive(Debug, Deserialize, IntoParams)]
+#[derive(Debug, Deserialize, IntoParams, Validate)]
 pub struct GetLatestTokensParams {
     /// 每页数量(默认20,最大100) / Items per page (default 20, max 100)
     #[serde(default = "default_limit")]
+    #[validate(range(min = 1, max = 100, message = "must be between 1 and 100"))]
     pub limit: usize,
     /// 查询此时间戳之前的tokens / Get tokens before this timestamp
     pub before_timestamp: Option<i64>,
 }
 
 /// 按slot范围查询Token参数 / Get tokens by slot range parameters
-#[derive(Debug, Deserialize, IntoParams)]
+#[derive(Debug, Deserialize, IntoParams, Validate)]
+#[validate(schema(function = "validate_slot_range"))]
 pub struct GetTokensBySlotRangeParams {
     /// 起始slot / Start slot
     pub start_slot: u64,
@@ -58,21 +71,61 @@ pub struct GetTokensBySlotRangeParams {
     pub end_slot: u64,
 }
 
+/// 活跃Token查询参数 / Active tokens parameters
+#[derive(Debug, Deserialize, IntoParams, Validate)]
+pub struct GetActiveTokensParams {
+    /// 起始时间戳, 返回此后有成交的Token / Start timestamp, tokens traded since then are returned
+    pub since: i64,
+    /// 每页数量(默认20,最大100) / Items per page (default 20, max 100)
+    #[serde(default = "default_limit")]
+    #[validate(range(min = 1, max = 100, message = "must be between 1 and 100"))]
+    pub limit: usize,
+    /// 查询最近成交早于此时间戳的tokens / Get tokens whose last trade is before this timestamp
+    pub before_timestamp: Option<i64>,
+}
+
+/// 遍历所有Token参数 / Iterate all tokens parameters
+#[derive(Debug, Deserialize, IntoParams, Validate)]
+pub struct GetAllTokensParams {
+    /// 每页数量(默认20,最大100) / Items per page (default 20, max 100)
+    #[serde(default = "default_limit")]
+    #[validate(range(min = 1, max = 100, message = "must be between 1 and 100"))]
+    pub limit: usize,
+    /// 游标, 上一页最后一个mint / Cursor, the last mint of the previous page
+    pub cursor: Option<String>,
+}
+
 /// Token列表响应 / Token list response
 #[derive(Debug, Serialize, ToSchema)]
 pub struct TokenListResponse {
     /// Token列表 / Token list
     pub tokens: Vec<crate::db::TokenDetail>,
-    /// 总数 / Total count
-    pub total: usize,
-    /// 下一页游标(如果有) / Next cursor (if exists)
-    pub next_cursor: Option<String>,
+    /// 分页信息; `total` 沿用原有含义, 为本页数量 / Pagination fields; `total` keeps its original meaning, the count of this page
+    #[serde(flatten)]
+    pub pagination: PageInfo,
+}
+
+impl TokenListResponse {
+    fn new(tokens: Vec<TokenDetail>, page_size: usize, next_cursor: Option<String>) -> Self {
+        let total = tokens.len() as u64;
+        Self {
+            tokens,
+            pagination: PageInfo::cursor(page_size as u32, next_cursor).with_total(total),
+        }
+    }
 }
 
 fn default_limit() -> usize {
     20
 }
 
+fn validate_slot_range(params: &GetTokensBySlotRangeParams) -> Result<(), ValidationError> {
+    if params.start_slot > params.end_slot {
+        return Err(invalid("slot_range", "start_slot must be less than or equal to end_slot"));
+    }
+    Ok(())
+}
+
 /// 根据mint查询Token详情
 /// Get token detail by mint address
 #[utoipa::path(
@@ -83,6 +136,7 @@ fn default_limit() -> usize {
     ),
     responses(
         (status = 200, description = "成功返回Token详情 / Successfully returned token detail"),
+        (status = 400, description = "mint 格式无效 / Malformed mint"),
         (status = 404, description = "Token未找到 / Token not found"),
         (status = 500, description = "服务器内部错误 / Internal server error")
     ),
@@ -91,17 +145,69 @@ fn default_limit() -> usize {
 pub async fn get_token_by_mint(
     State(state): State<TokenState>,
     Path(mint): Path<String>,
-) -> impl IntoResponse {
+) -> Result<Json<CommonResult<TokenDetail>>, ApiError> {
+    validate_mint(&mint)?;
+
     match state.token_storage.get_token_by_mint(&mint) {
         Ok(Some(token)) => Ok(Json(CommonResult::ok(token))),
-        Ok(None) => Err((
-            StatusCode::NOT_FOUND,
-            format!("Token not found: {}", mint),
-        )),
-        Err(e) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Failed to query token: {}", e),
-        )),
+        Ok(None) => Err(ApiError::NotFound(format!("Token not found: {}", mint))),
+        Err(e) => Err(ApiError::Storage(format!("Failed to query token: {}", e))),
+    }
+}
+
+/// 单次批量查询最多的 mint 数 / Maximum number of mints per batch query
+pub const MAX_TOKENS_BATCH: usize = 100;
+
+/// 批量查询Token请求 / Batch token query request
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct TokensBatchRequest {
+    /// Token mint 地址列表(最多100个) / Token mint addresses (at most 100)
+    pub mints: Vec<String>,
+}
+
+/// 批量查询Token响应 / Batch token query response
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TokensBatchResponse {
+    /// mint → Token详情, 未知的 mint 不返回 / mint → token detail, unknown mints are omitted
+    pub tokens: HashMap<String, TokenDetail>,
+}
+
+/// 批量查询Token详情
+/// Get token details for many mints at once
+#[utoipa::path(
+    post,
+    path = "/api/tokens/batch",
+    request_body = TokensBatchRequest,
+    responses(
+        (status = 200, description = "成功返回Token详情 / Successfully returned token details", body = TokensBatchResponse),
+        (status = 400, description = "mint 数量超过上限或格式无效 / Too many mints or a malformed mint"),
+        (status = 500, description = "服务器内部错误 / Internal server error")
+    ),
+    tag = "tokens"
+)]
+pub async fn get_tokens_batch(
+    State(state): State<TokenState>,
+    Json(req): Json<TokensBatchRequest>,
+) -> Result<Json<CommonResult<TokensBatchResponse>>, ApiError> {
+    if req.mints.len() > MAX_TOKENS_BATCH {
+        return Err(ApiError::BadRequest(format!(
+            "Too many mints: {}, at most {}",
+            req.mints.len(),
+            MAX_TOKENS_BATCH
+        )));
+    }
+
+    for mint in &req.mints {
+        validate_mint(mint)?;
+    }
+
+    let mut mints = req.mints;
+    mints.sort();
+    mints.dedup();
+
+    match state.token_storage.get_tokens_batch(&mints) {
+        Ok(tokens) => Ok(Json(CommonResult::ok(TokensBatchResponse { tokens }))),
+        Err(e) => Err(ApiError::Storage(format!("Failed to query tokens: {}", e))),
     }
 }
 
@@ -124,18 +230,17 @@ pub async fn get_token_by_mint(
 )]
 pub async fn get_tokens_by_symbol(
     State(state): State<TokenState>,
-    Query(params): Query<GetTokensBySymbolParams>,
-) -> impl IntoResponse {
+    ValidatedQuery(params): ValidatedQuery<GetTokensBySymbolParams>,
+) -> Result<Json<CommonResult<TokenListResponse>>, ApiError> {
     // 限制最大每页数量 / Limit max items per page
-    let limit = params.limit.min(100);
+    let limit = params.limit;
 
     match state
         .token_storage
         .get_tokens_by_symbol(&params.symbol, limit, params.cursor)
     {
         Ok(tokens) => {
-            let total = tokens.len();
-            let next_cursor = if total >= limit {
+            let next_cursor = if tokens.len() >= limit {
                 tokens.last().map(|t| {
                     format!(
                         "token_symbol:{}:{}",
@@ -147,16 +252,9 @@ pub async fn get_tokens_by_symbol(
                 None
             };
 
-            Ok(Json(CommonResult::ok(TokenListResponse {
-                tokens,
-                total,
-                next_cursor,
-            })))
+            Ok(Json(CommonResult::ok(TokenListResponse::new(tokens, limit, next_cursor))))
         }
-        Err(e) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Failed to query tokens by symbol: {}", e),
-        )),
+        Err(e) => Err(ApiError::Storage(format!("Failed to query tokens by symbol: {}", e))),
     }
 }
 
@@ -178,38 +276,29 @@ pub async fn get_tokens_by_symbol(
 )]
 pub async fn get_latest_tokens(
     State(state): State<TokenState>,
-    Query(params): Query<GetLatestTokensParams>,
-) -> impl IntoResponse {
+    ValidatedQuery(params): ValidatedQuery<GetLatestTokensParams>,
+) -> Result<Json<CommonResult<TokenListResponse>>, ApiError> {
     // 限制最大每页数量 / Limit max items per page
-    let limit = params.limit.min(100);
+    let limit = params.limit;
 
     match state
         .token_storage
         .get_latest_tokens(limit, params.before_timestamp)
     {
         Ok(tokens) => {
-            let total = tokens.len();
-
             // 计算下一页游标 / Calculate next cursor
             // 如果返回了完整的一页，使用最后一个token的created_at作为游标
             // If a full page is returned, use the last token's created_at as cursor
-            let next_cursor = if total >= limit {
+            let next_cursor = if tokens.len() >= limit {
                 tokens.last().map(|t| t.created_at.to_string())
             } else {
                 // 如果少于limit，说明已经是最后一页 / Less than limit means last page
                 None
             };
 
-            Ok(Json(CommonResult::ok(TokenListResponse {
-                tokens,
-                total,
-                next_cursor,
-            })))
+            Ok(Json(CommonResult::ok(TokenListResponse::new(tokens, limit, next_cursor))))
         }
-        Err(e) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Failed to query latest tokens: {}", e),
-        )),
+        Err(e) => Err(ApiError::Storage(format!("Failed to query latest tokens: {}", e))),
     }
 }
 
@@ -231,31 +320,93 @@ pub async fn get_latest_tokens(
 )]
 pub async fn get_tokens_by_slot_range(
     State(state): State<TokenState>,
-    Query(params): Query<GetTokensBySlotRangeParams>,
-) -> impl IntoResponse {
-    if params.start_slot > params.end_slot {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            "start_slot must be less than or equal to end_slot".to_string(),
-        ));
+    ValidatedQuery(params): ValidatedQuery<GetTokensBySlotRangeParams>,
+) -> Result<Json<CommonResult<TokenListResponse>>, ApiError> {
+    match state
+        .token_storage
+        .get_tokens_by_slot_range(params.start_slot, params.end_slot)
+    {
+        Ok(tokens) => {
+            // 一次返回整个 slot 范围 / The whole slot range is returned at once
+            let page_size = tokens.len();
+            Ok(Json(CommonResult::ok(TokenListResponse::new(tokens, page_size, None))))
+        }
+        Err(e) => Err(ApiError::Storage(format!("Failed to query tokens by slot range: {}", e))),
     }
+}
+
+/// 获取近期有成交的Token列表
+/// Get tokens traded recently
+#[utoipa::path(
+    get,
+    path = "/api/tokens/active",
+    params(
+        ("since" = i64, Query, description = "起始时间戳, 返回此后有成交的Token / Start timestamp, tokens traded since then are returned"),
+        ("limit" = Option<usize>, Query, description = "每页数量(默认20,最大100) / Items per page (default 20, max 100)"),
+        ("before_timestamp" = Option<i64>, Query, description = "查询最近成交早于此时间戳的tokens / Get tokens whose last trade is before this timestamp")
+    ),
+    responses(
+        (status = 200, description = "成功返回按最近成交倒序的Token列表 / Successfully returned tokens, newest last trade first",
+         body = crate::docs::ApiResponse<TokenListResponse>),
+        (status = 500, description = "服务器内部错误 / Internal server error")
+    ),
+    tag = "tokens"
+)]
+pub async fn get_active_tokens(
+    State(state): State<TokenState>,
+    ValidatedQuery(params): ValidatedQuery<GetActiveTokensParams>,
+) -> Result<Json<CommonResult<TokenListResponse>>, ApiError> {
+    // 限制最大每页数量 / Limit max items per page
+    let limit = params.limit;
 
     match state
         .token_storage
-        .get_tokens_by_slot_range(params.start_slot, params.end_slot)
+        .get_active_tokens(params.since, limit, params.before_timestamp)
     {
         Ok(tokens) => {
-            let total = tokens.len();
-            Ok(Json(CommonResult::ok(TokenListResponse {
-                tokens,
-                total,
-                next_cursor: None,
-            })))
+            // 返回完整一页时, 使用最后一个token的last_trade_time作为游标
+            // When a full page is returned, use the last token's last_trade_time as cursor
+            let next_cursor = if tokens.len() >= limit {
+                tokens
+                    .last()
+                    .and_then(|t| t.last_trade_time)
+                    .map(|t| t.to_string())
+            } else {
+                None
+            };
+
+            Ok(Json(CommonResult::ok(TokenListResponse::new(tokens, limit, next_cursor))))
         }
-        Err(e) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Failed to query tokens by slot range: {}", e),
-        )),
+        Err(e) => Err(ApiError::Storage(format!("Failed to query active tokens: {}", e))),
+    }
+}
+
+/// 按mint顺序分页遍历所有Token
+/// Page through all tokens in mint order
+#[utoipa::path(
+    get,
+    path = "/api/tokens/all",
+    params(
+        ("limit" = Option<usize>, Query, description = "每页数量(默认20,最大100) / Items per page (default 20, max 100)"),
+        ("cursor" = Option<String>, Query, description = "游标, 上一页返回的next_cursor / Cursor, next_cursor of the previous page")
+    ),
+    responses(
+        (status = 200, description = "成功返回Token列表 / Successfully returned token list",
+         body = crate::docs::ApiResponse<TokenListResponse>),
+        (status = 500, description = "服务器内部错误 / Internal server error")
+    ),
+    tag = "tokens"
+)]
+pub async fn get_all_tokens(
+    State(state): State<TokenState>,
+    ValidatedQuery(params): ValidatedQuery<GetAllTokensParams>,
+) -> Result<Json<CommonResult<TokenListResponse>>, ApiError> {
+    // 限制每页数量 / Clamp items per page
+    let limit = params.limit;
+
+    match state.token_storage.iter_all(params.cursor, limit) {
+        Ok((tokens, next_cursor)) => Ok(Json(CommonResult::ok(TokenListResponse::new(tokens, limit, next_cursor)))),
+        Err(e) => Err(ApiError::Storage(format!("Failed to iterate tokens: {}", e))),
     }
 }
 
@@ -272,31 +423,382 @@ pub async fn get_tokens_by_slot_range(
 )]
 pub async fn get_token_stats(
     State(state): State<TokenState>,
-) -> impl IntoResponse {
-    match state.token_storage.get_token_count() {
-        Ok(count) => Ok(Json(CommonResult::ok(TokenStatsResponse {
-            total_tokens: count,
+) -> Result<Json<CommonResult<TokenStatsResponse>>, ApiError> {
+    let stats = state
+        .token_storage
+        .get_token_count()
+        .and_then(|count| Ok((count, state.token_storage.total_trade_count()?)));
+
+    match stats {
+        Ok((total_tokens, total_trades)) => Ok(Json(CommonResult::ok(TokenStatsResponse {
+            total_tokens,
+            total_trades,
         }))),
-        Err(e) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Failed to get token stats: {}", e),
-        )),
+        Err(e) => Err(ApiError::Storage(format!("Failed to get token stats: {}", e))),
     }
 }
 
+/// 获取Token 24小时行情摘要
+/// Get token 24h market summary
+#[utoipa::path(
+    get,
+    path = "/api/tokens/mint/{mint}/summary",
+    params(
+        ("mint" = String, Path, description = "Token mint地址 / Token mint address")
+    ),
+    responses(
+        (status = 200, description = "成功返回24小时行情 (最新价、开高低、成交额、涨跌幅) / Successfully returned 24h summary (last, open/high/low, volume, change)",
+         body = crate::docs::ApiResponse<TokenSummary24h>),
+        (status = 400, description = "mint 格式无效 / Malformed mint"),
+        (status = 500, description = "服务器内部错误 / Internal server error")
+    ),
+    tag = "tokens"
+)]
+pub async fn get_token_summary(
+    State(state): State<TokenState>,
+    Path(mint): Path<String>,
+) -> Result<Json<CommonResult<TokenSummary24h>>, ApiError> {
+    validate_mint(&mint)?;
+
+    match state.event_storage.token_summary_24h(&mint, chrono::Utc::now()) {
+        Ok(summary) => Ok(Json(CommonResult::ok(summary))),
+        Err(e) => Err(ApiError::Storage(format!("Failed to compute token summary: {}", e))),
+    }
+}
+
+/// 获取Token的借贷曲线状态
+/// Get token borrowing-curve state
+///
+/// 价格、储备量和费率来自事件, fee_split / borrow_duration 来自链上账户快照 (未同步时为空)
+/// Price, reserves and fees come from events; fee_split / borrow_duration come from an on-chain account snapshot (null until synced)
+#[utoipa::path(
+    get,
+    path = "/api/tokens/mint/{mint}/curve",
+    params(
+        ("mint" = String, Path, description = "Token mint地址 / Token mint address")
+    ),
+    responses(
+        (status = 200, description = "成功返回曲线状态 / Successfully returned curve state",
+         body = crate::docs::ApiResponse<CurveState>),
+        (status = 400, description = "mint 格式无效 / Malformed mint"),
+        (status = 404, description = "曲线状态未找到 / Curve state not found"),
+        (status = 500, description = "服务器内部错误 / Internal server error")
+    ),
+    tag = "tokens"
+)]
+pub async fn get_token_curve(
+    State(state): State<TokenState>,
+    Path(mint): Path<String>,
+) -> Result<Json<CommonResult<CurveState>>, ApiError> {
+    validate_mint(&mint)?;
+
+    match state.curve_storage.get_curve(&mint) {
+        Ok(Some(curve)) => Ok(Json(CommonResult::ok(curve))),
+        Ok(None) => Err(ApiError::NotFound(format!("Curve state not found: {}", mint))),
+        Err(e) => Err(ApiError::Storage(format!("Failed to query curve state: {}", e))),
+    }
+}
+
+/// 最新价格响应 / Latest price response
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TokenPriceResponse {
+    /// Token mint地址 / Token mint address
+    pub mint: String,
+    /// 规范最新价格及其来源事件 / Canonical latest price and its source event
+    #[serde(flatten)]
+    pub record: PriceRecord,
+    /// 距上次更新的秒数 / Seconds since the last update
+    pub staleness_secs: i64,
+}
+
+/// 获取Token的规范最新价格
+/// Get the canonical latest price of a token
+///
+/// 由每个带 latest_price 的事件按 slot 前进更新, 报价与K线快照使用同一价格
+/// Advanced by every event carrying latest_price in slot order; quotes and K-line snapshots use the same price
+#[utoipa::path(
+    get,
+    path = "/api/tokens/mint/{mint}/price",
+    params(
+        ("mint" = String, Path, description = "Token mint地址 / Token mint address")
+    ),
+    responses(
+        (status = 200, description = "成功返回最新价格 / Successfully returned latest price",
+         body = crate::docs::ApiResponse<TokenPriceResponse>),
+        (status = 400, description = "mint 格式无效 / Malformed mint"),
+        (status = 404, description = "价格未找到 / Price not found"),
+        (status = 500, description = "服务器内部错误 / Internal server error")
+    ),
+    tag = "tokens"
+)]
+pub async fn get_token_price(
+    State(state): State<TokenState>,
+    Path(mint): Path<String>,
+) -> Result<Json<CommonResult<TokenPriceResponse>>, ApiError> {
+    validate_mint(&mint)?;
+
+    match state.token_storage.get_price(&mint) {
+        Ok(Some(record)) => {
+            let staleness_secs = (chrono::Utc::now().timestamp() - record.timestamp).max(0);
+            Ok(Json(CommonResult::ok(TokenPriceResponse { mint, record, staleness_secs })))
+        }
+        Ok(None) => Err(ApiError::NotFound(format!("Price not found: {}", mint))),
+        Err(e) => Err(ApiError::Storage(format!("Failed to query price: {}", e))),
+    }
+}
+
+/// 读取规范最新价格, 尚未写入时回退到Token记录中的价格
+/// Read the canonical latest price, falling back to the token record's price before one is written
+fn canonical_price(state: &TokenState, mint: &str, token: &TokenDetail) -> Result<u128, ApiError> {
+    match state.token_storage.get_price(mint) {
+        Ok(Some(record)) => Ok(record.price),
+        Ok(None) => token.latest_price.parse().map_err(|_| {
+            ApiError::Storage(format!("Invalid stored price for {}: {}", mint, token.latest_price))
+        }),
+        Err(e) => Err(ApiError::Storage(format!("Failed to query price: {}", e))),
+    }
+}
+
+/// 现货报价参数 / Spot swap quote parameters
+#[derive(Debug, Deserialize, IntoParams, Validate)]
+pub struct SwapQuoteParams {
+    /// 交易方向: buy 或 sell / Trade side: buy or sell
+    #[validate(custom(function = "validate_swap_side"))]
+    pub side: String,
+    /// 买入得到或卖出付出的 token 数量(6位精度) / Token amount to receive (buy) or pay (sell), 6 decimals
+    #[validate(range(min = MIN_TRADE_TOKEN_AMOUNT, message = "is below the minimum trade size"))]
+    pub token_amount: u64,
+}
+
+fn validate_swap_side(side: &str) -> Result<(), ValidationError> {
+    if side != "buy" && side != "sell" {
+        return Err(invalid("side", format!("invalid side '{}', expected 'buy' or 'sell'", side)));
+    }
+    Ok(())
+}
+
+/// 现货报价响应 / Spot swap quote response
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SwapQuoteResponse {
+    /// 交易方向 / Trade side
+    pub side: String,
+    /// token 数量 / Token amount
+    pub token_amount: u64,
+    /// 买入需支付的 SOL(含手续费) 或卖出可得的 SOL(已扣手续费) / SOL to pay incl. fee (buy) or receive net of fee (sell)
+    pub sol_amount: u64,
+    /// 手续费(SOL) / Fee (SOL)
+    pub fee_sol: u64,
+    /// 手续费率(以 100000 为分母) / Fee rate (over 100000)
+    pub swap_fee: u16,
+    /// 交易前价格(u128 字符串) / Price before the trade (u128 as string)
+    pub price_before: String,
+    /// 交易后价格(u128 字符串) / Price after the trade (u128 as string)
+    pub price_after: String,
+}
+
+/// 获取现货买卖报价
+/// Get spot buy/sell quote
+///
+/// 单段曲线近似: 按当前价格在曲线上直接计算, 不处理穿越保证金订单锁定区间(链上 buy_amounts/sell_amounts 会跳过锁定区间并强平),
+/// 价格穿越锁定区间时实际成交会比报价更差
+/// Single-segment approximation: computed directly on the curve from the current price, without the margin-order lock range
+/// handling of on-chain buy_amounts/sell_amounts; trades that cross a locked range will fill worse than quoted
+#[utoipa::path(
+    get,
+    path = "/api/tokens/mint/{mint}/quote",
+    params(
+        ("mint" = String, Path, description = "Token mint地址 / Token mint address"),
+        ("side" = String, Query, description = "交易方向: buy 或 sell / Trade side: buy or sell"),
+        ("token_amount" = u64, Query, description = "token 数量(6位精度) / Token amount (6 decimals)")
+    ),
+    responses(
+        (status = 200, description = "成功返回报价 / Successfully returned quote",
+         body = crate::docs::ApiResponse<SwapQuoteResponse>),
+        (status = 400, description = "无效的参数或数量低于最小交易量 / Invalid parameters or amount below minimum trade size"),
+        (status = 404, description = "Token未找到 / Token not found"),
+        (status = 500, description = "服务器内部错误 / Internal server error")
+    ),
+    tag = "tokens"
+)]
+pub async fn get_swap_quote(
+    State(state): State<TokenState>,
+    Path(mint): Path<String>,
+    ValidatedQuery(params): ValidatedQuery<SwapQuoteParams>,
+) -> Result<Json<CommonResult<SwapQuoteResponse>>, ApiError> {
+    validate_mint(&mint)?;
+
+    let token = match state.token_storage.get_token_by_mint(&mint) {
+        Ok(Some(token)) => token,
+        Ok(None) => {
+            return Err(ApiError::NotFound(format!("Token not found: {}", mint)))
+        }
+        Err(e) => {
+            return Err(ApiError::Storage(format!("Failed to query token: {}", e)))
+        }
+    };
+
+    let current_price = canonical_price(&state, &mint, &token)?;
+
+    // 与链上 buy_amounts/sell_amounts 的空订单簿分支一致 / Matches the empty-orderbook branch of on-chain buy_amounts/sell_amounts
+    let quote = if params.side == "buy" {
+        CurveAMM::buy_from_price_with_token_output(current_price, params.token_amount).and_then(
+            |(price_after, required_sol)| {
+                let total = CurveAMM::calculate_total_amount_with_fee(required_sol, token.swap_fee)?;
+                Some((price_after, total, total - required_sol))
+            },
+        )
+    } else {
+        CurveAMM::sell_from_price_with_token_input(current_price, params.token_amount).and_then(
+            |(price_after, output_sol)| {
+                let net = CurveAMM::calculate_amount_after_fee(output_sol, token.swap_fee)?;
+                Some((price_after, net, output_sol - net))
+            },
+        )
+    };
+
+    let (price_after, sol_amount, fee_sol) = quote.ok_or(ApiError::BadRequest(format!(
+        "Cannot quote {} of {} tokens at current price, amount exceeds curve liquidity",
+        params.side, params.token_amount
+    )))?;
+
+    Ok(Json(CommonResult::ok(SwapQuoteResponse {
+        side: params.side,
+        token_amount: params.token_amount,
+        sol_amount,
+        fee_sol,
+        swap_fee: token.swap_fee,
+        price_before: current_price.to_string(),
+        price_after: price_after.to_string(),
+    })))
+}
+
+/// 强平价预估参数 / Liquidation estimate parameters
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct LiquidationEstimateParams {
+    /// 开仓方向: long 或 short / Position side: long or short
+    pub side: String,
+    /// 做多买入 / 做空借出卖出的 token 数量(6位精度) / Tokens bought (long) or borrowed and sold (short), 6 decimals
+    pub token_amount: u64,
+    /// 愿意投入的最大保证金(lamports) / Maximum margin to post (lamports)
+    pub margin_sol: u64,
+}
+
+/// 强平价预估响应 / Liquidation estimate response
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LiquidationEstimateResponse {
+    /// 开仓方向 / Position side
+    pub side: String,
+    /// 当前价格(u128 字符串) / Current price (u128 as string)
+    pub current_price: String,
+    /// 给定保证金下的平仓(强平)价, 保证金不足时为空 / Close (liquidation) price for the margin, null if insufficient
+    pub close_price: Option<String>,
+    /// 链上实际收取的保证金(lamports) / Margin actually charged on-chain (lamports)
+    pub real_margin_sol: Option<u64>,
+    /// 止损价边界(u128 字符串): 做多须低于, 做空须高于 / Stop price bound (u128 as string): long below, short above
+    pub stop_price_limit: String,
+    /// 止损价边界处所需最小保证金 / Minimum margin required at the stop price bound
+    pub min_required_margin_sol: Option<u64>,
+    /// 是否满足链上约束 / Whether the inputs satisfy on-chain constraints
+    pub valid: bool,
+    /// 违反的约束说明 / Descriptions of violated constraints
+    pub violations: Vec<String>,
+}
+
+/// 预估保证金开仓的强平价
+/// Estimate the liquidation price of a hypothetical margin position
+///
+/// 与链上 long_trade/short_trade 的保证金与止损校验一致(单段曲线近似), 供客户端在付费提交前预校验
+/// Mirrors the margin and stop-loss validation of on-chain long_trade/short_trade (single-segment approximation),
+/// so clients can pre-validate before paying for a failed transaction
+#[utoipa::path(
+    get,
+    path = "/api/tokens/mint/{mint}/liquidation-estimate",
+    params(
+        ("mint" = String, Path, description = "Token mint地址 / Token mint address"),
+        ("side" = String, Query, description = "开仓方向: long 或 short / Position side: long or short"),
+        ("token_amount" = u64, Query, description = "token 数量(6位精度) / Token amount (6 decimals)"),
+        ("margin_sol" = u64, Query, description = "最大保证金(lamports) / Maximum margin (lamports)")
+    ),
+    responses(
+        (status = 200, description = "成功返回预估 / Successfully returned estimate",
+         body = crate::docs::ApiResponse<LiquidationEstimateResponse>),
+        (status = 400, description = "无效的参数 / Invalid parameters"),
+        (status = 404, description = "Token未找到 / Token not found"),
+        (status = 500, description = "服务器内部错误 / Internal server error")
+    ),
+    tag = "tokens"
+)]
+pub async fn get_liquidation_estimate(
+    State(state): State<TokenState>,
+    Path(mint): Path<String>,
+    Query(params): Query<LiquidationEstimateParams>,
+) -> Result<Json<CommonResult<LiquidationEstimateResponse>>, ApiError> {
+    validate_mint(&mint)?;
+
+    let side = match params.side.as_str() {
+        "long" => PositionSide::Long,
+        "short" => PositionSide::Short,
+        other => {
+            return Err(ApiError::BadRequest(format!("Invalid side: {}, expected 'long' or 'short'", other)))
+        }
+    };
+
+    let token = match state.token_storage.get_token_by_mint(&mint) {
+        Ok(Some(token)) => token,
+        Ok(None) => {
+            return Err(ApiError::NotFound(format!("Token not found: {}", mint)))
+        }
+        Err(e) => {
+            return Err(ApiError::Storage(format!("Failed to query token: {}", e)))
+        }
+    };
+
+    let current_price = canonical_price(&state, &mint, &token)?;
+
+    // 保证金交易使用 borrow_fee / Margin trades use borrow_fee
+    let estimate = estimate_liquidation(
+        side,
+        current_price,
+        params.token_amount,
+        params.margin_sol,
+        token.borrow_fee,
+    );
+
+    Ok(Json(CommonResult::ok(LiquidationEstimateResponse {
+        side: params.side,
+        current_price: current_price.to_string(),
+        close_price: estimate.close_price.map(|p| p.to_string()),
+        real_margin_sol: estimate.real_margin_sol,
+        stop_price_limit: estimate.stop_price_limit.to_string(),
+        min_required_margin_sol: estimate.min_required_margin_sol,
+        valid: estimate.violations.is_empty(),
+        violations: estimate.violations,
+    })))
+}
+
 /// Token统计响应 / Token statistics response
 #[derive(Debug, Serialize, ToSchema)]
 pub struct TokenStatsResponse {
     /// Token总数 / Total tokens
     pub total_tokens: u64,
+    /// 全部Token的累计成交笔数 / Total trade count across all tokens
+    pub total_trades: u64,
 }
 
 /// 创建Token相关路由 / Create token related routes
 pub fn routes() -> Router<TokenState> {
     Router::new()
         .route("/api/tokens/mint/:mint", get(get_token_by_mint))
+        .route("/api/tokens/batch", post(get_tokens_batch))
+        .route("/api/tokens/mint/:mint/summary", get(get_token_summary))
+        .route("/api/tokens/mint/:mint/curve", get(get_token_curve))
+        .route("/api/tokens/mint/:mint/price", get(get_token_price))
+        .route("/api/tokens/mint/:mint/quote", get(get_swap_quote))
+        .route("/api/tokens/mint/:mint/liquidation-estimate", get(get_liquidation_estimate))
         .route("/api/tokens/symbol", get(get_tokens_by_symbol))
         .route("/api/tokens/latest", get(get_latest_tokens))
+        .route("/api/tokens/all", get(get_all_tokens))
+        .route("/api/tokens/active", get(get_active_tokens))
         .route("/api/tokens/slot-range", get(get_tokens_by_slot_range))
         .route("/api/tokens/stats", get(get_token_stats))
 }
@@ -0,0 +1,118 @@
+// 启动自检 - 校验配置、RocksDB、Solana RPC 和程序ID, 不绑定 HTTP 端口
+// Startup self-test - validate config, RocksDB, Solana RPC and program id without binding the HTTP port
+//
+// 供 CI 和部署脚本在发布前验证运行环境: `pinpet-server-v2 --selftest`
+// Lets CI and deploy scripts validate an environment before promoting it: `pinpet-server-v2 --selftest`
+
+use anyhow::Context;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+use crate::{config, db, solana};
+
+/// 命令行参数 / Command line arguments
+#[derive(Debug, Default)]
+pub struct CliArgs {
+    /// 运行自检后退出 / Run the self-test and exit
+    pub selftest: bool,
+}
+
+impl CliArgs {
+    /// 解析命令行参数, 未知参数报错 / Parse command line arguments, rejecting unknown ones
+    pub fn parse() -> anyhow::Result<Self> {
+        let mut args = Self::default();
+        for arg in std::env::args().skip(1) {
+            match arg.as_str() {
+                "--selftest" => args.selftest = true,
+                other => anyhow::bail!(
+                    "未知参数 / Unknown argument: {} (可用 / available: --selftest)",
+                    other
+                ),
+            }
+        }
+        Ok(args)
+    }
+}
+
+/// 单项检查结果 / Result of a single check
+struct CheckResult {
+    name: &'static str,
+    outcome: anyhow::Result<String>,
+}
+
+/// 运行全部检查并输出报告, 任一检查失败则返回错误
+/// Run every check and print a report; returns an error if any check failed
+pub async fn run() -> anyhow::Result<()> {
+    let mut results = Vec::new();
+
+    let config = match config::Config::new().context("配置加载失败 / Failed to load config") {
+        Ok(config) => {
+            let outcome = config
+                .validate()
+                .map(|()| "ok".to_string())
+                .map_err(|problems| anyhow::anyhow!(problems.join("; ")));
+            results.push(CheckResult { name: "config", outcome });
+            Some(config)
+        }
+        Err(e) => {
+            results.push(CheckResult { name: "config", outcome: Err(e) });
+            None
+        }
+    };
+
+    // 后续检查依赖配置 / Remaining checks depend on the config
+    if let Some(config) = config {
+        results.push(CheckResult {
+            name: "program_id",
+            outcome: Pubkey::from_str(&config.solana.program_id)
+                .map(|pubkey| pubkey.to_string())
+                .with_context(|| format!("程序ID无效 / Invalid program id: {}", config.solana.program_id)),
+        });
+
+        results.push(CheckResult {
+            name: "rocksdb",
+            outcome: check_rocksdb(&config),
+        });
+
+        results.push(CheckResult {
+            name: "solana_rpc",
+            outcome: check_solana_rpc(&config).await,
+        });
+    }
+
+    print_report(&results);
+
+    let failed = results.iter().filter(|r| r.outcome.is_err()).count();
+    if failed > 0 {
+        anyhow::bail!("自检失败, {} 项未通过 / Self-test failed, {} check(s) did not pass", failed, failed);
+    }
+    Ok(())
+}
+
+/// 打开 RocksDB 并执行读写探测 / Open RocksDB and run a read/write probe
+fn check_rocksdb(config: &config::Config) -> anyhow::Result<String> {
+    let storage = db::RocksDbStorage::new(config).with_context(|| {
+        format!("RocksDB 打开失败 / Failed to open RocksDB at {}", config.database.rocksdb_path)
+    })?;
+    storage.health_check()?;
+    Ok(config.database.rocksdb_path.clone())
+}
+
+/// 通过 getSlot 验证 RPC 可达 / Verify the RPC is reachable via getSlot
+async fn check_solana_rpc(config: &config::Config) -> anyhow::Result<String> {
+    let client = solana::SolanaClient::from_config(&config.solana)
+        .context("Solana 客户端创建失败 / Failed to create Solana client")?;
+    let slot = client.get_slot().await.context("getSlot 失败 / getSlot failed")?;
+    Ok(format!("slot {}", slot))
+}
+
+/// 输出报告到标准输出, 便于脚本读取 / Print the report to stdout for scripts to read
+fn print_report(results: &[CheckResult]) {
+    println!("pinpet-server-v2 selftest");
+    for result in results {
+        match &result.outcome {
+            Ok(detail) => println!("  [PASS] {:<12} {}", result.name, detail),
+            Err(e) => println!("  [FAIL] {:<12} {:#}", result.name, e),
+        }
+    }
+}
@@ -1,31 +1,420 @@
 // Solana客户端模块 / Solana client module
 use anyhow::Result;
+use base64::engine::Engine;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use std::time::Duration;
-use tracing::{debug, error, info};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info, warn};
+use utoipa::ToSchema;
 
-/// Solana RPC客户端 / Solana RPC client
+use crate::config::SolanaConfig;
+
+/// 单个RPC节点的运行状态 / Runtime state of a single RPC endpoint
+#[derive(Debug, Default)]
+struct EndpointState {
+    consecutive_failures: u32,         // 连续失败次数 / Consecutive failures
+    unhealthy_until: Option<Instant>,  // 冷却结束时间 / Cooldown end time
+    total_requests: u64,               // 总请求数 / Total requests
+    total_failures: u64,               // 总失败数 / Total failures
+    last_error: Option<String>,        // 最近一次错误 / Last error
+}
+
+/// RPC节点 / RPC endpoint
+#[derive(Debug)]
+struct RpcEndpoint {
+    url: String,
+    state: Mutex<EndpointState>,
+}
+
+/// RPC节点健康状态 / RPC endpoint health
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RpcEndpointHealth {
+    /// 节点URL / Endpoint URL
+    pub url: String,
+    /// 是否主节点 / Whether this is the primary endpoint
+    pub primary: bool,
+    /// 当前是否健康 / Currently healthy
+    pub healthy: bool,
+    /// 连续失败次数 / Consecutive failures
+    pub consecutive_failures: u32,
+    /// 剩余冷却秒数 / Remaining cooldown seconds
+    pub cooldown_remaining_secs: u64,
+    /// 总请求数 / Total requests
+    pub total_requests: u64,
+    /// 总失败数 / Total failures
+    pub total_failures: u64,
+    /// 最近一次错误 / Last error
+    pub last_error: Option<String>,
+}
+
+/// 重试等待的上限 / Upper bound of the wait between retries
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(10);
+
+/// 一轮节点池请求的失败, 记录是否值得重试 / Failure of one pass over the endpoint pool, recording whether it is worth retrying
+#[derive(Debug)]
+struct RpcFailure {
+    retryable: bool,
+    message: String,
+}
+
+/// RPC重试计数 / RPC retry counters
+#[derive(Debug, Default)]
+struct RetryCounters {
+    retries: AtomicU64,
+    exhausted: AtomicU64,
+    non_retryable: AtomicU64,
+}
+
+/// RPC重试统计 / RPC retry metrics
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RpcRetryMetrics {
+    /// 最大重试次数 / Max retries per call
+    pub max_retries: u32,
+    /// 已发起的重试次数 / Retries performed
+    pub retries: u64,
+    /// 重试耗尽仍失败的调用数 / Calls that still failed after all retries
+    pub exhausted: u64,
+    /// 因不可重试错误直接失败的调用数 / Calls that failed on a non-retryable error
+    pub non_retryable: u64,
+}
+
+/// 最近观察到的链上时钟 / Latest observed on-chain clock
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct ChainClock {
+    /// 最近观察到的 slot / Latest observed slot
+    pub slot: u64,
+    /// 观察到该 slot 时的服务器时间(Unix 秒) / Server time when the slot was observed (Unix seconds)
+    pub slot_observed_at: i64,
+    /// 最近一笔已确认交易的区块时间(Unix 秒, 即链上 Clock::unix_timestamp) / Block time of the latest confirmed transaction (Unix seconds, i.e. the on-chain Clock::unix_timestamp)
+    pub block_time: Option<i64>,
+    /// block_time 所在的 slot / Slot the block_time belongs to
+    pub block_time_slot: Option<u64>,
+}
+
+/// JSON-RPC `error` 是否为暂时性错误, 值得换节点或稍后重试
+/// Whether a JSON-RPC `error` is transient and worth failing over or retrying later
+///
+/// - 限流: code 429 或消息含 rate limit / too many requests / Rate limits: code 429 or a rate-limit message
+/// - 节点落后或暂不可用: -32005 (node unhealthy / behind), -32016 (minimum context slot not reached),
+///   -32004 / -32014 (block not yet available) / Lagging or unavailable nodes
+/// - 服务端内部错误: -32603 / Server internal error: -32603
+///
+/// 参数错误、方法不存在、slot 被跳过或已清理 (-32602, -32601, -32007, -32009 等) 重试也不会成功
+/// Invalid params, unknown methods and skipped or cleaned-up slots (-32602, -32601, -32007, -32009, ...) never succeed on retry
+pub(crate) fn is_retryable_rpc_error(error: &Value) -> bool {
+    const RETRYABLE_CODES: [i64; 6] = [429, -32005, -32016, -32004, -32014, -32603];
+
+    if error.get("code").and_then(Value::as_i64).is_some_and(|code| RETRYABLE_CODES.contains(&code)) {
+        return true;
+    }
+    let message = error.get("message").and_then(Value::as_str).unwrap_or_default().to_ascii_lowercase();
+    message.contains("rate limit") || message.contains("too many requests")
+}
+
+/// Solana RPC客户端, 内置带健康检查的节点池 (优先主节点, 出错时切换备用节点)
+/// Solana RPC client with a health-checked endpoint pool (prefers primary, falls back on error)
 #[derive(Clone)]
 pub struct SolanaClient {
-    rpc_url: String,
+    endpoints: std::sync::Arc<Vec<RpcEndpoint>>,
     client: Client,
+    failure_threshold: u32,
+    cooldown: Duration,
+    max_retries: u32,
+    retry_backoff: Duration,
+    retry_counters: std::sync::Arc<RetryCounters>,
+    chain_clock: std::sync::Arc<Mutex<Option<ChainClock>>>,
 }
 
 impl SolanaClient {
-    /// 创建新的Solana客户端 / Create new Solana client
+    /// 创建单节点Solana客户端 / Create single-endpoint Solana client
     pub fn new(rpc_url: String) -> Result<Self> {
+        Self::with_endpoints(vec![rpc_url], 3, Duration::from_secs(30))
+    }
+
+    /// 根据配置创建客户端 (rpc_url 为主节点, rpc_fallback_urls 为备用)
+    /// Create client from config (rpc_url is primary, rpc_fallback_urls are fallbacks)
+    pub fn from_config(config: &SolanaConfig) -> Result<Self> {
+        let mut urls = vec![config.rpc_url.clone()];
+        for url in &config.rpc_fallback_urls {
+            if !urls.contains(url) {
+                urls.push(url.clone());
+            }
+        }
+        Ok(Self::with_endpoints(
+            urls,
+            config.rpc_failure_threshold,
+            Duration::from_secs(config.rpc_cooldown_secs),
+        )?
+        .with_retry(config.rpc_max_retries, Duration::from_millis(config.rpc_retry_backoff_ms)))
+    }
+
+    /// 使用多个节点创建客户端 / Create client with multiple endpoints
+    pub fn with_endpoints(urls: Vec<String>, failure_threshold: u32, cooldown: Duration) -> Result<Self> {
+        if urls.is_empty() {
+            return Err(anyhow::anyhow!("至少需要一个RPC节点 / At least one RPC endpoint is required"));
+        }
+
         let client = Client::builder()
             .timeout(Duration::from_secs(30))
             .build()?;
 
-        Ok(Self { rpc_url, client })
+        let endpoints = urls
+            .into_iter()
+            .map(|url| RpcEndpoint {
+                url,
+                state: Mutex::new(EndpointState::default()),
+            })
+            .collect();
+
+        Ok(Self {
+            endpoints: std::sync::Arc::new(endpoints),
+            client,
+            failure_threshold: failure_threshold.max(1),
+            cooldown,
+            max_retries: 0,
+            retry_backoff: Duration::ZERO,
+            retry_counters: std::sync::Arc::new(RetryCounters::default()),
+            chain_clock: std::sync::Arc::new(Mutex::new(None)),
+        })
+    }
+
+    /// 设置可重试错误的重试次数与首次退避时间 (之后逐次翻倍)
+    /// Set the retry count and initial backoff (doubled on each retry) for retryable errors
+    pub fn with_retry(mut self, max_retries: u32, backoff: Duration) -> Self {
+        self.max_retries = max_retries;
+        self.retry_backoff = backoff;
+        self
+    }
+
+    /// 获取重试统计 / Get retry metrics
+    pub fn retry_metrics(&self) -> RpcRetryMetrics {
+        RpcRetryMetrics {
+            max_retries: self.max_retries,
+            retries: self.retry_counters.retries.load(Ordering::Relaxed),
+            exhausted: self.retry_counters.exhausted.load(Ordering::Relaxed),
+            non_retryable: self.retry_counters.non_retryable.load(Ordering::Relaxed),
+        }
+    }
+
+    /// 主节点URL / Primary endpoint URL
+    pub fn primary_url(&self) -> &str {
+        &self.endpoints[0].url
+    }
+
+    /// 获取所有节点的健康状态 / Get health of every endpoint
+    pub fn endpoint_health(&self) -> Vec<RpcEndpointHealth> {
+        let now = Instant::now();
+        self.endpoints
+            .iter()
+            .enumerate()
+            .map(|(i, endpoint)| {
+                let state = endpoint.state.lock().unwrap_or_else(|e| e.into_inner());
+                let cooldown_remaining = state
+                    .unhealthy_until
+                    .filter(|until| *until > now)
+                    .map(|until| until.duration_since(now).as_secs())
+                    .unwrap_or(0);
+                RpcEndpointHealth {
+                    url: endpoint.url.clone(),
+                    primary: i == 0,
+                    healthy: !matches!(state.unhealthy_until, Some(until) if until > now),
+                    consecutive_failures: state.consecutive_failures,
+                    cooldown_remaining_secs: cooldown_remaining,
+                    total_requests: state.total_requests,
+                    total_failures: state.total_failures,
+                    last_error: state.last_error.clone(),
+                }
+            })
+            .collect()
+    }
+
+    /// 最近观察到的链上时钟, 尚未发起过 getSlot/getTransaction 时为空
+    /// Latest observed on-chain clock, None until a getSlot/getTransaction call has succeeded
+    pub fn chain_clock(&self) -> Option<ChainClock> {
+        self.chain_clock.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// 记录 RPC 响应中的 slot 和区块时间, 只前进不后退
+    /// Record the slot and block time from an RPC response, only ever moving forward
+    fn observe_chain(&self, slot: u64, block_time: Option<i64>) {
+        let mut clock = self.chain_clock.lock().unwrap_or_else(|e| e.into_inner());
+        let clock = clock.get_or_insert_with(ChainClock::default);
+        if slot >= clock.slot {
+            clock.slot = slot;
+            clock.slot_observed_at = crate::util::time::unix_now();
+        }
+        if let Some(block_time) = block_time {
+            if clock.block_time_slot.map_or(true, |s| slot >= s) {
+                clock.block_time = Some(block_time);
+                clock.block_time_slot = Some(slot);
+            }
+        }
+    }
+
+    /// 按优先级排列候选节点: 健康节点在前, 冷却中的节点兜底
+    /// Order candidate endpoints: healthy ones first, cooling-down ones as last resort
+    fn candidates(&self) -> Vec<&RpcEndpoint> {
+        let now = Instant::now();
+        let (healthy, cooling): (Vec<&RpcEndpoint>, Vec<&RpcEndpoint>) =
+            self.endpoints.iter().partition(|endpoint| {
+                let state = endpoint.state.lock().unwrap_or_else(|e| e.into_inner());
+                !matches!(state.unhealthy_until, Some(until) if until > now)
+            });
+        healthy.into_iter().chain(cooling).collect()
+    }
+
+    fn record_success(&self, endpoint: &RpcEndpoint) {
+        let mut state = endpoint.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.total_requests += 1;
+        state.consecutive_failures = 0;
+        state.unhealthy_until = None;
+    }
+
+    fn record_failure(&self, endpoint: &RpcEndpoint, err: &str) {
+        let mut state = endpoint.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.total_requests += 1;
+        state.total_failures += 1;
+        state.consecutive_failures += 1;
+        state.last_error = Some(err.to_string());
+        if state.consecutive_failures >= self.failure_threshold {
+            state.unhealthy_until = Some(Instant::now() + self.cooldown);
+            warn!(
+                "⚠️ RPC节点标记为不健康, 冷却 {}s / RPC endpoint marked unhealthy, cooldown {}s: {} ({})",
+                self.cooldown.as_secs(),
+                self.cooldown.as_secs(),
+                endpoint.url,
+                err
+            );
+        }
+    }
+
+    /// 依次尝试所有节点发送JSON-RPC请求, 传输错误、HTTP错误或可重试的 JSON-RPC 错误时切换到下一个节点
+    /// Send a JSON-RPC request over the endpoint pool, failing over to the next endpoint on transport or HTTP errors
+    /// and on retryable JSON-RPC errors
+    ///
+    /// 只有所有节点的错误都可重试(超时、连接失败、429、5xx、见 `is_retryable_rpc_error`)时, 这一轮失败才可重试;
+    /// 不可重试的 JSON-RPC 错误原样返回, 由调用方检查 `error` 字段
+    /// The pass is retryable only if every endpoint failed with a retryable error (timeout, connect, 429, 5xx, see
+    /// `is_retryable_rpc_error`); non-retryable JSON-RPC errors are returned as is for the caller to check `error`
+    async fn send_rpc_once(&self, request: &Value) -> std::result::Result<Value, RpcFailure> {
+        let mut last_error = None;
+        let mut retryable = true;
+
+        for endpoint in self.candidates() {
+            let outcome = match self.client.post(&endpoint.url).json(request).send().await {
+                Ok(response) if response.status().is_success() => {
+                    match response.json::<Value>().await {
+                        Ok(body) => match body.get("error") {
+                            // HTTP 200 中的限流/节点落后等错误与 429/5xx 同样处理 / Rate limits and lagging nodes inside an HTTP 200 are handled like 429/5xx
+                            Some(error) if is_retryable_rpc_error(error) => {
+                                Err((true, format!("RPC错误 / RPC error: {}", error)))
+                            }
+                            _ => Ok(body),
+                        },
+                        Err(e) => Err((e.is_timeout(), e.to_string())),
+                    }
+                }
+                Ok(response) => {
+                    let status = response.status();
+                    Err((
+                        status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error(),
+                        format!("RPC请求失败，状态码 / RPC request failed with status: {}", status),
+                    ))
+                }
+                Err(e) => Err((e.is_timeout() || e.is_connect(), e.to_string())),
+            };
+
+            match outcome {
+                Ok(body) => {
+                    self.record_success(endpoint);
+                    return Ok(body);
+                }
+                Err((is_retryable, e)) => {
+                    debug!("RPC节点请求失败, 尝试下一个 / RPC endpoint failed, trying next: {} ({})", endpoint.url, e);
+                    self.record_failure(endpoint, &e);
+                    retryable &= is_retryable;
+                    last_error = Some(format!("{}: {}", endpoint.url, e));
+                }
+            }
+        }
+
+        Err(RpcFailure {
+            retryable,
+            message: format!(
+                "所有RPC节点均不可用 / All RPC endpoints failed: {}",
+                last_error.unwrap_or_default()
+            ),
+        })
+    }
+
+    /// 发送JSON-RPC请求, 不重试 / Send a JSON-RPC request without retrying
+    async fn send_rpc(&self, request: &Value) -> Result<Value> {
+        self.send_rpc_once(request)
+            .await
+            .map_err(|failure| anyhow::anyhow!(failure.message))
+    }
+
+    /// 发送JSON-RPC请求, 可重试错误按指数退避重试, 最终错误附带方法名和尝试次数
+    /// Send a JSON-RPC request, retrying retryable errors with exponential backoff; the final error names the method and attempts
+    async fn send_rpc_with_retry(&self, request: &Value) -> Result<Value> {
+        let method = request.get("method").and_then(|m| m.as_str()).unwrap_or("unknown");
+        let mut attempt: u32 = 0;
+
+        loop {
+            let failure = match self.send_rpc_once(request).await {
+                Ok(body) => return Ok(body),
+                Err(failure) => failure,
+            };
+
+            if !failure.retryable {
+                self.retry_counters.non_retryable.fetch_add(1, Ordering::Relaxed);
+                return Err(anyhow::anyhow!(
+                    "RPC {} 调用失败(不可重试) / RPC {} failed (non-retryable): {}",
+                    method,
+                    method,
+                    failure.message
+                ));
+            }
+            if attempt >= self.max_retries {
+                if self.max_retries > 0 {
+                    self.retry_counters.exhausted.fetch_add(1, Ordering::Relaxed);
+                }
+                return Err(anyhow::anyhow!(
+                    "RPC {} 调用失败, 共尝试 {} 次 / RPC {} failed after {} attempts: {}",
+                    method,
+                    attempt + 1,
+                    method,
+                    attempt + 1,
+                    failure.message
+                ));
+            }
+
+            let backoff = self
+                .retry_backoff
+                .saturating_mul(1u32 << attempt.min(16))
+                .min(MAX_RETRY_BACKOFF);
+            attempt += 1;
+            self.retry_counters.retries.fetch_add(1, Ordering::Relaxed);
+            warn!(
+                "🔁 RPC {} 失败, {}ms 后第 {} 次重试 / RPC {} failed, retry {} in {}ms: {}",
+                method,
+                backoff.as_millis(),
+                attempt,
+                method,
+                attempt,
+                backoff.as_millis(),
+                failure.message
+            );
+            tokio::time::sleep(backoff).await;
+        }
     }
 
     /// 检查RPC连接 / Check RPC connection
     pub async fn check_connection(&self) -> Result<bool> {
-        info!("检查Solana RPC连接 / Checking Solana RPC connection: {}", self.rpc_url);
+        info!("检查Solana RPC连接 / Checking Solana RPC connection: {}", self.primary_url());
 
         let request = json!({
             "jsonrpc": "2.0",
@@ -33,27 +422,16 @@ impl SolanaClient {
             "method": "getHealth"
         });
 
-        match self.client
-            .post(&self.rpc_url)
-            .json(&request)
-            .send()
-            .await
-        {
-            Ok(response) => {
-                if response.status().is_success() {
-                    let body: Value = response.json().await?;
-                    if body.get("result").is_some() {
-                        info!("✅ Solana RPC连接正常 / Solana RPC connection is healthy");
-                        Ok(true)
-                    } else if let Some(error) = body.get("error") {
-                        error!("Solana RPC返回错误 / Solana RPC returned error: {:?}", error);
-                        Ok(false)
-                    } else {
-                        Ok(true)
-                    }
-                } else {
-                    error!("Solana RPC响应状态码错误 / Solana RPC response status code error: {}", response.status());
+        match self.send_rpc(&request).await {
+            Ok(body) => {
+                if body.get("result").is_some() {
+                    info!("✅ Solana RPC连接正常 / Solana RPC connection is healthy");
+                    Ok(true)
+                } else if let Some(error) = body.get("error") {
+                    error!("Solana RPC返回错误 / Solana RPC returned error: {:?}", error);
                     Ok(false)
+                } else {
+                    Ok(true)
                 }
             }
             Err(e) => {
@@ -81,20 +459,7 @@ impl SolanaClient {
             ]
         });
 
-        let response = self.client
-            .post(&self.rpc_url)
-            .json(&request)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            return Err(anyhow::anyhow!(
-                "RPC请求失败，状态码 / RPC request failed with status: {}",
-                response.status()
-            ));
-        }
-
-        let body: Value = response.json().await?;
+        let body = self.send_rpc_with_retry(&request).await?;
 
         if let Some(error) = body.get("error") {
             return Err(anyhow::anyhow!(
@@ -103,9 +468,14 @@ impl SolanaClient {
             ));
         }
 
-        body.get("result")
+        let result = body
+            .get("result")
             .cloned()
-            .ok_or_else(|| anyhow::anyhow!("响应中没有result字段 / No result field in response"))
+            .ok_or_else(|| anyhow::anyhow!("响应中没有result字段 / No result field in response"))?;
+        if let Some(slot) = result.get("slot").and_then(|s| s.as_u64()) {
+            self.observe_chain(slot, result.get("blockTime").and_then(|t| t.as_i64()));
+        }
+        Ok(result)
     }
 
     /// 获取最新区块高度 / Get slot
@@ -119,17 +489,188 @@ impl SolanaClient {
             }]
         });
 
-        let response = self.client
-            .post(&self.rpc_url)
-            .json(&request)
-            .send()
-            .await?;
+        let body = self.send_rpc_with_retry(&request).await?;
+
+        let slot = body
+            .get("result")
+            .and_then(|r| r.as_u64())
+            .ok_or_else(|| anyhow::anyhow!("无法获取slot / Failed to get slot"))?;
+        self.observe_chain(slot, None);
+        Ok(slot)
+    }
+
+    /// 获取最新的 finalized slot / Get the latest finalized slot
+    pub async fn get_finalized_slot(&self) -> Result<u64> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "getSlot",
+            "params": [{
+                "commitment": "finalized"
+            }]
+        });
 
-        let body: Value = response.json().await?;
+        let body = self.send_rpc_with_retry(&request).await?;
 
         body.get("result")
             .and_then(|r| r.as_u64())
-            .ok_or_else(|| anyhow::anyhow!("无法获取slot / Failed to get slot"))
+            .ok_or_else(|| anyhow::anyhow!("无法获取finalized slot / Failed to get finalized slot"))
+    }
+
+    /// 获取 [start, end] 内已终结的区块 slot, 不在其中的 slot 已被跳过或被分叉丢弃 (范围最多 500,000 个 slot)
+    /// Get the finalized block slots in [start, end]; slots missing from it were skipped or orphaned by a fork
+    /// (the range may span at most 500,000 slots)
+    pub async fn get_finalized_blocks(&self, start: u64, end: u64) -> Result<Vec<u64>> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "getBlocks",
+            "params": [start, end, { "commitment": "finalized" }]
+        });
+
+        let body = self.send_rpc_with_retry(&request).await?;
+
+        if let Some(error) = body.get("error") {
+            return Err(anyhow::anyhow!("RPC错误 / RPC error: {:?}", error));
+        }
+
+        let result = body
+            .get("result")
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("响应中没有result字段 / No result field in response"))?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// 从 `start` 起最多 `limit` 个已确认区块的 slot (getBlocksWithLimit)
+    /// Slots of up to `limit` confirmed blocks starting at `start` (getBlocksWithLimit)
+    pub async fn get_blocks_with_limit(&self, start: u64, limit: u64) -> Result<Vec<u64>> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "getBlocksWithLimit",
+            "params": [start, limit, { "commitment": "confirmed" }]
+        });
+
+        let body = self.send_rpc_with_retry(&request).await?;
+
+        if let Some(error) = body.get("error") {
+            return Err(anyhow::anyhow!("RPC错误 / RPC error: {:?}", error));
+        }
+
+        let result = body
+            .get("result")
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("响应中没有result字段 / No result field in response"))?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// 区块内全部交易签名 (按区块内顺序) / Every transaction signature of a block, in block order
+    pub async fn get_block_signatures(&self, slot: u64) -> Result<Vec<String>> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "getBlock",
+            "params": [
+                slot,
+                {
+                    "commitment": "confirmed",
+                    "transactionDetails": "signatures",
+                    "rewards": false,
+                    "maxSupportedTransactionVersion": 0
+                }
+            ]
+        });
+
+        let body = self.send_rpc_with_retry(&request).await?;
+
+        if let Some(error) = body.get("error") {
+            return Err(anyhow::anyhow!("RPC错误 / RPC error: {:?}", error));
+        }
+
+        let signatures = body
+            .pointer("/result/signatures")
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("响应中没有签名列表 / No signatures in response"))?;
+        Ok(serde_json::from_value(signatures)?)
+    }
+
+    /// 获取账户数据, 返回 (上下文slot, 账户数据), 账户不存在时为空
+    /// Get account data, returns (context slot, account data), None when the account does not exist
+    pub async fn get_account_info(&self, pubkey: &str) -> Result<Option<(u64, Vec<u8>)>> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "getAccountInfo",
+            "params": [
+                pubkey,
+                {
+                    "encoding": "base64",
+                    "commitment": "confirmed"
+                }
+            ]
+        });
+
+        let body = self.send_rpc_with_retry(&request).await?;
+
+        if let Some(error) = body.get("error") {
+            return Err(anyhow::anyhow!("RPC错误 / RPC error: {:?}", error));
+        }
+
+        let result = body
+            .get("result")
+            .ok_or_else(|| anyhow::anyhow!("响应中没有result字段 / No result field in response"))?;
+        let slot = result
+            .pointer("/context/slot")
+            .and_then(|s| s.as_u64())
+            .ok_or_else(|| anyhow::anyhow!("响应中没有context.slot / No context.slot in response"))?;
+
+        let value = match result.get("value") {
+            Some(value) if !value.is_null() => value,
+            _ => return Ok(None),
+        };
+        let encoded = value
+            .pointer("/data/0")
+            .and_then(|d| d.as_str())
+            .ok_or_else(|| anyhow::anyhow!("账户数据格式错误 / Malformed account data"))?;
+        let data = base64::engine::general_purpose::STANDARD.decode(encoded)?;
+
+        Ok(Some((slot, data)))
+    }
+
+    /// 按时间倒序获取地址相关的交易签名, `before` 为分页游标 (不含)
+    /// Get transaction signatures for an address, newest first; `before` is the (exclusive) page cursor
+    pub async fn get_signatures_for_address(
+        &self,
+        address: &str,
+        before: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<SignatureInfo>> {
+        let mut options = json!({
+            "limit": limit,
+            "commitment": "confirmed"
+        });
+        if let Some(before) = before {
+            options["before"] = json!(before);
+        }
+
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "getSignaturesForAddress",
+            "params": [address, options]
+        });
+
+        let body = self.send_rpc_with_retry(&request).await?;
+
+        if let Some(error) = body.get("error") {
+            return Err(anyhow::anyhow!("RPC错误 / RPC error: {:?}", error));
+        }
+
+        let result = body
+            .get("result")
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("响应中没有result字段 / No result field in response"))?;
+        Ok(serde_json::from_value(result)?)
     }
 
     /// 获取程序账户 / Get program accounts
@@ -147,13 +688,7 @@ impl SolanaClient {
             ]
         });
 
-        let response = self.client
-            .post(&self.rpc_url)
-            .json(&request)
-            .send()
-            .await?;
-
-        let body: Value = response.json().await?;
+        let body = self.send_rpc(&request).await?;
 
         if let Some(result) = body.get("result") {
             let accounts: Vec<ProgramAccount> = serde_json::from_value(result.clone())?;
@@ -164,6 +699,18 @@ impl SolanaClient {
     }
 }
 
+/// 交易签名信息 / Transaction signature info
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignatureInfo {
+    pub signature: String,
+    pub slot: u64,
+    /// 交易失败时的错误, 成功时为空 / Transaction error, None on success
+    #[serde(default)]
+    pub err: Option<Value>,
+    #[serde(rename = "blockTime", default)]
+    pub block_time: Option<i64>,
+}
+
 /// 程序账户数据结构 / Program account data structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProgramAccount {
@@ -180,4 +727,37 @@ pub struct AccountData {
     pub owner: String,
     #[serde(rename = "rentEpoch")]
     pub rent_epoch: u64,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limit_errors_are_retryable() {
+        assert!(is_retryable_rpc_error(&json!({ "code": 429, "message": "Too many requests for a specific RPC call" })));
+        assert!(is_retryable_rpc_error(&json!({ "code": -32000, "message": "Rate limit exceeded" })));
+    }
+
+    #[test]
+    fn test_lagging_node_errors_are_retryable() {
+        assert!(is_retryable_rpc_error(&json!({ "code": -32005, "message": "Node is behind by 42 slots" })));
+        assert!(is_retryable_rpc_error(&json!({ "code": -32016, "message": "Minimum context slot has not been reached" })));
+        assert!(is_retryable_rpc_error(&json!({ "code": -32004, "message": "Block not available for slot 1" })));
+        assert!(is_retryable_rpc_error(&json!({ "code": -32014, "message": "Block status not yet available" })));
+    }
+
+    #[test]
+    fn test_internal_errors_are_retryable() {
+        assert!(is_retryable_rpc_error(&json!({ "code": -32603, "message": "Internal error" })));
+    }
+
+    #[test]
+    fn test_request_errors_are_not_retryable() {
+        assert!(!is_retryable_rpc_error(&json!({ "code": -32602, "message": "Invalid params" })));
+        assert!(!is_retryable_rpc_error(&json!({ "code": -32601, "message": "Method not found" })));
+        assert!(!is_retryable_rpc_error(&json!({ "code": -32007, "message": "Slot 1 was skipped" })));
+        assert!(!is_retryable_rpc_error(&json!({ "code": -32009, "message": "Slot 1 missing in long-term storage" })));
+        assert!(!is_retryable_rpc_error(&json!({})));
+    }
+}
@@ -0,0 +1,205 @@
+// 事件解码诊断 - 按判别器统计解码失败并保留最近的失败样本
+// Event decoding diagnostics - counts decode failures per discriminator and keeps the latest failure samples
+//
+// 链上程序新增字段后, borsh 解码会失败而该类型事件不再入库, 这里让这种结构漂移可见
+// When the on-chain program adds a field, borsh decoding fails and that event type stops being recorded; this makes such schema drift visible
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use utoipa::ToSchema;
+
+use super::events::{
+    ADD_MARGIN_EVENT_DISCRIMINATOR, BUY_SELL_EVENT_DISCRIMINATOR, EXTEND_ORDER_EVENT_DISCRIMINATOR, FULL_CLOSE_EVENT_DISCRIMINATOR, LONG_SHORT_EVENT_DISCRIMINATOR,
+    MILESTONE_DISCOUNT_EVENT_DISCRIMINATOR, PARTIAL_CLOSE_EVENT_DISCRIMINATOR,
+    TOKEN_CREATED_EVENT_DISCRIMINATOR,
+};
+
+/// 保留的最近失败样本数 / Number of recent failure samples kept
+pub const DECODE_FAILURE_BUFFER_SIZE: usize = 100;
+
+/// Base64 解码失败时使用的类型名 / Type name used when Base64 decoding fails
+pub const BASE64_FAILURE: &str = "base64";
+
+/// 判别器不属于任何已知事件时使用的类型名 / Type name used when the discriminator matches no known event
+pub const UNKNOWN_DISCRIMINATOR: &str = "unknown";
+
+/// 单次解码失败 / A single decode failure
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(title = "DecodeFailure", description = "事件解码失败样本 / Event decode failure sample")]
+pub struct DecodeFailure {
+    /// 事件类型 (按判别器), Base64 失败为 base64, 未知判别器为 unknown / Event type (by discriminator), base64 for Base64 failures, unknown for unknown discriminators
+    #[schema(example = "long_short")]
+    pub event_type: String,
+    /// 判别器十六进制, 数据不足 8 字节时为空 / Discriminator in hex, empty when data is shorter than 8 bytes
+    #[schema(example = "1b4514743afa5fdc")]
+    pub discriminator: String,
+    /// 程序ID / Program ID
+    pub program_id: String,
+    /// 交易签名 / Transaction signature
+    pub signature: String,
+    /// 区块高度 / Slot
+    pub slot: u64,
+    /// 解码后的数据长度 (含判别器) / Decoded data length (including the discriminator)
+    pub data_len: usize,
+    /// 错误信息 / Error message
+    pub error: String,
+    /// 原始 base64 日志数据 / Raw base64 log data
+    pub raw_base64: String,
+    /// 记录时间(毫秒) / Recorded at (ms)
+    pub timestamp: i64,
+}
+
+/// 解码诊断报告 / Decode diagnostics report
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(title = "DecodeErrors", description = "事件解码失败统计 / Event decode failure statistics")]
+pub struct DecodeErrors {
+    /// 启动以来失败总数 / Total failures since startup
+    pub total: u64,
+    /// 按事件类型的失败计数 / Failure counts per event type
+    pub counts: HashMap<String, u64>,
+    /// 最近的失败样本, 新的在前 / Latest failure samples, newest first
+    pub failures: Vec<DecodeFailure>,
+}
+
+/// 解码诊断收集器, 由 EventParser 的所有克隆共享 / Decode diagnostics collector, shared by all clones of an EventParser
+pub struct DecodeDiagnostics {
+    counts: Mutex<HashMap<&'static str, u64>>,
+    recent: Mutex<VecDeque<DecodeFailure>>,
+    capacity: usize,
+}
+
+impl Default for DecodeDiagnostics {
+    fn default() -> Self {
+        Self::new(DECODE_FAILURE_BUFFER_SIZE)
+    }
+}
+
+impl DecodeDiagnostics {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            counts: Mutex::new(HashMap::new()),
+            recent: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+        }
+    }
+
+    /// 记录一次失败, 缓冲区满时丢弃最旧的样本 / Record a failure, dropping the oldest sample when the buffer is full
+    pub fn record(&self, event_type: &'static str, failure: DecodeFailure) {
+        if let Ok(mut counts) = self.counts.lock() {
+            *counts.entry(event_type).or_insert(0) += 1;
+        }
+        if self.capacity == 0 {
+            return;
+        }
+        if let Ok(mut recent) = self.recent.lock() {
+            if recent.len() >= self.capacity {
+                recent.pop_front();
+            }
+            recent.push_back(failure);
+        }
+    }
+
+    /// 读取统计和最近 `limit` 个失败样本 / Read statistics and the latest `limit` failure samples
+    pub fn report(&self, limit: usize) -> DecodeErrors {
+        let counts: HashMap<String, u64> = self
+            .counts
+            .lock()
+            .map(|c| c.iter().map(|(k, v)| (k.to_string(), *v)).collect())
+            .unwrap_or_default();
+        let failures = self
+            .recent
+            .lock()
+            .map(|r| r.iter().rev().take(limit).cloned().collect())
+            .unwrap_or_default();
+        DecodeErrors {
+            total: counts.values().sum(),
+            counts,
+            failures,
+        }
+    }
+}
+
+/// 判别器对应的事件类型名 / Event type name of a discriminator
+pub fn discriminator_name(data: &[u8]) -> &'static str {
+    let Some(discriminator) = data.get(0..8) else {
+        return UNKNOWN_DISCRIMINATOR;
+    };
+    match <[u8; 8]>::try_from(discriminator) {
+        Ok(TOKEN_CREATED_EVENT_DISCRIMINATOR) => "token_created",
+        Ok(BUY_SELL_EVENT_DISCRIMINATOR) => "buy_sell",
+        Ok(LONG_SHORT_EVENT_DISCRIMINATOR) => "long_short",
+        Ok(FULL_CLOSE_EVENT_DISCRIMINATOR) => "full_close",
+        Ok(PARTIAL_CLOSE_EVENT_DISCRIMINATOR) => "partial_close",
+        Ok(MILESTONE_DISCOUNT_EVENT_DISCRIMINATOR) => "milestone_discount",
+        Ok(ADD_MARGIN_EVENT_DISCRIMINATOR) => "add_margin",
+        Ok(EXTEND_ORDER_EVENT_DISCRIMINATOR) => "extend_order",
+        _ => UNKNOWN_DISCRIMINATOR,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failure(signature: &str) -> DecodeFailure {
+        DecodeFailure {
+            event_type: "long_short".to_string(),
+            discriminator: String::new(),
+            program_id: String::new(),
+            signature: signature.to_string(),
+            slot: 1,
+            data_len: 8,
+            error: "Unexpected length of input".to_string(),
+            raw_base64: String::new(),
+            timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn test_ring_buffer_keeps_latest() {
+        let diagnostics = DecodeDiagnostics::new(2);
+        diagnostics.record("long_short", failure("a"));
+        diagnostics.record("long_short", failure("b"));
+        diagnostics.record("buy_sell", failure("c"));
+
+        let report = diagnostics.report(10);
+        assert_eq!(report.total, 3);
+        assert_eq!(report.counts["long_short"], 2);
+        assert_eq!(report.counts["buy_sell"], 1);
+        let signatures: Vec<&str> = report.failures.iter().map(|f| f.signature.as_str()).collect();
+        assert_eq!(signatures, vec!["c", "b"]);
+        assert_eq!(diagnostics.report(1).failures.len(), 1);
+    }
+
+    #[test]
+    fn test_discriminator_name() {
+        assert_eq!(discriminator_name(&LONG_SHORT_EVENT_DISCRIMINATOR), "long_short");
+        assert_eq!(discriminator_name(&[0u8; 8]), "unknown");
+        assert_eq!(discriminator_name(&[1, 2, 3]), "unknown");
+    }
+
+    #[test]
+    fn test_unknown_discriminator_is_counted() {
+        use base64::engine::Engine;
+
+        let program_id = "HNaandW3U5sVTsoJaGx61UmX9Siupa6difFY9qRAPXyw";
+        let data = [9u8; 16];
+        let raw = base64::engine::general_purpose::STANDARD.encode(data);
+        let logs = vec![
+            format!("Program {} invoke [1]", program_id),
+            format!("Program data: {}", raw),
+            format!("Program {} success", program_id),
+        ];
+
+        let parser = crate::solana::events::EventParser::new(program_id).unwrap();
+        let events = parser.parse_events_with_call_stack(&logs, "UnknownSig", 7).unwrap();
+        assert!(events.is_empty());
+
+        let report = parser.diagnostics().report(10);
+        assert_eq!(report.counts[UNKNOWN_DISCRIMINATOR], 1);
+        assert_eq!(report.failures[0].discriminator, "0909090909090909");
+        assert_eq!(report.failures[0].raw_base64, raw);
+        assert_eq!((report.failures[0].signature.as_str(), report.failures[0].slot), ("UnknownSig", 7));
+    }
+}
@@ -0,0 +1,98 @@
+// 演练模式事件处理器 - 只记录日志和计数, 不写入 RocksDB、不推送 Socket
+// Dry-run event handler - only logs and counts, never writes to RocksDB or pushes to sockets
+//
+// 用于在预发环境用真实流量验证新的 RPC 节点 / 程序ID 与 EventParser
+// Used on staging to validate a new RPC endpoint / program id and the EventParser against live traffic
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tracing::info;
+use utoipa::ToSchema;
+
+use super::events::PinpetEvent;
+use super::listener::EventHandler;
+use crate::webhook::event_type_name;
+
+/// 演练模式统计 / Dry-run metrics
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(title = "DryRunMetrics", description = "演练模式统计 / Dry-run metrics")]
+pub struct DryRunMetrics {
+    /// 已解析的事件总数 / Total parsed events
+    pub events_total: u64,
+    /// 按事件类型计数 / Counts per event type
+    pub events_by_type: HashMap<String, u64>,
+    /// 最近一个事件的 slot / Slot of the latest event
+    pub last_slot: u64,
+}
+
+/// 演练模式事件处理器 / Dry-run event handler
+#[derive(Default)]
+pub struct DryRunEventHandler {
+    events_total: AtomicU64,
+    last_slot: AtomicU64,
+    events_by_type: Mutex<HashMap<&'static str, u64>>,
+}
+
+impl DryRunEventHandler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 读取统计 / Read metrics
+    pub fn metrics(&self) -> DryRunMetrics {
+        let events_by_type = self
+            .events_by_type
+            .lock()
+            .map(|m| m.iter().map(|(k, v)| (k.to_string(), *v)).collect())
+            .unwrap_or_default();
+        DryRunMetrics {
+            events_total: self.events_total.load(Ordering::Relaxed),
+            events_by_type,
+            last_slot: self.last_slot.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// 事件摘要: (slot, 签名, mint) / Event summary: (slot, signature, mint)
+fn event_summary(event: &PinpetEvent) -> (u64, &str, &str) {
+    match event {
+        PinpetEvent::TokenCreated(e) => (e.slot, &e.signature, &e.mint_account),
+        PinpetEvent::BuySell(e) => (e.slot, &e.signature, &e.mint_account),
+        PinpetEvent::LongShort(e) => (e.slot, &e.signature, &e.mint_account),
+        PinpetEvent::FullClose(e) => (e.slot, &e.signature, &e.mint_account),
+        PinpetEvent::PartialClose(e) => (e.slot, &e.signature, &e.mint_account),
+        PinpetEvent::MilestoneDiscount(e) => (e.slot, &e.signature, &e.mint_account),
+        PinpetEvent::AddMargin(e) => (e.slot, &e.signature, &e.mint_account),
+        PinpetEvent::ExtendOrder(e) => (e.slot, &e.signature, &e.mint_account),
+    }
+}
+
+#[async_trait]
+impl EventHandler for DryRunEventHandler {
+    async fn handle_event(&self, event: PinpetEvent) -> anyhow::Result<()> {
+        let event_type = event_type_name(&event);
+        let (slot, signature, mint) = event_summary(&event);
+
+        self.events_total.fetch_add(1, Ordering::Relaxed);
+        self.last_slot.fetch_max(slot, Ordering::Relaxed);
+        if let Ok(mut counts) = self.events_by_type.lock() {
+            *counts.entry(event_type).or_insert(0) += 1;
+        }
+
+        info!(
+            slot,
+            signature,
+            event_type,
+            mint,
+            "🧪 演练模式, 事件未写入 / Dry run, event not persisted"
+        );
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
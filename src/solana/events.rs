@@ -5,9 +5,14 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, DisplayFromStr};
 use solana_sdk::pubkey::Pubkey;
+use std::sync::Arc;
 use tracing::{debug, warn};
 use utoipa::ToSchema;
 
+use super::decode_diagnostics::{
+    discriminator_name, DecodeDiagnostics, DecodeFailure, BASE64_FAILURE, UNKNOWN_DISCRIMINATOR,
+};
+
 /// 事件判别器 - 来自IDL文件的正确判别器 / Event discriminators - correct discriminators from IDL file
 pub const TOKEN_CREATED_EVENT_DISCRIMINATOR: [u8; 8] = [96, 122, 113, 138, 50, 227, 149, 57];
 pub const BUY_SELL_EVENT_DISCRIMINATOR: [u8; 8] = [98, 208, 120, 60, 93, 32, 19, 180];
@@ -15,6 +20,8 @@ pub const LONG_SHORT_EVENT_DISCRIMINATOR: [u8; 8] = [27, 69, 20, 116, 58, 250, 9
 pub const FULL_CLOSE_EVENT_DISCRIMINATOR: [u8; 8] = [22, 244, 113, 245, 154, 168, 109, 139];
 pub const PARTIAL_CLOSE_EVENT_DISCRIMINATOR: [u8; 8] = [133, 94, 3, 222, 24, 68, 69, 155];
 pub const MILESTONE_DISCOUNT_EVENT_DISCRIMINATOR: [u8; 8] = [130, 232, 11, 37, 34, 185, 136, 128];
+pub const ADD_MARGIN_EVENT_DISCRIMINATOR: [u8; 8] = [22, 162, 182, 55, 29, 27, 102, 217];
+pub const EXTEND_ORDER_EVENT_DISCRIMINATOR: [u8; 8] = [71, 67, 67, 190, 130, 6, 244, 241];
 
 /// 所有Pinpet事件的统一枚举 / Unified enum for all Pinpet events
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -26,6 +33,161 @@ pub enum PinpetEvent {
     FullClose(FullCloseEvent),
     PartialClose(PartialCloseEvent),
     MilestoneDiscount(MilestoneDiscountEvent),
+    AddMargin(AddMarginEvent),
+    ExtendOrder(ExtendOrderEvent),
+}
+
+impl PinpetEvent {
+    /// 交易签名 / Transaction signature
+    pub fn signature(&self) -> &str {
+        match self {
+            PinpetEvent::TokenCreated(e) => &e.signature,
+            PinpetEvent::BuySell(e) => &e.signature,
+            PinpetEvent::LongShort(e) => &e.signature,
+            PinpetEvent::FullClose(e) => &e.signature,
+            PinpetEvent::PartialClose(e) => &e.signature,
+            PinpetEvent::MilestoneDiscount(e) => &e.signature,
+            PinpetEvent::AddMargin(e) => &e.signature,
+            PinpetEvent::ExtendOrder(e) => &e.signature,
+        }
+    }
+
+    /// 事件所在 slot / Slot the event was emitted in
+    pub fn slot(&self) -> u64 {
+        match self {
+            PinpetEvent::TokenCreated(e) => e.slot,
+            PinpetEvent::BuySell(e) => e.slot,
+            PinpetEvent::LongShort(e) => e.slot,
+            PinpetEvent::FullClose(e) => e.slot,
+            PinpetEvent::PartialClose(e) => e.slot,
+            PinpetEvent::MilestoneDiscount(e) => e.slot,
+            PinpetEvent::AddMargin(e) => e.slot,
+            PinpetEvent::ExtendOrder(e) => e.slot,
+        }
+    }
+
+    /// 交易内的事件序号 / Event ordinal within the transaction
+    pub fn event_index(&self) -> u32 {
+        match self {
+            PinpetEvent::TokenCreated(e) => e.event_index,
+            PinpetEvent::BuySell(e) => e.event_index,
+            PinpetEvent::LongShort(e) => e.event_index,
+            PinpetEvent::FullClose(e) => e.event_index,
+            PinpetEvent::PartialClose(e) => e.event_index,
+            PinpetEvent::MilestoneDiscount(e) => e.event_index,
+            PinpetEvent::AddMargin(e) => e.event_index,
+            PinpetEvent::ExtendOrder(e) => e.event_index,
+        }
+    }
+
+    /// 事件类型名称 / Event type name
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            PinpetEvent::TokenCreated(_) => "TokenCreated",
+            PinpetEvent::BuySell(_) => "BuySell",
+            PinpetEvent::LongShort(_) => "LongShort",
+            PinpetEvent::FullClose(_) => "FullClose",
+            PinpetEvent::PartialClose(_) => "PartialClose",
+            PinpetEvent::MilestoneDiscount(_) => "MilestoneDiscount",
+            PinpetEvent::AddMargin(_) => "AddMargin",
+            PinpetEvent::ExtendOrder(_) => "ExtendOrder",
+        }
+    }
+
+    /// 事件所属的 mint / The mint the event belongs to
+    pub fn mint(&self) -> &str {
+        match self {
+            PinpetEvent::TokenCreated(e) => &e.mint_account,
+            PinpetEvent::BuySell(e) => &e.mint_account,
+            PinpetEvent::LongShort(e) => &e.mint_account,
+            PinpetEvent::FullClose(e) => &e.mint_account,
+            PinpetEvent::PartialClose(e) => &e.mint_account,
+            PinpetEvent::MilestoneDiscount(e) => &e.mint_account,
+            PinpetEvent::AddMargin(e) => &e.mint_account,
+            PinpetEvent::ExtendOrder(e) => &e.mint_account,
+        }
+    }
+
+    /// 成交类事件的公共字段, 非成交事件返回 None; 成交摘要索引和实时成交推送共用这一映射
+    /// Common fields of a trade event, None for non-trade events; the trade summary index and live trade pushes share
+    /// this mapping
+    pub fn trade(&self) -> Option<TradeFields<'_>> {
+        let fields = match self {
+            PinpetEvent::BuySell(e) => TradeFields {
+                mint: &e.mint_account,
+                side: if e.is_buy { "buy" } else { "sell" },
+                user: &e.payer,
+                sol_amount: e.sol_amount,
+                token_amount: e.token_amount,
+                price: e.latest_price,
+                order_id: None,
+                signature: &e.signature,
+                slot: e.slot,
+                timestamp: e.timestamp,
+            },
+            PinpetEvent::LongShort(e) => TradeFields {
+                mint: &e.mint_account,
+                side: if e.order_type == 1 { "long" } else { "short" },
+                user: &e.payer,
+                sol_amount: e.margin_sol_amount,
+                token_amount: e.position_asset_amount,
+                price: e.latest_price,
+                order_id: Some(e.order_id),
+                signature: &e.signature,
+                slot: e.slot,
+                timestamp: e.timestamp,
+            },
+            // payer 可能是清算机器人或 keeper, 平仓归属订单所有者 / payer may be a liquidator bot or keeper, closes belong to the order owner
+            PinpetEvent::FullClose(e) => TradeFields {
+                mint: &e.mint_account,
+                side: if e.is_close_long { "close_long" } else { "close_short" },
+                user: &e.user_sol_account,
+                sol_amount: e.final_sol_amount,
+                token_amount: e.final_token_amount,
+                price: e.latest_price,
+                order_id: Some(e.order_id),
+                signature: &e.signature,
+                slot: e.slot,
+                timestamp: e.timestamp,
+            },
+            PinpetEvent::PartialClose(e) => TradeFields {
+                mint: &e.mint_account,
+                side: if e.is_close_long { "close_long" } else { "close_short" },
+                user: &e.user_sol_account,
+                sol_amount: e.final_sol_amount,
+                token_amount: e.final_token_amount,
+                price: e.latest_price,
+                order_id: Some(e.order_id),
+                signature: &e.signature,
+                slot: e.slot,
+                timestamp: e.timestamp,
+            },
+            PinpetEvent::TokenCreated(_)
+            | PinpetEvent::MilestoneDiscount(_)
+            | PinpetEvent::AddMargin(_)
+            | PinpetEvent::ExtendOrder(_) => return None,
+        };
+        Some(fields)
+    }
+}
+
+/// 成交类事件的公共字段 / Common fields of a trade event
+#[derive(Debug, Clone, Copy)]
+pub struct TradeFields<'a> {
+    pub mint: &'a str,
+    /// buy/sell/long/short/close_long/close_short
+    pub side: &'static str,
+    /// 成交归属的用户, 平仓为订单所有者 / User the trade belongs to, the order owner for closes
+    pub user: &'a str,
+    /// 买卖为成交的 SOL, 开仓为保证金, 平仓为取回的 SOL / Traded SOL for buy/sell, margin for opens, SOL returned for closes
+    pub sol_amount: u64,
+    pub token_amount: u64,
+    pub price: u128,
+    /// 订单ID (保证金事件) / Order ID (margin events)
+    pub order_id: Option<u64>,
+    pub signature: &'a str,
+    pub slot: u64,
+    pub timestamp: DateTime<Utc>,
 }
 
 /// 创建基本代币事件 / Token creation event
@@ -54,6 +216,10 @@ pub struct TokenCreatedEvent {
     pub timestamp: DateTime<Utc>,
     pub signature: String,
     pub slot: u64,
+    /// 交易内的事件序号 (目标程序的第几条 Program data 日志, 从 0 开始), 与签名一起唯一标识事件
+    /// Event ordinal within the transaction (which target-program Program data log, from 0); unique together with the signature
+    #[serde(default)]
+    pub event_index: u32,
 }
 
 /// 买卖交易事件 / Buy/Sell event
@@ -72,6 +238,10 @@ pub struct BuySellEvent {
     pub timestamp: DateTime<Utc>,
     pub signature: String,
     pub slot: u64,
+    /// 交易内的事件序号 (目标程序的第几条 Program data 日志, 从 0 开始), 与签名一起唯一标识事件
+    /// Event ordinal within the transaction (which target-program Program data log, from 0); unique together with the signature
+    #[serde(default)]
+    pub event_index: u32,
 }
 
 /// 保证金做多做空交易事件 / Long/Short margin trading event
@@ -100,10 +270,19 @@ pub struct LongShortEvent {
     pub position_asset_amount: u64,      // 当前持仓币的数量 / Current position asset amount
     pub borrow_fee: u16,                 // 保证金交易手续费 / Margin trading fee
     pub liquidate_indices: Vec<u16>,    // 需要清算的订单索引列表 / Liquidation order indices
+    /// 止盈价格, 0 表示未设置 / Take-profit price, 0 when not set
+    #[serde_as(as = "DisplayFromStr")]
+    #[serde(default)]
+    #[schema(value_type = String)]
+    pub take_profit_price: u128,
     #[schema(value_type = String)]
     pub timestamp: DateTime<Utc>,
     pub signature: String,
     pub slot: u64,
+    /// 交易内的事件序号 (目标程序的第几条 Program data 日志, 从 0 开始), 与签名一起唯一标识事件
+    /// Event ordinal within the transaction (which target-program Program data log, from 0); unique together with the signature
+    #[serde(default)]
+    pub event_index: u32,
 }
 
 /// 全平仓事件 / Full close event
@@ -122,10 +301,17 @@ pub struct FullCloseEvent {
     pub order_id: u64,                   // 平仓订单的唯一编号 / Unique order ID
     pub order_index: u16,                // 平仓订单的索引 / Order index in the orderbook
     pub liquidate_indices: Vec<u16>,    // 需要清算的订单索引列表 / Liquidation indices
+    /// 支付给调用者的清算奖励, 开仓者自己平仓时为0 / Keeper reward paid to the caller, 0 when the owner self-closes
+    #[serde(default)]
+    pub keeper_reward: u64,
     #[schema(value_type = String)]
     pub timestamp: DateTime<Utc>,
     pub signature: String,
     pub slot: u64,
+    /// 交易内的事件序号 (目标程序的第几条 Program data 日志, 从 0 开始), 与签名一起唯一标识事件
+    /// Event ordinal within the transaction (which target-program Program data log, from 0); unique together with the signature
+    #[serde(default)]
+    pub event_index: u32,
 }
 
 /// 部分平仓事件 / Partial close event
@@ -164,6 +350,10 @@ pub struct PartialCloseEvent {
     pub timestamp: DateTime<Utc>,
     pub signature: String,
     pub slot: u64,
+    /// 交易内的事件序号 (目标程序的第几条 Program data 日志, 从 0 开始), 与签名一起唯一标识事件
+    /// Event ordinal within the transaction (which target-program Program data log, from 0); unique together with the signature
+    #[serde(default)]
+    pub event_index: u32,
 }
 
 /// 交易里程碑折扣事件 / Milestone discount event
@@ -179,6 +369,64 @@ pub struct MilestoneDiscountEvent {
     pub timestamp: DateTime<Utc>,
     pub signature: String,
     pub slot: u64,
+    /// 交易内的事件序号 (目标程序的第几条 Program data 日志, 从 0 开始), 与签名一起唯一标识事件
+    /// Event ordinal within the transaction (which target-program Program data log, from 0); unique together with the signature
+    #[serde(default)]
+    pub event_index: u32,
+}
+
+/// 追加保证金事件 / Add margin event
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AddMarginEvent {
+    pub payer: String,                   // 订单所有者 / Order owner
+    pub mint_account: String,
+    pub order_id: u64,                   // 订单的唯一编号 / Unique order ID
+    pub order_index: u16,                // 订单在订单账本中的索引 / Order index in the orderbook
+    pub order_type: u8,                  // 订单类型 / Order type: 1:做多/long 2:做空/short
+    pub add_sol_amount: u64,             // 本次追加的保证金SOL数量 / Margin SOL added by this call
+    pub margin_sol_amount: u64,          // 追加后的保证金SOL数量 / Margin SOL amount after the top-up
+    #[serde_as(as = "DisplayFromStr")]
+    pub lock_lp_start_price: u128,       // 锁定流动池区间开始价(追加后) / LP lock range start price (after the top-up)
+    #[serde_as(as = "DisplayFromStr")]
+    pub lock_lp_end_price: u128,         // 锁定流动池区间结束价(追加后) / LP lock range end price (after the top-up)
+    pub lock_lp_sol_amount: u64,         // 锁定流动池区间sol数量(追加后) / Locked LP SOL amount (after the top-up)
+    pub lock_lp_token_amount: u64,       // 锁定流动池区间token数量 / Locked LP token amount
+    #[serde_as(as = "DisplayFromStr")]
+    pub latest_price: u128,              // 最新的价格 / Latest price
+    #[schema(value_type = String)]
+    pub timestamp: DateTime<Utc>,
+    pub signature: String,
+    pub slot: u64,
+    /// 交易内的事件序号 (目标程序的第几条 Program data 日志, 从 0 开始), 与签名一起唯一标识事件
+    /// Event ordinal within the transaction (which target-program Program data log, from 0); unique together with the signature
+    #[serde(default)]
+    pub event_index: u32,
+}
+
+/// 订单延期事件 / Extend order event
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ExtendOrderEvent {
+    pub payer: String,                   // 订单所有者 / Order owner
+    pub mint_account: String,
+    pub order_id: u64,                   // 订单的唯一编号 / Unique order ID
+    pub order_index: u16,                // 订单在订单账本中的索引 / Order index in the orderbook
+    pub order_type: u8,                  // 订单类型 / Order type: 1:做多/long 2:做空/short
+    pub extend_seconds: u32,             // 本次延期的秒数 / Seconds added by this extension
+    pub old_end_time: u32,               // 延期前的到期时间戳(秒) / Expiry timestamp before the extension
+    pub end_time: u32,                   // 延期后的到期时间戳(秒) / Expiry timestamp after the extension
+    pub fee_sol_amount: u64,             // 本次收取的延期手续费(SOL) / Extension fee charged (SOL)
+    #[serde_as(as = "DisplayFromStr")]
+    pub latest_price: u128,              // 最新的价格 / Latest price
+    #[schema(value_type = String)]
+    pub timestamp: DateTime<Utc>,
+    pub signature: String,
+    pub slot: u64,
+    /// 交易内的事件序号 (目标程序的第几条 Program data 日志, 从 0 开始), 与签名一起唯一标识事件
+    /// Event ordinal within the transaction (which target-program Program data log, from 0); unique together with the signature
+    #[serde(default)]
+    pub event_index: u32,
 }
 
 /// 事件解析器 / Event parser
@@ -186,13 +434,54 @@ pub struct MilestoneDiscountEvent {
 pub struct EventParser {
     #[allow(dead_code)]
     pub program_id: Pubkey,
+    /// 解码失败诊断, 克隆间共享 / Decode failure diagnostics, shared across clones
+    diagnostics: Arc<DecodeDiagnostics>,
 }
 
 impl EventParser {
     /// 创建新的事件解析器 / Create new event parser
     pub fn new(program_id: &str) -> anyhow::Result<Self> {
         let program_id = program_id.parse::<Pubkey>()?;
-        Ok(Self { program_id })
+        Ok(Self {
+            program_id,
+            diagnostics: Arc::new(DecodeDiagnostics::default()),
+        })
+    }
+
+    /// 获取解码失败诊断 / Get decode failure diagnostics
+    pub fn diagnostics(&self) -> Arc<DecodeDiagnostics> {
+        Arc::clone(&self.diagnostics)
+    }
+
+    /// 记录一次解码失败 / Record a decode failure
+    fn record_decode_failure(
+        &self,
+        event_type: &'static str,
+        data: &[u8],
+        raw_base64: &str,
+        error: String,
+        signature: &str,
+        slot: u64,
+    ) {
+        let discriminator = data.get(0..8).map(hex::encode).unwrap_or_default();
+        debug!(
+            "事件解码失败 / Event decode failed: program_id={}, event_type={}, discriminator={}, signature={}, slot={}, raw={}",
+            self.program_id, event_type, discriminator, signature, slot, raw_base64
+        );
+        self.diagnostics.record(
+            event_type,
+            DecodeFailure {
+                event_type: event_type.to_string(),
+                discriminator,
+                program_id: self.program_id.to_string(),
+                signature: signature.to_string(),
+                slot,
+                data_len: data.len(),
+                error,
+                raw_base64: raw_base64.to_string(),
+                timestamp: Utc::now().timestamp_millis(),
+            },
+        );
     }
 
     /// 使用调用栈跟踪解析事件以捕获CPI事件 / Parse events with call stack tracking to capture CPI events
@@ -205,6 +494,7 @@ impl EventParser {
         let mut events = Vec::new();
         let mut program_stack = Vec::new();
         let mut in_target_program = false;
+        let mut data_logs: u32 = 0;
 
         debug!("开始调用栈解析，共{}行日志 / Starting call stack parsing for {} log lines", logs.len(), logs.len());
 
@@ -255,6 +545,11 @@ impl EventParser {
             if in_target_program && log.starts_with("Program data:") {
                 debug!("在目标程序上下文中找到Program data，位于日志[{}] / Found Program data in target program context at log[{}]", i, i);
 
+                // 解码失败的日志也占用序号, 保证同一交易每次解析得到的序号一致
+                // Logs that fail to decode still take an ordinal, so every parse of a transaction yields the same ordinals
+                let event_index = data_logs;
+                data_logs += 1;
+
                 if let Some(data_part) = log.strip_prefix("Program data: ") {
                     let data_part = data_part.trim();
 
@@ -264,7 +559,7 @@ impl EventParser {
                             debug!("成功解码Base64数据，长度:{} / Successfully decoded Base64 data, length: {}", data.len(), data.len());
 
                             // 从数据解析事件 / Parse event from data
-                            match self.parse_event_data(&data, signature, slot) {
+                            match self.parse_event_data(&data, signature, slot, event_index) {
                                 Ok(Some(event)) => {
                                     debug!(
                                         "成功从CPI上下文解析事件 / Successfully parsed event from CPI context: {:?}",
@@ -273,15 +568,26 @@ impl EventParser {
                                     events.push(event);
                                 }
                                 Ok(None) => {
-                                    debug!("数据不匹配任何事件判别器 / Data didn't match any event discriminator");
+                                    // 程序新增的事件类型也要可见 / Event types newly added to the program must show up too
+                                    self.record_decode_failure(
+                                        UNKNOWN_DISCRIMINATOR,
+                                        &data,
+                                        data_part,
+                                        "未知事件判别器 / Unknown event discriminator".to_string(),
+                                        signature,
+                                        slot,
+                                    );
                                 }
                                 Err(e) => {
-                                    warn!("解析事件数据失败 / Failed to parse event data: {}", e);
+                                    let event_type = discriminator_name(&data);
+                                    warn!("解析事件数据失败 / Failed to parse event data: event_type={}, {}", event_type, e);
+                                    self.record_decode_failure(event_type, &data, data_part, e.to_string(), signature, slot);
                                 }
                             }
                         }
                         Err(e) => {
                             warn!("Base64解码失败 / Base64 decoding failed: {}", e);
+                            self.record_decode_failure(BASE64_FAILURE, &[], data_part, e.to_string(), signature, slot);
                         }
                     }
                 }
@@ -310,6 +616,7 @@ impl EventParser {
         data: &[u8],
         signature: &str,
         slot: u64,
+        event_index: u32,
     ) -> anyhow::Result<Option<PinpetEvent>> {
         if data.len() < 8 {
             return Ok(None);
@@ -344,6 +651,7 @@ impl EventParser {
                     timestamp,
                     signature: signature.to_string(),
                     slot,
+                    event_index,
                 })))
             }
             BUY_SELL_EVENT_DISCRIMINATOR => {
@@ -361,12 +669,30 @@ impl EventParser {
                     timestamp,
                     signature: signature.to_string(),
                     slot,
+                    event_index,
                 })))
             }
             LONG_SHORT_EVENT_DISCRIMINATOR => {
                 debug!("解析LongShort事件 / Parsing LongShort event, data_len={}", event_data.len());
-                let event = LongShortRaw::try_from_slice(event_data)
+                // take_profit_price 是后加的尾部字段, 旧事件没有 / take_profit_price is a trailing field that older events lack
+                let mut buf = event_data;
+                let event = LongShortRaw::deserialize(&mut buf)
                     .map_err(|e| anyhow::anyhow!("LongShort解析失败: {}, data_len={}", e, event_data.len()))?;
+                let take_profit_price = if buf.is_empty() {
+                    0
+                } else {
+                    let price = u128::deserialize(&mut buf)
+                        .map_err(|e| anyhow::anyhow!("LongShort解析失败: {}, data_len={}", e, event_data.len()))?;
+                    if !buf.is_empty() {
+                        return Err(anyhow::anyhow!(
+                            "LongShort解析失败: {} 字节未读取 / {} unread bytes, data_len={}",
+                            buf.len(),
+                            buf.len(),
+                            event_data.len()
+                        ));
+                    }
+                    price
+                };
                 Ok(Some(PinpetEvent::LongShort(LongShortEvent {
                     payer: event.payer.to_string(),
                     mint_account: event.mint_account.to_string(),
@@ -386,15 +712,34 @@ impl EventParser {
                     position_asset_amount: event.position_asset_amount,
                     borrow_fee: event.borrow_fee,
                     liquidate_indices: event.liquidate_indices,
+                    take_profit_price,
                     timestamp,
                     signature: signature.to_string(),
                     slot,
+                    event_index,
                 })))
             }
             FULL_CLOSE_EVENT_DISCRIMINATOR => {
                 debug!("解析FullClose事件 / Parsing FullClose event, data_len={}", event_data.len());
-                let event = FullCloseRaw::try_from_slice(event_data)
+                // keeper_reward 是后加的尾部字段, 旧事件没有 / keeper_reward is a trailing field that older events lack
+                let mut buf = event_data;
+                let event = FullCloseRaw::deserialize(&mut buf)
                     .map_err(|e| anyhow::anyhow!("FullClose解析失败: {}, data_len={}", e, event_data.len()))?;
+                let keeper_reward = if buf.is_empty() {
+                    0
+                } else {
+                    let reward = u64::deserialize(&mut buf)
+                        .map_err(|e| anyhow::anyhow!("FullClose解析失败: {}, data_len={}", e, event_data.len()))?;
+                    if !buf.is_empty() {
+                        return Err(anyhow::anyhow!(
+                            "FullClose解析失败: {} 字节未读取 / {} unread bytes, data_len={}",
+                            buf.len(),
+                            buf.len(),
+                            event_data.len()
+                        ));
+                    }
+                    reward
+                };
                 Ok(Some(PinpetEvent::FullClose(FullCloseEvent {
                     payer: event.payer.to_string(),
                     user_sol_account: event.user_sol_account.to_string(),
@@ -407,9 +752,11 @@ impl EventParser {
                     order_id: event.order_id,
                     order_index: event.order_index,
                     liquidate_indices: event.liquidate_indices,
+                    keeper_reward,
                     timestamp,
                     signature: signature.to_string(),
                     slot,
+                    event_index,
                 })))
             }
             PARTIAL_CLOSE_EVENT_DISCRIMINATOR => {
@@ -444,6 +791,7 @@ impl EventParser {
                     timestamp,
                     signature: signature.to_string(),
                     slot,
+                    event_index,
                 })))
             }
             MILESTONE_DISCOUNT_EVENT_DISCRIMINATOR => {
@@ -459,6 +807,51 @@ impl EventParser {
                     timestamp,
                     signature: signature.to_string(),
                     slot,
+                    event_index,
+                })))
+            }
+            ADD_MARGIN_EVENT_DISCRIMINATOR => {
+                debug!("解析AddMargin事件 / Parsing AddMargin event, data_len={}", event_data.len());
+                let event = AddMarginRaw::try_from_slice(event_data)
+                    .map_err(|e| anyhow::anyhow!("AddMargin解析失败: {}, data_len={}", e, event_data.len()))?;
+                Ok(Some(PinpetEvent::AddMargin(AddMarginEvent {
+                    payer: event.payer.to_string(),
+                    mint_account: event.mint_account.to_string(),
+                    order_id: event.order_id,
+                    order_index: event.order_index,
+                    order_type: event.order_type,
+                    add_sol_amount: event.add_sol_amount,
+                    margin_sol_amount: event.margin_sol_amount,
+                    lock_lp_start_price: event.lock_lp_start_price,
+                    lock_lp_end_price: event.lock_lp_end_price,
+                    lock_lp_sol_amount: event.lock_lp_sol_amount,
+                    lock_lp_token_amount: event.lock_lp_token_amount,
+                    latest_price: event.latest_price,
+                    timestamp,
+                    signature: signature.to_string(),
+                    slot,
+                    event_index,
+                })))
+            }
+            EXTEND_ORDER_EVENT_DISCRIMINATOR => {
+                debug!("解析ExtendOrder事件 / Parsing ExtendOrder event, data_len={}", event_data.len());
+                let event = ExtendOrderRaw::try_from_slice(event_data)
+                    .map_err(|e| anyhow::anyhow!("ExtendOrder解析失败: {}, data_len={}", e, event_data.len()))?;
+                Ok(Some(PinpetEvent::ExtendOrder(ExtendOrderEvent {
+                    payer: event.payer.to_string(),
+                    mint_account: event.mint_account.to_string(),
+                    order_id: event.order_id,
+                    order_index: event.order_index,
+                    order_type: event.order_type,
+                    extend_seconds: event.extend_seconds,
+                    old_end_time: event.old_end_time,
+                    end_time: event.end_time,
+                    fee_sol_amount: event.fee_sol_amount,
+                    latest_price: event.latest_price,
+                    timestamp,
+                    signature: signature.to_string(),
+                    slot,
+                    event_index,
                 })))
             }
             _ => {
@@ -575,4 +968,34 @@ struct MilestoneDiscountRaw {
     swap_fee: u16,
     borrow_fee: u16,
     fee_discount_flag: u8,
-}
\ No newline at end of file
+}
+
+#[derive(BorshDeserialize)]
+struct AddMarginRaw {
+    payer: Pubkey,
+    mint_account: Pubkey,
+    order_id: u64,
+    order_index: u16,
+    order_type: u8,
+    add_sol_amount: u64,
+    margin_sol_amount: u64,
+    lock_lp_start_price: u128,
+    lock_lp_end_price: u128,
+    lock_lp_sol_amount: u64,
+    lock_lp_token_amount: u64,
+    latest_price: u128,
+}
+
+#[derive(BorshDeserialize)]
+struct ExtendOrderRaw {
+    payer: Pubkey,
+    mint_account: Pubkey,
+    order_id: u64,
+    order_index: u16,
+    order_type: u8,
+    extend_seconds: u32,
+    old_end_time: u32,
+    end_time: u32,
+    fee_sol_amount: u64,
+    latest_price: u128,
+}
@@ -0,0 +1,512 @@
+// 终结性闸门 - 跟踪尚未终结的 slot, 所在 slot 被分叉丢弃 (orphaned) 时丢弃或回滚它的事件
+// Finality gate - tracks slots that are not finalized yet and drops or rolls back their events when the slot is
+// orphaned by a fork
+//
+// 监听器以 processed/confirmed 订阅, 这些状态仍可能被分叉回滚; 订单簿镜像一旦应用了回滚的事件就无法自行修复
+// The listener subscribes at processed/confirmed, which can still be rolled back by a fork; once the order book
+// mirror has applied a rolled-back event it cannot repair itself
+//
+// 两种模式 / Two modes:
+// - 缓冲: 事件按 slot 缓冲, slot 终结后才交给存储处理器, 被丢弃的 slot 的事件直接删除
+//   Buffered: events are held per slot and handed to the storage handler once the slot is finalized; events of an
+//   orphaned slot are dropped
+// - 乐观: 事件立即应用并记录撤销日志, slot 被丢弃时回滚订单簿和事件存储, slot 终结后删除撤销日志
+//   Optimistic: events are applied at once with an undo journal; an orphaned slot is rolled back in the order book and
+//   event stores, and a finalized slot's undo journal is deleted
+//
+// slot 是否终结由 finalized 承诺下的 getBlocks 判定: 不超过 finalized slot 却不在其中的 slot 即被丢弃
+// 缓冲的事件同时写入事件库 (finality_pending:), 待终结的 slot 由撤销日志记录, 两者都在启动时恢复
+// Finality is decided with getBlocks at finalized commitment: a slot at or below the finalized slot that is missing
+// from it was orphaned. Buffered events are also written to the event DB (finality_pending:) and unsettled slots are
+// recorded by the undo journals, both are restored at startup
+//
+// 回滚覆盖不到的状态 / State a rollback does not cover:
+// - 合并 (merge) 计数器不经撤销日志, 被丢弃 slot 的计数保留
+//   Merge-operator counters bypass the undo journal, so an orphaned slot's counts stay
+// - 曲线状态 (curve:) 和 token 价格 (TokenStorage) 不记录撤销日志, 保留被丢弃 slot 的最后值, 直到下一笔交易覆盖
+//   Curve state (curve:) and token prices (TokenStorage) are not journaled and keep an orphaned slot's last value
+//   until the next trade overwrites it
+// - user_pnl 随事件批次记录撤销日志, 但之后的 slot 又累加过时无法撤销, 作为冲突计数
+//   user_pnl is journaled with the event batch, but once a later slot has added to it it cannot be undone and is
+//   counted as a conflict
+// 订单簿按整本回滚, 被之后的 slot 修改过的订单簿保持不变并标记重建 (见 OrderBookStorage::revert_slot)
+// Order books are rolled back whole; a book changed by a later slot is left untouched and marked for a rebuild (see
+// OrderBookStorage::revert_slot)
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{debug, error, info, warn};
+use utoipa::ToSchema;
+
+use super::client::SolanaClient;
+use super::events::PinpetEvent;
+use super::listener::EventHandler;
+use crate::db::{EventStorage, OrderBookStorage, RevertOutcome};
+
+/// 检查 finalized slot 的间隔 / Interval between finalized slot checks
+pub const FINALITY_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// getBlocks 单次最多覆盖的 slot 数 / Max slots a single getBlocks call may span
+const MAX_BLOCKS_RANGE: u64 = 500_000;
+
+/// 乐观模式下回滚被丢弃 slot 的存储 / Stores rolled back for orphaned slots in optimistic mode
+pub struct SlotRollback {
+    orderbook_storage: Arc<OrderBookStorage>,
+    event_storage: Arc<EventStorage>,
+}
+
+impl SlotRollback {
+    /// 两个存储都必须开启撤销日志 / Both stores must have their undo journal enabled
+    pub fn new(orderbook_storage: Arc<OrderBookStorage>, event_storage: Arc<EventStorage>) -> Self {
+        Self {
+            orderbook_storage,
+            event_storage,
+        }
+    }
+
+    /// 回滚 slot, 返回恢复的键数和未能撤销的冲突 / Roll back a slot, returning the keys restored and the conflicts left in place
+    fn revert(&self, slot: u64) -> anyhow::Result<RevertOutcome> {
+        let mut outcome = self.orderbook_storage.revert_slot(slot)?;
+        let events = self.event_storage.revert_slot(slot)?;
+        outcome.restored += events.restored;
+        outcome.conflicted.extend(events.conflicted);
+        Ok(outcome)
+    }
+
+    /// 仍有撤销记录的 slot / Slots that still have undo records
+    fn journaled_slots(&self) -> anyhow::Result<BTreeSet<u64>> {
+        let mut slots = self.orderbook_storage.journaled_slots()?;
+        slots.extend(self.event_storage.journaled_slots()?);
+        Ok(slots)
+    }
+
+    fn commit(&self, slot: u64) -> anyhow::Result<()> {
+        self.orderbook_storage.commit_slot(slot)?;
+        self.event_storage.commit_slot(slot)?;
+        Ok(())
+    }
+}
+
+/// 终结性闸门统计 / Finality gate metrics
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(title = "FinalityMetrics", description = "终结性闸门统计 / Finality gate metrics")]
+pub struct FinalityMetrics {
+    /// 乐观模式 (立即应用, 丢弃时回滚) / Optimistic mode (applied at once, rolled back when orphaned)
+    pub optimistic: bool,
+    /// 等待终结的缓冲事件数 / Buffered events awaiting finality
+    pub pending_events: usize,
+    /// 已应用但尚未终结的 slot 数 / Slots applied but not finalized yet
+    pub unsettled_slots: usize,
+    /// 终结后交给存储处理器的缓冲事件数 / Buffered events handed to the storage handler once finalized
+    pub applied: u64,
+    /// 因 slot 被丢弃而删除的缓冲事件数 / Buffered events dropped because their slot was orphaned
+    pub discarded: u64,
+    /// 已回滚的 slot 数 / Slots rolled back
+    pub reverted_slots: u64,
+    /// 回滚时未能撤销的订单簿或键数, 大于0说明需要重建 / Books or keys a rollback left in place; above 0 means a rebuild is needed
+    pub revert_conflicts: u64,
+}
+
+/// 终结性闸门, 包装存储事件处理器 / Finality gate wrapping the storage event handler
+pub struct FinalityGate {
+    inner: Arc<dyn EventHandler>,
+    /// 乐观模式的回滚入口, 为空时是缓冲模式 / Rollback hooks of the optimistic mode, None in buffered mode
+    rollback: Option<SlotRollback>,
+    /// 缓冲模式: 缓冲事件的持久化存储, 为空时只在内存中 / Buffered mode: where buffered events are persisted, memory only when None
+    pending_store: Option<Arc<EventStorage>>,
+    /// 缓冲模式: slot -> 按到达顺序排列的事件 / Buffered mode: slot -> events in arrival order
+    pending: Mutex<BTreeMap<u64, Vec<PinpetEvent>>>,
+    /// 乐观模式: 已应用但尚未终结的 slot / Optimistic mode: slots applied but not finalized yet
+    unsettled: Mutex<BTreeSet<u64>>,
+    /// 应用与回滚互斥, 回滚时不会有事件写到一半 / Applying and rolling back are exclusive, so no event is half-written during a rollback
+    apply_lock: tokio::sync::Mutex<()>,
+    applied: AtomicU64,
+    discarded: AtomicU64,
+    reverted_slots: AtomicU64,
+    revert_conflicts: AtomicU64,
+}
+
+impl FinalityGate {
+    /// 缓冲模式 / Buffered mode
+    pub fn new(inner: Arc<dyn EventHandler>) -> Self {
+        Self {
+            inner,
+            rollback: None,
+            pending_store: None,
+            pending: Mutex::new(BTreeMap::new()),
+            unsettled: Mutex::new(BTreeSet::new()),
+            apply_lock: tokio::sync::Mutex::new(()),
+            applied: AtomicU64::new(0),
+            discarded: AtomicU64::new(0),
+            reverted_slots: AtomicU64::new(0),
+            revert_conflicts: AtomicU64::new(0),
+        }
+    }
+
+    /// 乐观模式, 从撤销日志恢复重启前尚未终结的 slot
+    /// Optimistic mode, restoring the slots left unsettled before a restart from the undo journals
+    pub fn optimistic(inner: Arc<dyn EventHandler>, rollback: SlotRollback) -> anyhow::Result<Self> {
+        let unsettled = rollback.journaled_slots()?;
+        if !unsettled.is_empty() {
+            info!(
+                "🔒 从撤销日志恢复 {} 个待终结 slot / Restored {} unsettled slot(s) from the undo journals",
+                unsettled.len(),
+                unsettled.len()
+            );
+        }
+        Ok(Self {
+            rollback: Some(rollback),
+            unsettled: Mutex::new(unsettled),
+            ..Self::new(inner)
+        })
+    }
+
+    /// 缓冲事件同时写入事件库, 并载入重启前尚未结算的缓冲事件
+    /// Also write buffered events to the event DB, loading the ones left unsettled before a restart
+    pub fn with_pending_store(self, store: Arc<EventStorage>) -> anyhow::Result<Self> {
+        let events = store.pending_events()?;
+        if !events.is_empty() {
+            info!(
+                "🔒 载入 {} 个重启前缓冲的事件 / Loaded {} event(s) buffered before the restart",
+                events.len(),
+                events.len()
+            );
+        }
+        for event in events {
+            self.buffer(event.slot(), event);
+        }
+        Ok(Self {
+            pending_store: Some(store),
+            ..self
+        })
+    }
+
+    /// 等待终结的事件数 / Events awaiting finality
+    pub fn pending_len(&self) -> usize {
+        self.lock_pending().values().map(Vec::len).sum()
+    }
+
+    /// 已应用但尚未终结的 slot 数 / Slots applied but not finalized yet
+    pub fn unsettled_len(&self) -> usize {
+        self.lock_unsettled().len()
+    }
+
+    /// 缓冲模式下交给存储处理器的事件数 / Events handed to the storage handler in buffered mode
+    pub fn applied(&self) -> u64 {
+        self.applied.load(Ordering::Relaxed)
+    }
+
+    /// 因 slot 被丢弃而删除的缓冲事件数 / Buffered events dropped because their slot was orphaned
+    pub fn discarded(&self) -> u64 {
+        self.discarded.load(Ordering::Relaxed)
+    }
+
+    /// 已回滚的 slot 数 / Slots rolled back
+    pub fn reverted_slots(&self) -> u64 {
+        self.reverted_slots.load(Ordering::Relaxed)
+    }
+
+    /// 回滚时因之后的 slot 又修改过而未能撤销的订单簿或键数 / Books or keys a rollback left in place because a later slot changed them
+    pub fn revert_conflicts(&self) -> u64 {
+        self.revert_conflicts.load(Ordering::Relaxed)
+    }
+
+    /// 当前统计 / Current metrics
+    pub fn metrics(&self) -> FinalityMetrics {
+        FinalityMetrics {
+            optimistic: self.rollback.is_some(),
+            pending_events: self.pending_len(),
+            unsettled_slots: self.unsettled_len(),
+            applied: self.applied(),
+            discarded: self.discarded(),
+            reverted_slots: self.reverted_slots(),
+            revert_conflicts: self.revert_conflicts(),
+        }
+    }
+
+    fn lock_pending(&self) -> std::sync::MutexGuard<'_, BTreeMap<u64, Vec<PinpetEvent>>> {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn lock_unsettled(&self) -> std::sync::MutexGuard<'_, BTreeSet<u64>> {
+        self.unsettled.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn buffer(&self, slot: u64, event: PinpetEvent) {
+        self.lock_pending().entry(slot).or_default().push(event);
+    }
+
+    /// 最早的未终结 slot / Earliest slot not settled yet
+    fn first_slot(&self) -> Option<u64> {
+        let pending = self.lock_pending().keys().next().copied();
+        let unsettled = self.lock_unsettled().first().copied();
+        pending.into_iter().chain(unsettled).min()
+    }
+
+    /// 取出 [first, last] 内的缓冲事件, 按 slot 和到达顺序排列
+    /// Take the buffered events in [first, last], ordered by slot and arrival
+    fn take_events(&self, first: u64, last: u64) -> Vec<PinpetEvent> {
+        let mut pending = self.lock_pending();
+        let mut taken = pending.split_off(&first);
+        let mut later = taken.split_off(&last.saturating_add(1));
+        pending.append(&mut later);
+        taken.into_values().flatten().collect()
+    }
+
+    /// 取出 [first, last] 内已应用的 slot / Take the applied slots in [first, last]
+    fn take_unsettled(&self, first: u64, last: u64) -> Vec<u64> {
+        let mut unsettled = self.lock_unsettled();
+        let mut taken = unsettled.split_off(&first);
+        let mut later = taken.split_off(&last.saturating_add(1));
+        unsettled.append(&mut later);
+        taken.into_iter().collect()
+    }
+
+    /// 结算 [first, last] 内的 slot, `rooted` 为其中已终结的 slot
+    /// Settle the slots in [first, last], `rooted` being the finalized ones among them
+    ///
+    /// 缓冲事件: 已终结的交给存储处理器, 其余删除; 已应用的 slot: 已终结的删除撤销日志, 其余从新到旧回滚
+    /// Buffered events are handed to the storage handler when finalized and dropped otherwise; applied slots have their
+    /// undo journal deleted when finalized and are rolled back newest first otherwise
+    pub(crate) async fn settle_range(&self, first: u64, last: u64, rooted: &HashSet<u64>) -> anyhow::Result<()> {
+        for event in self.take_events(first, last) {
+            if rooted.contains(&event.slot()) {
+                if let Err(e) = self.inner.handle_event(event).await {
+                    warn!("存储处理器处理已终结事件失败 / Storage handler failed on a finalized event: {}", e);
+                }
+                self.applied.fetch_add(1, Ordering::Relaxed);
+            } else {
+                warn!(
+                    "🗑️ slot 被分叉丢弃, 删除事件 / Slot orphaned, dropping event: signature={}, slot={}",
+                    event.signature(),
+                    event.slot()
+                );
+                self.discarded.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        // 重复应用是安全的 (事件按签名去重), 因此处理完再删除持久化的副本
+        // Applying twice is safe (events dedup by signature), so the persisted copies are deleted only after handling
+        if let Some(store) = &self.pending_store {
+            store.remove_pending_events(first, last)?;
+        }
+
+        let Some(rollback) = &self.rollback else {
+            return Ok(());
+        };
+        let _guard = self.apply_lock.lock().await;
+        let mut slots = self.take_unsettled(first, last);
+        while let Some(slot) = slots.pop() {
+            let result = if rooted.contains(&slot) {
+                rollback.commit(slot)
+            } else {
+                rollback.revert(slot).map(|outcome| {
+                    self.reverted_slots.fetch_add(1, Ordering::Relaxed);
+                    warn!(
+                        "⏪ slot {} 被分叉丢弃, 已回滚 {} 个键 / Slot {} orphaned, rolled back {} key(s)",
+                        slot, outcome.restored, slot, outcome.restored
+                    );
+                    if !outcome.conflicted.is_empty() {
+                        self.revert_conflicts
+                            .fetch_add(outcome.conflicted.len() as u64, Ordering::Relaxed);
+                        error!(
+                            "❌ slot {} 有 {} 处无法回滚, 需要重建 / Slot {} left {} conflict(s) in place, a rebuild is needed: {:?}",
+                            slot,
+                            outcome.conflicted.len(),
+                            slot,
+                            outcome.conflicted.len(),
+                            outcome.conflicted
+                        );
+                    }
+                })
+            };
+            if let Err(e) = result {
+                // 未处理的 slot 放回, 下次重试 / Put the unhandled slots back for the next attempt
+                let mut unsettled = self.lock_unsettled();
+                unsettled.insert(slot);
+                unsettled.extend(slots);
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    /// 检查一次 finalized slot 并结算它覆盖的 slot / Check the finalized slot once and settle the slots it covers
+    pub async fn settle(&self, client: &SolanaClient) -> anyhow::Result<()> {
+        let Some(first) = self.first_slot() else {
+            return Ok(());
+        };
+        let finalized_slot = client.get_finalized_slot().await?;
+        if first > finalized_slot {
+            return Ok(());
+        }
+
+        let last = finalized_slot.min(first + MAX_BLOCKS_RANGE - 1);
+        let rooted: HashSet<u64> = client.get_finalized_blocks(first, last).await?.into_iter().collect();
+        self.settle_range(first, last, &rooted).await?;
+        debug!(
+            "已结算至 slot {}: {} 个事件缓冲中, {} 个 slot 待终结 / Settled through slot {}: {} event(s) buffered, {} slot(s) unsettled",
+            last,
+            self.pending_len(),
+            self.unsettled_len(),
+            last,
+            self.pending_len(),
+            self.unsettled_len()
+        );
+        Ok(())
+    }
+
+    /// 后台循环: 定期检查 finalized slot / Background loop: periodically check the finalized slot
+    pub async fn run(self: Arc<Self>, client: Arc<SolanaClient>, interval: Duration) {
+        if self.rollback.is_some() {
+            info!("🔒 事件立即应用, 被分叉丢弃的 slot 将回滚 / Events are applied at once, orphaned slots are rolled back");
+        } else {
+            info!("🔒 事件等待 finalized 后再应用 / Events are applied once finalized");
+        }
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = self.settle(&client).await {
+                warn!("⚠️ 检查 slot 终结状态失败, 下次重试 / Failed to check slot finality, retrying next time: {}", e);
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl EventHandler for FinalityGate {
+    async fn handle_event(&self, event: PinpetEvent) -> anyhow::Result<()> {
+        let Some(rollback) = &self.rollback else {
+            if let Some(store) = &self.pending_store {
+                store.put_pending_event(&event)?;
+            }
+            self.buffer(event.slot(), event);
+            return Ok(());
+        };
+
+        let _guard = self.apply_lock.lock().await;
+        let slot = event.slot();
+        self.lock_unsettled().insert(slot);
+        rollback.orderbook_storage.begin_slot(slot);
+        self.inner.handle_event(event).await
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solana::events::MilestoneDiscountEvent;
+    use chrono::Utc;
+
+    /// 记录收到的事件 / Records the events it receives
+    #[derive(Default)]
+    struct Recorder {
+        events: Mutex<Vec<(String, u64)>>,
+    }
+
+    #[async_trait]
+    impl EventHandler for Recorder {
+        async fn handle_event(&self, event: PinpetEvent) -> anyhow::Result<()> {
+            self.events.lock().unwrap().push((event.signature().to_string(), event.slot()));
+            Ok(())
+        }
+
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+    }
+
+    fn event(signature: &str, slot: u64) -> PinpetEvent {
+        PinpetEvent::MilestoneDiscount(MilestoneDiscountEvent {
+            payer: "payer".to_string(),
+            mint_account: "mint".to_string(),
+            curve_account: "curve".to_string(),
+            swap_fee: 0,
+            borrow_fee: 0,
+            fee_discount_flag: 0,
+            timestamp: Utc::now(),
+            signature: signature.to_string(),
+            slot,
+            event_index: 0,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_events_wait_for_their_slot_to_finalize() {
+        let recorder = Arc::new(Recorder::default());
+        let gate = FinalityGate::new(recorder.clone());
+
+        for (signature, slot) in [("c", 7), ("a", 3), ("b", 5), ("a2", 3)] {
+            gate.handle_event(event(signature, slot)).await.unwrap();
+        }
+        assert_eq!(gate.pending_len(), 4);
+        assert_eq!(gate.first_slot(), Some(3));
+        assert!(recorder.events.lock().unwrap().is_empty());
+
+        gate.settle_range(3, 5, &HashSet::from([3, 5])).await.unwrap();
+        assert_eq!(
+            *recorder.events.lock().unwrap(),
+            vec![("a".to_string(), 3), ("a2".to_string(), 3), ("b".to_string(), 5)]
+        );
+        assert_eq!(gate.pending_len(), 1);
+        assert_eq!(gate.first_slot(), Some(7));
+    }
+
+    #[tokio::test]
+    async fn test_events_of_orphaned_slots_are_dropped() {
+        let recorder = Arc::new(Recorder::default());
+        let gate = FinalityGate::new(recorder.clone());
+
+        for (signature, slot) in [("kept", 10), ("orphaned", 11), ("later", 12)] {
+            gate.handle_event(event(signature, slot)).await.unwrap();
+        }
+
+        gate.settle_range(10, 11, &HashSet::from([10])).await.unwrap();
+        assert_eq!(*recorder.events.lock().unwrap(), vec![("kept".to_string(), 10)]);
+        assert_eq!((gate.applied(), gate.discarded()), (1, 1));
+        // 范围之外的 slot 留待下次 / Slots outside the range wait for the next round
+        assert_eq!(gate.pending_len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_buffered_events_survive_restart() {
+        let path = std::env::temp_dir().join(format!("finality_pending_test_{}", uuid::Uuid::new_v4()));
+        let mut opts = rocksdb::Options::default();
+        opts.create_if_missing(true);
+        crate::db::storage::register_counter_merge(&mut opts);
+        let db = Arc::new(rocksdb::DB::open(&opts, &path).unwrap());
+        let store = Arc::new(EventStorage::new(db, Arc::new(crate::db::ShardedLocks::default())).unwrap());
+
+        let gate = FinalityGate::new(Arc::new(Recorder::default()))
+            .with_pending_store(store.clone())
+            .unwrap();
+        for (signature, slot) in [("b", 5), ("a", 3)] {
+            gate.handle_event(event(signature, slot)).await.unwrap();
+        }
+
+        // 模拟重启: 新闸门载入持久化的缓冲事件 / Simulate a restart: a fresh gate loads the persisted buffered events
+        drop(gate);
+        let recorder = Arc::new(Recorder::default());
+        let gate = FinalityGate::new(recorder.clone()).with_pending_store(store.clone()).unwrap();
+        assert_eq!(gate.pending_len(), 2);
+
+        gate.settle_range(3, 5, &HashSet::from([3, 5])).await.unwrap();
+        assert_eq!(
+            *recorder.events.lock().unwrap(),
+            vec![("a".to_string(), 3), ("b".to_string(), 5)]
+        );
+        assert!(store.pending_events().unwrap().is_empty());
+
+        drop(gate);
+        drop(store);
+        let _ = std::fs::remove_dir_all(path);
+    }
+}
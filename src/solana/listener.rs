@@ -1,14 +1,17 @@
 // 事件监听器模块 / Event listener module
 use super::client::SolanaClient;
+use super::decode_diagnostics::DecodeDiagnostics;
 use super::events::{EventParser, PinpetEvent};
 use crate::config::SolanaConfig;
 use async_trait::async_trait;
 use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use tokio::sync::{broadcast, mpsc};
+use tokio::sync::mpsc::{self, error::TrySendError};
 use tokio::time::{interval, sleep, Duration};
 use tokio_tungstenite::{connect_async, tungstenite::Message};
 use tracing::{debug, error, info, warn};
@@ -16,6 +19,7 @@ use uuid::Uuid;
 use tokio::fs::OpenOptions;
 use tokio::io::AsyncWriteExt;
 use chrono;
+use utoipa::ToSchema;
 
 /// 事件监听器trait / Event listener trait
 #[async_trait]
@@ -120,6 +124,28 @@ impl EventHandler for DefaultEventHandler {
                 info!("   - 交易签名 / Transaction signature: {}", e.signature);
                 info!("   - 区块高度 / Block height: {}", e.slot);
             }
+            PinpetEvent::AddMargin(e) => {
+                let direction = if e.order_type == 1 { "多单/long" } else { "空单/short" };
+                info!(
+                    "💰 追加保证金事件 / Add margin event: {} 为 / topped up {} 订单 / order {} 代币 / on token {} 追加 / by {} lamports",
+                    e.payer, direction, e.order_id, e.mint_account, e.add_sol_amount
+                );
+                info!("   - 保证金 / Margin: {}", e.margin_sol_amount);
+                info!("   - 锁定区间 / Locked range: {} -> {}", e.lock_lp_start_price, e.lock_lp_end_price);
+                info!("   - 交易签名 / Transaction signature: {}", e.signature);
+                info!("   - 区块高度 / Block height: {}", e.slot);
+            }
+            PinpetEvent::ExtendOrder(e) => {
+                let direction = if e.order_type == 1 { "多单/long" } else { "空单/short" };
+                info!(
+                    "⏳ 订单延期事件 / Extend order event: {} 延期了 / extended {} 订单 / order {} 代币 / on token {} ({} 秒/seconds)",
+                    e.payer, direction, e.order_id, e.mint_account, e.extend_seconds
+                );
+                info!("   - 到期时间 / End time: {} -> {}", e.old_end_time, e.end_time);
+                info!("   - 延期手续费 / Extension fee: {}", e.fee_sol_amount);
+                info!("   - 交易签名 / Transaction signature: {}", e.signature);
+                info!("   - 区块高度 / Block height: {}", e.slot);
+            }
         }
         Ok(())
     }
@@ -129,6 +155,96 @@ impl EventHandler for DefaultEventHandler {
     }
 }
 
+/// 有界事件队列的发送端, 同时记录队列满的次数
+/// Sender side of a bounded event queue, also counting how often it was full
+#[derive(Clone)]
+pub struct EventQueue {
+    name: &'static str,
+    sender: mpsc::Sender<PinpetEvent>,
+    capacity: usize,
+    full_count: Arc<AtomicU64>,
+}
+
+/// 事件队列指标 / Event queue metrics
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(title = "EventQueueMetrics", description = "事件队列指标 / Event queue metrics")]
+pub struct EventQueueMetrics {
+    /// 队列名称 / Queue name
+    #[schema(example = "ingest")]
+    pub name: String,
+    /// 当前积压的事件数 / Events currently queued
+    pub depth: usize,
+    /// 队列容量 / Queue capacity
+    pub capacity: usize,
+    /// 队列满的次数: ingest 为背压等待次数, kline 为丢弃的事件数
+    /// Times the queue was full: backpressure waits for ingest, dropped events for kline
+    pub full_count: u64,
+}
+
+impl EventQueue {
+    /// 创建有界队列 / Create a bounded queue
+    pub fn bounded(name: &'static str, capacity: usize) -> (Self, mpsc::Receiver<PinpetEvent>) {
+        let (sender, receiver) = mpsc::channel(capacity);
+        let queue = Self {
+            name,
+            sender,
+            capacity,
+            full_count: Arc::new(AtomicU64::new(0)),
+        };
+        (queue, receiver)
+    }
+
+    /// 入队, 队列满时等待消费者 (背压) / Enqueue, waiting for the consumer when full (backpressure)
+    pub async fn send(&self, event: PinpetEvent) -> anyhow::Result<()> {
+        let event = match self.sender.try_send(event) {
+            Ok(()) => return Ok(()),
+            Err(TrySendError::Full(event)) => event,
+            Err(TrySendError::Closed(_)) => {
+                return Err(anyhow::anyhow!("事件队列已关闭 / Event queue {} is closed", self.name));
+            }
+        };
+
+        self.full_count.fetch_add(1, Ordering::Relaxed);
+        warn!(
+            "⏳ 事件队列已满, 等待消费者 / Event queue {} is full, waiting for the consumer (capacity={})",
+            self.name, self.capacity
+        );
+        self.sender
+            .send(event)
+            .await
+            .map_err(|_| anyhow::anyhow!("事件队列已关闭 / Event queue {} is closed", self.name))
+    }
+
+    /// 尝试入队, 队列满时丢弃并告警 / Try to enqueue, dropping with a warning when full
+    pub fn try_send(&self, event: PinpetEvent) -> bool {
+        match self.sender.try_send(event) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                self.full_count.fetch_add(1, Ordering::Relaxed);
+                warn!(
+                    "⚠️ 事件队列已满, 丢弃事件 / Event queue {} is full, dropping event (capacity={})",
+                    self.name, self.capacity
+                );
+                false
+            }
+            Err(TrySendError::Closed(_)) => {
+                warn!("⚠️ 事件队列已关闭 / Event queue {} is closed", self.name);
+                false
+            }
+        }
+    }
+
+    /// 读取队列指标 / Read queue metrics
+    pub fn metrics(&self) -> EventQueueMetrics {
+        EventQueueMetrics {
+            name: self.name.to_string(),
+            depth: self.capacity.saturating_sub(self.sender.capacity()),
+            capacity: self.capacity,
+            full_count: self.full_count.load(Ordering::Relaxed),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 enum ConnectionState {
     Disconnected,
@@ -143,8 +259,10 @@ pub struct SolanaEventListener {
     client: Arc<SolanaClient>,
     event_parser: EventParser,
     event_handler: Arc<dyn EventHandler>,
-    // 使用广播通道避免"通道已关闭"错误 / Use broadcast channel to avoid "channel closed" errors
-    event_broadcaster: broadcast::Sender<PinpetEvent>,
+    // 订阅任务解析后入队, 消费任务运行处理器链 / The subscription task enqueues parsed events, the consumer task runs the handler chain
+    event_queue: EventQueue,
+    // 消费任务运行期间持有锁, 停止后可重新启动 / Held by the consumer task while running, so the listener can be restarted
+    event_receiver: Arc<Mutex<mpsc::Receiver<PinpetEvent>>>,
     connection_state: Arc<tokio::sync::RwLock<ConnectionState>>,
     reconnect_attempts: Arc<tokio::sync::RwLock<u32>>,
     should_stop: Arc<tokio::sync::RwLock<bool>>,
@@ -193,14 +311,15 @@ impl SolanaEventListener {
         event_handler: Arc<dyn EventHandler>,
     ) -> anyhow::Result<Self> {
         let event_parser = EventParser::new(&config.program_id)?;
-        let (event_broadcaster, _) = broadcast::channel(1000);
+        let (event_queue, event_receiver) = EventQueue::bounded("ingest", config.event_buffer_size);
 
         Ok(Self {
             config,
             client,
             event_parser,
             event_handler,
-            event_broadcaster,
+            event_queue,
+            event_receiver: Arc::new(Mutex::new(event_receiver)),
             connection_state: Arc::new(tokio::sync::RwLock::new(ConnectionState::Disconnected)),
             reconnect_attempts: Arc::new(tokio::sync::RwLock::new(0)),
             should_stop: Arc::new(tokio::sync::RwLock::new(false)),
@@ -209,30 +328,38 @@ impl SolanaEventListener {
         })
     }
 
-    /// 使用广播通道启动事件处理器 / Start event processor using broadcast channel
+    /// 获取入队句柄 (用于读取队列指标) / Get the queue handle (for reading queue metrics)
+    pub fn event_queue(&self) -> EventQueue {
+        self.event_queue.clone()
+    }
+
+    /// 获取解码失败诊断 / Get decode failure diagnostics
+    pub fn decode_diagnostics(&self) -> Arc<DecodeDiagnostics> {
+        self.event_parser.diagnostics()
+    }
+
+    /// 启动消费任务, 从有界队列取事件并运行处理器链
+    /// Start the consumer task, taking events from the bounded queue and running the handler chain
     async fn start_event_processor(&self) -> anyhow::Result<()> {
-        let mut event_receiver = self.event_broadcaster.subscribe();
+        let event_receiver = Arc::clone(&self.event_receiver);
         let handler = Arc::clone(&self.event_handler);
         let should_stop = Arc::clone(&self.should_stop);
 
         tokio::spawn(async move {
-            info!("🎯 事件处理器启动，使用广播通道 / Event processor started with broadcast channel");
+            let mut event_receiver = event_receiver.lock().await;
+            info!("🎯 事件处理器启动，使用有界队列 / Event processor started with bounded queue");
 
             loop {
                 tokio::select! {
-                    event_result = event_receiver.recv() => {
-                        match event_result {
-                            Ok(event) => {
+                    event = event_receiver.recv() => {
+                        match event {
+                            Some(event) => {
                                 if let Err(e) = handler.handle_event(event).await {
                                     error!("处理事件失败 / Failed to process event: {}", e);
                                 }
                             }
-                            Err(broadcast::error::RecvError::Lagged(skipped)) => {
-                                warn!("事件处理器延迟，跳过了{}个事件 / Event processor lagged, skipped {} events", skipped, skipped);
-                                continue;
-                            }
-                            Err(broadcast::error::RecvError::Closed) => {
-                                info!("事件广播器关闭，停止处理器 / Event broadcaster closed, stopping processor");
+                            None => {
+                                info!("事件队列关闭，停止处理器 / Event queue closed, stopping processor");
                                 break;
                             }
                         }
@@ -257,7 +384,7 @@ impl SolanaEventListener {
         let config = self.config.clone();
         let client = Arc::clone(&self.client);
         let event_parser = self.event_parser.clone();
-        let event_broadcaster = self.event_broadcaster.clone();
+        let event_queue = self.event_queue.clone();
         let connection_state = Arc::clone(&self.connection_state);
         let reconnect_attempts = Arc::clone(&self.reconnect_attempts);
         let should_stop = Arc::clone(&self.should_stop);
@@ -280,7 +407,7 @@ impl SolanaEventListener {
                     &config,
                     &client,
                     &event_parser,
-                    &event_broadcaster,
+                    &event_queue,
                     &connection_state,
                     &should_stop,
                     &processed_signatures,
@@ -324,7 +451,7 @@ impl SolanaEventListener {
         config: &SolanaConfig,
         client: &Arc<SolanaClient>,
         event_parser: &EventParser,
-        event_broadcaster: &broadcast::Sender<PinpetEvent>,
+        event_queue: &EventQueue,
         connection_state: &Arc<tokio::sync::RwLock<ConnectionState>>,
         should_stop: &Arc<tokio::sync::RwLock<bool>>,
         processed_signatures: &Arc<tokio::sync::RwLock<HashSet<String>>>,
@@ -409,7 +536,7 @@ impl SolanaEventListener {
         });
 
         // 消息处理循环 / Message handling loop
-        let event_broadcaster_clone = event_broadcaster.clone();
+        let event_queue_clone = event_queue.clone();
         let event_parser_clone = event_parser.clone();
         let client_clone = Arc::clone(client);
         let processed_signatures_clone = Arc::clone(processed_signatures);
@@ -433,7 +560,7 @@ impl SolanaEventListener {
                     if let Err(e) = Self::handle_websocket_message(
                         &text,
                         &event_parser_clone,
-                        &event_broadcaster_clone,
+                        &event_queue_clone,
                         &client_clone,
                         &processed_signatures_clone,
                         config,
@@ -479,7 +606,7 @@ impl SolanaEventListener {
     async fn handle_websocket_message(
         message: &str,
         event_parser: &EventParser,
-        event_broadcaster: &broadcast::Sender<PinpetEvent>,
+        event_queue: &EventQueue,
         client: &Arc<SolanaClient>,
         processed_signatures: &Arc<tokio::sync::RwLock<HashSet<String>>>,
         config: &SolanaConfig,
@@ -620,17 +747,17 @@ impl SolanaEventListener {
                             }
                         }
 
-                        // 广播事件 / Broadcast events
+                        // 入队事件, 队列满时背压 / Enqueue events, applying backpressure when full
                         if !all_events.is_empty() {
                             info!(
-                                "✅ 广播{}个事件，交易 / Broadcasting {} events for transaction {}",
+                                "✅ 入队{}个事件，交易 / Enqueueing {} events for transaction {}",
                                 all_events.len(), all_events.len(),
                                 signature
                             );
 
                             for event in all_events {
-                                if let Err(e) = event_broadcaster.send(event) {
-                                    error!("广播事件失败 / Failed to broadcast event: {}", e);
+                                if let Err(e) = event_queue.send(event).await {
+                                    error!("事件入队失败 / Failed to enqueue event: {}", e);
                                 }
                             }
                         }
@@ -646,23 +773,11 @@ impl SolanaEventListener {
         events.iter().any(|e| Self::events_are_equal(e, new_event))
     }
 
+    /// 按 (签名, 事件序号) 判断是否同一事件; 一笔交易可包含多条同类型的交易指令, 只比较签名会丢掉合法事件
+    /// Same event iff (signature, event ordinal) match; one transaction may hold several trade instructions of the
+    /// same type, so comparing signatures alone would drop legitimate events
     fn events_are_equal(e1: &PinpetEvent, e2: &PinpetEvent) -> bool {
-        use PinpetEvent::*;
-        match (e1, e2) {
-            (TokenCreated(a), TokenCreated(b)) => a.signature == b.signature,
-            (BuySell(a), BuySell(b)) => a.signature == b.signature,
-            (LongShort(a), LongShort(b)) => {
-                a.signature == b.signature && a.order_id == b.order_id
-            }
-            (PartialClose(a), PartialClose(b)) => {
-                a.signature == b.signature && a.order_id == b.order_id
-            }
-            (FullClose(a), FullClose(b)) => {
-                a.signature == b.signature && a.order_id == b.order_id
-            }
-            (MilestoneDiscount(a), MilestoneDiscount(b)) => a.signature == b.signature,
-            _ => false,
-        }
+        e1.signature() == e2.signature() && e1.event_index() == e2.event_index()
     }
 
     #[allow(dead_code)]
@@ -760,6 +875,16 @@ impl EventListenerManager {
         Ok(())
     }
 
+    /// 获取入队句柄, 未初始化时为空 / Get the ingest queue handle, None before initialization
+    pub fn event_queue(&self) -> Option<EventQueue> {
+        self.listener.as_ref().map(|l| l.event_queue())
+    }
+
+    /// 获取解码失败诊断, 未初始化时为空 / Get decode failure diagnostics, None before initialization
+    pub fn decode_diagnostics(&self) -> Option<Arc<DecodeDiagnostics>> {
+        self.listener.as_ref().map(|l| l.decode_diagnostics())
+    }
+
     pub async fn start(&mut self) -> anyhow::Result<()> {
         if let Some(listener) = &mut self.listener {
             listener.start().await
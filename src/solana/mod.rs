@@ -1,13 +1,22 @@
 // Solana模块 / Solana module
 
 pub mod client;
+pub mod decode_diagnostics;
+pub mod dry_run;
 pub mod events;
+pub mod finality;
 pub mod listener;
+pub mod reprocess;
 pub mod storage_handler;
 
 pub use client::SolanaClient;
+pub use decode_diagnostics::{DecodeDiagnostics, DecodeErrors, DecodeFailure};
+pub use dry_run::{DryRunEventHandler, DryRunMetrics};
 pub use events::{EventParser, PinpetEvent};
+pub use finality::{FinalityGate, FinalityMetrics, SlotRollback};
 pub use listener::{
-    DefaultEventHandler, EventHandler, EventListener, EventListenerManager, SolanaEventListener,
+    DefaultEventHandler, EventHandler, EventListener, EventListenerManager, EventQueue,
+    EventQueueMetrics, SolanaEventListener,
 };
-pub use storage_handler::{StorageEventHandler, process_transaction_events, process_buy_sell_with_liquidations};
\ No newline at end of file
+pub use reprocess::{ReprocessReport, Reprocessor};
+pub use storage_handler::{EventObserver, EventObservers, PersistedEventCounts, StorageEventHandler, process_transaction_events, process_buy_sell_with_liquidations};
\ No newline at end of file
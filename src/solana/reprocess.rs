@@ -0,0 +1,233 @@
+// 历史交易重处理 - 修复解析错误后按 slot 区间重新拉取交易并重放事件
+// Historical transaction reprocessing - refetch transactions in a slot window and replay their events after a parsing fix
+//
+// 与启动时的补数据不同, 这里针对任意历史区间按需执行
+// Unlike startup backfill, this targets an arbitrary historical window on demand
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::{debug, info, warn};
+use utoipa::ToSchema;
+
+use super::client::{SignatureInfo, SolanaClient};
+use super::events::{EventParser, PinpetEvent};
+use super::listener::EventHandler;
+
+/// getSignaturesForAddress 单页数量上限 / Page size limit of getSignaturesForAddress
+const SIGNATURE_PAGE_SIZE: usize = 1000;
+
+/// 单次重处理最多翻页数, 超出时要求缩小区间 / Page cap per reprocess; a larger window must be split
+const MAX_SIGNATURE_PAGES: usize = 200;
+
+/// 重处理结果 / Reprocess report
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+#[schema(title = "ReprocessReport", description = "历史交易重处理结果 / Historical transaction reprocess report")]
+pub struct ReprocessReport {
+    /// 起始 slot (含) / Start slot (inclusive)
+    #[schema(example = 250000000)]
+    pub from_slot: u64,
+    /// 结束 slot (含) / End slot (inclusive)
+    #[schema(example = 250001000)]
+    pub to_slot: u64,
+    /// 区间内的交易数 / Transactions found in the window
+    #[schema(example = 42)]
+    pub transactions: u64,
+    /// 跳过的失败交易数 / Failed transactions skipped
+    #[schema(example = 3)]
+    pub skipped_failed_transactions: u64,
+    /// 获取详情失败的交易数 / Transactions whose details could not be fetched
+    #[schema(example = 0)]
+    pub fetch_errors: u64,
+    /// 重新应用的事件数 / Events re-applied
+    #[schema(example = 57)]
+    pub events_applied: u64,
+    /// 处理失败的事件数 / Events the handler failed on
+    #[schema(example = 0)]
+    pub events_failed: u64,
+    /// 从链上重新读取的曲线状态数 (覆盖模式) / Curve states re-read from chain (overwrite mode)
+    #[schema(example = 2)]
+    pub curves_resynced: u64,
+    /// 按事件重新统计后发生变化的成交计数 (覆盖模式) / Trade counts changed by the recount from events (overwrite mode)
+    #[schema(example = 1)]
+    pub trade_counts_reset: u64,
+    /// 与事件回放不一致、已标记重建的订单簿 `{mint}:{direction}` (覆盖模式)
+    /// Order books `{mint}:{direction}` that disagree with the event replay and were marked for a rebuild (overwrite mode)
+    pub orderbooks_marked_for_rebuild: Vec<String>,
+}
+
+/// 历史交易重处理器 / Historical transaction reprocessor
+pub struct Reprocessor<'a> {
+    client: &'a SolanaClient,
+    parser: &'a EventParser,
+    handler: &'a dyn EventHandler,
+    program_id: &'a str,
+    process_failed_transactions: bool,
+}
+
+impl<'a> Reprocessor<'a> {
+    pub fn new(
+        client: &'a SolanaClient,
+        parser: &'a EventParser,
+        handler: &'a dyn EventHandler,
+        program_id: &'a str,
+        process_failed_transactions: bool,
+    ) -> Self {
+        Self {
+            client,
+            parser,
+            handler,
+            program_id,
+            process_failed_transactions,
+        }
+    }
+
+    /// 重处理 [from_slot, to_slot] 区间内的程序交易, 按 slot 从旧到新重放
+    /// Reprocess program transactions in [from_slot, to_slot], replaying from oldest to newest
+    pub async fn run(&self, from_slot: u64, to_slot: u64) -> anyhow::Result<ReprocessReport> {
+        let mut report = ReprocessReport {
+            from_slot,
+            to_slot,
+            ..Default::default()
+        };
+
+        let signatures = self.signatures_in_range(from_slot, to_slot).await?;
+        report.transactions = signatures.len() as u64;
+        info!(
+            "♻️ 开始重处理 / Starting reprocess: slots {}..={}, {} 笔交易 / transactions",
+            from_slot, to_slot, report.transactions
+        );
+
+        for info in signatures {
+            if info.err.is_some() && !self.process_failed_transactions {
+                report.skipped_failed_transactions += 1;
+                continue;
+            }
+
+            let events = match self.fetch_events(&info).await {
+                Ok(events) => events,
+                Err(e) => {
+                    warn!("获取交易失败, 跳过 / Failed to fetch transaction, skipping: {} ({})", info.signature, e);
+                    report.fetch_errors += 1;
+                    continue;
+                }
+            };
+
+            for event in events {
+                match self.handler.handle_event(event).await {
+                    Ok(()) => report.events_applied += 1,
+                    Err(e) => {
+                        warn!("重处理事件失败 / Failed to reprocess event: {} ({})", info.signature, e);
+                        report.events_failed += 1;
+                    }
+                }
+            }
+        }
+
+        info!(
+            "✅ 重处理完成 / Reprocess finished: {} 个事件已重新应用, {} 个失败 / {} events re-applied, {} failed",
+            report.events_applied, report.events_failed, report.events_applied, report.events_failed
+        );
+        Ok(report)
+    }
+
+    /// 从区间结束后的第一个区块开始向前翻页, 收集区间内的签名 (按 slot 升序), 最多 MAX_SIGNATURE_PAGES 页
+    /// Page backwards from the first block after the window, collecting the signatures inside it (ascending by slot),
+    /// at most MAX_SIGNATURE_PAGES pages
+    async fn signatures_in_range(&self, from_slot: u64, to_slot: u64) -> anyhow::Result<Vec<SignatureInfo>> {
+        let mut in_range = Vec::new();
+        let mut before = self.anchor_after(to_slot).await;
+
+        for page_number in 1.. {
+            if page_number > MAX_SIGNATURE_PAGES {
+                anyhow::bail!(
+                    "区间签名超过 {} 页, 请缩小区间 / Window spans more than {} signature pages, split it",
+                    MAX_SIGNATURE_PAGES,
+                    MAX_SIGNATURE_PAGES
+                );
+            }
+
+            let page = self
+                .client
+                .get_signatures_for_address(self.program_id, before.as_deref(), SIGNATURE_PAGE_SIZE)
+                .await?;
+            let Some(last) = page.last() else {
+                break;
+            };
+            let reached_start = last.slot < from_slot;
+            before = Some(last.signature.clone());
+            debug!("签名分页 / Signature page: {} 条, 最旧 slot / oldest slot {}", page.len(), last.slot);
+
+            in_range.extend(
+                page.into_iter()
+                    .filter(|info| info.slot >= from_slot && info.slot <= to_slot),
+            );
+            if reached_start {
+                break;
+            }
+        }
+
+        in_range.reverse();
+        Ok(in_range)
+    }
+
+    /// 区间结束后第一个区块的首个签名, 作为翻页起点 (getBlocksWithLimit + getBlock); 区间已到链顶或查询失败时从最新签名开始
+    /// First signature of the first block after the window, used as the paging anchor (getBlocksWithLimit + getBlock);
+    /// starts from the newest signature when the window reaches the tip or the lookup fails
+    async fn anchor_after(&self, to_slot: u64) -> Option<String> {
+        let anchor = async {
+            let Some(&slot) = self.client.get_blocks_with_limit(to_slot.saturating_add(1), 1).await?.first() else {
+                return anyhow::Ok(None);
+            };
+            Ok(self.client.get_block_signatures(slot).await?.into_iter().next())
+        };
+        match anchor.await {
+            Ok(anchor) => anchor,
+            Err(e) => {
+                warn!("⚠️ 无法定位区间结束区块, 从最新签名翻页 / Cannot locate the block after the window, paging from the newest signature: {}", e);
+                None
+            }
+        }
+    }
+
+    /// 获取交易日志并解析事件, 时间戳取区块时间 / Fetch transaction logs and parse events, stamped with the block time
+    async fn fetch_events(&self, info: &SignatureInfo) -> anyhow::Result<Vec<PinpetEvent>> {
+        let tx = self.client.get_transaction_with_logs(&info.signature).await?;
+        let logs: Vec<String> = tx
+            .pointer("/meta/logMessages")
+            .and_then(Value::as_array)
+            .map(|logs| logs.iter().filter_map(|l| l.as_str()).map(str::to_string).collect())
+            .unwrap_or_default();
+
+        let mut events = self
+            .parser
+            .parse_events_with_call_stack(&logs, &info.signature, info.slot)?;
+
+        let block_time = tx
+            .get("blockTime")
+            .and_then(Value::as_i64)
+            .or(info.block_time)
+            .and_then(|t| DateTime::<Utc>::from_timestamp(t, 0));
+        if let Some(block_time) = block_time {
+            for event in &mut events {
+                set_timestamp(event, block_time);
+            }
+        }
+
+        Ok(events)
+    }
+}
+
+/// 解析器以当前时间标记事件, 历史事件改用区块时间 / The parser stamps events with the current time; historical events use the block time instead
+fn set_timestamp(event: &mut PinpetEvent, timestamp: DateTime<Utc>) {
+    match event {
+        PinpetEvent::TokenCreated(e) => e.timestamp = timestamp,
+        PinpetEvent::BuySell(e) => e.timestamp = timestamp,
+        PinpetEvent::LongShort(e) => e.timestamp = timestamp,
+        PinpetEvent::FullClose(e) => e.timestamp = timestamp,
+        PinpetEvent::PartialClose(e) => e.timestamp = timestamp,
+        PinpetEvent::MilestoneDiscount(e) => e.timestamp = timestamp,
+        PinpetEvent::AddMargin(e) => e.timestamp = timestamp,
+        PinpetEvent::ExtendOrder(e) => e.timestamp = timestamp,
+    }
+}
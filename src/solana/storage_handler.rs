@@ -1,17 +1,123 @@
 // 存储事件处理器 - 将事件存储到RocksDB / Storage event handler - store events to RocksDB
 use async_trait::async_trait;
-use std::sync::Arc;
-use tracing::{info, error};
-use crate::db::{EventStorage, TokenStorage, OrderBookStorage};
-use crate::orderbook::MarginOrder;
-use super::events::PinpetEvent;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::{Arc, Mutex};
+use chrono::{DateTime, Utc};
+use tracing::{debug, info, error, warn};
+use crate::db::{CurveStorage, CurveUpdate, EventStorage, TokenStorage, OrderBookStorage, LiquidationRecord, PnlSettlement};
+use crate::orderbook::{replay_orderbook_at, Direction, MarginOrder, OrderBookDBManager, OrderBookError, MAX_REPLAY_EVENTS};
+use crate::webhook::{self, WebhookDispatcher};
+use super::client::SolanaClient;
+use super::events::{PinpetEvent, TokenCreatedEvent};
 use super::listener::EventHandler;
+use super::reprocess::ReprocessReport;
+
+/// 事件观察者, 在事件持久化后回调 (缓存失效、指标等)
+/// Event observer, called back after an event is persisted (cache invalidation, metrics, ...)
+///
+/// 回调在事件处理路径上同步执行, 实现必须轻量且不阻塞
+/// Callbacks run synchronously on the event processing path, implementations must be cheap and non-blocking
+pub trait EventObserver: Send + Sync {
+    /// 事件持久化后 / After an event is persisted
+    fn on_event(&self, _event: &PinpetEvent) {}
+
+    /// 新Token首次写入后 (重放和重处理不触发) / After a new token is first written (not on replays or reprocessing)
+    fn on_new_token(&self, _event: &TokenCreatedEvent) {}
+}
+
+/// 事件观察者注册表, 按注册顺序分发 / Event observer registry, fans out in registration order
+#[derive(Default, Clone)]
+pub struct EventObservers {
+    observers: Vec<Arc<dyn EventObserver>>,
+}
+
+impl EventObservers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 注册观察者 / Register an observer
+    pub fn register(&mut self, observer: Arc<dyn EventObserver>) {
+        self.observers.push(observer);
+    }
+
+    pub fn len(&self) -> usize {
+        self.observers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.observers.is_empty()
+    }
+
+    /// 通知所有观察者 / Notify all observers
+    pub fn notify(&self, event: &PinpetEvent) {
+        for observer in &self.observers {
+            observer.on_event(event);
+        }
+    }
+
+    /// 通知所有观察者有新Token / Notify all observers of a new token
+    pub fn notify_new_token(&self, event: &TokenCreatedEvent) {
+        for observer in &self.observers {
+            observer.on_new_token(event);
+        }
+    }
+}
+
+/// 按事件类型统计已持久化的事件数, 通过 /metrics 暴露
+/// Counts persisted events per event type, exposed via /metrics
+#[derive(Debug, Default)]
+pub struct PersistedEventCounts {
+    counts: Mutex<BTreeMap<&'static str, u64>>,
+}
+
+impl PersistedEventCounts {
+    /// 各事件类型的累计数 / Running count per event type
+    pub fn snapshot(&self) -> BTreeMap<String, u64> {
+        self.counts
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(|(name, count)| (name.to_string(), *count))
+            .collect()
+    }
+}
+
+impl EventObserver for PersistedEventCounts {
+    fn on_event(&self, event: &PinpetEvent) {
+        *self
+            .counts
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(event.type_name())
+            .or_insert(0) += 1;
+    }
+}
 
 /// 存储事件处理器 - 将接收到的事件存储到RocksDB / Storage event handler - stores received events to RocksDB
 pub struct StorageEventHandler {
     event_storage: Arc<EventStorage>,
     token_storage: Arc<TokenStorage>,
     orderbook_storage: Arc<OrderBookStorage>,
+    webhook: Option<Arc<WebhookDispatcher>>,
+    curve_storage: Option<Arc<CurveStorage>>,
+    solana_client: Option<Arc<SolanaClient>>,
+    observers: Arc<EventObservers>,
+    /// 重处理模式: 签名 -> 处理前是否已存储 / Reprocess mode: signature -> whether it was stored before this run
+    reprocessed: Option<Mutex<HashMap<String, bool>>>,
+    /// 重处理模式下涉及的 mint, 结束后重新同步 / Mints touched in reprocess mode, resynced afterwards
+    reprocessed_mints: Mutex<BTreeSet<String>>,
+}
+
+/// 触发清算的事件信息 / Info about the event that triggered liquidations
+struct LiquidationTrigger<'a> {
+    event_type: &'static str,
+    mint: &'a str,
+    direction: Direction,
+    signature: &'a str,
+    slot: u64,
+    timestamp: DateTime<Utc>,
+    price: u128,
 }
 
 impl StorageEventHandler {
@@ -25,36 +131,88 @@ impl StorageEventHandler {
             event_storage,
             token_storage,
             orderbook_storage,
+            webhook: None,
+            curve_storage: None,
+            solana_client: None,
+            observers: Arc::new(EventObservers::new()),
+            reprocessed: None,
+            reprocessed_mints: Mutex::new(BTreeSet::new()),
         }
     }
+
+    /// 处理完成后推送 Webhook / Push webhooks after events are processed
+    pub fn with_webhook(mut self, webhook: Arc<WebhookDispatcher>) -> Self {
+        self.webhook = Some(webhook);
+        self
+    }
+
+    /// 维护每个 mint 的曲线状态 / Maintain per-mint curve state
+    pub fn with_curve_storage(mut self, curve_storage: Arc<CurveStorage>) -> Self {
+        self.curve_storage = Some(curve_storage);
+        self
+    }
+
+    /// 用于从链上读取曲线账户补齐状态 / Used to read curve accounts from chain to fill in state
+    pub fn with_solana_client(mut self, solana_client: Arc<SolanaClient>) -> Self {
+        self.solana_client = Some(solana_client);
+        self
+    }
+
+    /// 事件持久化及新Token首次写入后通知观察者 / Notify observers after events are persisted and new tokens first written
+    pub fn with_observers(mut self, observers: Arc<EventObservers>) -> Self {
+        self.observers = observers;
+        self
+    }
+
+    /// 重处理模式: 允许覆盖已存储的事件和Token, 用于修正解析错误后重放历史交易
+    /// Reprocess mode: allows overwriting stored events and tokens, for replaying history after a parsing fix
+    ///
+    /// 已存储过的交易只替换事件记录和Token详情; 订单簿、成交计数、曲线状态和 Webhook 在首次处理时已生效, 不再重复应用;
+    /// 结束后用 `resync_reprocessed_mints` 修正涉及 mint 的曲线状态、成交计数, 并标记与事件回放不一致的订单簿
+    /// Transactions stored before only have their event records and token details replaced; orderbook, trade counts,
+    /// curve state and webhooks already took effect the first time and are not applied again. Afterwards
+    /// `resync_reprocessed_mints` corrects the curve state and trade counts of the touched mints and marks order books
+    /// that disagree with a replay of their events
+    pub fn with_overwrite(mut self) -> Self {
+        self.reprocessed = Some(Mutex::new(HashMap::new()));
+        self
+    }
 }
 
 #[async_trait]
 impl EventHandler for StorageEventHandler {
     async fn handle_event(&self, event: PinpetEvent) -> anyhow::Result<()> {
         // 提取签名和事件基本信息 / Extract signature and basic event info
-        let signature = match &event {
-            PinpetEvent::TokenCreated(e) => e.signature.clone(),
-            PinpetEvent::BuySell(e) => e.signature.clone(),
-            PinpetEvent::LongShort(e) => e.signature.clone(),
-            PinpetEvent::FullClose(e) => e.signature.clone(),
-            PinpetEvent::PartialClose(e) => e.signature.clone(),
-            PinpetEvent::MilestoneDiscount(e) => e.signature.clone(),
-        };
+        let signature = event.signature().to_string();
 
         // 获取事件类型 / Get event type
-        let event_type = match &event {
-            PinpetEvent::TokenCreated(_) => "TokenCreated",
-            PinpetEvent::BuySell(_) => "BuySell",
-            PinpetEvent::LongShort(_) => "LongShort",
-            PinpetEvent::FullClose(_) => "FullClose",
-            PinpetEvent::PartialClose(_) => "PartialClose",
-            PinpetEvent::MilestoneDiscount(_) => "MilestoneDiscount",
-        };
+        let event_type = event.type_name();
 
         info!("📝 存储事件 / Storing event: 类型/type={}, 签名/signature={}",
               event_type, &signature[..8]);
 
+        if self.reprocessed.is_some() {
+            self.reprocessed_mints
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(event.mint().to_string());
+        }
+        if self.was_stored_before(&signature).await? {
+            return self.replace_stored_event(&signature, event).await;
+        }
+
+        // 按 (签名, 事件序号) 去重: 重放的事件不再重复应用订单簿和成交计数, 同一交易的其他事件照常处理
+        // Dedup on (signature, event ordinal): a replayed event is not applied to the order book and trade counts again,
+        // while the other events of the same transaction are still processed
+        if self.event_storage.contains_event(&event)? {
+            debug!(
+                "⏭️ 事件已存储, 跳过 / Event already stored, skipping: signature={}, event_index={}",
+                &signature[..8],
+                event.event_index()
+            );
+            return Ok(());
+        }
+
         // 如果是 TokenCreatedEvent，同时存储到 TokenStorage / If TokenCreatedEvent, also store to TokenStorage
         if let PinpetEvent::TokenCreated(ref tc_event) = event {
             if let Err(e) = self.store_token_created(tc_event).await {
@@ -63,29 +221,33 @@ impl EventHandler for StorageEventHandler {
             }
         }
 
-        // 更新Token的latest_price（所有带latest_price的事件）/ Update token's latest_price (all events with latest_price)
+        // 记录Token成交（所有带latest_price的交易事件）/ Record token trades (all trade events with latest_price)
         match &event {
-            PinpetEvent::TokenCreated(_e) => {
-                // TokenCreated已经在store_token_created中设置了初始价格 / Initial price already set in store_token_created
+            PinpetEvent::TokenCreated(e) => {
+                // TokenCreated已经在store_token_created中设置了初始价格, 这里只写入规范价格
+                // Initial price already set in store_token_created, only the canonical price is written here
+                if let Err(err) = self.token_storage.update_price(&e.mint_account, e.latest_price, e.slot, e.timestamp.timestamp()) {
+                    error!("❌ 更新规范价格失败 (TokenCreated) / Failed to update canonical price (TokenCreated): {}", err);
+                }
             }
             PinpetEvent::BuySell(e) => {
-                if let Err(err) = self.token_storage.update_token_price(&e.mint_account, e.latest_price) {
-                    error!("❌ 更新Token价格失败 (BuySell) / Failed to update token price (BuySell): {}", err);
+                if let Err(err) = self.token_storage.record_trade(&e.mint_account, e.latest_price, e.slot, e.timestamp.timestamp()) {
+                    error!("❌ 记录Token成交失败 (BuySell) / Failed to record token trade (BuySell): {}", err);
                 }
             }
             PinpetEvent::LongShort(e) => {
-                if let Err(err) = self.token_storage.update_token_price(&e.mint_account, e.latest_price) {
-                    error!("❌ 更新Token价格失败 (LongShort) / Failed to update token price (LongShort): {}", err);
+                if let Err(err) = self.token_storage.record_trade(&e.mint_account, e.latest_price, e.slot, e.timestamp.timestamp()) {
+                    error!("❌ 记录Token成交失败 (LongShort) / Failed to record token trade (LongShort): {}", err);
                 }
             }
             PinpetEvent::FullClose(e) => {
-                if let Err(err) = self.token_storage.update_token_price(&e.mint_account, e.latest_price) {
-                    error!("❌ 更新Token价格失败 (FullClose) / Failed to update token price (FullClose): {}", err);
+                if let Err(err) = self.token_storage.record_trade(&e.mint_account, e.latest_price, e.slot, e.timestamp.timestamp()) {
+                    error!("❌ 记录Token成交失败 (FullClose) / Failed to record token trade (FullClose): {}", err);
                 }
             }
             PinpetEvent::PartialClose(e) => {
-                if let Err(err) = self.token_storage.update_token_price(&e.mint_account, e.latest_price) {
-                    error!("❌ 更新Token价格失败 (PartialClose) / Failed to update token price (PartialClose): {}", err);
+                if let Err(err) = self.token_storage.record_trade(&e.mint_account, e.latest_price, e.slot, e.timestamp.timestamp()) {
+                    error!("❌ 记录Token成交失败 (PartialClose) / Failed to record token trade (PartialClose): {}", err);
                 }
             }
             PinpetEvent::MilestoneDiscount(e) => {
@@ -99,48 +261,111 @@ impl EventHandler for StorageEventHandler {
                     error!("❌ 更新Token费率失败 (MilestoneDiscount) / Failed to update token fees (MilestoneDiscount): {}", err);
                 }
             }
+            // 追加保证金和订单延期不成交, 价格不变 / Adding margin and extending orders are not trades and leave the price unchanged
+            PinpetEvent::AddMargin(_) | PinpetEvent::ExtendOrder(_) => {}
+        }
+
+        // 更新曲线状态, 需在订单簿删除订单之前读取借款数量 / Update curve state, must read borrow amounts before orders leave the orderbook
+        if let Some(ref curve_storage) = self.curve_storage {
+            if let Err(e) = self.update_curve_state(curve_storage, &event).await {
+                error!("❌ 更新曲线状态失败 / Failed to update curve state: {}", e);
+            }
         }
 
+        // 结清订单的已实现盈亏, 需在订单簿删除订单之前读取 / Realized PnL of settled orders, must be read before orders leave the orderbook
+        let settlements = match self.pnl_settlements(&event) {
+            Ok(settlements) => settlements,
+            Err(e) => {
+                error!("❌ 读取结清订单失败 / Failed to read settled orders: {}", e);
+                Vec::new()
+            }
+        };
+
+        // 本事件清算的订单 / Orders liquidated by this event
+        let mut liquidations = Vec::new();
+
         // 如果是 LongShortEvent，插入到 OrderBook / If LongShortEvent, insert to OrderBook
         if let PinpetEvent::LongShort(ref ls_event) = event {
-            if let Err(e) = self.handle_long_short_event(ls_event) {
-                error!("❌ 处理 LongShortEvent 失败 / Failed to handle LongShortEvent: {}", e);
-                // 继续存储事件，不因 OrderBook 失败而中断 / Continue storing event, don't fail due to OrderBook error
+            match self.handle_long_short_event(ls_event) {
+                Ok(records) => liquidations = records,
+                Err(e) => {
+                    error!("❌ 处理 LongShortEvent 失败 / Failed to handle LongShortEvent: {}", e);
+                    // 继续存储事件，不因 OrderBook 失败而中断 / Continue storing event, don't fail due to OrderBook error
+                }
             }
         }
 
         // 如果是 BuySellEvent，处理清算 / If BuySellEvent, handle liquidations
         if let PinpetEvent::BuySell(ref bs_event) = event {
-            if let Err(e) = self.handle_buy_sell_event(bs_event) {
-                error!("❌ 处理 BuySellEvent 清算失败 / Failed to handle BuySellEvent liquidations: {}", e);
-                // 继续存储事件，不因 OrderBook 失败而中断 / Continue storing event, don't fail due to OrderBook error
+            match self.handle_buy_sell_event(bs_event) {
+                Ok(records) => liquidations = records,
+                Err(e) => {
+                    error!("❌ 处理 BuySellEvent 清算失败 / Failed to handle BuySellEvent liquidations: {}", e);
+                    // 继续存储事件，不因 OrderBook 失败而中断 / Continue storing event, don't fail due to OrderBook error
+                }
             }
         }
 
         // 如果是 FullCloseEvent，处理清算 / If FullCloseEvent, handle liquidations
         if let PinpetEvent::FullClose(ref fc_event) = event {
-            if let Err(e) = self.handle_full_close_event(fc_event) {
-                error!("❌ 处理 FullCloseEvent 清算失败 / Failed to handle FullCloseEvent liquidations: {}", e);
-                // 继续存储事件，不因 OrderBook 失败而中断 / Continue storing event, don't fail due to OrderBook error
+            match self.handle_full_close_event(fc_event) {
+                Ok(records) => liquidations = records,
+                Err(e) => {
+                    error!("❌ 处理 FullCloseEvent 清算失败 / Failed to handle FullCloseEvent liquidations: {}", e);
+                    // 继续存储事件，不因 OrderBook 失败而中断 / Continue storing event, don't fail due to OrderBook error
+                }
             }
         }
 
         // 如果是 PartialCloseEvent，处理更新和清算 / If PartialCloseEvent, handle update and liquidations
         if let PinpetEvent::PartialClose(ref pc_event) = event {
-            if let Err(e) = self.handle_partial_close_event(pc_event) {
-                error!("❌ 处理 PartialCloseEvent 更新和清算失败 / Failed to handle PartialCloseEvent update and liquidations: {}", e);
+            match self.handle_partial_close_event(pc_event) {
+                Ok(records) => liquidations = records,
+                Err(e) => {
+                    error!("❌ 处理 PartialCloseEvent 更新和清算失败 / Failed to handle PartialCloseEvent update and liquidations: {}", e);
+                    // 继续存储事件，不因 OrderBook 失败而中断 / Continue storing event, don't fail due to OrderBook error
+                }
+            }
+        }
+
+        // 如果是 AddMarginEvent，更新订单 / If AddMarginEvent, update the order
+        if let PinpetEvent::AddMargin(ref am_event) = event {
+            if let Err(e) = self.handle_add_margin_event(am_event) {
+                error!("❌ 处理 AddMarginEvent 失败 / Failed to handle AddMarginEvent: {}", e);
+                // 继续存储事件，不因 OrderBook 失败而中断 / Continue storing event, don't fail due to OrderBook error
+            }
+        }
+
+        // 如果是 ExtendOrderEvent，更新到期时间 / If ExtendOrderEvent, update the expiry
+        if let PinpetEvent::ExtendOrder(ref eo_event) = event {
+            if let Err(e) = self.handle_extend_order_event(eo_event) {
+                error!("❌ 处理 ExtendOrderEvent 失败 / Failed to handle ExtendOrderEvent: {}", e);
                 // 继续存储事件，不因 OrderBook 失败而中断 / Continue storing event, don't fail due to OrderBook error
             }
         }
 
+        // 存储清算记录 / Store liquidation records
+        if let Err(e) = self.event_storage.store_liquidations(&liquidations) {
+            error!("❌ 存储清算记录失败 / Failed to store liquidation records: {}", e);
+        }
+
+        // 入队 Webhook 推送, 不等待投递 / Enqueue webhook pushes without waiting for delivery
+        if let Some(ref webhook) = self.webhook {
+            webhook.notify(webhook::event_type_name(&event), &event);
+            for record in &liquidations {
+                webhook.notify("liquidation", record);
+            }
+        }
+
         // 目前我们一次只处理一个事件，但store_events支持批量存储
         // Currently we process one event at a time, but store_events supports batch storage
-        let events = vec![event];
+        let events = vec![event.clone()];
 
-        // 存储事件到数据库 / Store event to database
-        match self.event_storage.store_events(&signature, events).await {
+        // 存储事件到数据库, 结清订单的用户盈亏在同一批次中累加 / Store event to database, settled orders' user PnL is added in the same batch
+        match self.event_storage.store_events_with_settlements(&signature, events, &settlements).await {
             Ok(_) => {
                 info!("✅ 事件存储成功 / Event stored successfully: {}", &signature[..8]);
+                self.observers.notify(&event);
                 Ok(())
             }
             Err(e) => {
@@ -166,27 +391,428 @@ impl StorageEventHandler {
             event.mint_account, event.symbol
         );
 
-        // 异步保存token（包括IPFS元数据获取）/ Save token asynchronously (including IPFS metadata fetch)
-        self.token_storage.save_token_from_event(event).await?;
+        // 重处理模式覆盖已有Token / Reprocess mode overwrites the existing token
+        if self.reprocessed.is_some() {
+            let detail = self.token_storage.build_token_detail(event).await;
+            self.token_storage.overwrite(detail, event.slot)?;
+            info!(
+                "♻️ TokenCreatedEvent 已重新写入 TokenStorage / TokenCreatedEvent rewritten to TokenStorage: mint={}",
+                event.mint_account
+            );
+            return Ok(());
+        }
 
-        info!(
-            "✅ TokenCreatedEvent 已存储到 TokenStorage / TokenCreatedEvent stored to TokenStorage: mint={}",
-            event.mint_account
-        );
+        // 重放的旧事件直接跳过, 避免重复获取IPFS元数据
+        // Skip replayed stale events up front to avoid refetching IPFS metadata
+        let seen_slot = self.token_storage.seen_slot(&event.mint_account)?;
+        if let Some(seen_slot) = seen_slot {
+            if event.slot <= seen_slot {
+                debug!(
+                    "⏭️ TokenCreatedEvent 已处理过, 跳过 / TokenCreatedEvent already seen, skipping: mint={}, slot={}, seen_slot={}",
+                    event.mint_account, event.slot, seen_slot
+                );
+                return Ok(());
+            }
+        }
+
+        // 构建token（包括IPFS元数据获取）并幂等写入 / Build token (including IPFS metadata fetch) and upsert idempotently
+        let detail = self.token_storage.build_token_detail(event).await;
+        if self.token_storage.upsert(detail, event.slot)? {
+            info!(
+                "✅ TokenCreatedEvent 已存储到 TokenStorage / TokenCreatedEvent stored to TokenStorage: mint={}",
+                event.mint_account
+            );
+            if seen_slot.is_none() && self.reprocessed.is_none() {
+                self.observers.notify_new_token(event);
+            }
+        }
 
         Ok(())
     }
 
+    /// 重处理模式下, 该交易在本次重处理之前是否已存储 (首次查询后缓存, 同一交易的后续事件结果一致)
+    /// In reprocess mode, whether the transaction was stored before this run (cached after the first lookup so
+    /// every event of the same transaction gets the same answer)
+    async fn was_stored_before(&self, signature: &str) -> anyhow::Result<bool> {
+        let Some(ref reprocessed) = self.reprocessed else {
+            return Ok(false);
+        };
+        if let Some(stored) = reprocessed.lock().unwrap_or_else(|e| e.into_inner()).get(signature) {
+            return Ok(*stored);
+        }
+
+        let stored = !self.event_storage.query_by_signature(signature).await?.is_empty();
+        Ok(*reprocessed
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(signature.to_string())
+            .or_insert(stored))
+    }
+
+    /// 重处理结束后修正涉及 mint 的派生状态, 结果写入报告
+    /// Correct the derived state of the mints touched by a reprocess, recording the outcome in the report
+    ///
+    /// - 曲线状态从链上曲线账户重新读取 / Curve state is re-read from the on-chain curve account
+    /// - 成交笔数按已存储的事件重新统计 / Trade counts are recounted from the stored events
+    /// - 订单簿与事件回放结果比较, 不一致时写入重建标记 (订单簿无法在线安全改写)
+    ///   Order books are compared with a replay of the events and marked for a rebuild when they disagree (a live
+    ///   book cannot be rewritten safely)
+    pub async fn resync_reprocessed_mints(&self, slot: u64, report: &mut ReprocessReport) {
+        let mints = std::mem::take(&mut *self.reprocessed_mints.lock().unwrap_or_else(|e| e.into_inner()));
+        for mint in mints {
+            if let Some(ref curve_storage) = self.curve_storage {
+                if self.seed_curve_state(curve_storage, &mint, None).await {
+                    report.curves_resynced += 1;
+                }
+            }
+
+            match self
+                .event_storage
+                .count_mint_trades(&mint)
+                .and_then(|count| self.token_storage.reset_trade_count(&mint, count).map(|previous| previous != count))
+            {
+                Ok(true) => report.trade_counts_reset += 1,
+                Ok(false) => {}
+                Err(e) => warn!("⚠️ 重新统计成交笔数失败 / Failed to recount trades: mint={}, {}", mint, e),
+            }
+
+            let events = match self.event_storage.query_by_mint(&mint, Some(MAX_REPLAY_EVENTS + 1)).await {
+                Ok(events) => events,
+                Err(e) => {
+                    warn!("⚠️ 读取事件失败, 跳过订单簿比对 / Failed to load events, skipping the book check: mint={}, {}", mint, e);
+                    continue;
+                }
+            };
+            for direction in [Direction::Dn, Direction::Up] {
+                match self.orderbook_matches_replay(&mint, direction, &events) {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        let book = format!("{}:{}", mint, direction);
+                        match self.orderbook_storage.mark_rebuild(&mint, direction, slot, "disagrees with the replayed events after a reprocess") {
+                            Ok(()) => report.orderbooks_marked_for_rebuild.push(book),
+                            Err(e) => error!("❌ 写入重建标记失败 / Failed to write rebuild marker: {}: {}", book, e),
+                        }
+                    }
+                    Err(e) => warn!(
+                        "⚠️ 无法回放订单簿, 跳过比对 / Cannot replay the order book, skipping the check: {}:{}, {}",
+                        mint, direction, e
+                    ),
+                }
+            }
+        }
+    }
+
+    /// 本地订单簿是否与事件回放结果一致 (按链表顺序比较订单及其数量) / Whether the local book matches a replay of the events (orders and amounts in list order)
+    fn orderbook_matches_replay(&self, mint: &str, direction: Direction, events: &[PinpetEvent]) -> anyhow::Result<bool> {
+        let replayed = replay_orderbook_at(events, direction, Utc::now())?;
+        let manager = self.orderbook_storage.get_or_create_manager(mint.to_string(), direction)?;
+        let local = manager.get_all_active_orders_sorted()?;
+
+        let key = |order: &MarginOrder| {
+            (
+                order.order_id,
+                order.margin_sol_amount,
+                order.borrow_amount,
+                order.position_asset_amount,
+                order.lock_lp_start_price,
+                order.lock_lp_end_price,
+            )
+        };
+        Ok(local.len() == replayed.orders.len()
+            && local
+                .iter()
+                .zip(&replayed.orders)
+                .all(|((_, a), (_, b))| key(a) == key(b)))
+    }
+
+    /// 替换已存储交易的事件记录和Token详情 / Replace the event record and token detail of a stored transaction
+    async fn replace_stored_event(&self, signature: &str, event: PinpetEvent) -> anyhow::Result<()> {
+        if let PinpetEvent::TokenCreated(ref tc_event) = event {
+            if let Err(e) = self.store_token_created(tc_event).await {
+                error!("❌ 重写 TokenCreatedEvent 失败 / Failed to rewrite TokenCreatedEvent: {}", e);
+            }
+        }
+
+        self.event_storage.store_events(signature, vec![event.clone()]).await?;
+        info!("♻️ 事件已覆盖 / Event overwritten: {}", &signature[..8]);
+        self.observers.notify(&event);
+        Ok(())
+    }
+
+    /// 将事件应用到曲线状态 / Apply an event to the curve state
+    async fn update_curve_state(&self, curve_storage: &CurveStorage, event: &PinpetEvent) -> anyhow::Result<()> {
+        if let PinpetEvent::TokenCreated(e) = event {
+            // 事件不含 fee_split / borrow_duration, 从链上补齐 / The event lacks fee_split / borrow_duration, fill them from chain
+            if curve_storage.init_from_token_created(e)? {
+                self.seed_curve_state(curve_storage, &e.mint_account, Some(&e.curve_account)).await;
+            }
+            return Ok(());
+        }
+
+        let Some((mint, slot, update)) = self.curve_update(event)? else {
+            return Ok(());
+        };
+
+        // 服务启动前创建的代币没有初始状态, 从链上读取一次 / Tokens created before the server started have no state, read it from chain once
+        if curve_storage.get_curve(mint)?.is_none()
+            && !self.seed_curve_state(curve_storage, mint, None).await
+        {
+            return Ok(());
+        }
+
+        curve_storage.apply_update(mint, slot, &update)?;
+        Ok(())
+    }
+
+    /// 读取链上曲线账户覆盖曲线状态, 返回是否成功 / Overwrite curve state with the on-chain curve account, returns whether it succeeded
+    async fn seed_curve_state(&self, curve_storage: &CurveStorage, mint: &str, curve_account: Option<&str>) -> bool {
+        let Some(ref client) = self.solana_client else {
+            return false;
+        };
+
+        let curve_account = match curve_account {
+            Some(curve_account) => curve_account.to_string(),
+            None => match self.token_storage.get_token_by_mint(mint) {
+                Ok(Some(token)) => token.curve_account,
+                _ => {
+                    warn!("⚠️ 未找到Token, 无法读取曲线账户 / Token not found, cannot read curve account: mint={}", mint);
+                    return false;
+                }
+            },
+        };
+
+        let result = match client.get_account_info(&curve_account).await {
+            Ok(Some((slot, data))) => crate::db::CurveState::from_account_data(mint, &curve_account, &data, slot)
+                .and_then(|state| curve_storage.save_curve(&state)),
+            Ok(None) => Err(anyhow::anyhow!("曲线账户不存在 / Curve account not found: {}", curve_account)),
+            Err(e) => Err(e),
+        };
+
+        match result {
+            Ok(()) => {
+                info!("✅ 已从链上同步曲线状态 / Curve state synced from chain: mint={}", mint);
+                true
+            }
+            Err(e) => {
+                warn!("⚠️ 从链上同步曲线状态失败 / Failed to sync curve state from chain: mint={}, {}", mint, e);
+                false
+            }
+        }
+    }
+
+    /// 计算事件对曲线状态的影响, 返回 (mint, slot, 更新)
+    /// Compute an event's effect on the curve state, returns (mint, slot, update)
+    ///
+    /// 开仓从借贷池借出 borrow_amount (做多借SOL, 做空借token), 平仓与清算按订单簿中的 borrow_amount 归还;
+    /// 部分平仓归还的是平仓前后 borrow_amount 的差值
+    /// Opening lends borrow_amount from the borrow pool (longs borrow SOL, shorts borrow tokens), closes and liquidations
+    /// return the borrow_amount recorded in the orderbook; a partial close returns the difference in borrow_amount
+    fn curve_update<'a>(&self, event: &'a PinpetEvent) -> anyhow::Result<Option<(&'a str, u64, CurveUpdate)>> {
+        let (mint, slot, update) = match event {
+            // 追加的保证金进入 pool_sol_account, 延期手续费付给手续费账户, 都不影响曲线和借贷池
+            // Added margin goes to pool_sol_account and extension fees to the fee recipients, neither touches the curve or borrow pool
+            PinpetEvent::TokenCreated(_) | PinpetEvent::AddMargin(_) | PinpetEvent::ExtendOrder(_) => return Ok(None),
+            PinpetEvent::BuySell(e) => {
+                // 买入清算做空订单, 卖出清算做多订单 / Buys liquidate shorts, sells liquidate longs
+                let direction = if e.is_buy { Direction::Up } else { Direction::Dn };
+                let returned = self.returned_borrow(&e.mint_account, direction, &e.liquidate_indices, None)?;
+                let mut update = CurveUpdate {
+                    price: Some(e.latest_price),
+                    ..Default::default()
+                };
+                add_returned_borrow(&mut update, direction, returned);
+                (&e.mint_account, e.slot, update)
+            }
+            PinpetEvent::LongShort(e) => {
+                let mut update = CurveUpdate {
+                    price: Some(e.latest_price),
+                    ..Default::default()
+                };
+                let liquidate_direction = if e.order_type == 1 {
+                    update.borrow_sol_delta = -i128::from(e.borrow_amount);
+                    Direction::Up
+                } else {
+                    update.borrow_token_delta = -i128::from(e.borrow_amount);
+                    Direction::Dn
+                };
+                let returned = self.returned_borrow(&e.mint_account, liquidate_direction, &e.liquidate_indices, None)?;
+                add_returned_borrow(&mut update, liquidate_direction, returned);
+                (&e.mint_account, e.slot, update)
+            }
+            PinpetEvent::FullClose(e) => {
+                // 列表中包含被平仓订单本身, 它同样归还借款 / The list includes the closed order, which also repays its borrow
+                let direction = if e.is_close_long { Direction::Dn } else { Direction::Up };
+                let returned = self.returned_borrow(&e.mint_account, direction, &e.liquidate_indices, None)?;
+                let mut update = CurveUpdate {
+                    price: Some(e.latest_price),
+                    ..Default::default()
+                };
+                add_returned_borrow(&mut update, direction, returned);
+                (&e.mint_account, e.slot, update)
+            }
+            PinpetEvent::PartialClose(e) => {
+                let direction = if e.is_close_long { Direction::Dn } else { Direction::Up };
+                let manager = self.orderbook_storage
+                    .get_or_create_manager(e.mint_account.clone(), direction)?;
+                let repaid = match manager.get_order(e.order_index) {
+                    Ok(order) if order.order_id == e.order_id => order.borrow_amount.saturating_sub(e.borrow_amount),
+                    _ => {
+                        warn!(
+                            "⚠️ 未找到部分平仓订单, 归还借款按0计算 / Partially closed order not found, repaid borrow counted as 0: order_id={}, order_index={}",
+                            e.order_id, e.order_index
+                        );
+                        0
+                    }
+                };
+                let liquidated = self.returned_borrow(&e.mint_account, direction, &e.liquidate_indices, Some(e.order_id))?;
+                let mut update = CurveUpdate {
+                    price: Some(e.latest_price),
+                    ..Default::default()
+                };
+                add_returned_borrow(&mut update, direction, repaid.saturating_add(liquidated));
+                (&e.mint_account, e.slot, update)
+            }
+            PinpetEvent::MilestoneDiscount(e) => (
+                &e.mint_account,
+                e.slot,
+                CurveUpdate {
+                    swap_fee: Some(e.swap_fee),
+                    borrow_fee: Some(e.borrow_fee),
+                    fee_discount_flag: Some(e.fee_discount_flag),
+                    ..Default::default()
+                },
+            ),
+        };
+
+        Ok(Some((mint.as_str(), slot, update)))
+    }
+
+    /// 本事件结清的订单及其已实现盈亏: 全平的订单本身, 以及 liquidate_indices 中被清算的订单
+    /// Orders settled by this event with their realized PnL: the fully closed order itself and the orders liquidated
+    /// through liquidate_indices
+    ///
+    /// 方向与 curve_update 一致; 部分平仓的订单仍在簿中, 不算结清
+    /// Directions match curve_update; a partially closed order stays in the book and is not settled
+    fn pnl_settlements(&self, event: &PinpetEvent) -> anyhow::Result<Vec<PnlSettlement>> {
+        // (mint, 方向, 索引, 全平的 (order_id, 取回), 跳过的 order_id) / (mint, direction, indices, fully closed (order_id, payout), skipped order_id)
+        let (mint, direction, indices, closed, skip_order_id, slot) = match event {
+            PinpetEvent::BuySell(e) => (
+                &e.mint_account,
+                if e.is_buy { Direction::Up } else { Direction::Dn },
+                &e.liquidate_indices,
+                None,
+                None,
+                e.slot,
+            ),
+            PinpetEvent::LongShort(e) => (
+                &e.mint_account,
+                if e.order_type == 1 { Direction::Up } else { Direction::Dn },
+                &e.liquidate_indices,
+                None,
+                None,
+                e.slot,
+            ),
+            PinpetEvent::FullClose(e) => (
+                &e.mint_account,
+                if e.is_close_long { Direction::Dn } else { Direction::Up },
+                &e.liquidate_indices,
+                Some((e.order_id, e.user_close_profit)),
+                None,
+                e.slot,
+            ),
+            PinpetEvent::PartialClose(e) => (
+                &e.mint_account,
+                if e.is_close_long { Direction::Dn } else { Direction::Up },
+                &e.liquidate_indices,
+                None,
+                Some(e.order_id),
+                e.slot,
+            ),
+            PinpetEvent::TokenCreated(_)
+            | PinpetEvent::MilestoneDiscount(_)
+            | PinpetEvent::AddMargin(_)
+            | PinpetEvent::ExtendOrder(_) => return Ok(Vec::new()),
+        };
+        if indices.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let manager = self.orderbook_storage
+            .get_or_create_manager(mint.clone(), direction)?;
+
+        let mut settlements = Vec::with_capacity(indices.len());
+        for &index in indices {
+            let order = match manager.get_order(index) {
+                Ok(order) => order,
+                Err(e) => {
+                    warn!(
+                        "⚠️ 读取结清订单失败, 不计入盈亏 / Failed to read settled order, PnL not counted: index={}, {}",
+                        index, e
+                    );
+                    continue;
+                }
+            };
+            if Some(order.order_id) == skip_order_id {
+                continue;
+            }
+
+            let final_payout = closed.and_then(|(order_id, payout)| (order_id == order.order_id).then_some(payout));
+            settlements.push(PnlSettlement {
+                user: order.user,
+                mint: mint.clone(),
+                order_id: order.order_id,
+                payout: order.realized_sol_amount.saturating_add(final_payout.unwrap_or(0)),
+                margin: order.margin_init_sol_amount,
+                liquidated: final_payout.is_none(),
+                slot,
+            });
+        }
+
+        Ok(settlements)
+    }
+
+    /// 订单簿中指定索引订单的 borrow_amount 之和 / Sum of borrow_amount of the orders at the given indices
+    fn returned_borrow(
+        &self,
+        mint: &str,
+        direction: Direction,
+        indices: &[u16],
+        skip_order_id: Option<u64>,
+    ) -> anyhow::Result<u64> {
+        if indices.is_empty() {
+            return Ok(0);
+        }
+
+        let manager = self.orderbook_storage
+            .get_or_create_manager(mint.to_string(), direction)?;
+
+        let mut total = 0u64;
+        for &index in indices {
+            match manager.get_order(index) {
+                Ok(order) if Some(order.order_id) != skip_order_id => {
+                    total = total.saturating_add(order.borrow_amount);
+                }
+                Ok(_) => {}
+                Err(e) => warn!(
+                    "⚠️ 读取订单借款数量失败 / Failed to read order borrow amount: index={}, {}",
+                    index, e
+                ),
+            }
+        }
+
+        Ok(total)
+    }
+
     /// 处理 LongShortEvent 并插入到 OrderBook / Handle LongShortEvent and insert to OrderBook
     fn handle_long_short_event(
         &self,
         event: &super::events::LongShortEvent,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<Vec<LiquidationRecord>> {
         // 1. 确定方向 / Determine direction
         // order_type: 1=做多/long/dn, 2=做空/short/up
         let direction = match event.order_type {
-            1 => "dn",  // 做多 / Long
-            2 => "up",  // 做空 / Short
+            1 => Direction::Dn,  // 做多 / Long
+            2 => Direction::Up,  // 做空 / Short
             _ => {
                 return Err(anyhow::anyhow!(
                     "Invalid order_type: {}, expected 1 (long/dn) or 2 (short/up)",
@@ -202,7 +828,7 @@ impl StorageEventHandler {
 
         // 2. 获取或创建 OrderBook 管理器 / Get or create OrderBook manager
         let manager = self.orderbook_storage
-            .get_or_create_manager(event.mint_account.clone(), direction.to_string())?;
+            .get_or_create_manager(event.mint_account.clone(), direction)?;
 
         // 3. 构造 MarginOrder / Construct MarginOrder
         let order = MarginOrder {
@@ -210,6 +836,7 @@ impl StorageEventHandler {
             lock_lp_start_price: event.lock_lp_start_price,
             lock_lp_end_price: event.lock_lp_end_price,
             open_price: event.open_price,
+            take_profit_price: event.take_profit_price,
             order_id: event.order_id,  // ✅ 使用事件中的 order_id / Use order_id from event
             lock_lp_sol_amount: event.lock_lp_sol_amount,
             lock_lp_token_amount: event.lock_lp_token_amount,
@@ -261,14 +888,18 @@ impl StorageEventHandler {
         );
 
         // 5. 插入订单 / Insert order
-        let (index, assigned_order_id) = if insert_pos == u16::MAX || header.total == 0 {
+        let inserted = if insert_pos == u16::MAX || header.total == 0 {
             // 插入到头部或空链表 / Insert at head or empty list
             // 使用 insert_after(u16::MAX, ...) 会在头部插入 / Using insert_after(u16::MAX, ...) inserts at head
-            manager.insert_after(u16::MAX, &order)?
+            manager.insert_after(u16::MAX, &order)
         } else {
             // 插入到指定位置之后 / Insert after specified position
-            manager.insert_after(insert_pos, &order)?
+            manager.insert_after(insert_pos, &order)
         };
+        if let Err(OrderBookError::ExceedsMaxCapacity { .. }) = inserted {
+            self.orderbook_storage.record_capacity_rejection(&event.mint_account, direction);
+        }
+        let (index, assigned_order_id) = inserted?;
 
         info!(
             "✅ 订单已插入 OrderBook / Order inserted to OrderBook: mint={}, direction={}, index={}, assigned_order_id={}, event_order_id={}",
@@ -293,8 +924,8 @@ impl StorageEventHandler {
             // order_type=1 (做多/long) 删 up 方向的订单 / order_type=1 (long) deletes up direction orders
             // order_type=2 (做空/short) 删 dn 方向的订单 / order_type=2 (short) deletes dn direction orders
             let liquidate_direction = match event.order_type {
-                1 => "up",  // 做多时清算做空订单 / When going long, liquidate short orders
-                2 => "dn",  // 做空时清算做多订单 / When going short, liquidate long orders
+                1 => Direction::Up,  // 做多时清算做空订单 / When going long, liquidate short orders
+                2 => Direction::Dn,  // 做空时清算做多订单 / When going short, liquidate long orders
                 _ => {
                     return Err(anyhow::anyhow!(
                         "Invalid order_type for liquidation: {}, expected 1 or 2",
@@ -304,10 +935,25 @@ impl StorageEventHandler {
             };
 
             let liquidate_manager = self.orderbook_storage
-                .get_or_create_manager(event.mint_account.clone(), liquidate_direction.to_string())?;
+                .get_or_create_manager(event.mint_account.clone(), liquidate_direction)?;
 
             // 强制清算,使用 CloseReason::ForcedLiquidation (2) 和开仓价格
             // Forced liquidation, use CloseReason::ForcedLiquidation (2) and open price
+            let records = Self::snapshot_liquidations(
+                &liquidate_manager,
+                &event.liquidate_indices,
+                None,
+                &LiquidationTrigger {
+                    event_type: "LongShort",
+                    mint: &event.mint_account,
+                    direction: liquidate_direction,
+                    signature: &event.signature,
+                    slot: event.slot,
+                    timestamp: event.timestamp,
+                    price: event.open_price,
+                },
+            );
+
             liquidate_manager.batch_remove_by_indices_unsafe(
                 &event.liquidate_indices,
                 2, // ForcedLiquidation
@@ -318,25 +964,27 @@ impl StorageEventHandler {
                 "✅ LongShortEvent 清算完成 / LongShortEvent liquidations completed: direction={}, count={}",
                 liquidate_direction, event.liquidate_indices.len()
             );
+
+            return Ok(records);
         }
 
-        Ok(())
+        Ok(Vec::new())
     }
 
     /// 处理 BuySellEvent 的清算 / Handle BuySellEvent liquidations
     fn handle_buy_sell_event(
         &self,
         event: &super::events::BuySellEvent,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<Vec<LiquidationRecord>> {
         // 检查是否有需要清算的订单 / Check if there are orders to liquidate
         if event.liquidate_indices.is_empty() {
-            return Ok(());
+            return Ok(Vec::new());
         }
 
         // 确定清算的方向 / Determine liquidation direction
         // is_buy=true 删 up 方向的订单 / is_buy=true deletes up direction orders
         // is_buy=false 删 dn 方向的订单 / is_buy=false deletes dn direction orders
-        let direction = if event.is_buy { "up" } else { "dn" };
+        let direction = if event.is_buy { Direction::Up } else { Direction::Dn };
 
         info!(
             "🔥 处理 BuySellEvent 清算 / Processing BuySellEvent liquidations: mint={}, direction={}, count={}",
@@ -345,7 +993,22 @@ impl StorageEventHandler {
 
         // 获取 OrderBook 管理器 / Get OrderBook manager
         let manager = self.orderbook_storage
-            .get_or_create_manager(event.mint_account.clone(), direction.to_string())?;
+            .get_or_create_manager(event.mint_account.clone(), direction)?;
+
+        let records = Self::snapshot_liquidations(
+            &manager,
+            &event.liquidate_indices,
+            None,
+            &LiquidationTrigger {
+                event_type: "BuySell",
+                mint: &event.mint_account,
+                direction,
+                signature: &event.signature,
+                slot: event.slot,
+                timestamp: event.timestamp,
+                price: event.latest_price,
+            },
+        );
 
         // 批量删除订单 / Batch remove orders
         // 强制清算,使用 CloseReason::ForcedLiquidation (2)
@@ -361,23 +1024,23 @@ impl StorageEventHandler {
             &event.mint_account[..8], direction, event.liquidate_indices.len()
         );
 
-        Ok(())
+        Ok(records)
     }
 
     /// 处理 FullCloseEvent 的清算 / Handle FullCloseEvent liquidations
     fn handle_full_close_event(
         &self,
         event: &super::events::FullCloseEvent,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<Vec<LiquidationRecord>> {
         // 检查是否有需要清算的订单 / Check if there are orders to liquidate
         if event.liquidate_indices.is_empty() {
-            return Ok(());
+            return Ok(Vec::new());
         }
 
         // 确定清算的方向 / Determine liquidation direction
         // is_close_long=true 删 dn 方向的订单 / is_close_long=true deletes dn direction orders
         // is_close_long=false 删 up 方向的订单 / is_close_long=false deletes up direction orders
-        let direction = if event.is_close_long { "dn" } else { "up" };
+        let direction = if event.is_close_long { Direction::Dn } else { Direction::Up };
 
         info!(
             "🔥 处理 FullCloseEvent 清算 / Processing FullCloseEvent liquidations: mint={}, direction={}, count={}",
@@ -386,7 +1049,23 @@ impl StorageEventHandler {
 
         // 获取 OrderBook 管理器 / Get OrderBook manager
         let manager = self.orderbook_storage
-            .get_or_create_manager(event.mint_account.clone(), direction.to_string())?;
+            .get_or_create_manager(event.mint_account.clone(), direction)?;
+
+        // 列表中包含被平仓订单本身, 它不是清算 / The list includes the closed order itself, which is not a liquidation
+        let records = Self::snapshot_liquidations(
+            &manager,
+            &event.liquidate_indices,
+            Some(event.order_id),
+            &LiquidationTrigger {
+                event_type: "FullClose",
+                mint: &event.mint_account,
+                direction,
+                signature: &event.signature,
+                slot: event.slot,
+                timestamp: event.timestamp,
+                price: event.latest_price,
+            },
+        );
 
         // 批量删除订单 / Batch remove orders
         // 用户主动平仓,使用 CloseReason::UserInitiated (1)
@@ -402,18 +1081,18 @@ impl StorageEventHandler {
             &event.mint_account[..8], direction, event.liquidate_indices.len()
         );
 
-        Ok(())
+        Ok(records)
     }
 
     /// 处理 PartialCloseEvent 的更新和清算 / Handle PartialCloseEvent update and liquidations
     fn handle_partial_close_event(
         &self,
         event: &super::events::PartialCloseEvent,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<Vec<LiquidationRecord>> {
         // 确定更新和清算的方向 / Determine update and liquidation direction
         // is_close_long=true 更新 dn 方向的订单 / is_close_long=true updates dn direction orders
         // is_close_long=false 更新 up 方向的订单 / is_close_long=false updates up direction orders
-        let direction = if event.is_close_long { "dn" } else { "up" };
+        let direction = if event.is_close_long { Direction::Dn } else { Direction::Up };
 
         info!(
             "🔄 处理 PartialCloseEvent / Processing PartialCloseEvent: mint={}, direction={}, order_id={}, order_index={}",
@@ -422,7 +1101,7 @@ impl StorageEventHandler {
 
         // 获取 OrderBook 管理器 / Get OrderBook manager
         let manager = self.orderbook_storage
-            .get_or_create_manager(event.mint_account.clone(), direction.to_string())?;
+            .get_or_create_manager(event.mint_account.clone(), direction)?;
 
         // 1. 先更新订单 / First update the order
         use crate::orderbook::MarginOrderUpdateData;
@@ -443,7 +1122,8 @@ impl StorageEventHandler {
             realized_sol_amount: Some(event.realized_sol_amount),
         };
 
-        manager.update_order(event.order_index, event.order_id, &update_data)?;
+        // 起点价格前移后同步前节点的 next_lp_*, 与链上一致 / Resync the predecessor's next_lp_* after the start price moves, as on-chain
+        manager.update_order_with_neighbor_recalc(event.order_index, event.order_id, &update_data)?;
 
         info!(
             "✅ PartialCloseEvent 订单更新完成 / PartialCloseEvent order update completed: order_id={}, order_index={}",
@@ -457,6 +1137,21 @@ impl StorageEventHandler {
                 event.liquidate_indices.len()
             );
 
+            let records = Self::snapshot_liquidations(
+                &manager,
+                &event.liquidate_indices,
+                Some(event.order_id),
+                &LiquidationTrigger {
+                    event_type: "PartialClose",
+                    mint: &event.mint_account,
+                    direction,
+                    signature: &event.signature,
+                    slot: event.slot,
+                    timestamp: event.timestamp,
+                    price: event.latest_price,
+                },
+            );
+
             // 强制清算,使用 CloseReason::ForcedLiquidation (2)
             // Forced liquidation, use CloseReason::ForcedLiquidation (2)
             manager.batch_remove_by_indices_unsafe(
@@ -469,10 +1164,133 @@ impl StorageEventHandler {
                 "✅ PartialCloseEvent 清算完成 / PartialCloseEvent liquidations completed: count={}",
                 event.liquidate_indices.len()
             );
+
+            return Ok(records);
         }
 
+        Ok(Vec::new())
+    }
+
+    /// 处理追加保证金事件: 更新订单的保证金和锁定区间
+    /// Handle an add margin event: update the order's margin and locked range
+    ///
+    /// 链上追加保证金可能把锁定区间移离当前价格, 本订单和前节点的 next_lp_* 随之重新计算
+    /// Adding margin on-chain may move the locked range away from the current price; the next_lp_* of the order and
+    /// its predecessor are recomputed accordingly
+    fn handle_add_margin_event(&self, event: &super::events::AddMarginEvent) -> anyhow::Result<()> {
+        // order_type: 1=做多/long/dn, 2=做空/short/up
+        let direction = if event.order_type == 1 { Direction::Dn } else { Direction::Up };
+
+        info!(
+            "💰 处理 AddMarginEvent / Processing AddMarginEvent: mint={}, direction={}, order_id={}, add_sol={}",
+            &event.mint_account[..8], direction, event.order_id, event.add_sol_amount
+        );
+
+        let manager = self.orderbook_storage
+            .get_or_create_manager(event.mint_account.clone(), direction)?;
+
+        use crate::orderbook::MarginOrderUpdateData;
+        let update_data = MarginOrderUpdateData {
+            lock_lp_start_price: Some(event.lock_lp_start_price),
+            lock_lp_end_price: Some(event.lock_lp_end_price),
+            lock_lp_sol_amount: Some(event.lock_lp_sol_amount),
+            lock_lp_token_amount: Some(event.lock_lp_token_amount),
+            margin_sol_amount: Some(event.margin_sol_amount),
+            ..Default::default()
+        };
+        manager.update_order_with_neighbor_recalc(event.order_index, event.order_id, &update_data)?;
+
+        info!(
+            "✅ AddMarginEvent 订单更新完成 / AddMarginEvent order update completed: order_id={}, order_index={}",
+            event.order_id, event.order_index
+        );
         Ok(())
     }
+
+    /// 处理订单延期事件: 更新订单的到期时间 / Handle an extend order event: update the order's expiry
+    fn handle_extend_order_event(&self, event: &super::events::ExtendOrderEvent) -> anyhow::Result<()> {
+        // order_type: 1=做多/long/dn, 2=做空/short/up
+        let direction = if event.order_type == 1 { Direction::Dn } else { Direction::Up };
+
+        info!(
+            "⏳ 处理 ExtendOrderEvent / Processing ExtendOrderEvent: mint={}, direction={}, order_id={}, end_time={}",
+            &event.mint_account[..8], direction, event.order_id, event.end_time
+        );
+
+        let manager = self.orderbook_storage
+            .get_or_create_manager(event.mint_account.clone(), direction)?;
+
+        use crate::orderbook::MarginOrderUpdateData;
+        let update_data = MarginOrderUpdateData {
+            end_time: Some(event.end_time),
+            ..Default::default()
+        };
+        manager.update_order(event.order_index, event.order_id, &update_data)?;
+
+        info!(
+            "✅ ExtendOrderEvent 订单更新完成 / ExtendOrderEvent order update completed: order_id={}, order_index={}",
+            event.order_id, event.order_index
+        );
+        Ok(())
+    }
+
+    /// 在删除前读取待清算订单, 生成清算记录
+    /// Read the orders about to be liquidated before removal and build liquidation records
+    ///
+    /// `skip_order_id` 用于排除平仓订单本身; 读取失败的索引仅记录警告
+    /// `skip_order_id` excludes the order being closed; indices that fail to read only log a warning
+    fn snapshot_liquidations(
+        manager: &OrderBookDBManager,
+        indices: &[u16],
+        skip_order_id: Option<u64>,
+        trigger: &LiquidationTrigger,
+    ) -> Vec<LiquidationRecord> {
+        let mut records = Vec::with_capacity(indices.len());
+
+        for &index in indices {
+            let order = match manager.get_order(index) {
+                Ok(order) => order,
+                Err(e) => {
+                    warn!(
+                        "⚠️ 读取待清算订单失败 / Failed to read order to liquidate: index={}, {}",
+                        index, e
+                    );
+                    continue;
+                }
+            };
+
+            if Some(order.order_id) == skip_order_id {
+                continue;
+            }
+
+            records.push(LiquidationRecord {
+                mint: trigger.mint.to_string(),
+                direction: trigger.direction.to_string(),
+                order_id: order.order_id,
+                user: order.user,
+                order_index: index,
+                trigger_event_type: trigger.event_type.to_string(),
+                signature: trigger.signature.to_string(),
+                slot: trigger.slot,
+                timestamp: trigger.timestamp.timestamp_millis(),
+                price: trigger.price.to_string(),
+                margin_sol_amount: order.margin_sol_amount,
+                position_asset_amount: order.position_asset_amount,
+            });
+        }
+
+        records
+    }
+}
+
+/// 归还到借贷池: dn 方向(做多)归还SOL, up 方向(做空)归还token
+/// Return to the borrow pool: the dn (long) direction returns SOL, the up (short) direction returns tokens
+fn add_returned_borrow(update: &mut CurveUpdate, direction: Direction, amount: u64) {
+    if direction == Direction::Dn {
+        update.borrow_sol_delta += i128::from(amount);
+    } else {
+        update.borrow_token_delta += i128::from(amount);
+    }
 }
 
 /// 处理包含多个事件的交易 / Process transactions containing multiple events
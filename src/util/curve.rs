@@ -0,0 +1,534 @@
+// 联合曲线 AMM 数学 (链上 CurveAMM 的链下副本)
+// Bonding-curve AMM math (off-chain copy of the on-chain CurveAMM)
+//
+// 与 other-code/programs/pinpet/src/curve/curve_amm.rs 保持一致, 修改时需同步
+// Mirrors other-code/programs/pinpet/src/curve/curve_amm.rs, keep both in sync when changing
+
+use rust_decimal::prelude::*;
+use rust_decimal::Decimal;
+
+/// 手续费计算使用的分母 (10^5) / Fee denominator (10^5)
+pub const FEE_DENOMINATOR: u64 = 100_000;
+
+/// 最大手续费率 (10%) / Maximum fee rate (10%)
+pub const MAX_FEE_RATE: u16 = 10_000;
+
+// ==================== 链上交易约束 (constants.rs) / On-chain trade constraints (constants.rs) ====================
+
+/// 最小交易 token 数量 / Minimum trade token amount
+pub const MIN_TRADE_TOKEN_AMOUNT: u64 = 100_000;
+
+/// 保证金交易最小保证金 (0.002 SOL) / Minimum margin for margin trades (0.002 SOL)
+pub const MIN_MARGIN_SOL_AMOUNT: u64 = 2_000_000;
+
+/// 止损价与当前价的最小距离 (百分比) / Minimum distance between stop price and current price (percent)
+pub const MIN_STOP_LOSS_PERCENT: u16 = 3;
+
+/// 开平仓指令可携带的最大插入/删除索引数 / Maximum insert/close indices an open or close instruction may carry
+pub const MAX_CLOSE_INSERT_INDICES: usize = 21;
+
+/// 平仓冷却时间(秒), 从订单 start_time 起算, 未满时平仓指令失败 (TradeCooldownNotExpired)
+/// Close cooldown (seconds) counted from the order's start_time; closing earlier fails with TradeCooldownNotExpired
+pub const TRADE_COOLDOWN_SECONDS: u32 = 2;
+
+/// 传统 AMM 交易模型 / Constant-product AMM model
+pub struct CurveAMM;
+
+impl CurveAMM {
+    pub const INITIAL_SOL_RESERVE_DECIMAL: Decimal = Decimal::from_parts(30, 0, 0, false, 0);
+    pub const INITIAL_TOKEN_RESERVE_DECIMAL: Decimal =
+        Decimal::from_parts(1073000000, 0, 0, false, 0);
+
+    /// 可以出现的最小价格,低于这个价格可能溢出
+    /// Minimum allowed price, lower prices may overflow
+    pub const INITIAL_MIN_PRICE_DECIMAL: Decimal = Decimal::from_parts(1, 0, 0, false, 9);
+
+    /// 价格精度因子 = 10^26 / Price precision factor = 10^26
+    pub const PRICE_PRECISION_FACTOR_DECIMAL: Decimal =
+        Decimal::from_parts(3825205248, 3704098002, 5421010, false, 0);
+
+    /// Token 精度因子 = 10^6 / Token precision factor = 10^6
+    pub const TOKEN_PRECISION_FACTOR_DECIMAL: Decimal =
+        Decimal::from_parts(1000000, 0, 0, false, 0);
+
+    /// SOL 精度因子 = 10^9 / SOL precision factor = 10^9
+    pub const SOL_PRECISION_FACTOR_DECIMAL: Decimal =
+        Decimal::from_parts(1000000000, 0, 0, false, 0);
+
+    /// u64 的极大值,用来表示无限流动性 / Sentinel "max" u64, used for infinite liquidity
+    pub const MAX_U64: u64 = 3046744073709551614;
+
+    /// 价格计算上限,防止 Decimal 运算溢出 / Price calculation limit, prevents Decimal overflow
+    pub const PRICE_CALCULATION_LIMIT: u128 = 50_000_000_000_000_000_000_000_000_000;
+
+    // ==================== 精度转换 / Precision Conversion ====================
+
+    /// 将 u128 价格转换为 Decimal / Convert u128 price to Decimal
+    #[inline(always)]
+    pub fn u128_to_decimal(price: u128) -> Option<Decimal> {
+        if price > Self::PRICE_CALCULATION_LIMIT {
+            return None;
+        }
+
+        Decimal::from(price).checked_div(Self::PRICE_PRECISION_FACTOR_DECIMAL)
+    }
+
+    /// 将 Decimal 价格转换为 u128,向下取整 / Convert Decimal price to u128, rounding down
+    #[inline(always)]
+    pub fn decimal_to_u128(price: Decimal) -> Option<u128> {
+        let scaled = price.checked_mul(Self::PRICE_PRECISION_FACTOR_DECIMAL)?;
+        let result = scaled.floor().to_u128()?;
+
+        if result > Self::PRICE_CALCULATION_LIMIT {
+            return None;
+        }
+
+        Some(result)
+    }
+
+    /// Decimal token 数量转 u64 (6 位精度),向下取整 / Decimal token amount to u64 (6 decimals), rounded down
+    #[inline(always)]
+    pub fn token_decimal_to_u64(amount: Decimal) -> Option<u64> {
+        let scaled = amount.checked_mul(Self::TOKEN_PRECISION_FACTOR_DECIMAL)?;
+        scaled.floor().to_u64()
+    }
+
+    /// Decimal token 数量转 u64 (6 位精度),向上取整 / Decimal token amount to u64 (6 decimals), rounded up
+    #[inline(always)]
+    pub fn token_decimal_to_u64_ceil(amount: Decimal) -> Option<u64> {
+        let scaled = amount.checked_mul(Self::TOKEN_PRECISION_FACTOR_DECIMAL)?;
+        scaled.ceil().to_u64()
+    }
+
+    /// Decimal token 数量转 u64 (6 位精度),四舍五入 / Decimal token amount to u64 (6 decimals), rounded
+    #[inline(always)]
+    pub fn token_decimal_to_u64_rounded(amount: Decimal) -> Option<u64> {
+        let scaled = amount.checked_mul(Self::TOKEN_PRECISION_FACTOR_DECIMAL)?;
+        scaled.round().to_u64()
+    }
+
+    /// Decimal SOL 数量转 u64 (9 位精度),向下取整 / Decimal SOL amount to u64 (9 decimals), rounded down
+    #[inline(always)]
+    pub fn sol_decimal_to_u64(amount: Decimal) -> Option<u64> {
+        let scaled = amount.checked_mul(Self::SOL_PRECISION_FACTOR_DECIMAL)?;
+        scaled.floor().to_u64()
+    }
+
+    /// Decimal SOL 数量转 u64 (9 位精度),向上取整 / Decimal SOL amount to u64 (9 decimals), rounded up
+    #[inline(always)]
+    pub fn sol_decimal_to_u64_ceil(amount: Decimal) -> Option<u64> {
+        let scaled = amount.checked_mul(Self::SOL_PRECISION_FACTOR_DECIMAL)?;
+        scaled.ceil().to_u64()
+    }
+
+    /// Decimal SOL 数量转 u64 (9 位精度),四舍五入 / Decimal SOL amount to u64 (9 decimals), rounded
+    #[inline(always)]
+    pub fn sol_decimal_to_u64_rounded(amount: Decimal) -> Option<u64> {
+        let scaled = amount.checked_mul(Self::SOL_PRECISION_FACTOR_DECIMAL)?;
+        scaled.round().to_u64()
+    }
+
+    /// u64 token 数量转 Decimal (6 位精度) / u64 token amount to Decimal (6 decimals)
+    #[inline(always)]
+    pub fn u64_to_token_decimal(amount: u64) -> Option<Decimal> {
+        Decimal::from(amount).checked_div(Self::TOKEN_PRECISION_FACTOR_DECIMAL)
+    }
+
+    /// u64 SOL 数量转 Decimal (9 位精度) / u64 SOL amount to Decimal (9 decimals)
+    #[inline(always)]
+    pub fn u64_to_sol_decimal(amount: u64) -> Option<Decimal> {
+        Decimal::from(amount).checked_div(Self::SOL_PRECISION_FACTOR_DECIMAL)
+    }
+
+    // ==================== 储备量 / Reserves ====================
+
+    /// 计算初始 k 值 / Calculate the initial k value
+    #[inline(always)]
+    pub fn calculate_initial_k() -> Decimal {
+        Self::INITIAL_SOL_RESERVE_DECIMAL * Self::INITIAL_TOKEN_RESERVE_DECIMAL
+    }
+
+    /// 获取初始价格 (1 个 token 兑换的 SOL) / Get the initial price (SOL per token)
+    #[inline(always)]
+    pub fn get_initial_price() -> Option<u128> {
+        let initial_price =
+            Self::INITIAL_SOL_RESERVE_DECIMAL.checked_div(Self::INITIAL_TOKEN_RESERVE_DECIMAL)?;
+        Self::decimal_to_u128(initial_price)
+    }
+
+    /// 根据价格计算储备量 (u64 接口, 四舍五入) / Reserves for a price (u64 interface, rounded)
+    ///
+    /// # 返回值 / Returns
+    /// (SOL 储备, token 储备) / (SOL reserve, token reserve)
+    pub fn price_to_reserves(price: u128) -> Option<(u64, u64)> {
+        let price_decimal = Self::u128_to_decimal(price)?;
+        let k = Self::calculate_initial_k();
+        let (sol_reserve, token_reserve) = Self::calculate_reserves_by_price(price_decimal, k)?;
+
+        Some((
+            Self::sol_decimal_to_u64_rounded(sol_reserve)?,
+            Self::token_decimal_to_u64_rounded(token_reserve)?,
+        ))
+    }
+
+    /// 给定价格计算储备量 / Calculate reserves for a given price
+    ///
+    /// # 返回值 / Returns
+    /// (SOL 储备, token 储备) / (SOL reserve, token reserve)
+    pub fn calculate_reserves_by_price(price: Decimal, k: Decimal) -> Option<(Decimal, Decimal)> {
+        if price <= Decimal::ZERO || k <= Decimal::ZERO {
+            return None;
+        }
+
+        // 最小价格判断,防溢出 / Minimum price check, prevents overflow
+        if price < Self::INITIAL_MIN_PRICE_DECIMAL {
+            return None;
+        }
+
+        // k = sol * token, price = sol / token
+        // => token = sqrt(k / price), sol = price * token
+        let token_reserve = k.checked_div(price)?.sqrt()?;
+        let sol_reserve = price.checked_mul(token_reserve)?;
+
+        Some((sol_reserve, token_reserve))
+    }
+
+    // ==================== 交易计算 / Trade Calculation ====================
+
+    /// 从低价买到高价 / Buy from a lower price up to a higher price
+    ///
+    /// # 返回值 / Returns
+    /// (需要投入的 SOL, 获得的 token), 均四舍五入 / (SOL required, tokens received), both rounded
+    pub fn buy_from_price_to_price(start_low_price: u128, end_high_price: u128) -> Option<(u64, u64)> {
+        let start_price_dec = Self::u128_to_decimal(start_low_price)?;
+        let end_price_dec = Self::u128_to_decimal(end_high_price)?;
+
+        if start_price_dec >= end_price_dec {
+            return None;
+        }
+
+        let k = Self::calculate_initial_k();
+        let (start_sol_reserve, start_token_reserve) =
+            Self::calculate_reserves_by_price(start_price_dec, k)?;
+        let (end_sol_reserve, end_token_reserve) =
+            Self::calculate_reserves_by_price(end_price_dec, k)?;
+
+        let sol_input_amount = end_sol_reserve.checked_sub(start_sol_reserve)?;
+        let token_output_amount = start_token_reserve.checked_sub(end_token_reserve)?;
+
+        if sol_input_amount <= Decimal::ZERO || token_output_amount <= Decimal::ZERO {
+            return None;
+        }
+
+        Some((
+            Self::sol_decimal_to_u64_rounded(sol_input_amount)?,
+            Self::token_decimal_to_u64_rounded(token_output_amount)?,
+        ))
+    }
+
+    /// 从高价卖到低价 / Sell from a higher price down to a lower price
+    ///
+    /// # 返回值 / Returns
+    /// (需要卖出的 token, 获得的 SOL), 均四舍五入 / (tokens sold, SOL received), both rounded
+    pub fn sell_from_price_to_price(start_high_price: u128, end_low_price: u128) -> Option<(u64, u64)> {
+        let start_price_dec = Self::u128_to_decimal(start_high_price)?;
+        let end_price_dec = Self::u128_to_decimal(end_low_price)?;
+
+        if start_price_dec <= end_price_dec {
+            return None;
+        }
+
+        let k = Self::calculate_initial_k();
+        let (start_sol_reserve, start_token_reserve) =
+            Self::calculate_reserves_by_price(start_price_dec, k)?;
+        let (end_sol_reserve, end_token_reserve) =
+            Self::calculate_reserves_by_price(end_price_dec, k)?;
+
+        let token_input_amount = end_token_reserve.checked_sub(start_token_reserve)?;
+        let sol_output_amount = start_sol_reserve.checked_sub(end_sol_reserve)?;
+
+        if token_input_amount <= Decimal::ZERO || sol_output_amount <= Decimal::ZERO {
+            return None;
+        }
+
+        Some((
+            Self::token_decimal_to_u64_rounded(token_input_amount)?,
+            Self::sol_decimal_to_u64_rounded(sol_output_amount)?,
+        ))
+    }
+
+    /// 从起始价格投入指定 SOL 买入 / Buy with a given SOL input starting from a price
+    ///
+    /// # 返回值 / Returns
+    /// (交易后价格, 得到的 token 数量) / (price after trade, tokens received)
+    /// 价格向下取整, token 四舍五入 / Price rounded down, tokens rounded
+    pub fn buy_from_price_with_sol_input(
+        start_low_price: u128,
+        sol_input_amount: u64,
+    ) -> Option<(u128, u64)> {
+        let start_price_dec = Self::u128_to_decimal(start_low_price)?;
+        let sol_input_dec = Self::u64_to_sol_decimal(sol_input_amount)?;
+
+        if start_price_dec <= Decimal::ZERO || sol_input_dec <= Decimal::ZERO {
+            return None;
+        }
+
+        let k = Self::calculate_initial_k();
+        let (start_sol_reserve, start_token_reserve) =
+            Self::calculate_reserves_by_price(start_price_dec, k)?;
+
+        let end_sol_reserve = start_sol_reserve.checked_add(sol_input_dec)?;
+        let end_token_reserve = k.checked_div(end_sol_reserve)?;
+        let token_output_amount = start_token_reserve.checked_sub(end_token_reserve)?;
+        let end_price = end_sol_reserve.checked_div(end_token_reserve)?;
+
+        if token_output_amount <= Decimal::ZERO || end_price <= Decimal::ZERO {
+            return None;
+        }
+
+        let end_price_u128 = Self::decimal_to_u128(end_price)?;
+        let token_amount_u64 = Self::token_decimal_to_u64_rounded(token_output_amount)?;
+
+        Some((end_price_u128, token_amount_u64))
+    }
+
+    /// 从起始价格卖出指定数量 token / Sell a given token amount starting from a price
+    ///
+    /// # 返回值 / Returns
+    /// (交易后价格, 获得的 SOL 数量) / (price after trade, SOL received)
+    /// 价格向下取整, SOL 四舍五入 / Price rounded down, SOL rounded
+    pub fn sell_from_price_with_token_input(
+        start_high_price: u128,
+        token_input_amount: u64,
+    ) -> Option<(u128, u64)> {
+        let start_price_dec = Self::u128_to_decimal(start_high_price)?;
+        let token_input_dec = Self::u64_to_token_decimal(token_input_amount)?;
+
+        if start_price_dec <= Decimal::ZERO || token_input_dec <= Decimal::ZERO {
+            return None;
+        }
+
+        let k = Self::calculate_initial_k();
+        let (start_sol_reserve, start_token_reserve) =
+            Self::calculate_reserves_by_price(start_price_dec, k)?;
+
+        let end_token_reserve = start_token_reserve.checked_add(token_input_dec)?;
+        let end_sol_reserve = k.checked_div(end_token_reserve)?;
+        let sol_output_amount = start_sol_reserve.checked_sub(end_sol_reserve)?;
+        let end_price = end_sol_reserve.checked_div(end_token_reserve)?;
+
+        if sol_output_amount <= Decimal::ZERO || end_price <= Decimal::ZERO {
+            return None;
+        }
+
+        let end_price_u128 = Self::decimal_to_u128(end_price)?;
+        let sol_amount_u64 = Self::sol_decimal_to_u64_rounded(sol_output_amount)?;
+
+        Some((end_price_u128, sol_amount_u64))
+    }
+
+    /// 从起始价格买入指定数量 token / Buy a given token amount starting from a price
+    ///
+    /// # 返回值 / Returns
+    /// (交易后价格, 需要付出的 SOL 数量) / (price after trade, SOL required)
+    /// 价格向下取整, SOL 四舍五入 / Price rounded down, SOL rounded
+    pub fn buy_from_price_with_token_output(
+        start_low_price: u128,
+        token_output_amount: u64,
+    ) -> Option<(u128, u64)> {
+        let start_price_dec = Self::u128_to_decimal(start_low_price)?;
+        let token_output_dec = Self::u64_to_token_decimal(token_output_amount)?;
+
+        if start_price_dec <= Decimal::ZERO || token_output_dec <= Decimal::ZERO {
+            return None;
+        }
+
+        let k = Self::calculate_initial_k();
+        let (start_sol_reserve, start_token_reserve) =
+            Self::calculate_reserves_by_price(start_price_dec, k)?;
+
+        let end_token_reserve = start_token_reserve.checked_sub(token_output_dec)?;
+        if end_token_reserve <= Decimal::ZERO {
+            return None;
+        }
+
+        let end_sol_reserve = k.checked_div(end_token_reserve)?;
+        let sol_input_amount = end_sol_reserve.checked_sub(start_sol_reserve)?;
+        let end_price = end_sol_reserve.checked_div(end_token_reserve)?;
+
+        if sol_input_amount <= Decimal::ZERO || end_price <= Decimal::ZERO {
+            return None;
+        }
+
+        let end_price_u128 = Self::decimal_to_u128(end_price)?;
+        let sol_amount_u64 = Self::sol_decimal_to_u64_rounded(sol_input_amount)?;
+
+        Some((end_price_u128, sol_amount_u64))
+    }
+
+    /// 从起始价格卖出以获得指定 SOL / Sell to receive a given SOL amount starting from a price
+    ///
+    /// # 返回值 / Returns
+    /// (交易后价格, 需要付出的 token 数量) / (price after trade, tokens required)
+    /// 价格向下取整, token 四舍五入 / Price rounded down, tokens rounded
+    pub fn sell_from_price_with_sol_output(
+        start_high_price: u128,
+        sol_output_amount: u64,
+    ) -> Option<(u128, u64)> {
+        let start_price_dec = Self::u128_to_decimal(start_high_price)?;
+        let sol_output_dec = Self::u64_to_sol_decimal(sol_output_amount)?;
+
+        if start_price_dec <= Decimal::ZERO || sol_output_dec <= Decimal::ZERO {
+            return None;
+        }
+
+        let k = Self::calculate_initial_k();
+        let (start_sol_reserve, start_token_reserve) =
+            Self::calculate_reserves_by_price(start_price_dec, k)?;
+
+        let end_sol_reserve = start_sol_reserve.checked_sub(sol_output_dec)?;
+        if end_sol_reserve <= Decimal::ZERO {
+            return None;
+        }
+
+        let end_token_reserve = k.checked_div(end_sol_reserve)?;
+        let token_input_amount = end_token_reserve.checked_sub(start_token_reserve)?;
+        let end_price = end_sol_reserve.checked_div(end_token_reserve)?;
+
+        if token_input_amount <= Decimal::ZERO || end_price <= Decimal::ZERO {
+            return None;
+        }
+
+        let end_price_u128 = Self::decimal_to_u128(end_price)?;
+        let token_amount_u64 = Self::token_decimal_to_u64_rounded(token_input_amount)?;
+
+        Some((end_price_u128, token_amount_u64))
+    }
+
+    // ==================== 手续费 / Fees ====================
+
+    /// 计算扣除手续费后的金额 (手续费向下取整) / Amount after fee (fee rounded down)
+    ///
+    /// `fee` 以 FEE_DENOMINATOR 为分母, 例如 1000 = 1%
+    /// `fee` is over FEE_DENOMINATOR, e.g. 1000 = 1%
+    #[inline(always)]
+    pub fn calculate_amount_after_fee(amount: u64, fee: u16) -> Option<u64> {
+        if fee > MAX_FEE_RATE {
+            return None;
+        }
+
+        let fee_amount = amount
+            .checked_mul(u64::from(fee))?
+            .checked_div(FEE_DENOMINATOR)?;
+
+        amount.checked_sub(fee_amount)
+    }
+
+    /// 计算加上手续费后的总金额 (向上取整) / Total amount including fee (rounded up)
+    ///
+    /// total = sol_amount * (FEE_DENOMINATOR + fee) / FEE_DENOMINATOR
+    #[inline(always)]
+    pub fn calculate_total_amount_with_fee(sol_amount: u64, fee: u16) -> Option<u64> {
+        if fee > MAX_FEE_RATE {
+            return None;
+        }
+
+        let numerator = sol_amount.checked_mul(FEE_DENOMINATOR.checked_add(u64::from(fee))?)?;
+
+        numerator
+            .checked_add(FEE_DENOMINATOR)?
+            .checked_sub(1)?
+            .checked_div(FEE_DENOMINATOR)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 初始价格 (由链上 CurveAMM::get_initial_price 得出)
+    /// Initial price (as produced by on-chain CurveAMM::get_initial_price)
+    const INITIAL_PRICE: u128 = 2_795_899_347_623_485_554;
+
+    // 以下期望值均由链上 curve_amm.rs 对相同输入计算得出, 若不一致说明链下副本已偏离
+    // Expected values below are on-chain curve_amm.rs outputs for the same inputs; a mismatch means the off-chain copy has drifted
+
+    #[test]
+    fn test_initial_price_and_reserves() {
+        assert_eq!(CurveAMM::get_initial_price(), Some(INITIAL_PRICE));
+        assert_eq!(
+            CurveAMM::price_to_reserves(INITIAL_PRICE),
+            Some((30_000_000_000, 1_073_000_000_000_000))
+        );
+        assert_eq!(
+            CurveAMM::price_to_reserves(INITIAL_PRICE * 2),
+            Some((42_426_406_871, 758_725_576_213_165))
+        );
+        assert_eq!(
+            CurveAMM::price_to_reserves(1u128 << 90),
+            Some((631_262_939_388_940, 50_993_014_149))
+        );
+    }
+
+    #[test]
+    fn test_trade_vectors() {
+        assert_eq!(
+            CurveAMM::buy_from_price_with_token_output(INITIAL_PRICE, 10_000_000_000_000),
+            Some((2_848_750_717_940_049_682, 282_220_132))
+        );
+        assert_eq!(
+            CurveAMM::sell_from_price_with_token_input(INITIAL_PRICE, 10_000_000_000_000),
+            Some((2_744_505_234_510_682_596, 277_008_310))
+        );
+        assert_eq!(
+            CurveAMM::buy_from_price_with_sol_input(INITIAL_PRICE, 1_000_000_000),
+            Some((2_985_399_192_295_744_019, 34_612_903_225_806))
+        );
+        assert_eq!(
+            CurveAMM::sell_from_price_with_sol_output(INITIAL_PRICE * 2, 1_000_000_000),
+            Some((5_331_305_331_648_382_406, 18_315_022_554_871))
+        );
+        assert_eq!(
+            CurveAMM::buy_from_price_to_price(INITIAL_PRICE, INITIAL_PRICE * 2),
+            Some((12_426_406_871, 314_274_423_786_835))
+        );
+        assert_eq!(
+            CurveAMM::sell_from_price_to_price(INITIAL_PRICE * 2, INITIAL_PRICE),
+            Some((314_274_423_786_835, 12_426_406_871))
+        );
+    }
+
+    #[test]
+    fn test_invalid_and_sentinel_inputs() {
+        // 价格方向错误或数量为 0 / Wrong price direction or zero amount
+        assert_eq!(CurveAMM::buy_from_price_to_price(INITIAL_PRICE, INITIAL_PRICE), None);
+        assert_eq!(CurveAMM::sell_from_price_to_price(INITIAL_PRICE, INITIAL_PRICE * 2), None);
+        assert_eq!(CurveAMM::sell_from_price_with_token_input(INITIAL_PRICE, 0), None);
+        assert_eq!(
+            CurveAMM::u128_to_decimal(CurveAMM::PRICE_CALCULATION_LIMIT + 1),
+            None
+        );
+
+        // MAX_U64 表示无限流动性: 卖出可计算, 买入超过储备返回 None
+        // MAX_U64 means infinite liquidity: selling computes, buying beyond reserves returns None
+        assert_eq!(
+            CurveAMM::sell_from_price_with_token_input(INITIAL_PRICE, CurveAMM::MAX_U64),
+            Some((346_531_869_415, 29_989_438_343))
+        );
+        assert_eq!(
+            CurveAMM::buy_from_price_with_token_output(INITIAL_PRICE, CurveAMM::MAX_U64),
+            None
+        );
+    }
+
+    #[test]
+    fn test_fee_vectors() {
+        assert_eq!(CurveAMM::calculate_amount_after_fee(1_000_000_000, 1000), Some(990_000_000));
+        assert_eq!(CurveAMM::calculate_total_amount_with_fee(1_000_000_000, 1000), Some(1_010_000_000));
+        // 手续费向下取整, 总额向上取整 / Fee rounds down, total rounds up
+        assert_eq!(CurveAMM::calculate_amount_after_fee(999, 50), Some(999));
+        assert_eq!(CurveAMM::calculate_total_amount_with_fee(999, 50), Some(1000));
+        assert_eq!(CurveAMM::calculate_amount_after_fee(1000, MAX_FEE_RATE + 1), None);
+        assert_eq!(CurveAMM::calculate_total_amount_with_fee(1000, MAX_FEE_RATE + 1), None);
+    }
+}
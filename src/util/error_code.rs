@@ -0,0 +1,111 @@
+// API 错误码 / API error codes
+//
+// 错误响应中的 error_code 字段, 按类别分段, 已发布的数值不再改变; code 字段仍与 HTTP 状态码一致
+// The error_code field of error responses, grouped into ranges; published values never change. The code field still mirrors the HTTP status
+//
+// 1xxx 请求校验 / request validation
+// 2xxx 资源不存在 / not found
+// 3xxx 存储与服务端 / storage and server
+// 4xxx 上游 RPC / upstream RPC
+// 5xxx 访问控制 / access control
+
+/// 稳定的 API 错误码, 客户端应按该值分支而不是解析错误消息
+/// Stable API error code; clients should branch on it instead of parsing the message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    /// 1000 请求无效 / Invalid request
+    InvalidRequest,
+    /// 1001 参数错误 / Invalid parameter
+    InvalidParameter,
+    /// 1002 请求体过大 / Payload too large
+    PayloadTooLarge,
+    /// 1003 与当前状态冲突 (如订单簿已满、保留键) / Conflicts with the current state (e.g. full order book, reserved key)
+    Conflict,
+    /// 1004 不支持的K线间隔 / Unsupported K-line interval
+    UnknownInterval,
+    /// 1005 mint 不是合法的 Solana 地址 / Mint is not a valid Solana address
+    InvalidMint,
+    /// 1006 超出每客户端订阅上限 / Per-client subscription limit exceeded
+    SubscriptionLimitExceeded,
+    /// 2000 资源不存在 / Resource not found
+    NotFound,
+    /// 3000 存储读写失败 / Storage read or write failed
+    Storage,
+    /// 3001 服务端内部错误 / Internal server error
+    Internal,
+    /// 3002 业务规则错误 / Business rule error
+    BusinessRule,
+    /// 3003 服务繁忙, 并发请求已满 / Server busy, concurrent requests saturated
+    Overloaded,
+    /// 4000 上游 RPC 请求失败 / Upstream RPC request failed
+    UpstreamRpc,
+    /// 5000 未授权 / Unauthorized
+    Unauthorized,
+    /// 5001 禁止访问 / Forbidden
+    Forbidden,
+    /// 5002 请求过于频繁 / Rate limited
+    RateLimited,
+}
+
+impl ErrorCode {
+    /// 全部错误码, 用于文档与测试 / Every error code, for docs and tests
+    pub const ALL: [ErrorCode; 16] = [
+        ErrorCode::InvalidRequest,
+        ErrorCode::InvalidParameter,
+        ErrorCode::PayloadTooLarge,
+        ErrorCode::Conflict,
+        ErrorCode::UnknownInterval,
+        ErrorCode::InvalidMint,
+        ErrorCode::SubscriptionLimitExceeded,
+        ErrorCode::NotFound,
+        ErrorCode::Storage,
+        ErrorCode::Internal,
+        ErrorCode::BusinessRule,
+        ErrorCode::Overloaded,
+        ErrorCode::UpstreamRpc,
+        ErrorCode::Unauthorized,
+        ErrorCode::Forbidden,
+        ErrorCode::RateLimited,
+    ];
+
+    /// 数值错误码 / Numeric error code
+    pub const fn as_u32(self) -> u32 {
+        match self {
+            ErrorCode::InvalidRequest => 1000,
+            ErrorCode::InvalidParameter => 1001,
+            ErrorCode::PayloadTooLarge => 1002,
+            ErrorCode::Conflict => 1003,
+            ErrorCode::UnknownInterval => 1004,
+            ErrorCode::InvalidMint => 1005,
+            ErrorCode::SubscriptionLimitExceeded => 1006,
+            ErrorCode::NotFound => 2000,
+            ErrorCode::Storage => 3000,
+            ErrorCode::Internal => 3001,
+            ErrorCode::BusinessRule => 3002,
+            ErrorCode::Overloaded => 3003,
+            ErrorCode::UpstreamRpc => 4000,
+            ErrorCode::Unauthorized => 5000,
+            ErrorCode::Forbidden => 5001,
+            ErrorCode::RateLimited => 5002,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_codes_are_unique_and_in_their_range() {
+        let codes: HashSet<u32> = ErrorCode::ALL.iter().map(|c| c.as_u32()).collect();
+        assert_eq!(codes.len(), ErrorCode::ALL.len());
+
+        assert_eq!(ErrorCode::InvalidParameter.as_u32() / 1000, 1);
+        assert_eq!(ErrorCode::SubscriptionLimitExceeded.as_u32() / 1000, 1);
+        assert_eq!(ErrorCode::NotFound.as_u32() / 1000, 2);
+        assert_eq!(ErrorCode::Storage.as_u32() / 1000, 3);
+        assert_eq!(ErrorCode::UpstreamRpc.as_u32() / 1000, 4);
+        assert_eq!(ErrorCode::RateLimited.as_u32() / 1000, 5);
+    }
+}
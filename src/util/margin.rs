@@ -0,0 +1,251 @@
+// 保证金开仓预估: 强平价与保证金要求
+// Margin position pre-check: liquidation price and margin requirement
+//
+// 与链上 long_trade / short_trade 的保证金计算和校验一致
+// Mirrors the margin math and validation of on-chain long_trade / short_trade
+
+use crate::util::curve::{
+    CurveAMM, MIN_MARGIN_SOL_AMOUNT, MIN_STOP_LOSS_PERCENT, MIN_TRADE_TOKEN_AMOUNT,
+};
+
+/// 平仓价 -> 链上保证金 / Close price -> on-chain margin
+type MarginFn = Box<dyn Fn(u128) -> Option<u64>>;
+
+/// 开仓方向 / Position side
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PositionSide {
+    Long,
+    Short,
+}
+
+/// 开仓预估结果 / Position pre-check result
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LiquidationEstimate {
+    /// 给定保证金可设置的最远平仓(强平)价, 保证金不足时为 None
+    /// Farthest close (liquidation) price the margin allows, None if margin is insufficient
+    pub close_price: Option<u128>,
+
+    /// 该平仓价下链上实际收取的保证金 / Margin actually charged on-chain at that close price
+    pub real_margin_sol: Option<u64>,
+
+    /// 止损价边界: 做多须低于, 做空须高于 / Stop price bound: long must be below, short must be above
+    pub stop_price_limit: u128,
+
+    /// 在止损价边界处所需的最小保证金 / Minimum margin required at the stop price bound
+    pub min_required_margin_sol: Option<u64>,
+
+    /// 违反的链上约束, 为空表示可以开仓 / Violated on-chain constraints, empty means the order can be opened
+    pub violations: Vec<String>,
+}
+
+/// 按当前价格预估开仓的强平价与保证金
+/// Estimate liquidation price and margin for opening a position at the current price
+///
+/// 单段曲线近似, 不考虑穿越其他订单锁定区间 / Single-segment curve approximation, ignores crossing other orders' locked ranges
+///
+/// # 参数 / Parameters
+/// * `side` - 开仓方向 / Position side
+/// * `current_price` - 当前价格 / Current price
+/// * `token_amount` - 做多买入 / 做空借出卖出的 token 数量 / Tokens bought (long) or borrowed and sold (short)
+/// * `margin_sol` - 愿意投入的最大保证金 / Maximum margin the user is willing to post
+/// * `fee` - 保证金交易手续费率 (borrow_fee) / Margin trading fee rate (borrow_fee)
+pub fn estimate_liquidation(
+    side: PositionSide,
+    current_price: u128,
+    token_amount: u64,
+    margin_sol: u64,
+    fee: u16,
+) -> LiquidationEstimate {
+    let mut violations = Vec::new();
+    if token_amount < MIN_TRADE_TOKEN_AMOUNT {
+        violations.push(format!(
+            "token_amount {} is below the minimum trade size {}",
+            token_amount, MIN_TRADE_TOKEN_AMOUNT
+        ));
+    }
+
+    let (stop_price_limit, margin_at) = match side {
+        PositionSide::Long => {
+            // 做多止损价须低于当前价的 97% / Long stop price must be below 97% of current price
+            let limit = current_price * (100 - MIN_STOP_LOSS_PERCENT as u128) / 100;
+            (limit, long_margin_fn(current_price, token_amount, fee))
+        }
+        PositionSide::Short => {
+            // 做空止损价须高于当前价的 103% / Short stop price must be above 103% of current price
+            let limit = current_price * (100 + MIN_STOP_LOSS_PERCENT as u128) / 100;
+            (limit, short_margin_fn(current_price, token_amount, fee))
+        }
+    };
+
+    let Some(margin_at) = margin_at else {
+        violations.push(format!(
+            "Cannot open {} tokens at current price, amount exceeds curve liquidity",
+            token_amount
+        ));
+        return LiquidationEstimate {
+            close_price: None,
+            real_margin_sol: None,
+            stop_price_limit,
+            min_required_margin_sol: None,
+            violations,
+        };
+    };
+
+    // 最近的合法平仓价 / Closest valid close price
+    let closest_valid = match side {
+        PositionSide::Long => stop_price_limit.saturating_sub(1),
+        PositionSide::Short => stop_price_limit + 1,
+    };
+    let min_required_margin_sol = margin_at(closest_valid);
+
+    // 保证金随平仓价单调变化, 二分查找保证金 <= margin_sol 的最远平仓价
+    // Margin is monotonic in close price, binary search the farthest close price with margin <= margin_sol
+    let fits = |price: u128| margin_at(price).is_some_and(|m| m <= margin_sol);
+    let close_price = match side {
+        PositionSide::Long => {
+            let min_price = CurveAMM::decimal_to_u128(CurveAMM::INITIAL_MIN_PRICE_DECIMAL).unwrap_or(1);
+            search_lowest(min_price, current_price, fits)
+        }
+        PositionSide::Short => search_highest(current_price, CurveAMM::PRICE_CALCULATION_LIMIT, fits),
+    };
+    let real_margin_sol = close_price.and_then(&margin_at);
+
+    let stop_ok = match (side, close_price) {
+        (PositionSide::Long, Some(p)) => p < stop_price_limit,
+        (PositionSide::Short, Some(p)) => p > stop_price_limit,
+        (_, None) => false,
+    };
+    if !stop_ok {
+        violations.push(format!(
+            "margin_sol {} is insufficient, a stop price {}{}% from the current price needs at least {}",
+            margin_sol,
+            if side == PositionSide::Long { "-" } else { "+" },
+            MIN_STOP_LOSS_PERCENT,
+            min_required_margin_sol.map_or("-".to_string(), |m| m.to_string())
+        ));
+    }
+    if real_margin_sol.is_some_and(|m| m < MIN_MARGIN_SOL_AMOUNT) || margin_sol < MIN_MARGIN_SOL_AMOUNT {
+        violations.push(format!(
+            "margin is below the minimum margin {}",
+            MIN_MARGIN_SOL_AMOUNT
+        ));
+    }
+
+    LiquidationEstimate {
+        close_price,
+        real_margin_sol,
+        stop_price_limit,
+        min_required_margin_sol,
+        violations,
+    }
+}
+
+/// 做多: 保证金 = 买入所需 SOL - 以平仓价卖出全部 token 扣费后所得
+/// Long: margin = SOL to buy - net SOL from selling all tokens at the close price
+fn long_margin_fn(current_price: u128, token_amount: u64, fee: u16) -> Option<MarginFn> {
+    let (_, required_sol) = CurveAMM::buy_from_price_with_token_output(current_price, token_amount)?;
+
+    Some(Box::new(move |close_price: u128| {
+        let (_, output_sol) = CurveAMM::sell_from_price_with_token_input(close_price, token_amount)?;
+        let output_after_fee = CurveAMM::calculate_amount_after_fee(output_sol, fee)?;
+        Some(required_sol.saturating_sub(output_after_fee))
+    }))
+}
+
+/// 做空: 保证金 = 以平仓价买回全部 token 含费成本 - 卖出所得(含手续费部分)
+/// Short: margin = cost incl. fee to buy back all tokens at the close price - gross SOL from the sale
+fn short_margin_fn(current_price: u128, token_amount: u64, fee: u16) -> Option<MarginFn> {
+    // 链上 output_sol(扣费后) + fee_sol 即毛收入 / On-chain output_sol (after fee) + fee_sol is the gross output
+    let (_, sell_output_sol) = CurveAMM::sell_from_price_with_token_input(current_price, token_amount)?;
+
+    Some(Box::new(move |close_price: u128| {
+        let (_, close_cost_sol) =
+            CurveAMM::buy_from_price_with_token_output(close_price, token_amount)?;
+        let close_cost_with_fee = CurveAMM::calculate_total_amount_with_fee(close_cost_sol, fee)?;
+        Some(close_cost_with_fee.saturating_sub(sell_output_sol))
+    }))
+}
+
+/// 在 [lo, hi] 中查找满足条件的最小值 (条件对价格单调不减)
+/// Find the smallest value in [lo, hi] satisfying a predicate that is monotonically non-decreasing
+fn search_lowest(mut lo: u128, mut hi: u128, ok: impl Fn(u128) -> bool) -> Option<u128> {
+    if lo > hi || !ok(hi) {
+        return None;
+    }
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if ok(mid) {
+            hi = mid;
+        } else {
+            lo = mid + 1;
+        }
+    }
+    Some(lo)
+}
+
+/// 在 [lo, hi] 中查找满足条件的最大值 (条件对价格单调不增)
+/// Find the largest value in [lo, hi] satisfying a predicate that is monotonically non-increasing
+fn search_highest(mut lo: u128, mut hi: u128, ok: impl Fn(u128) -> bool) -> Option<u128> {
+    if lo > hi || !ok(lo) {
+        return None;
+    }
+    while lo < hi {
+        let mid = lo + (hi - lo).div_ceil(2);
+        if ok(mid) {
+            lo = mid;
+        } else {
+            hi = mid - 1;
+        }
+    }
+    Some(lo)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FEE: u16 = 1000;
+    const TOKENS: u64 = 10_000_000_000_000;
+
+    fn initial_price() -> u128 {
+        CurveAMM::get_initial_price().unwrap()
+    }
+
+    #[test]
+    fn test_long_estimate_with_enough_margin() {
+        let price = initial_price();
+        let est = estimate_liquidation(PositionSide::Long, price, TOKENS, 100_000_000, FEE);
+
+        assert!(est.violations.is_empty(), "{:?}", est.violations);
+        let close_price = est.close_price.unwrap();
+        assert!(close_price < est.stop_price_limit);
+        assert!(est.real_margin_sol.unwrap() <= 100_000_000);
+        // 更多保证金可以把强平价放得更远 / More margin allows a farther liquidation price
+        let more = estimate_liquidation(PositionSide::Long, price, TOKENS, 200_000_000, FEE);
+        assert!(more.close_price.unwrap() < close_price);
+    }
+
+    #[test]
+    fn test_short_estimate_with_enough_margin() {
+        let price = initial_price();
+        let est = estimate_liquidation(PositionSide::Short, price, TOKENS, 100_000_000, FEE);
+
+        assert!(est.violations.is_empty(), "{:?}", est.violations);
+        assert!(est.close_price.unwrap() > est.stop_price_limit);
+        assert!(est.real_margin_sol.unwrap() <= 100_000_000);
+    }
+
+    #[test]
+    fn test_insufficient_inputs_are_reported() {
+        let price = initial_price();
+
+        // 保证金连 3% 止损都不够 / Margin does not even cover a 3% stop
+        let est = estimate_liquidation(PositionSide::Long, price, TOKENS, 2_000_000, FEE);
+        assert!(!est.violations.is_empty());
+        assert!(est.min_required_margin_sol.unwrap() > 2_000_000);
+
+        // 交易量低于最小值 / Amount below minimum trade size
+        let est = estimate_liquidation(PositionSide::Short, price, 1_000, 100_000_000, FEE);
+        assert!(est.violations.iter().any(|v| v.contains("minimum trade size")));
+    }
+}
@@ -1,3 +1,13 @@
+pub mod curve;
+pub mod error_code;
+pub mod margin;
+pub mod page;
+pub mod pnl;
 pub mod result;
+pub mod time;
+pub mod validate;
 
+pub use error_code::ErrorCode;
+pub use page::PageInfo;
 pub use result::{ApiResult, CommonResult, ok_result};
+pub use validate::validate_mint;
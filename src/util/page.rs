@@ -0,0 +1,136 @@
+// 统一的分页信息 / Shared pagination fields
+//
+// 各列表响应保留原有的列表字段名 (events / rows / orders / positions / markets / records / tokens),
+// 并通过 `#[serde(flatten)]` 嵌入 PageInfo, 因此原有的 total/page/page_size/total_pages 字段不变,
+// next_cursor/has_more 是新增字段
+// Each listing response keeps its original list field name (events / rows / orders / positions / markets / records /
+// tokens) and embeds PageInfo with `#[serde(flatten)]`, so the existing total/page/page_size/total_pages fields are
+// unchanged and next_cursor/has_more are additions
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// 分页信息: 页码分页填写 page/total/total_pages, 游标分页填写 next_cursor, 两者都用 has_more 表示是否还有下一页
+/// Pagination fields: page-numbered listings fill page/total/total_pages, cursor listings fill next_cursor; both report has_more
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(title = "PageInfo", description = "分页信息 / Pagination fields")]
+pub struct PageInfo {
+    /// 总数, 列表不统计总数时为空 / Total count, null when the listing does not count it
+    #[schema(example = 100)]
+    pub total: Option<u64>,
+
+    /// 总页数, 已知总数时存在 / Total pages, present when the total is known
+    #[schema(example = 5)]
+    pub total_pages: Option<u32>,
+
+    /// 当前页码(从 1 开始), 游标分页时为空 / Current page (starting from 1), null for cursor listings
+    #[schema(example = 1)]
+    pub page: Option<u32>,
+
+    /// 每页数量 / Page size
+    #[schema(example = 20)]
+    pub page_size: u32,
+
+    /// 下一页游标, 游标分页且还有下一页时存在 / Next cursor, present for cursor listings with another page
+    pub next_cursor: Option<String>,
+
+    /// 是否还有下一页 / Whether another page exists
+    pub has_more: bool,
+}
+
+impl PageInfo {
+    /// 按页码分页, 已知总数 / Page-numbered listing with a known total
+    pub fn numbered(page: u32, page_size: u32, total: u64) -> Self {
+        Self {
+            total: Some(total),
+            total_pages: Some(total_pages(total, page_size)),
+            page: Some(page),
+            page_size,
+            next_cursor: None,
+            has_more: (page as u64).saturating_mul(page_size as u64) < total,
+        }
+    }
+
+    /// 按页码分页, 不统计总数 / Page-numbered listing without a total
+    pub fn open_ended(page: u32, page_size: u32, has_more: bool) -> Self {
+        Self {
+            total: None,
+            total_pages: None,
+            page: Some(page),
+            page_size,
+            next_cursor: None,
+            has_more,
+        }
+    }
+
+    /// 按游标分页 / Cursor listing
+    pub fn cursor(page_size: u32, next_cursor: Option<String>) -> Self {
+        Self {
+            total: None,
+            total_pages: None,
+            page: None,
+            page_size,
+            has_more: next_cursor.is_some(),
+            next_cursor,
+        }
+    }
+
+    /// 附带总数 / Attach a total count
+    pub fn with_total(mut self, total: u64) -> Self {
+        self.total = Some(total);
+        self
+    }
+}
+
+/// 总页数, 每页数量为 0 时为 0 / Total pages, 0 when the page size is 0
+fn total_pages(total: u64, page_size: u32) -> u32 {
+    if page_size == 0 {
+        return 0;
+    }
+    total.div_ceil(page_size as u64).min(u32::MAX as u64) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_numbered_has_more() {
+        assert!(PageInfo::numbered(1, 20, 45).has_more);
+        assert!(PageInfo::numbered(2, 20, 45).has_more);
+        assert!(!PageInfo::numbered(3, 20, 45).has_more);
+        assert!(!PageInfo::numbered(1, 20, 0).has_more);
+        assert_eq!(PageInfo::numbered(1, 20, 45).total_pages, Some(3));
+        assert_eq!(PageInfo::numbered(1, 20, 0).total_pages, Some(0));
+    }
+
+    #[test]
+    fn test_cursor_page() {
+        let page = PageInfo::cursor(2, Some("next".to_string())).with_total(10);
+        assert!(page.has_more);
+        assert_eq!(page.page, None);
+        assert_eq!(page.total, Some(10));
+        assert!(!PageInfo::cursor(2, None).has_more);
+    }
+
+    #[test]
+    fn test_flattened_fields_keep_legacy_names() {
+        #[derive(Serialize)]
+        struct Listing {
+            events: Vec<u32>,
+            #[serde(flatten)]
+            pagination: PageInfo,
+        }
+
+        let json = serde_json::to_value(Listing {
+            events: vec![1, 2],
+            pagination: PageInfo::numbered(1, 2, 3),
+        })
+        .unwrap();
+        assert_eq!(json["events"], serde_json::json!([1, 2]));
+        assert_eq!(json["total"], 3);
+        assert_eq!(json["total_pages"], 2);
+        assert_eq!(json["page"], 1);
+        assert_eq!(json["page_size"], 2);
+    }
+}
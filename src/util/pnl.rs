@@ -0,0 +1,153 @@
+// 保证金订单未实现盈亏估算
+// Unrealized PnL estimation for margin orders
+
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::orderbook::MarginOrder;
+use crate::util::curve::CurveAMM;
+
+/// 订单类型: 做多 / Order type: long
+pub const ORDER_TYPE_LONG: u8 = 1;
+
+/// 订单类型: 做空 / Order type: short
+pub const ORDER_TYPE_SHORT: u8 = 2;
+
+/// 按当前价格估算的持仓估值 / Position valuation at the current price
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+pub struct PositionPnl {
+    /// 当前价格下平仓可取回的 SOL (lamports, 可能为负表示资不抵债)
+    /// SOL returned if closed at the current price (lamports, negative means underwater)
+    pub liquidation_value_sol: i64,
+
+    /// 未实现盈亏 = 平仓可取回 SOL - 当前保证金 (lamports)
+    /// Unrealized PnL = liquidation value - current margin (lamports)
+    pub unrealized_pnl_sol: i64,
+}
+
+/// 按当前价格估算订单的平仓价值与未实现盈亏
+/// Estimate liquidation value and unrealized PnL of an order at the current price
+///
+/// 与链上平仓结算一致 / Consistent with on-chain close settlement:
+/// - 做多: 以当前价卖出 `position_asset_amount` token (扣手续费), 加保证金, 归还 `borrow_amount` SOL
+/// - Long: sell `position_asset_amount` tokens at the current price (after fee), add margin, repay `borrow_amount` SOL
+/// - 做空: 持有 `position_asset_amount` SOL 加保证金, 以当前价买回 `borrow_amount` token (含手续费)
+/// - Short: hold `position_asset_amount` SOL plus margin, buy back `borrow_amount` tokens at the current price (with fee)
+///
+/// # 注意 / Note
+/// 单段曲线近似, 不考虑穿越其他订单锁定区间 / Single-segment curve approximation, ignores crossing other orders' locked ranges
+///
+/// # 返回值 / Returns
+/// 未知订单类型或曲线计算失败时返回 None / None for unknown order type or curve math failure
+pub fn estimate_position_pnl(order: &MarginOrder, current_price: u128) -> Option<PositionPnl> {
+    let margin = order.margin_sol_amount as i128;
+
+    let liquidation_value = match order.order_type {
+        ORDER_TYPE_LONG => {
+            let (_, sol_output) = CurveAMM::sell_from_price_with_token_input(
+                current_price,
+                order.position_asset_amount,
+            )?;
+            let sol_after_fee = CurveAMM::calculate_amount_after_fee(sol_output, order.borrow_fee)?;
+
+            sol_after_fee as i128 + margin - order.borrow_amount as i128
+        }
+        ORDER_TYPE_SHORT => {
+            let (_, sol_cost) =
+                CurveAMM::buy_from_price_with_token_output(current_price, order.borrow_amount)?;
+            let sol_cost_with_fee =
+                CurveAMM::calculate_total_amount_with_fee(sol_cost, order.borrow_fee)?;
+
+            order.position_asset_amount as i128 + margin - sol_cost_with_fee as i128
+        }
+        _ => return None,
+    };
+
+    Some(PositionPnl {
+        liquidation_value_sol: i64::try_from(liquidation_value).ok()?,
+        unrealized_pnl_sol: i64::try_from(liquidation_value - margin).ok()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 初始曲线价格 / Initial curve price
+    fn initial_price() -> u128 {
+        let price = CurveAMM::INITIAL_SOL_RESERVE_DECIMAL / CurveAMM::INITIAL_TOKEN_RESERVE_DECIMAL;
+        CurveAMM::decimal_to_u128(price).unwrap()
+    }
+
+    fn order(order_type: u8, position_asset_amount: u64, borrow_amount: u64) -> MarginOrder {
+        MarginOrder {
+            user: "User".to_string(),
+            lock_lp_start_price: 0,
+            lock_lp_end_price: 0,
+            open_price: initial_price(),
+            take_profit_price: 0,
+            order_id: 1,
+            lock_lp_sol_amount: 0,
+            lock_lp_token_amount: 0,
+            next_lp_sol_amount: 0,
+            next_lp_token_amount: 0,
+            margin_init_sol_amount: 100_000_000,
+            margin_sol_amount: 100_000_000,
+            borrow_amount,
+            position_asset_amount,
+            realized_sol_amount: 0,
+            version: 1,
+            start_time: 0,
+            end_time: 0,
+            next_order: u16::MAX,
+            prev_order: u16::MAX,
+            borrow_fee: 1000,
+            order_type,
+        }
+    }
+
+    #[test]
+    fn test_long_pnl_follows_price() {
+        let open = initial_price();
+        let tokens = 10_000_000_000_000; // 1000 万 token / 10M tokens
+        let (_, borrowed_sol) = CurveAMM::buy_from_price_with_token_output(open, tokens).unwrap();
+        let long = order(ORDER_TYPE_LONG, tokens, borrowed_sol);
+
+        let at_open = estimate_position_pnl(&long, open).unwrap();
+        let up = estimate_position_pnl(&long, open * 2).unwrap();
+        let down = estimate_position_pnl(&long, open / 2).unwrap();
+
+        // 开仓价平仓只亏手续费和滑点 / Closing at open price only loses fee and slippage
+        assert!(at_open.unrealized_pnl_sol < 0);
+        assert!(up.unrealized_pnl_sol > 0);
+        assert!(down.unrealized_pnl_sol < at_open.unrealized_pnl_sol);
+        assert_eq!(
+            up.liquidation_value_sol - up.unrealized_pnl_sol,
+            long.margin_sol_amount as i64
+        );
+    }
+
+    #[test]
+    fn test_short_pnl_follows_price() {
+        let open = initial_price();
+        let tokens = 10_000_000_000_000;
+        let (_, sol_output) = CurveAMM::sell_from_price_with_token_input(open, tokens).unwrap();
+        let held_sol = CurveAMM::calculate_amount_after_fee(sol_output, 1000).unwrap();
+        let short = order(ORDER_TYPE_SHORT, held_sol, tokens);
+
+        let at_open = estimate_position_pnl(&short, open).unwrap();
+        let up = estimate_position_pnl(&short, open * 2).unwrap();
+        let down = estimate_position_pnl(&short, open / 2).unwrap();
+
+        assert!(at_open.unrealized_pnl_sol < 0);
+        assert!(down.unrealized_pnl_sol > 0);
+        assert!(up.unrealized_pnl_sol < at_open.unrealized_pnl_sol);
+    }
+
+    #[test]
+    fn test_unknown_order_type_or_empty_position() {
+        let open = initial_price();
+        assert_eq!(estimate_position_pnl(&order(0, 1_000_000, 1_000_000), open), None);
+        assert_eq!(estimate_position_pnl(&order(ORDER_TYPE_LONG, 0, 0), open), None);
+    }
+}
@@ -1,4 +1,5 @@
 use axum::{
+    http::StatusCode,
     response::{IntoResponse, Response},
     Json,
 };
@@ -7,6 +8,8 @@ use std::fmt::Display;
 use tracing::error;
 use utoipa::ToSchema;
 
+use super::error_code::ErrorCode;
+
 /// API 统一响应结果类型
 pub type ApiResult = Result<Response, ApiError>;
 
@@ -19,12 +22,15 @@ pub struct CommonResult<T: Serialize> {
     pub msg: String,
     /// 响应数据（成功时包含数据，失败时为 None）
     pub data: Option<T>,
+    /// 稳定错误码（仅错误响应）, 见 ErrorCode / Stable error code (error responses only), see ErrorCode
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<u32>,
 }
 
 impl<T: Serialize> CommonResult<T> {
     /// 自定义响应
     pub fn default(code: u32, msg: String, data: Option<T>) -> Self {
-        CommonResult { code, msg, data }
+        CommonResult { code, msg, data, error_code: None }
     }
 
     /// 成功响应（带数据）
@@ -41,6 +47,12 @@ impl<T: Serialize> CommonResult<T> {
     pub fn error_response(code: u32, msg: String) -> Response {
         Self::error(code, msg).into_response()
     }
+
+    /// 附带稳定错误码
+    pub fn with_error_code(mut self, error_code: ErrorCode) -> Self {
+        self.error_code = Some(error_code.as_u32());
+        self
+    }
 }
 
 impl<T: Serialize> IntoResponse for CommonResult<T> {
@@ -70,10 +82,24 @@ pub enum ApiError {
     RequestParamError(String),
     /// 未授权
     Unauthorized(String),
+    /// 禁止访问
+    Forbidden(String),
     /// 资源不存在
     NotFound(String),
+    /// 资源冲突
+    Conflict(String),
+    /// 请求体过大
+    PayloadTooLarge(String),
+    /// 请求过于频繁
+    TooManyRequests(String),
     /// 业务错误
     BusinessError(String),
+    /// 存储读写错误
+    Storage(String),
+    /// 上游 RPC 错误
+    Upstream(String),
+    /// 服务繁忙
+    ServiceUnavailable(String),
     /// 内部错误
     InternalError(String),
 }
@@ -84,11 +110,72 @@ impl ApiError {
         matches!(
             self,
             ApiError::Unauthorized(_)
+                | ApiError::Forbidden(_)
                 | ApiError::BadRequest(_)
+                | ApiError::RequestParamError(_)
                 | ApiError::NotFound(_)
+                | ApiError::Conflict(_)
+                | ApiError::PayloadTooLarge(_)
+                | ApiError::TooManyRequests(_)
+                | ApiError::ServiceUnavailable(_)
                 | ApiError::BusinessError(_)
         )
     }
+
+    /// 对应的 HTTP 状态码
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::Response(resp) => resp.status(),
+            ApiError::BadRequest(_) | ApiError::RequestParamError(_) => StatusCode::BAD_REQUEST,
+            ApiError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::Conflict(_) => StatusCode::CONFLICT,
+            ApiError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            ApiError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::AnyhowError(_)
+            | ApiError::BusinessError(_)
+            | ApiError::Storage(_)
+            | ApiError::InternalError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::Upstream(_) => StatusCode::BAD_GATEWAY,
+            ApiError::ServiceUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+
+    /// 对应的稳定错误码, 直接返回的响应没有错误码
+    pub fn error_code(&self) -> Option<ErrorCode> {
+        let code = match self {
+            ApiError::Response(_) => return None,
+            ApiError::BadRequest(_) => ErrorCode::InvalidRequest,
+            ApiError::RequestParamError(_) => ErrorCode::InvalidParameter,
+            ApiError::PayloadTooLarge(_) => ErrorCode::PayloadTooLarge,
+            ApiError::Conflict(_) => ErrorCode::Conflict,
+            ApiError::NotFound(_) => ErrorCode::NotFound,
+            ApiError::Storage(_) => ErrorCode::Storage,
+            ApiError::AnyhowError(_) | ApiError::InternalError(_) => ErrorCode::Internal,
+            ApiError::BusinessError(_) => ErrorCode::BusinessRule,
+            ApiError::ServiceUnavailable(_) => ErrorCode::Overloaded,
+            ApiError::Upstream(_) => ErrorCode::UpstreamRpc,
+            ApiError::Unauthorized(_) => ErrorCode::Unauthorized,
+            ApiError::Forbidden(_) => ErrorCode::Forbidden,
+            ApiError::TooManyRequests(_) => ErrorCode::RateLimited,
+        };
+        Some(code)
+    }
+
+    /// 按状态码构建错误, 用于转换自带状态码的领域错误
+    pub fn from_status(status: u16, msg: String) -> Self {
+        match status {
+            400 => ApiError::BadRequest(msg),
+            401 => ApiError::Unauthorized(msg),
+            403 => ApiError::Forbidden(msg),
+            404 => ApiError::NotFound(msg),
+            409 => ApiError::Conflict(msg),
+            413 => ApiError::PayloadTooLarge(msg),
+            429 => ApiError::TooManyRequests(msg),
+            _ => ApiError::InternalError(msg),
+        }
+    }
 }
 
 impl Display for ApiError {
@@ -99,8 +186,15 @@ impl Display for ApiError {
             ApiError::BadRequest(e) => write!(f, "请求错误: {}", e),
             ApiError::RequestParamError(e) => write!(f, "参数错误: {}", e),
             ApiError::Unauthorized(e) => write!(f, "未授权: {}", e),
+            ApiError::Forbidden(e) => write!(f, "禁止访问: {}", e),
             ApiError::NotFound(e) => write!(f, "未找到: {}", e),
+            ApiError::Conflict(e) => write!(f, "冲突: {}", e),
+            ApiError::PayloadTooLarge(e) => write!(f, "请求体过大: {}", e),
+            ApiError::TooManyRequests(e) => write!(f, "请求过于频繁: {}", e),
             ApiError::BusinessError(e) => write!(f, "业务错误: {}", e),
+            ApiError::Storage(e) => write!(f, "存储错误: {}", e),
+            ApiError::Upstream(e) => write!(f, "上游RPC错误: {}", e),
+            ApiError::ServiceUnavailable(e) => write!(f, "服务繁忙: {}", e),
             ApiError::InternalError(e) => write!(f, "内部错误: {}", e),
         }
     }
@@ -116,9 +210,10 @@ impl From<anyhow::Error> for ApiError {
     }
 }
 
-/// 构建错误响应的辅助函数
-fn error(code: u32, msg: String) -> Response {
-    CommonResult::<()>::error(code, msg).into_response()
+/// 构建错误响应的辅助函数, HTTP 状态码与响应体中的 code 保持一致, error_code 为稳定错误码
+fn error(status: StatusCode, error_code: ErrorCode, msg: String) -> Response {
+    let body = CommonResult::<()>::error(status.as_u16() as u32, msg).with_error_code(error_code);
+    (status, body).into_response()
 }
 
 impl IntoResponse for ApiError {
@@ -128,15 +223,84 @@ impl IntoResponse for ApiError {
             error!("系统错误: {:?}", self);
         }
 
+        let status = self.status_code();
+        // 只有直接返回的响应没有错误码, 它们不经过 error()
+        let code = self.error_code().unwrap_or(ErrorCode::Internal);
         match self {
             Self::Response(resp) => resp,
-            Self::NotFound(e) => error(404, format!("404错误：{}", e)),
-            Self::Unauthorized(e) => error(401, e),
-            Self::BadRequest(e) => error(400, format!("请求错误：{}", e)),
-            Self::RequestParamError(e) => error(400, format!("参数错误：{}", e)),
-            Self::InternalError(e) => error(500, format!("内部错误：{}", e)),
-            Self::BusinessError(e) => error(500, format!("业务错误：{}", e)),
-            Self::AnyhowError(e) => error(500, e.to_string()),
+            Self::NotFound(e)
+            | Self::Unauthorized(e)
+            | Self::Forbidden(e)
+            | Self::BadRequest(e)
+            | Self::Conflict(e)
+            | Self::PayloadTooLarge(e)
+            | Self::TooManyRequests(e)
+            | Self::Storage(e)
+            | Self::Upstream(e)
+            | Self::ServiceUnavailable(e)
+            | Self::InternalError(e) => error(status, code, e),
+            Self::RequestParamError(e) => error(status, code, format!("参数错误：{}", e)),
+            Self::BusinessError(e) => error(status, code, format!("业务错误：{}", e)),
+            Self::AnyhowError(e) => error(status, code, e.to_string()),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_response_carries_http_status() {
+        let cases = [
+            (ApiError::BadRequest("bad".into()), StatusCode::BAD_REQUEST),
+            (ApiError::Unauthorized("key".into()), StatusCode::UNAUTHORIZED),
+            (ApiError::Forbidden("reserved".into()), StatusCode::FORBIDDEN),
+            (ApiError::NotFound("missing".into()), StatusCode::NOT_FOUND),
+            (ApiError::Conflict("full".into()), StatusCode::CONFLICT),
+            (ApiError::PayloadTooLarge("huge".into()), StatusCode::PAYLOAD_TOO_LARGE),
+            (ApiError::TooManyRequests("slow down".into()), StatusCode::TOO_MANY_REQUESTS),
+            (ApiError::Storage("rocksdb".into()), StatusCode::INTERNAL_SERVER_ERROR),
+            (ApiError::Upstream("rpc".into()), StatusCode::BAD_GATEWAY),
+            (ApiError::ServiceUnavailable("busy".into()), StatusCode::SERVICE_UNAVAILABLE),
+            (ApiError::InternalError("boom".into()), StatusCode::INTERNAL_SERVER_ERROR),
+            (anyhow::anyhow!("boom").into(), StatusCode::INTERNAL_SERVER_ERROR),
+        ];
+
+        for (err, status) in cases {
+            assert_eq!(err.into_response().status(), status);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_error_response_carries_stable_error_code() {
+        let cases = [
+            (ApiError::BadRequest("bad".into()), 1000),
+            (ApiError::NotFound("missing".into()), 2000),
+            (ApiError::Storage("rocksdb".into()), 3000),
+            (ApiError::ServiceUnavailable("busy".into()), 3003),
+            (ApiError::Upstream("rpc".into()), 4000),
+            (ApiError::TooManyRequests("slow down".into()), 5002),
+        ];
+
+        for (err, error_code) in cases {
+            let response = err.into_response();
+            let status = response.status();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let body: CommonResult<()> = serde_json::from_slice(&body).unwrap();
+            assert_eq!(body.code, status.as_u16() as u32);
+            assert_eq!(body.error_code, Some(error_code));
+        }
+
+        let ok = serde_json::to_value(CommonResult::ok(1u32)).unwrap();
+        assert!(ok.get("error_code").is_none());
+    }
+
+    #[test]
+    fn test_from_status_maps_domain_codes() {
+        assert!(matches!(ApiError::from_status(400, String::new()), ApiError::BadRequest(_)));
+        assert!(matches!(ApiError::from_status(404, String::new()), ApiError::NotFound(_)));
+        assert!(matches!(ApiError::from_status(409, String::new()), ApiError::Conflict(_)));
+        assert!(matches!(ApiError::from_status(503, String::new()), ApiError::InternalError(_)));
+    }
+}
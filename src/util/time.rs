@@ -0,0 +1,68 @@
+// 统一的 UTC 时间戳工具
+// Shared UTC timestamp helpers
+//
+// 订单簿中的时间 (start_time / end_time / created_at / last_modified) 与链上
+// Clock::unix_timestamp 一致, 都是自 Unix 纪元起的秒数, 以 u32 存储。
+// 已知限制: u32 秒在 2106-02-07 06:28:15 UTC 溢出 (Year-2106), 超出范围的值会被饱和截断而不是回绕。
+// 事件与K线使用的毫秒时间戳 (i64) 不受此限制。
+//
+// Order book times (start_time / end_time / created_at / last_modified) match the on-chain
+// Clock::unix_timestamp: seconds since the Unix epoch, stored as u32.
+// Known constraint: u32 seconds overflow at 2106-02-07 06:28:15 UTC (Year-2106); out-of-range values saturate instead of wrapping.
+// Millisecond timestamps (i64) used by events and K-lines are not affected.
+
+use chrono::Utc;
+
+use crate::util::curve::TRADE_COOLDOWN_SECONDS;
+
+/// 当前 Unix 时间(秒) / Current Unix time (seconds)
+pub fn unix_now() -> i64 {
+    Utc::now().timestamp()
+}
+
+/// 当前 Unix 时间(秒), 按订单簿的 u32 格式 / Current Unix time (seconds) in the order book's u32 format
+pub fn unix_now_u32() -> u32 {
+    to_u32_secs(unix_now())
+}
+
+/// 将 Unix 秒转换为 u32, 负值取 0, 超过 Year-2106 上限取 u32::MAX
+/// Convert Unix seconds to u32, clamping negatives to 0 and values past the Year-2106 limit to u32::MAX
+pub fn to_u32_secs(secs: i64) -> u32 {
+    secs.clamp(0, u32::MAX as i64) as u32
+}
+
+/// 订单平仓冷却的剩余秒数, 与链上 close_long_short 的校验一致; 时间早于 start_time 时同样视为冷却中
+/// Remaining close cooldown of an order, matching the on-chain close_long_short check; times before start_time also count as cooling down
+pub fn cooldown_remaining_secs(start_time: u32, now: u32) -> u32 {
+    start_time.saturating_add(TRADE_COOLDOWN_SECONDS).saturating_sub(now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_u32_secs_saturates() {
+        assert_eq!(to_u32_secs(1_735_660_800), 1_735_660_800);
+        assert_eq!(to_u32_secs(-5), 0);
+        // 2106-02-07 06:28:16 UTC 之后不再回绕 / No wrap-around past 2106-02-07 06:28:16 UTC
+        assert_eq!(to_u32_secs(u32::MAX as i64 + 1), u32::MAX);
+    }
+
+    #[test]
+    fn test_cooldown_remaining_secs() {
+        let start = 1_735_660_800;
+        assert_eq!(cooldown_remaining_secs(start, start), TRADE_COOLDOWN_SECONDS);
+        assert_eq!(cooldown_remaining_secs(start, start + 1), TRADE_COOLDOWN_SECONDS - 1);
+        assert_eq!(cooldown_remaining_secs(start, start + TRADE_COOLDOWN_SECONDS), 0);
+        assert_eq!(cooldown_remaining_secs(start, start + 3600), 0);
+        // 服务器时钟落后于链上时间时仍在冷却 / Still cooling down when the server clock lags the chain
+        assert_eq!(cooldown_remaining_secs(start, start - 5), TRADE_COOLDOWN_SECONDS + 5);
+    }
+
+    #[test]
+    fn test_unix_now_u32_matches_unix_now() {
+        let now = unix_now();
+        assert!((unix_now_u32() as i64 - now).abs() <= 1);
+    }
+}
@@ -0,0 +1,155 @@
+// 查询参数校验 / Query parameter validation
+//
+// 参数结构体派生 validator::Validate, 由 router::extract::ValidatedQuery 在进入处理函数前统一校验;
+// 这里放多个路由共用的校验函数和错误消息格式化
+// Parameter structs derive validator::Validate and are checked by router::extract::ValidatedQuery before the
+// handler runs; this module holds the validators shared across routers and the error message formatting
+
+use std::borrow::Cow;
+use std::str::FromStr;
+
+use solana_sdk::pubkey::Pubkey;
+use validator::{ValidationError, ValidationErrors};
+
+use crate::orderbook::Direction;
+use crate::util::result::ApiError;
+
+/// 结构体级校验错误在 ValidationErrors 中的键 / Key under which struct-level errors are stored
+const STRUCT_LEVEL_KEY: &str = "__all__";
+
+/// 构造带消息的校验错误 / Build a validation error carrying a message
+pub fn invalid(code: &'static str, message: impl Into<String>) -> ValidationError {
+    let mut err = ValidationError::new(code);
+    err.message = Some(Cow::Owned(message.into()));
+    err
+}
+
+/// 订单方向必须为 "up" 或 "dn" / Order direction must be "up" or "dn"
+pub fn validate_direction(direction: &str) -> Result<(), ValidationError> {
+    direction
+        .parse::<Direction>()
+        .map(|_| ())
+        .map_err(|_| invalid("direction", format!("invalid direction '{}', expected 'up' or 'dn'", direction)))
+}
+
+/// mint 必须是 base58 编码的 32 字节公钥 / A mint must be a base58-encoded 32-byte public key
+pub fn validate_mint_field(mint: &str) -> Result<(), ValidationError> {
+    if mint.is_empty() {
+        return Err(invalid("mint", "must not be empty"));
+    }
+    Pubkey::from_str(mint)
+        .map(|_| ())
+        .map_err(|e| invalid("mint", format!("invalid mint '{}', expected a base58 public key: {}", mint, e)))
+}
+
+/// 校验路径或请求体中的 mint, 格式错误时返回 400 参数错误
+/// Validate a mint taken from a path or request body, returning a 400 parameter error when malformed
+///
+/// 查询参数结构体应改用 `#[validate(custom(function = "validate_mint_field"))]`
+/// Query parameter structs should use `#[validate(custom(function = "validate_mint_field"))]` instead
+pub fn validate_mint(mint: &str) -> Result<(), ApiError> {
+    validate_mint_field(mint).map_err(|e| {
+        let reason = e.message.as_deref().unwrap_or(e.code.as_ref()).to_string();
+        ApiError::RequestParamError(format!("mint: {}", reason))
+    })
+}
+
+/// 把校验错误展开为按字段名排序的 "字段: 原因" 列表, 以分号连接
+/// Flatten validation errors into a "field: reason" list sorted by field name, joined by semicolons
+pub fn describe_errors(errors: &ValidationErrors) -> String {
+    let mut lines: Vec<String> = errors
+        .field_errors()
+        .into_iter()
+        .flat_map(|(field, errs)| {
+            errs.iter().map(move |e| {
+                let reason = e.message.as_deref().unwrap_or(e.code.as_ref()).to_string();
+                if field == STRUCT_LEVEL_KEY {
+                    reason
+                } else {
+                    format!("{}: {}", field, reason)
+                }
+            })
+        })
+        .collect();
+    lines.sort();
+    lines.join("; ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use validator::Validate;
+
+    #[derive(Validate)]
+    #[validate(schema(function = "validate_bounds", skip_on_field_errors = false))]
+    struct Params {
+        #[validate(length(min = 1, message = "must not be empty"))]
+        mint: String,
+        #[validate(custom(function = "validate_direction"))]
+        direction: String,
+        #[validate(range(min = 1, max = 100, message = "must be between 1 and 100"))]
+        page_size: u32,
+        from: u64,
+        to: u64,
+    }
+
+    fn validate_bounds(params: &Params) -> Result<(), ValidationError> {
+        if params.from > params.to {
+            return Err(invalid("range", "from must not exceed to"));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_direction() {
+        assert!(validate_direction("up").is_ok());
+        assert!(validate_direction("dn").is_ok());
+        assert!(validate_direction("down").is_err());
+        assert!(validate_direction("").is_err());
+    }
+
+    #[test]
+    fn test_validate_mint() {
+        assert!(validate_mint("So11111111111111111111111111111111111111112").is_ok());
+        assert!(validate_mint("").is_err());
+        // 0, O, I, l 不在 base58 字母表中 / 0, O, I and l are not in the base58 alphabet
+        assert!(validate_mint("0OIl1111111111111111111111111111111111111112").is_err());
+        // 合法字符但长度不足 32 字节 / Valid characters but shorter than 32 bytes
+        assert!(validate_mint("So1111111111").is_err());
+
+        let err = validate_mint("not-a-mint").unwrap_err();
+        assert!(matches!(err, ApiError::RequestParamError(ref msg) if msg.starts_with("mint: invalid mint 'not-a-mint'")));
+    }
+
+    #[test]
+    fn test_describe_errors_lists_every_invalid_field() {
+        let params = Params {
+            mint: String::new(),
+            direction: "sideways".to_string(),
+            page_size: 500,
+            from: 10,
+            to: 1,
+        };
+        let errors = params.validate().unwrap_err();
+
+        assert_eq!(
+            describe_errors(&errors),
+            "direction: invalid direction 'sideways', expected 'up' or 'dn'; \
+             from must not exceed to; \
+             mint: must not be empty; \
+             page_size: must be between 1 and 100"
+        );
+    }
+
+    #[test]
+    fn test_valid_params_pass() {
+        let params = Params {
+            mint: "mint".to_string(),
+            direction: "dn".to_string(),
+            page_size: 20,
+            from: 1,
+            to: 1,
+        };
+        assert!(params.validate().is_ok());
+    }
+}
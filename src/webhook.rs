@@ -0,0 +1,305 @@
+// Webhook 推送 - 将事件以 HTTP POST 推送给集成方
+// Webhook delivery - pushes events to integrators via HTTP POST
+//
+// 事件处理路径只负责入队 (有界队列, 满时丢弃), 投递、重试和退避都在后台任务中完成
+// The event-processing path only enqueues (bounded queue, drops when full); delivery, retries and backoff run in a background task
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, Semaphore};
+use tracing::{error, info, warn};
+use utoipa::ToSchema;
+
+use crate::config::{WebhookConfig, WebhookEndpoint};
+use crate::solana::events::PinpetEvent;
+
+/// 签名请求头, 值为 `sha256=<hex>` / Signature header, value is `sha256=<hex>`
+pub const SIGNATURE_HEADER: &str = "X-Pinpet-Signature";
+/// 事件类型请求头 / Event type header
+pub const EVENT_HEADER: &str = "X-Pinpet-Event";
+/// 投递 ID 请求头, 重试时保持不变 / Delivery ID header, unchanged across retries
+pub const DELIVERY_HEADER: &str = "X-Pinpet-Delivery";
+
+/// 同时进行的最大投递数 / Max concurrent deliveries
+const MAX_CONCURRENT_DELIVERIES: usize = 16;
+/// 退避上限 / Backoff ceiling
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Webhook 推送内容 / Webhook payload
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WebhookPayload<T> {
+    /// 事件类型 / Event type
+    pub event_type: String,
+    /// 入队时间(毫秒) / Enqueue time (ms)
+    pub created_at: i64,
+    /// 事件数据 / Event data
+    pub data: T,
+}
+
+/// Webhook 投递统计 / Webhook delivery metrics
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(title = "WebhookMetrics", description = "Webhook 投递统计 / Webhook delivery metrics")]
+pub struct WebhookMetrics {
+    /// 已入队 / Enqueued
+    pub enqueued: u64,
+    /// 投递成功 / Delivered
+    pub delivered: u64,
+    /// 重试次数 / Retry attempts
+    pub retried: u64,
+    /// 重试耗尽后失败 / Failed after exhausting retries
+    pub failed: u64,
+    /// 队列满被丢弃 / Dropped because the queue was full
+    pub dropped: u64,
+}
+
+#[derive(Default)]
+struct WebhookStats {
+    enqueued: AtomicU64,
+    delivered: AtomicU64,
+    retried: AtomicU64,
+    failed: AtomicU64,
+    dropped: AtomicU64,
+}
+
+/// 单次投递任务 / A single delivery job
+struct WebhookJob {
+    url: String,
+    event_type: &'static str,
+    delivery_id: String,
+    body: Arc<Vec<u8>>,
+    signature: Arc<String>,
+}
+
+/// Webhook 分发器 / Webhook dispatcher
+pub struct WebhookDispatcher {
+    endpoints: Vec<WebhookEndpoint>,
+    secret: String,
+    sender: mpsc::Sender<WebhookJob>,
+    stats: Arc<WebhookStats>,
+}
+
+impl WebhookDispatcher {
+    /// 创建分发器并启动后台投递任务 / Create the dispatcher and start the background delivery task
+    pub fn start(config: &WebhookConfig) -> anyhow::Result<Arc<Self>> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.request_timeout_secs))
+            .build()?;
+        let (sender, receiver) = mpsc::channel(config.queue_size);
+        let stats = Arc::new(WebhookStats::default());
+
+        tokio::spawn(run_deliveries(
+            receiver,
+            client,
+            config.max_retries,
+            Duration::from_millis(config.initial_backoff_ms),
+            stats.clone(),
+        ));
+
+        info!(
+            "🔔 Webhook 已启用 / Webhooks enabled: endpoints={}, queue_size={}",
+            config.endpoints.len(),
+            config.queue_size
+        );
+
+        Ok(Arc::new(Self {
+            endpoints: config.endpoints.clone(),
+            secret: config.secret.clone(),
+            sender,
+            stats,
+        }))
+    }
+
+    /// 推送事件给所有订阅了该类型的目标, 不阻塞调用方
+    /// Push an event to every endpoint subscribed to its type, without blocking the caller
+    pub fn notify<T: Serialize>(&self, event_type: &'static str, data: &T) {
+        let targets: Vec<&WebhookEndpoint> = self
+            .endpoints
+            .iter()
+            .filter(|e| e.events.is_empty() || e.events.iter().any(|t| t == event_type))
+            .collect();
+        if targets.is_empty() {
+            return;
+        }
+
+        let payload = WebhookPayload {
+            event_type: event_type.to_string(),
+            created_at: chrono::Utc::now().timestamp_millis(),
+            data,
+        };
+        let body = match serde_json::to_vec(&payload) {
+            Ok(body) => body,
+            Err(e) => {
+                error!("❌ Webhook 序列化失败 / Failed to serialize webhook payload: {}", e);
+                return;
+            }
+        };
+        let signature = Arc::new(sign(&self.secret, &body));
+        let body = Arc::new(body);
+
+        for endpoint in targets {
+            let job = WebhookJob {
+                url: endpoint.url.clone(),
+                event_type,
+                delivery_id: uuid::Uuid::new_v4().to_string(),
+                body: body.clone(),
+                signature: signature.clone(),
+            };
+            match self.sender.try_send(job) {
+                Ok(()) => {
+                    self.stats.enqueued.fetch_add(1, Ordering::Relaxed);
+                }
+                Err(e) => {
+                    self.stats.dropped.fetch_add(1, Ordering::Relaxed);
+                    warn!(
+                        "⚠️ Webhook 队列已满, 丢弃推送 / Webhook queue full, dropping delivery: url={}, event={}, {}",
+                        endpoint.url, event_type, e
+                    );
+                }
+            }
+        }
+    }
+
+    /// 读取投递统计 / Read delivery metrics
+    pub fn metrics(&self) -> WebhookMetrics {
+        WebhookMetrics {
+            enqueued: self.stats.enqueued.load(Ordering::Relaxed),
+            delivered: self.stats.delivered.load(Ordering::Relaxed),
+            retried: self.stats.retried.load(Ordering::Relaxed),
+            failed: self.stats.failed.load(Ordering::Relaxed),
+            dropped: self.stats.dropped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Webhook 事件类型名 / Webhook event type name
+pub fn event_type_name(event: &PinpetEvent) -> &'static str {
+    match event {
+        PinpetEvent::TokenCreated(_) => "token_created",
+        PinpetEvent::BuySell(_) => "buy_sell",
+        PinpetEvent::LongShort(_) => "long_short",
+        PinpetEvent::FullClose(_) => "full_close",
+        PinpetEvent::PartialClose(_) => "partial_close",
+        PinpetEvent::MilestoneDiscount(_) => "milestone_discount",
+        PinpetEvent::AddMargin(_) => "add_margin",
+        PinpetEvent::ExtendOrder(_) => "extend_order",
+    }
+}
+
+/// 计算请求体签名: `sha256=` + HMAC-SHA256(secret, body) 的十六进制
+/// Sign a request body: `sha256=` + hex of HMAC-SHA256(secret, body)
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// 第 `attempt` 次重试前的等待时间 (从 0 开始, 指数增长, 有上限)
+/// Delay before retry number `attempt` (0-based, exponential, capped)
+pub fn backoff_delay(initial: Duration, attempt: u32) -> Duration {
+    initial
+        .checked_mul(1u32.checked_shl(attempt).unwrap_or(u32::MAX))
+        .unwrap_or(MAX_BACKOFF)
+        .min(MAX_BACKOFF)
+}
+
+/// 后台投递循环 / Background delivery loop
+async fn run_deliveries(
+    mut receiver: mpsc::Receiver<WebhookJob>,
+    client: reqwest::Client,
+    max_retries: u32,
+    initial_backoff: Duration,
+    stats: Arc<WebhookStats>,
+) {
+    let permits = Arc::new(Semaphore::new(MAX_CONCURRENT_DELIVERIES));
+
+    while let Some(job) = receiver.recv().await {
+        let permit = match permits.clone().acquire_owned().await {
+            Ok(permit) => permit,
+            Err(_) => break,
+        };
+        let client = client.clone();
+        let stats = stats.clone();
+        tokio::spawn(async move {
+            deliver(&client, &job, max_retries, initial_backoff, &stats).await;
+            drop(permit);
+        });
+    }
+}
+
+/// 投递单个任务, 失败时指数退避重试 / Deliver one job, retrying with exponential backoff on failure
+async fn deliver(
+    client: &reqwest::Client,
+    job: &WebhookJob,
+    max_retries: u32,
+    initial_backoff: Duration,
+    stats: &WebhookStats,
+) {
+    let mut attempt = 0;
+    loop {
+        let result = client
+            .post(&job.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, job.signature.as_str())
+            .header(EVENT_HEADER, job.event_type)
+            .header(DELIVERY_HEADER, &job.delivery_id)
+            .body(job.body.as_ref().clone())
+            .send()
+            .await;
+
+        let failure = match result {
+            Ok(resp) if resp.status().is_success() => {
+                stats.delivered.fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+            Ok(resp) => format!("HTTP {}", resp.status()),
+            Err(e) => e.to_string(),
+        };
+
+        if attempt >= max_retries {
+            stats.failed.fetch_add(1, Ordering::Relaxed);
+            error!(
+                "❌ Webhook 投递失败, 已重试 {} 次 / Webhook delivery failed after {} retries: url={}, event={}, delivery={}, {}",
+                attempt, attempt, job.url, job.event_type, job.delivery_id, failure
+            );
+            return;
+        }
+
+        let delay = backoff_delay(initial_backoff, attempt);
+        warn!(
+            "⚠️ Webhook 投递失败, {:?} 后重试 / Webhook delivery failed, retrying in {:?}: url={}, attempt={}, {}",
+            delay, delay, job.url, attempt + 1, failure
+        );
+        stats.retried.fetch_add(1, Ordering::Relaxed);
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_matches_known_vector() {
+        // RFC 4231 测试用例 2 / RFC 4231 test case 2
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_backoff_doubles_and_caps() {
+        let initial = Duration::from_millis(500);
+        assert_eq!(backoff_delay(initial, 0), Duration::from_millis(500));
+        assert_eq!(backoff_delay(initial, 1), Duration::from_millis(1000));
+        assert_eq!(backoff_delay(initial, 3), Duration::from_millis(4000));
+        assert_eq!(backoff_delay(initial, 10), MAX_BACKOFF);
+        assert_eq!(backoff_delay(initial, 40), MAX_BACKOFF);
+    }
+}
@@ -92,6 +92,10 @@ function connectAndSubscribe(mint) {
         console.log('🎉 收到连接成功消息:', JSON.stringify(data, null, 2));
     });
 
+    socket.on('subscribed', (data) => {
+        console.log('✅ 订阅ACK:', JSON.stringify(data, null, 2));
+    });
+
     socket.on('subscription_confirmed', (data) => {
         console.log('✅ 订阅确认:', JSON.stringify(data, null, 2));
     });
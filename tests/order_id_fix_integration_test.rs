@@ -4,7 +4,7 @@
 // 这是一个独立的集成测试,不依赖其他测试文件
 // This is a standalone integration test, independent of other test files
 
-use pinpet_server_v2::orderbook::{MarginOrder, OrderBookDBManager, OrderBookError};
+use pinpet_server_v2::orderbook::{Direction, MarginOrder, OrderBookDBManager, OrderBookError};
 use rocksdb::{Options, DB};
 use std::sync::Arc;
 use uuid::Uuid;
@@ -27,7 +27,7 @@ fn cleanup_test_db(path: &str) {
 fn create_test_manager() -> (OrderBookDBManager, String) {
     let (db, temp_path) = create_test_db();
     let mint = "EPjFWaLb3crLvQQf89kiNqEX5jg5Kv431J06Y1AD3ic".to_string();
-    let direction = "dn".to_string();
+    let direction = Direction::Dn;
     let manager = OrderBookDBManager::new(db, mint, direction);
     (manager, temp_path)
 }
@@ -39,6 +39,7 @@ fn create_order_with_id(user: &str, order_id: u64, price: u128) -> MarginOrder {
         lock_lp_start_price: price,
         lock_lp_end_price: price + 100000,
         open_price: price + 50000,
+        take_profit_price: 0,
         order_id,
         lock_lp_sol_amount: 1000000000,
         lock_lp_token_amount: 5000000000,